@@ -0,0 +1,129 @@
+#!/usr/bin/env python3
+#
+# Copyright Cedar Contributors
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#      https://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+"""Export Criterion results to a JSON baseline, and compare two baselines.
+
+    bench-baseline.py export <criterion dir> <baseline.json> [--prefix corpora/]
+    bench-baseline.py compare <base.json> <head.json> [--threshold 10]
+
+A baseline is a JSON object:
+
+    {
+      "version": 1,
+      "unit": "ns",
+      "benchmarks": {
+        "corpora/is_authorized/rbac": { "mean": 1234.5, "median": 1200.0, "stdDev": 40.1 }
+      }
+    }
+
+`compare` prints a Markdown table of the benchmarks in both baselines and
+exits with status 1 if the mean time of any benchmark increased by more than
+the threshold, in percent.
+"""
+
+import argparse
+import json
+import os
+import sys
+
+VERSION = 1
+
+
+def export(criterion_dir, out, prefix):
+    benchmarks = {}
+    for root, _, files in os.walk(criterion_dir):
+        # Criterion writes the results of the latest run to `<id>/new/`
+        if os.path.basename(root) != "new" or "benchmark.json" not in files:
+            continue
+        with open(os.path.join(root, "benchmark.json")) as f:
+            full_id = json.load(f)["full_id"]
+        if not full_id.startswith(prefix):
+            continue
+        with open(os.path.join(root, "estimates.json")) as f:
+            estimates = json.load(f)
+        benchmarks[full_id] = {
+            "mean": estimates["mean"]["point_estimate"],
+            "median": estimates["median"]["point_estimate"],
+            "stdDev": estimates["std_dev"]["point_estimate"],
+        }
+    if not benchmarks:
+        sys.exit(f"no Criterion results matching `{prefix}` in {criterion_dir}")
+    with open(out, "w") as f:
+        json.dump(
+            {"version": VERSION, "unit": "ns", "benchmarks": benchmarks},
+            f,
+            indent=2,
+            sort_keys=True,
+        )
+        f.write("\n")
+
+
+def load(path):
+    with open(path) as f:
+        baseline = json.load(f)
+    if baseline.get("version") != VERSION:
+        sys.exit(f"{path}: unsupported baseline version {baseline.get('version')}")
+    return baseline["benchmarks"]
+
+
+def compare(base_path, head_path, threshold):
+    base = load(base_path)
+    head = load(head_path)
+    regressions = []
+    print("| benchmark | base (ns) | head (ns) | change |")
+    print("|---|---:|---:|---:|")
+    for name in sorted(base.keys() | head.keys()):
+        if name not in base or name not in head:
+            where = "head" if name in head else "base"
+            print(f"| {name} | | | only in {where} |")
+            continue
+        before = base[name]["mean"]
+        after = head[name]["mean"]
+        change = (after - before) / before * 100
+        flag = ""
+        if change > threshold:
+            regressions.append(name)
+            flag = " :warning:"
+        print(f"| {name} | {before:.0f} | {after:.0f} | {change:+.1f}%{flag} |")
+    if regressions:
+        print()
+        print(
+            f"{len(regressions)} benchmark(s) regressed by more than {threshold}%: "
+            + ", ".join(regressions)
+        )
+        sys.exit(1)
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    commands = parser.add_subparsers(dest="command", required=True)
+    export_args = commands.add_parser("export", help="export Criterion results")
+    export_args.add_argument("criterion_dir")
+    export_args.add_argument("out")
+    export_args.add_argument("--prefix", default="")
+    compare_args = commands.add_parser("compare", help="compare two baselines")
+    compare_args.add_argument("base")
+    compare_args.add_argument("head")
+    compare_args.add_argument("--threshold", type=float, default=10.0)
+    args = parser.parse_args()
+    if args.command == "export":
+        export(args.criterion_dir, args.out, args.prefix)
+    else:
+        compare(args.base, args.head, args.threshold)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,543 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A policy decision point (PDP) server, run by the `cedar-agent` binary,
+//! which answers authorization and validation requests over HTTP using the
+//! policies of a bundle directory:
+//!
+//! - `policies.cedar`: policies and templates, in Cedar syntax (required)
+//! - `schema.cedarschema` or `schema.cedarschema.json`: the schema
+//! - `entities.json`: entities, in the Cedar entity JSON format
+//! - `template-links.json`: template-linked policies, in the format of the
+//!   `--template-linked` CLI option
+//!
+//! The bundle is reloaded when any of these files changes, or on
+//! `POST /v1/reload`. A bundle which fails to load, or whose policies fail
+//! validation against its schema, is rejected, and the agent keeps serving
+//! the previous bundle.
+//!
+//! Endpoints:
+//!
+//! - `POST /v1/is_authorized`: the body is a request in the format of the
+//!   `--request-json` CLI option, optionally with an `entities` array which
+//!   is added to the entities of the bundle for this request
+//! - `POST /v1/validate`: the body is `{ "policies": "<Cedar policies>" }`,
+//!   which are validated against the schema of the bundle
+//! - `POST /v1/reload`: reload the bundle
+//! - `GET /v1/health`: the revision of the bundle being served
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use arc_swap::ArcSwap;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Parser;
+use miette::{miette, IntoDiagnostic, Report, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use cedar_policy::*;
+
+use super::{
+    add_template_links_to_set, load_entities, read_cedar_policy_set, read_schema_file, RequestJSON,
+    SchemaFormat,
+};
+
+const POLICIES_FILE: &str = "policies.cedar";
+const CEDAR_SCHEMA_FILE: &str = "schema.cedarschema";
+const JSON_SCHEMA_FILE: &str = "schema.cedarschema.json";
+const ENTITIES_FILE: &str = "entities.json";
+const TEMPLATE_LINKS_FILE: &str = "template-links.json";
+
+/// Cedar policy decision point, serving authorization and validation
+/// requests over HTTP
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct AgentArgs {
+    /// Directory containing the policy bundle
+    #[arg(short, long, value_name = "DIR")]
+    pub bundle: PathBuf,
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8180")]
+    pub listen: SocketAddr,
+    /// How often to check the bundle for changes, in seconds. `0` disables
+    /// reloading on changes; `POST /v1/reload` still reloads the bundle.
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    pub reload_interval: u64,
+}
+
+/// The policies, schema, and entities loaded from a bundle directory
+#[derive(Debug)]
+pub struct Bundle {
+    policies: PolicySet,
+    schema: Option<Schema>,
+    entities: Entities,
+    /// Incremented each time a bundle is loaded
+    revision: u64,
+    /// Modification times of the bundle files when the bundle was loaded
+    fingerprint: Vec<Option<SystemTime>>,
+}
+
+impl Bundle {
+    /// Load the bundle in `dir`, giving it revision `revision`
+    pub fn load(dir: &Path, revision: u64) -> Result<Self> {
+        // read the fingerprint first, so that changes made while loading
+        // trigger another reload
+        let fingerprint = fingerprint(dir);
+        let schema = if dir.join(CEDAR_SCHEMA_FILE).exists() {
+            Some(read_schema_file(
+                dir.join(CEDAR_SCHEMA_FILE).as_path(),
+                SchemaFormat::Cedar,
+            )?)
+        } else if dir.join(JSON_SCHEMA_FILE).exists() {
+            Some(read_schema_file(
+                dir.join(JSON_SCHEMA_FILE).as_path(),
+                SchemaFormat::Json,
+            )?)
+        } else {
+            None
+        };
+        let mut policies = read_cedar_policy_set(
+            Some(dir.join(POLICIES_FILE).as_path()),
+            &Constants::default(),
+        )?;
+        if dir.join(TEMPLATE_LINKS_FILE).exists() {
+            add_template_links_to_set(dir.join(TEMPLATE_LINKS_FILE), &mut policies)?;
+        }
+        if let Some(schema) = &schema {
+            let result = Validator::new(schema.clone()).validate(&policies, ValidationMode::Strict);
+            if !result.validation_passed() {
+                return Err(Report::new(result)).wrap_err("bundle policies failed validation");
+            }
+        }
+        let entities = if dir.join(ENTITIES_FILE).exists() {
+            load_entities(dir.join(ENTITIES_FILE), schema.as_ref())?
+        } else {
+            Entities::empty()
+        };
+        Ok(Self {
+            policies,
+            schema,
+            entities,
+            revision,
+            fingerprint,
+        })
+    }
+
+    /// Whether any of the files in `dir` changed since this bundle was loaded
+    fn is_stale(&self, dir: &Path) -> bool {
+        fingerprint(dir) != self.fingerprint
+    }
+
+    /// Answer `request` with the policies and entities of this bundle
+    pub fn is_authorized(&self, request: AuthorizeRequest) -> Result<AuthorizeResponse> {
+        let request_json = RequestJSON {
+            principal: request.principal,
+            action: request.action,
+            resource: request.resource,
+            context: request.context,
+        };
+        let request_entities = request.entities;
+        let request = request_json.into_request(self.schema.as_ref(), true, "request")?;
+        let entities = match request_entities {
+            Some(extra) => self
+                .entities
+                .clone()
+                .add_entities_from_json_value(extra, self.schema.as_ref())
+                .wrap_err("failed to add request entities")?,
+            None => self.entities.clone(),
+        };
+        let response = Authorizer::new().is_authorized(&request, &self.policies, &entities);
+        Ok(AuthorizeResponse {
+            decision: response.decision(),
+            reasons: response
+                .diagnostics()
+                .reason()
+                .map(ToString::to_string)
+                .collect(),
+            errors: response
+                .diagnostics()
+                .errors()
+                .map(ToString::to_string)
+                .collect(),
+            revision: self.revision,
+        })
+    }
+
+    /// Validate `policies` against the schema of this bundle
+    pub fn validate(&self, policies: &str) -> Result<ValidateResponse> {
+        let schema = self
+            .schema
+            .as_ref()
+            .ok_or_else(|| miette!("the bundle has no schema to validate against"))?;
+        let policies = PolicySet::from_str(policies).wrap_err("failed to parse policies")?;
+        let result = Validator::new(schema.clone()).validate(&policies, ValidationMode::Strict);
+        Ok(ValidateResponse {
+            valid: result.validation_passed(),
+            errors: result
+                .validation_errors()
+                .map(ToString::to_string)
+                .collect(),
+            warnings: result
+                .validation_warnings()
+                .map(ToString::to_string)
+                .collect(),
+            revision: self.revision,
+        })
+    }
+}
+
+/// Modification times of the bundle files in `dir`
+fn fingerprint(dir: &Path) -> Vec<Option<SystemTime>> {
+    [
+        POLICIES_FILE,
+        CEDAR_SCHEMA_FILE,
+        JSON_SCHEMA_FILE,
+        ENTITIES_FILE,
+        TEMPLATE_LINKS_FILE,
+    ]
+    .iter()
+    .map(|file| {
+        std::fs::metadata(dir.join(file))
+            .and_then(|m| m.modified())
+            .ok()
+    })
+    .collect()
+}
+
+/// Body of `POST /v1/is_authorized`
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeRequest {
+    /// Principal for the request
+    #[serde(default)]
+    pub principal: String,
+    /// Action for the request
+    #[serde(default)]
+    pub action: String,
+    /// Resource for the request
+    #[serde(default)]
+    pub resource: String,
+    /// Context for the request
+    #[serde(default = "empty_context")]
+    pub context: serde_json::Value,
+    /// Entities to add to the entities of the bundle for this request
+    #[serde(default)]
+    pub entities: Option<serde_json::Value>,
+}
+
+fn empty_context() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+/// Response of `POST /v1/is_authorized`
+#[derive(Debug, Serialize)]
+pub struct AuthorizeResponse {
+    /// The decision
+    pub decision: Decision,
+    /// Ids of the policies which determined the decision
+    pub reasons: Vec<String>,
+    /// Errors encountered while evaluating policies
+    pub errors: Vec<String>,
+    /// Revision of the bundle which answered the request
+    pub revision: u64,
+}
+
+/// Body of `POST /v1/validate`
+#[derive(Debug, Deserialize)]
+pub struct ValidateRequest {
+    /// Policies, in Cedar syntax
+    pub policies: String,
+}
+
+/// Response of `POST /v1/validate`
+#[derive(Debug, Serialize)]
+pub struct ValidateResponse {
+    /// Whether the policies passed validation
+    pub valid: bool,
+    /// Validation errors
+    pub errors: Vec<String>,
+    /// Validation warnings
+    pub warnings: Vec<String>,
+    /// Revision of the bundle whose schema the policies were validated
+    /// against
+    pub revision: u64,
+}
+
+/// Response of `GET /v1/health` and `POST /v1/reload`
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    /// Revision of the bundle being served
+    pub revision: u64,
+}
+
+/// Error response of any endpoint
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+fn api_error(status: StatusCode, report: &Report) -> ApiError {
+    (
+        status,
+        Json(ErrorResponse {
+            error: report
+                .chain()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": "),
+        }),
+    )
+}
+
+/// The state shared by the request handlers: the bundle directory and the
+/// bundle currently being served
+///
+/// Like [`SharedPolicySet`], the bundle is replaced atomically, so requests
+/// never take a lock and each request sees a single revision of the bundle.
+/// Reloads are serialized by `reloading`, so that concurrent reloads (from
+/// watching the bundle files and from `POST /v1/reload`) publish increasing
+/// revisions, each loaded after the previous one was published.
+#[derive(Debug)]
+struct Agent {
+    dir: PathBuf,
+    bundle: ArcSwap<Bundle>,
+    reloading: Mutex<()>,
+}
+
+impl Agent {
+    fn new(dir: PathBuf, bundle: Bundle) -> Self {
+        Self {
+            dir,
+            bundle: ArcSwap::from_pointee(bundle),
+            reloading: Mutex::new(()),
+        }
+    }
+
+    fn current(&self) -> Arc<Bundle> {
+        self.bundle.load_full()
+    }
+
+    fn lock_reloading(&self) -> MutexGuard<'_, ()> {
+        // the lock guards no data, so it cannot be poisoned in a harmful way
+        self.reloading
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Load the bundle, replacing the current bundle if it loads successfully
+    fn reload(&self) -> Result<u64> {
+        let _reloading = self.lock_reloading();
+        self.publish_next_revision()
+    }
+
+    /// Reload the bundle if any of its files changed since the current bundle
+    /// was loaded, returning the new revision if it was reloaded
+    fn reload_if_stale(&self) -> Result<Option<u64>> {
+        let _reloading = self.lock_reloading();
+        if self.current().is_stale(&self.dir) {
+            self.publish_next_revision().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Load the next revision of the bundle and publish it. Callers must hold
+    /// the `reloading` lock across this call.
+    fn publish_next_revision(&self) -> Result<u64> {
+        let revision = self.current().revision + 1;
+        let bundle = Bundle::load(&self.dir, revision)?;
+        self.bundle.store(Arc::new(bundle));
+        Ok(revision)
+    }
+}
+
+async fn is_authorized(
+    State(agent): State<Arc<Agent>>,
+    Json(request): Json<AuthorizeRequest>,
+) -> Result<Json<AuthorizeResponse>, ApiError> {
+    agent
+        .current()
+        .is_authorized(request)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, &e))
+}
+
+async fn validate(
+    State(agent): State<Arc<Agent>>,
+    Json(request): Json<ValidateRequest>,
+) -> Result<Json<ValidateResponse>, ApiError> {
+    agent
+        .current()
+        .validate(&request.policies)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, &e))
+}
+
+async fn reload(State(agent): State<Arc<Agent>>) -> Result<Json<StatusResponse>, ApiError> {
+    let reloaded = tokio::task::spawn_blocking(move || agent.reload())
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, &miette!("{e}")))?;
+    reloaded
+        .map(|revision| Json(StatusResponse { revision }))
+        .map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, &e))
+}
+
+async fn health(State(agent): State<Arc<Agent>>) -> Json<StatusResponse> {
+    Json(StatusResponse {
+        revision: agent.current().revision,
+    })
+}
+
+/// Reload the bundle whenever its files change, checking every `interval`
+async fn watch(agent: Arc<Agent>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        let agent = Arc::clone(&agent);
+        let _ = tokio::task::spawn_blocking(move || match agent.reload_if_stale() {
+            Ok(Some(revision)) => eprintln!("loaded bundle revision {revision}"),
+            Ok(None) => {}
+            Err(e) => eprintln!("{:?}", e.wrap_err("failed to reload bundle")),
+        })
+        .await;
+    }
+}
+
+/// The HTTP routes of the agent, serving `bundle` from `dir`
+fn router(dir: PathBuf, bundle: Bundle) -> (Router, Arc<Agent>) {
+    let agent = Arc::new(Agent::new(dir, bundle));
+    let router = Router::new()
+        .route("/v1/is_authorized", post(is_authorized))
+        .route("/v1/validate", post(validate))
+        .route("/v1/reload", post(reload))
+        .route("/v1/health", get(health))
+        .with_state(Arc::clone(&agent));
+    (router, agent)
+}
+
+/// Load the bundle and serve it until the process is terminated
+pub async fn run(args: AgentArgs) -> Result<()> {
+    let bundle = Bundle::load(&args.bundle, 1).wrap_err("failed to load bundle")?;
+    let (router, agent) = router(args.bundle, bundle);
+    if args.reload_interval > 0 {
+        tokio::spawn(watch(agent, Duration::from_secs(args.reload_interval)));
+    }
+    let listener = tokio::net::TcpListener::bind(args.listen)
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to listen on {}", args.listen))?;
+    eprintln!("serving bundle revision 1 on {}", args.listen);
+    axum::serve(listener, router).await.into_diagnostic()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_bundle(dir: &Path, policies: &str) {
+        std::fs::write(
+            dir.join(CEDAR_SCHEMA_FILE),
+            "entity User; entity Doc; action view appliesTo { principal: User, resource: Doc };",
+        )
+        .unwrap();
+        std::fs::write(dir.join(POLICIES_FILE), policies).unwrap();
+    }
+
+    fn request() -> AuthorizeRequest {
+        serde_json::from_value(serde_json::json!({
+            "principal": r#"User::"alice""#,
+            "action": r#"Action::"view""#,
+            "resource": r#"Doc::"readme""#,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reload() {
+        let dir = tempfile::tempdir().unwrap();
+        write_bundle(
+            dir.path(),
+            r#"permit(principal == User::"alice", action, resource);"#,
+        );
+        let (_, agent) = router(
+            dir.path().to_path_buf(),
+            Bundle::load(dir.path(), 1).unwrap(),
+        );
+        let response = agent.current().is_authorized(request()).unwrap();
+        assert_eq!(response.decision, Decision::Allow);
+        assert_eq!(response.reasons, vec!["policy0".to_string()]);
+
+        // a bundle which fails validation is rejected
+        write_bundle(
+            dir.path(),
+            r#"permit(principal == Doc::"x", action, resource);"#,
+        );
+        assert!(agent.reload().is_err());
+        assert_eq!(agent.current().revision, 1);
+
+        write_bundle(dir.path(), r#"forbid(principal, action, resource);"#);
+        assert_eq!(agent.reload().unwrap(), 2);
+        let response = agent.current().is_authorized(request()).unwrap();
+        assert_eq!(response.decision, Decision::Deny);
+    }
+
+    #[test]
+    fn concurrent_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        write_bundle(dir.path(), r#"forbid(principal, action, resource);"#);
+        let (_, agent) = router(
+            dir.path().to_path_buf(),
+            Bundle::load(dir.path(), 1).unwrap(),
+        );
+        let mut revisions: Vec<u64> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8).map(|_| s.spawn(|| agent.reload())).collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap().unwrap())
+                .collect()
+        });
+        revisions.sort_unstable();
+        assert_eq!(revisions, (2..10).collect::<Vec<_>>());
+        assert_eq!(agent.current().revision, 9);
+        assert_eq!(agent.reload_if_stale().unwrap(), None);
+    }
+
+    #[test]
+    fn validate() {
+        let dir = tempfile::tempdir().unwrap();
+        write_bundle(dir.path(), "");
+        let bundle = Bundle::load(dir.path(), 1).unwrap();
+        assert!(
+            bundle
+                .validate(r#"permit(principal is User, action, resource);"#)
+                .unwrap()
+                .valid
+        );
+        assert!(
+            !bundle
+                .validate(r#"permit(principal is Photo, action, resource);"#)
+                .unwrap()
+                .valid
+        );
+    }
+}
@@ -19,10 +19,10 @@
 // omitted.
 #![allow(clippy::needless_return)]
 
-use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use miette::{miette, IntoDiagnostic, NamedSource, Report, Result, WrapErr};
 use serde::{Deserialize, Serialize};
-use std::io::Write;
+use std::io::{BufRead, BufReader, Write};
 use std::{
     collections::HashMap,
     fmt::{self, Display},
@@ -30,12 +30,16 @@ use std::{
     path::Path,
     process::{ExitCode, Termination},
     str::FromStr,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use cedar_policy::*;
 use cedar_policy_formatter::{policies_str_to_pretty, Config};
 
+/// The `cedar-agent` policy decision point server
+#[cfg(feature = "agent")]
+pub mod agent;
+
 /// Basic Cedar CLI for evaluating authorization queries
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)] // Pull from `Cargo.toml`
@@ -81,6 +85,20 @@ impl Display for ErrorFormat {
     }
 }
 
+/// Format of the results of commands. Errors which stop a command are
+/// reported according to `--error-format` instead.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output
+    #[default]
+    Human,
+    /// A single JSON document
+    Json,
+    /// Newline-delimited JSON: one JSON object per line, printed as soon as
+    /// it is available, for use in pipelines
+    Ndjson,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Evaluate an authorization request
@@ -93,11 +111,16 @@ pub enum Commands {
     CheckParse(CheckParseArgs),
     /// Link a template
     Link(LinkArgs),
+    /// Link many templates at once, from a CSV or JSON file with a row per
+    /// link, reporting the rows which fail
+    BulkLink(BulkLinkArgs),
     /// Format a policy set
     Format(FormatArgs),
     /// Translate Cedar policy syntax to JSON policy syntax (except comments)
     TranslatePolicy(TranslatePolicyArgs),
-    /// Translate Cedar schema syntax to JSON schema syntax and vice versa (except comments)
+    /// Translate Cedar schema syntax to JSON schema syntax and vice versa. Doc
+    /// comments of entity types and actions are translated to and from `doc`
+    /// annotations; other comments are dropped.
     TranslateSchema(TranslateSchemaArgs),
     /// Visualize a set of JSON entities to the graphviz format.
     /// Warning: Entity visualization is best-effort and not well tested.
@@ -106,6 +129,23 @@ pub enum Commands {
     New(NewArgs),
     /// Partially evaluate an authorization request
     PartiallyAuthorize(PartiallyAuthorizeArgs),
+    /// Generate a random entity store conforming to a schema, e.g., for load testing
+    GenerateEntities(GenerateEntitiesArgs),
+    /// Generate TypeScript declarations for the entity attributes and action
+    /// contexts of a schema
+    GenerateTypescript(GenerateTypescriptArgs),
+    /// Benchmark authorization of a corpus of requests, reporting latency
+    /// percentiles and throughput
+    Bench(BenchArgs),
+    /// Compute the entity manifest of a policy set: for each kind of request,
+    /// the attributes and ancestors the policies may need to load
+    EntityManifest(EntityManifestArgs),
+    /// Analyze a policy set against a schema, reporting shadowed permit
+    /// policies, duplicate policies, and unused schema elements, and,
+    /// optionally, entity attribute values which make the policies error
+    Analyze(AnalyzeArgs),
+    /// Generate a shell completion script for the CLI
+    Completions(CompletionsArgs),
 }
 
 #[derive(Args, Debug)]
@@ -168,6 +208,8 @@ pub enum ValidationMode {
     Permissive,
     /// Partial validation
     Partial,
+    /// Strict validation, treating entity types not declared in the schema as opaque
+    OpaqueEntityTypes,
 }
 
 #[derive(Args, Debug)]
@@ -181,15 +223,28 @@ pub struct ValidateArgs {
     /// Report a validation failure for non-fatal warnings
     #[arg(long)]
     pub deny_warnings: bool,
+    /// File recording known errors and warnings, which are not reported.
+    /// Only new errors and warnings fail validation.
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<String>,
+    /// Record all current errors and warnings in the `--baseline` file,
+    /// replacing its contents, instead of reporting them
+    #[arg(long, requires = "baseline")]
+    pub update_baseline: bool,
     /// Schema format (Cedar or JSON)
     #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
     pub schema_format: SchemaFormat,
     /// Validate the policy using this mode.
-    /// The options `permissive` and `partial` are experimental
+    /// The options `permissive`, `partial`, and `opaque-entity-types` are experimental
     /// and will cause the CLI to exit if it was not built with the
-    /// experimental feature `permissive-validate` and `partial-validate`, respectively, enabled.
+    /// experimental feature `permissive-validate` or `partial-validate`, respectively, enabled.
     #[arg(long, value_enum, default_value_t = ValidationMode::Strict)]
     pub validation_mode: ValidationMode,
+    /// Output format. With `json`, the errors and warnings are printed as a
+    /// single JSON object. With `ndjson`, each error and warning is printed as
+    /// a JSON object, followed by a summary object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
 }
 
 #[derive(Args, Debug)]
@@ -197,6 +252,10 @@ pub struct CheckParseArgs {
     /// Policies args (incorporated by reference)
     #[command(flatten)]
     pub policies: PoliciesArgs,
+    /// Output format. With `json` or `ndjson`, whether the policies parsed
+    /// and the parse errors are printed as a JSON object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
 }
 
 /// This struct contains the arguments that together specify a request.
@@ -218,7 +277,7 @@ pub struct RequestArgs {
     /// File containing a JSON object representing the entire request. Must have
     /// fields "principal", "action", "resource", and "context", where "context"
     /// is a (possibly empty) map from keys to values. This option replaces
-    /// --principal, --action, etc.
+    /// --principal, --action, etc. Use `-` to read the request from stdin.
     #[arg(long = "request-json", value_name = "FILE", conflicts_with_all = &["principal", "action", "resource", "context_json_file"])]
     pub request_json_file: Option<String>,
     /// Whether to enable request validation. This has no effect if a schema is
@@ -262,35 +321,15 @@ impl RequestArgs {
     fn get_request(&self, schema: Option<&Schema>) -> Result<Request> {
         match &self.request_json_file {
             Some(jsonfile) => {
-                let jsonstring = std::fs::read_to_string(jsonfile)
-                    .into_diagnostic()
-                    .wrap_err_with(|| format!("failed to open request-json file {jsonfile}"))?;
+                let jsonstring = read_from_file(jsonfile, "request-json")?;
                 let qjson: RequestJSON = serde_json::from_str(&jsonstring)
                     .into_diagnostic()
                     .wrap_err_with(|| format!("failed to parse request-json file {jsonfile}"))?;
-                let principal = qjson.principal.parse().wrap_err_with(|| {
-                    format!("failed to parse principal in {jsonfile} as entity Uid")
-                })?;
-                let action = qjson.action.parse().wrap_err_with(|| {
-                    format!("failed to parse action in {jsonfile} as entity Uid")
-                })?;
-                let resource = qjson.resource.parse().wrap_err_with(|| {
-                    format!("failed to parse resource in {jsonfile} as entity Uid")
-                })?;
-                let context = Context::from_json_value(qjson.context, schema.map(|s| (s, &action)))
-                    .wrap_err_with(|| format!("failed to create a context from {jsonfile}"))?;
-                Request::new(
-                    principal,
-                    action,
-                    resource,
-                    context,
-                    if self.request_validation {
-                        schema
-                    } else {
-                        None
-                    },
+                qjson.into_request(
+                    schema,
+                    self.request_validation,
+                    &format!("request-json file {jsonfile}"),
                 )
-                .map_err(|e| miette!("{e}"))
             }
             None => {
                 let principal = self
@@ -440,7 +479,7 @@ impl PartialRequestArgs {
 /// This struct contains the arguments that together specify an input policy or policy set.
 #[derive(Args, Debug)]
 pub struct PoliciesArgs {
-    /// File containing the static Cedar policies and/or templates. If not provided, read policies from stdin.
+    /// File containing the static Cedar policies and/or templates. If not provided, or `-`, read policies from stdin.
     #[arg(short, long = "policies", value_name = "FILE")]
     pub policies_file: Option<String>,
     /// Format of policies in the `--policies` file
@@ -449,13 +488,21 @@ pub struct PoliciesArgs {
     /// File containing template-linked policies
     #[arg(short = 'k', long = "template-linked", value_name = "FILE")]
     pub template_linked_file: Option<String>,
+    /// File containing constants, `const NAME = value;`, which the Cedar
+    /// policies may reference by name
+    #[arg(long = "constants", value_name = "FILE")]
+    pub constants_file: Option<String>,
 }
 
 impl PoliciesArgs {
     /// Turn this `PoliciesArgs` into the appropriate `PolicySet` object
     fn get_policy_set(&self) -> Result<PolicySet> {
+        let constants = match self.constants_file.as_ref() {
+            Some(filename) => read_constants(filename)?,
+            None => Constants::default(),
+        };
         let mut pset = match self.policy_format {
-            PolicyFormat::Cedar => read_cedar_policy_set(self.policies_file.as_ref()),
+            PolicyFormat::Cedar => read_cedar_policy_set(self.policies_file.as_ref(), &constants),
             PolicyFormat::Json => read_json_policy_set(self.policies_file.as_ref()),
         }?;
         if let Some(links_filename) = self.template_linked_file.as_ref() {
@@ -463,6 +510,11 @@ impl PoliciesArgs {
         }
         Ok(pset)
     }
+
+    /// Whether the policies are read from stdin
+    fn reads_stdin(&self) -> bool {
+        self.policies_file.as_deref().map_or(true, is_stdin)
+    }
 }
 
 #[derive(Args, Debug)]
@@ -482,7 +534,8 @@ pub struct AuthorizeArgs {
     /// Schema format (Cedar or JSON)
     #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
     pub schema_format: SchemaFormat,
-    /// File containing JSON representation of the Cedar entity hierarchy
+    /// File containing JSON representation of the Cedar entity hierarchy.
+    /// Use `-` to read the entities from stdin.
     #[arg(long = "entities", value_name = "FILE")]
     pub entities_file: String,
     /// More verbose output. (For instance, indicate which policies applied to the request, if any.)
@@ -491,6 +544,17 @@ pub struct AuthorizeArgs {
     /// Time authorization and report timing information
     #[arg(short, long)]
     pub timing: bool,
+    /// Output format. With `json` or `ndjson`, the result of the request is
+    /// printed as a JSON object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+    /// File containing one request per line, each a JSON object as for
+    /// `--request-json`, or `-` to read the requests from stdin. Each request
+    /// is authorized as soon as it is read, and its result is printed as one
+    /// line of JSON, whatever the `--output-format`. This option replaces
+    /// --principal, --request-json, etc.
+    #[arg(long = "requests-ndjson", value_name = "FILE", conflicts_with_all = &["principal", "action", "resource", "context_json_file", "request_json_file"])]
+    pub requests_ndjson_file: Option<String>,
 }
 
 #[cfg(feature = "partial-eval")]
@@ -508,6 +572,10 @@ pub struct PartiallyAuthorizeArgs {
     /// Time authorization and report timing information
     #[arg(short, long)]
     pub timing: bool,
+    /// Output format. With `json` or `ndjson`, the decision and the residual
+    /// policies are printed as a JSON object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
 }
 
 #[cfg(not(feature = "partial-eval"))]
@@ -543,6 +611,50 @@ pub struct LinkArgs {
     /// Arguments to fill slots
     #[arg(short, long)]
     pub arguments: Arguments,
+    /// Output format. With `json` or `ndjson`, the linked policy is printed
+    /// as a JSON object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct BulkLinkArgs {
+    /// Policies args (incorporated by reference). If a `--template-linked`
+    /// file is given, the new links are added to it, provided every row
+    /// succeeds.
+    #[command(flatten)]
+    pub policies: PoliciesArgs,
+    /// File containing the schema. If given, each link is validated against
+    /// it in strict mode, and links which do not validate fail.
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: Option<String>,
+    /// Schema format (Cedar or JSON)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
+    pub schema_format: SchemaFormat,
+    /// File containing the links to add. A CSV file starts with a header row
+    /// naming the columns: `template_id`, `link_id`, and the slots to fill,
+    /// e.g., `?principal`. Cells containing quotes must be quoted, e.g.,
+    /// `"User::""alice"""`, and empty cells leave the slot unfilled. A JSON
+    /// file has the same format as `--template-linked` files.
+    #[arg(long = "parameters", value_name = "FILE")]
+    pub parameters_file: String,
+    /// Format of the parameters file
+    #[arg(long, value_enum, default_value_t)]
+    pub parameters_format: ParametersFormat,
+    /// Output format. With `json` or `ndjson`, the linked policies and the
+    /// failed rows are printed as a JSON object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+}
+
+/// Format of the parameters file of `cedar bulk-link`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ParametersFormat {
+    /// Comma-separated values, with a header row
+    #[default]
+    Csv,
+    /// A JSON array of links, as in `--template-linked` files
+    Json,
 }
 
 #[derive(Args, Debug)]
@@ -566,6 +678,11 @@ pub struct FormatArgs {
     /// Check that the policies formats without any changes. Mutually exclusive with `write`.
     #[arg(short, long, group = "action")]
     pub check: bool,
+
+    /// Output format. With `json` or `ndjson`, the result is printed as a
+    /// JSON object with the formatted policies and whether they changed.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
 }
 
 #[derive(Args, Debug)]
@@ -619,6 +736,42 @@ struct RequestJSON {
     context: serde_json::Value,
 }
 
+impl RequestJSON {
+    /// Turn this `RequestJSON` into the appropriate `Request` object.
+    ///
+    /// `schema` is used as in [`RequestArgs::get_request()`]. `source`
+    /// describes where the request came from, for use in error messages.
+    fn into_request(
+        self,
+        schema: Option<&Schema>,
+        request_validation: bool,
+        source: &str,
+    ) -> Result<Request> {
+        let principal = self
+            .principal
+            .parse()
+            .wrap_err_with(|| format!("failed to parse principal in {source} as entity Uid"))?;
+        let action = self
+            .action
+            .parse()
+            .wrap_err_with(|| format!("failed to parse action in {source} as entity Uid"))?;
+        let resource = self
+            .resource
+            .parse()
+            .wrap_err_with(|| format!("failed to parse resource in {source} as entity Uid"))?;
+        let context = Context::from_json_value(self.context, schema.map(|s| (s, &action)))
+            .wrap_err_with(|| format!("failed to create a context from {source}"))?;
+        Request::new(
+            principal,
+            action,
+            resource,
+            context,
+            if request_validation { schema } else { None },
+        )
+        .map_err(|e| miette!("{e}"))
+    }
+}
+
 #[cfg(feature = "partial-eval")]
 /// This struct is the serde structure expected for --request-json
 #[derive(Deserialize)]
@@ -653,6 +806,149 @@ pub struct EvaluateArgs {
     /// Expression to evaluate
     #[arg(value_name = "EXPRESSION")]
     pub expression: String,
+    /// Output format. With `json` or `ndjson`, the value is printed as a JSON
+    /// object, using the Cedar JSON format for values.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct GenerateEntitiesArgs {
+    /// File containing the schema
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: String,
+    /// Schema format (Cedar or JSON)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
+    pub schema_format: SchemaFormat,
+    /// Number of entities to generate for each entity type in the schema
+    #[arg(long, default_value_t = 100)]
+    pub entities_per_type: usize,
+    /// Maximum number of parents to give each entity, per parent entity type
+    #[arg(long, default_value_t = 3)]
+    pub max_parents: usize,
+    /// Maximum number of elements in generated set-typed attribute values
+    #[arg(long, default_value_t = 4)]
+    pub max_set_size: usize,
+    /// Percentage (0 to 100) of optional attributes to populate
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub optional_attr_percent: u8,
+    /// Seed for the random number generator. The same schema, options, and
+    /// seed always produce the same entities.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// File to write the JSON entities to.
+    /// If not provided, will default to writing to stdout.
+    #[arg(short, long = "output", value_name = "FILE")]
+    pub output_file: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct GenerateTypescriptArgs {
+    /// File containing the schema
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: String,
+    /// Schema format (Cedar or JSON)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
+    pub schema_format: SchemaFormat,
+    /// File to write the TypeScript declarations to.
+    /// If not provided, will default to writing to stdout.
+    #[arg(short, long = "output", value_name = "FILE")]
+    pub output_file: Option<String>,
+}
+
+#[cfg(feature = "entity-manifest")]
+#[derive(Args, Debug)]
+pub struct EntityManifestArgs {
+    /// Policies args (incorporated by reference)
+    #[command(flatten)]
+    pub policies: PoliciesArgs,
+    /// File containing the schema. The policies must validate against it in
+    /// strict mode.
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: String,
+    /// Schema format (Cedar or JSON)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
+    pub schema_format: SchemaFormat,
+    /// File to write the JSON entity manifest to.
+    /// If not provided, will default to writing to stdout.
+    #[arg(short, long = "output", value_name = "FILE")]
+    pub output_file: Option<String>,
+}
+
+#[cfg(not(feature = "entity-manifest"))]
+#[derive(Debug, Args)]
+pub struct EntityManifestArgs;
+
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Policies args (incorporated by reference)
+    #[command(flatten)]
+    pub policies: PoliciesArgs,
+    /// File containing the schema
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: String,
+    /// Schema format (Cedar or JSON)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
+    pub schema_format: SchemaFormat,
+    /// Format of the analysis report
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+    /// Also compute the entity manifest of the policy set. The policies must
+    /// validate against the schema in strict mode.
+    /// This option is experimental and will cause the CLI to exit if it was
+    /// not built with the experimental feature `entity-manifest` enabled.
+    #[arg(long)]
+    pub entity_manifest: bool,
+    /// File containing a JSON snapshot of the entities the policies will be
+    /// evaluated with. If present, also report the attribute values which
+    /// make expressions in the policies error, e.g., strings which are not
+    /// valid arguments to an extension function.
+    #[arg(long = "entities", value_name = "FILE")]
+    pub entities_file: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Policies args (incorporated by reference)
+    #[command(flatten)]
+    pub policies: PoliciesArgs,
+    /// File containing schema information
+    ///
+    /// Used to populate the store with action entities, for schema-based
+    /// parsing of entity hierarchy and contexts, and for request validation
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: Option<String>,
+    /// Schema format (Cedar or JSON)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Cedar)]
+    pub schema_format: SchemaFormat,
+    /// File containing JSON representation of the Cedar entity hierarchy
+    #[arg(long = "entities", value_name = "FILE")]
+    pub entities_file: String,
+    /// File containing a JSON array of requests. Each request has the same
+    /// form as for `authorize --request-json`.
+    #[arg(long = "requests", value_name = "FILE")]
+    pub requests_file: String,
+    /// Number of times to authorize each request in the corpus
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    pub iterations: u32,
+    /// Number of untimed passes over the corpus to run before measuring
+    #[arg(long, default_value_t = 1)]
+    pub warmup: u32,
+    /// Whether to enable request validation. This has no effect if a schema is
+    /// not provided.
+    #[arg(long = "request-validation", action = ArgAction::Set, default_value_t = true)]
+    pub request_validation: bool,
+    /// Output format. With `json` or `ndjson`, the statistics are printed as
+    /// a JSON object.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// The shell to generate the completion script for
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -688,15 +984,65 @@ impl Termination for CedarExitCode {
 }
 
 pub fn check_parse(args: &CheckParseArgs) -> CedarExitCode {
-    match args.policies.get_policy_set() {
+    let result = args.policies.get_policy_set();
+    if args.output_format != OutputFormat::Human {
+        let record = ParseRecord {
+            parsed: result.is_ok(),
+            errors: result
+                .as_ref()
+                .err()
+                .map(JsonDiagnostic::from_report)
+                .into_iter()
+                .collect(),
+        };
+        if let Err(err) = print_json(&record, args.output_format) {
+            eprintln!("{err:?}");
+            return CedarExitCode::Failure;
+        }
+    }
+    match result {
         Ok(_) => CedarExitCode::Success,
         Err(e) => {
-            println!("{e:?}");
+            if args.output_format == OutputFormat::Human {
+                println!("{e:?}");
+            }
             CedarExitCode::Failure
         }
     }
 }
 
+/// The result of `cedar check-parse`, printed as JSON
+#[derive(Debug, Serialize)]
+struct ParseRecord {
+    /// Whether the policies parsed
+    parsed: bool,
+    /// The errors parsing the policies
+    errors: Vec<JsonDiagnostic>,
+}
+
+/// Filter the findings recorded in the baseline file from `result`, or, if
+/// `update` is set, record the findings of `result` in the baseline file
+fn apply_baseline(
+    baseline_file: &str,
+    update: bool,
+    result: ValidationResult,
+) -> Result<ValidationResult> {
+    if update {
+        let baseline = ValidationBaseline::from_result(&result);
+        let json = baseline.to_json_string().into_diagnostic()?;
+        std::fs::write(baseline_file, json + "\n")
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write baseline file {baseline_file}"))?;
+    }
+    let json = std::fs::read_to_string(baseline_file)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read baseline file {baseline_file}"))?;
+    let baseline = ValidationBaseline::from_json_str(&json)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to parse baseline file {baseline_file}"))?;
+    Ok(baseline.filter(result))
+}
+
 pub fn validate(args: &ValidateArgs) -> CedarExitCode {
     let mode = match args.validation_mode {
         ValidationMode::Strict => cedar_policy::ValidationMode::Strict,
@@ -718,12 +1064,21 @@ pub fn validate(args: &ValidateArgs) -> CedarExitCode {
             #[cfg(feature = "partial-validate")]
             cedar_policy::ValidationMode::Partial
         }
+        ValidationMode::OpaqueEntityTypes => {
+            #[cfg(not(feature = "partial-validate"))]
+            {
+                eprintln!("Error: arguments include the experimental option `--validation-mode opaque-entity-types`, but this executable was not built with `partial-validate` experimental feature enabled");
+                return CedarExitCode::Failure;
+            }
+            #[cfg(feature = "partial-validate")]
+            cedar_policy::ValidationMode::OpaqueEntityTypes
+        }
     };
 
     let pset = match args.policies.get_policy_set() {
         Ok(pset) => pset,
         Err(e) => {
-            println!("{e:?}");
+            print_error(&e, args.output_format);
             return CedarExitCode::Failure;
         }
     };
@@ -731,33 +1086,128 @@ pub fn validate(args: &ValidateArgs) -> CedarExitCode {
     let schema = match read_schema_file(&args.schema_file, args.schema_format) {
         Ok(schema) => schema,
         Err(e) => {
-            println!("{e:?}");
+            print_error(&e, args.output_format);
             return CedarExitCode::Failure;
         }
     };
 
     let validator = Validator::new(schema);
-    let result = validator.validate(&pset, mode);
+    let mut result = validator.validate(&pset, mode);
+    if let Some(baseline_file) = &args.baseline {
+        match apply_baseline(baseline_file, args.update_baseline, result) {
+            Ok(filtered) => result = filtered,
+            Err(e) => {
+                print_error(&e, args.output_format);
+                return CedarExitCode::Failure;
+            }
+        }
+    }
 
-    if !result.validation_passed()
-        || (args.deny_warnings && !result.validation_passed_without_warnings())
-    {
-        println!(
-            "{:?}",
-            Report::new(result).wrap_err("policy set validation failed")
-        );
-        CedarExitCode::ValidationFailure
-    } else {
-        println!(
-            "{:?}",
-            Report::new(result).wrap_err("policy set validation passed")
-        );
+    let passed = result.validation_passed()
+        && (!args.deny_warnings || result.validation_passed_without_warnings());
+    match args.output_format {
+        OutputFormat::Human => {
+            let message = if passed {
+                "policy set validation passed"
+            } else {
+                "policy set validation failed"
+            };
+            println!("{:?}", Report::new(result).wrap_err(message));
+        }
+        OutputFormat::Json => {
+            if let Err(err) =
+                print_json(&ValidationReport::new(&result, passed), OutputFormat::Json)
+            {
+                eprintln!("{err:?}");
+                return CedarExitCode::Failure;
+            }
+        }
+        OutputFormat::Ndjson => {
+            if let Err(err) = print_ndjson_validation_result(&result, passed) {
+                eprintln!("{err:?}");
+                return CedarExitCode::Failure;
+            }
+        }
+    }
+    if passed {
         CedarExitCode::Success
+    } else {
+        CedarExitCode::ValidationFailure
+    }
+}
+
+/// One line of the NDJSON output of `cedar validate`
+#[derive(Debug, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum ValidationRecord {
+    /// A validation error
+    Error(JsonDiagnostic),
+    /// A validation warning. The `policy_id` is `None` for warnings about the
+    /// schema.
+    Warning(JsonDiagnostic),
+    /// The last line of the output
+    Summary {
+        validation_passed: bool,
+        errors: usize,
+        warnings: usize,
+    },
+}
+
+/// Print the errors and warnings of `result`, then a summary, as NDJSON
+fn print_ndjson_validation_result(result: &ValidationResult, passed: bool) -> Result<()> {
+    for err in result.validation_errors() {
+        print_ndjson(&ValidationRecord::Error(JsonDiagnostic::validation_error(
+            err,
+        )))?;
+    }
+    for warning in result.validation_warnings() {
+        print_ndjson(&ValidationRecord::Warning(
+            JsonDiagnostic::validation_warning(warning),
+        ))?;
+    }
+    print_ndjson(&ValidationRecord::Summary {
+        validation_passed: passed,
+        errors: result.validation_errors().count(),
+        warnings: result.validation_warnings().count(),
+    })
+}
+
+/// The result of `cedar validate`, printed as a single JSON object
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidationReport {
+    /// Whether validation passed, taking `--deny-warnings` into account
+    validation_passed: bool,
+    /// The validation errors
+    errors: Vec<JsonDiagnostic>,
+    /// The validation warnings
+    warnings: Vec<JsonDiagnostic>,
+}
+
+impl ValidationReport {
+    fn new(result: &ValidationResult, passed: bool) -> Self {
+        Self {
+            validation_passed: passed,
+            errors: result
+                .validation_errors()
+                .map(JsonDiagnostic::validation_error)
+                .collect(),
+            warnings: result
+                .validation_warnings()
+                .map(JsonDiagnostic::validation_warning)
+                .collect(),
+        }
     }
 }
 
 pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
-    println!();
+    if args.output_format == OutputFormat::Human {
+        println!();
+    }
     let schema = match args
         .schema_file
         .as_ref()
@@ -766,14 +1216,14 @@ pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
         None => None,
         Some(Ok(schema)) => Some(schema),
         Some(Err(e)) => {
-            println!("{e:?}");
+            print_error(&e, args.output_format);
             return (CedarExitCode::Failure, EvalResult::Bool(false));
         }
     };
     let request = match args.request.get_request(schema.as_ref()) {
         Ok(q) => q,
         Err(e) => {
-            println!("{e:?}");
+            print_error(&e, args.output_format);
             return (CedarExitCode::Failure, EvalResult::Bool(false));
         }
     };
@@ -781,7 +1231,10 @@ pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
         match Expression::from_str(&args.expression).wrap_err("failed to parse the expression") {
             Ok(expr) => expr,
             Err(e) => {
-                println!("{:?}", e.with_source_code(args.expression.clone()));
+                print_error(
+                    &e.with_source_code(args.expression.clone()),
+                    args.output_format,
+                );
                 return (CedarExitCode::Failure, EvalResult::Bool(false));
             }
         };
@@ -790,7 +1243,7 @@ pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
         Some(file) => match load_entities(file, schema.as_ref()) {
             Ok(entities) => entities,
             Err(e) => {
-                println!("{e:?}");
+                print_error(&e, args.output_format);
                 return (CedarExitCode::Failure, EvalResult::Bool(false));
             }
         },
@@ -798,19 +1251,51 @@ pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
     match eval_expression(&request, &entities, &expr).wrap_err("failed to evaluate the expression")
     {
         Err(e) => {
-            println!("{e:?}");
+            print_error(&e, args.output_format);
             return (CedarExitCode::Failure, EvalResult::Bool(false));
         }
         Ok(result) => {
-            println!("{result}");
+            if args.output_format == OutputFormat::Human {
+                println!("{result}");
+            } else if let Err(err) = print_json(
+                &serde_json::json!({ "value": eval_result_to_json(&result) }),
+                args.output_format,
+            ) {
+                eprintln!("{err:?}");
+                return (CedarExitCode::Failure, result);
+            }
             return (CedarExitCode::Success, result);
         }
     }
 }
 
+/// Convert the result of `cedar evaluate` to the Cedar JSON format for values
+fn eval_result_to_json(result: &EvalResult) -> serde_json::Value {
+    match result {
+        EvalResult::Bool(b) => serde_json::Value::Bool(*b),
+        EvalResult::Long(l) => serde_json::Value::from(*l),
+        EvalResult::String(s) => serde_json::Value::String(s.clone()),
+        EvalResult::EntityUid(uid) => serde_json::json!({
+            "__entity": {
+                "type": uid.type_name().to_string(),
+                "id": uid.id().as_ref(),
+            }
+        }),
+        EvalResult::Null => serde_json::Value::Null,
+        EvalResult::Set(set) => set.iter().map(eval_result_to_json).collect(),
+        EvalResult::Record(record) => serde_json::Value::Object(
+            record
+                .iter()
+                .map(|(k, v)| (k.clone(), eval_result_to_json(v)))
+                .collect(),
+        ),
+        EvalResult::ExtensionValue(v) => serde_json::Value::String(v.clone()),
+    }
+}
+
 pub fn link(args: &LinkArgs) -> CedarExitCode {
     if let Err(err) = link_inner(args) {
-        println!("{err:?}");
+        print_error(&err, args.output_format);
         CedarExitCode::Failure
     } else {
         CedarExitCode::Success
@@ -843,8 +1328,8 @@ fn format_policies_inner(args: &FormatArgs) -> Result<bool> {
     let formatted_policy = policies_str_to_pretty(&policies_str, &config)?;
     let are_policies_equivalent = policies_str == formatted_policy;
 
-    match &args.policies_file {
-        Some(policies_file) if args.write => {
+    let written = match &args.policies_file {
+        Some(policies_file) if args.write && !is_stdin(policies_file) => {
             let mut file = OpenOptions::new()
                 .write(true)
                 .truncate(true)
@@ -856,17 +1341,43 @@ fn format_policies_inner(args: &FormatArgs) -> Result<bool> {
                 .wrap_err(format!(
                     "failed to write formatted policies to {policies_file}"
                 ))?;
+            true
         }
-        _ => println!("{}", formatted_policy),
+        _ => false,
+    };
+    match args.output_format {
+        OutputFormat::Human if !written => println!("{}", formatted_policy),
+        OutputFormat::Human => (),
+        OutputFormat::Json | OutputFormat::Ndjson => print_json(
+            &FormatRecord {
+                policies_file: args.policies_file.as_deref().filter(|f| !is_stdin(f)),
+                changed: !are_policies_equivalent,
+                formatted: (!written).then_some(formatted_policy.as_str()),
+            },
+            args.output_format,
+        )?,
     }
     Ok(are_policies_equivalent)
 }
 
+/// The result of `cedar format`, printed as JSON
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FormatRecord<'a> {
+    /// The file containing the policies, or `None` for stdin
+    policies_file: Option<&'a str>,
+    /// Whether formatting changed the policies
+    changed: bool,
+    /// The formatted policies, unless they were written back to the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    formatted: Option<&'a str>,
+}
+
 pub fn format_policies(args: &FormatArgs) -> CedarExitCode {
     match format_policies_inner(args) {
         Ok(false) if args.check => CedarExitCode::Failure,
         Err(err) => {
-            println!("{err:?}");
+            print_error(&err, args.output_format);
             CedarExitCode::Failure
         }
         _ => CedarExitCode::Success,
@@ -1052,7 +1563,17 @@ fn link_inner(args: &LinkArgs) -> Result<()> {
     let linked = policies
         .policy(&PolicyId::new(&args.new_id))
         .ok_or_else(|| miette!("Failed to find newly-added template-linked policy"))?;
-    println!("Template-linked policy added: {linked}");
+    if args.output_format == OutputFormat::Human {
+        println!("Template-linked policy added: {linked}");
+    } else {
+        print_json(
+            &serde_json::json!({
+                "policyId": linked.id().to_string(),
+                "policy": linked.to_string(),
+            }),
+            args.output_format,
+        )?;
+    }
 
     // If a `--template-linked` / `-k` option was provided, update that file with the new link
     if let Some(links_filename) = args.policies.template_linked_file.as_ref() {
@@ -1182,28 +1703,749 @@ fn write_template_linked_file(linked: &[TemplateLinked], path: impl AsRef<Path>)
     serde_json::to_writer(f, linked).into_diagnostic()
 }
 
+/// The result of `cedar bulk-link`
+#[derive(Debug, Default, Serialize)]
+struct BulkLinkRecord {
+    /// The ids of the links which were added
+    linked: Vec<String>,
+    /// The rows which failed, sorted by row
+    failures: Vec<BulkLinkRowFailure>,
+}
+
+/// A row of the parameters file of `cedar bulk-link` which failed
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkLinkRowFailure {
+    /// The line of the row in a CSV file, or its position (from 1) in a JSON
+    /// file
+    row: usize,
+    /// The id of the link, unless the row could not be parsed
+    link_id: Option<String>,
+    /// Why the row failed
+    errors: Vec<JsonDiagnostic>,
+}
+
+impl BulkLinkRowFailure {
+    fn new(row: usize, link_id: Option<String>, err: &Report) -> Self {
+        Self {
+            row,
+            link_id,
+            errors: vec![JsonDiagnostic::from_report(err)],
+        }
+    }
+}
+
+/// Split a line of a CSV file into its fields. A field may be quoted, in
+/// which case it may contain commas, and `""` stands for a quote.
+fn parse_csv_line(line: &str) -> Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(miette!("unterminated quoted field"));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Parse the rows of a CSV parameters file, each with its line number. Fails
+/// if the header row is invalid; each other row fails on its own.
+fn parse_csv_link_parameters(src: &str) -> Result<Vec<(usize, Result<TemplateLinked>)>> {
+    let mut lines = src
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = parse_csv_line(header)
+        .wrap_err("failed to parse the header row")?
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| miette!("the header row has no `{name}` column"))
+    };
+    let template_column = column("template_id")?;
+    let link_column = column("link_id")?;
+    let slot_columns = header
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != template_column && *i != link_column)
+        .map(|(i, name)| parse_slot_id(name).map(|slot| (i, slot)))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|err| miette!("{err}"))?;
+    Ok(lines
+        .map(|(i, line)| {
+            let linked = parse_csv_line(line).and_then(|fields| {
+                if fields.len() != header.len() {
+                    return Err(miette!(
+                        "expected {} fields, found {}",
+                        header.len(),
+                        fields.len()
+                    ));
+                }
+                // there is a field for every column, as checked above
+                let field = |i: usize| fields.get(i).cloned().unwrap_or_default();
+                Ok(TemplateLinked {
+                    template_id: field(template_column),
+                    link_id: field(link_column),
+                    args: slot_columns
+                        .iter()
+                        .map(|(i, slot)| (slot.clone(), field(*i)))
+                        .filter(|(_, value)| !value.is_empty())
+                        .collect(),
+                })
+            });
+            (i + 1, linked)
+        })
+        .collect())
+}
+
+/// Parse the rows of the parameters file of `cedar bulk-link`, each with its
+/// row number
+fn load_link_parameters(args: &BulkLinkArgs) -> Result<Vec<(usize, Result<TemplateLinked>)>> {
+    let src = read_from_file(&args.parameters_file, "parameters")?;
+    match args.parameters_format {
+        ParametersFormat::Csv => parse_csv_link_parameters(&src),
+        ParametersFormat::Json => serde_json::from_str::<Vec<TemplateLinked>>(&src)
+            .into_diagnostic()
+            .map(|links| {
+                links
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, linked)| (i + 1, Ok(linked)))
+                    .collect()
+            }),
+    }
+    .wrap_err_with(|| format!("failed to parse parameters file {}", args.parameters_file))
+}
+
+fn bulk_link_inner(args: &BulkLinkArgs) -> Result<BulkLinkRecord> {
+    let mut policies = args.policies.get_policy_set()?;
+    let validator = args
+        .schema_file
+        .as_ref()
+        .map(|f| read_schema_file(f, args.schema_format))
+        .transpose()?
+        .map(Validator::new);
+    let mut record = BulkLinkRecord::default();
+    let mut rows = Vec::new();
+    let mut links = Vec::new();
+    for (row, linked) in load_link_parameters(args)? {
+        let linked = match linked {
+            Ok(linked) => linked,
+            Err(err) => {
+                record
+                    .failures
+                    .push(BulkLinkRowFailure::new(row, None, &err));
+                continue;
+            }
+        };
+        match create_slot_env(&linked.args) {
+            Ok(values) => {
+                links.push(BulkTemplateLink {
+                    template_id: PolicyId::new(&linked.template_id),
+                    link_id: PolicyId::new(&linked.link_id),
+                    values,
+                });
+                rows.push((row, linked));
+            }
+            Err(err) => {
+                record
+                    .failures
+                    .push(BulkLinkRowFailure::new(row, Some(linked.link_id), &err))
+            }
+        }
+    }
+
+    let report = policies.link_all(links, validator.as_ref());
+    record.linked = report.linked().map(ToString::to_string).collect();
+    for failure in report.failures() {
+        let errors = match failure.error() {
+            BulkLinkError::Validation { errors, .. } => errors
+                .iter()
+                .map(JsonDiagnostic::validation_error)
+                .collect(),
+            err => vec![JsonDiagnostic::new(err)],
+        };
+        // PANIC SAFETY: `rows` has an entry for each link passed to `link_all`
+        #[allow(clippy::indexing_slicing)]
+        let row = rows[failure.index()].0;
+        record.failures.push(BulkLinkRowFailure {
+            row,
+            link_id: Some(failure.link_id().to_string()),
+            errors,
+        });
+    }
+    record.failures.sort_by_key(|failure| failure.row);
+
+    // Only update the `--template-linked` file if every row succeeded, so
+    // that the parameters file can be fixed and used again
+    if let Some(links_filename) = args.policies.template_linked_file.as_ref() {
+        if record.failures.is_empty() {
+            let mut template_linked = load_links_from_file(links_filename)?;
+            template_linked.extend(rows.into_iter().map(|(_, linked)| linked));
+            write_template_linked_file(&template_linked, links_filename)?;
+        }
+    }
+    Ok(record)
+}
+
+pub fn bulk_link(args: &BulkLinkArgs) -> CedarExitCode {
+    let record = match bulk_link_inner(args) {
+        Ok(record) => record,
+        Err(err) => {
+            print_error(&err, args.output_format);
+            return CedarExitCode::Failure;
+        }
+    };
+    if args.output_format == OutputFormat::Human {
+        for failure in &record.failures {
+            match &failure.link_id {
+                Some(link_id) => println!("row {}: failed to link `{link_id}`", failure.row),
+                None => println!("row {}: failed to parse the row", failure.row),
+            }
+            for err in &failure.errors {
+                println!("  {}", err.message);
+            }
+        }
+        println!(
+            "linked {} of {} rows",
+            record.linked.len(),
+            record.linked.len() + record.failures.len()
+        );
+    } else if let Err(err) = print_json(&record, args.output_format) {
+        eprintln!("{err:?}");
+        return CedarExitCode::Failure;
+    }
+    if record.failures.is_empty() {
+        CedarExitCode::Success
+    } else {
+        CedarExitCode::Failure
+    }
+}
+
+fn generate_entities_inner(args: &GenerateEntitiesArgs) -> Result<()> {
+    let schema = read_schema_file(&args.schema_file, args.schema_format)?;
+    let config = EntityGeneratorConfig {
+        entities_per_type: args.entities_per_type,
+        max_parents: args.max_parents,
+        max_set_size: args.max_set_size,
+        optional_attr_percent: args.optional_attr_percent,
+        seed: args.seed,
+    };
+    let entities = cedar_policy::generate_entities(&schema, &config)?;
+    match &args.output_file {
+        Some(filename) => {
+            let f = OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .create(true)
+                .open(filename)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to open output file {filename}"))?;
+            entities.write_to_json(f)?;
+        }
+        None => {
+            entities.write_to_json(std::io::stdout())?;
+            println!();
+        }
+    }
+    Ok(())
+}
+
+pub fn generate_entities_cmd(args: &GenerateEntitiesArgs) -> CedarExitCode {
+    match generate_entities_inner(args) {
+        Ok(()) => CedarExitCode::Success,
+        Err(err) => {
+            eprintln!("{err:?}");
+            CedarExitCode::Failure
+        }
+    }
+}
+
+fn generate_typescript_inner(args: &GenerateTypescriptArgs) -> Result<()> {
+    let schema = read_schema_file(&args.schema_file, args.schema_format)?;
+    let ts = cedar_policy::generate_typescript(&schema)?;
+    match &args.output_file {
+        Some(filename) => std::fs::write(filename, ts)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write output file {filename}"))?,
+        None => print!("{ts}"),
+    }
+    Ok(())
+}
+
+pub fn generate_typescript(args: &GenerateTypescriptArgs) -> CedarExitCode {
+    match generate_typescript_inner(args) {
+        Ok(()) => CedarExitCode::Success,
+        Err(err) => {
+            eprintln!("{err:?}");
+            CedarExitCode::Failure
+        }
+    }
+}
+
+#[cfg(feature = "entity-manifest")]
+fn entity_manifest_inner(args: &EntityManifestArgs) -> Result<()> {
+    let pset = args.policies.get_policy_set()?;
+    let schema = read_schema_file(&args.schema_file, args.schema_format)?;
+    let manifest = compute_entity_manifest(&schema, &pset)?;
+    let json = manifest.to_json_string().into_diagnostic()?;
+    match &args.output_file {
+        Some(filename) => std::fs::write(filename, json)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write output file {filename}"))?,
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "entity-manifest")]
+pub fn entity_manifest(args: &EntityManifestArgs) -> CedarExitCode {
+    match entity_manifest_inner(args) {
+        Ok(()) => CedarExitCode::Success,
+        Err(err) => {
+            eprintln!("{err:?}");
+            CedarExitCode::Failure
+        }
+    }
+}
+
+#[cfg(not(feature = "entity-manifest"))]
+pub fn entity_manifest(_: &EntityManifestArgs) -> CedarExitCode {
+    eprintln!("Error: option `entity-manifest` is experimental, but this executable was not built with `entity-manifest` experimental feature enabled");
+    CedarExitCode::Failure
+}
+
+/// Findings of `cedar analyze`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalysisReport {
+    /// Permit policies which are always overridden by a forbid policy
+    shadowed_permits: Vec<ShadowedPermit>,
+    /// Groups of ids of policies which are semantically equivalent
+    duplicates: Vec<Vec<String>>,
+    /// Warnings for the entity types, attributes, and actions of the schema
+    /// which no policy uses
+    unused_schema_elements: Vec<JsonDiagnostic>,
+    /// Warnings for the expressions in the policies which error when
+    /// evaluated with an entity of the entities file, if provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entity_data_errors: Option<Vec<JsonDiagnostic>>,
+    /// The entity manifest, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entity_manifest: Option<serde_json::Value>,
+}
+
+/// A permit policy which is always overridden by a forbid policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ShadowedPermit {
+    /// The id of the permit policy
+    permit: String,
+    /// The id of the forbid policy
+    forbid: String,
+}
+
+impl AnalysisReport {
+    /// Whether the analysis found any problem with the policy set
+    fn has_findings(&self) -> bool {
+        !self.shadowed_permits.is_empty()
+            || !self.duplicates.is_empty()
+            || !self.unused_schema_elements.is_empty()
+            || self
+                .entity_data_errors
+                .as_ref()
+                .is_some_and(|errors| !errors.is_empty())
+    }
+}
+
+impl Display for AnalysisReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "shadowed permit policies: {}",
+            self.shadowed_permits.len()
+        )?;
+        for ShadowedPermit { permit, forbid } in &self.shadowed_permits {
+            writeln!(f, "  {permit} is always overridden by {forbid}")?;
+        }
+        writeln!(f, "duplicate policies: {}", self.duplicates.len())?;
+        for group in &self.duplicates {
+            writeln!(f, "  {}", group.join(", "))?;
+        }
+        write!(
+            f,
+            "unused schema elements: {}",
+            self.unused_schema_elements.len()
+        )?;
+        for warning in &self.unused_schema_elements {
+            write!(f, "\n  {}", warning.message)?;
+        }
+        if let Some(errors) = &self.entity_data_errors {
+            write!(f, "\nentity data errors: {}", errors.len())?;
+            for warning in errors {
+                write!(f, "\n  {}", warning.message)?;
+            }
+        }
+        if let Some(manifest) = &self.entity_manifest {
+            write!(f, "\nentity manifest:\n{manifest:#}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "entity-manifest")]
+fn analysis_entity_manifest(schema: &Schema, pset: &PolicySet) -> Result<serde_json::Value> {
+    let manifest = compute_entity_manifest(schema, pset)?;
+    manifest.to_json_value().into_diagnostic()
+}
+
+#[cfg(not(feature = "entity-manifest"))]
+fn analysis_entity_manifest(_: &Schema, _: &PolicySet) -> Result<serde_json::Value> {
+    Err(miette!("option `--entity-manifest` is experimental, but this executable was not built with `entity-manifest` experimental feature enabled"))
+}
+
+/// Run the analyses described by `args`
+fn analyze_inner(args: &AnalyzeArgs) -> Result<AnalysisReport> {
+    let pset = args.policies.get_policy_set()?;
+    let schema = read_schema_file(&args.schema_file, args.schema_format)?;
+    let entity_manifest = if args.entity_manifest {
+        Some(analysis_entity_manifest(&schema, &pset)?)
+    } else {
+        None
+    };
+    let shadowed_permits = pset
+        .shadowed_permits(&schema)
+        .into_iter()
+        .map(|(permit, forbid)| ShadowedPermit {
+            permit: permit.to_string(),
+            forbid: forbid.to_string(),
+        })
+        .collect();
+    let duplicates = pset
+        .find_duplicates(&schema, PolicyEquivalence::Semantic)
+        .into_iter()
+        .map(|group| group.iter().map(ToString::to_string).collect())
+        .collect();
+    let entities = args
+        .entities_file
+        .as_ref()
+        .map(|file| load_entities(file, Some(&schema)))
+        .transpose()?;
+    let validator = Validator::new(schema);
+    let unused_schema_elements = validator
+        .unused_schema_elements(&pset)
+        .map(|warning| JsonDiagnostic::validation_warning(&warning))
+        .collect();
+    let entity_data_errors = entities.map(|entities| {
+        validator
+            .entity_data_errors(&pset, &entities)
+            .map(|warning| JsonDiagnostic::validation_warning(&warning))
+            .collect()
+    });
+    Ok(AnalysisReport {
+        shadowed_permits,
+        duplicates,
+        unused_schema_elements,
+        entity_data_errors,
+        entity_manifest,
+    })
+}
+
+pub fn analyze(args: &AnalyzeArgs) -> CedarExitCode {
+    match analyze_inner(args) {
+        Ok(report) => {
+            if args.output_format == OutputFormat::Human {
+                println!("{report}");
+            } else if let Err(err) = print_json(&report, args.output_format) {
+                eprintln!("{err:?}");
+                return CedarExitCode::Failure;
+            }
+            if report.has_findings() {
+                CedarExitCode::ValidationFailure
+            } else {
+                CedarExitCode::Success
+            }
+        }
+        Err(err) => {
+            eprintln!("{err:?}");
+            CedarExitCode::Failure
+        }
+    }
+}
+
+pub fn completions(args: &CompletionsArgs) -> CedarExitCode {
+    clap_complete::generate(
+        args.shell,
+        &mut Cli::command(),
+        "cedar",
+        &mut std::io::stdout(),
+    );
+    CedarExitCode::Success
+}
+
+/// Latency statistics and decision counts collected by `cedar bench`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BenchReport {
+    /// Number of distinct requests in the corpus
+    requests: usize,
+    /// Total number of timed `is_authorized` calls
+    samples: usize,
+    /// Number of requests in the corpus which are allowed
+    allowed: usize,
+    /// Number of requests in the corpus which are denied
+    denied: usize,
+    /// Number of requests in the corpus which produced evaluation errors
+    with_errors: usize,
+    /// Mean latency, in nanoseconds
+    mean_ns: u128,
+    /// Median latency, in nanoseconds
+    p50_ns: u128,
+    /// 90th percentile latency, in nanoseconds
+    p90_ns: u128,
+    /// 99th percentile latency, in nanoseconds
+    p99_ns: u128,
+    /// Maximum latency, in nanoseconds
+    max_ns: u128,
+    /// Throughput, in requests per second
+    throughput: f64,
+}
+
+impl Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn us(ns: u128) -> f64 {
+            ns as f64 / 1000.0
+        }
+        writeln!(
+            f,
+            "requests: {} ({} allowed, {} denied, {} with errors)",
+            self.requests, self.allowed, self.denied, self.with_errors
+        )?;
+        writeln!(f, "samples: {}", self.samples)?;
+        writeln!(
+            f,
+            "latency (micro seconds): mean {:.2}, p50 {:.2}, p90 {:.2}, p99 {:.2}, max {:.2}",
+            us(self.mean_ns),
+            us(self.p50_ns),
+            us(self.p90_ns),
+            us(self.p99_ns),
+            us(self.max_ns)
+        )?;
+        write!(f, "throughput: {:.0} requests/second", self.throughput)
+    }
+}
+
+impl BenchReport {
+    /// Summarize the given latencies (which must be sorted and nonempty)
+    fn from_sorted_latencies(latencies: &[u128]) -> Self {
+        let samples = latencies.len();
+        let total: u128 = latencies.iter().sum();
+        let percentile = |p: usize| {
+            latencies
+                .get(((samples * p).div_ceil(100)).clamp(1, samples) - 1)
+                .copied()
+                .unwrap_or_default()
+        };
+        Self {
+            requests: 0,
+            samples,
+            allowed: 0,
+            denied: 0,
+            with_errors: 0,
+            mean_ns: total / samples as u128,
+            p50_ns: percentile(50),
+            p90_ns: percentile(90),
+            p99_ns: percentile(99),
+            max_ns: latencies.last().copied().unwrap_or_default(),
+            throughput: if total == 0 {
+                f64::INFINITY
+            } else {
+                samples as f64 / (total as f64 / 1e9)
+            },
+        }
+    }
+}
+
+fn load_requests(
+    requests_filename: impl AsRef<Path>,
+    schema: Option<&Schema>,
+    request_validation: bool,
+) -> Result<Vec<Request>> {
+    let filename = requests_filename.as_ref().display();
+    let src = read_from_file(requests_filename.as_ref(), "requests")?;
+    let qjsons: Vec<RequestJSON> = serde_json::from_str(&src)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to parse requests file {filename}"))?;
+    qjsons
+        .into_iter()
+        .enumerate()
+        .map(|(i, qjson)| {
+            qjson.into_request(
+                schema,
+                request_validation,
+                &format!("request {i} of {filename}"),
+            )
+        })
+        .collect()
+}
+
+/// Run the benchmark described by `args`, returning the collected statistics
+fn bench_inner(args: &BenchArgs) -> Result<BenchReport> {
+    let policies = args.policies.get_policy_set()?;
+    let schema = args
+        .schema_file
+        .as_ref()
+        .map(|f| read_schema_file(f, args.schema_format))
+        .transpose()?;
+    let entities = load_entities(&args.entities_file, schema.as_ref())?;
+    let requests = load_requests(
+        &args.requests_file,
+        schema.as_ref(),
+        args.request_validation,
+    )?;
+    if requests.is_empty() {
+        return Err(miette!(
+            "requests file {} contains no requests",
+            args.requests_file
+        ));
+    }
+
+    let authorizer = Authorizer::new();
+    for _ in 0..args.warmup {
+        for request in &requests {
+            std::hint::black_box(authorizer.is_authorized(request, &policies, &entities));
+        }
+    }
+
+    let mut latencies = Vec::with_capacity(requests.len() * args.iterations as usize);
+    let (mut allowed, mut denied, mut with_errors) = (0, 0, 0);
+    for request in &requests {
+        let mut response = None;
+        for _ in 0..args.iterations {
+            let start = Instant::now();
+            let ans = authorizer.is_authorized(request, &policies, &entities);
+            latencies.push(start.elapsed().as_nanos());
+            response = Some(std::hint::black_box(ans));
+        }
+        if let Some(response) = response {
+            match response.decision() {
+                Decision::Allow => allowed += 1,
+                Decision::Deny => denied += 1,
+            }
+            if response.diagnostics().errors().next().is_some() {
+                with_errors += 1;
+            }
+        }
+    }
+    latencies.sort_unstable();
+
+    Ok(BenchReport {
+        requests: requests.len(),
+        allowed,
+        denied,
+        with_errors,
+        ..BenchReport::from_sorted_latencies(&latencies)
+    })
+}
+
+pub fn bench(args: &BenchArgs) -> CedarExitCode {
+    match bench_inner(args) {
+        Ok(report) => {
+            if args.output_format == OutputFormat::Human {
+                println!("{report}");
+            } else if let Err(err) = print_json(&report, args.output_format) {
+                eprintln!("{err:?}");
+                return CedarExitCode::Failure;
+            }
+            CedarExitCode::Success
+        }
+        Err(err) => {
+            eprintln!("{err:?}");
+            CedarExitCode::Failure
+        }
+    }
+}
+
 pub fn authorize(args: &AuthorizeArgs) -> CedarExitCode {
-    println!();
+    let stdin_inputs = [
+        args.policies.reads_stdin(),
+        is_stdin(&args.entities_file),
+        args.request
+            .request_json_file
+            .as_deref()
+            .is_some_and(is_stdin),
+        args.requests_ndjson_file.as_deref().is_some_and(is_stdin),
+    ];
+    if stdin_inputs.into_iter().filter(|stdin| *stdin).count() > 1 {
+        eprintln!("Error: at most one of the policies, the entities, and the requests may be read from stdin");
+        return CedarExitCode::Failure;
+    }
+    if let Some(requests_filename) = &args.requests_ndjson_file {
+        return match authorize_stream(args, requests_filename) {
+            Ok(true) => CedarExitCode::Success,
+            Ok(false) => CedarExitCode::Failure,
+            Err(err) => {
+                eprintln!("{err:?}");
+                CedarExitCode::Failure
+            }
+        };
+    }
+
+    if args.output_format == OutputFormat::Human {
+        println!();
+    }
     let ans = execute_request(
         &args.request,
         &args.policies,
         &args.entities_file,
         args.schema_file.as_ref(),
         args.schema_format,
-        args.timing,
     );
     match ans {
-        Ok(ans) => {
+        Ok((ans, duration)) => {
             let status = match ans.decision() {
-                Decision::Allow => {
-                    println!("ALLOW");
-                    CedarExitCode::Success
-                }
-                Decision::Deny => {
-                    println!("DENY");
-                    CedarExitCode::AuthorizeDeny
-                }
+                Decision::Allow => CedarExitCode::Success,
+                Decision::Deny => CedarExitCode::AuthorizeDeny,
             };
+            if args.output_format != OutputFormat::Human {
+                let record = AuthorizationRecord::new(None, &ans, args.timing.then_some(duration));
+                if let Err(err) = print_json(&record, args.output_format) {
+                    eprintln!("{err:?}");
+                    return CedarExitCode::Failure;
+                }
+                return status;
+            }
+            if args.timing {
+                println!(
+                    "Authorization Time (micro seconds) : {}",
+                    duration.as_micros()
+                );
+            }
+            match ans.decision() {
+                Decision::Allow => println!("ALLOW"),
+                Decision::Deny => println!("DENY"),
+            }
             if ans.diagnostics().errors().peekable().peek().is_some() {
                 println!();
                 for err in ans.diagnostics().errors() {
@@ -1226,13 +2468,111 @@ pub fn authorize(args: &AuthorizeArgs) -> CedarExitCode {
         }
         Err(errs) => {
             for err in errs {
-                println!("{err:?}");
+                print_error(&err, args.output_format);
             }
             CedarExitCode::Failure
         }
     }
 }
 
+/// The result of an authorization request, printed as JSON
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthorizationRecord {
+    /// The line of the request in the `--requests-ndjson` file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    /// The decision, or `None` if the request could not be parsed
+    decision: Option<Decision>,
+    /// The ids of the policies which determined the decision
+    reasons: Vec<String>,
+    /// The errors evaluating policies, or parsing the request
+    errors: Vec<JsonDiagnostic>,
+    /// The time spent authorizing the request, if `--timing` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_micros: Option<u128>,
+}
+
+impl AuthorizationRecord {
+    fn new(line: Option<usize>, response: &Response, duration: Option<Duration>) -> Self {
+        Self {
+            line,
+            decision: Some(response.decision()),
+            reasons: response.diagnostics().reason().map(raw_policy_id).collect(),
+            errors: response
+                .diagnostics()
+                .errors()
+                .map(JsonDiagnostic::authorization_error)
+                .collect(),
+            authorization_micros: duration.map(|d| d.as_micros()),
+        }
+    }
+
+    /// The record for a request which could not be parsed
+    fn invalid_request(line: usize, err: &Report) -> Self {
+        Self {
+            line: Some(line),
+            decision: None,
+            reasons: Vec::new(),
+            errors: vec![JsonDiagnostic::from_report(err)],
+            authorization_micros: None,
+        }
+    }
+}
+
+/// Authorize each request of the `--requests-ndjson` file as soon as it is
+/// read, printing the result of each one as a line of JSON. Returns whether
+/// every request could be parsed.
+fn authorize_stream(args: &AuthorizeArgs, requests_filename: &str) -> Result<bool> {
+    let policies = args.policies.get_policy_set()?;
+    let schema = args
+        .schema_file
+        .as_ref()
+        .map(|f| read_schema_file(f, args.schema_format))
+        .transpose()?;
+    let entities = load_entities(&args.entities_file, schema.as_ref())?;
+    let (name, reader): (&str, Box<dyn BufRead>) = if is_stdin(requests_filename) {
+        ("<stdin>", Box::new(std::io::stdin().lock()))
+    } else {
+        let file = std::fs::File::open(requests_filename)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to open requests file {requests_filename}"))?;
+        (requests_filename, Box::new(BufReader::new(file)))
+    };
+
+    let authorizer = Authorizer::new();
+    let mut all_parsed = true;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read requests from {name}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let source = format!("line {} of {name}", i + 1);
+        let request = serde_json::from_str::<RequestJSON>(&line)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse request on {source}"))
+            .and_then(|qjson| {
+                qjson.into_request(schema.as_ref(), args.request.request_validation, &source)
+            });
+        let record = match request {
+            Ok(request) => {
+                let start = Instant::now();
+                let ans = authorizer.is_authorized(&request, &policies, &entities);
+                let duration = start.elapsed();
+                AuthorizationRecord::new(Some(i + 1), &ans, args.timing.then_some(duration))
+            }
+            Err(err) => {
+                all_parsed = false;
+                AuthorizationRecord::invalid_request(i + 1, &err)
+            }
+        };
+        print_ndjson(&record)?;
+    }
+    Ok(all_parsed)
+}
+
 #[cfg(not(feature = "partial-eval"))]
 pub fn partial_authorize(_: &PartiallyAuthorizeArgs) -> CedarExitCode {
     {
@@ -1243,14 +2583,35 @@ pub fn partial_authorize(_: &PartiallyAuthorizeArgs) -> CedarExitCode {
 
 #[cfg(feature = "partial-eval")]
 pub fn partial_authorize(args: &PartiallyAuthorizeArgs) -> CedarExitCode {
-    println!();
+    if args.output_format == OutputFormat::Human {
+        println!();
+    }
     let ans = execute_partial_request(
         &args.request,
         &args.policies,
         &args.entities_file,
-        args.timing,
+        args.timing && args.output_format == OutputFormat::Human,
     );
     match ans {
+        Ok(ans) if args.output_format != OutputFormat::Human => {
+            let decision = ans.decision();
+            let record = serde_json::json!({
+                "decision": decision,
+                "residuals": ans
+                    .nontrivial_residuals()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>(),
+            });
+            if let Err(err) = print_json(&record, args.output_format) {
+                eprintln!("{err:?}");
+                return CedarExitCode::Failure;
+            }
+            match decision {
+                Some(Decision::Allow) => CedarExitCode::Success,
+                Some(Decision::Deny) => CedarExitCode::AuthorizeDeny,
+                None => CedarExitCode::Unknown,
+            }
+        }
         Ok(ans) => {
             let status = match ans.decision() {
                 Some(Decision::Allow) => {
@@ -1274,7 +2635,7 @@ pub fn partial_authorize(args: &PartiallyAuthorizeArgs) -> CedarExitCode {
         }
         Err(errs) => {
             for err in errs {
-                println!("{err:?}");
+                print_error(&err, args.output_format);
             }
             CedarExitCode::Failure
         }
@@ -1283,6 +2644,10 @@ pub fn partial_authorize(args: &PartiallyAuthorizeArgs) -> CedarExitCode {
 
 /// Load an `Entities` object from the given JSON filename and optional schema.
 fn load_entities(entities_filename: impl AsRef<Path>, schema: Option<&Schema>) -> Result<Entities> {
+    if is_stdin(&entities_filename) {
+        return Entities::from_json_file(std::io::stdin(), schema)
+            .wrap_err("failed to parse entities from stdin");
+    }
     match std::fs::OpenOptions::new()
         .read(true)
         .open(entities_filename.as_ref())
@@ -1333,10 +2698,136 @@ fn rename_from_id_annotation(ps: PolicySet) -> Result<PolicySet> {
     Ok(new_ps)
 }
 
+/// Whether `filename` is `-`, which stands for stdin
+fn is_stdin(filename: impl AsRef<Path>) -> bool {
+    filename.as_ref() == Path::new("-")
+}
+
+/// Print `value` as one line of JSON
+fn print_ndjson(value: &impl Serialize) -> Result<()> {
+    let line = serde_json::to_string(value).into_diagnostic()?;
+    println!("{line}");
+    Ok(())
+}
+
+/// Print `value` as JSON: pretty-printed for `json`, or as one line for
+/// `ndjson`
+fn print_json(value: &impl Serialize, format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Ndjson {
+        return print_ndjson(value);
+    }
+    let json = serde_json::to_string_pretty(value).into_diagnostic()?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Print an error which stopped a command. Human-readable output has always
+/// included such errors on stdout; with JSON output they go to stderr, so that
+/// stdout only contains JSON.
+fn print_error(err: &Report, format: OutputFormat) {
+    match format {
+        OutputFormat::Human => println!("{err:?}"),
+        OutputFormat::Json | OutputFormat::Ndjson => eprintln!("{err:?}"),
+    }
+}
+
+/// A diagnostic (error or warning) in the JSON output of commands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonDiagnostic {
+    /// A stable code for the kind of diagnostic, e.g., the
+    /// [`ValidationError::kind()`] of validation errors
+    code: Option<String>,
+    /// The policy the diagnostic is about, if any
+    policy_id: Option<String>,
+    /// The message of the diagnostic
+    message: String,
+    /// Advice on how to fix the problem, if any
+    help: Option<String>,
+    /// The locations of the problem in the source of the policies (or of
+    /// other input)
+    spans: Vec<JsonSpan>,
+}
+
+/// A labeled location in the JSON output of commands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct JsonSpan {
+    /// Offset of the start of the span, in bytes
+    offset: usize,
+    /// Length of the span, in bytes
+    length: usize,
+    /// Label of the span, if any
+    label: Option<String>,
+}
+
+impl JsonDiagnostic {
+    fn new(diagnostic: &dyn miette::Diagnostic) -> Self {
+        Self {
+            code: diagnostic.code().map(|code| code.to_string()),
+            policy_id: None,
+            message: diagnostic.to_string(),
+            help: diagnostic.help().map(|help| help.to_string()),
+            spans: diagnostic
+                .labels()
+                .into_iter()
+                .flatten()
+                .map(|span| JsonSpan {
+                    offset: span.offset(),
+                    length: span.len(),
+                    label: span.label().map(ToString::to_string),
+                })
+                .collect(),
+        }
+    }
+
+    /// The diagnostic for an error which stopped a command. The message
+    /// includes the context of the error.
+    fn from_report(err: &Report) -> Self {
+        Self {
+            message: err
+                .chain()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": "),
+            ..Self::new(err.as_ref())
+        }
+    }
+
+    fn validation_error(err: &ValidationError) -> Self {
+        Self {
+            code: Some(err.kind().to_string()),
+            policy_id: Some(raw_policy_id(err.policy_id())),
+            ..Self::new(err)
+        }
+    }
+
+    fn validation_warning(warning: &ValidationWarning) -> Self {
+        Self {
+            code: Some(warning.kind().to_string()),
+            policy_id: warning.policy_id().map(raw_policy_id),
+            ..Self::new(warning)
+        }
+    }
+
+    fn authorization_error(err: &AuthorizationError) -> Self {
+        let AuthorizationError::PolicyEvaluationError(eval_err) = err;
+        Self {
+            policy_id: Some(raw_policy_id(eval_err.policy_id())),
+            ..Self::new(err)
+        }
+    }
+}
+
+// The id of a policy as given in the policy set, since its `Display` form
+// escapes quotes and other special characters
+fn raw_policy_id(id: &PolicyId) -> String {
+    AsRef::<str>::as_ref(id).to_owned()
+}
+
 // Read from a file (when `filename` is a `Some`) or stdin (when `filename` is `None`) to a `String`
 fn read_from_file_or_stdin(filename: Option<impl AsRef<Path>>, context: &str) -> Result<String> {
     let mut src_str = String::new();
-    match filename.as_ref() {
+    match filename.as_ref().filter(|path| !is_stdin(path)) {
         Some(path) => {
             src_str = std::fs::read_to_string(path)
                 .into_diagnostic()
@@ -1358,14 +2849,27 @@ fn read_from_file(filename: impl AsRef<Path>, context: &str) -> Result<String> {
     read_from_file_or_stdin(Some(filename), context)
 }
 
+/// Read constants, in Cedar syntax, from the file given in `filename`
+fn read_constants(filename: impl AsRef<Path>) -> Result<Constants> {
+    let context = "constants";
+    let src = read_from_file(&filename, context)?;
+    Constants::from_str(&src)
+        .map_err(|err| {
+            let name = filename.as_ref().display().to_string();
+            Report::new(err).with_source_code(NamedSource::new(name, src))
+        })
+        .wrap_err_with(|| format!("failed to parse {context}"))
+}
+
 /// Read a policy set, in Cedar syntax, from the file given in `filename`,
 /// or from stdin if `filename` is `None`.
 fn read_cedar_policy_set(
     filename: Option<impl AsRef<Path> + std::marker::Copy>,
+    constants: &Constants,
 ) -> Result<PolicySet> {
     let context = "policy set";
     let ps_str = read_from_file_or_stdin(filename, context)?;
-    let ps = PolicySet::from_str(&ps_str)
+    let ps = PolicySet::from_str_with_constants(&ps_str, constants)
         .map_err(|err| {
             let name = filename.map_or_else(
                 || "<stdin>".to_owned(),
@@ -1467,8 +2971,7 @@ fn execute_request(
     entities_filename: impl AsRef<Path>,
     schema_filename: Option<impl AsRef<Path> + std::marker::Copy>,
     schema_format: SchemaFormat,
-    compute_duration: bool,
-) -> Result<Response, Vec<Report>> {
+) -> Result<(Response, Duration), Vec<Report>> {
     let mut errs = vec![];
     let policies = match policies.get_policy_set() {
         Ok(pset) => pset,
@@ -1497,14 +3000,7 @@ fn execute_request(
             let authorizer = Authorizer::new();
             let auth_start = Instant::now();
             let ans = authorizer.is_authorized(&request, &policies, &entities);
-            let auth_dur = auth_start.elapsed();
-            if compute_duration {
-                println!(
-                    "Authorization Time (micro seconds) : {}",
-                    auth_dur.as_micros()
-                );
-            }
-            Ok(ans)
+            Ok((ans, auth_start.elapsed()))
         }
         Ok(_) => Err(errs),
         Err(e) => {
@@ -20,7 +20,8 @@ use clap::Parser;
 use miette::ErrorHook;
 
 use cedar_policy_cli::{
-    authorize, check_parse, evaluate, format_policies, link, new, partial_authorize,
+    analyze, authorize, bench, bulk_link, check_parse, completions, entity_manifest, evaluate,
+    format_policies, generate_entities_cmd, generate_typescript, link, new, partial_authorize,
     translate_policy, translate_schema, validate, visualize, CedarExitCode, Cli, Commands,
     ErrorFormat,
 };
@@ -48,10 +49,17 @@ fn main() -> CedarExitCode {
         Commands::Validate(args) => validate(&args),
         Commands::Format(args) => format_policies(&args),
         Commands::Link(args) => link(&args),
+        Commands::BulkLink(args) => bulk_link(&args),
         Commands::TranslatePolicy(args) => translate_policy(&args),
         Commands::Visualize(args) => visualize(&args),
         Commands::TranslateSchema(args) => translate_schema(&args),
         Commands::New(args) => new(&args),
         Commands::PartiallyAuthorize(args) => partial_authorize(&args),
+        Commands::GenerateEntities(args) => generate_entities_cmd(&args),
+        Commands::GenerateTypescript(args) => generate_typescript(&args),
+        Commands::Bench(args) => bench(&args),
+        Commands::EntityManifest(args) => entity_manifest(&args),
+        Commands::Analyze(args) => analyze(&args),
+        Commands::Completions(args) => completions(&args),
     }
 }
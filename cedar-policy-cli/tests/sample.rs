@@ -27,7 +27,7 @@ use cedar_policy_cli::check_parse;
 use cedar_policy_cli::SchemaFormat;
 use cedar_policy_cli::{
     authorize, evaluate, link, validate, Arguments, AuthorizeArgs, CedarExitCode, CheckParseArgs,
-    EvaluateArgs, LinkArgs, PoliciesArgs, PolicyFormat, RequestArgs, ValidateArgs,
+    EvaluateArgs, LinkArgs, OutputFormat, PoliciesArgs, PolicyFormat, RequestArgs, ValidateArgs,
 };
 
 use predicates::prelude::*;
@@ -39,7 +39,9 @@ fn run_check_parse_test(policies_file: impl Into<String>, expected_exit_code: Ce
             policies_file: Some(policies_file.into()),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: None,
+            constants_file: None,
         },
+        output_format: OutputFormat::Human,
     };
     let output = check_parse(&cmd);
     assert_eq!(output, expected_exit_code, "{:#?}", cmd);
@@ -86,12 +88,15 @@ fn run_authorize_test_with_linked_policies(
             policies_file: Some(policies_file.into()),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: links_file.map(Into::into),
+            constants_file: None,
         },
         schema_file: None,
         schema_format: SchemaFormat::default(),
         entities_file: entities_file.into(),
         verbose: true,
         timing: false,
+        output_format: OutputFormat::Human,
+        requests_ndjson_file: None,
     };
     let output = authorize(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd,);
@@ -110,10 +115,12 @@ fn run_link_test(
             policies_file: Some(policies_file.into()),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: Some(links_file.into()),
+            constants_file: None,
         },
         template_id: template_id.into(),
         new_id: linked_id.into(),
         arguments: Arguments { data: env },
+        output_format: OutputFormat::Human,
     };
     let output = link(&cmd);
     assert_eq!(output, expected);
@@ -141,12 +148,15 @@ fn run_authorize_test_context(
             policies_file: Some(policies_file.into()),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: None,
+            constants_file: None,
         },
         schema_file: None,
         schema_format: SchemaFormat::default(),
         entities_file: entities_file.into(),
         verbose: true,
         timing: false,
+        output_format: OutputFormat::Human,
+        requests_ndjson_file: None,
     };
     let output = authorize(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd,);
@@ -171,12 +181,15 @@ fn run_authorize_test_json(
             policies_file: Some(policies_file.into()),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: None,
+            constants_file: None,
         },
         schema_file: None,
         schema_format: SchemaFormat::default(),
         entities_file: entities_file.into(),
         verbose: true,
         timing: false,
+        output_format: OutputFormat::Human,
+        requests_ndjson_file: None,
     };
     let output = authorize(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd,);
@@ -567,10 +580,14 @@ fn test_validate_samples(
             policies_file: Some(policies_file.clone()),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: None,
+            constants_file: None,
         },
         deny_warnings: false,
+        baseline: None,
+        update_baseline: false,
         validation_mode: cedar_policy_cli::ValidationMode::Strict,
         schema_format: SchemaFormat::Json,
+        output_format: OutputFormat::Human,
     };
     let output = validate(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd);
@@ -585,10 +602,14 @@ fn test_validate_samples(
             policies_file: Some(policies_file),
             policy_format: PolicyFormat::Cedar,
             template_linked_file: None,
+            constants_file: None,
         },
         deny_warnings: false,
+        baseline: None,
+        update_baseline: false,
         validation_mode: cedar_policy_cli::ValidationMode::Strict,
         schema_format: SchemaFormat::Cedar,
+        output_format: OutputFormat::Human,
     };
     let output = validate(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd)
@@ -702,6 +723,7 @@ fn test_evaluate_samples(
             request_validation: true,
         },
         expression: expression.into(),
+        output_format: OutputFormat::Human,
     };
     let output = evaluate(&cmd);
     assert_eq!(exit_code, output.0, "{:#?}", cmd,);
@@ -1154,3 +1176,377 @@ fn test_translate_schema() {
         .assert()
         .code(0);
 }
+
+#[test]
+fn test_translate_schema_doc_comments() {
+    let translate_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("translate-schema")
+        .arg("--direction")
+        .arg("cedar-to-json")
+        .write_stdin("/// A user of the application\nentity User;")
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("A user of the application"));
+    let json = std::str::from_utf8(&translate_cmd.get_output().stdout)
+        .expect("output should be decodable")
+        .to_string();
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("translate-schema")
+        .arg("--direction")
+        .arg("json-to-cedar")
+        .write_stdin(json)
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains(
+            "/// A user of the application\nentity User;",
+        ));
+}
+
+#[test]
+fn test_generate_entities() {
+    let schema_filename = "sample-data/tiny_sandboxes/translate-schema/tinytodo.cedarschema";
+
+    let generate_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("generate-entities")
+        .arg("-s")
+        .arg(schema_filename)
+        .arg("--entities-per-type")
+        .arg("5")
+        .arg("--seed")
+        .arg("42")
+        .assert()
+        .code(0);
+
+    let generated =
+        std::str::from_utf8(&generate_cmd.get_output().stdout).expect("output should be decodable");
+    let schema_src = std::fs::read_to_string(schema_filename).expect("schema file should exist");
+    let (schema, _) =
+        cedar_policy::Schema::from_cedarschema_str(&schema_src).expect("schema should parse");
+    cedar_policy::Entities::from_json_str(generated, Some(&schema))
+        .expect("generated entities should conform to the schema");
+}
+
+#[test]
+fn test_analyze() {
+    let schema_filename = "sample-data/tiny_sandboxes/analyze/schema.cedarschema";
+    let policies_filename = "sample-data/tiny_sandboxes/analyze/policies.cedar";
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("analyze")
+        .arg("-s")
+        .arg(schema_filename)
+        .arg("-p")
+        .arg(policies_filename)
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "alice deletes is always overridden by no deletes",
+        ))
+        .stdout(predicate::str::contains(
+            "everyone views, everyone views again",
+        ));
+
+    let analyze_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("analyze")
+        .arg("-s")
+        .arg(schema_filename)
+        .arg("-p")
+        .arg(policies_filename)
+        .arg("--output-format")
+        .arg("json")
+        .assert()
+        .code(3);
+    let report: serde_json::Value =
+        serde_json::from_slice(&analyze_cmd.get_output().stdout).expect("output should be JSON");
+    assert_eq!(
+        report["shadowedPermits"],
+        serde_json::json!([{ "permit": "alice deletes", "forbid": "no deletes" }])
+    );
+    assert_eq!(
+        report["duplicates"],
+        serde_json::json!([["everyone views", "everyone views again"]])
+    );
+}
+
+#[test]
+fn test_analyze_entities() {
+    let schema_filename = "sample-data/tiny_sandboxes/analyze/schema.cedarschema";
+    let policies_filename = "sample-data/tiny_sandboxes/analyze/policies.cedar";
+    let entities_filename = "sample-data/tiny_sandboxes/analyze/entities.json";
+
+    let analyze_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("analyze")
+        .arg("-s")
+        .arg(schema_filename)
+        .arg("-p")
+        .arg(policies_filename)
+        .arg("--entities")
+        .arg(entities_filename)
+        .arg("--output-format")
+        .arg("json")
+        .assert()
+        .code(3);
+    let report: serde_json::Value =
+        serde_json::from_slice(&analyze_cmd.get_output().stdout).expect("output should be JSON");
+    let errors = report["entityDataErrors"]
+        .as_array()
+        .expect("entity data errors should be reported");
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    let message = errors[0]["message"]
+        .as_str()
+        .expect("should have a message");
+    assert!(message.contains("rich users view"), "{message}");
+    assert!(message.contains(r#"User::"bob""#), "{message}");
+}
+
+/// Parse each line of the output of `assert` as JSON
+fn ndjson_output(assert: &assert_cmd::assert::Assert) -> Vec<serde_json::Value> {
+    std::str::from_utf8(&assert.get_output().stdout)
+        .expect("output should be decodable")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line should be JSON"))
+        .collect()
+}
+
+#[test]
+fn test_ndjson_output() {
+    let policies =
+        std::fs::read_to_string("sample-data/sandbox_a/policies_1_bad.cedar").expect("file exists");
+    let validate_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("validate")
+        .arg("-s")
+        .arg("sample-data/sandbox_a/schema.cedarschema")
+        .arg("--output-format")
+        .arg("ndjson")
+        .write_stdin(policies)
+        .assert()
+        .code(3);
+    let records = ndjson_output(&validate_cmd);
+    assert_eq!(records[0]["type"], "error");
+    assert_eq!(
+        records[0]["policyId"],
+        "jane's friends view-permission policy"
+    );
+    assert_eq!(records.last().unwrap()["type"], "summary");
+    assert_eq!(records.last().unwrap()["validationPassed"], false);
+
+    let requests = [
+        r#"{"principal": "User::\"alice\"", "action": "Action::\"view\"", "resource": "Photo::\"VacationPhoto94.jpg\"", "context": {}}"#,
+        r#"{"principal": "User::\"bob\"", "action": "Action::\"delete\"", "resource": "Photo::\"VacationPhoto94.jpg\"", "context": {}}"#,
+        "",
+        r#"{"principal": "User::\"bob\""}"#,
+    ];
+    let authorize_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("authorize")
+        .arg("-p")
+        .arg("sample-data/sandbox_a/policies_2.cedar")
+        .arg("--entities")
+        .arg("sample-data/sandbox_a/entities.json")
+        .arg("--requests-ndjson")
+        .arg("-")
+        .write_stdin(requests.join("\n"))
+        .assert()
+        .code(1);
+    let records = ndjson_output(&authorize_cmd);
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["line"], 1);
+    assert_eq!(records[0]["decision"], "allow");
+    assert_eq!(
+        records[0]["reasons"],
+        serde_json::json!(["alice's access policy"])
+    );
+    assert_eq!(records[1]["decision"], "deny");
+    assert_eq!(records[2]["line"], 4);
+    assert!(records[2]["decision"].is_null());
+
+    let format_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("--output-format")
+        .arg("ndjson")
+        .write_stdin("permit(principal,action,resource);")
+        .assert()
+        .code(0);
+    let records = ndjson_output(&format_cmd);
+    assert_eq!(records.len(), 1);
+    assert!(records[0]["policiesFile"].is_null());
+    assert_eq!(records[0]["changed"], true);
+    assert!(records[0]["formatted"]
+        .as_str()
+        .unwrap()
+        .starts_with("permit (principal, action, resource);"));
+}
+
+#[test]
+fn test_json_output() {
+    let validate_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("validate")
+        .arg("-s")
+        .arg("sample-data/sandbox_a/schema.cedarschema")
+        .arg("-p")
+        .arg("sample-data/sandbox_a/policies_1_bad.cedar")
+        .arg("--output-format")
+        .arg("json")
+        .assert()
+        .code(3);
+    let report: serde_json::Value =
+        serde_json::from_slice(&validate_cmd.get_output().stdout).expect("output should be JSON");
+    assert_eq!(report["validationPassed"], false);
+    let error = &report["errors"][0];
+    assert_eq!(error["policyId"], "jane's friends view-permission policy");
+    assert!(error["code"].is_string());
+    assert!(!error["spans"].as_array().unwrap().is_empty());
+
+    let check_parse_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("check-parse")
+        .arg("--output-format")
+        .arg("json")
+        .write_stdin("permit(principal,action,resource")
+        .assert()
+        .code(1);
+    let report: serde_json::Value = serde_json::from_slice(&check_parse_cmd.get_output().stdout)
+        .expect("output should be JSON");
+    assert_eq!(report["parsed"], false);
+    assert_eq!(report["errors"].as_array().unwrap().len(), 1);
+
+    let evaluate_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("evaluate")
+        .arg("--principal")
+        .arg(r#"User::"alice""#)
+        .arg("--action")
+        .arg(r#"Action::"view""#)
+        .arg("--resource")
+        .arg(r#"Photo::"VacationPhoto94.jpg""#)
+        .arg("--output-format")
+        .arg("json")
+        .arg(r#"{"a": [1, "b"], "c": principal}"#)
+        .assert()
+        .code(0);
+    let report: serde_json::Value =
+        serde_json::from_slice(&evaluate_cmd.get_output().stdout).expect("output should be JSON");
+    assert_eq!(
+        report["value"],
+        serde_json::json!({
+            "a": [1, "b"],
+            "c": { "__entity": { "type": "User", "id": "alice" } },
+        })
+    );
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("completions")
+        .arg("bash")
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("check-parse"));
+}
+
+#[test]
+fn test_bulk_link() {
+    let dir = "sample-data/tiny_sandboxes/bulk-link";
+    let linked_file = tempfile::NamedTempFile::new().expect("Failed to create linked file");
+    let bulk_link_cmd = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("bulk-link")
+        .arg("-p")
+        .arg(format!("{dir}/policies.cedar"))
+        .arg("-k")
+        .arg(linked_file.path())
+        .arg("-s")
+        .arg(format!("{dir}/schema.cedarschema"))
+        .arg("--parameters")
+        .arg(format!("{dir}/links.csv"))
+        .arg("--output-format")
+        .arg("json")
+        .assert()
+        .code(1);
+    let report: serde_json::Value =
+        serde_json::from_slice(&bulk_link_cmd.get_output().stdout).expect("output should be JSON");
+    assert_eq!(
+        report["linked"],
+        serde_json::json!(["alice views", "bob views"])
+    );
+    let failures = report["failures"].as_array().unwrap();
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0]["row"], 4);
+    assert_eq!(failures[0]["errors"][0]["policyId"], "photo views");
+    assert_eq!(failures[1]["row"], 5);
+    assert_eq!(failures[1]["linkId"], "carol views");
+    // no link is written to the `--template-linked` file if a row fails
+    assert_eq!(
+        std::fs::read_to_string(linked_file.path()).expect("file exists"),
+        ""
+    );
+
+    let links_file = tempfile::NamedTempFile::new().expect("Failed to create links file");
+    std::fs::write(
+        links_file.path(),
+        r#"[{"template_id": "viewer", "link_id": "alice views", "args": {"?principal": "User::\"alice\""}}]"#,
+    )
+    .expect("Failed to write links file");
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("bulk-link")
+        .arg("-p")
+        .arg(format!("{dir}/policies.cedar"))
+        .arg("-k")
+        .arg(linked_file.path())
+        .arg("-s")
+        .arg(format!("{dir}/schema.cedarschema"))
+        .arg("--parameters")
+        .arg(links_file.path())
+        .arg("--parameters-format")
+        .arg("json")
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("linked 1 of 1 rows"));
+    let linked: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(linked_file.path()).expect("file exists"))
+            .expect("linked file should be JSON");
+    assert_eq!(linked[0]["link_id"], "alice views");
+}
+
+#[test]
+fn test_bench() {
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("bench")
+        .arg("-p")
+        .arg("sample-data/sandbox_a/policies_2.cedar")
+        .arg("--entities")
+        .arg("sample-data/sandbox_a/entities.json")
+        .arg("--requests")
+        .arg("sample-data/sandbox_a/requests.json")
+        .arg("--iterations")
+        .arg("10")
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains(
+            "requests: 5 (3 allowed, 2 denied, 0 with errors)",
+        ))
+        .stdout(predicate::str::contains("samples: 50"));
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("bench")
+        .arg("-p")
+        .arg("sample-data/sandbox_a/policies_2.cedar")
+        .arg("--entities")
+        .arg("sample-data/sandbox_a/entities.json")
+        .arg("--requests")
+        .arg("sample-data/sandbox_a/entities.json")
+        .assert()
+        .code(1);
+}
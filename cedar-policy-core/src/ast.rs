@@ -26,6 +26,8 @@ mod id;
 pub use id::*;
 mod integer;
 pub use integer::{InputInteger, Integer};
+mod interner;
+pub use interner::ValueInterner;
 mod literal;
 pub use literal::*;
 mod name;
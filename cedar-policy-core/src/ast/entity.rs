@@ -18,7 +18,7 @@ use crate::ast::*;
 use crate::entities::{err::EntitiesError, json::err::JsonSerializationError, EntityJson};
 use crate::evaluator::{EvaluationError, RestrictedEvaluator};
 use crate::extensions::Extensions;
-use crate::parser::err::ParseErrors;
+use crate::parser::err::{ParseError, ParseErrors, ToASTError, ToASTErrorKind};
 use crate::parser::Loc;
 use crate::transitive_closure::TCNode;
 use crate::FromNormalizedStr;
@@ -30,6 +30,7 @@ use serde_with::{serde_as, TryFromInto};
 use smol_str::SmolStr;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 use thiserror::Error;
 
 /// The entity type that Actions must have
@@ -221,6 +222,41 @@ impl EntityUID {
     pub fn is_action(&self) -> bool {
         self.entity_type().is_action()
     }
+
+    /// Render this `EntityUID` as a string which contains only printable ASCII
+    /// characters. All quotes, backslashes, control characters, and non-ASCII
+    /// characters in the EID are escaped, so the result is always a single
+    /// line and is safe to embed in logs or audit trails.
+    ///
+    /// The result can be parsed back with [`EntityUID::from_safe_str`].
+    pub fn to_safe_string(&self) -> String {
+        format!("{}::\"{}\"", self.entity_type(), self.eid.escaped_ascii())
+    }
+
+    /// Parse an `EntityUID` which was rendered by either [`EntityUID::to_safe_string`]
+    /// or the `Display` impl. Like `from_normalized_str`, this rejects strings
+    /// with spurious whitespace or comments.
+    pub fn from_safe_str(s: &str) -> Result<Self, ParseErrors> {
+        let parsed: Self = s.parse()?;
+        let safe_src = parsed.to_safe_string();
+        if s == safe_src || s == parsed.to_string() {
+            Ok(parsed)
+        } else {
+            let diff_byte = s
+                .bytes()
+                .zip(safe_src.bytes())
+                .position(|(b0, b1)| b0 != b1)
+                .unwrap_or(s.len().min(safe_src.len()));
+            Err(ParseErrors::singleton(ParseError::ToAST(ToASTError::new(
+                ToASTErrorKind::NonNormalizedString {
+                    kind: Self::describe_self(),
+                    src: s.to_string(),
+                    normalized_src: safe_src,
+                },
+                Loc::new(diff_byte, s.into()),
+            ))))
+        }
+    }
 }
 
 impl std::fmt::Display for EntityUID {
@@ -277,6 +313,54 @@ impl Eid {
     pub fn escaped(&self) -> SmolStr {
         self.0.escape_debug().collect()
     }
+
+    /// Get the contents of the `Eid` as an escaped string containing only
+    /// printable ASCII characters. Unlike [`Eid::escaped`], every non-ASCII
+    /// character is escaped (as `\u{...}`), so the result can't contain e.g.
+    /// bidirectional-text overrides or Unicode line separators.
+    pub fn escaped_ascii(&self) -> SmolStr {
+        self.0.escape_default().collect()
+    }
+
+    /// Get the first character in this `Eid` which is likely to cause
+    /// confusion when displayed, along with its byte offset.
+    ///
+    /// Suspicious characters are control characters (including newlines),
+    /// Unicode line and paragraph separators, and bidirectional-text
+    /// formatting characters.
+    pub fn first_suspicious_char(&self) -> Option<(usize, char)> {
+        self.0.char_indices().find(|(_, c)| is_suspicious_char(*c))
+    }
+
+    /// Check that this `Eid` contains no suspicious characters (see
+    /// [`Eid::first_suspicious_char`])
+    pub fn check_not_suspicious(&self) -> Result<(), SuspiciousEidError> {
+        match self.first_suspicious_char() {
+            None => Ok(()),
+            Some((offset, ch)) => Err(SuspiciousEidError {
+                eid: self.clone(),
+                offset,
+                ch,
+            }),
+        }
+    }
+}
+
+/// Is `c` a character which is likely to cause confusion when an `Eid`
+/// containing it is displayed?
+fn is_suspicious_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            // line and paragraph separators
+            '\u{2028}' | '\u{2029}'
+            // bidirectional-text marks, embeddings, overrides, and isolates
+            | '\u{061C}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2066}'..='\u{2069}'
+        )
 }
 
 impl AsRef<SmolStr> for Eid {
@@ -314,7 +398,17 @@ pub struct Entity {
 
     /// Set of ancestors of this `Entity` (i.e., all direct and transitive
     /// parents), as UIDs
-    ancestors: HashSet<EntityUID>,
+    ///
+    /// This is behind an `Arc` so that entities with identical ancestor sets
+    /// can share a single allocation (see `Entities::share_ancestor_sets`).
+    /// Mutation goes through `Arc::make_mut`, so sharing never affects semantics.
+    ancestors: Arc<HashSet<EntityUID>>,
+
+    /// Memoized transitively-closed ancestor set, used only when the
+    /// containing `Entities` was built with `TCComputation::ComputeOnDemand`
+    /// (in which case `ancestors` holds only the direct parents).
+    #[serde(skip)]
+    closed_ancestors: OnceLock<Arc<HashSet<EntityUID>>>,
 }
 
 impl std::hash::Hash for Entity {
@@ -351,7 +445,8 @@ impl Entity {
         Ok(Entity {
             uid,
             attrs: evaluated_attrs,
-            ancestors,
+            ancestors: Arc::new(ancestors),
+            closed_ancestors: OnceLock::new(),
         })
     }
 
@@ -374,7 +469,8 @@ impl Entity {
         Entity {
             uid,
             attrs: attrs.into_iter().map(|(k, v)| (k, v.into())).collect(), // TODO(#540): can we do this without disassembling and reassembling the HashMap
-            ancestors,
+            ancestors: Arc::new(ancestors),
+            closed_ancestors: OnceLock::new(),
         }
     }
 
@@ -390,7 +486,8 @@ impl Entity {
         Entity {
             uid,
             attrs,
-            ancestors,
+            ancestors: Arc::new(ancestors),
+            closed_ancestors: OnceLock::new(),
         }
     }
 
@@ -405,6 +502,10 @@ impl Entity {
     }
 
     /// Is this `Entity` a descendant of `e` in the entity hierarchy?
+    ///
+    /// This only consults the ancestors stored on this `Entity`. If the
+    /// containing `Entities` computes the TC on demand, those are only the
+    /// direct parents; use `Entities::is_descendant_of` instead.
     pub fn is_descendant_of(&self, e: &EntityUID) -> bool {
         self.ancestors.contains(e)
     }
@@ -414,6 +515,52 @@ impl Entity {
         self.ancestors.iter()
     }
 
+    /// Get the number of ancestors of this entity
+    pub fn ancestors_len(&self) -> usize {
+        self.ancestors.len()
+    }
+
+    /// Does this entity share its ancestor set allocation with `other`?
+    ///
+    /// This is purely a memory-layout question: two entities with equal
+    /// ancestor sets may or may not share storage.
+    pub fn shares_ancestors_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ancestors, &other.ancestors)
+    }
+
+    /// Replace this entity's ancestor set with `ancestors`, which must be
+    /// equal (as a set) to the current ancestor set. Used to let entities with
+    /// identical ancestor sets share one allocation.
+    pub(crate) fn set_shared_ancestors(&mut self, ancestors: Arc<HashSet<EntityUID>>) {
+        debug_assert_eq!(self.ancestors, ancestors);
+        self.ancestors = ancestors;
+    }
+
+    /// Get a shared handle to this entity's ancestor set
+    pub(crate) fn ancestors_arc(&self) -> &Arc<HashSet<EntityUID>> {
+        &self.ancestors
+    }
+
+    /// Get the memoized transitively-closed ancestor set, if it has been
+    /// computed
+    pub(crate) fn memoized_closed_ancestors(&self) -> Option<&Arc<HashSet<EntityUID>>> {
+        self.closed_ancestors.get()
+    }
+
+    /// Get the memoized transitively-closed ancestor set, computing it with
+    /// `f` if it has not been computed yet
+    pub(crate) fn closed_ancestors_or_init(
+        &self,
+        f: impl FnOnce() -> HashSet<EntityUID>,
+    ) -> &Arc<HashSet<EntityUID>> {
+        self.closed_ancestors.get_or_init(|| Arc::new(f()))
+    }
+
+    /// Forget the memoized transitively-closed ancestor set
+    pub(crate) fn clear_closed_ancestors(&mut self) {
+        self.closed_ancestors.take();
+    }
+
     /// Get the number of attributes on this entity
     pub fn attrs_len(&self) -> usize {
         self.attrs.len()
@@ -434,7 +581,8 @@ impl Entity {
         Self {
             uid,
             attrs: BTreeMap::new(),
-            ancestors: HashSet::new(),
+            ancestors: Arc::new(HashSet::new()),
+            closed_ancestors: OnceLock::new(),
         }
     }
 
@@ -463,12 +611,14 @@ impl Entity {
     // When fuzzing, `add_ancestor()` is fully `pub`.
     #[cfg(not(fuzzing))]
     pub(crate) fn add_ancestor(&mut self, uid: EntityUID) {
-        self.ancestors.insert(uid);
+        Arc::make_mut(&mut self.ancestors).insert(uid);
+        self.closed_ancestors.take();
     }
     /// Mark the given `UID` as an ancestor of this `Entity`
     #[cfg(fuzzing)]
     pub fn add_ancestor(&mut self, uid: EntityUID) {
-        self.ancestors.insert(uid);
+        Arc::make_mut(&mut self.ancestors).insert(uid);
+        self.closed_ancestors.take();
     }
 
     /// Consume the entity and return the entity's owned Uid, attributes and parents.
@@ -483,11 +633,12 @@ impl Entity {
             uid,
             attrs,
             ancestors,
+            ..
         } = self;
         (
             uid,
             attrs.into_iter().map(|(k, v)| (k, v.0)).collect(),
-            ancestors,
+            Arc::unwrap_or_clone(ancestors),
         )
     }
 
@@ -616,6 +767,39 @@ pub struct EntityAttrEvaluationError {
     pub err: EvaluationError,
 }
 
+/// Error type for an `Eid` which contains a character that is likely to cause
+/// confusion when displayed (for instance, in a log line or audit trail).
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+#[error("entity id \"{}\" contains a suspicious character `{}` at byte offset {offset}", .eid.escaped_ascii(), .ch.escape_unicode())]
+#[diagnostic(help(
+    "control characters, line separators, and bidirectional-text formatting characters are rejected in strict mode"
+))]
+pub struct SuspiciousEidError {
+    /// The `Eid` containing the suspicious character
+    eid: Eid,
+    /// Byte offset of the suspicious character in the `Eid`
+    offset: usize,
+    /// The suspicious character
+    ch: char,
+}
+
+impl SuspiciousEidError {
+    /// The `Eid` containing the suspicious character
+    pub fn eid(&self) -> &Eid {
+        &self.eid
+    }
+
+    /// Byte offset of the (first) suspicious character in the `Eid`
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The (first) suspicious character
+    pub fn suspicious_char(&self) -> char {
+        self.ch
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
@@ -669,6 +853,51 @@ mod test {
         assert!(!euid.is_action());
     }
 
+    #[test]
+    fn safe_string_roundtrip() {
+        for eid in [
+            "alice",
+            "b'ob\"by",
+            "multi\nline\r\n",
+            "caf\u{e9}",
+            "evil\u{202E}txt.exe",
+            "back\\slash",
+            "nul\0",
+        ] {
+            let euid = EntityUID::from_components(
+                "User".parse::<Name>().unwrap().into(),
+                Eid::new(eid),
+                None,
+            );
+            let safe = euid.to_safe_string();
+            assert!(
+                safe.chars().all(|c| c.is_ascii() && !c.is_ascii_control()),
+                "{safe:?} should be printable ASCII"
+            );
+            assert_eq!(EntityUID::from_safe_str(&safe).unwrap(), euid);
+            assert_eq!(EntityUID::from_safe_str(&euid.to_string()).unwrap(), euid);
+        }
+        assert!(EntityUID::from_safe_str(r#"User :: "alice""#).is_err());
+    }
+
+    #[test]
+    fn suspicious_eids() {
+        assert_eq!(Eid::new("alice").first_suspicious_char(), None);
+        assert_eq!(Eid::new("caf\u{e9}").first_suspicious_char(), None);
+        assert_eq!(Eid::new("a\nb").first_suspicious_char(), Some((1, '\n')));
+        assert_eq!(
+            Eid::new("ab\u{202E}c").first_suspicious_char(),
+            Some((2, '\u{202E}'))
+        );
+        let err = Eid::new("x\u{2028}").check_not_suspicious().unwrap_err();
+        assert_eq!(err.offset(), 1);
+        assert_eq!(err.suspicious_char(), '\u{2028}');
+        assert_eq!(
+            err.to_string(),
+            r#"entity id "x\u{2028}" contains a suspicious character `\u{2028}` at byte offset 1"#
+        );
+    }
+
     #[test]
     fn action_type_is_valid_id() {
         assert!(Id::from_normalized_str(ACTION_ENTITY_TYPE).is_ok());
@@ -44,6 +44,60 @@ pub struct Expr<T = ()> {
     expr_kind: ExprKind<T>,
     source_loc: Option<Loc>,
     data: T,
+    /// The value of this expression, if it is a call of an extension
+    /// constructor on a string literal
+    #[serde(skip)]
+    preparsed: Preparsed,
+}
+
+/// The value of a call of an extension constructor on a string literal, e.g.,
+/// `ip("10.0.0.0/8")` or `decimal("1.50")`, parsed when the call is built, as
+/// policies are loaded, so that evaluation doesn't parse the string again for
+/// every request. It is a cache rather than part of the expression, so it is
+/// ignored by comparisons, hashing, and serialization.
+#[derive(Clone, Default)]
+struct Preparsed(Option<Value>);
+
+impl Preparsed {
+    /// Parse the call of the extension function `fn_name` on `args`, if it is
+    /// a constructor called on a string literal it accepts
+    fn new<T>(fn_name: &Name, args: &[Expr<T>]) -> Self {
+        let [arg] = args else {
+            return Self::default();
+        };
+        let ExprKind::Lit(Literal::String(s)) = arg.expr_kind() else {
+            return Self::default();
+        };
+        match Extensions::all_available().func(fn_name) {
+            Ok(func) if func.is_constructor() => match func.call(&[Value::from(s.clone())]) {
+                Ok(PartialValue::Value(v)) => Self(Some(v)),
+                // the call fails again when it is evaluated
+                _ => Self::default(),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+impl PartialEq for Preparsed {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Preparsed {}
+
+impl Hash for Preparsed {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl std::fmt::Debug for Preparsed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(v) => write!(f, "Preparsed({v})"),
+            None => write!(f, "Preparsed"),
+        }
+    }
 }
 
 /// The possible expression variants. This enum should be matched on by code
@@ -214,6 +268,7 @@ impl<T> Expr<T> {
             expr_kind,
             source_loc,
             data,
+            preparsed: Preparsed::default(),
         }
     }
 
@@ -250,6 +305,13 @@ impl<T> Expr<T> {
         Self { source_loc, ..self }
     }
 
+    /// The value of this expression parsed when it was built, if it is a call
+    /// of an extension constructor on a string literal, e.g.,
+    /// `ip("10.0.0.0/8")`
+    pub fn preparsed_value(&self) -> Option<&Value> {
+        self.preparsed.0.as_ref()
+    }
+
     /// Update the data for this `Expr`. A convenient function used by the
     /// Validator in one place.
     pub fn set_data(&mut self, data: T) {
@@ -373,7 +435,7 @@ impl<T> Expr<T> {
                 .func(fn_name)
                 .ok()?
                 .return_type()
-                .map(|rty| rty.clone().into()),
+                .and_then(|rty| rty.clone().try_into().ok()),
             // We could try to be more complete here, but we can't do all that
             // much better without evaluating the argument. Even if we know it's
             // a record `Type::Record` tells us nothing about the type of the
@@ -1130,10 +1192,15 @@ impl<T> ExprBuilder<T> {
         fn_name: Name,
         args: impl IntoIterator<Item = Expr<T>>,
     ) -> Expr<T> {
-        self.with_expr_kind(ExprKind::ExtensionFunctionApp {
-            fn_name,
-            args: Arc::new(args.into_iter().collect()),
-        })
+        let args: Vec<_> = args.into_iter().collect();
+        let preparsed = Preparsed::new(&fn_name, &args);
+        Expr {
+            preparsed,
+            ..self.with_expr_kind(ExprKind::ExtensionFunctionApp {
+                fn_name,
+                args: Arc::new(args),
+            })
+        }
     }
 
     /// Create an application `Expr` which applies the given built-in unary
@@ -1795,6 +1862,28 @@ mod test {
         assert_eq!(e.into_data(), "data");
     }
 
+    #[cfg(feature = "ipaddr")]
+    #[test]
+    fn preparsed_constants() {
+        let e: Expr = r#"ip("10.0.0.0/8")"#.parse().unwrap();
+        assert_matches!(e.preparsed_value(), Some(v) => {
+            assert_eq!(v.to_string(), "10.0.0.0/8");
+        });
+        // the cache is not part of the expression
+        let unparsed: Expr = serde_json::from_value(serde_json::to_value(&e).unwrap()).unwrap();
+        assert_eq!(unparsed.preparsed_value(), None);
+        assert_eq!(unparsed, e);
+
+        for src in [
+            r#"ip("not an ip")"#,
+            r#"ip(context.addr)"#,
+            r#"ip("10.0.0.1").isLoopback()"#,
+        ] {
+            let e: Expr = src.parse().unwrap();
+            assert_eq!(e.preparsed_value(), None, "{src}");
+        }
+    }
+
     #[test]
     fn expr_shape_only_eq() {
         let temp = ExprBuilder::with_data(1).val(1);
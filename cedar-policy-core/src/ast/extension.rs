@@ -128,6 +128,9 @@ pub struct ExtensionFunction {
     return_type: Option<SchemaType>,
     /// The argument types that this function expects, as `SchemaType`s.
     arg_types: Vec<SchemaType>,
+    /// The weight of a call of this function in an
+    /// [`crate::evaluator::ExtensionBudget`]
+    cost: u32,
 }
 
 impl ExtensionFunction {
@@ -145,9 +148,19 @@ impl ExtensionFunction {
             style,
             return_type,
             arg_types,
+            cost: 1,
         }
     }
 
+    /// Set the weight of a call of this function in an
+    /// [`crate::evaluator::ExtensionBudget`], by default 1. Functions which
+    /// are significantly more expensive than a comparison, e.g., because they
+    /// parse a string, should cost more.
+    pub fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+
     /// Create a new `ExtensionFunction` taking no arguments
     pub fn nullary(
         name: Name,
@@ -304,6 +317,12 @@ impl ExtensionFunction {
         &self.arg_types
     }
 
+    /// Get the weight of a call of the `ExtensionFunction` in an
+    /// [`crate::evaluator::ExtensionBudget`]
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
     /// Returns `true` if this function is considered a "constructor".
     ///
     /// Currently, the only impact of this is that non-constructors are not
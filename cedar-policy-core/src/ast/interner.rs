@@ -0,0 +1,179 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`ValueInterner`], which hash-conses set and record
+//! [`Value`]s.
+
+use super::{Value, ValueKind};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Hash-consing of set and record [`Value`]s: interning equal values returns
+/// values sharing one allocation, so that comparing them afterwards is a
+/// pointer comparison. Elements of sets and attributes of records are
+/// interned as well, so that, e.g., looking up an interned record in a set of
+/// interned records compares pointers for the matching element.
+///
+/// Interning a value which has been interned before, or which shares its
+/// allocation with one, only costs a lookup by address. Otherwise, it costs
+/// hashing the value and comparing it to the interned values with the same
+/// hash, so interning only pays off for values which are compared repeatedly.
+#[derive(Debug, Default)]
+pub struct ValueInterner {
+    /// The interned values, by fingerprint
+    canonical: HashMap<u64, Vec<Value>>,
+    /// The interned value equal to each value interned so far, by address of
+    /// its allocation
+    interned: HashMap<usize, Value>,
+    /// The values interned so far which are not themselves interned values,
+    /// kept alive so that their addresses are not reused
+    aliases: Vec<Value>,
+}
+
+impl ValueInterner {
+    /// An interner which hasn't interned any values yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The interned value equal to `v`, with the source location of `v`.
+    /// Values other than sets and records are returned unchanged.
+    pub fn intern(&mut self, v: Value) -> Value {
+        let Some(v_address) = address(&v) else {
+            return v;
+        };
+        if let Some(interned) = self.interned.get(&v_address) {
+            return interned.clone().with_maybe_source_loc(v.loc);
+        }
+        let loc = v.loc.clone();
+        let candidate = match v.value_kind() {
+            // elements of sets of literals need no interning
+            ValueKind::Set(set) if set.fast.is_some() => v.clone(),
+            ValueKind::Set(set) => {
+                let elems: Vec<Value> = set
+                    .authoritative
+                    .iter()
+                    .map(|e| self.intern(e.clone()))
+                    .collect();
+                Value::set(elems, loc.clone())
+            }
+            ValueKind::Record(attrs) => {
+                let attrs = attrs
+                    .iter()
+                    .map(|(k, e)| (k.clone(), self.intern(e.clone())))
+                    .collect();
+                Value::record_arc(Arc::new(attrs), loc.clone())
+            }
+            ValueKind::Lit(_) | ValueKind::ExtensionValue(_) => return v,
+        };
+        let mut hasher = DefaultHasher::new();
+        fingerprint(&candidate, &mut hasher);
+        let bucket = self.canonical.entry(hasher.finish()).or_default();
+        let interned = match bucket.iter().find(|interned| **interned == candidate) {
+            Some(interned) => interned.clone(),
+            None => {
+                bucket.push(candidate.clone());
+                if let Some(candidate_address) = address(&candidate) {
+                    self.interned.insert(candidate_address, candidate.clone());
+                }
+                candidate
+            }
+        };
+        if !interned.value_kind().shares_allocation(v.value_kind()) {
+            self.interned.insert(v_address, interned.clone());
+            self.aliases.push(v);
+        }
+        interned.with_maybe_source_loc(loc)
+    }
+}
+
+/// Address of the allocation of a set or record
+fn address(v: &Value) -> Option<usize> {
+    match v.value_kind() {
+        ValueKind::Set(set) => Some(Arc::as_ptr(&set.authoritative) as usize),
+        ValueKind::Record(attrs) => Some(Arc::as_ptr(attrs) as usize),
+        ValueKind::Lit(_) | ValueKind::ExtensionValue(_) => None,
+    }
+}
+
+/// Hash `v`, ignoring source locations. Equal values have equal fingerprints.
+fn fingerprint(v: &Value, hasher: &mut DefaultHasher) {
+    match v.value_kind() {
+        ValueKind::Lit(lit) => {
+            0u8.hash(hasher);
+            lit.hash(hasher);
+        }
+        ValueKind::Set(set) => {
+            1u8.hash(hasher);
+            set.authoritative.len().hash(hasher);
+            // `authoritative` is ordered, so this doesn't depend on the order
+            // in which the elements were added
+            for e in set.authoritative.iter() {
+                fingerprint(e, hasher);
+            }
+        }
+        ValueKind::Record(attrs) => {
+            2u8.hash(hasher);
+            attrs.len().hash(hasher);
+            for (k, e) in attrs.iter() {
+                k.hash(hasher);
+                fingerprint(e, hasher);
+            }
+        }
+        // extension values are only compared within the bucket
+        ValueKind::ExtensionValue(_) => 3u8.hash(hasher),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::EntityUID;
+
+    fn group(name: &str) -> Value {
+        Value::from(EntityUID::with_eid(name))
+    }
+
+    #[test]
+    fn interned_values_share_allocations() {
+        let mut interner = ValueInterner::new();
+        let groups = || Value::set([group("a"), group("b")], None);
+        let profile = || Value::record([("groups", groups()), ("level", Value::from(3))], None);
+        let (v1, v2) = (profile(), profile());
+        assert!(!v1.value_kind().shares_allocation(v2.value_kind()));
+
+        let (v1, v2) = (interner.intern(v1), interner.intern(v2));
+        assert!(v1.value_kind().shares_allocation(v2.value_kind()));
+        assert_eq!(v1, profile());
+
+        // elements are interned too
+        let set = interner.intern(Value::set([profile(), Value::from(1)], None));
+        let ValueKind::Set(set) = set.value_kind() else {
+            panic!("expected a set")
+        };
+        assert!(set
+            .authoritative
+            .iter()
+            .any(|e| e.value_kind().shares_allocation(v1.value_kind())));
+
+        // interning again only looks up the address
+        let v3 = interner.intern(v1.clone());
+        assert!(v3.value_kind().shares_allocation(v1.value_kind()));
+        assert_eq!(interner.intern(Value::from(1)), Value::from(1));
+    }
+}
@@ -43,6 +43,9 @@ pub enum Literal {
     /// Entity, represented by its UID. To get the actual `Entity`, you have to
     /// look up this UID in a Store or Slice.
     EntityUID(Arc<EntityUID>),
+    /// The `null` value, representing an attribute which is present but has
+    /// no value
+    Null,
 }
 
 impl StaticallyTyped for Literal {
@@ -52,6 +55,7 @@ impl StaticallyTyped for Literal {
             Self::Long(_) => Type::Long,
             Self::String(_) => Type::String,
             Self::EntityUID(uid) => uid.type_of(),
+            Self::Null => Type::Null,
         }
     }
 }
@@ -66,6 +70,7 @@ impl std::fmt::Display for Literal {
             // e.g., a single quote is printed as `\'`.
             Self::String(s) => write!(f, "\"{}\"", s.escape_debug()),
             Self::EntityUID(uid) => write!(f, "{}", uid),
+            Self::Null => write!(f, "null"),
         }
     }
 }
@@ -133,4 +138,9 @@ impl Literal {
     pub fn is_ref(&self) -> bool {
         matches!(self, Self::EntityUID(..))
     }
+
+    /// Check if this literal is `null`
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
 }
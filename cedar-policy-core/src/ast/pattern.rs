@@ -136,6 +136,48 @@ impl Pattern {
 
         j == pattern_len
     }
+
+    /// Whether the pattern contains a wildcard. A pattern without wildcards
+    /// only matches the string made of its characters.
+    pub fn has_wildcard(&self) -> bool {
+        self.iter().any(PatternElem::is_wildcard)
+    }
+
+    /// Decide whether some string matches both this pattern and `other`.
+    ///
+    /// This explores the product of the two patterns: a state is a pair of
+    /// positions in the patterns, a wildcard may be skipped or consume a
+    /// character, and both patterns must consume the same characters. The
+    /// patterns overlap iff the state where both are exhausted is reachable.
+    pub fn overlaps(&self, other: &Pattern) -> bool {
+        let (lhs, rhs) = (self.get_elems(), other.get_elems());
+        let mut visited = std::collections::HashSet::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((i, j)) = stack.pop() {
+            if !visited.insert((i, j)) {
+                continue;
+            }
+            let (l, r) = (lhs.get(i), rhs.get(j));
+            if l.is_none() && r.is_none() {
+                return true;
+            }
+            if l.is_some_and(PatternElem::is_wildcard) {
+                stack.push((i + 1, j));
+            }
+            if r.is_some_and(PatternElem::is_wildcard) {
+                stack.push((i, j + 1));
+            }
+            match (l, r) {
+                (Some(PatternElem::Char(a)), Some(PatternElem::Char(b))) if a == b => {
+                    stack.push((i + 1, j + 1))
+                }
+                (Some(PatternElem::Char(_)), Some(PatternElem::Wildcard)) => stack.push((i + 1, j)),
+                (Some(PatternElem::Wildcard), Some(PatternElem::Char(_))) => stack.push((i, j + 1)),
+                _ => {}
+            }
+        }
+        false
+    }
 }
 
 #[cfg(test)]
@@ -215,4 +257,48 @@ pub mod test {
         // Patterns that do not match "ḛ̶͑͝x̶͔͛a̵̰̯͛m̴͉̋́p̷̠͂l̵͇̍̔ȩ̶̣͝"
         assert!(!(string_map("y") + star()).wildcard_match("ḛ̶͑͝x̶͔͛a̵̰̯͛m̴͉̋́p̷̠͂l̵͇̍̔ȩ̶̣͝"));
     }
+
+    #[test]
+    fn test_has_wildcard() {
+        assert!(!empty().has_wildcard());
+        assert!(!string_map("foo*").has_wildcard());
+        assert!((string_map("foo") + star()).has_wildcard());
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let overlaps = |a: &Pattern, b: &Pattern| {
+            let ans = a.overlaps(b);
+            assert_eq!(ans, b.overlaps(a), "overlap should be symmetric");
+            ans
+        };
+        assert!(overlaps(&empty(), &empty()));
+        assert!(overlaps(&empty(), &star()));
+        assert!(!overlaps(&empty(), &string_map("a")));
+        assert!(overlaps(&string_map("abc"), &string_map("abc")));
+        assert!(!overlaps(&string_map("abc"), &string_map("abd")));
+        assert!(overlaps(
+            &(string_map("a") + star()),
+            &(star() + string_map("b"))
+        ));
+        assert!(overlaps(
+            &(string_map("foo") + star()),
+            &(star() + string_map("o") + star())
+        ));
+        assert!(!overlaps(
+            &(string_map("foo") + star()),
+            &(string_map("bar") + star())
+        ));
+        assert!(!overlaps(
+            &(star() + string_map(".jpg")),
+            &(star() + string_map(".png"))
+        ));
+        assert!(overlaps(
+            &(string_map("a") + star() + string_map("c")),
+            &(star() + string_map("b") + star())
+        ));
+        // the literal character `*` is not a wildcard
+        assert!(!overlaps(&string_map("*"), &string_map("a")));
+        assert!(overlaps(&string_map("*"), &star()));
+    }
 }
@@ -141,6 +141,18 @@ impl Template {
         self.body.non_scope_constraints()
     }
 
+    /// Get the conditions of the body with their kinds, if known
+    pub fn conditions(&self) -> Option<Vec<(ConditionKind, &Expr)>> {
+        self.body.conditions()
+    }
+
+    /// Record that the non-scope constraints of this template are the
+    /// conjunction of conditions of the given `kinds`, in order
+    pub fn with_condition_kinds(mut self, kinds: Vec<ConditionKind>) -> Self {
+        self.body.condition_kinds = Arc::new(kinds);
+        self
+    }
+
     /// Get Arc to non-scope constraint on the body
     pub fn non_scope_constraints_arc(&self) -> &Arc<Expr> {
         self.body.non_scope_constraints_arc()
@@ -424,6 +436,48 @@ impl Policy {
         Self::new(Arc::new(t), None, SlotEnv::new())
     }
 
+    /// Construct a policy like this one, but with `f` applied to the
+    /// non-scope constraints of its template. The id, annotations, scope, and
+    /// links are unchanged. `f` must not introduce slots.
+    pub fn map_non_scope_constraints(&self, f: impl FnOnce(&Expr) -> Expr) -> Self {
+        let t = &self.template;
+        let template = Template::new_shared(
+            t.id().clone(),
+            t.loc().cloned(),
+            Arc::clone(t.annotations_arc()),
+            t.effect(),
+            t.principal_constraint().clone(),
+            t.action_constraint().clone(),
+            t.resource_constraint().clone(),
+            Arc::new(f(t.non_scope_constraints())),
+        );
+        // INVARIANT (values total map): `f` does not introduce slots, so the
+        // new template has the same slots as the old one
+        Self::new(Arc::new(template), self.link.clone(), self.values.clone())
+    }
+
+    /// Construct a policy like this one, but with `f` applied to the action
+    /// constraint of its template. Everything else is unchanged.
+    pub fn map_action_constraint(
+        &self,
+        f: impl FnOnce(&ActionConstraint) -> ActionConstraint,
+    ) -> Self {
+        let t = &self.template;
+        let template = Template::new_shared(
+            t.id().clone(),
+            t.loc().cloned(),
+            Arc::clone(t.annotations_arc()),
+            t.effect(),
+            t.principal_constraint().clone(),
+            f(t.action_constraint()),
+            t.resource_constraint().clone(),
+            Arc::clone(t.non_scope_constraints_arc()),
+        );
+        // INVARIANT (values total map): action constraints never contain
+        // slots, so the new template has the same slots as the old one
+        Self::new(Arc::new(template), self.link.clone(), self.values.clone())
+    }
+
     /// Get pointer to the template for this policy
     pub fn template(&self) -> &Template {
         &self.template
@@ -917,6 +971,20 @@ pub struct TemplateBody {
     /// This will be a conjunction of the policy's `when` conditions and the
     /// negation of each of the policy's `unless` conditions.
     non_scope_constraints: Arc<Expr>,
+    /// Kinds of the conditions conjoined in `non_scope_constraints`, in
+    /// order. Empty if the conditions are unknown, e.g., for policies
+    /// constructed directly from an expression.
+    #[serde(default)]
+    condition_kinds: Arc<Vec<ConditionKind>>,
+}
+
+/// Whether a policy condition is a `when` or an `unless` clause
+#[derive(Serialize, Deserialize, Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub enum ConditionKind {
+    /// A `when` clause
+    When,
+    /// An `unless` clause, which appears negated in the non-scope constraints
+    Unless,
 }
 
 impl TemplateBody {
@@ -1001,6 +1069,28 @@ impl TemplateBody {
         &self.non_scope_constraints
     }
 
+    /// Get the conditions of this policy with their kinds, in order. `unless`
+    /// conditions are negated, as in the non-scope constraints. Returns `None`
+    /// if the conditions are unknown.
+    pub fn conditions(&self) -> Option<Vec<(ConditionKind, &Expr)>> {
+        let (first_kind, kinds) = self.condition_kinds.split_first()?;
+        // The non-scope constraints are a left fold of the conditions with `&&`
+        let mut conds = Vec::with_capacity(self.condition_kinds.len());
+        let mut expr = self.non_scope_constraints.as_ref();
+        for kind in kinds.iter().rev() {
+            match expr.expr_kind() {
+                ExprKind::And { left, right } => {
+                    conds.push((*kind, right.as_ref()));
+                    expr = left;
+                }
+                _ => return None,
+            }
+        }
+        conds.push((*first_kind, expr));
+        conds.reverse();
+        Some(conds)
+    }
+
     /// Get the Arc owning the non scope constraints
     pub fn non_scope_constraints_arc(&self) -> &Arc<Expr> {
         &self.non_scope_constraints
@@ -1048,6 +1138,7 @@ impl TemplateBody {
             action_constraint,
             resource_constraint,
             non_scope_constraints,
+            condition_kinds: Arc::default(),
         }
     }
 
@@ -1072,6 +1163,7 @@ impl TemplateBody {
             action_constraint,
             resource_constraint,
             non_scope_constraints: Arc::new(non_scope_constraints),
+            condition_kinds: Arc::default(),
         }
     }
 }
@@ -1587,16 +1679,24 @@ impl PrincipalOrResourceConstraint {
         }
     }
 
+    /// Get the entity type of the `is` in this constraint or `None` if there is
+    /// no `is` in the constraint
+    pub fn get_entity_type(&self) -> Option<&EntityType> {
+        match self {
+            PrincipalOrResourceConstraint::Is(entity_type)
+            | PrincipalOrResourceConstraint::IsIn(entity_type, _) => Some(entity_type.as_ref()),
+            PrincipalOrResourceConstraint::Any
+            | PrincipalOrResourceConstraint::In(_)
+            | PrincipalOrResourceConstraint::Eq(_) => None,
+        }
+    }
+
     /// Get an iterator over all of the entity type names in this constraint.
     pub fn iter_entity_type_names(&self) -> impl Iterator<Item = &'_ EntityType> {
         self.get_euid()
             .into_iter()
             .map(|euid| euid.entity_type())
-            .chain(match self {
-                PrincipalOrResourceConstraint::Is(entity_type)
-                | PrincipalOrResourceConstraint::IsIn(entity_type, _) => Some(entity_type.as_ref()),
-                _ => None,
-            })
+            .chain(self.get_entity_type())
     }
 }
 
@@ -26,6 +26,10 @@ use std::{borrow::Borrow, sync::Arc};
 use thiserror::Error;
 
 /// Represents a set of `Policy`s
+///
+/// The maps below are shared between clones, and copied on the first
+/// modification of a clone, so cloning a `PolicySet` is cheap. Copying a map
+/// does not copy templates, which are behind an `Arc`.
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(try_from = "LiteralPolicySet")]
 #[serde(into = "LiteralPolicySet")]
@@ -35,18 +39,18 @@ pub struct PolicySet {
     /// - A Body of a `Template`, which has slots that need to be filled in
     /// - A Body of a `StaticPolicy`, which has been converted into a `Template` that has zero slots.
     ///   The static policy's [`PolicyID`] is the same in both `templates` and `links`.
-    templates: HashMap<PolicyID, Arc<Template>>,
+    templates: Arc<HashMap<PolicyID, Arc<Template>>>,
     /// `links` contains all of the executable policies in the `PolicySet`
     /// A `StaticPolicy` must have exactly one `Policy` in `links`
     ///   (this is managed by `PolicySet::add`)
     ///   The static policy's PolicyID is the same in both `templates` and `links`
     /// A `Template` may have zero or many links
-    links: HashMap<PolicyID, Policy>,
+    links: Arc<HashMap<PolicyID, Policy>>,
 
     /// Map from a template `PolicyID` to the set of `PolicyID`s in `links` that are linked to that template.
     /// There is a key `t` iff `templates` contains the key `t`. The value of `t` will be a (possibly empty)
     /// set of every `p` in `links` s.t. `p.template().id() == t`.
-    template_to_links_map: HashMap<PolicyID, HashSet<PolicyID>>,
+    template_to_links_map: Arc<HashMap<PolicyID, HashSet<PolicyID>>>,
 }
 
 /// Converts a LiteralPolicySet into a PolicySet, ensuring the invariants are met
@@ -79,9 +83,9 @@ impl TryFrom<LiteralPolicySet> for PolicySet {
         }
 
         Ok(Self {
-            templates,
-            links,
-            template_to_links_map,
+            templates: Arc::new(templates),
+            links: Arc::new(links),
+            template_to_links_map: Arc::new(template_to_links_map),
         })
     }
 }
@@ -95,13 +99,11 @@ struct LiteralPolicySet {
 
 impl From<PolicySet> for LiteralPolicySet {
     fn from(pset: PolicySet) -> Self {
-        let templates = pset
-            .templates
+        let templates = Arc::unwrap_or_clone(pset.templates)
             .into_iter()
-            .map(|(id, template)| (id, template.as_ref().clone()))
+            .map(|(id, template)| (id, Arc::unwrap_or_clone(template)))
             .collect();
-        let links = pset
-            .links
+        let links = Arc::unwrap_or_clone(pset.links)
             .into_iter()
             .map(|(id, p)| (id, p.into()))
             .collect();
@@ -175,9 +177,9 @@ impl PolicySet {
     /// Create a fresh empty `PolicySet`
     pub fn new() -> Self {
         Self {
-            templates: HashMap::new(),
-            links: HashMap::new(),
-            template_to_links_map: HashMap::new(),
+            templates: Arc::default(),
+            links: Arc::default(),
+            template_to_links_map: Arc::default(),
         }
     }
 
@@ -189,7 +191,7 @@ impl PolicySet {
         // modifications to `self`.
         // So we just collect the `ventry` here, and we only do the insertion
         // once we know there will be no error
-        let template_ventry = match self.templates.entry(t.id().clone()) {
+        let template_ventry = match Arc::make_mut(&mut self.templates).entry(t.id().clone()) {
             Entry::Vacant(ventry) => Some(ventry),
             Entry::Occupied(oentry) => {
                 if oentry.get() != &t {
@@ -201,7 +203,7 @@ impl PolicySet {
             }
         };
 
-        let link_ventry = match self.links.entry(policy.id().clone()) {
+        let link_ventry = match Arc::make_mut(&mut self.links).entry(policy.id().clone()) {
             Entry::Vacant(ventry) => Some(ventry),
             Entry::Occupied(oentry) => {
                 return Err(PolicySetError::Occupied {
@@ -213,7 +215,7 @@ impl PolicySet {
         // if we get here, there will be no errors.  So actually do the
         // insertions.
         if let Some(ventry) = template_ventry {
-            self.template_to_links_map.insert(
+            Arc::make_mut(&mut self.template_to_links_map).insert(
                 t.id().clone(),
                 vec![policy.id().clone()]
                     .into_iter()
@@ -222,7 +224,7 @@ impl PolicySet {
             ventry.insert(t);
         } else {
             //`template_ventry` is None, so `templates` has `t` and we never use the `HashSet::new()`
-            self.template_to_links_map
+            Arc::make_mut(&mut self.template_to_links_map)
                 .entry(t.id().clone())
                 .or_default()
                 .insert(policy.id().clone());
@@ -241,7 +243,7 @@ impl PolicySet {
     ) -> Result<Policy, PolicySetPolicyRemovalError> {
         // Invariant: if `policy_id` is a key in both `self.links` and `self.templates`,
         // then self.templates[policy_id] has exactly one link: self.links[policy_id]
-        let policy = match self.links.remove(policy_id) {
+        let policy = match Arc::make_mut(&mut self.links).remove(policy_id) {
             Some(p) => p,
             None => {
                 return Err(PolicySetPolicyRemovalError::RemovePolicyNoLinkError(
@@ -250,15 +252,15 @@ impl PolicySet {
             }
         };
         //links mapped by `PolicyId`, so `policy` is unique
-        match self.templates.remove(policy_id) {
+        match Arc::make_mut(&mut self.templates).remove(policy_id) {
             Some(_) => {
-                self.template_to_links_map.remove(policy_id);
+                Arc::make_mut(&mut self.template_to_links_map).remove(policy_id);
                 Ok(policy)
             }
             None => {
                 //If we removed the link but failed to remove the template
                 //restore the link and return an error
-                self.links.insert(policy_id.clone(), policy);
+                Arc::make_mut(&mut self.links).insert(policy_id.clone(), policy);
                 Err(PolicySetPolicyRemovalError::RemovePolicyNoTemplateError(
                     policy_id.clone(),
                 ))
@@ -271,11 +273,11 @@ impl PolicySet {
         let (t, p) = Template::link_static_policy(policy);
 
         match (
-            self.templates.entry(t.id().clone()),
-            self.links.entry(t.id().clone()),
+            Arc::make_mut(&mut self.templates).entry(t.id().clone()),
+            Arc::make_mut(&mut self.links).entry(t.id().clone()),
         ) {
             (Entry::Vacant(templates_entry), Entry::Vacant(links_entry)) => {
-                self.template_to_links_map.insert(
+                Arc::make_mut(&mut self.template_to_links_map).insert(
                     t.id().clone(),
                     vec![p.id().clone()]
                         .into_iter()
@@ -301,12 +303,12 @@ impl PolicySet {
             return Err(PolicySetError::Occupied { id: t.id().clone() });
         }
 
-        match self.templates.entry(t.id().clone()) {
+        match Arc::make_mut(&mut self.templates).entry(t.id().clone()) {
             Entry::Occupied(oentry) => Err(PolicySetError::Occupied {
                 id: oentry.key().clone(),
             }),
             Entry::Vacant(ventry) => {
-                self.template_to_links_map
+                Arc::make_mut(&mut self.template_to_links_map)
                     .insert(t.id().clone(), HashSet::new());
                 ventry.insert(Arc::new(t));
                 Ok(())
@@ -345,9 +347,9 @@ impl PolicySet {
 
         // PANIC SAFETY: every linked policy should have a template
         #[allow(clippy::panic)]
-        match self.templates.remove(policy_id) {
+        match Arc::make_mut(&mut self.templates).remove(policy_id) {
             Some(t) => {
-                self.template_to_links_map.remove(policy_id);
+                Arc::make_mut(&mut self.template_to_links_map).remove(policy_id);
                 Ok((*t).clone())
             }
             None => panic!("Found in template_to_links_map but not in templates"),
@@ -388,12 +390,12 @@ impl PolicySet {
 
         // Both maps must not contain the `new_id`
         match (
-            self.links.entry(new_id.clone()),
-            self.templates.entry(new_id.clone()),
+            Arc::make_mut(&mut self.links).entry(new_id.clone()),
+            Arc::make_mut(&mut self.templates).entry(new_id.clone()),
         ) {
             (Entry::Vacant(links_entry), Entry::Vacant(_)) => {
                 //We will never use the .or_default() because we just found `t` above
-                self.template_to_links_map
+                Arc::make_mut(&mut self.template_to_links_map)
                     .entry(template_id)
                     .or_default()
                     .insert(new_id);
@@ -415,11 +417,13 @@ impl PolicySet {
         if self.templates.contains_key(policy_id) {
             return Err(PolicySetUnlinkError::NotLinkError(policy_id.clone()));
         }
-        match self.links.remove(policy_id) {
+        match Arc::make_mut(&mut self.links).remove(policy_id) {
             Some(p) => {
                 // PANIC SAFETY: every linked policy should have a template
                 #[allow(clippy::panic)]
-                match self.template_to_links_map.entry(p.template().id().clone()) {
+                match Arc::make_mut(&mut self.template_to_links_map)
+                    .entry(p.template().id().clone())
+                {
                     Entry::Occupied(t) => t.into_mut().remove(policy_id),
                     Entry::Vacant(_) => {
                         panic!("No template found for linked policy")
@@ -748,6 +752,43 @@ mod test {
         assert_eq!(v[0].template().id(), &tid);
     }
 
+    #[test]
+    fn clone_on_write() {
+        let mut pset = PolicySet::new();
+        let p1 = parser::parse_policy(
+            Some(PolicyID::from_string("p1")),
+            "permit(principal,action,resource);",
+        )
+        .expect("Failed to parse");
+        pset.add_static(p1).expect("Failed to add!");
+
+        let mut clone = pset.clone();
+        assert!(Arc::ptr_eq(&pset.links, &clone.links));
+        let p2 = parser::parse_policy(
+            Some(PolicyID::from_string("p2")),
+            "forbid(principal,action,resource);",
+        )
+        .expect("Failed to parse");
+        clone.add_static(p2).expect("Failed to add!");
+        assert!(!Arc::ptr_eq(&pset.links, &clone.links));
+        assert_eq!(pset.policies().count(), 1);
+        assert_eq!(clone.policies().count(), 2);
+        // the template of `p1` is still shared
+        assert!(Arc::ptr_eq(
+            &pset
+                .get_template_arc(&PolicyID::from_string("p1"))
+                .expect("should find the template"),
+            &clone
+                .get_template_arc(&PolicyID::from_string("p1"))
+                .expect("should find the template"),
+        ));
+
+        clone
+            .remove_static(&PolicyID::from_string("p1"))
+            .expect("Failed to remove");
+        assert!(pset.get(&PolicyID::from_string("p1")).is_some());
+    }
+
     #[test]
     fn linking_empty_set() {
         let s = PolicySet::new();
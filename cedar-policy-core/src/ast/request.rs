@@ -249,6 +249,12 @@ pub enum Context {
 }
 
 impl Context {
+    /// The well-known context attribute holding the time of the request, as
+    /// a number of seconds since the Unix epoch. Policies must check that it
+    /// is present (`context has now`) before using it, since only requests
+    /// whose application provides the time have it.
+    pub const CURRENT_TIME_ATTR: &'static str = "now";
+
     /// Create an empty `Context`
     pub fn empty() -> Self {
         Self::Value(Arc::new(BTreeMap::new()))
@@ -181,6 +181,11 @@ impl RestrictedExpr {
         }
     }
 
+    /// Is this `RestrictedExpr` the `null` literal
+    pub fn is_null(&self) -> bool {
+        matches!(self.expr_kind(), ExprKind::Lit(Literal::Null))
+    }
+
     /// Get the `EntityUID` value of this `RestrictedExpr` if it's an entity
     /// reference, or `None` if it is not an entity reference
     pub fn as_euid(&self) -> Option<&EntityUID> {
@@ -391,6 +396,11 @@ impl<'a> BorrowedRestrictedExpr<'a> {
         }
     }
 
+    /// Is this `RestrictedExpr` the `null` literal
+    pub fn is_null(&self) -> bool {
+        matches!(self.expr_kind(), ExprKind::Lit(Literal::Null))
+    }
+
     /// Get the `EntityUID` value of this `RestrictedExpr` if it's an entity
     /// reference, or `None` if it is not an entity reference
     pub fn as_euid(&self) -> Option<&EntityUID> {
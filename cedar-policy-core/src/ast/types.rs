@@ -62,6 +62,8 @@ pub enum Type {
         /// confusion.
         name: Name,
     },
+    /// Type of the `null` value
+    Null,
 }
 
 impl Type {
@@ -81,6 +83,7 @@ impl std::fmt::Display for Type {
             Self::Record => write!(f, "record"),
             Self::Entity { ty } => write!(f, "(entity of type `{ty}`)"),
             Self::Extension { name } => write!(f, "{}", name),
+            Self::Null => write!(f, "null"),
         }
     }
 }
@@ -56,6 +56,9 @@ pub enum ValueKind {
 // Custom impl of `Ord`, ignoring the `Loc`s
 impl Ord for Value {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self.value.shares_allocation(&other.value) {
+            return std::cmp::Ordering::Equal;
+        }
         self.value.cmp(&other.value)
     }
 }
@@ -401,6 +404,9 @@ impl FromIterator<Literal> for Set {
 // implementation manually.
 impl PartialEq for ValueKind {
     fn eq(&self, other: &Self) -> bool {
+        if self.shares_allocation(other) {
+            return true;
+        }
         match (self, other) {
             (ValueKind::Lit(lit1), ValueKind::Lit(lit2)) => lit1 == lit2,
             (ValueKind::Set(set1), ValueKind::Set(set2)) => set1 == set2,
@@ -413,6 +419,21 @@ impl PartialEq for ValueKind {
 
 impl Eq for ValueKind {}
 
+impl ValueKind {
+    /// True if `self` and `other` are sets or records sharing one allocation,
+    /// and hence equal. This makes comparing interned values (see
+    /// [`super::ValueInterner`]) a pointer comparison.
+    pub(crate) fn shares_allocation(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ValueKind::Set(set1), ValueKind::Set(set2)) => {
+                Arc::ptr_eq(&set1.authoritative, &set2.authoritative)
+            }
+            (ValueKind::Record(r1), ValueKind::Record(r2)) => Arc::ptr_eq(r1, r2),
+            (_, _) => false,
+        }
+    }
+}
+
 // The implementation of `PartialEq` for `Value` ignores the `Loc` of the values.
 impl PartialEq for Value {
     fn eq(&self, other: &Value) -> bool {
@@ -22,7 +22,10 @@
 
 use crate::ast::*;
 use crate::entities::Entities;
-use crate::evaluator::Evaluator;
+use crate::evaluator::{
+    CompiledEntitySets, DependencyTracer, EvaluationError, Evaluator, ExtensionBudget,
+    OverflowMode, SharedSubexpressions, UnknownFunctionMode,
+};
 use crate::extensions::Extensions;
 use itertools::{Either, Itertools};
 use serde::{Deserialize, Serialize};
@@ -33,6 +36,7 @@ use std::sync::Arc;
 extern crate tsify;
 
 mod err;
+mod fast_path;
 mod partial_response;
 pub use err::{AuthorizationError, ConcretizationError, ReauthorizationError};
 
@@ -40,11 +44,30 @@ pub use partial_response::ErrorState;
 pub use partial_response::PartialResponse;
 
 /// Authorizer
+#[derive(Clone)]
 pub struct Authorizer {
     /// Cedar `Extension`s which will be used during requests to this `Authorizer`
     extensions: &'static Extensions<'static>,
     /// Error-handling behavior of this `Authorizer`
     error_handling: ErrorHandling,
+    /// What happens when Long arithmetic overflows
+    overflow_mode: OverflowMode,
+    /// Whether to evaluate subexpressions shared by several policies only once
+    share_subexpressions: bool,
+    /// Whether to intern the sets and records produced during evaluation
+    intern_values: bool,
+    /// Set literals of entity uids compiled to bitsets
+    entity_sets: Option<Arc<CompiledEntitySets>>,
+    /// Whether to decide policies with trivial conditions without the evaluator
+    fast_paths: bool,
+    /// Whether to stop evaluating policies once a forbid is satisfied
+    stop_at_first_forbid: bool,
+    /// What happens when a policy calls an extension function which doesn't
+    /// exist
+    unknown_functions: UnknownFunctionMode,
+    /// The cost budget of the extension function calls of each request, if
+    /// any
+    extension_budget: Option<u64>,
 }
 
 /// Describes the possible Cedar error-handling modes.
@@ -70,9 +93,89 @@ impl Authorizer {
         Self {
             extensions: Extensions::all_available(), // set at compile time
             error_handling: Default::default(),
+            overflow_mode: OverflowMode::default(),
+            share_subexpressions: false,
+            intern_values: false,
+            entity_sets: None,
+            fast_paths: true,
+            stop_at_first_forbid: false,
+            unknown_functions: UnknownFunctionMode::default(),
+            extension_budget: None,
         }
     }
 
+    /// Set what happens when Long arithmetic overflows while evaluating
+    /// policies, by default [`OverflowMode::Error`]
+    pub fn with_overflow_mode(mut self, overflow_mode: OverflowMode) -> Self {
+        self.overflow_mode = overflow_mode;
+        self
+    }
+
+    /// Evaluate subexpressions which occur in the conditions of several
+    /// policies only once per request, e.g., guards shared by generated
+    /// policies. Finding the shared subexpressions takes time for each
+    /// request, so this only pays off if they are expensive to evaluate.
+    pub fn with_shared_subexpressions(mut self, share: bool) -> Self {
+        self.share_subexpressions = share;
+        self
+    }
+
+    /// Intern the sets and records produced while evaluating a request, so
+    /// that repeated comparisons of equal sets and records, e.g., large group
+    /// sets compared by many policies, are pointer comparisons. Interning
+    /// hashes each distinct set or record once per request, so this only pays
+    /// off if they are compared repeatedly.
+    pub fn with_interned_values(mut self, intern: bool) -> Self {
+        self.intern_values = intern;
+        self
+    }
+
+    /// Test membership in the set literals compiled in `entity_sets` with
+    /// their bitsets, e.g., for policies with large allowlists. Set literals
+    /// of other policy sets are evaluated as usual.
+    pub fn with_compiled_entity_sets(mut self, entity_sets: Arc<CompiledEntitySets>) -> Self {
+        self.entity_sets = Some(entity_sets);
+        self
+    }
+
+    /// Decide the policies whose conditions are trivially `true` or `false`,
+    /// e.g., role-based policies with only a scope, by matching their scope
+    /// against the request without the evaluator. Enabled by default. If all
+    /// policies are decided this way, no evaluator is built for the request.
+    pub fn with_fast_paths(mut self, enabled: bool) -> Self {
+        self.fast_paths = enabled;
+        self
+    }
+
+    /// Evaluate the forbid policies first, and stop evaluating policies once
+    /// one of them is satisfied, since the decision is then `Deny` whatever
+    /// the other policies evaluate to. The response then only has the first
+    /// satisfied forbid as its reason, and only the errors of the policies
+    /// evaluated before it.
+    pub fn with_stop_at_first_forbid(mut self, stop: bool) -> Self {
+        self.stop_at_first_forbid = stop;
+        self
+    }
+
+    /// Set what happens when a policy calls an extension function which
+    /// doesn't exist, by default [`UnknownFunctionMode::Error`]. Unless they
+    /// are errors, the calls are reported in the `unknown_function_calls` of
+    /// responses.
+    pub fn with_unknown_function_mode(mut self, mode: UnknownFunctionMode) -> Self {
+        self.unknown_functions = mode;
+        self
+    }
+
+    /// Bound the total cost of the extension function calls evaluated for
+    /// each request to `budget`, see [`ExtensionBudget`]. Once it is spent,
+    /// the policies calling an extension function fail with an
+    /// [`EvaluationError::LimitExceeded`] error, so that they are skipped.
+    /// Unbounded by default.
+    pub fn with_extension_budget(mut self, budget: u64) -> Self {
+        self.extension_budget = Some(budget);
+        self
+    }
+
     /// Returns an authorization response for `q` with respect to the given `Slice`.
     ///
     /// The language spec and formal model give a precise definition of how this is
@@ -84,13 +187,40 @@ impl Authorizer {
     /// Returns an authorization response for `q` with respect to the given `Slice`.
     /// Partial Evaluation of is_authorized
     ///
+    /// Residuals are not simplified: see [`PartialResponse::simplified`].
     pub fn is_authorized_core(
         &self,
         q: Request,
         pset: &PolicySet,
         entities: &Entities,
     ) -> PartialResponse {
-        let eval = Evaluator::new(q.clone(), entities, self.extensions);
+        self.is_authorized_traced(q, pset, entities, None)
+    }
+
+    /// Like [`Authorizer::is_authorized`], also recording to `tracer` the
+    /// entities whose data the policies read. The response may only change
+    /// if the data of one of these entities, the request, or the policies
+    /// change.
+    pub fn is_authorized_with_dependencies(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+        tracer: &DependencyTracer,
+    ) -> Response {
+        self.is_authorized_traced(q, pset, entities, Some(tracer))
+            .concretize()
+    }
+
+    /// [`Authorizer::is_authorized_core`], recording the entities whose data
+    /// the policies read to `tracer`, if any
+    fn is_authorized_traced(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+        tracer: Option<&DependencyTracer>,
+    ) -> PartialResponse {
         let mut true_permits = vec![];
         let mut true_forbids = vec![];
         let mut false_permits = vec![];
@@ -98,50 +228,140 @@ impl Authorizer {
         let mut residual_permits = vec![];
         let mut residual_forbids = vec![];
         let mut errors = vec![];
+        let mut unknown_calls = vec![];
 
-        for p in pset.policies() {
+        let ordered = if self.stop_at_first_forbid {
+            Either::Left(
+                pset.policies()
+                    .filter(|p| p.effect() == Effect::Forbid)
+                    .chain(pset.policies().filter(|p| p.effect() == Effect::Permit)),
+            )
+        } else {
+            Either::Right(pset.policies())
+        };
+        let mut evaluated = vec![];
+        for p in ordered {
+            let decided = match self.fast_paths {
+                true => fast_path::decide(p, &q, entities, tracer),
+                false => None,
+            };
             let (id, annotations) = (p.id().clone(), p.annotations_arc().clone());
-            match eval.partial_evaluate(p) {
-                Ok(Either::Left(satisfied)) => match (satisfied, p.effect()) {
-                    (true, Effect::Permit) => true_permits.push((id, annotations)),
-                    (true, Effect::Forbid) => true_forbids.push((id, annotations)),
-                    (false, Effect::Permit) => {
-                        false_permits.push((id, (ErrorState::NoError, annotations)))
-                    }
-                    (false, Effect::Forbid) => {
-                        false_forbids.push((id, (ErrorState::NoError, annotations)))
+            match (decided, p.effect()) {
+                (None, _) => evaluated.push(p),
+                (Some(true), Effect::Permit) => true_permits.push((id, annotations)),
+                (Some(true), Effect::Forbid) => {
+                    true_forbids.push((id, annotations));
+                    if self.stop_at_first_forbid {
+                        evaluated.clear();
+                        break;
                     }
-                },
-                Ok(Either::Right(residual)) => match p.effect() {
-                    Effect::Permit => {
-                        residual_permits.push((id, (Arc::new(residual), annotations)))
-                    }
-                    Effect::Forbid => {
-                        residual_forbids.push((id, (Arc::new(residual), annotations)))
-                    }
-                },
-                Err(e) => {
-                    errors.push(AuthorizationError::PolicyEvaluationError {
+                }
+                (Some(false), Effect::Permit) => {
+                    false_permits.push((id, (ErrorState::NoError, annotations)))
+                }
+                (Some(false), Effect::Forbid) => {
+                    false_forbids.push((id, (ErrorState::NoError, annotations)))
+                }
+            }
+        }
+
+        let policies: Vec<(&Policy, Expr)> =
+            evaluated.into_iter().map(|p| (p, p.condition())).collect();
+        // building the evaluator clones the request, so skip it if every
+        // policy was decided without it
+        if !policies.is_empty() {
+            let shared = if self.share_subexpressions {
+                SharedSubexpressions::new(policies.iter().map(|(_, condition)| condition))
+            } else {
+                SharedSubexpressions::default()
+            };
+            // shared by the evaluation of all the policies of the request
+            let budget = self.extension_budget.map(ExtensionBudget::new);
+            let mut eval = Evaluator::new(q.clone(), entities, self.extensions)
+                .with_overflow_mode(self.overflow_mode)
+                .with_unknown_function_mode(self.unknown_functions);
+            if !shared.is_empty() {
+                eval = eval.with_shared_subexpressions(&shared);
+            }
+            if self.intern_values {
+                eval = eval.with_value_interning();
+            }
+            if let Some(entity_sets) = &self.entity_sets {
+                eval = eval.with_compiled_entity_sets(entity_sets);
+            }
+            if let Some(tracer) = tracer {
+                eval = eval.with_dependency_tracer(tracer);
+            }
+            if let Some(budget) = &budget {
+                eval = eval.with_extension_budget(budget);
+            }
+            for (p, condition) in &policies {
+                let (id, annotations) = (p.id().clone(), p.annotations_arc().clone());
+                let result = eval.partial_evaluate_condition(condition, p.env());
+                unknown_calls.extend(eval.take_unknown_calls().into_iter().map(|err| {
+                    AuthorizationError::PolicyEvaluationError {
                         id: id.clone(),
-                        error: e,
-                    });
-                    let satisfied = match self.error_handling {
-                        ErrorHandling::Skip => false,
-                    };
-                    match (satisfied, p.effect()) {
+                        error: err.into(),
+                    }
+                }));
+                match result {
+                    Ok(Either::Left(satisfied)) => match (satisfied, p.effect()) {
                         (true, Effect::Permit) => true_permits.push((id, annotations)),
-                        (true, Effect::Forbid) => true_forbids.push((id, annotations)),
+                        (true, Effect::Forbid) => {
+                            true_forbids.push((id, annotations));
+                            if self.stop_at_first_forbid {
+                                break;
+                            }
+                        }
                         (false, Effect::Permit) => {
-                            false_permits.push((id, (ErrorState::Error, annotations)))
+                            false_permits.push((id, (ErrorState::NoError, annotations)))
                         }
                         (false, Effect::Forbid) => {
-                            false_forbids.push((id, (ErrorState::Error, annotations)))
+                            false_forbids.push((id, (ErrorState::NoError, annotations)))
+                        }
+                    },
+                    Ok(Either::Right(residual)) => match p.effect() {
+                        Effect::Permit => {
+                            residual_permits.push((id, (Arc::new(residual), annotations)))
+                        }
+                        Effect::Forbid => {
+                            residual_forbids.push((id, (Arc::new(residual), annotations)))
+                        }
+                    },
+                    Err(e) => {
+                        errors.push(AuthorizationError::PolicyEvaluationError {
+                            id: id.clone(),
+                            error: e,
+                        });
+                        let satisfied = match self.error_handling {
+                            ErrorHandling::Skip => false,
+                        };
+                        match (satisfied, p.effect()) {
+                            (true, Effect::Permit) => true_permits.push((id, annotations)),
+                            (true, Effect::Forbid) => true_forbids.push((id, annotations)),
+                            (false, Effect::Permit) => {
+                                false_permits.push((id, (ErrorState::Error, annotations)))
+                            }
+                            (false, Effect::Forbid) => {
+                                false_forbids.push((id, (ErrorState::Error, annotations)))
+                            }
                         }
                     }
-                }
-            };
+                };
+            }
         }
 
+        // the policies of a policy set are stored in hash maps, so sort the
+        // errors to make them independent of the iteration order
+        errors.sort_by(
+            |AuthorizationError::PolicyEvaluationError { id: a, .. },
+             AuthorizationError::PolicyEvaluationError { id: b, .. }| a.cmp(b),
+        );
+        unknown_calls.sort_by(
+            |AuthorizationError::PolicyEvaluationError { id: a, .. },
+             AuthorizationError::PolicyEvaluationError { id: b, .. }| a.cmp(b),
+        );
+
         PartialResponse::new(
             true_permits,
             false_permits,
@@ -152,6 +372,40 @@ impl Authorizer {
             errors,
             Arc::new(q),
         )
+        .with_unknown_function_calls(unknown_calls)
+    }
+
+    /// Evaluates every policy of `pset` for `q`, returning the result of each
+    /// policy, in the order of their ids.
+    ///
+    /// Unlike [`Authorizer::is_authorized`], this never skips a policy, even
+    /// when the decision is already known, and reports the result of the
+    /// policies which don't contribute to the decision. Evaluation errors,
+    /// including unknowns, are returned as the result of the policy which
+    /// encountered them.
+    pub fn evaluate_all(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+    ) -> Vec<PolicyEvaluation> {
+        let budget = self.extension_budget.map(ExtensionBudget::new);
+        let mut eval = Evaluator::new(q, entities, self.extensions)
+            .with_overflow_mode(self.overflow_mode)
+            .with_unknown_function_mode(self.unknown_functions);
+        if let Some(budget) = &budget {
+            eval = eval.with_extension_budget(budget);
+        }
+        let mut evaluations: Vec<_> = pset
+            .policies()
+            .map(|p| PolicyEvaluation {
+                id: p.id().clone(),
+                effect: p.effect(),
+                result: eval.evaluate(p),
+            })
+            .collect();
+        evaluations.sort_by(|a, b| a.id.cmp(&b.id));
+        evaluations
     }
 }
 
@@ -180,7 +434,9 @@ impl std::fmt::Debug for Authorizer {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::entities::{NoEntitiesSchema, TCComputation};
     use crate::parser;
+    use cool_asserts::assert_matches;
 
     /// Sanity unit test case for is_authorized.
     /// More robust testing is accomplished through the integration tests.
@@ -258,6 +514,265 @@ mod test {
         assert_eq!(ans.decision, Decision::Deny);
     }
 
+    /// The errors of a response are in the order of the policy ids, whatever
+    /// the order the policies are stored in
+    #[test]
+    fn errors_are_sorted() {
+        let a = Authorizer::new();
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        let ids = ["5", "3", "9", "1", "7", "2", "8", "4", "6", "0"];
+        for id in ids {
+            let p = parser::parse_policy(
+                Some(PolicyID::from_string(id)),
+                r#"permit(principal, action, resource) when { context.bad == 2 };"#,
+            )
+            .unwrap();
+            pset.add_static(p).unwrap();
+        }
+        let ans = a.is_authorized(q, &pset, &Entities::new());
+        let error_ids: Vec<_> = ans
+            .diagnostics
+            .errors
+            .iter()
+            .map(|AuthorizationError::PolicyEvaluationError { id, .. }| id.to_string())
+            .collect();
+        assert_eq!(
+            error_ids,
+            ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+        );
+    }
+
+    /// With `with_stop_at_first_forbid`, the policies after the first
+    /// satisfied forbid aren't evaluated, so their errors aren't reported
+    #[test]
+    fn stop_at_first_forbid() {
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        let srcs = [
+            (
+                "0",
+                "permit(principal, action, resource) when { context.bad == 2 };",
+            ),
+            ("1", "permit(principal, action, resource);"),
+            (
+                "2",
+                "forbid(principal, action, resource) when { principal == principal };",
+            ),
+        ];
+        for (id, src) in srcs {
+            let p = parser::parse_policy(Some(PolicyID::from_string(id)), src).unwrap();
+            pset.add_static(p).unwrap();
+        }
+        let entities = Entities::new();
+
+        let ans = Authorizer::new().is_authorized(q.clone(), &pset, &entities);
+        assert_eq!(ans.decision, Decision::Deny);
+        assert_eq!(ans.diagnostics.errors.len(), 1);
+
+        for fast_paths in [true, false] {
+            let a = Authorizer::new()
+                .with_fast_paths(fast_paths)
+                .with_stop_at_first_forbid(true);
+            let ans = a.is_authorized(q.clone(), &pset, &entities);
+            assert_eq!(ans.decision, Decision::Deny);
+            assert_eq!(
+                ans.diagnostics.reason,
+                HashSet::from([PolicyID::from_string("2")])
+            );
+            assert!(ans.diagnostics.errors.is_empty());
+        }
+
+        // a satisfied forbid decided by the fast path stops before evaluating
+        // the forbid which needs the evaluator
+        pset.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("3")),
+                "forbid(principal, action, resource);",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let a = Authorizer::new().with_stop_at_first_forbid(true);
+        let ans = a.is_authorized(q, &pset, &entities);
+        assert_eq!(
+            ans.diagnostics.reason,
+            HashSet::from([PolicyID::from_string("3")])
+        );
+    }
+
+    /// Calls of extension functions which don't exist, e.g., in policies
+    /// loaded from the stable AST, are handled according to the
+    /// `UnknownFunctionMode`
+    #[test]
+    fn unknown_functions() {
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        pset.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("permit")),
+                "permit(principal, action, resource);",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        pset.add(Policy::from_when_clause(
+            Effect::Forbid,
+            Expr::call_extension_fn("geo::inRegion".parse().unwrap(), vec![Expr::val("eu")]),
+            PolicyID::from_string("forbid"),
+            None,
+        ))
+        .unwrap();
+        let entities = Entities::new();
+
+        let ans = Authorizer::new().is_authorized(q.clone(), &pset, &entities);
+        assert_eq!(ans.decision, Decision::Allow);
+        assert_matches!(
+            ans.diagnostics.errors.as_slice(),
+            [AuthorizationError::PolicyEvaluationError {
+                error: EvaluationError::FailedExtensionFunctionLookup(_),
+                ..
+            }]
+        );
+        assert!(ans.diagnostics.unknown_function_calls.is_empty());
+
+        let a = Authorizer::new().with_unknown_function_mode(UnknownFunctionMode::False);
+        let ans = a.is_authorized(q.clone(), &pset, &entities);
+        assert_eq!(ans.decision, Decision::Allow);
+        assert!(ans.diagnostics.errors.is_empty());
+        assert_matches!(
+            ans.diagnostics.unknown_function_calls.as_slice(),
+            [AuthorizationError::PolicyEvaluationError { id, .. }] => {
+                assert_eq!(id, &PolicyID::from_string("forbid"));
+            }
+        );
+
+        let a = Authorizer::new().with_unknown_function_mode(UnknownFunctionMode::Residual);
+        let ans = a.is_authorized_core(q, &pset, &entities);
+        assert_eq!(ans.decision(), None);
+        assert_eq!(ans.unknown_function_calls.len(), 1);
+        let ans = ans.concretize();
+        assert_matches!(
+            ans.diagnostics.errors.as_slice(),
+            [AuthorizationError::PolicyEvaluationError {
+                error: EvaluationError::NonValue(_),
+                ..
+            }]
+        );
+        assert_eq!(ans.diagnostics.unknown_function_calls.len(), 1);
+    }
+
+    /// The extension function budget is shared by all the policies of a
+    /// request, and the policies exceeding it are skipped with an error
+    #[cfg(feature = "ipaddr")]
+    #[test]
+    fn extension_budget() {
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::from_pairs(
+                [("addr".into(), RestrictedExpr::val("127.0.0.1"))],
+                Extensions::none(),
+            )
+            .unwrap(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let pset = parser::parse_policyset(
+            r#"
+            permit(principal, action, resource) when { ip(context.addr).isLoopback() };
+            permit(principal, action, resource) when { ip(context.addr).isIpv4() };
+            "#,
+        )
+        .unwrap();
+        let entities = Entities::new();
+
+        let ans = Authorizer::new().is_authorized(q.clone(), &pset, &entities);
+        assert_eq!(ans.decision, Decision::Allow);
+        assert!(ans.diagnostics.errors.is_empty());
+
+        // each policy costs 11: 10 for `ip()` and 1 for the method
+        let a = Authorizer::new().with_extension_budget(15);
+        let ans = a.is_authorized(q, &pset, &entities);
+        assert_matches!(
+            ans.diagnostics.errors.as_slice(),
+            [AuthorizationError::PolicyEvaluationError {
+                error: EvaluationError::LimitExceeded(_),
+                ..
+            }]
+        );
+    }
+
+    /// `evaluate_all` reports the result of every policy, including the
+    /// errors of policies which don't affect the decision
+    #[test]
+    fn evaluate_all() {
+        let a = Authorizer::new();
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        let srcs = [
+            ("0", "forbid(principal, action, resource);"),
+            ("1", "permit(principal, action, resource);"),
+            (
+                "2",
+                "permit(principal, action, resource) when { context.bad == 2 };",
+            ),
+            ("3", "forbid(principal, action, resource) when { false };"),
+        ];
+        for (id, src) in srcs {
+            let p = parser::parse_policy(Some(PolicyID::from_string(id)), src).unwrap();
+            pset.add_static(p).unwrap();
+        }
+        let evaluations = a.evaluate_all(q, &pset, &Entities::new());
+        let results: Vec<_> = evaluations
+            .iter()
+            .map(|e| (e.id.to_string(), e.effect, e.result.as_ref().ok().copied()))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("0".to_string(), Effect::Forbid, Some(true)),
+                ("1".to_string(), Effect::Permit, Some(true)),
+                ("2".to_string(), Effect::Permit, None),
+                ("3".to_string(), Effect::Forbid, Some(false)),
+            ]
+        );
+    }
+
     fn true_policy(id: &str, e: Effect) -> StaticPolicy {
         let pid = PolicyID::from_string(id);
         StaticPolicy::new(
@@ -633,6 +1148,185 @@ mod test {
         assert!(r.residual_permits.contains_key(&PolicyID::from_string("2")));
         assert!(r.residual_forbids.is_empty());
     }
+
+    #[test]
+    #[cfg(feature = "partial-eval")]
+    fn simplified_residuals() {
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let a = Authorizer::new();
+        let mut pset = PolicySet::new();
+        let es = Entities::new();
+
+        let src1 = r#"
+        permit(principal, action, resource) when { unknown("a") && 1 < 2 && unknown("a") };
+        "#;
+        let src2 = r#"
+        forbid(principal, action, resource) when { unknown("a") || true };
+        "#;
+        pset.add_static(parser::parse_policy(Some(PolicyID::from_string("1")), src1).unwrap())
+            .unwrap();
+        pset.add_static(parser::parse_policy(Some(PolicyID::from_string("2")), src2).unwrap())
+            .unwrap();
+
+        let r = a.is_authorized_core(q, &pset, &es);
+        // residuals are only simplified on request
+        let (residual, _) = &r.residual_permits[&PolicyID::from_string("1")];
+        assert_ne!(residual.to_string(), r#"unknown("a")"#);
+
+        let r = r.simplified();
+        let (residual, _) = &r.residual_permits[&PolicyID::from_string("1")];
+        assert_eq!(residual.to_string(), r#"unknown("a")"#);
+        let (residual, _) = &r.residual_forbids[&PolicyID::from_string("2")];
+        assert_eq!(residual.to_string(), r#"(unknown("a")) || true"#);
+    }
+
+    #[test]
+    fn shared_subexpressions() {
+        let context = Context::from_pairs(
+            [
+                ("level".into(), RestrictedExpr::val(5)),
+                ("mfa".into(), RestrictedExpr::val(false)),
+            ],
+            Extensions::none(),
+        )
+        .unwrap();
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            context,
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let es = Entities::new();
+        let mut pset = PolicySet::new();
+        let srcs = [
+            r#"permit(principal, action, resource) when { context.level > 3 && context.mfa };"#,
+            r#"permit(principal, action, resource) when { context.level > 3 && principal == resource };"#,
+            r#"forbid(principal, action, resource) when { context.level > 3 && context.missing };"#,
+            r#"forbid(principal, action, resource) when { context.level > 3 && !context.mfa && context.missing };"#,
+            r#"permit(principal, action, resource) when { context.level > 3 && !context.mfa };"#,
+        ];
+        for (i, src) in srcs.into_iter().enumerate() {
+            let id = PolicyID::from_string(i.to_string());
+            pset.add_static(parser::parse_policy(Some(id), src).unwrap())
+                .unwrap();
+        }
+
+        let shared = Authorizer::new().with_shared_subexpressions(true);
+        let response = shared.is_authorized(q.clone(), &pset, &es);
+        assert_eq!(response.decision, Decision::Allow);
+        assert_eq!(response.diagnostics.errors.len(), 2);
+        assert_eq!(response, Authorizer::new().is_authorized(q, &pset, &es));
+    }
+
+    #[test]
+    fn compiled_entity_sets() {
+        let users = |range: std::ops::Range<usize>| {
+            format!("[{}]", range.map(|i| format!(r#"User::"u{i}""#)).join(", "))
+        };
+        let src = format!(
+            r#"
+            permit(principal, action, resource) when {{ {}.contains(principal) }};
+            permit(principal, action, resource) when {{ {}.containsAny([principal, resource]) }};
+            forbid(principal, action, resource) when {{ {}.containsAll({}) }};
+            forbid(principal, action, resource) when {{ {}.containsAny({}) }};
+            "#,
+            users(0..100),
+            users(100..200),
+            users(0..100),
+            users(100..200),
+            users(200..300),
+            users(0..200),
+        );
+        let pset = parser::parse_policyset(&src).unwrap();
+        let compiled =
+            Authorizer::new().with_compiled_entity_sets(Arc::new(CompiledEntitySets::new(&pset)));
+        let es = Entities::new();
+        for (principal, resource, decision) in [
+            ("u1", "r", Decision::Allow),
+            ("u100", "u199", Decision::Allow),
+            ("u250", "r", Decision::Deny),
+        ] {
+            let q = Request::new(
+                (
+                    EntityUID::with_eid_and_type("User", principal).unwrap(),
+                    None,
+                ),
+                (EntityUID::with_eid("a"), None),
+                (
+                    EntityUID::with_eid_and_type("User", resource).unwrap(),
+                    None,
+                ),
+                Context::empty(),
+                None::<&RequestSchemaAllPass>,
+                Extensions::none(),
+            )
+            .unwrap();
+            let response = compiled.is_authorized(q.clone(), &pset, &es);
+            assert_eq!(response.decision, decision);
+            assert_eq!(response, Authorizer::new().is_authorized(q, &pset, &es));
+        }
+    }
+
+    #[test]
+    fn interned_values() {
+        let group = |name: &str| EntityUID::with_eid(name);
+        let entity = |name: &str, groups: &[&str]| {
+            Entity::new_with_attr_partial_value(
+                group(name),
+                [(
+                    "groups".into(),
+                    PartialValue::Value(Value::set(
+                        groups.iter().map(|g| Value::from(group(g))),
+                        None,
+                    )),
+                )]
+                .into_iter()
+                .collect(),
+                HashSet::new(),
+            )
+        };
+        let es = Entities::from_entities(
+            [entity("p", &["a", "b"]), entity("r", &["b", "a"])],
+            None::<&NoEntitiesSchema>,
+            TCComputation::AssumeAlreadyComputed,
+            Extensions::none(),
+        )
+        .unwrap();
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        let src = r#"
+        permit(principal, action, resource) when {
+            principal.groups == resource.groups &&
+            [principal.groups, {"groups": resource.groups}].contains({"groups": principal.groups})
+        };
+        "#;
+        pset.add_static(parser::parse_policy(Some(PolicyID::from_string("1")), src).unwrap())
+            .unwrap();
+
+        let interned = Authorizer::new().with_interned_values(true);
+        let response = interned.is_authorized(q.clone(), &pset, &es);
+        assert_eq!(response.decision, Decision::Allow);
+        assert_eq!(response, Authorizer::new().is_authorized(q, &pset, &es));
+    }
 }
 // by default, Coverlay does not track coverage for lines after a line
 // containing #[cfg(test)].
@@ -649,6 +1343,19 @@ pub struct Response {
     pub diagnostics: Diagnostics,
 }
 
+/// Result of evaluating a single policy, returned by
+/// [`Authorizer::evaluate_all`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct PolicyEvaluation {
+    /// Id of the policy
+    pub id: PolicyID,
+    /// Effect of the policy
+    pub effect: Effect,
+    /// Whether the policy is satisfied, or the error its evaluation
+    /// encountered
+    pub result: Result<bool, EvaluationError>,
+}
+
 /// Policy evaluation response returned from the `Authorizer`.
 #[derive(Debug, PartialEq, Clone)]
 pub struct EvaluationResponse {
@@ -672,6 +1379,9 @@ pub struct Diagnostics {
     pub reason: HashSet<PolicyID>,
     /// List of errors that occurred
     pub errors: Vec<AuthorizationError>,
+    /// Calls of extension functions which don't exist, which weren't errors
+    /// due to the [`UnknownFunctionMode`] of the authorizer
+    pub unknown_function_calls: Vec<AuthorizationError>,
 }
 
 impl Response {
@@ -683,7 +1393,11 @@ impl Response {
     ) -> Self {
         Response {
             decision,
-            diagnostics: Diagnostics { reason, errors },
+            diagnostics: Diagnostics {
+                reason,
+                errors,
+                unknown_function_calls: Vec::new(),
+            },
         }
     }
 }
@@ -0,0 +1,270 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deciding policies whose conditions are trivially `true` or `false`, e.g.,
+//! role-based policies with only a scope, without the evaluator.
+
+use crate::ast::{
+    ActionConstraint, EntityReference, EntityUID, Expr, ExprKind, Literal, Policy,
+    PrincipalOrResourceConstraint, Request, SlotId, UnaryOp,
+};
+use crate::entities::{Dereference, Entities};
+use crate::evaluator::DependencyTracer;
+
+/// Decide whether `p` is satisfied by `q`, if its non-scope constraints are
+/// trivially `true` or `false` and its scope can be matched without the
+/// evaluator. Returns `None` if the evaluator is needed, e.g., for
+/// conditions which aren't trivial, or unknowns in the request or the
+/// entities.
+///
+/// Matching the scope never errors, so the result is the same as evaluating
+/// the condition of `p`.
+pub(super) fn decide(
+    p: &Policy,
+    q: &Request,
+    entities: &Entities,
+    tracer: Option<&DependencyTracer>,
+) -> Option<bool> {
+    match trivial_condition(p.non_scope_constraints())? {
+        false => Some(false),
+        true => Some(
+            principal_or_resource_satisfied(
+                p.template().principal_constraint().as_inner(),
+                p.env().get(&SlotId::principal()),
+                q.principal().uid()?,
+                entities,
+                tracer,
+            )? && action_satisfied(p.action_constraint(), q.action().uid()?, entities, tracer)?
+                && principal_or_resource_satisfied(
+                    p.template().resource_constraint().as_inner(),
+                    p.env().get(&SlotId::resource()),
+                    q.resource().uid()?,
+                    entities,
+                    tracer,
+                )?,
+        ),
+    }
+}
+
+/// The value of `e`, if it is built from boolean literals with `&&`, `||`,
+/// and `!` only, e.g., `when { true }` or `unless { false }`.
+///
+/// Evaluation of `&&` and `||` is from left to right, so the right operand
+/// only decides the value when the left one is trivial: `x && false` is an
+/// error if `x` is.
+fn trivial_condition(e: &Expr) -> Option<bool> {
+    match e.expr_kind() {
+        ExprKind::Lit(Literal::Bool(b)) => Some(*b),
+        ExprKind::And { left, right } => match trivial_condition(left)? {
+            false => Some(false),
+            true => trivial_condition(right),
+        },
+        ExprKind::Or { left, right } => match trivial_condition(left)? {
+            true => Some(true),
+            false => trivial_condition(right),
+        },
+        ExprKind::UnaryApp {
+            op: UnaryOp::Not,
+            arg,
+        } => trivial_condition(arg).map(|b| !b),
+        _ => None,
+    }
+}
+
+fn principal_or_resource_satisfied(
+    constraint: &PrincipalOrResourceConstraint,
+    slot: Option<&EntityUID>,
+    uid: &EntityUID,
+    entities: &Entities,
+    tracer: Option<&DependencyTracer>,
+) -> Option<bool> {
+    match constraint {
+        PrincipalOrResourceConstraint::Any => Some(true),
+        PrincipalOrResourceConstraint::Eq(r) => Some(uid == resolve(r, slot)?),
+        PrincipalOrResourceConstraint::Is(ety) => Some(uid.entity_type() == ety.as_ref()),
+        PrincipalOrResourceConstraint::In(r) => is_in(uid, [resolve(r, slot)?], entities, tracer),
+        PrincipalOrResourceConstraint::IsIn(ety, r) => {
+            let target = resolve(r, slot)?;
+            match uid.entity_type() == ety.as_ref() {
+                true => is_in(uid, [target], entities, tracer),
+                false => Some(false),
+            }
+        }
+    }
+}
+
+/// The entity uid `r` refers to, if the slot it may be is filled
+fn resolve<'a>(r: &'a EntityReference, slot: Option<&'a EntityUID>) -> Option<&'a EntityUID> {
+    match r {
+        EntityReference::EUID(euid) => Some(euid.as_ref()),
+        EntityReference::Slot => slot,
+    }
+}
+
+fn action_satisfied(
+    constraint: &ActionConstraint,
+    uid: &EntityUID,
+    entities: &Entities,
+    tracer: Option<&DependencyTracer>,
+) -> Option<bool> {
+    match constraint {
+        ActionConstraint::Any => Some(true),
+        ActionConstraint::Eq(action) => Some(uid == action.as_ref()),
+        ActionConstraint::In(actions) => {
+            is_in(uid, actions.iter().map(AsRef::as_ref), entities, tracer)
+        }
+    }
+}
+
+/// Like [`crate::ast::BinaryOp::In`], `uid` is in the `targets` if it is one of them or
+/// a descendant of one of them. Returns `None` if the entity of `uid` is a
+/// residual.
+fn is_in<'a>(
+    uid: &EntityUID,
+    targets: impl IntoIterator<Item = &'a EntityUID>,
+    entities: &Entities,
+    tracer: Option<&DependencyTracer>,
+) -> Option<bool> {
+    if let Some(tracer) = tracer {
+        tracer.record_entity(uid);
+    }
+    let entity = match entities.entity(uid) {
+        Dereference::Residual(_) => return None,
+        Dereference::NoSuchEntity => None,
+        Dereference::Data(entity) => Some(entity),
+    };
+    Some(targets.into_iter().any(|target| {
+        uid == target || entity.is_some_and(|entity| entities.is_descendant_of(entity, target))
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{Context, EntityUIDEntry, PolicyID};
+    use crate::entities::{EntityJsonParser, NoEntitiesSchema, TCComputation};
+    use crate::evaluator::Evaluator;
+    use crate::extensions::Extensions;
+    use crate::parser::parse_policy;
+
+    fn entities() -> Entities {
+        EntityJsonParser::<NoEntitiesSchema>::new(
+            None,
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {},
+                  "parents": [{ "type": "Role", "id": "admin" }] },
+                { "uid": { "type": "Role", "id": "admin" }, "attrs": {},
+                  "parents": [{ "type": "Role", "id": "staff" }] },
+                { "uid": { "type": "Action", "id": "edit" }, "attrs": {},
+                  "parents": [{ "type": "Action", "id": "write" }] }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn request(principal: &str, action: &str) -> Request {
+        let entry = |uid: &str| EntityUIDEntry::known(uid.parse().unwrap(), None);
+        Request::new_unchecked(
+            entry(principal),
+            entry(action),
+            entry(r#"Photo::"p""#),
+            Some(Context::empty()),
+        )
+    }
+
+    /// Decide `src` with the fast path, checking it agrees with the evaluator
+    fn fast_path(src: &str, q: &Request) -> Option<bool> {
+        let p: Policy = parse_policy(Some(PolicyID::from_string("p")), src)
+            .unwrap()
+            .into();
+        let entities = entities();
+        let decided = decide(&p, q, &entities, None);
+        if let Some(decided) = decided {
+            let eval = Evaluator::new(q.clone(), &entities, Extensions::all_available());
+            assert_eq!(eval.evaluate(&p).unwrap(), decided, "{src}");
+        }
+        decided
+    }
+
+    #[test]
+    fn scope_only_policies() {
+        let alice = request(r#"User::"alice""#, r#"Action::"edit""#);
+        let bob = request(r#"User::"bob""#, r#"Action::"view""#);
+        for (src, alice_satisfies, bob_satisfies) in [
+            ("permit(principal, action, resource);", true, true),
+            (
+                r#"permit(principal in Role::"staff", action, resource);"#,
+                true,
+                false,
+            ),
+            (
+                r#"permit(principal is User in Role::"admin", action in [Action::"write", Action::"read"], resource);"#,
+                true,
+                false,
+            ),
+            (
+                r#"permit(principal == User::"bob", action, resource is Photo);"#,
+                false,
+                true,
+            ),
+            (
+                r#"forbid(principal, action == Action::"view", resource) when { true } unless { false };"#,
+                false,
+                true,
+            ),
+            (
+                "permit(principal, action, resource) when { false && principal.missing };",
+                false,
+                false,
+            ),
+        ] {
+            assert_eq!(fast_path(src, &alice), Some(alice_satisfies), "{src}");
+            assert_eq!(fast_path(src, &bob), Some(bob_satisfies), "{src}");
+        }
+    }
+
+    #[test]
+    fn nontrivial_policies() {
+        let alice = request(r#"User::"alice""#, r#"Action::"edit""#);
+        for src in [
+            "permit(principal, action, resource) when { principal.missing && false };",
+            "permit(principal, action, resource) when { context has x };",
+        ] {
+            assert_eq!(fast_path(src, &alice), None, "{src}");
+        }
+        let unknown = Request::new_unchecked(
+            EntityUIDEntry::Unknown { loc: None },
+            EntityUIDEntry::known(r#"Action::"edit""#.parse().unwrap(), None),
+            EntityUIDEntry::known(r#"Photo::"p""#.parse().unwrap(), None),
+            Some(Context::empty()),
+        );
+        assert_eq!(
+            fast_path("permit(principal, action, resource);", &unknown),
+            None
+        );
+        assert_eq!(
+            fast_path(
+                "permit(principal, action, resource) when { false };",
+                &unknown
+            ),
+            Some(false)
+        );
+    }
+}
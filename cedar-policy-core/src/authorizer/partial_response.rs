@@ -25,7 +25,12 @@ use super::{
     Annotations, AuthorizationError, Authorizer, Context, Decision, Effect, EntityUIDEntry, Expr,
     Policy, PolicySet, PolicySetError, Request, Response, Value,
 };
-use crate::{ast::PolicyID, entities::Entities, evaluator::EvaluationError};
+use crate::{
+    ast::{ExprKind, Literal, PolicyID},
+    entities::Entities,
+    evaluator::EvaluationError,
+    optimizer,
+};
 
 type PolicyComponents<'a> = (Effect, &'a PolicyID, &'a Arc<Expr>, &'a Arc<Annotations>);
 
@@ -60,6 +65,9 @@ pub struct PartialResponse {
     pub residual_forbids: HashMap<PolicyID, (Arc<Expr>, Arc<Annotations>)>,
     /// All of the policy errors encountered during evaluation
     pub errors: Vec<AuthorizationError>,
+    /// Calls of extension functions which don't exist, which weren't errors
+    /// due to the [`crate::evaluator::UnknownFunctionMode`] of the authorizer
+    pub unknown_function_calls: Vec<AuthorizationError>,
     /// The trivial `true` expression, used for materializing a residual for satisfied policies
     true_expr: Arc<Expr>,
     /// The trivial `false` expression, used for materializing a residual for non-satisfied policies
@@ -89,18 +97,53 @@ impl PartialResponse {
             false_forbids: false_forbids.into_iter().collect(),
             residual_forbids: residual_forbids.into_iter().collect(),
             errors: errors.into_iter().collect(),
+            unknown_function_calls: Vec::new(),
             true_expr: Arc::new(Expr::val(true)),
             false_expr: Arc::new(Expr::val(false)),
             request,
         }
     }
 
+    /// Set the calls of extension functions which don't exist, which weren't
+    /// errors
+    pub(crate) fn with_unknown_function_calls(mut self, calls: Vec<AuthorizationError>) -> Self {
+        self.unknown_function_calls = calls;
+        self
+    }
+
+    /// The request this response is for
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
     /// Convert this response into a concrete evaluation response.
     /// All residuals are treated as errors
     pub fn concretize(self) -> Response {
         self.into()
     }
 
+    /// Simplify the residuals of this response with
+    /// [`optimizer::simplify_residual`], to make them easier to convert to,
+    /// e.g., query filters. Policies whose residual simplifies to `true` or
+    /// `false` become satisfied or not satisfied.
+    ///
+    /// The simplification assumes that the policies pass validation: e.g., it
+    /// rewrites `true && e` to `e`, which errors differently if `e` is not a
+    /// boolean. Don't simplify the residuals of unvalidated policies.
+    pub fn simplified(mut self) -> Self {
+        simplify_residuals(
+            &mut self.residual_permits,
+            &mut self.satisfied_permits,
+            &mut self.false_permits,
+        );
+        simplify_residuals(
+            &mut self.residual_forbids,
+            &mut self.satisfied_forbids,
+            &mut self.false_forbids,
+        );
+        self
+    }
+
     /// Attempt to reach a partial decision; the presence of residuals may result in returning [`None`],
     /// indicating that a decision could not be reached given the unknowns
     pub fn decision(&self) -> Option<Decision> {
@@ -459,18 +502,44 @@ impl PartialResponse {
     }
 }
 
+/// Simplify the `residuals` of policies with one effect, moving the policies
+/// whose residual simplifies to a boolean to `satisfied` or `not_satisfied`
+fn simplify_residuals(
+    residuals: &mut HashMap<PolicyID, (Arc<Expr>, Arc<Annotations>)>,
+    satisfied: &mut HashMap<PolicyID, Arc<Annotations>>,
+    not_satisfied: &mut HashMap<PolicyID, (ErrorState, Arc<Annotations>)>,
+) {
+    for (id, (residual, annotations)) in std::mem::take(residuals) {
+        let residual = optimizer::simplify_residual(&residual);
+        match residual.expr_kind() {
+            ExprKind::Lit(Literal::Bool(true)) => {
+                satisfied.insert(id, annotations);
+            }
+            ExprKind::Lit(Literal::Bool(false)) => {
+                not_satisfied.insert(id, (ErrorState::NoError, annotations));
+            }
+            _ => {
+                residuals.insert(id, (Arc::new(residual), annotations));
+            }
+        }
+    }
+}
+
 impl From<PartialResponse> for Response {
-    fn from(p: PartialResponse) -> Self {
+    fn from(mut p: PartialResponse) -> Self {
         let decision = if !p.satisfied_permits.is_empty() && p.satisfied_forbids.is_empty() {
             Decision::Allow
         } else {
             Decision::Deny
         };
-        Response::new(
+        let unknown_function_calls = std::mem::take(&mut p.unknown_function_calls);
+        let mut response = Response::new(
             decision,
             p.must_be_determining().map(|p| p.id().clone()).collect(),
             p.errors().collect(),
-        )
+        );
+        response.diagnostics.unknown_function_calls = unknown_function_calls;
+        response
     }
 }
 
@@ -0,0 +1,225 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains a rewriting of policy conditions into a canonical
+//! form, so that conditions which differ only in the order of commutative
+//! operands, the order of set literal elements, or the grouping of `&&` and
+//! `||` have the same canonical form (and hence the same text).
+//!
+//! The canonical form of a condition evaluates to the same value as the
+//! condition for every request. It may fail with a different error than the
+//! condition when several of its operands fail, since the operands of `==`,
+//! `+`, and `*` and the elements of set literals may be evaluated in a
+//! different order. The operands of `&&` and `||` are never reordered, since
+//! they short-circuit, e.g., `principal has age && principal.age > 3` does
+//! not fail while `principal.age > 3 && principal has age` may.
+
+use crate::ast::{ActionConstraint, BinaryOp, Expr, ExprKind};
+use std::sync::Arc;
+
+/// The canonical form of the policy condition `expr`
+pub fn canonicalize(expr: &Expr) -> Expr {
+    let loc = expr.source_loc().cloned();
+    match expr.expr_kind() {
+        ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+            expr.clone()
+        }
+        ExprKind::If {
+            test_expr,
+            then_expr,
+            else_expr,
+        } => Expr::ite(
+            canonicalize(test_expr),
+            canonicalize(then_expr),
+            canonicalize(else_expr),
+        ),
+        ExprKind::And { .. } => {
+            let mut operands = Vec::new();
+            flatten_and(expr, &mut operands);
+            right_nested(operands, Expr::and)
+        }
+        ExprKind::Or { .. } => {
+            let mut operands = Vec::new();
+            flatten_or(expr, &mut operands);
+            right_nested(operands, Expr::or)
+        }
+        ExprKind::UnaryApp { op, arg } => Expr::unary_app(*op, canonicalize(arg)),
+        ExprKind::BinaryApp { op, arg1, arg2 } => {
+            let (arg1, arg2) = (canonicalize(arg1), canonicalize(arg2));
+            match op {
+                BinaryOp::Eq | BinaryOp::Add | BinaryOp::Mul
+                    if arg2.to_string() < arg1.to_string() =>
+                {
+                    Expr::binary_app(*op, arg2, arg1)
+                }
+                _ => Expr::binary_app(*op, arg1, arg2),
+            }
+        }
+        ExprKind::ExtensionFunctionApp { fn_name, args } => {
+            Expr::call_extension_fn(fn_name.clone(), args.iter().map(canonicalize).collect())
+        }
+        ExprKind::GetAttr { expr, attr } => Expr::get_attr(canonicalize(expr), attr.clone()),
+        ExprKind::HasAttr { expr, attr } => Expr::has_attr(canonicalize(expr), attr.clone()),
+        ExprKind::Like { expr, pattern } => Expr::like(canonicalize(expr), pattern.iter().cloned()),
+        ExprKind::Is { expr, entity_type } => {
+            Expr::is_entity_type(canonicalize(expr), entity_type.clone())
+        }
+        ExprKind::Set(elems) => {
+            // sorting by the text of the elements puts identical elements
+            // next to each other, so that they can be deduplicated
+            let mut elems: Vec<(String, Expr)> = elems
+                .iter()
+                .map(|e| {
+                    let e = canonicalize(e);
+                    (e.to_string(), e)
+                })
+                .collect();
+            elems.sort_by(|(a, _), (b, _)| a.cmp(b));
+            elems.dedup_by(|(a, _), (b, _)| a == b);
+            Expr::set(elems.into_iter().map(|(_, e)| e))
+        }
+        // records are already ordered by attribute name
+        ExprKind::Record(attrs) => Expr::record_arc(Arc::new(
+            attrs
+                .iter()
+                .map(|(k, v)| (k.clone(), canonicalize(v)))
+                .collect(),
+        )),
+    }
+    .with_maybe_source_loc(loc)
+}
+
+/// The canonical form of the action constraint `constraint`, where the
+/// actions of an `in` constraint are sorted and deduplicated
+pub fn canonicalize_action_constraint(constraint: &ActionConstraint) -> ActionConstraint {
+    match constraint {
+        ActionConstraint::In(euids) => {
+            let mut euids = euids.clone();
+            euids.sort();
+            euids.dedup();
+            ActionConstraint::In(euids)
+        }
+        ActionConstraint::Any | ActionConstraint::Eq(_) => constraint.clone(),
+    }
+}
+
+/// Push the canonical forms of the operands of the `&&` chain `expr` to
+/// `operands`, from left to right
+fn flatten_and(expr: &Expr, operands: &mut Vec<Expr>) {
+    match expr.expr_kind() {
+        ExprKind::And { left, right } => {
+            flatten_and(left, operands);
+            flatten_and(right, operands);
+        }
+        _ => operands.push(canonicalize(expr)),
+    }
+}
+
+/// Push the canonical forms of the operands of the `||` chain `expr` to
+/// `operands`, from left to right
+fn flatten_or(expr: &Expr, operands: &mut Vec<Expr>) {
+    match expr.expr_kind() {
+        ExprKind::Or { left, right } => {
+            flatten_or(left, operands);
+            flatten_or(right, operands);
+        }
+        _ => operands.push(canonicalize(expr)),
+    }
+}
+
+/// Combine the (nonempty) `operands` with the associative operator `op`,
+/// grouping to the right, i.e., `a op (b op c)`
+fn right_nested(operands: Vec<Expr>, op: fn(Expr, Expr) -> Expr) -> Expr {
+    let mut operands = operands.into_iter().rev();
+    // PANIC SAFETY: callers pass the operands of a `&&` or `||` expression
+    #[allow(clippy::expect_used)]
+    let last = operands
+        .next()
+        .expect("a `&&` or `||` chain has at least two operands");
+    operands.fold(last, |acc, e| op(e, acc))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_expr;
+
+    #[track_caller]
+    fn assert_same_canonical_form(e1: &str, e2: &str) {
+        let c1 = canonicalize(&parse_expr(e1).unwrap());
+        let c2 = canonicalize(&parse_expr(e2).unwrap());
+        assert_eq!(c1.to_string(), c2.to_string());
+    }
+
+    #[track_caller]
+    fn assert_different_canonical_form(e1: &str, e2: &str) {
+        let c1 = canonicalize(&parse_expr(e1).unwrap());
+        let c2 = canonicalize(&parse_expr(e2).unwrap());
+        assert_ne!(c1.to_string(), c2.to_string());
+    }
+
+    #[test]
+    fn commutative_operators() {
+        assert_same_canonical_form("principal.age == 3", "3 == principal.age");
+        assert_same_canonical_form("principal.age != 3", "3 != principal.age");
+        assert_same_canonical_form("1 + principal.age * 2", "2 * principal.age + 1");
+        assert_different_canonical_form("principal.age - 3", "3 - principal.age");
+        assert_different_canonical_form("principal.age < 3", "3 < principal.age");
+    }
+
+    #[test]
+    fn sets() {
+        assert_same_canonical_form("[3, 1, 2]", "[1, 2, 3]");
+        assert_same_canonical_form("[1, principal, 1]", "[principal, 1]");
+        assert_same_canonical_form("[[2, 1], [3]]", "[[3], [1, 2]]");
+    }
+
+    #[test]
+    fn boolean_chains() {
+        assert_same_canonical_form(
+            "(context.a && context.b) && context.c",
+            "context.a && (context.b && context.c)",
+        );
+        assert_same_canonical_form(
+            "(context.a || context.b) || context.c",
+            "context.a || (context.b || context.c)",
+        );
+        assert_same_canonical_form(
+            "(3 == context.a && context.b) || context.c",
+            "(context.a == 3 && context.b) || context.c",
+        );
+        // short-circuiting operands are not reordered
+        assert_different_canonical_form("context.a && context.b", "context.b && context.a");
+        assert_different_canonical_form(
+            "(context.a && context.b) || context.c",
+            "context.a && (context.b || context.c)",
+        );
+    }
+
+    #[test]
+    fn action_constraints() {
+        let a = Arc::new(r#"Action::"a""#.parse().unwrap());
+        let b = Arc::new(r#"Action::"b""#.parse().unwrap());
+        assert_eq!(
+            canonicalize_action_constraint(&ActionConstraint::In(vec![
+                Arc::clone(&b),
+                Arc::clone(&a),
+                Arc::clone(&b)
+            ])),
+            ActionConstraint::In(vec![a, b])
+        );
+    }
+}
@@ -0,0 +1,710 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains an ahead-of-time compiler from Cedar policy sets to
+//! Rust source code.
+//!
+//! The generated code is a Rust module exposing a single authorization
+//! function with the same semantics as [`crate::authorizer::Authorizer`] for
+//! concrete requests. Scope constraints become direct comparisons on the
+//! request, and `when`/`unless` conditions become straight-line Rust code
+//! calling the operators in [`runtime`]. Constants (strings, entity uids,
+//! patterns, ...) are built once, on first use.
+//!
+//! Since policies are compiled into the binary, the generated module must be
+//! regenerated and the binary rebuilt whenever the policies change.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use crate::ast::{
+    ActionConstraint, Effect, EntityReference, EntityType, EntityUID, Expr, ExprKind, Literal,
+    Name, Pattern, PatternElem, Policy, PolicyID, PolicySet, PrincipalOrResourceConstraint, SlotId,
+    UnaryOp, Var,
+};
+use crate::ast::{BinaryOp, Request};
+use crate::authorizer::{AuthorizationError, Authorizer, Response};
+use crate::entities::Entities;
+use crate::extensions::Extensions;
+
+pub mod runtime;
+
+/// Options controlling code generation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// Path of the runtime module the generated code uses, e.g.,
+    /// `::cedar_policy_core::codegen::runtime`
+    pub runtime_path: String,
+    /// Name of the generated authorization function
+    pub function_name: String,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            runtime_path: "::cedar_policy_core::codegen::runtime".into(),
+            function_name: "is_authorized".into(),
+        }
+    }
+}
+
+/// Errors which can occur when compiling a policy set to Rust
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+pub enum CodegenError {
+    /// The policy contains an `unknown`, which compiled code cannot evaluate
+    #[error("policy `{policy_id}` contains an unknown, which cannot be compiled")]
+    Unknown {
+        /// Id of the offending policy
+        policy_id: PolicyID,
+    },
+    /// The policy contains a slot which is not filled
+    #[error("policy `{policy_id}` contains an unfilled slot `{slot}`")]
+    UnlinkedSlot {
+        /// Id of the offending policy
+        policy_id: PolicyID,
+        /// The unfilled slot
+        slot: SlotId,
+    },
+    /// The policy calls an extension function which is not available
+    #[error("policy `{policy_id}` calls unknown extension function `{name}`")]
+    UnknownExtensionFunction {
+        /// Id of the offending policy
+        policy_id: PolicyID,
+        /// Name of the function
+        name: Name,
+    },
+    /// The requested function name is not a Rust identifier
+    #[error("`{0}` is not a valid Rust function name")]
+    #[diagnostic(help("function names must start with a letter or `_` and contain only ASCII alphanumerics and `_`"))]
+    InvalidFunctionName(String),
+}
+
+/// Compile `pset` into the source of a Rust module.
+///
+/// The module defines `pub fn <function_name>(request: &Request, entities:
+/// &Entities) -> Response`, where the types are those of the runtime module
+/// named in `options`, and `pub const POLICY_IDS: &[&str]` listing the ids of
+/// the compiled policies. For concrete requests, the function returns the same
+/// decision, determining policies, and erroring policies as
+/// [`crate::authorizer::Authorizer::is_authorized`] does (error messages may
+/// lack source locations). Requests containing unknowns produce errors for
+/// every policy which depends on the unknown part of the request.
+pub fn compile_policy_set(
+    pset: &PolicySet,
+    options: &CodegenOptions,
+) -> Result<String, CodegenError> {
+    if !is_rust_identifier(&options.function_name) {
+        return Err(CodegenError::InvalidFunctionName(
+            options.function_name.clone(),
+        ));
+    }
+    let mut gen = Generator::default();
+    let policies = pset.policies().sorted_by(|p1, p2| p1.id().cmp(p2.id()));
+    let bodies = policies
+        .map(|p| gen.policy(p).map(|body| (p, body)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(gen.finish(&bodies, options))
+}
+
+/// A difference between the response computed by compiled policies and the
+/// response computed by the interpreter
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+#[error("compiled policies disagree with the interpreter on the {aspect}: compiled code gave {compiled}, interpreter gave {interpreted}")]
+pub struct EquivalenceMismatch {
+    /// Which aspect of the response differs
+    pub aspect: &'static str,
+    /// The compiled code's answer
+    pub compiled: String,
+    /// The interpreter's answer
+    pub interpreted: String,
+}
+
+/// Check that `compiled`, the response of code generated from `pset` for
+/// `request`, is the same as the response of the interpreter: the decision,
+/// the determining policies, and the set of policies which produced errors
+/// must all agree. Error messages are not compared.
+///
+/// This is intended for testing generated code against a corpus of requests.
+pub fn check_equivalence(
+    pset: &PolicySet,
+    request: &Request,
+    entities: &Entities,
+    compiled: &Response,
+) -> Result<(), EquivalenceMismatch> {
+    fn ids<'a>(ids: impl Iterator<Item = &'a PolicyID>) -> String {
+        format!("[{}]", ids.sorted().join(", "))
+    }
+    fn error_ids(response: &Response) -> String {
+        ids(response.diagnostics.errors.iter().map(|e| match e {
+            AuthorizationError::PolicyEvaluationError { id, .. } => id,
+        }))
+    }
+    let interpreted = Authorizer::new().is_authorized(request.clone(), pset, entities);
+    let aspects = [
+        (
+            "decision",
+            format!("{:?}", compiled.decision),
+            format!("{:?}", interpreted.decision),
+        ),
+        (
+            "determining policies",
+            ids(compiled.diagnostics.reason.iter()),
+            ids(interpreted.diagnostics.reason.iter()),
+        ),
+        (
+            "erroring policies",
+            error_ids(compiled),
+            error_ids(&interpreted),
+        ),
+    ];
+    for (aspect, compiled, interpreted) in aspects {
+        if compiled != interpreted {
+            return Err(EquivalenceMismatch {
+                aspect,
+                compiled,
+                interpreted,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Is `s` usable as a (non-keyword) Rust identifier?
+fn is_rust_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && s != "_"
+        && !RUST_KEYWORDS.contains(&s)
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// A constant used by the generated code, built once on first use
+struct Constant {
+    /// Rust type of the constant
+    ty: &'static str,
+    /// Rust expression initializing the constant
+    init: String,
+}
+
+/// State for generating the code of one policy set
+#[derive(Default)]
+struct Generator {
+    /// Constants used by the generated code, in order of first use
+    constants: Vec<Constant>,
+    /// Index of each constant in `constants`, by initializer
+    constant_indices: HashMap<String, usize>,
+}
+
+impl Generator {
+    /// Get the name of the (nullary) function returning the constant with the
+    /// given type and initializer, adding the constant if it is new
+    fn constant(&mut self, ty: &'static str, init: String) -> String {
+        let idx = match self.constant_indices.get(&init) {
+            Some(idx) => *idx,
+            None => {
+                let idx = self.constants.len();
+                self.constant_indices.insert(init.clone(), idx);
+                self.constants.push(Constant { ty, init });
+                idx
+            }
+        };
+        format!("const_{idx}()")
+    }
+
+    fn uid_constant(&mut self, uid: &EntityUID) -> String {
+        self.constant(
+            "rt::EntityUID",
+            format!(
+                "rt::entity_uid({:?}, {:?})",
+                uid.entity_type().to_string(),
+                <crate::ast::Eid as AsRef<str>>::as_ref(uid.eid())
+            ),
+        )
+    }
+
+    fn uid_value_constant(&mut self, uid: &EntityUID) -> String {
+        let c = self.uid_constant(uid);
+        self.constant("rt::Value", format!("rt::Value::from({c}.clone())"))
+    }
+
+    fn entity_type_constant(&mut self, ty: &EntityType) -> String {
+        self.constant(
+            "rt::EntityType",
+            format!("rt::entity_type({:?})", ty.to_string()),
+        )
+    }
+
+    fn attr_constant(&mut self, attr: &SmolStr) -> String {
+        self.constant("rt::SmolStr", format!("rt::attr({:?})", attr.as_str()))
+    }
+
+    /// Generate the body of the function evaluating the given policy
+    fn policy(&mut self, p: &Policy) -> Result<String, CodegenError> {
+        let mut body = String::new();
+        self.principal_or_resource_scope(
+            &mut body,
+            "principal",
+            p.principal_constraint().as_inner(),
+            SlotId::principal(),
+            p.id(),
+        )?;
+        match p.action_constraint() {
+            ActionConstraint::Any => {}
+            ActionConstraint::Eq(uid) => {
+                let c = self.uid_constant(uid);
+                let _ = writeln!(
+                    body,
+                    "    if env.action()? != {c} {{\n        return Ok(false);\n    }}"
+                );
+            }
+            ActionConstraint::In(uids) => {
+                let cs = uids
+                    .iter()
+                    .map(|uid| format!("{}.clone()", self.uid_constant(uid)))
+                    .join(", ");
+                let c = self.constant("Vec<rt::EntityUID>", format!("vec![{cs}]"));
+                let _ = writeln!(
+                    body,
+                    "    if !env.is_descendant_of(env.action()?, {c}) {{\n        return Ok(false);\n    }}"
+                );
+            }
+        }
+        self.principal_or_resource_scope(
+            &mut body,
+            "resource",
+            p.resource_constraint().as_inner(),
+            SlotId::resource(),
+            p.id(),
+        )?;
+        let cond = p.non_scope_constraints();
+        match cond.expr_kind() {
+            ExprKind::Lit(Literal::Bool(true)) => {
+                let _ = writeln!(body, "    Ok(true)");
+            }
+            _ => {
+                let cond = self.expr(cond, p)?;
+                let _ = writeln!(body, "    rt::as_bool(&{cond})");
+            }
+        }
+        Ok(body)
+    }
+
+    /// Generate code returning early from the policy function if the
+    /// principal or resource constraint is not satisfied
+    fn principal_or_resource_scope(
+        &mut self,
+        body: &mut String,
+        var: &str,
+        constraint: &PrincipalOrResourceConstraint,
+        slot: SlotId,
+        policy_id: &PolicyID,
+    ) -> Result<(), CodegenError> {
+        let uid = |gen: &mut Self, r: &EntityReference| match r {
+            EntityReference::EUID(uid) => Ok(gen.uid_constant(uid)),
+            EntityReference::Slot => Err(CodegenError::UnlinkedSlot {
+                policy_id: policy_id.clone(),
+                slot,
+            }),
+        };
+        let ret = "{\n        return Ok(false);\n    }";
+        match constraint {
+            PrincipalOrResourceConstraint::Any => {}
+            PrincipalOrResourceConstraint::Eq(r) => {
+                let c = uid(self, r)?;
+                let _ = writeln!(body, "    if env.{var}()? != {c} {ret}");
+            }
+            PrincipalOrResourceConstraint::In(r) => {
+                let c = uid(self, r)?;
+                let _ = writeln!(
+                    body,
+                    "    if !env.is_descendant_of(env.{var}()?, std::slice::from_ref({c})) {ret}"
+                );
+            }
+            PrincipalOrResourceConstraint::Is(ty) => {
+                let t = self.entity_type_constant(ty);
+                let _ = writeln!(body, "    if env.{var}()?.entity_type() != {t} {ret}");
+            }
+            PrincipalOrResourceConstraint::IsIn(ty, r) => {
+                let t = self.entity_type_constant(ty);
+                let c = uid(self, r)?;
+                let _ = writeln!(
+                    body,
+                    "    if env.{var}()?.entity_type() != {t}\n        || !env.is_descendant_of(env.{var}()?, std::slice::from_ref({c}))\n    {ret}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Generate a Rust expression of type `rt::Value` computing the value of
+    /// `e`. The expression may use `?` to propagate evaluation errors.
+    fn expr(&mut self, e: &Expr, p: &Policy) -> Result<String, CodegenError> {
+        Ok(match e.expr_kind() {
+            ExprKind::Lit(Literal::Bool(b)) => format!("rt::Value::from({b})"),
+            ExprKind::Lit(Literal::Long(i)) => format!("rt::Value::from({i}_i64)"),
+            ExprKind::Lit(Literal::String(s)) => {
+                let c = self.constant("rt::Value", format!("rt::Value::from({:?})", s.as_str()));
+                format!("{c}.clone()")
+            }
+            ExprKind::Lit(Literal::EntityUID(uid)) => {
+                let c = self.uid_value_constant(uid);
+                format!("{c}.clone()")
+            }
+            ExprKind::Lit(Literal::Null) => "rt::null()".to_string(),
+            ExprKind::Slot(slot) => match p.env().get(slot) {
+                Some(uid) => {
+                    let c = self.uid_value_constant(uid);
+                    format!("{c}.clone()")
+                }
+                None => {
+                    return Err(CodegenError::UnlinkedSlot {
+                        policy_id: p.id().clone(),
+                        slot: *slot,
+                    })
+                }
+            },
+            ExprKind::Var(Var::Principal) => "env.principal_value()?".into(),
+            ExprKind::Var(Var::Action) => "env.action_value()?".into(),
+            ExprKind::Var(Var::Resource) => "env.resource_value()?".into(),
+            ExprKind::Var(Var::Context) => "env.context()?".into(),
+            ExprKind::Unknown(_) => {
+                return Err(CodegenError::Unknown {
+                    policy_id: p.id().clone(),
+                })
+            }
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => format!(
+                "if rt::as_bool(&{})? {{ {} }} else {{ {} }}",
+                self.expr(test_expr, p)?,
+                self.expr(then_expr, p)?,
+                self.expr(else_expr, p)?
+            ),
+            ExprKind::And { left, right } => format!(
+                "rt::Value::from(rt::as_bool(&{})? && rt::as_bool(&{})?)",
+                self.expr(left, p)?,
+                self.expr(right, p)?
+            ),
+            ExprKind::Or { left, right } => format!(
+                "rt::Value::from(rt::as_bool(&{})? || rt::as_bool(&{})?)",
+                self.expr(left, p)?,
+                self.expr(right, p)?
+            ),
+            ExprKind::UnaryApp { op, arg } => {
+                let op = match op {
+                    UnaryOp::Not => "Not",
+                    UnaryOp::Neg => "Neg",
+                };
+                format!("rt::unary_app(rt::UnaryOp::{op}, {})?", self.expr(arg, p)?)
+            }
+            ExprKind::BinaryApp { op, arg1, arg2 } => {
+                let op = match op {
+                    BinaryOp::Eq => "Eq",
+                    BinaryOp::Less => "Less",
+                    BinaryOp::LessEq => "LessEq",
+                    BinaryOp::Add => "Add",
+                    BinaryOp::Sub => "Sub",
+                    BinaryOp::Mul => "Mul",
+                    BinaryOp::In => "In",
+                    BinaryOp::Contains => "Contains",
+                    BinaryOp::ContainsAll => "ContainsAll",
+                    BinaryOp::ContainsAny => "ContainsAny",
+                };
+                format!(
+                    "env.binary_app(rt::BinaryOp::{op}, {}, {})?",
+                    self.expr(arg1, p)?,
+                    self.expr(arg2, p)?
+                )
+            }
+            ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                if Extensions::all_available().func(fn_name).is_err() {
+                    return Err(CodegenError::UnknownExtensionFunction {
+                        policy_id: p.id().clone(),
+                        name: fn_name.clone(),
+                    });
+                }
+                let f = self.constant("rt::Name", format!("rt::name({:?})", fn_name.to_string()));
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg, p))
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("rt::call_extension_fn({f}, &[{}])?", args.join(", "))
+            }
+            ExprKind::GetAttr { expr, attr } => {
+                let a = self.attr_constant(attr);
+                format!("env.get_attr({}, {a})?", self.expr(expr, p)?)
+            }
+            ExprKind::HasAttr { expr, attr } => {
+                let a = self.attr_constant(attr);
+                format!("env.has_attr({}, {a})?", self.expr(expr, p)?)
+            }
+            ExprKind::Like { expr, pattern } => {
+                let c = self.constant("rt::Pattern", pattern_init(pattern));
+                format!("rt::like(&{}, {c})?", self.expr(expr, p)?)
+            }
+            ExprKind::Is { expr, entity_type } => {
+                let t = self.entity_type_constant(entity_type);
+                format!("rt::is_entity_type(&{}, {t})?", self.expr(expr, p)?)
+            }
+            ExprKind::Set(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.expr(item, p))
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("rt::set(vec![{}])", items.join(", "))
+            }
+            ExprKind::Record(map) => {
+                let fields = map
+                    .iter()
+                    .map(|(k, v)| {
+                        let a = self.attr_constant(k);
+                        Ok(format!("({a}.clone(), {})", self.expr(v, p)?))
+                    })
+                    .collect::<Result<Vec<_>, CodegenError>>()?;
+                format!("rt::record(vec![{}])", fields.join(", "))
+            }
+        })
+    }
+
+    /// Assemble the generated module
+    fn finish(self, policies: &[(&Policy, String)], options: &CodegenOptions) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "// This file was generated from a Cedar policy set. Do not edit it by hand;\n\
+             // regenerate it whenever the policies change.\n\n\
+             use {} as rt;\n",
+            options.runtime_path
+        );
+        let _ = writeln!(
+            out,
+            "/// Ids of the compiled policies\npub const POLICY_IDS: &[&str] = &[{}];\n",
+            policies
+                .iter()
+                .map(|(p, _)| format!("{:?}", p.id().as_ref()))
+                .join(", ")
+        );
+        let _ = writeln!(
+            out,
+            "/// Authorize `request` against the compiled policies\n\
+             pub fn {}(request: &rt::Request, entities: &rt::Entities) -> rt::Response {{\n    \
+             let env = rt::env(request, entities);\n    \
+             let mut decisions = rt::Decisions::default();",
+            options.function_name
+        );
+        for (i, (p, _)) in policies.iter().enumerate() {
+            let effect = match p.effect() {
+                Effect::Permit => "Permit",
+                Effect::Forbid => "Forbid",
+            };
+            let _ = writeln!(
+                out,
+                "    decisions.record(POLICY_IDS[{i}], rt::Effect::{effect}, policy_{i}(&env));"
+            );
+        }
+        let _ = writeln!(out, "    rt::response(decisions.finish())\n}}");
+        for (i, (p, body)) in policies.iter().enumerate() {
+            let _ = write!(
+                out,
+                "\n/// Policy `{}`\nfn policy_{i}(env: &rt::Env<'_>) -> rt::Result<bool> {{\n{body}}}\n",
+                p.id()
+            );
+        }
+        for (i, Constant { ty, init }) in self.constants.iter().enumerate() {
+            let _ = write!(
+                out,
+                "\nfn const_{i}() -> &'static {ty} {{\n    \
+                 static CELL: std::sync::OnceLock<{ty}> = std::sync::OnceLock::new();\n    \
+                 CELL.get_or_init(|| {init})\n}}\n"
+            );
+        }
+        out
+    }
+}
+
+/// Rust expression building `pattern`
+fn pattern_init(pattern: &Pattern) -> String {
+    let elems = pattern
+        .iter()
+        .map(|elem| match elem {
+            PatternElem::Char(c) => format!("rt::PatternElem::Char({c:?})"),
+            PatternElem::Wildcard => "rt::PatternElem::Wildcard".into(),
+        })
+        .join(", ");
+    format!("rt::Pattern::new([{elems}])")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_policyset;
+    use cool_asserts::assert_matches;
+
+    fn compile(src: &str) -> Result<String, CodegenError> {
+        compile_policy_set(&parse_policyset(src).unwrap(), &CodegenOptions::default())
+    }
+
+    #[test]
+    fn scope_and_conditions() {
+        let code = compile(
+            r#"
+            permit(principal == User::"alice", action in [Action::"view", Action::"edit"], resource is Photo in Album::"trip")
+            when { resource.owner == principal && context.hour < 18 };
+            forbid(principal, action, resource) unless { principal has name && principal.name like "a*" };
+            "#,
+        )
+        .unwrap();
+        assert!(code.contains("use ::cedar_policy_core::codegen::runtime as rt;"));
+        assert!(code.contains(r#"pub const POLICY_IDS: &[&str] = &["policy0", "policy1"];"#));
+        assert!(code.contains(
+            "pub fn is_authorized(request: &rt::Request, entities: &rt::Entities) -> rt::Response"
+        ));
+        assert!(code.contains("rt::Effect::Permit, policy_0(&env)"));
+        assert!(code.contains("rt::Effect::Forbid, policy_1(&env)"));
+        assert!(code.contains(r#"rt::entity_uid("User", "alice")"#));
+        assert!(code.contains(r#"rt::entity_type("Photo")"#));
+        assert!(code.contains("rt::BinaryOp::Less"));
+        assert!(code.contains("rt::PatternElem::Wildcard"));
+        // constants are shared between uses
+        assert_eq!(code.matches(r#"rt::attr("name")"#).count(), 1);
+    }
+
+    #[test]
+    fn linked_templates() {
+        let mut pset =
+            parse_policyset(r#"@id("t") permit(principal in ?principal, action, resource);"#)
+                .unwrap();
+        pset.link(
+            PolicyID::from_string("policy0"),
+            PolicyID::from_string("link"),
+            HashMap::from([(SlotId::principal(), r#"Group::"admins""#.parse().unwrap())]),
+        )
+        .unwrap();
+        let code = compile_policy_set(&pset, &CodegenOptions::default()).unwrap();
+        assert!(code.contains(r#"POLICY_IDS: &[&str] = &["link"]"#));
+        assert!(code.contains(r#"rt::entity_uid("Group", "admins")"#));
+    }
+
+    /// What the generator emits for
+    /// `permit(principal in Group::"admins", action, resource) when { resource.public || context.override };`
+    fn handwritten_is_authorized(request: &Request, entities: &Entities) -> Response {
+        use runtime as rt;
+        fn policy_0(env: &rt::Env<'_>) -> rt::Result<bool> {
+            if !env.is_descendant_of(
+                env.principal()?,
+                std::slice::from_ref(&rt::entity_uid("Group", "admins")),
+            ) {
+                return Ok(false);
+            }
+            rt::as_bool(&rt::Value::from(
+                rt::as_bool(&env.get_attr(env.resource_value()?, &rt::attr("public"))?)?
+                    || rt::as_bool(&env.get_attr(env.context()?, &rt::attr("override"))?)?,
+            ))
+        }
+        let env = rt::env(request, entities);
+        let mut decisions = rt::Decisions::default();
+        decisions.record("policy0", rt::Effect::Permit, policy_0(&env));
+        rt::response(decisions.finish())
+    }
+
+    #[test]
+    fn runtime_matches_interpreter() {
+        use crate::ast::{Context, RequestSchemaAllPass};
+        use crate::entities::{EntityJsonParser, NoEntitiesSchema, TCComputation};
+
+        let pset = parse_policyset(
+            r#"permit(principal in Group::"admins", action, resource) when { resource.public || context.override };"#,
+        )
+        .unwrap();
+        let code = compile_policy_set(&pset, &CodegenOptions::default()).unwrap();
+        assert!(code.contains(r#"rt::entity_uid("Group", "admins")"#));
+        assert!(code.contains(r#"rt::attr("override")"#));
+
+        let entities = EntityJsonParser::new(
+            None::<&NoEntitiesSchema>,
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Group", "id": "admins" }] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Doc", "id": "public" }, "attrs": { "public": true }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "private" }, "attrs": { "public": false }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "broken" }, "attrs": {}, "parents": [] }
+            ]"#,
+        )
+        .unwrap();
+        for principal in [r#"User::"alice""#, r#"User::"bob""#, r#"User::"unknown""#] {
+            for resource in [r#"Doc::"public""#, r#"Doc::"private""#, r#"Doc::"broken""#] {
+                for context in [r#"{}"#, r#"{ "override": true }"#, r#"{ "override": 1 }"#] {
+                    let request = Request::new(
+                        (principal.parse().unwrap(), None),
+                        (r#"Action::"view""#.parse().unwrap(), None),
+                        (resource.parse().unwrap(), None),
+                        Context::from_json_str(context).unwrap(),
+                        None::<&RequestSchemaAllPass>,
+                        Extensions::all_available(),
+                    )
+                    .unwrap();
+                    let compiled = handwritten_is_authorized(&request, &entities);
+                    check_equivalence(&pset, &request, &entities, &compiled).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut pset = PolicySet::new();
+        pset.add(Policy::from_when_clause(
+            Effect::Permit,
+            Expr::call_extension_fn("frobnicate".parse().unwrap(), vec![]),
+            PolicyID::from_string("p"),
+            None,
+        ))
+        .unwrap();
+        assert_matches!(
+            compile_policy_set(&pset, &CodegenOptions::default()),
+            Err(CodegenError::UnknownExtensionFunction { .. })
+        );
+        assert_matches!(
+            compile_policy_set(
+                &PolicySet::new(),
+                &CodegenOptions {
+                    function_name: "fn".into(),
+                    ..Default::default()
+                }
+            ),
+            Err(CodegenError::InvalidFunctionName(_))
+        );
+    }
+}
@@ -0,0 +1,268 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Runtime support for code generated by [`super::compile_policy_set()`].
+//!
+//! The operators here share their implementation with the evaluator, so
+//! compiled policies behave exactly like interpreted ones. This module is not
+//! intended to be used directly.
+
+use std::collections::HashSet;
+
+use crate::ast::{Literal, PartialValue, PolicyID, Unknown, Var};
+use crate::authorizer::{AuthorizationError, Decision};
+use crate::entities::Dereference;
+use crate::evaluator;
+use crate::extensions::Extensions;
+use crate::FromNormalizedStr;
+
+pub use crate::ast::{
+    BinaryOp, Effect, EntityType, EntityUID, Name, Pattern, PatternElem, Request, UnaryOp, Value,
+};
+pub use crate::authorizer::Response;
+pub use crate::entities::Entities;
+pub use crate::evaluator::EvaluationError;
+pub use smol_str::SmolStr;
+
+/// Result of evaluating part of a compiled policy
+pub type Result<T> = std::result::Result<T, EvaluationError>;
+
+/// Create the evaluation environment for authorizing `request`
+pub fn env<'a>(request: &Request, entities: &'a Entities) -> Env<'a> {
+    Env::new(request, entities)
+}
+
+/// Convert the response computed by [`Decisions::finish()`] into the response
+/// type of the generated code
+pub fn response(response: Response) -> Response {
+    response
+}
+
+/// Evaluation environment for compiled policies: the request and entities
+#[derive(Debug)]
+pub struct Env<'a> {
+    principal: PartialValue,
+    action: PartialValue,
+    resource: PartialValue,
+    context: PartialValue,
+    entities: &'a Entities,
+}
+
+impl<'a> Env<'a> {
+    /// Create the environment for authorizing `request`
+    pub fn new(request: &Request, entities: &'a Entities) -> Self {
+        Self {
+            principal: request.principal().evaluate(Var::Principal),
+            action: request.action().evaluate(Var::Action),
+            resource: request.resource().evaluate(Var::Resource),
+            context: match request.context() {
+                None => PartialValue::unknown(Unknown::new_untyped("context")),
+                Some(ctx) => ctx.clone().into(),
+            },
+            entities,
+        }
+    }
+
+    /// The principal of the request
+    pub fn principal(&self) -> Result<&EntityUID> {
+        as_value(&self.principal)?.get_as_entity()
+    }
+
+    /// The action of the request
+    pub fn action(&self) -> Result<&EntityUID> {
+        as_value(&self.action)?.get_as_entity()
+    }
+
+    /// The resource of the request
+    pub fn resource(&self) -> Result<&EntityUID> {
+        as_value(&self.resource)?.get_as_entity()
+    }
+
+    /// The principal of the request, as a `Value`
+    pub fn principal_value(&self) -> Result<Value> {
+        as_value(&self.principal).cloned()
+    }
+
+    /// The action of the request, as a `Value`
+    pub fn action_value(&self) -> Result<Value> {
+        as_value(&self.action).cloned()
+    }
+
+    /// The resource of the request, as a `Value`
+    pub fn resource_value(&self) -> Result<Value> {
+        as_value(&self.resource).cloned()
+    }
+
+    /// The context of the request
+    pub fn context(&self) -> Result<Value> {
+        as_value(&self.context).cloned()
+    }
+
+    /// Is `uid` equal to, or a descendant of, any of `ancestors`? This is the
+    /// semantics of the `in` scope constraints.
+    pub fn is_descendant_of(&self, uid: &EntityUID, ancestors: &[EntityUID]) -> bool {
+        let entity = match self.entities.entity(uid) {
+            Dereference::Data(entity) => Some(entity),
+            Dereference::NoSuchEntity | Dereference::Residual(_) => None,
+        };
+        ancestors.iter().any(|ancestor| {
+            uid == ancestor
+                || entity
+                    .map(|e| self.entities.is_descendant_of(e, ancestor))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Apply a binary operator
+    pub fn binary_app(&self, op: BinaryOp, arg1: Value, arg2: Value) -> Result<Value> {
+        into_value(evaluator::binary_app(
+            op,
+            arg1,
+            arg2,
+            self.entities,
+            evaluator::OverflowMode::Error,
+            None,
+        )?)
+    }
+
+    /// Get an attribute of a record or entity
+    pub fn get_attr(&self, val: Value, attr: &SmolStr) -> Result<Value> {
+        into_value(evaluator::get_attr(val, attr, self.entities, None)?)
+    }
+
+    /// Test whether a record or entity has an attribute
+    pub fn has_attr(&self, val: Value, attr: &SmolStr) -> Result<Value> {
+        into_value(evaluator::has_attr(val, attr, self.entities)?)
+    }
+}
+
+fn as_value(val: &PartialValue) -> Result<&Value> {
+    match val {
+        PartialValue::Value(v) => Ok(v),
+        PartialValue::Residual(r) => Err(EvaluationError::non_value(r.clone())),
+    }
+}
+
+fn into_value(val: PartialValue) -> Result<Value> {
+    match val {
+        PartialValue::Value(v) => Ok(v),
+        PartialValue::Residual(r) => Err(EvaluationError::non_value(r)),
+    }
+}
+
+/// Convert a value to a boolean, or return a type error
+pub fn as_bool(val: &Value) -> Result<bool> {
+    val.get_as_bool()
+}
+
+/// Apply a unary operator
+pub fn unary_app(op: UnaryOp, arg: Value) -> Result<Value> {
+    evaluator::unary_app(op, arg, evaluator::OverflowMode::Error, None)
+}
+
+/// Evaluate `val like pattern`
+pub fn like(val: &Value, pattern: &Pattern) -> Result<Value> {
+    Ok(pattern.wildcard_match(val.get_as_string()?).into())
+}
+
+/// Evaluate `val is entity_type`
+pub fn is_entity_type(val: &Value, entity_type: &EntityType) -> Result<Value> {
+    Ok((val.get_as_entity()?.entity_type() == entity_type).into())
+}
+
+/// Build the `null` value
+pub fn null() -> Value {
+    Value::from(Literal::Null)
+}
+
+/// Build a set value
+pub fn set(items: Vec<Value>) -> Value {
+    Value::set(items, None)
+}
+
+/// Build a record value
+pub fn record(fields: Vec<(SmolStr, Value)>) -> Value {
+    Value::record(fields, None)
+}
+
+/// Call an extension function
+pub fn call_extension_fn(name: &Name, args: &[Value]) -> Result<Value> {
+    into_value(Extensions::all_available().func(name)?.call(args)?)
+}
+
+/// Construct an entity uid. `ty` must be a normalized entity type name, as
+/// emitted by the code generator.
+pub fn entity_uid(ty: &str, eid: &str) -> EntityUID {
+    EntityUID::from_components(entity_type(ty), crate::ast::Eid::new(eid), None)
+}
+
+/// Construct an entity type. `ty` must be a normalized entity type name, as
+/// emitted by the code generator.
+pub fn entity_type(ty: &str) -> EntityType {
+    name(ty).into()
+}
+
+/// Construct a name. `name` must be a normalized name, as emitted by the code
+/// generator.
+// PANIC SAFETY: the code generator only emits names obtained by displaying a valid `Name`
+#[allow(clippy::expect_used)]
+pub fn name(name: &str) -> Name {
+    Name::from_normalized_str(name).expect("generated code should only contain valid names")
+}
+
+/// Construct an attribute name
+pub fn attr(attr: &str) -> SmolStr {
+    SmolStr::new(attr)
+}
+
+/// Collects the results of evaluating each compiled policy and computes the
+/// authorization response, like [`crate::authorizer::Authorizer`] does
+#[derive(Debug, Default)]
+pub struct Decisions {
+    satisfied_permits: HashSet<PolicyID>,
+    satisfied_forbids: HashSet<PolicyID>,
+    errors: Vec<AuthorizationError>,
+}
+
+impl Decisions {
+    /// Record the result of evaluating the policy with the given id and effect
+    pub fn record(&mut self, id: &str, effect: Effect, result: Result<bool>) {
+        match (result, effect) {
+            (Ok(true), Effect::Permit) => {
+                self.satisfied_permits.insert(PolicyID::from_string(id));
+            }
+            (Ok(true), Effect::Forbid) => {
+                self.satisfied_forbids.insert(PolicyID::from_string(id));
+            }
+            (Ok(false), _) => {}
+            (Err(error), _) => self.errors.push(AuthorizationError::PolicyEvaluationError {
+                id: PolicyID::from_string(id),
+                error,
+            }),
+        }
+    }
+
+    /// Compute the authorization response
+    pub fn finish(self) -> Response {
+        if !self.satisfied_forbids.is_empty() {
+            Response::new(Decision::Deny, self.satisfied_forbids, self.errors)
+        } else if !self.satisfied_permits.is_empty() {
+            Response::new(Decision::Allow, self.satisfied_permits, self.errors)
+        } else {
+            Response::new(Decision::Deny, HashSet::new(), self.errors)
+        }
+    }
+}
@@ -19,7 +19,8 @@
 use crate::ast::*;
 use crate::extensions::Extensions;
 use crate::transitive_closure::{compute_tc, enforce_tc_and_dag};
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use serde::Serialize;
@@ -57,8 +58,11 @@ pub struct Entities {
     ///
     /// Important internal invariant: for any `Entities` object that exists, the
     /// the `ancestor` relation is transitively closed.
-    #[serde_as(as = "Vec<(_, _)>")]
-    entities: HashMap<EntityUID, Entity>,
+    ///
+    /// The entities are shared between clones, and copied when a clone is
+    /// modified.
+    #[serde_as(as = "Arc<Vec<(_, _)>>")]
+    entities: Arc<HashMap<EntityUID, Entity>>,
 
     /// The mode flag determines whether this store functions as a partial store or
     /// as a fully concrete store.
@@ -68,14 +72,22 @@ pub struct Entities {
     #[serde(skip_deserializing)]
     #[serde(skip_serializing)]
     mode: Mode,
+
+    /// If `true`, the `ancestor` relation stored on each entity is _not_
+    /// transitively closed (it may hold only direct parents), and ancestor
+    /// queries walk parent pointers on demand, memoizing the result per entity.
+    /// See [`TCComputation::ComputeOnDemand`].
+    #[serde(skip)]
+    tc_on_demand: bool,
 }
 
 impl Entities {
     /// Create a fresh `Entities` with no entities
     pub fn new() -> Self {
         Self {
-            entities: HashMap::new(),
+            entities: Arc::default(),
             mode: Mode::default(),
+            tc_on_demand: false,
         }
     }
 
@@ -87,9 +99,16 @@ impl Entities {
         Self {
             entities: self.entities,
             mode: Mode::Partial,
+            tc_on_demand: self.tc_on_demand,
         }
     }
 
+    /// Does this store compute the TC on demand, i.e., was it built with
+    /// [`TCComputation::ComputeOnDemand`]?
+    pub fn computes_tc_on_demand(&self) -> bool {
+        self.tc_on_demand
+    }
+
     /// Get the `Entity` with the given UID, if any
     pub fn entity(&self, uid: &EntityUID) -> Dereference<'_, Entity> {
         match self.entities.get(uid) {
@@ -112,6 +131,60 @@ impl Entities {
         self.entities.values()
     }
 
+    /// Is `entity` (which should be an entity in this store) a descendant of
+    /// `ancestor` in the entity hierarchy?
+    ///
+    /// Unlike [`Entity::is_descendant_of`], this is correct regardless of the
+    /// [`TCComputation`] this store was built with.
+    pub fn is_descendant_of(&self, entity: &Entity, ancestor: &EntityUID) -> bool {
+        // direct parents (or, if TC was precomputed, all ancestors) are
+        // always stored on the entity itself
+        entity.is_descendant_of(ancestor)
+            || (self.tc_on_demand && self.closed_ancestors(entity).contains(ancestor))
+    }
+
+    /// Iterate over all ancestors (direct and transitive) of `entity` (which
+    /// should be an entity in this store).
+    ///
+    /// Unlike [`Entity::ancestors`], this is correct regardless of the
+    /// [`TCComputation`] this store was built with.
+    pub fn ancestors_of<'a>(&'a self, entity: &'a Entity) -> impl Iterator<Item = &'a EntityUID> {
+        self.closed_ancestors(entity).iter()
+    }
+
+    /// Get the transitively-closed ancestor set of `entity`, walking (and
+    /// memoizing) parent pointers if this store computes the TC on demand
+    fn closed_ancestors<'a>(&'a self, entity: &'a Entity) -> &'a Arc<HashSet<EntityUID>> {
+        if self.tc_on_demand {
+            entity.closed_ancestors_or_init(|| self.walk_ancestors(entity))
+        } else {
+            entity.ancestors_arc()
+        }
+    }
+
+    /// Compute the transitively-closed ancestor set of `entity` by walking
+    /// parent pointers. Entities whose closed ancestor set has already been
+    /// memoized are not walked again.
+    ///
+    /// This tolerates cycles (every entity in a cycle is its own ancestor) and
+    /// parents that are not present in the store.
+    fn walk_ancestors(&self, entity: &Entity) -> HashSet<EntityUID> {
+        let mut closed = HashSet::new();
+        let mut worklist: Vec<&EntityUID> = entity.ancestors().collect();
+        while let Some(uid) = worklist.pop() {
+            if !closed.insert(uid.clone()) {
+                continue;
+            }
+            if let Some(parent) = self.entities.get(uid) {
+                match parent.memoized_closed_ancestors() {
+                    Some(memoized) => closed.extend(memoized.iter().cloned()),
+                    None => worklist.extend(parent.ancestors()),
+                }
+            }
+        }
+        closed
+    }
+
     /// Adds the [`crate::ast::Entity`]s in the iterator to this [`Entities`].
     /// Fails if the passed iterator contains any duplicate entities with this structure,
     /// or if any error is encountered in the transitive closure computation.
@@ -131,11 +204,12 @@ impl Entities {
         extensions: &Extensions<'_>,
     ) -> Result<Self> {
         let checker = schema.map(|schema| EntitySchemaConformanceChecker::new(schema, extensions));
+        let entities = Arc::make_mut(&mut self.entities);
         for entity in collection.into_iter() {
             if let Some(checker) = checker.as_ref() {
                 checker.validate_entity(&entity)?;
             }
-            match self.entities.entry(entity.uid().clone()) {
+            match entities.entry(entity.uid().clone()) {
                 hash_map::Entry::Occupied(_) => {
                     return Err(EntitiesError::duplicate(entity.uid().clone()))
                 }
@@ -146,9 +220,20 @@ impl Entities {
         }
         match tc_computation {
             TCComputation::AssumeAlreadyComputed => (),
-            TCComputation::EnforceAlreadyComputed => enforce_tc_and_dag(&self.entities)?,
-            TCComputation::ComputeNow => compute_tc(&mut self.entities, true)?,
+            TCComputation::EnforceAlreadyComputed => enforce_tc_and_dag(entities)?,
+            TCComputation::ComputeNow => {
+                compute_tc(entities, true)?;
+                self.tc_on_demand = false;
+            }
+            TCComputation::ComputeOnDemand => self.tc_on_demand = true,
         };
+        if self.tc_on_demand {
+            // the new entities may add ancestors to existing ones
+            for entity in entities.values_mut() {
+                entity.clear_closed_ancestors();
+            }
+        }
+        share_ancestor_sets(entities);
         Ok(self)
     }
 
@@ -195,7 +280,9 @@ impl Entities {
             TCComputation::ComputeNow => {
                 compute_tc(&mut entity_map, true)?;
             }
+            TCComputation::ComputeOnDemand => {}
         }
+        share_ancestor_sets(&mut entity_map);
         // Now that TC has been enforced, we can check action entities for
         // conformance with the schema and add action entities to the store.
         // This is fine to do after TC because the action hierarchy in the
@@ -217,8 +304,9 @@ impl Entities {
             );
         }
         Ok(Self {
-            entities: entity_map,
+            entities: Arc::new(entity_map),
             mode: Mode::default(),
+            tc_on_demand: tc_computation == TCComputation::ComputeOnDemand,
         })
     }
 
@@ -335,13 +423,51 @@ fn create_entity_map(es: impl Iterator<Item = Entity>) -> Result<HashMap<EntityU
     Ok(map)
 }
 
+/// Make entities with identical ancestor sets share a single allocation.
+///
+/// Large entity stores commonly have many entities with exactly the same
+/// (transitively closed) group memberships, and storing each of those sets
+/// separately dominates memory. We bucket ancestor sets by their size and an
+/// order-independent fingerprint, and only compare sets for equality within a
+/// bucket.
+fn share_ancestor_sets(entities: &mut HashMap<EntityUID, Entity>) {
+    let mut interned: HashMap<(usize, u64), Vec<Arc<HashSet<EntityUID>>>> = HashMap::new();
+    for entity in entities.values_mut() {
+        let ancestors = Arc::clone(entity.ancestors_arc());
+        if ancestors.is_empty() {
+            continue;
+        }
+        let bucket = interned
+            .entry((ancestors.len(), ancestor_set_fingerprint(&ancestors)))
+            .or_default();
+        match bucket.iter().find(|shared| **shared == ancestors) {
+            Some(shared) => {
+                if !Arc::ptr_eq(shared, &ancestors) {
+                    entity.set_shared_ancestors(Arc::clone(shared));
+                }
+            }
+            None => bucket.push(ancestors),
+        }
+    }
+}
+
+/// Fingerprint of a set of `EntityUID`s which does not depend on iteration order
+fn ancestor_set_fingerprint(ancestors: &HashSet<EntityUID>) -> u64 {
+    ancestors.iter().fold(0u64, |acc, uid| {
+        // `DefaultHasher::new()` is deterministic, which is all we need here
+        let mut hasher = hash_map::DefaultHasher::new();
+        uid.hash(&mut hasher);
+        acc.wrapping_add(hasher.finish())
+    })
+}
+
 impl IntoIterator for Entities {
     type Item = Entity;
 
     type IntoIter = hash_map::IntoValues<EntityUID, Entity>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.entities.into_values()
+        Arc::unwrap_or_clone(self.entities).into_values()
     }
 }
 
@@ -440,6 +566,16 @@ pub enum TCComputation {
     /// This doesn't make any assumptions about the input, which can in fact
     /// contain just parent edges and not transitive ancestor edges. Also checks for cycles and returns an error if found.
     ComputeNow,
+    /// Don't compute the TC up front. Instead, hierarchy queries (e.g., `in`
+    /// during evaluation) walk parent pointers on demand, memoizing the
+    /// resulting ancestor set per queried entity.
+    ///
+    /// This trades some per-request CPU for much faster construction of large
+    /// entity stores in which most entities are never queried. Cycles are not
+    /// detected in this mode. Note that [`Entity::ancestors`] only returns
+    /// direct parents for stores built in this mode; use
+    /// [`Entities::ancestors_of`] to get all ancestors.
+    ComputeOnDemand,
 }
 
 // PANIC SAFETY: Unit Test Code
@@ -1442,6 +1578,8 @@ mod json_parsing_tests {
             ).build());
         });
 
+        // `null`s are allowed as attribute values, including nested in
+        // records and sets
         let json = serde_json::json!(
             [
             {
@@ -1451,11 +1589,7 @@ mod json_parsing_tests {
             }
             ]
         );
-        assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
-            expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
-            ).build());
-        });
+        assert_matches!(eparser.from_json_value(json), Ok(_));
 
         let json = serde_json::json!(
             [
@@ -1466,11 +1600,7 @@ mod json_parsing_tests {
             }
             ]
         );
-        assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
-            expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
-            ).build());
-        });
+        assert_matches!(eparser.from_json_value(json), Ok(_));
 
         let json = serde_json::json!(
             [
@@ -1481,9 +1611,32 @@ mod json_parsing_tests {
             }
             ]
         );
+        assert_matches!(eparser.from_json_value(json), Ok(_));
+
+        let json = serde_json::json!(
+            [
+            {
+                "uid": { "type": "foo", "id": "bar" },
+                "attrs": { "attr": [ 3, { "subattr" : null } ] },
+                "parents": [],
+            }
+            ]
+        );
+        assert_matches!(eparser.from_json_value(json), Ok(_));
+
+        // but not in `__extn` or `__entity` escapes
+        let json = serde_json::json!(
+            [
+            {
+                "uid": { "type": "foo", "id": "bar" },
+                "attrs": { "attr": { "__extn": { "fn": null, "args": [] } } },
+                "parents": [],
+            }
+            ]
+        );
         assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
             expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
+                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in entity references or extension values"#,
             ).build());
         });
 
@@ -1491,14 +1644,14 @@ mod json_parsing_tests {
             [
             {
                 "uid": { "type": "foo", "id": "bar" },
-                "attrs": { "attr": [ 3, { "subattr" : null } ] },
+                "attrs": { "attr": { "__extn": { "fn": "ip", "args": null } } },
                 "parents": [],
             }
             ]
         );
         assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
             expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
+                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in entity references or extension values"#,
             ).build());
         });
 
@@ -1506,14 +1659,14 @@ mod json_parsing_tests {
             [
             {
                 "uid": { "type": "foo", "id": "bar" },
-                "attrs": { "__extn": { "fn": null, "args": [] } },
+                "attrs": { "attr": { "__extn": { "fn": "ip", "args": [ null ] } } },
                 "parents": [],
             }
             ]
         );
         assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
             expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `__extn` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
+                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in entity references or extension values"#,
             ).build());
         });
 
@@ -1521,14 +1674,14 @@ mod json_parsing_tests {
             [
             {
                 "uid": { "type": "foo", "id": "bar" },
-                "attrs": { "__extn": { "fn": "ip", "args": null } },
+                "attrs": { "attr": { "__extn": { "fn": "ip", "arg": null } } },
                 "parents": [],
             }
             ]
         );
         assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
             expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `__extn` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
+                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in entity references or extension values"#,
             ).build());
         });
 
@@ -1536,14 +1689,14 @@ mod json_parsing_tests {
             [
             {
                 "uid": { "type": "foo", "id": "bar" },
-                "attrs": { "__extn": { "fn": "ip", "args": [ null ] } },
+                "attrs": { "attr": { "__entity": { "type": "foo", "id": null } } },
                 "parents": [],
             }
             ]
         );
         assert_matches!(eparser.from_json_value(json.clone()), Err(EntitiesError::Deserialization(e)) => {
             expect_err(&json, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
-                r#"in attribute `__extn` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in Cedar"#,
+                r#"in attribute `attr` on `foo::"bar"`, found a `null`; JSON `null`s are not allowed in entity references or extension values"#,
             ).build());
         });
 
@@ -1614,6 +1767,45 @@ mod json_parsing_tests {
         });
     }
 
+    /// Test that `null` is accepted as an attribute value, including nested
+    /// inside records and sets
+    #[test]
+    fn null_attrs() {
+        let eparser: EntityJsonParser<'_, '_> =
+            EntityJsonParser::new(None, Extensions::all_available(), TCComputation::ComputeNow);
+
+        let json = serde_json::json!(
+            [
+            {
+                "uid": { "type": "foo", "id": "bar" },
+                "attrs": {
+                    "attr": null,
+                    "rec": { "subattr": null },
+                    "set": [ 3, null ],
+                },
+                "parents": [],
+            }
+            ]
+        );
+        let entities = eparser
+            .from_json_value(json)
+            .expect("`null` attributes should be accepted");
+        let entity = entities.entity(&r#"foo::"bar""#.parse().unwrap()).unwrap();
+        assert_matches!(
+            entity.get("attr"),
+            Some(PartialValue::Value(Value {
+                value: ValueKind::Lit(Literal::Null),
+                ..
+            }))
+        );
+        assert_matches!(entity.get("rec"), Some(PartialValue::Value(_)));
+        assert_matches!(entity.get("set"), Some(PartialValue::Value(_)));
+
+        // `null` attributes survive a round trip through JSON
+        let roundtripped = roundtrip(&entities).expect("should roundtrip");
+        assert_eq!(entities, roundtripped);
+    }
+
     /// helper function to round-trip an Entities (with no schema-based parsing)
     fn roundtrip(entities: &Entities) -> Result<Entities> {
         let mut buf = Vec::new();
@@ -1884,6 +2076,32 @@ mod entities_tests {
         assert!(es_v.contains(&&e3));
     }
 
+    /// Clones share their entities until one of them is modified
+    #[test]
+    fn clones_share_entities() {
+        let (e0, e1, e2, e3) = test_entities();
+        let es = Entities::from_entities(
+            vec![e0, e1, e2],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        let clone = es.clone();
+        assert!(Arc::ptr_eq(&es.entities, &clone.entities));
+        let modified = clone
+            .add_entities(
+                [e3],
+                None::<&NoEntitiesSchema>,
+                TCComputation::ComputeNow,
+                Extensions::all_available(),
+            )
+            .expect("Failed to add entities");
+        assert!(!Arc::ptr_eq(&es.entities, &modified.entities));
+        assert_eq!(es.iter().count(), 3);
+        assert_eq!(modified.iter().count(), 4);
+    }
+
     #[test]
     fn test_enforce_already_computed_fail() {
         // Hierarchy
@@ -1929,6 +2147,118 @@ mod entities_tests {
         )
         .expect("Should have succeeded");
     }
+
+    #[test]
+    fn tc_on_demand() {
+        // Hierarchy
+        // a -> b -> c -> d
+        // e -> c
+        // only direct parents are given
+        let mut a = Entity::with_uid(EntityUID::with_eid("a"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("b"));
+        let mut c = Entity::with_uid(EntityUID::with_eid("c"));
+        let d = Entity::with_uid(EntityUID::with_eid("d"));
+        let mut e = Entity::with_uid(EntityUID::with_eid("e"));
+        a.add_ancestor(EntityUID::with_eid("b"));
+        b.add_ancestor(EntityUID::with_eid("c"));
+        c.add_ancestor(EntityUID::with_eid("d"));
+        e.add_ancestor(EntityUID::with_eid("c"));
+
+        let es = Entities::from_entities(
+            vec![a, b, c, d, e],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeOnDemand,
+            Extensions::all_available(),
+        )
+        .expect("Should have succeeded");
+        let get = |eid| es.entity(&EntityUID::with_eid(eid)).unwrap();
+        // the stored ancestors are just the parents
+        assert!(!get("a").is_descendant_of(&EntityUID::with_eid("d")));
+        // but queries through the store walk the hierarchy
+        assert!(es.is_descendant_of(get("a"), &EntityUID::with_eid("b")));
+        assert!(es.is_descendant_of(get("a"), &EntityUID::with_eid("d")));
+        assert!(es.is_descendant_of(get("e"), &EntityUID::with_eid("d")));
+        assert!(!es.is_descendant_of(get("e"), &EntityUID::with_eid("b")));
+        assert!(!es.is_descendant_of(get("d"), &EntityUID::with_eid("a")));
+        let mut ancestors = es.ancestors_of(get("a")).cloned().collect::<Vec<_>>();
+        ancestors.sort();
+        assert_eq!(
+            ancestors,
+            vec![
+                EntityUID::with_eid("b"),
+                EntityUID::with_eid("c"),
+                EntityUID::with_eid("d")
+            ]
+        );
+
+        // adding entities invalidates the memoized ancestor sets
+        let mut d2 = Entity::with_uid(EntityUID::with_eid("d2"));
+        d2.add_ancestor(EntityUID::with_eid("f"));
+        let mut g = Entity::with_uid(EntityUID::with_eid("g"));
+        g.add_ancestor(EntityUID::with_eid("d2"));
+        let mut es = es
+            .add_entities(
+                vec![g],
+                None::<&NoEntitiesSchema>,
+                TCComputation::ComputeOnDemand,
+                Extensions::all_available(),
+            )
+            .expect("Should have succeeded");
+        assert!(!es.is_descendant_of(
+            es.entity(&EntityUID::with_eid("g")).unwrap(),
+            &EntityUID::with_eid("f")
+        ));
+        es = es
+            .add_entities(
+                vec![d2],
+                None::<&NoEntitiesSchema>,
+                TCComputation::ComputeOnDemand,
+                Extensions::all_available(),
+            )
+            .expect("Should have succeeded");
+        assert!(es.is_descendant_of(
+            es.entity(&EntityUID::with_eid("g")).unwrap(),
+            &EntityUID::with_eid("f")
+        ));
+    }
+
+    #[test]
+    fn identical_ancestor_sets_are_shared() {
+        // Hierarchy
+        // a -> g1 -> g2
+        // b -> g1 -> g2
+        // c -> g2
+        let mut a = Entity::with_uid(EntityUID::with_eid("a"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("b"));
+        let mut c = Entity::with_uid(EntityUID::with_eid("c"));
+        let mut g1 = Entity::with_uid(EntityUID::with_eid("g1"));
+        let g2 = Entity::with_uid(EntityUID::with_eid("g2"));
+        a.add_ancestor(EntityUID::with_eid("g1"));
+        b.add_ancestor(EntityUID::with_eid("g1"));
+        c.add_ancestor(EntityUID::with_eid("g2"));
+        g1.add_ancestor(EntityUID::with_eid("g2"));
+
+        let es = Entities::from_entities(
+            vec![a, b, c, g1, g2],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Should have succeeded");
+        let get = |eid| es.entity(&EntityUID::with_eid(eid)).unwrap();
+        assert!(get("a").shares_ancestors_with(get("b")));
+        assert!(get("c").shares_ancestors_with(get("g1")));
+        assert!(!get("a").shares_ancestors_with(get("c")));
+        assert_eq!(get("a").ancestors_len(), 2);
+        assert_eq!(get("c").ancestors_len(), 1);
+
+        // adding a new ancestor to one entity must not affect the others
+        let mut a = get("a").clone();
+        let b = get("b");
+        a.add_ancestor(EntityUID::with_eid("c"));
+        assert!(a.is_descendant_of(&EntityUID::with_eid("c")));
+        assert!(!b.is_descendant_of(&EntityUID::with_eid("c")));
+    }
 }
 
 // PANIC SAFETY: Unit Test Code
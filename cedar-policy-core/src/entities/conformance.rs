@@ -196,7 +196,7 @@ pub fn typecheck_restricted_expr_against_schematype(
         // error <https://github.com/cedar-policy/cedar/issues/418>.
         ExprKind::Unknown(u) => match u.type_annotation.clone().and_then(SchemaType::from_ty) {
             Some(ty) => {
-                if &ty == expected_ty {
+                if expected_ty.includes(&ty) {
                     return Ok(());
                 } else {
                     return type_mismatch_err();
@@ -216,7 +216,7 @@ pub fn typecheck_restricted_expr_against_schematype(
                     Ok(())
                 }
                 Some(rty) => {
-                    if rty == expected_ty {
+                    if expected_ty.includes(rty) {
                         Ok(())
                     } else {
                         type_mismatch_err()
@@ -256,6 +256,13 @@ pub fn typecheck_restricted_expr_against_schematype(
                 type_mismatch_err()
             }
         }
+        Null => {
+            if expr.is_null() {
+                Ok(())
+            } else {
+                type_mismatch_err()
+            }
+        }
         EmptySet => {
             if expr.as_set_elements().is_some_and(|e| e.count() == 0) {
                 Ok(())
@@ -327,6 +334,15 @@ pub fn typecheck_restricted_expr_against_schematype(
             Some(actual_euid) if actual_euid.entity_type() == ty => Ok(()),
             _ => type_mismatch_err(),
         },
+        Union { arms } => {
+            if arms.iter().any(|arm| {
+                typecheck_restricted_expr_against_schematype(expr, arm, extensions).is_ok()
+            }) {
+                Ok(())
+            } else {
+                type_mismatch_err()
+            }
+        }
     }
 }
 
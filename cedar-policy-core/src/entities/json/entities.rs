@@ -19,19 +19,26 @@ use super::{
     CedarValueJson, EntityTypeDescription, EntityUidJson, NoEntitiesSchema, Schema, TypeAndId,
     ValueParser,
 };
-use crate::ast::{BorrowedRestrictedExpr, Entity, EntityUID, PartialValue, RestrictedExpr};
+use crate::ast::{
+    BorrowedRestrictedExpr, Context, Entity, EntityAttrEvaluationError, EntityUID, EntityUIDEntry,
+    Expr, PartialValue, Request, RestrictedExpr,
+};
 use crate::entities::conformance::EntitySchemaConformanceChecker;
 use crate::entities::{
     conformance::err::{EntitySchemaConformanceError, UnexpectedEntityTypeError},
     Entities, EntitiesError, TCComputation,
 };
+use crate::evaluator::{Evaluator, RestrictedEvaluator};
 use crate::extensions::Extensions;
 use crate::jsonvalue::JsonValueWithNoDuplicateKeys;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use smol_str::SmolStr;
 use std::sync::Arc;
-use std::{collections::HashMap, io::Read};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::Read,
+};
 
 #[cfg(feature = "wasm")]
 extern crate tsify;
@@ -292,7 +299,7 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
             }
         };
         let vparser = ValueParser::new(self.extensions);
-        let attrs: HashMap<SmolStr, RestrictedExpr> = ejson
+        let mut attrs: HashMap<SmolStr, RestrictedExpr> = ejson
             .attrs
             .into_iter()
             .map(|(k, v)| match &entity_schema_info {
@@ -358,7 +365,7 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                 Ok(()) // all parents are allowed
             }
         };
-        let parents = ejson
+        let mut parents: HashSet<EntityUID> = ejson
             .parents
             .into_iter()
             .map(|parent| {
@@ -373,8 +380,72 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                 })
             })
             .collect::<Result<_, JsonDeserializationError>>()?;
+        if let EntitySchemaInfo::NonAction(desc) = &entity_schema_info {
+            let computed: Vec<_> = desc.computed_attrs().collect();
+            if !computed.is_empty() {
+                self.compute_attrs(&uid, &mut attrs, computed)?;
+            }
+        }
+        // the values of parent attributes are implied parents
+        if let EntitySchemaInfo::NonAction(desc) = &entity_schema_info {
+            for attr in desc.parent_attrs() {
+                let Some(val) = attrs.get(&attr) else {
+                    continue;
+                };
+                match val.as_set_elements() {
+                    Some(elems) => parents.extend(elems.filter_map(|e| e.as_euid().cloned())),
+                    None => parents.extend(val.as_euid().cloned()),
+                }
+            }
+        }
         Ok(Entity::new(uid, attrs, parents, self.extensions)?)
     }
+
+    /// Add the computed attributes `computed` to the attributes `attrs` of the
+    /// entity `uid`, replacing the values given in the entity data, if any.
+    /// The expressions computing them are evaluated with the other attributes
+    /// as `context`.
+    fn compute_attrs(
+        &self,
+        uid: &EntityUID,
+        attrs: &mut HashMap<SmolStr, RestrictedExpr>,
+        computed: Vec<(SmolStr, &Expr)>,
+    ) -> Result<(), EntityAttrEvaluationError> {
+        let evaluator = RestrictedEvaluator::new(self.extensions);
+        let context = attrs
+            .iter()
+            .filter(|(attr, _)| !computed.iter().any(|(c, _)| c == *attr))
+            .map(|(attr, val)| {
+                let val = evaluator.interpret(val.as_borrowed()).map_err(|err| {
+                    EntityAttrEvaluationError {
+                        uid: uid.clone(),
+                        attr: attr.clone(),
+                        err,
+                    }
+                })?;
+                Ok((attr.clone(), val))
+            })
+            .collect::<Result<BTreeMap<_, _>, EntityAttrEvaluationError>>()?;
+        let request = Request::new_unchecked(
+            EntityUIDEntry::Unknown { loc: None },
+            EntityUIDEntry::Unknown { loc: None },
+            EntityUIDEntry::Unknown { loc: None },
+            Some(Context::Value(Arc::new(context))),
+        );
+        let entities = Entities::new();
+        let evaluator = Evaluator::new(request, &entities, self.extensions);
+        for (attr, expr) in computed {
+            let val = evaluator.interpret(expr, &HashMap::new()).map_err(|err| {
+                EntityAttrEvaluationError {
+                    uid: uid.clone(),
+                    attr: attr.clone(),
+                    err,
+                }
+            })?;
+            attrs.insert(attr, val.into());
+        }
+        Ok(())
+    }
 }
 
 impl EntityJson {
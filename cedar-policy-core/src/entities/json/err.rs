@@ -120,8 +120,10 @@ pub enum JsonDeserializationError {
     #[error("{0}, the `__expr` escape is no longer supported")]
     #[diagnostic(help("to create an entity reference, use `__entity`; to create an extension value, use `__extn`; and for all other values, use JSON directly"))]
     ExprTag(Box<JsonDeserializationErrorContext>),
-    /// Raised when the input JSON contains a `null`
-    #[error("{0}, found a `null`; JSON `null`s are not allowed in Cedar")]
+    /// Raised when the input JSON contains a `null` where `null` is not a
+    /// valid value: inside an `__entity` or `__extn` escape, or in place of
+    /// an entity reference
+    #[error("{0}, found a `null`; JSON `null`s are not allowed in entity references or extension values")]
     Null(Box<JsonDeserializationErrorContext>),
     /// Returned when a name contains `__cedar`
     #[error(transparent)]
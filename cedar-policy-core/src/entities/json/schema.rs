@@ -15,7 +15,7 @@
  */
 
 use super::SchemaType;
-use crate::ast::{Entity, EntityType, EntityUID};
+use crate::ast::{Entity, EntityType, EntityUID, Expr};
 use crate::entities::{Name, UnreservedId};
 use smol_str::SmolStr;
 use std::collections::{HashMap, HashSet};
@@ -128,6 +128,21 @@ pub trait EntityTypeDescription {
     /// Get the entity types which are allowed to be parents of this entity type.
     fn allowed_parent_types(&self) -> Arc<HashSet<EntityType>>;
 
+    /// Get the names of the attributes whose values, entities or sets of
+    /// entities, are parents of entities of this type, even if the entity data
+    /// doesn't list them as parents.
+    fn parent_attrs<'s>(&'s self) -> Box<dyn Iterator<Item = SmolStr> + 's> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Get the computed attributes of entities of this type, with the
+    /// expressions computing their values. The expressions are evaluated when
+    /// loading entity data, with the other attributes of the entity as
+    /// `context`.
+    fn computed_attrs<'s>(&'s self) -> Box<dyn Iterator<Item = (SmolStr, &'s Expr)> + 's> {
+        Box::new(std::iter::empty())
+    }
+
     /// May entities with this type have attributes other than those specified
     /// in the schema
     fn open_attributes(&self) -> bool;
@@ -28,6 +28,9 @@ pub enum SchemaType {
     Long,
     /// String
     String,
+    /// Type of the `null` value. Nullable types are represented as a
+    /// [`SchemaType::Union`] with this as one of the arms.
+    Null,
     /// Set, with homogeneous elements of the specified type
     Set {
         /// Element type
@@ -55,6 +58,13 @@ pub enum SchemaType {
         /// they have the same typename here.
         name: Name,
     },
+    /// Union of types. A value has this type if it has any of the types in
+    /// `arms`. The validator only constructs unions of distinct primitive
+    /// types, since their values are distinguished in entity data.
+    Union {
+        /// Types of the values of this type
+        arms: Vec<SchemaType>,
+    },
 }
 
 /// Attribute type structure used in [`SchemaType`]
@@ -80,6 +90,7 @@ impl SchemaType {
             Type::Set => None,
             Type::Record => None,
             Type::Extension { name } => Some(SchemaType::Extension { name }),
+            Type::Null => Some(SchemaType::Null),
         }
     }
 
@@ -88,16 +99,44 @@ impl SchemaType {
         match self {
             Self::Extension { name } => Box::new(std::iter::once(name)),
             Self::Set { element_ty } => element_ty.contained_ext_types(),
+            Self::Union { arms } => Box::new(arms.iter().flat_map(|ty| ty.contained_ext_types())),
             Self::Record { attrs, .. } => Box::new(
                 attrs
                     .values()
                     .flat_map(|ty| ty.attr_type.contained_ext_types()),
             ),
-            Self::Bool | Self::Long | Self::String | Self::EmptySet | Self::Entity { .. } => {
-                Box::new(std::iter::empty())
+            Self::Bool
+            | Self::Long
+            | Self::String
+            | Self::Null
+            | Self::EmptySet
+            | Self::Entity { .. } => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// Construct the nullable version of `ty`, i.e., a union of `ty` and
+    /// [`SchemaType::Null`]
+    pub fn nullable(ty: SchemaType) -> Self {
+        match ty {
+            Self::Null => Self::Null,
+            Self::Union { mut arms } => {
+                if !arms.contains(&Self::Null) {
+                    arms.push(Self::Null);
+                }
+                Self::Union { arms }
             }
+            ty => Self::Union {
+                arms: vec![ty, Self::Null],
+            },
         }
     }
+
+    /// Does this type include all values of type `ty`, because it is `ty` or
+    /// a union with `ty` as one of its arms?
+    pub fn includes(&self, ty: &SchemaType) -> bool {
+        self == ty
+            || matches!(self, Self::Union { arms } if arms.iter().any(|arm| arm.includes(ty)))
+    }
 }
 
 impl AttributeType {
@@ -128,17 +167,22 @@ impl AttributeType {
     }
 }
 
-impl From<SchemaType> for Type {
-    fn from(ty: SchemaType) -> Self {
+impl TryFrom<SchemaType> for Type {
+    type Error = String;
+    fn try_from(ty: SchemaType) -> Result<Self, String> {
         match ty {
-            SchemaType::Bool => Type::Bool,
-            SchemaType::Long => Type::Long,
-            SchemaType::String => Type::String,
-            SchemaType::Set { .. } => Type::Set,
-            SchemaType::EmptySet => Type::Set,
-            SchemaType::Record { .. } => Type::Record,
-            SchemaType::Entity { ty } => Type::Entity { ty },
-            SchemaType::Extension { name } => Type::Extension { name },
+            SchemaType::Bool => Ok(Type::Bool),
+            SchemaType::Long => Ok(Type::Long),
+            SchemaType::String => Ok(Type::String),
+            SchemaType::Null => Ok(Type::Null),
+            SchemaType::Set { .. } => Ok(Type::Set),
+            SchemaType::EmptySet => Ok(Type::Set),
+            SchemaType::Record { .. } => Ok(Type::Record),
+            SchemaType::Entity { ty } => Ok(Type::Entity { ty }),
+            SchemaType::Extension { name } => Ok(Type::Extension { name }),
+            SchemaType::Union { .. } => Err(format!(
+                "union type is not representable as a single type: {ty}"
+            )),
         }
     }
 }
@@ -149,6 +193,7 @@ impl std::fmt::Display for SchemaType {
             Self::Bool => write!(f, "bool"),
             Self::Long => write!(f, "long"),
             Self::String => write!(f, "string"),
+            Self::Null => write!(f, "null"),
             Self::Set { element_ty } => write!(f, "[{element_ty}]"),
             Self::EmptySet => write!(f, "[]"),
             Self::Record { attrs, open_attrs } => {
@@ -174,6 +219,7 @@ impl std::fmt::Display for SchemaType {
             }
             Self::Entity { ty } => write!(f, "`{ty}`"),
             Self::Extension { name } => write!(f, "{name}"),
+            Self::Union { arms } => write!(f, "{}", arms.iter().join(" | ")),
         }
     }
 }
@@ -99,8 +99,7 @@ pub enum CedarValueJson {
     Record(
         #[cfg_attr(feature = "wasm", tsify(type = "{ [key: string]: CedarValueJson }"))] JsonRecord,
     ),
-    /// JSON null, which is never valid, but we put this here in order to
-    /// provide a better error message.
+    /// JSON null => Cedar `null`
     Null,
 }
 
@@ -160,6 +159,12 @@ impl JsonRecord {
     pub fn is_empty(&self) -> bool {
         self.values.is_empty()
     }
+
+    /// Does the record have a key reserved for the `__entity` or `__extn`
+    /// escapes. Such a record is a malformed escape.
+    fn has_escape_key(&self) -> bool {
+        self.values.contains_key("__entity") || self.values.contains_key("__extn")
+    }
 }
 
 /// Structure expected by the `__entity` escape
@@ -244,6 +249,9 @@ impl CedarValueJson {
                     .map(|v| v.into_expr(ctx.clone()))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
+            Self::Record(map) if map.has_escape_key() && self.contains_null() => {
+                Err(JsonDeserializationError::Null(Box::new(ctx())))
+            }
             Self::Record(map) => Ok(RestrictedExpr::record(
                 map.into_iter()
                     .map(|(k, v)| Ok((k, v.into_expr(ctx.clone())?)))
@@ -263,7 +271,22 @@ impl CedarValueJson {
             )),
             Self::ExtnEscape { __extn: extn } => extn.into_expr(ctx),
             Self::ExprEscape { .. } => Err(JsonDeserializationError::ExprTag(Box::new(ctx()))),
-            Self::Null => Err(JsonDeserializationError::Null(Box::new(ctx()))),
+            Self::Null => Ok(RestrictedExpr::val(Literal::Null)),
+        }
+    }
+
+    /// Does this value contain a `null`, at any depth
+    fn contains_null(&self) -> bool {
+        match self {
+            Self::Null => true,
+            Self::Set(vals) => vals.iter().any(Self::contains_null),
+            Self::Record(map) => map.iter().any(|(_, v)| v.contains_null()),
+            Self::ExtnEscape { __extn } => __extn.arg.contains_null(),
+            Self::ExprEscape { .. }
+            | Self::EntityEscape { .. }
+            | Self::Bool(_)
+            | Self::Long(_)
+            | Self::String(_) => false,
         }
     }
 
@@ -387,6 +410,7 @@ impl CedarValueJson {
             Literal::EntityUID(euid) => Self::EntityEscape {
                 __entity: Arc::unwrap_or_clone(euid).into(),
             },
+            Literal::Null => Self::Null,
         }
     }
 }
@@ -414,6 +438,9 @@ impl FnAndArg {
         self,
         ctx: impl Fn() -> JsonDeserializationErrorContext + Clone,
     ) -> Result<RestrictedExpr, JsonDeserializationError> {
+        if self.arg.contains_null() {
+            return Err(JsonDeserializationError::Null(Box::new(ctx())));
+        }
         Ok(RestrictedExpr::call_extension_fn(
             Name::from_normalized_str(&self.ext_fn).map_err(|errs| {
                 JsonDeserializationError::parse_escape(EscapeKind::Extension, self.ext_fn, errs)
@@ -582,6 +609,22 @@ impl<'e> ValueParser<'e> {
                     }
                 }
             },
+            // The expected type is nullable. `null` is accepted directly, and
+            // any other value is parsed according to the non-null type, if
+            // there is exactly one.
+            Some(SchemaType::Union { arms }) if arms.contains(&SchemaType::Null) => {
+                if val.is_null() {
+                    return Ok(RestrictedExpr::val(Literal::Null));
+                }
+                let mut non_null_arms = arms.iter().filter(|arm| **arm != SchemaType::Null);
+                match (non_null_arms.next(), non_null_arms.next()) {
+                    (Some(arm), None) => self.val_into_restricted_expr(val, Some(arm), ctx),
+                    _ => {
+                        let jvalue: CedarValueJson = serde_json::from_value(val)?;
+                        Ok(jvalue.into_expr(ctx)?)
+                    }
+                }
+            }
             // The expected type is any other type, or we don't have an expected type.
             // No special parsing rules apply; we do ordinary, non-schema-based parsing.
             Some(_) | None => {
@@ -633,6 +676,9 @@ impl<'e> ValueParser<'e> {
                             expected_return_type,
                         )
                     })?;
+                if val.contains_null() {
+                    return Err(JsonDeserializationError::Null(Box::new(ctx())));
+                }
                 let arg = val.into_expr(ctx.clone())?;
                 Ok(RestrictedExpr::call_extension_fn(
                     func.name().clone(),
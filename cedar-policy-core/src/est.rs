@@ -84,6 +84,46 @@ pub enum Clause {
 }
 
 impl Policy {
+    /// Create a policy with the given effect, no scope constraints, no
+    /// conditions, and no annotations
+    pub fn new(effect: ast::Effect) -> Self {
+        Self {
+            effect,
+            principal: PrincipalConstraint::All,
+            action: ActionConstraint::All,
+            resource: ResourceConstraint::All,
+            conditions: Vec::new(),
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    /// Replace the principal scope constraint
+    pub fn with_principal(self, principal: PrincipalConstraint) -> Self {
+        Self { principal, ..self }
+    }
+
+    /// Replace the action scope constraint
+    pub fn with_action(self, action: ActionConstraint) -> Self {
+        Self { action, ..self }
+    }
+
+    /// Replace the resource scope constraint
+    pub fn with_resource(self, resource: ResourceConstraint) -> Self {
+        Self { resource, ..self }
+    }
+
+    /// Add a `when` or `unless` clause after the existing ones
+    pub fn with_condition(mut self, clause: Clause) -> Self {
+        self.conditions.push(clause);
+        self
+    }
+
+    /// Add an annotation, replacing any existing annotation with the same key
+    pub fn with_annotation(mut self, key: ast::AnyId, value: SmolStr) -> Self {
+        self.annotations.insert(key, value);
+        self
+    }
+
     /// Fill in any slots in the policy using the values in `vals`. Throws an
     /// error if `vals` doesn't contain a necessary mapping, but does not throw
     /// an error if `vals` contains unused mappings -- and in particular if
@@ -216,6 +256,14 @@ impl Policy {
         id: Option<ast::PolicyID>,
     ) -> Result<ast::Template, FromJsonError> {
         let id = id.unwrap_or(ast::PolicyID::from_string("JSON policy"));
+        let kinds = self
+            .conditions
+            .iter()
+            .map(|cond| match cond {
+                Clause::When(_) => ast::ConditionKind::When,
+                Clause::Unless(_) => ast::ConditionKind::Unless,
+            })
+            .collect();
         let mut conditions_iter = self
             .conditions
             .into_iter()
@@ -237,7 +285,8 @@ impl Policy {
             self.action.try_into()?,
             self.resource.try_into()?,
             conditions,
-        ))
+        )
+        .with_condition_kinds(kinds))
     }
 }
 
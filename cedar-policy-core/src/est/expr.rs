@@ -25,7 +25,7 @@ use crate::extensions::Extensions;
 use crate::parser::cst::{self, Ident};
 use crate::parser::err::{ParseErrors, ToASTError, ToASTErrorKind};
 use crate::parser::unescape::to_unescaped_string;
-use crate::parser::util::flatten_tuple_2;
+use crate::parser::util::{flatten_tuple_2, is_null_literal};
 use crate::parser::{Loc, Node};
 use either::Either;
 use itertools::Itertools;
@@ -869,6 +869,9 @@ impl TryFrom<&Node<Option<cst::And>>> for Expr {
 impl TryFrom<&Node<Option<cst::Relation>>> for Expr {
     type Error = ParseErrors;
     fn try_from(r: &Node<Option<cst::Relation>>) -> Result<Expr, ParseErrors> {
+        if let Some(guarded) = r.guard_optional_attrs() {
+            return (&guarded).try_into();
+        }
         match r.try_as_inner()? {
             cst::Relation::Common { initial, extended } => {
                 let mut expr = initial.try_into()?;
@@ -1180,6 +1183,12 @@ impl TryFrom<&Node<Option<cst::Member>>> for Expr {
                         Either::Right(expr) => Either::Right(Expr::get_attr(expr, id.to_smolstr())),
                     };
                 }
+                cst::MemAccess::OptionalField(node) => {
+                    let id = node.to_valid_ident()?;
+                    return Err(access
+                        .to_ast_err(ToASTErrorKind::UnguardedOptionalAttr(id.to_smolstr()))
+                        .into());
+                }
                 cst::MemAccess::Call(args) => {
                     // we have item(args).  We hope item is either:
                     //   - an `ast::Name`, in which case we have a standard function call
@@ -1244,6 +1253,7 @@ impl TryFrom<&Node<Option<cst::Member>>> for Expr {
             }
         }
         match item {
+            Either::Left(name) if is_null_literal(&name) => Ok(Expr::lit(CedarValueJson::Null)),
             Either::Left(_) => Err(m.to_ast_err(ToASTErrorKind::MembershipInvariantViolation))?,
             Either::Right(expr) => Ok(expr),
         }
@@ -1284,14 +1294,16 @@ impl TryFrom<&Node<Option<cst::Literal>>> for Expr {
                     .map_err(|_| lit.to_ast_err(ToASTErrorKind::IntegerLiteralTooLarge(*n)))?,
             ))),
             cst::Literal::Str(node) => match node.try_as_inner()? {
-                cst::Str::String(s) => match to_unescaped_string(s) {
-                    Ok(s) => Ok(Expr::lit(CedarValueJson::String(s))),
-                    Err(errs) => {
-                        Err(ParseErrors::new_from_nonempty(errs.map(|err| {
-                            node.to_ast_err(ToASTErrorKind::Unescape(err)).into()
-                        })))
+                cst::Str::String(s) | cst::Str::Raw { escaped: s, .. } => {
+                    match to_unescaped_string(s) {
+                        Ok(s) => Ok(Expr::lit(CedarValueJson::String(s))),
+                        Err(errs) => {
+                            Err(ParseErrors::new_from_nonempty(errs.map(|err| {
+                                node.to_ast_err(ToASTErrorKind::Unescape(err)).into()
+                            })))
+                        }
                     }
-                },
+                }
                 cst::Str::Invalid(invalid_str) => Err(node
                     .to_ast_err(ToASTErrorKind::InvalidString(invalid_str.to_string()))
                     .into()),
@@ -139,6 +139,17 @@ pub struct PrincipalOrResourceIsConstraint {
     in_entity: Option<PrincipalOrResourceInConstraint>,
 }
 
+impl PrincipalOrResourceIsConstraint {
+    /// Create an `is` constraint on `entity_type`, optionally combined with an
+    /// `in` constraint
+    pub fn new(entity_type: SmolStr, in_entity: Option<PrincipalOrResourceInConstraint>) -> Self {
+        Self {
+            entity_type,
+            in_entity,
+        }
+    }
+}
+
 /// Serde JSON structure for an `in` scope constraint for action in the EST
 /// format
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
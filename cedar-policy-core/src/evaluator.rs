@@ -18,8 +18,9 @@
 
 use crate::ast::*;
 use crate::entities::{Dereference, Entities};
-use crate::extensions::Extensions;
+use crate::extensions::{ExtensionFunctionLookupError, Extensions};
 use crate::parser::Loc;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 #[cfg(test)]
 use std::collections::HashMap;
@@ -34,6 +35,16 @@ use itertools::Either;
 use nonempty::nonempty;
 use smol_str::SmolStr;
 
+mod budget;
+pub use budget::ExtensionBudget;
+mod dependencies;
+pub use dependencies::DependencyTracer;
+mod entity_sets;
+pub use entity_sets::CompiledEntitySets;
+use entity_sets::CompiledSet;
+mod shared;
+pub use shared::SharedSubexpressions;
+
 #[cfg(not(target_arch = "wasm32"))]
 const REQUIRED_STACK_SPACE: usize = 1024 * 100;
 
@@ -68,6 +79,53 @@ pub struct Evaluator<'e> {
     entities: &'e Entities,
     /// Extensions which are active for this evaluation
     extensions: &'e Extensions<'e>,
+    /// What happens when Long arithmetic overflows
+    overflow_mode: OverflowMode,
+    /// Subexpressions which are evaluated only once
+    shared: Option<&'e SharedSubexpressions>,
+    /// Interner of the sets and records this evaluator produces, if enabled
+    interner: Option<RefCell<ValueInterner>>,
+    /// Set literals of entity uids compiled to bitsets
+    entity_sets: Option<&'e CompiledEntitySets>,
+    /// Records the entities whose data is read, if enabled
+    tracer: Option<&'e DependencyTracer>,
+    /// What happens when an extension function doesn't exist
+    unknown_functions: UnknownFunctionMode,
+    /// The calls of extension functions which don't exist, unless they are
+    /// errors
+    unknown_calls: RefCell<Vec<ExtensionFunctionLookupError>>,
+    /// Bounds the cost of the extension function calls, if enabled
+    budget: Option<&'e ExtensionBudget>,
+}
+
+/// What happens when Long arithmetic (`+`, `-`, `*`, and negation)
+/// overflows during evaluation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowMode {
+    /// Fail with an [`EvaluationError::IntegerOverflow`] error, so that the
+    /// policy is skipped. This is the default.
+    #[default]
+    Error,
+    /// Clamp the result to the range of Longs, e.g., `i64::MAX + 1` is
+    /// `i64::MAX`
+    Saturate,
+}
+
+/// What happens when a policy calls an extension function which isn't
+/// registered with the evaluator, e.g., a function of a newer version of
+/// Cedar in policies loaded from the [`crate::stable_ast`] format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnknownFunctionMode {
+    /// Fail with an [`EvaluationError::FailedExtensionFunctionLookup`] error,
+    /// so that the policy is skipped. This is the default.
+    #[default]
+    Error,
+    /// Leave the call as a residual, so that partial evaluation returns the
+    /// policy as a residual, and concrete evaluation fails with an
+    /// [`EvaluationError::NonValue`] error
+    Residual,
+    /// Evaluate the call to `false`
+    False,
 }
 
 /// Evaluator for "restricted" expressions. See notes on `RestrictedExpr`.
@@ -165,6 +223,10 @@ impl<'e> RestrictedEvaluator<'e> {
                 }
             }
             ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                // the string was parsed when the call was built
+                if let (Some(value), Ok(_)) = (expr.as_ref().preparsed_value(), self.extensions.func(fn_name)) {
+                    return Ok(value.clone().into());
+                }
                 let args = args
                     .iter()
                     .map(|arg| self.partial_interpret(BorrowedRestrictedExpr::new_unchecked(arg))) // assuming the invariant holds for `e`, it will hold here
@@ -202,9 +264,75 @@ impl<'e> Evaluator<'e> {
             },
             entities,
             extensions,
+            overflow_mode: OverflowMode::default(),
+            shared: None,
+            interner: None,
+            entity_sets: None,
+            tracer: None,
+            unknown_functions: UnknownFunctionMode::default(),
+            unknown_calls: RefCell::new(Vec::new()),
+            budget: None,
         }
     }
 
+    /// Set what happens when Long arithmetic overflows, by default
+    /// [`OverflowMode::Error`]
+    pub fn with_overflow_mode(mut self, overflow_mode: OverflowMode) -> Self {
+        self.overflow_mode = overflow_mode;
+        self
+    }
+
+    /// Evaluate the subexpressions in `shared` only once, and reuse their
+    /// values for identical subexpressions
+    pub fn with_shared_subexpressions(mut self, shared: &'e SharedSubexpressions) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// Intern the sets and records this evaluator produces, e.g., from
+    /// attributes of entities, so that repeated comparisons of equal sets and
+    /// records are pointer comparisons. See [`ValueInterner`].
+    pub fn with_value_interning(mut self) -> Self {
+        self.interner = Some(RefCell::default());
+        self
+    }
+
+    /// Use the bitsets in `entity_sets` to evaluate the set literals compiled
+    /// to them
+    pub fn with_compiled_entity_sets(mut self, entity_sets: &'e CompiledEntitySets) -> Self {
+        self.entity_sets = Some(entity_sets);
+        self
+    }
+
+    /// Set what happens when a policy calls an extension function which
+    /// doesn't exist, by default [`UnknownFunctionMode::Error`]. Unless they
+    /// are errors, the calls are recorded, see
+    /// [`Evaluator::take_unknown_calls`].
+    pub fn with_unknown_function_mode(mut self, mode: UnknownFunctionMode) -> Self {
+        self.unknown_functions = mode;
+        self
+    }
+
+    /// The calls of extension functions which don't exist evaluated since the
+    /// last call of this method, if they are not errors, in the order they
+    /// were evaluated
+    pub fn take_unknown_calls(&self) -> Vec<ExtensionFunctionLookupError> {
+        self.unknown_calls.take()
+    }
+
+    /// Charge the cost of each extension function call to `budget`, failing
+    /// with an [`EvaluationError::LimitExceeded`] error once it is spent
+    pub fn with_extension_budget(mut self, budget: &'e ExtensionBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Record the entities whose data this evaluator reads to `tracer`
+    pub fn with_dependency_tracer(mut self, tracer: &'e DependencyTracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
     /// Evaluate the given `Policy`, returning either a bool or an error.
     /// The bool indicates whether the policy applies, ie, "is satisfied" for the
     /// current `request`.
@@ -225,7 +353,17 @@ impl<'e> Evaluator<'e> {
     ///    it doesn't consider whether we're processing a `Permit` policy or a
     ///    `Forbid` policy.
     pub fn partial_evaluate(&self, p: &Policy) -> Result<Either<bool, Expr>> {
-        match self.partial_interpret(&p.condition(), p.env())? {
+        self.partial_evaluate_condition(&p.condition(), p.env())
+    }
+
+    /// Partially evaluate `condition`, the condition of a policy with the slot
+    /// values `slots`, like [`Evaluator::partial_evaluate`]
+    pub fn partial_evaluate_condition(
+        &self,
+        condition: &Expr,
+        slots: &SlotEnv,
+    ) -> Result<Either<bool, Expr>> {
+        match self.partial_interpret(condition, slots)? {
             PartialValue::Value(v) => v.get_as_bool().map(Either::Left),
             PartialValue::Residual(e) => Ok(Either::Right(e)),
         }
@@ -251,7 +389,18 @@ impl<'e> Evaluator<'e> {
     pub fn partial_interpret(&self, expr: &Expr, slots: &SlotEnv) -> Result<PartialValue> {
         stack_size_check()?;
 
-        let res = self.partial_interpret_internal(expr, slots);
+        let res = match self.shared.and_then(|shared| shared.get(expr)) {
+            Some(pval) => Ok(pval),
+            None => {
+                let res = self
+                    .partial_interpret_internal(expr, slots)
+                    .map(|pval| self.intern(pval));
+                if let (Some(shared), Ok(pval)) = (self.shared, &res) {
+                    shared.insert(expr, pval);
+                }
+                res
+            }
+        };
 
         // set the returned value's source location to the same source location
         // as the input expression had.
@@ -267,6 +416,74 @@ impl<'e> Evaluator<'e> {
             })
     }
 
+    /// The compiled set for `e`, if `e` is a compiled set literal
+    fn compiled_set(&self, e: &Expr) -> Option<&'e CompiledSet> {
+        self.entity_sets.and_then(|entity_sets| entity_sets.get(e))
+    }
+
+    /// Evaluate `arg1.contains(arg2)`, `arg1.containsAll(arg2)`, or
+    /// `arg1.containsAny(arg2)` with the bitset of `arg1`, if `arg1` is a
+    /// compiled set literal. Returns `None` for other expressions.
+    fn compiled_set_op(
+        &self,
+        op: BinaryOp,
+        arg1: &Expr,
+        arg2: &Expr,
+        slots: &SlotEnv,
+    ) -> Option<Result<PartialValue>> {
+        if !matches!(
+            op,
+            BinaryOp::Contains | BinaryOp::ContainsAll | BinaryOp::ContainsAny
+        ) {
+            return None;
+        }
+        let (entity_sets, set) = (self.entity_sets?, self.compiled_set(arg1)?);
+        // word-wise operations if both operands are compiled set literals
+        if let Some(other) = self.compiled_set(arg2) {
+            match op {
+                BinaryOp::ContainsAll => return Some(Ok(set.is_superset(other).into())),
+                BinaryOp::ContainsAny => return Some(Ok(set.intersects(other).into())),
+                _ => {}
+            }
+        }
+        let arg2 = match self.partial_interpret(arg2, slots) {
+            Ok(PartialValue::Value(v)) => v,
+            Ok(PartialValue::Residual(r)) => {
+                return Some(Ok(PartialValue::Residual(Expr::binary_app(
+                    op,
+                    set.value().clone().into(),
+                    r,
+                ))))
+            }
+            Err(err) => return Some(Err(err)),
+        };
+        Some(match op {
+            BinaryOp::Contains => Ok(entity_sets.contains(set, &arg2).into()),
+            BinaryOp::ContainsAll => arg2.get_as_set().map(|arg2| {
+                arg2.authoritative
+                    .iter()
+                    .all(|v| entity_sets.contains(set, v))
+                    .into()
+            }),
+            _ => arg2.get_as_set().map(|arg2| {
+                arg2.authoritative
+                    .iter()
+                    .any(|v| entity_sets.contains(set, v))
+                    .into()
+            }),
+        })
+    }
+
+    /// Intern `pval` if it is a value and interning is enabled
+    fn intern(&self, pval: PartialValue) -> PartialValue {
+        match (&self.interner, pval) {
+            (Some(interner), PartialValue::Value(v)) => {
+                PartialValue::Value(interner.borrow_mut().intern(v))
+            }
+            (_, pval) => pval,
+        }
+    }
+
     /// Internal function to interpret an `Expr`. (External callers, use
     /// `interpret()` or `partial_interpret()`.)
     ///
@@ -349,29 +566,17 @@ impl<'e> Evaluator<'e> {
                 }
             }
             ExprKind::UnaryApp { op, arg } => match self.partial_interpret(arg, slots)? {
-                PartialValue::Value(arg) => match op {
-                    UnaryOp::Not => match arg.get_as_bool()? {
-                        true => Ok(false.into()),
-                        false => Ok(true.into()),
-                    },
-                    UnaryOp::Neg => {
-                        let i = arg.get_as_long()?;
-                        match i.checked_neg() {
-                            Some(v) => Ok(v.into()),
-                            None => Err(IntegerOverflowError::UnaryOp(UnaryOpOverflowError {
-                                op: *op,
-                                arg,
-                                source_loc: loc.cloned(),
-                            })
-                            .into()),
-                        }
-                    }
-                },
+                PartialValue::Value(arg) => {
+                    unary_app(*op, arg, self.overflow_mode, loc).map(Into::into)
+                }
                 // NOTE, there was a bug here found during manual review. (I forgot to wrap in unary_app call)
                 // Could be a nice target for fault injection
                 PartialValue::Residual(r) => Ok(PartialValue::Residual(Expr::unary_app(*op, r))),
             },
             ExprKind::BinaryApp { op, arg1, arg2 } => {
+                if let Some(res) = self.compiled_set_op(*op, arg1, arg2, slots) {
+                    return res;
+                }
                 // NOTE: There are more precise partial eval opportunities here, esp w/ typed unknowns
                 // Current limitations:
                 //   Operators are not partially evaluated.
@@ -390,150 +595,19 @@ impl<'e> Evaluator<'e> {
                         return Ok(PartialValue::Residual(Expr::binary_app(*op, e1, e2)))
                     }
                 };
-                match op {
-                    BinaryOp::Eq => Ok((arg1 == arg2).into()),
-                    // comparison and arithmetic operators, which only work on Longs
-                    BinaryOp::Less
-                    | BinaryOp::LessEq
-                    | BinaryOp::Add
-                    | BinaryOp::Sub
-                    | BinaryOp::Mul => {
-                        let i1 = arg1.get_as_long()?;
-                        let i2 = arg2.get_as_long()?;
-                        match op {
-                            BinaryOp::Less => Ok((i1 < i2).into()),
-                            BinaryOp::LessEq => Ok((i1 <= i2).into()),
-                            BinaryOp::Add => match i1.checked_add(i2) {
-                                Some(sum) => Ok(sum.into()),
-                                None => {
-                                    Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
-                                        op: *op,
-                                        arg1,
-                                        arg2,
-                                        source_loc: loc.cloned(),
-                                    })
-                                    .into())
-                                }
-                            },
-                            BinaryOp::Sub => match i1.checked_sub(i2) {
-                                Some(diff) => Ok(diff.into()),
-                                None => {
-                                    Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
-                                        op: *op,
-                                        arg1,
-                                        arg2,
-                                        source_loc: loc.cloned(),
-                                    })
-                                    .into())
-                                }
-                            },
-                            BinaryOp::Mul => match i1.checked_mul(i2) {
-                                Some(prod) => Ok(prod.into()),
-                                None => {
-                                    Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
-                                        op: *op,
-                                        arg1,
-                                        arg2,
-                                        source_loc: loc.cloned(),
-                                    })
-                                    .into())
-                                }
-                            },
-                            // PANIC SAFETY `op` is checked to be one of the above
-                            #[allow(clippy::unreachable)]
-                            _ => {
-                                unreachable!("Should have already checked that op was one of these")
-                            }
-                        }
-                    }
-                    // hierarchy membership operator; see note on `BinaryOp::In`
-                    BinaryOp::In => {
-                        let uid1 = arg1.get_as_entity().map_err(|mut e|
-                            {
-                                // If arg1 is not an entity and arg2 is a set, then possibly
-                                // the user intended `arg2.contains(arg1)` rather than `arg1 in arg2`.
-                                // If arg2 is a record, then possibly they intended `arg2 has arg1`.
-                                if let EvaluationError::TypeError(TypeError { advice, .. }) = &mut e {
-                                    match arg2.type_of() {
-                                        Type::Set => *advice = Some("`in` is for checking the entity hierarchy; use `.contains()` to test set membership".into()),
-                                        Type::Record => *advice = Some("`in` is for checking the entity hierarchy; use `has` to test if a record has a key".into()),
-                                        _ => {}
-                                    }
-                                };
-                                e
-                            })?;
-                        match self.entities.entity(uid1) {
-                            Dereference::Residual(r) => Ok(PartialValue::Residual(
-                                Expr::binary_app(BinaryOp::In, r, arg2.into()),
-                            )),
-                            Dereference::NoSuchEntity => self.eval_in(uid1, None, arg2),
-                            Dereference::Data(entity1) => self.eval_in(uid1, Some(entity1), arg2),
-                        }
-                    }
-                    // contains, which works on Sets
-                    BinaryOp::Contains => match arg1.value {
-                        ValueKind::Set(Set { fast: Some(h), .. }) => match arg2.try_as_lit() {
-                            Some(lit) => Ok((h.contains(lit)).into()),
-                            None => Ok(false.into()), // we know it doesn't contain a non-literal
-                        },
-                        ValueKind::Set(Set {
-                            fast: None,
-                            authoritative,
-                        }) => Ok((authoritative.contains(&arg2)).into()),
-                        _ => Err(EvaluationError::type_error_single(Type::Set, &arg1)),
-                    },
-                    // ContainsAll and ContainsAny, which work on Sets
-                    BinaryOp::ContainsAll | BinaryOp::ContainsAny => {
-                        let arg1_set = arg1.get_as_set()?;
-                        let arg2_set = arg2.get_as_set()?;
-                        match (&arg1_set.fast, &arg2_set.fast) {
-                            (Some(arg1_set), Some(arg2_set)) => {
-                                // both sets are in fast form, ie, they only contain literals.
-                                // Fast hashset-based implementation.
-                                match op {
-                                    BinaryOp::ContainsAll => {
-                                        Ok((arg2_set.is_subset(arg1_set)).into())
-                                    }
-                                    BinaryOp::ContainsAny => {
-                                        Ok((!arg1_set.is_disjoint(arg2_set)).into())
-                                    }
-                                    // PANIC SAFETY `op` is checked to be one of these two above
-                                    #[allow(clippy::unreachable)]
-                                    _ => unreachable!(
-                                        "Should have already checked that op was one of these"
-                                    ),
-                                }
-                            }
-                            (_, _) => {
-                                // one or both sets are in slow form, ie, contain a non-literal.
-                                // Fallback to slow implementation.
-                                match op {
-                                    BinaryOp::ContainsAll => {
-                                        let is_subset = arg2_set
-                                            .authoritative
-                                            .iter()
-                                            .all(|item| arg1_set.authoritative.contains(item));
-                                        Ok(is_subset.into())
-                                    }
-                                    BinaryOp::ContainsAny => {
-                                        let not_disjoint = arg1_set
-                                            .authoritative
-                                            .iter()
-                                            .any(|item| arg2_set.authoritative.contains(item));
-                                        Ok(not_disjoint.into())
-                                    }
-                                    // PANIC SAFETY `op` is checked to be one of these two above
-                                    #[allow(clippy::unreachable)]
-                                    _ => unreachable!(
-                                        "Should have already checked that op was one of these"
-                                    ),
-                                }
-                            }
-                        }
-                    }
+                if *op == BinaryOp::In {
+                    self.trace(&arg1);
                 }
+                binary_app(*op, arg1, arg2, self.entities, self.overflow_mode, loc)
             }
             ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                // the string was parsed when the call was built, so this is
+                // not a call, and costs nothing
+                if let (Some(value), Ok(_)) =
+                    (expr.preparsed_value(), self.extensions.func(fn_name))
+                {
+                    return Ok(value.clone().into());
+                }
                 let args = args
                     .iter()
                     .map(|arg| self.partial_interpret(arg, slots))
@@ -541,7 +615,31 @@ impl<'e> Evaluator<'e> {
                 match split(args) {
                     Either::Left(vals) => {
                         let vals: Vec<_> = vals.collect();
-                        let efunc = self.extensions.func(fn_name)?;
+                        let efunc = match (self.extensions.func(fn_name), self.unknown_functions) {
+                            (Ok(efunc), _) => efunc,
+                            (Err(err), UnknownFunctionMode::Error) => return Err(err.into()),
+                            (Err(err), mode) => {
+                                self.unknown_calls
+                                    .borrow_mut()
+                                    .push(err.with_maybe_source_loc(loc.cloned()));
+                                return Ok(match mode {
+                                    UnknownFunctionMode::False => Value::from(false).into(),
+                                    _ => PartialValue::Residual(Expr::call_extension_fn(
+                                        fn_name.clone(),
+                                        vals.into_iter().map(Expr::from).collect(),
+                                    )),
+                                });
+                            }
+                        };
+                        if let Some(budget) = self.budget {
+                            if !budget.charge(efunc.cost()) {
+                                return Err(EvaluationError::limit_exceeded(
+                                    fn_name.clone(),
+                                    budget.limit(),
+                                    loc.cloned(),
+                                ));
+                            }
+                        }
                         efunc.call(&vals)
                     }
                     Either::Right(residuals) => Ok(PartialValue::Residual(
@@ -551,27 +649,10 @@ impl<'e> Evaluator<'e> {
             }
             ExprKind::GetAttr { expr, attr } => self.get_attr(expr.as_ref(), attr, slots, loc),
             ExprKind::HasAttr { expr, attr } => match self.partial_interpret(expr, slots)? {
-                PartialValue::Value(Value {
-                    value: ValueKind::Record(record),
-                    ..
-                }) => Ok(record.get(attr).is_some().into()),
-                PartialValue::Value(Value {
-                    value: ValueKind::Lit(Literal::EntityUID(uid)),
-                    ..
-                }) => match self.entities.entity(&uid) {
-                    Dereference::NoSuchEntity => Ok(false.into()),
-                    Dereference::Residual(r) => {
-                        Ok(PartialValue::Residual(Expr::has_attr(r, attr.clone())))
-                    }
-                    Dereference::Data(e) => Ok(e.get(attr).is_some().into()),
-                },
-                PartialValue::Value(val) => Err(err::EvaluationError::type_error(
-                    nonempty![
-                        Type::Record,
-                        Type::entity_type(names::ANY_ENTITY_TYPE.clone())
-                    ],
-                    &val,
-                )),
+                PartialValue::Value(val) => {
+                    self.trace(&val);
+                    has_attr(val, attr, self.entities)
+                }
                 PartialValue::Residual(r) => Ok(Expr::has_attr(r, attr.clone()).into()),
             },
             ExprKind::Like { expr, pattern } => {
@@ -595,6 +676,9 @@ impl<'e> Evaluator<'e> {
                 }
             }
             ExprKind::Set(items) => {
+                if let Some(set) = self.compiled_set(expr) {
+                    return Ok(set.value().clone().into());
+                }
                 let vals = items
                     .iter()
                     .map(|item| self.partial_interpret(item, slots))
@@ -628,43 +712,6 @@ impl<'e> Evaluator<'e> {
         }
     }
 
-    fn eval_in(
-        &self,
-        uid1: &EntityUID,
-        entity1: Option<&Entity>,
-        arg2: Value,
-    ) -> Result<PartialValue> {
-        // `rhs` is a list of all the UIDs for which we need to
-        // check if `uid1` is a descendant of
-        let rhs = match arg2.value {
-            ValueKind::Lit(Literal::EntityUID(uid)) => vec![(*uid).clone()],
-            // we assume that iterating the `authoritative` BTreeSet is
-            // approximately the same cost as iterating the `fast` HashSet
-            ValueKind::Set(Set { authoritative, .. }) => authoritative
-                .iter()
-                .map(|val| Ok(val.get_as_entity()?.clone()))
-                .collect::<Result<Vec<EntityUID>>>()?,
-            _ => {
-                return Err(EvaluationError::type_error(
-                    nonempty![Type::Set, Type::entity_type(names::ANY_ENTITY_TYPE.clone())],
-                    &arg2,
-                ))
-            }
-        };
-        for uid2 in rhs {
-            if uid1 == &uid2
-                || entity1
-                    .map(|e1| e1.is_descendant_of(&uid2))
-                    .unwrap_or(false)
-            {
-                return Ok(true.into());
-            }
-        }
-        // if we get here, `uid1` is not a descendant of (or equal to)
-        // any UID in `rhs`
-        Ok(false.into())
-    }
-
     /// Evaluation of conditionals
     /// Must be sure to respect short-circuiting semantics
     fn eval_if(
@@ -691,6 +738,14 @@ impl<'e> Evaluator<'e> {
     /// We don't use the `source_loc()` on `expr` because that's only the loc
     /// for the LHS of the GetAttr. `source_loc` argument should be the loc for
     /// the entire GetAttr expression
+    /// Record that the data of the entity `val` is read, if it is an entity
+    /// and tracing is enabled
+    fn trace(&self, val: &Value) {
+        if let Some(tracer) = self.tracer {
+            tracer.record(val);
+        }
+    }
+
     fn get_attr(
         &self,
         expr: &Expr,
@@ -739,55 +794,9 @@ impl<'e> Evaluator<'e> {
                     _ => Ok(PartialValue::Residual(Expr::get_attr(res, attr.clone()))),
                 }
             }
-            PartialValue::Value(Value {
-                value: ValueKind::Record(record),
-                ..
-            }) => record
-                .as_ref()
-                .get(attr)
-                .ok_or_else(|| {
-                    EvaluationError::record_attr_does_not_exist(
-                        attr.clone(),
-                        record.keys(),
-                        record.len(),
-                        source_loc.cloned(),
-                    )
-                })
-                .map(|v| PartialValue::Value(v.clone())),
-            PartialValue::Value(Value {
-                value: ValueKind::Lit(Literal::EntityUID(uid)),
-                loc,
-            }) => match self.entities.entity(uid.as_ref()) {
-                Dereference::NoSuchEntity => {
-                    // intentionally using the location of the euid (the LHS) and not the entire GetAttr expression
-                    Err(EvaluationError::entity_does_not_exist(uid.clone(), loc))
-                }
-                Dereference::Residual(r) => {
-                    Ok(PartialValue::Residual(Expr::get_attr(r, attr.clone())))
-                }
-                Dereference::Data(entity) => entity
-                    .get(attr)
-                    .ok_or_else(|| {
-                        EvaluationError::entity_attr_does_not_exist(
-                            uid,
-                            attr.clone(),
-                            entity.keys(),
-                            entity.attrs_len(),
-                            source_loc.cloned(),
-                        )
-                    })
-                    .cloned(),
-            },
             PartialValue::Value(v) => {
-                // PANIC SAFETY Entity type name is fully static and a valid unqualified `Name`
-                #[allow(clippy::unwrap_used)]
-                Err(EvaluationError::type_error(
-                    nonempty![
-                        Type::Record,
-                        Type::entity_type(names::ANY_ENTITY_TYPE.clone()),
-                    ],
-                    &v,
-                ))
+                self.trace(&v);
+                get_attr(v, attr, self.entities, source_loc)
             }
         }
     }
@@ -832,6 +841,311 @@ impl<'e> std::fmt::Debug for Evaluator<'e> {
     }
 }
 
+/// Apply the unary operator `op` to the value `arg`.
+///
+/// `loc` is the source location of the entire application, used for errors.
+pub(crate) fn unary_app(
+    op: UnaryOp,
+    arg: Value,
+    overflow_mode: OverflowMode,
+    loc: Option<&Loc>,
+) -> Result<Value> {
+    match op {
+        UnaryOp::Not => match arg.get_as_bool()? {
+            true => Ok(false.into()),
+            false => Ok(true.into()),
+        },
+        UnaryOp::Neg => {
+            let i = arg.get_as_long()?;
+            match i.checked_neg() {
+                Some(v) => Ok(v.into()),
+                None if overflow_mode == OverflowMode::Saturate => Ok(i.saturating_neg().into()),
+                None => Err(IntegerOverflowError::UnaryOp(UnaryOpOverflowError {
+                    op,
+                    arg,
+                    source_loc: loc.cloned(),
+                })
+                .into()),
+            }
+        }
+    }
+}
+
+/// Apply the binary operator `op` to the values `arg1` and `arg2`, using
+/// `entities` to resolve hierarchy membership.
+///
+/// This may return a residual only if `entities` is partial.
+/// `loc` is the source location of the entire application, used for errors.
+pub(crate) fn binary_app(
+    op: BinaryOp,
+    arg1: Value,
+    arg2: Value,
+    entities: &Entities,
+    overflow_mode: OverflowMode,
+    loc: Option<&Loc>,
+) -> Result<PartialValue> {
+    match op {
+        BinaryOp::Eq => Ok((arg1 == arg2).into()),
+        // comparison and arithmetic operators, which only work on Longs
+        BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul => {
+            let i1 = arg1.get_as_long()?;
+            let i2 = arg2.get_as_long()?;
+            match op {
+                BinaryOp::Less => Ok((i1 < i2).into()),
+                BinaryOp::LessEq => Ok((i1 <= i2).into()),
+                BinaryOp::Add => match i1.checked_add(i2) {
+                    Some(sum) => Ok(sum.into()),
+                    None if overflow_mode == OverflowMode::Saturate => {
+                        Ok(i1.saturating_add(i2).into())
+                    }
+                    None => Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
+                        op,
+                        arg1,
+                        arg2,
+                        source_loc: loc.cloned(),
+                    })
+                    .into()),
+                },
+                BinaryOp::Sub => match i1.checked_sub(i2) {
+                    Some(diff) => Ok(diff.into()),
+                    None if overflow_mode == OverflowMode::Saturate => {
+                        Ok(i1.saturating_sub(i2).into())
+                    }
+                    None => Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
+                        op,
+                        arg1,
+                        arg2,
+                        source_loc: loc.cloned(),
+                    })
+                    .into()),
+                },
+                BinaryOp::Mul => match i1.checked_mul(i2) {
+                    Some(prod) => Ok(prod.into()),
+                    None if overflow_mode == OverflowMode::Saturate => {
+                        Ok(i1.saturating_mul(i2).into())
+                    }
+                    None => Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
+                        op,
+                        arg1,
+                        arg2,
+                        source_loc: loc.cloned(),
+                    })
+                    .into()),
+                },
+                // PANIC SAFETY `op` is checked to be one of the above
+                #[allow(clippy::unreachable)]
+                _ => {
+                    unreachable!("Should have already checked that op was one of these")
+                }
+            }
+        }
+        // hierarchy membership operator; see note on `BinaryOp::In`
+        BinaryOp::In => {
+            let uid1 = arg1.get_as_entity().map_err(|mut e|
+                {
+                    // If arg1 is not an entity and arg2 is a set, then possibly
+                    // the user intended `arg2.contains(arg1)` rather than `arg1 in arg2`.
+                    // If arg2 is a record, then possibly they intended `arg2 has arg1`.
+                    if let EvaluationError::TypeError(TypeError { advice, .. }) = &mut e {
+                        match arg2.type_of() {
+                            Type::Set => *advice = Some("`in` is for checking the entity hierarchy; use `.contains()` to test set membership".into()),
+                            Type::Record => *advice = Some("`in` is for checking the entity hierarchy; use `has` to test if a record has a key".into()),
+                            _ => {}
+                        }
+                    };
+                    e
+                })?;
+            match entities.entity(uid1) {
+                Dereference::Residual(r) => Ok(PartialValue::Residual(Expr::binary_app(
+                    BinaryOp::In,
+                    r,
+                    arg2.into(),
+                ))),
+                Dereference::NoSuchEntity => eval_in(uid1, None, arg2, entities),
+                Dereference::Data(entity1) => eval_in(uid1, Some(entity1), arg2, entities),
+            }
+        }
+        // contains, which works on Sets
+        BinaryOp::Contains => match arg1.value {
+            ValueKind::Set(Set { fast: Some(h), .. }) => match arg2.try_as_lit() {
+                Some(lit) => Ok((h.contains(lit)).into()),
+                None => Ok(false.into()), // we know it doesn't contain a non-literal
+            },
+            ValueKind::Set(Set {
+                fast: None,
+                authoritative,
+            }) => Ok((authoritative.contains(&arg2)).into()),
+            _ => Err(EvaluationError::type_error_single(Type::Set, &arg1)),
+        },
+        // ContainsAll and ContainsAny, which work on Sets
+        BinaryOp::ContainsAll | BinaryOp::ContainsAny => {
+            let arg1_set = arg1.get_as_set()?;
+            let arg2_set = arg2.get_as_set()?;
+            match (&arg1_set.fast, &arg2_set.fast) {
+                (Some(arg1_set), Some(arg2_set)) => {
+                    // both sets are in fast form, ie, they only contain literals.
+                    // Fast hashset-based implementation.
+                    match op {
+                        BinaryOp::ContainsAll => Ok((arg2_set.is_subset(arg1_set)).into()),
+                        BinaryOp::ContainsAny => Ok((!arg1_set.is_disjoint(arg2_set)).into()),
+                        // PANIC SAFETY `op` is checked to be one of these two above
+                        #[allow(clippy::unreachable)]
+                        _ => unreachable!("Should have already checked that op was one of these"),
+                    }
+                }
+                (_, _) => {
+                    // one or both sets are in slow form, ie, contain a non-literal.
+                    // Fallback to slow implementation.
+                    match op {
+                        BinaryOp::ContainsAll => {
+                            let is_subset = arg2_set
+                                .authoritative
+                                .iter()
+                                .all(|item| arg1_set.authoritative.contains(item));
+                            Ok(is_subset.into())
+                        }
+                        BinaryOp::ContainsAny => {
+                            let not_disjoint = arg1_set
+                                .authoritative
+                                .iter()
+                                .any(|item| arg2_set.authoritative.contains(item));
+                            Ok(not_disjoint.into())
+                        }
+                        // PANIC SAFETY `op` is checked to be one of these two above
+                        #[allow(clippy::unreachable)]
+                        _ => unreachable!("Should have already checked that op was one of these"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Evaluate `uid1 in arg2`, where `entity1` is the entity data for `uid1` (if
+/// it exists in `entities`)
+fn eval_in(
+    uid1: &EntityUID,
+    entity1: Option<&Entity>,
+    arg2: Value,
+    entities: &Entities,
+) -> Result<PartialValue> {
+    // `rhs` is a list of all the UIDs for which we need to
+    // check if `uid1` is a descendant of
+    let rhs = match arg2.value {
+        ValueKind::Lit(Literal::EntityUID(uid)) => vec![(*uid).clone()],
+        // we assume that iterating the `authoritative` BTreeSet is
+        // approximately the same cost as iterating the `fast` HashSet
+        ValueKind::Set(Set { authoritative, .. }) => authoritative
+            .iter()
+            .map(|val| Ok(val.get_as_entity()?.clone()))
+            .collect::<Result<Vec<EntityUID>>>()?,
+        _ => {
+            return Err(EvaluationError::type_error(
+                nonempty![Type::Set, Type::entity_type(names::ANY_ENTITY_TYPE.clone())],
+                &arg2,
+            ))
+        }
+    };
+    for uid2 in rhs {
+        if uid1 == &uid2
+            || entity1
+                .map(|e1| entities.is_descendant_of(e1, &uid2))
+                .unwrap_or(false)
+        {
+            return Ok(true.into());
+        }
+    }
+    // if we get here, `uid1` is not a descendant of (or equal to)
+    // any UID in `rhs`
+    Ok(false.into())
+}
+
+/// Evaluate `val has attr`, using `entities` to look up entity attributes
+pub(crate) fn has_attr(val: Value, attr: &SmolStr, entities: &Entities) -> Result<PartialValue> {
+    match val {
+        Value {
+            value: ValueKind::Record(record),
+            ..
+        } => Ok(record.get(attr).is_some().into()),
+        Value {
+            value: ValueKind::Lit(Literal::EntityUID(uid)),
+            ..
+        } => match entities.entity(&uid) {
+            Dereference::NoSuchEntity => Ok(false.into()),
+            Dereference::Residual(r) => Ok(PartialValue::Residual(Expr::has_attr(r, attr.clone()))),
+            Dereference::Data(e) => Ok(e.get(attr).is_some().into()),
+        },
+        val => Err(err::EvaluationError::type_error(
+            nonempty![
+                Type::Record,
+                Type::entity_type(names::ANY_ENTITY_TYPE.clone())
+            ],
+            &val,
+        )),
+    }
+}
+
+/// Evaluate `val.attr`, using `entities` to look up entity attributes.
+///
+/// `source_loc` is the source location of the entire `GetAttr` expression.
+pub(crate) fn get_attr(
+    val: Value,
+    attr: &SmolStr,
+    entities: &Entities,
+    source_loc: Option<&Loc>,
+) -> Result<PartialValue> {
+    match val {
+        Value {
+            value: ValueKind::Record(record),
+            ..
+        } => record
+            .as_ref()
+            .get(attr)
+            .ok_or_else(|| {
+                EvaluationError::record_attr_does_not_exist(
+                    attr.clone(),
+                    record.keys(),
+                    record.len(),
+                    source_loc.cloned(),
+                )
+            })
+            .map(|v| PartialValue::Value(v.clone())),
+        Value {
+            value: ValueKind::Lit(Literal::EntityUID(uid)),
+            loc,
+        } => match entities.entity(uid.as_ref()) {
+            Dereference::NoSuchEntity => {
+                // intentionally using the location of the euid (the LHS) and not the entire GetAttr expression
+                Err(EvaluationError::entity_does_not_exist(uid.clone(), loc))
+            }
+            Dereference::Residual(r) => Ok(PartialValue::Residual(Expr::get_attr(r, attr.clone()))),
+            Dereference::Data(entity) => entity
+                .get(attr)
+                .ok_or_else(|| {
+                    EvaluationError::entity_attr_does_not_exist(
+                        uid,
+                        attr.clone(),
+                        entity.keys(),
+                        entity.attrs_len(),
+                        source_loc.cloned(),
+                    )
+                })
+                .cloned(),
+        },
+        v => {
+            // PANIC SAFETY Entity type name is fully static and a valid unqualified `Name`
+            #[allow(clippy::unwrap_used)]
+            Err(EvaluationError::type_error(
+                nonempty![
+                    Type::Record,
+                    Type::entity_type(names::ANY_ENTITY_TYPE.clone()),
+                ],
+                &v,
+            ))
+        }
+    }
+}
+
 impl Value {
     /// Convert the `Value` to a boolean, or throw a type error if it's not a
     /// boolean.
@@ -2482,6 +2796,58 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn interpret_nulls() {
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, Extensions::none());
+        // null
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::val(Literal::Null)),
+            Ok(Value::from(Literal::Null))
+        );
+        // null == null
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::is_eq(
+                Expr::val(Literal::Null),
+                Expr::val(Literal::Null)
+            )),
+            Ok(Value::from(true))
+        );
+        // 33 != null
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::noteq(Expr::val(33), Expr::val(Literal::Null))),
+            Ok(Value::from(true))
+        );
+        // {foo: null}.foo == null
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::is_eq(
+                Expr::get_attr(
+                    Expr::record([("foo".into(), Expr::val(Literal::Null))]).unwrap(),
+                    "foo".into()
+                ),
+                Expr::val(Literal::Null)
+            )),
+            Ok(Value::from(true))
+        );
+        // {foo: null} has foo
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::has_attr(
+                Expr::record([("foo".into(), Expr::val(Literal::Null))]).unwrap(),
+                "foo".into()
+            )),
+            Ok(Value::from(true))
+        );
+        // null < 3
+        assert_matches!(
+            eval.interpret_inline_policy(&Expr::less(Expr::val(Literal::Null), Expr::val(3))),
+            Err(EvaluationError::TypeError(TypeError { expected, actual, .. })) => {
+                assert_eq!(expected, nonempty![Type::Long]);
+                assert_eq!(actual, Type::Null);
+            }
+        );
+    }
+
     #[test]
     fn interpret_compares() {
         let request = basic_request();
@@ -2865,6 +3231,35 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn interpret_saturating_arithmetic() {
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, Extensions::none())
+            .with_overflow_mode(OverflowMode::Saturate);
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::add(Expr::val(Integer::MAX), Expr::val(1))),
+            Ok(Value::from(Integer::MAX))
+        );
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::sub(Expr::val(Integer::MIN), Expr::val(1))),
+            Ok(Value::from(Integer::MIN))
+        );
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::mul(Expr::val(Integer::MIN), Expr::val(2))),
+            Ok(Value::from(Integer::MIN))
+        );
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::neg(Expr::val(Integer::MIN))),
+            Ok(Value::from(Integer::MAX))
+        );
+        // results in range are not affected
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::mul(Expr::val(-3), Expr::val(7))),
+            Ok(Value::from(-21))
+        );
+    }
+
     #[test]
     fn interpret_arithmetic() {
         let request = basic_request();
@@ -0,0 +1,116 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`ExtensionBudget`], which bounds the cost of the
+//! extension function calls of evaluators.
+
+use std::cell::Cell;
+
+/// Bounds the total [cost](crate::ast::ExtensionFunction::cost) of the
+/// extension function calls evaluated by the evaluators sharing it, e.g., all
+/// the evaluators of a request. A call which would exceed the budget fails
+/// with an [`EvaluationError::LimitExceeded`](super::EvaluationError::LimitExceeded)
+/// error instead of calling the function. Calls of constructors on a string
+/// literal are parsed when they are built, see
+/// [`Expr::preparsed_value`](crate::ast::Expr::preparsed_value), so they cost
+/// nothing.
+#[derive(Debug)]
+pub struct ExtensionBudget {
+    limit: u64,
+    spent: Cell<u64>,
+}
+
+impl ExtensionBudget {
+    /// A budget of `limit` of which nothing is spent yet
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            spent: Cell::new(0),
+        }
+    }
+
+    /// The total cost of the calls allowed
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// The total cost of the calls made so far
+    pub fn spent(&self) -> u64 {
+        self.spent.get()
+    }
+
+    /// Spend `cost` on a call, returning `false`, and spending nothing, if it
+    /// would exceed the budget
+    pub(crate) fn charge(&self, cost: u32) -> bool {
+        match self.spent.get().checked_add(u64::from(cost)) {
+            Some(spent) if spent <= self.limit => {
+                self.spent.set(spent);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{Context, EntityUIDEntry, Request, RestrictedExpr};
+    use crate::entities::Entities;
+    use crate::evaluator::{EvaluationError, Evaluator};
+    use crate::extensions::Extensions;
+    use crate::parser::parse_expr;
+    use cool_asserts::assert_matches;
+    use std::collections::HashMap;
+
+    #[cfg(feature = "ipaddr")]
+    #[test]
+    fn bounds_extension_calls() {
+        let entry = |uid: &str| EntityUIDEntry::known(uid.parse().unwrap(), None);
+        let request = Request::new_unchecked(
+            entry(r#"User::"alice""#),
+            entry(r#"Action::"view""#),
+            entry(r#"Photo::"p""#),
+            Some(
+                Context::from_pairs(
+                    [("addr".into(), RestrictedExpr::val("127.0.0.1"))],
+                    Extensions::none(),
+                )
+                .unwrap(),
+            ),
+        );
+        let entities = Entities::new();
+        // `ip()` costs 10 and `isLoopback()` costs 1. Calls on a string
+        // literal are parsed with the policy, so the argument is not one.
+        let expr = parse_expr(r#"ip(context.addr).isLoopback()"#).unwrap();
+
+        let budget = ExtensionBudget::new(25);
+        let evaluator = Evaluator::new(request.clone(), &entities, Extensions::all_available())
+            .with_extension_budget(&budget);
+        assert!(evaluator.interpret(&expr, &HashMap::new()).is_ok());
+        assert_eq!(budget.spent(), 11);
+        // the budget is shared with other evaluators
+        let evaluator = Evaluator::new(request, &entities, Extensions::all_available())
+            .with_extension_budget(&budget);
+        assert!(evaluator.interpret(&expr, &HashMap::new()).is_ok());
+        assert_eq!(budget.spent(), 22);
+        assert_matches!(
+            evaluator.interpret(&expr, &HashMap::new()),
+            Err(EvaluationError::LimitExceeded(_))
+        );
+        assert_eq!(budget.spent(), 22);
+    }
+}
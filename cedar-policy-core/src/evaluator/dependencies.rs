@@ -0,0 +1,110 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`DependencyTracer`], which records the entity data
+//! an evaluator reads.
+
+use crate::ast::{EntityUID, Literal, Value, ValueKind};
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+/// Records the entities whose data an evaluator reads, i.e., whose attributes
+/// are accessed or tested with `has`, or whose ancestors are checked with
+/// `in`. The result of the evaluation may only change if the data of one of
+/// these entities changes, including if it is added to or removed from the
+/// entities, or if the evaluated expressions or the request change.
+///
+/// Ancestors are stored transitively, so changing the parents of an entity
+/// also changes the data of its descendants.
+#[derive(Debug, Default)]
+pub struct DependencyTracer {
+    entities: RefCell<HashSet<EntityUID>>,
+}
+
+impl DependencyTracer {
+    /// A tracer which has recorded nothing yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the data of the entity `val` is read, if it is an entity
+    pub(crate) fn record(&self, val: &Value) {
+        if let ValueKind::Lit(Literal::EntityUID(uid)) = &val.value {
+            self.record_entity(uid);
+        }
+    }
+
+    /// Record that the data of the entity `uid` is read
+    pub(crate) fn record_entity(&self, uid: &EntityUID) {
+        self.entities.borrow_mut().insert(uid.clone());
+    }
+
+    /// The entities whose data was read so far
+    pub fn into_entities(self) -> HashSet<EntityUID> {
+        self.entities.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{Context, EntityUIDEntry, Request};
+    use crate::entities::{Entities, EntityJsonParser, NoEntitiesSchema, TCComputation};
+    use crate::evaluator::Evaluator;
+    use crate::extensions::Extensions;
+    use crate::parser::parse_expr;
+    use std::collections::HashMap;
+
+    #[test]
+    fn records_entities_read() {
+        let entities: Entities = EntityJsonParser::<NoEntitiesSchema>::new(
+            None,
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "parents": [],
+                  "attrs": { "manager": { "__entity": { "type": "User", "id": "bob" } } } },
+                { "uid": { "type": "User", "id": "bob" }, "parents": [], "attrs": { "level": 5 } }
+            ]"#,
+        )
+        .unwrap();
+        let entry = |uid: &str| EntityUIDEntry::known(uid.parse().unwrap(), None);
+        let request = Request::new_unchecked(
+            entry(r#"User::"alice""#),
+            entry(r#"Action::"view""#),
+            entry(r#"Photo::"p""#),
+            Some(Context::empty()),
+        );
+        let tracer = DependencyTracer::new();
+        let evaluator = Evaluator::new(request, &entities, Extensions::all_available())
+            .with_dependency_tracer(&tracer);
+        let expr = parse_expr(
+            r#"principal.manager.level > 3 && resource in Album::"a" && action == Action::"view""#,
+        )
+        .unwrap();
+        assert!(evaluator.interpret(&expr, &HashMap::new()).is_ok());
+        assert_eq!(
+            tracer.into_entities(),
+            HashSet::from([
+                r#"User::"alice""#.parse().unwrap(),
+                r#"User::"bob""#.parse().unwrap(),
+                r#"Photo::"p""#.parse().unwrap(),
+            ])
+        );
+    }
+}
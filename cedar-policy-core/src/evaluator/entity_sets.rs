@@ -0,0 +1,234 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`CompiledEntitySets`], which lets an evaluator test
+//! membership in large sets of entity uids written in policies with bitsets.
+
+use crate::ast::{EntityUID, Expr, ExprKind, Literal, PolicySet, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Sets with fewer elements are not compiled, since hashing is as fast
+const MIN_COMPILED_SET_LEN: usize = 64;
+
+/// The large set literals of entity uids in a policy set, e.g., allowlists,
+/// compiled to bitsets over a shared numbering of their elements. Evaluating
+/// such a set then doesn't rebuild it, and `contains`, `containsAll`, and
+/// `containsAny` test bits, comparing whole words when both operands are
+/// compiled sets.
+///
+/// Set literals are identified by the address of their elements, which this
+/// keeps alive, so compiled sets are only used for the very set literals they
+/// were compiled from, even when evaluating other policy sets.
+#[derive(Debug, Default)]
+pub struct CompiledEntitySets {
+    /// Number of each element of a compiled set
+    ids: HashMap<EntityUID, usize>,
+    /// Compiled sets by address of their elements
+    sets: HashMap<usize, CompiledSet>,
+}
+
+impl CompiledEntitySets {
+    /// Compile the set literals of at least 64 entity uids in the conditions
+    /// of `policies`
+    pub fn new(policies: &PolicySet) -> Self {
+        let mut compiled = Self::default();
+        for t in policies.all_templates() {
+            for e in t.non_scope_constraints().subexpressions() {
+                if let ExprKind::Set(elems) = e.expr_kind() {
+                    compiled.compile(elems);
+                }
+            }
+        }
+        compiled
+    }
+
+    fn compile(&mut self, elems: &Arc<Vec<Expr>>) {
+        if elems.len() < MIN_COMPILED_SET_LEN || self.sets.contains_key(&address(elems)) {
+            return;
+        }
+        let Some(uids) = elems
+            .iter()
+            .map(|e| match e.expr_kind() {
+                ExprKind::Lit(Literal::EntityUID(uid)) => Some(uid.as_ref().clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<EntityUID>>>()
+        else {
+            return;
+        };
+        let mut bits = BitSet::default();
+        for uid in &uids {
+            let next = self.ids.len();
+            bits.insert(*self.ids.entry(uid.clone()).or_insert(next));
+        }
+        let value = Value::set(uids.into_iter().map(Value::from), None);
+        self.sets.insert(
+            address(elems),
+            CompiledSet {
+                elems: Arc::clone(elems),
+                value,
+                bits,
+            },
+        );
+    }
+
+    /// True if no set literal was compiled
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// The compiled set for `e`, if `e` is a compiled set literal
+    pub(crate) fn get(&self, e: &Expr) -> Option<&CompiledSet> {
+        match e.expr_kind() {
+            ExprKind::Set(elems) => self
+                .sets
+                .get(&address(elems))
+                .filter(|set| Arc::ptr_eq(&set.elems, elems)),
+            _ => None,
+        }
+    }
+
+    /// True if `v` is an element of `set`
+    pub(crate) fn contains(&self, set: &CompiledSet, v: &Value) -> bool {
+        match v.try_as_lit() {
+            Some(Literal::EntityUID(uid)) => self
+                .ids
+                .get(uid.as_ref())
+                .is_some_and(|id| set.bits.contains(*id)),
+            _ => false,
+        }
+    }
+}
+
+fn address(elems: &Arc<Vec<Expr>>) -> usize {
+    Arc::as_ptr(elems) as usize
+}
+
+/// A set literal of entity uids compiled to a bitset
+#[derive(Debug)]
+pub(crate) struct CompiledSet {
+    /// The elements of the set literal, kept alive so that their address
+    /// isn't reused, and compared with the elements of the set literals
+    /// looked up
+    elems: Arc<Vec<Expr>>,
+    /// Value of the set literal
+    value: Value,
+    /// Numbers of the elements
+    bits: BitSet,
+}
+
+impl CompiledSet {
+    /// Value of the set literal
+    pub(crate) fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// True if every element of `other` is an element of `self`
+    pub(crate) fn is_superset(&self, other: &Self) -> bool {
+        other.bits.is_subset(&self.bits)
+    }
+
+    /// True if `self` and `other` have an element in common
+    pub(crate) fn intersects(&self, other: &Self) -> bool {
+        self.bits.intersects(&other.bits)
+    }
+}
+
+/// A set of small numbers, with word-wise operations the compiler can
+/// vectorize
+#[derive(Debug, Default)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn insert(&mut self, n: usize) {
+        let (word, bit) = (n / 64, n % 64);
+        if self.words.len() <= word {
+            self.words.resize(word + 1, 0);
+        }
+        if let Some(w) = self.words.get_mut(word) {
+            *w |= 1 << bit;
+        }
+    }
+
+    fn contains(&self, n: usize) -> bool {
+        self.words
+            .get(n / 64)
+            .is_some_and(|word| word & (1 << (n % 64)) != 0)
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .enumerate()
+            .all(|(i, word)| word & !other.words.get(i).copied().unwrap_or_default() == 0)
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .any(|(w1, w2)| w1 & w2 != 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_policyset;
+    use itertools::Itertools;
+
+    fn set_literal(range: std::ops::Range<usize>) -> String {
+        format!("[{}]", range.map(|i| format!(r#"User::"u{i}""#)).join(", "))
+    }
+
+    #[test]
+    fn compiles_large_entity_sets() {
+        let src = format!(
+            r#"
+            permit(principal, action, resource) when {{ {}.contains(principal) }};
+            permit(principal, action, resource) when {{ {}.containsAny({}) }};
+            permit(principal, action, resource) when {{ {}.contains(principal) }};
+            permit(principal, action, resource) when {{ [principal, User::"u1"].contains(principal) }};
+            "#,
+            set_literal(0..100),
+            set_literal(50..200),
+            set_literal(150..350),
+            set_literal(0..10),
+        );
+        let policies = parse_policyset(&src).unwrap();
+        let compiled = CompiledEntitySets::new(&policies);
+        assert_eq!(compiled.sets.len(), 3);
+        assert_eq!(compiled.ids.len(), 350);
+
+        let mut sets = compiled.sets.values().sorted_by_key(|set| set.elems.len());
+        let (small, medium, large) = (
+            sets.next().unwrap(),
+            sets.next().unwrap(),
+            sets.next().unwrap(),
+        );
+        let user = |eid| Value::from(EntityUID::with_eid_and_type("User", eid).unwrap());
+        assert!(compiled.contains(small, &user("u99")));
+        assert!(!compiled.contains(small, &user("u100")));
+        assert!(!compiled.contains(small, &Value::from(99)));
+        assert!(small.intersects(medium));
+        assert!(!small.intersects(large));
+        assert!(!small.is_superset(medium));
+        assert!(large.is_superset(large));
+    }
+}
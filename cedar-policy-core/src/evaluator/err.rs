@@ -93,6 +93,12 @@ pub enum EvaluationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     RecursionLimit(#[from] evaluation_errors::RecursionLimitError),
+
+    /// Calling an extension function would exceed the cost budget of the
+    /// extension function calls
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    LimitExceeded(#[from] evaluation_errors::LimitExceededError),
 }
 
 impl EvaluationError {
@@ -110,6 +116,7 @@ impl EvaluationError {
             Self::FailedExtensionFunctionExecution(e) => e.source_loc.as_ref(),
             Self::NonValue(e) => e.source_loc.as_ref(),
             Self::RecursionLimit(e) => e.source_loc.as_ref(),
+            Self::LimitExceeded(e) => e.source_loc.as_ref(),
         }
     }
 
@@ -157,6 +164,9 @@ impl EvaluationError {
             Self::RecursionLimit(_) => {
                 Self::RecursionLimit(evaluation_errors::RecursionLimitError { source_loc })
             }
+            Self::LimitExceeded(e) => {
+                Self::LimitExceeded(evaluation_errors::LimitExceededError { source_loc, ..e })
+            }
         }
     }
 
@@ -291,6 +301,16 @@ impl EvaluationError {
     pub(crate) fn recursion_limit(source_loc: Option<Loc>) -> Self {
         evaluation_errors::RecursionLimitError { source_loc }.into()
     }
+
+    /// Construct a [`LimitExceeded`] error
+    pub(crate) fn limit_exceeded(function_name: Name, limit: u64, source_loc: Option<Loc>) -> Self {
+        evaluation_errors::LimitExceededError {
+            function_name,
+            limit,
+            source_loc,
+        }
+        .into()
+    }
 }
 
 /// Error subtypes for [`EvaluationError`]
@@ -643,6 +663,33 @@ pub mod evaluation_errors {
     impl Diagnostic for RecursionLimitError {
         impl_diagnostic_from_source_loc_opt_field!(source_loc);
     }
+
+    /// Calling an extension function would exceed the cost budget of the
+    /// extension function calls
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, PartialEq, Eq, Clone, Error)]
+    #[error("calling extension function `{function_name}` would exceed the extension function budget of {limit}")]
+    pub struct LimitExceededError {
+        /// Extension function which wasn't called
+        pub(crate) function_name: Name,
+        /// The budget
+        pub(crate) limit: u64,
+        /// Source location
+        pub(crate) source_loc: Option<Loc>,
+    }
+
+    impl Diagnostic for LimitExceededError {
+        impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+        fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+            Some(Box::new(
+                "the budget is shared by all the policies evaluated for the request",
+            ))
+        }
+    }
 }
 
 /// Type alias for convenience
@@ -0,0 +1,133 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`SharedSubexpressions`], which lets an evaluator
+//! evaluate identical subexpressions of several policy conditions only once.
+
+use crate::ast::{Expr, ExprKind, PartialValue};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+/// The subexpressions which occur more than once in a set of policy
+/// conditions, e.g., guards shared by generated policies, and their values
+/// for the current request.
+///
+/// Subexpressions are identified by their address, so the conditions must
+/// outlive this and must be the ones which are evaluated. Subexpressions
+/// containing slots are never shared, since their value depends on the
+/// policy. Only successful evaluations are remembered, so that errors are
+/// reported with the source location of each policy.
+#[derive(Debug, Default)]
+pub struct SharedSubexpressions {
+    /// Group of identical subexpressions of each shared subexpression, by
+    /// address
+    groups: HashMap<usize, usize>,
+    /// Values of the groups evaluated so far
+    values: RefCell<HashMap<usize, PartialValue>>,
+}
+
+impl SharedSubexpressions {
+    /// Find the subexpressions which occur more than once in `conditions`
+    pub fn new<'a>(conditions: impl IntoIterator<Item = &'a Expr>) -> Self {
+        // subexpressions by hash of their shape, grouped by equal shape
+        let mut candidates: HashMap<u64, Vec<Vec<&Expr>>> = HashMap::new();
+        for condition in conditions {
+            for e in condition.subexpressions() {
+                if !is_shareable(e) {
+                    continue;
+                }
+                let mut hasher = DefaultHasher::new();
+                e.hash_shape(&mut hasher);
+                let classes = candidates.entry(hasher.finish()).or_default();
+                match classes
+                    .iter_mut()
+                    .find(|class| class.first().is_some_and(|c| c.eq_shape(e)))
+                {
+                    Some(class) => class.push(e),
+                    None => classes.push(vec![e]),
+                }
+            }
+        }
+        let groups = candidates
+            .into_values()
+            .flatten()
+            .filter(|class| class.len() > 1)
+            .enumerate()
+            .flat_map(|(group, class)| class.into_iter().map(move |e| (address(e), group)))
+            .collect();
+        Self {
+            groups,
+            values: RefCell::default(),
+        }
+    }
+
+    /// True if no subexpression is shared
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The value of `e` if it is shared and an identical subexpression has
+    /// been evaluated
+    pub(crate) fn get(&self, e: &Expr) -> Option<PartialValue> {
+        let group = self.groups.get(&address(e))?;
+        self.values.borrow().get(group).cloned()
+    }
+
+    /// Remember `value` as the value of `e`, if `e` is shared
+    pub(crate) fn insert(&self, e: &Expr, value: &PartialValue) {
+        if let Some(group) = self.groups.get(&address(e)) {
+            self.values.borrow_mut().insert(*group, value.clone());
+        }
+    }
+}
+
+fn address(e: &Expr) -> usize {
+    e as *const Expr as usize
+}
+
+/// Whether evaluating `e` only once may save time: it must have
+/// subexpressions, and its value must not depend on the policy
+fn is_shareable(e: &Expr) -> bool {
+    !matches!(
+        e.expr_kind(),
+        ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_)
+    ) && !e
+        .subexpressions()
+        .any(|e| matches!(e.expr_kind(), ExprKind::Slot(_)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_expr;
+
+    #[test]
+    fn finds_shared_subexpressions() {
+        let conditions = [
+            parse_expr(r#"principal.level > 3 && resource.public"#).unwrap(),
+            parse_expr(r#"principal.level > 3 && context.mfa"#).unwrap(),
+            parse_expr(r#"principal in ?principal && context.mfa"#).unwrap(),
+        ];
+        let shared = SharedSubexpressions::new(&conditions);
+        // `principal.level > 3`, which is `!(principal.level <= 3)`,
+        // `principal.level <= 3`, `principal.level`, and `context.mfa`
+        let groups: std::collections::HashSet<_> = shared.groups.values().collect();
+        assert_eq!(groups.len(), 4);
+        assert_eq!(shared.groups.len(), 8);
+    }
+}
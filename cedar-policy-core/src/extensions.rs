@@ -19,6 +19,8 @@
 #[cfg(feature = "ipaddr")]
 pub mod ipaddr;
 
+#[cfg(feature = "bitwise")]
+pub mod bitwise;
 #[cfg(feature = "decimal")]
 pub mod decimal;
 pub mod partial_evaluation;
@@ -42,6 +44,8 @@ lazy_static::lazy_static! {
         ipaddr::extension(),
         #[cfg(feature = "decimal")]
         decimal::extension(),
+        #[cfg(feature = "bitwise")]
+        bitwise::extension(),
         #[cfg(feature = "partial-eval")]
         partial_evaluation::extension(),
     ];
@@ -0,0 +1,205 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the Cedar 'bitwise' extension, which provides bit
+//! operations on `Long` values, e.g., for permission bitmasks.
+//!
+//! All functions are method-style: `context.flags.bitAnd(4)`,
+//! `context.flags.hasBits(0x6)`, `context.flags.shiftLeft(2)`. Bits are those
+//! of the two's complement representation of a `Long`.
+
+use crate::ast::{CallStyle, Extension, ExtensionFunction, ExtensionOutputValue, Value};
+use crate::entities::SchemaType;
+use crate::evaluator;
+
+// PANIC SAFETY All the names are valid names
+#[allow(clippy::expect_used)]
+mod names {
+    use crate::ast::Name;
+    lazy_static::lazy_static! {
+        pub static ref EXTENSION_NAME : Name = Name::parse_unqualified_name("bitwise").expect("should be a valid identifier");
+        pub static ref BIT_AND : Name = Name::parse_unqualified_name("bitAnd").expect("should be a valid identifier");
+        pub static ref BIT_OR : Name = Name::parse_unqualified_name("bitOr").expect("should be a valid identifier");
+        pub static ref BIT_XOR : Name = Name::parse_unqualified_name("bitXor").expect("should be a valid identifier");
+        pub static ref BIT_NOT : Name = Name::parse_unqualified_name("bitNot").expect("should be a valid identifier");
+        pub static ref HAS_BITS : Name = Name::parse_unqualified_name("hasBits").expect("should be a valid identifier");
+        pub static ref SHIFT_LEFT : Name = Name::parse_unqualified_name("shiftLeft").expect("should be a valid identifier");
+        pub static ref SHIFT_RIGHT : Name = Name::parse_unqualified_name("shiftRight").expect("should be a valid identifier");
+    }
+}
+
+/// Number of bits of a `Long`. Shift amounts must be lower than this.
+const LONG_BITS: u32 = i64::BITS;
+
+fn extension_err(msg: impl Into<String>) -> evaluator::EvaluationError {
+    evaluator::EvaluationError::failed_extension_function_application(
+        names::EXTENSION_NAME.clone(),
+        msg.into(),
+        None, // source loc will be added by the evaluator
+    )
+}
+
+/// Cedar function which returns the bitwise and of two `Long`s
+fn bit_and(lhs: Value, rhs: Value) -> evaluator::Result<ExtensionOutputValue> {
+    Ok((lhs.get_as_long()? & rhs.get_as_long()?).into())
+}
+
+/// Cedar function which returns the bitwise or of two `Long`s
+fn bit_or(lhs: Value, rhs: Value) -> evaluator::Result<ExtensionOutputValue> {
+    Ok((lhs.get_as_long()? | rhs.get_as_long()?).into())
+}
+
+/// Cedar function which returns the bitwise exclusive or of two `Long`s
+fn bit_xor(lhs: Value, rhs: Value) -> evaluator::Result<ExtensionOutputValue> {
+    Ok((lhs.get_as_long()? ^ rhs.get_as_long()?).into())
+}
+
+/// Cedar function which returns the bitwise negation of a `Long`
+fn bit_not(arg: Value) -> evaluator::Result<ExtensionOutputValue> {
+    Ok((!arg.get_as_long()?).into())
+}
+
+/// Cedar function which tests whether all the bits set in `mask` are set in
+/// `value`
+fn has_bits(value: Value, mask: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let mask = mask.get_as_long()?;
+    Ok((value.get_as_long()? & mask == mask).into())
+}
+
+/// Check that `amount` is a valid shift amount, i.e., between 0 and 63
+fn shift_amount(amount: &Value) -> evaluator::Result<u32> {
+    let amount = amount.get_as_long()?;
+    match u32::try_from(amount) {
+        Ok(amount) if amount < LONG_BITS => Ok(amount),
+        _ => Err(extension_err(format!(
+            "shift amount must be between 0 and {}, got {amount}",
+            LONG_BITS - 1
+        ))),
+    }
+}
+
+/// Cedar function which shifts a `Long` to the left, discarding the bits
+/// shifted out
+fn shift_left(value: Value, amount: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let amount = shift_amount(&amount)?;
+    Ok((value.get_as_long()? << amount).into())
+}
+
+/// Cedar function which shifts a `Long` to the right, preserving its sign
+fn shift_right(value: Value, amount: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let amount = shift_amount(&amount)?;
+    Ok((value.get_as_long()? >> amount).into())
+}
+
+/// Construct the extension
+pub fn extension() -> Extension {
+    Extension::new(
+        names::EXTENSION_NAME.clone(),
+        vec![
+            ExtensionFunction::binary(
+                names::BIT_AND.clone(),
+                CallStyle::MethodStyle,
+                Box::new(bit_and),
+                SchemaType::Long,
+                (SchemaType::Long, SchemaType::Long),
+            ),
+            ExtensionFunction::binary(
+                names::BIT_OR.clone(),
+                CallStyle::MethodStyle,
+                Box::new(bit_or),
+                SchemaType::Long,
+                (SchemaType::Long, SchemaType::Long),
+            ),
+            ExtensionFunction::binary(
+                names::BIT_XOR.clone(),
+                CallStyle::MethodStyle,
+                Box::new(bit_xor),
+                SchemaType::Long,
+                (SchemaType::Long, SchemaType::Long),
+            ),
+            ExtensionFunction::unary(
+                names::BIT_NOT.clone(),
+                CallStyle::MethodStyle,
+                Box::new(bit_not),
+                SchemaType::Long,
+                SchemaType::Long,
+            ),
+            ExtensionFunction::binary(
+                names::HAS_BITS.clone(),
+                CallStyle::MethodStyle,
+                Box::new(has_bits),
+                SchemaType::Bool,
+                (SchemaType::Long, SchemaType::Long),
+            ),
+            ExtensionFunction::binary(
+                names::SHIFT_LEFT.clone(),
+                CallStyle::MethodStyle,
+                Box::new(shift_left),
+                SchemaType::Long,
+                (SchemaType::Long, SchemaType::Long),
+            ),
+            ExtensionFunction::binary(
+                names::SHIFT_RIGHT.clone(),
+                CallStyle::MethodStyle,
+                Box::new(shift_right),
+                SchemaType::Long,
+                (SchemaType::Long, SchemaType::Long),
+            ),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::test::{basic_entities, basic_request};
+    use crate::evaluator::{EvaluationError, Evaluator};
+    use crate::extensions::Extensions;
+    use crate::parser::parse_expr;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn bit_operations() {
+        let ext_array = [extension()];
+        let exts = Extensions::specific_extensions(&ext_array).unwrap();
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, &exts);
+        let eval_str = |src: &str| eval.interpret_inline_policy(&parse_expr(src).unwrap());
+
+        assert_eq!(eval_str("12.bitAnd(10)"), Ok(Value::from(8)));
+        assert_eq!(eval_str("12.bitOr(10)"), Ok(Value::from(14)));
+        assert_eq!(eval_str("12.bitXor(10)"), Ok(Value::from(6)));
+        assert_eq!(eval_str("0.bitNot()"), Ok(Value::from(-1)));
+        assert_eq!(eval_str("14.hasBits(6)"), Ok(Value::from(true)));
+        assert_eq!(eval_str("12.hasBits(6)"), Ok(Value::from(false)));
+        assert_eq!(eval_str("1.shiftLeft(4)"), Ok(Value::from(16)));
+        assert_eq!(eval_str("1.shiftLeft(63)"), Ok(Value::from(i64::MIN)));
+        assert_eq!(eval_str("(-16).shiftRight(2)"), Ok(Value::from(-4)));
+        assert_matches!(
+            eval_str("1.shiftLeft(64)"),
+            Err(EvaluationError::FailedExtensionFunctionExecution(_))
+        );
+        assert_matches!(
+            eval_str("1.shiftRight(-1)"),
+            Err(EvaluationError::FailedExtensionFunctionExecution(_))
+        );
+        assert_matches!(
+            eval_str(r#"1.bitAnd("1")"#),
+            Err(EvaluationError::TypeError(_))
+        );
+    }
+}
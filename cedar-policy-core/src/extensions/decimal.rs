@@ -270,7 +270,9 @@ pub fn extension() -> Extension {
                 Box::new(decimal_from_str),
                 decimal_type.clone(),
                 SchemaType::String,
-            ),
+            )
+            // parsing the string costs much more than the other functions
+            .with_cost(10),
             ExtensionFunction::binary(
                 constants::LESS_THAN.clone(),
                 CallStyle::MethodStyle,
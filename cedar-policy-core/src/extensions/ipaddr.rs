@@ -398,7 +398,9 @@ pub fn extension() -> Extension {
                 Box::new(ip_from_str),
                 ipaddr_type.clone(),
                 SchemaType::String,
-            ),
+            )
+            // parsing the string costs much more than the other functions
+            .with_cost(10),
             ExtensionFunction::unary(
                 names::IS_IPV4.clone(),
                 CallStyle::MethodStyle,
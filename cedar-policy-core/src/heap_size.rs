@@ -0,0 +1,434 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`HeapSize`], an estimate of the heap memory used by
+//! policies and entities.
+//!
+//! The estimates count the allocations of the data structures, but not the
+//! overhead of the allocator. Data shared behind an `Arc` is counted once for
+//! each reference, except for the templates of a policy set, which are
+//! counted once, and the source text of policies, which is counted once for
+//! each distinct source.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem::{size_of, size_of_val};
+use std::sync::Arc;
+
+use smol_str::SmolStr;
+
+use crate::ast::{
+    ActionConstraint, Annotation, AnyId, Eid, Entity, EntityReference, EntityType, EntityUID, Expr,
+    ExprKind, ExtensionValueWithArgs, Id, InternalName, Literal, Name, PartialValue, Pattern,
+    PatternElem, Policy, PolicyID, PolicySet, PrincipalOrResourceConstraint, RestrictedExpr, Set,
+    Slot, SlotId, Template, Type, Unknown, Value, ValueKind,
+};
+use crate::entities::Entities;
+
+/// Approximate heap memory used by a value
+pub trait HeapSize {
+    /// The approximate number of bytes of heap memory owned by `self`, not
+    /// counting `size_of_val(self)`
+    fn heap_size(&self) -> usize;
+}
+
+impl HeapSize for SmolStr {
+    fn heap_size(&self) -> usize {
+        // short strings are stored inline
+        if self.is_heap_allocated() {
+            self.len()
+        } else {
+            0
+        }
+    }
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Arc<T> {
+    fn heap_size(&self) -> usize {
+        // the strong and weak reference counts
+        2 * size_of::<usize>() + size_of::<T>() + self.as_ref().heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for HashMap<K, V> {
+    fn heap_size(&self) -> usize {
+        // one control byte per bucket
+        self.capacity() * (size_of::<K>() + size_of::<V>() + 1)
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for HashSet<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * (size_of::<T>() + 1) + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        // B-tree nodes are not full, so this undercounts slightly
+        self.len() * (size_of::<K>() + size_of::<V>())
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl HeapSize for Id {
+    fn heap_size(&self) -> usize {
+        SmolStr::new(self.as_ref()).heap_size()
+    }
+}
+
+impl HeapSize for AnyId {
+    fn heap_size(&self) -> usize {
+        SmolStr::new(self.as_ref()).heap_size()
+    }
+}
+
+impl HeapSize for InternalName {
+    fn heap_size(&self) -> usize {
+        self.id.heap_size() + self.path.heap_size()
+    }
+}
+
+impl HeapSize for Name {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
+
+impl HeapSize for EntityType {
+    fn heap_size(&self) -> usize {
+        self.name().heap_size()
+    }
+}
+
+impl HeapSize for Eid {
+    fn heap_size(&self) -> usize {
+        AsRef::<SmolStr>::as_ref(self).heap_size()
+    }
+}
+
+impl HeapSize for EntityUID {
+    fn heap_size(&self) -> usize {
+        self.entity_type().heap_size() + self.eid().heap_size()
+    }
+}
+
+impl HeapSize for PolicyID {
+    fn heap_size(&self) -> usize {
+        SmolStr::new(self.as_ref()).heap_size()
+    }
+}
+
+impl HeapSize for SlotId {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for Literal {
+    fn heap_size(&self) -> usize {
+        match self {
+            Literal::Bool(_) | Literal::Long(_) | Literal::Null => 0,
+            Literal::String(s) => s.heap_size(),
+            Literal::EntityUID(uid) => uid.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Pattern {
+    fn heap_size(&self) -> usize {
+        2 * size_of::<usize>() + size_of::<Vec<PatternElem>>() + size_of_val(self.get_elems())
+    }
+}
+
+impl HeapSize for Type {
+    fn heap_size(&self) -> usize {
+        match self {
+            Type::Entity { ty } => ty.heap_size(),
+            Type::Extension { name } => name.heap_size(),
+            _ => 0,
+        }
+    }
+}
+
+impl HeapSize for Unknown {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size() + self.type_annotation.heap_size()
+    }
+}
+
+impl<T> HeapSize for Expr<T> {
+    fn heap_size(&self) -> usize {
+        match self.expr_kind() {
+            ExprKind::Lit(lit) => lit.heap_size(),
+            ExprKind::Var(_) | ExprKind::Slot(_) => 0,
+            ExprKind::Unknown(unknown) => unknown.heap_size(),
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => test_expr.heap_size() + then_expr.heap_size() + else_expr.heap_size(),
+            ExprKind::And { left, right } | ExprKind::Or { left, right } => {
+                left.heap_size() + right.heap_size()
+            }
+            ExprKind::UnaryApp { arg, .. } => arg.heap_size(),
+            ExprKind::BinaryApp { arg1, arg2, .. } => arg1.heap_size() + arg2.heap_size(),
+            ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                fn_name.heap_size() + args.heap_size()
+            }
+            ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr } => {
+                expr.heap_size() + attr.heap_size()
+            }
+            ExprKind::Like { expr, pattern } => expr.heap_size() + pattern.heap_size(),
+            ExprKind::Is { expr, entity_type } => expr.heap_size() + entity_type.heap_size(),
+            ExprKind::Set(elems) => elems.heap_size(),
+            ExprKind::Record(fields) => fields.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Value {
+    fn heap_size(&self) -> usize {
+        self.value.heap_size()
+    }
+}
+
+impl HeapSize for ValueKind {
+    fn heap_size(&self) -> usize {
+        match self {
+            ValueKind::Lit(lit) => lit.heap_size(),
+            ValueKind::Set(set) => set.heap_size(),
+            ValueKind::Record(fields) => fields.heap_size(),
+            ValueKind::ExtensionValue(ev) => ev.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Set {
+    fn heap_size(&self) -> usize {
+        let authoritative = 2 * size_of::<usize>()
+            + self.authoritative.len() * size_of::<Value>()
+            + self
+                .authoritative
+                .iter()
+                .map(HeapSize::heap_size)
+                .sum::<usize>();
+        authoritative + self.fast.heap_size()
+    }
+}
+
+impl HeapSize for ExtensionValueWithArgs {
+    fn heap_size(&self) -> usize {
+        // the extension value itself is opaque; the arguments it was
+        // constructed from are a good proxy for its size
+        self.constructor.heap_size()
+            + self.args.capacity() * size_of::<RestrictedExpr>()
+            + self.args.iter().map(|arg| arg.heap_size()).sum::<usize>()
+    }
+}
+
+impl HeapSize for PartialValue {
+    fn heap_size(&self) -> usize {
+        match self {
+            PartialValue::Value(v) => v.heap_size(),
+            PartialValue::Residual(e) => e.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Annotation {
+    fn heap_size(&self) -> usize {
+        self.val.heap_size()
+    }
+}
+
+impl HeapSize for EntityReference {
+    fn heap_size(&self) -> usize {
+        match self {
+            EntityReference::EUID(uid) => uid.heap_size(),
+            EntityReference::Slot => 0,
+        }
+    }
+}
+
+impl HeapSize for PrincipalOrResourceConstraint {
+    fn heap_size(&self) -> usize {
+        match self {
+            PrincipalOrResourceConstraint::Any => 0,
+            PrincipalOrResourceConstraint::In(r) | PrincipalOrResourceConstraint::Eq(r) => {
+                r.heap_size()
+            }
+            PrincipalOrResourceConstraint::Is(ty) => ty.heap_size(),
+            PrincipalOrResourceConstraint::IsIn(ty, r) => ty.heap_size() + r.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for ActionConstraint {
+    fn heap_size(&self) -> usize {
+        match self {
+            ActionConstraint::Any => 0,
+            ActionConstraint::In(uids) => uids.heap_size(),
+            ActionConstraint::Eq(uid) => uid.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Template {
+    fn heap_size(&self) -> usize {
+        let annotations = 2 * size_of::<usize>()
+            + self
+                .annotations()
+                .map(|(k, v)| {
+                    size_of::<AnyId>() + size_of::<Annotation>() + k.heap_size() + v.heap_size()
+                })
+                .sum::<usize>();
+        self.id().heap_size()
+            + annotations
+            + self.principal_constraint().as_inner().heap_size()
+            + self.action_constraint().heap_size()
+            + self.resource_constraint().as_inner().heap_size()
+            + 2 * size_of::<usize>()
+            + size_of::<Expr>()
+            + self.non_scope_constraints().heap_size()
+            + self.slots().count() * size_of::<Slot>()
+    }
+}
+
+impl HeapSize for PolicySet {
+    /// Each template, and each distinct policy source text, is counted once
+    fn heap_size(&self) -> usize {
+        let arc_template = 2 * size_of::<usize>() + size_of::<Template>();
+        let templates = self
+            .all_templates()
+            .map(|t| {
+                size_of::<PolicyID>()
+                    + size_of::<Arc<Template>>()
+                    + arc_template
+                    + t.heap_size()
+                    // `template_to_links_map`
+                    + size_of::<PolicyID>()
+                    + size_of::<HashSet<PolicyID>>()
+            })
+            .sum::<usize>();
+        let links = self
+            .policies()
+            .map(|p| {
+                // `links` and `template_to_links_map`, not counting the
+                // template, which is shared
+                2 * size_of::<PolicyID>()
+                    + size_of::<Policy>()
+                    + p.id().heap_size()
+                    + p.env().heap_size()
+            })
+            .sum::<usize>();
+        let mut sources = HashSet::new();
+        let source_text = self
+            .all_templates()
+            .filter_map(|t| t.loc())
+            .filter(|loc| sources.insert(Arc::as_ptr(&loc.src).cast::<u8>()))
+            .map(|loc| loc.src.len())
+            .sum::<usize>();
+        templates + links + source_text
+    }
+}
+
+impl HeapSize for Entity {
+    fn heap_size(&self) -> usize {
+        let attrs = self
+            .attrs()
+            .map(|(k, v)| {
+                size_of::<SmolStr>() + size_of::<PartialValue>() + k.heap_size() + v.heap_size()
+            })
+            .sum::<usize>();
+        // ancestor sets may be shared between entities, but are counted for
+        // each entity
+        let ancestors = 2 * size_of::<usize>()
+            + size_of::<HashSet<EntityUID>>()
+            + self
+                .ancestors()
+                .map(|a| size_of::<EntityUID>() + 1 + a.heap_size())
+                .sum::<usize>();
+        self.uid().heap_size() + attrs + ancestors
+    }
+}
+
+impl HeapSize for Entities {
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|e| {
+                size_of::<EntityUID>()
+                    + size_of::<Entity>()
+                    + 1
+                    + e.uid().heap_size()
+                    + e.heap_size()
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_policyset;
+
+    #[test]
+    fn grows_with_policies() {
+        let small = parse_policyset(r#"permit(principal, action, resource);"#).unwrap();
+        let large = parse_policyset(
+            &(0..100)
+                .map(|i| {
+                    format!(
+                        r#"permit(principal == User::"a-rather-long-user-name-{i}", action, resource) when {{ context.x like "*{i}*" }};"#
+                    )
+                })
+                .collect::<String>(),
+        )
+        .unwrap();
+        assert!(small.heap_size() > 0);
+        assert!(large.heap_size() > 10 * small.heap_size());
+    }
+
+    #[test]
+    fn short_strings_are_inline() {
+        assert_eq!(SmolStr::new("short").heap_size(), 0);
+        assert_eq!(SmolStr::new("x".repeat(100)).heap_size(), 100);
+    }
+}
@@ -25,6 +25,9 @@ extern crate lalrpop_util;
 
 pub mod ast;
 pub mod authorizer;
+pub mod canonicalizer;
+#[cfg(feature = "codegen")]
+pub mod codegen;
 mod from_normalized_str;
 pub use from_normalized_str::*;
 pub mod entities;
@@ -33,8 +36,12 @@ mod error_macros;
 pub mod est;
 pub mod evaluator;
 pub mod extensions;
+#[cfg(feature = "heap-size")]
+pub mod heap_size;
 pub mod jsonvalue;
+pub mod optimizer;
 pub mod parser;
+pub mod stable_ast;
 pub mod transitive_closure;
 
 #[cfg(any(test, feature = "test-util"))]
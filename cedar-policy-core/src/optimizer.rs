@@ -0,0 +1,371 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains an optimization pass over policy conditions, which
+//! folds constant expressions and removes redundant boolean operators.
+//!
+//! The pass preserves the result of evaluating a condition for every request,
+//! including evaluation errors, for conditions which pass validation. In
+//! particular, the rewrites `!!e` to `e` and `true && e` to `e` rely on `e`
+//! being a boolean, which validation guarantees. Expressions whose evaluation
+//! fails (e.g., overflowing arithmetic) are never folded, so the optimized
+//! condition fails in the same way.
+//!
+//! The same pass simplifies the residuals of partial evaluation, where it also
+//! removes repeated operands of `&&` and `||` chains and repeated elements of
+//! sets, e.g., repeated conditions on the same unknown.
+//!
+//! Cedar has no construct to bind the value of an expression to a name, so
+//! common subexpressions cannot be hoisted out of a condition.
+
+use crate::ast::{
+    BinaryOp, EntityUIDEntry, Expr, ExprKind, Literal, PartialValue, Request, SlotEnv, UnaryOp,
+};
+use crate::entities::Entities;
+use crate::evaluator::Evaluator;
+use crate::extensions::Extensions;
+use std::sync::Arc;
+
+/// Optimize the policy condition `expr`. See the module documentation for the
+/// guarantees of the optimization.
+pub fn optimize(expr: &Expr) -> Expr {
+    optimize_with(expr, false)
+}
+
+/// Simplify the residual `residual` of partially evaluating a policy
+/// condition, to make it easier to convert to, e.g., a query filter. In
+/// addition to the optimizations of [`optimize`], with the same guarantees
+/// for every value of the unknowns, operands of `&&` and `||` chains and set
+/// elements which repeat an earlier one are removed.
+pub fn simplify_residual(residual: &Expr) -> Expr {
+    optimize_with(residual, true)
+}
+
+fn optimize_with(expr: &Expr, deduplicate: bool) -> Expr {
+    let entities = Entities::new();
+    let request = Request::new_unchecked(
+        EntityUIDEntry::Unknown { loc: None },
+        EntityUIDEntry::Unknown { loc: None },
+        EntityUIDEntry::Unknown { loc: None },
+        None,
+    );
+    let evaluator = Evaluator::new(request, &entities, Extensions::all_available());
+    Optimizer {
+        evaluator,
+        deduplicate,
+    }
+    .optimize(expr)
+}
+
+struct Optimizer<'e> {
+    /// Evaluator with no request and no entities, used to fold constant
+    /// expressions. Expressions which depend on either are not folded.
+    evaluator: Evaluator<'e>,
+    /// Whether to remove repeated operands of `&&` and `||` chains and
+    /// repeated set elements, and to fold expressions into residuals, e.g.,
+    /// `unknown("a")` into the unknown `a`
+    deduplicate: bool,
+}
+
+impl Optimizer<'_> {
+    fn optimize(&self, expr: &Expr) -> Expr {
+        let loc = expr.source_loc().cloned();
+        // Subexpressions which `expr` simplifies to are returned directly, so
+        // that they keep their own source location
+        let optimized = match expr.expr_kind() {
+            ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+                return expr.clone()
+            }
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => match self.optimize(test_expr) {
+                e if as_bool(&e) == Some(true) => return self.optimize(then_expr),
+                e if as_bool(&e) == Some(false) => return self.optimize(else_expr),
+                test_expr => Expr::ite(
+                    test_expr,
+                    self.optimize(then_expr),
+                    self.optimize(else_expr),
+                ),
+            },
+            ExprKind::And { .. } if self.deduplicate => self.optimize_chain(expr, true),
+            ExprKind::Or { .. } if self.deduplicate => self.optimize_chain(expr, false),
+            ExprKind::And { left, right } => match self.optimize(left) {
+                e if as_bool(&e) == Some(false) => e,
+                e if as_bool(&e) == Some(true) => return self.optimize(right),
+                left => Expr::and(left, self.optimize(right)),
+            },
+            ExprKind::Or { left, right } => match self.optimize(left) {
+                e if as_bool(&e) == Some(true) => e,
+                e if as_bool(&e) == Some(false) => return self.optimize(right),
+                left => Expr::or(left, self.optimize(right)),
+            },
+            ExprKind::UnaryApp {
+                op: UnaryOp::Not,
+                arg,
+            } => {
+                if let ExprKind::UnaryApp {
+                    op: UnaryOp::Not,
+                    arg: inner,
+                } = arg.expr_kind()
+                {
+                    return self.optimize(inner);
+                }
+                let arg = self.optimize(arg);
+                match arg.expr_kind() {
+                    ExprKind::UnaryApp {
+                        op: UnaryOp::Not,
+                        arg: inner,
+                    } => inner.as_ref().clone(),
+                    _ => Expr::not(arg),
+                }
+            }
+            ExprKind::UnaryApp { op, arg } => Expr::unary_app(*op, self.optimize(arg)),
+            ExprKind::BinaryApp { op, arg1, arg2 } => {
+                Expr::binary_app(*op, self.optimize(arg1), self.optimize(arg2))
+            }
+            ExprKind::ExtensionFunctionApp { fn_name, args } => Expr::call_extension_fn(
+                fn_name.clone(),
+                args.iter().map(|arg| self.optimize(arg)).collect(),
+            ),
+            ExprKind::GetAttr { expr, attr } => Expr::get_attr(self.optimize(expr), attr.clone()),
+            ExprKind::HasAttr { expr, attr } => Expr::has_attr(self.optimize(expr), attr.clone()),
+            ExprKind::Like { expr, pattern } => {
+                Expr::like(self.optimize(expr), pattern.iter().cloned())
+            }
+            ExprKind::Is { expr, entity_type } => {
+                Expr::is_entity_type(self.optimize(expr), entity_type.clone())
+            }
+            ExprKind::Set(elems) if self.deduplicate => {
+                let mut optimized: Vec<Expr> = Vec::with_capacity(elems.len());
+                for e in elems.iter() {
+                    let e = self.optimize(e);
+                    if !optimized.iter().any(|o| o.eq_shape(&e)) {
+                        optimized.push(e);
+                    }
+                }
+                Expr::set(optimized)
+            }
+            ExprKind::Set(elems) => Expr::set(elems.iter().map(|e| self.optimize(e))),
+            ExprKind::Record(attrs) => Expr::record_arc(Arc::new(
+                attrs
+                    .iter()
+                    .map(|(k, v)| (k.clone(), self.optimize(v)))
+                    .collect(),
+            )),
+        }
+        .with_maybe_source_loc(loc.clone());
+        self.fold(optimized).with_maybe_source_loc(loc)
+    }
+
+    /// Optimize the chain of `&&` (if `is_and`) or `||` operators `expr`,
+    /// removing operands which repeat an earlier one
+    fn optimize_chain(&self, expr: &Expr, is_and: bool) -> Expr {
+        let mut operands: Vec<Expr> = Vec::new();
+        for operand in chain_operands(expr, is_and) {
+            let operand = self.optimize(operand);
+            match as_bool(&operand) {
+                // `true && e` is `e`, and `false || e` is `e`
+                Some(b) if b == is_and => continue,
+                // the operands after `false &&` or `true ||` are never evaluated
+                Some(_) => {
+                    operands.push(operand);
+                    break;
+                }
+                None if operands.iter().any(|o| o.eq_shape(&operand)) => continue,
+                None => operands.push(operand),
+            }
+        }
+        operands
+            .into_iter()
+            .reduce(|left, right| {
+                if is_and {
+                    Expr::and(left, right)
+                } else {
+                    Expr::or(left, right)
+                }
+            })
+            .unwrap_or_else(|| Expr::val(is_and))
+    }
+
+    /// Evaluate `expr` if its operands are constants and it does not depend on
+    /// the entity store
+    fn fold(&self, expr: Expr) -> Expr {
+        let foldable = match expr.expr_kind() {
+            ExprKind::Lit(_)
+            | ExprKind::Var(_)
+            | ExprKind::Slot(_)
+            | ExprKind::Unknown(_)
+            | ExprKind::Record(_) => false,
+            // `in` and attribute accesses on entities require the entity store
+            ExprKind::BinaryApp {
+                op: BinaryOp::In, ..
+            } => false,
+            ExprKind::GetAttr { expr, .. } | ExprKind::HasAttr { expr, .. } => {
+                matches!(expr.expr_kind(), ExprKind::Record(_)) && is_constant(expr)
+            }
+            _ => expr.subexpressions().skip(1).all(is_constant_leaf),
+        };
+        if !foldable {
+            return expr;
+        }
+        match self.evaluator.partial_interpret(&expr, &SlotEnv::new()) {
+            Ok(PartialValue::Value(value)) => Expr::from(value),
+            // a call of the `unknown` extension function which partial
+            // evaluation left unevaluated, e.g., after a residual `&&` operand
+            Ok(PartialValue::Residual(residual)) if self.deduplicate => residual,
+            _ => expr,
+        }
+    }
+}
+
+/// The boolean value of `expr`, if it is a boolean literal
+fn as_bool(expr: &Expr) -> Option<bool> {
+    match expr.expr_kind() {
+        ExprKind::Lit(Literal::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+/// The operands of the chain of `&&` (if `is_and`) or `||` operators `expr`,
+/// in evaluation order
+fn chain_operands(expr: &Expr, is_and: bool) -> Vec<&Expr> {
+    match (expr.expr_kind(), is_and) {
+        (ExprKind::And { left, right }, true) | (ExprKind::Or { left, right }, false) => {
+            let mut operands = chain_operands(left, is_and);
+            operands.extend(chain_operands(right, is_and));
+            operands
+        }
+        _ => vec![expr],
+    }
+}
+
+/// Whether `expr` is a constant value: a literal, or a set, record, or
+/// extension function call of constants
+fn is_constant(expr: &Expr) -> bool {
+    expr.subexpressions().all(is_constant_leaf)
+}
+
+/// Whether `expr` may be part of a constant value
+fn is_constant_leaf(expr: &Expr) -> bool {
+    matches!(
+        expr.expr_kind(),
+        ExprKind::Lit(_)
+            | ExprKind::Set(_)
+            | ExprKind::Record(_)
+            | ExprKind::ExtensionFunctionApp { .. }
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_expr;
+
+    #[track_caller]
+    fn assert_optimizes_to(src: &str, expected: &str) {
+        let optimized = optimize(&parse_expr(src).unwrap());
+        assert_eq!(
+            optimized.to_string(),
+            parse_expr(expected).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn folds_constants() {
+        assert_optimizes_to("1 + 2 * 3", "7");
+        assert_optimizes_to(r#""abc" like "a*""#, "true");
+        assert_optimizes_to("[1, 2, 1] == [2, 1]", "true");
+        assert_optimizes_to("[1, 1 + 1, 2]", "[1, 2]");
+        assert_optimizes_to("{a: 1 + 1}.a", "2");
+        assert_optimizes_to(r#"User::"alice" is User"#, "true");
+        #[cfg(feature = "decimal")]
+        assert_optimizes_to(r#"decimal("1.5").lessThan(decimal("2.0"))"#, "true");
+        assert_optimizes_to("principal.age + (1 + 1)", "principal.age + 2");
+    }
+
+    #[test]
+    fn does_not_fold_entity_store_lookups() {
+        assert_optimizes_to(
+            r#"User::"alice" in Group::"admins""#,
+            r#"User::"alice" in Group::"admins""#,
+        );
+        assert_optimizes_to(r#"User::"alice".age > 3"#, r#"User::"alice".age > 3"#);
+        assert_optimizes_to(r#"User::"alice" has age"#, r#"User::"alice" has age"#);
+    }
+
+    #[test]
+    fn does_not_fold_errors() {
+        assert_optimizes_to(
+            "9223372036854775807 + 1 == 0",
+            "9223372036854775807 + 1 == 0",
+        );
+        #[cfg(feature = "decimal")]
+        assert_optimizes_to(r#"decimal("abc")"#, r#"decimal("abc")"#);
+    }
+
+    #[test]
+    fn simplifies_booleans() {
+        assert_optimizes_to("!!principal.admin", "principal.admin");
+        assert_optimizes_to("!!!principal.admin", "!principal.admin");
+        assert_optimizes_to("true && principal.admin", "principal.admin");
+        assert_optimizes_to("(1 > 2) && principal.admin", "false");
+        assert_optimizes_to("(1 < 2) || principal.admin", "true");
+        assert_optimizes_to("false || principal.admin", "principal.admin");
+        assert_optimizes_to("if 1 < 2 then principal.a else principal.b", "principal.a");
+        // the right operand is evaluated when the left one is not a constant,
+        // so it may not be dropped
+        assert_optimizes_to("principal.admin && false", "principal.admin && false");
+    }
+
+    #[track_caller]
+    #[cfg(feature = "partial-eval")]
+    fn assert_simplifies_to(src: &str, expected: &str) {
+        let simplified = simplify_residual(&parse_expr(src).unwrap());
+        assert_eq!(
+            simplified.to_string(),
+            parse_expr(expected).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "partial-eval")]
+    fn simplifies_residuals() {
+        assert_simplifies_to(
+            r#"true && (true && unknown("a")) && 1 < 2"#,
+            r#"unknown("a")"#,
+        );
+        assert_simplifies_to(
+            r#"unknown("a") && (unknown("b") && unknown("a"))"#,
+            r#"unknown("a") && unknown("b")"#,
+        );
+        assert_simplifies_to(
+            r#"false || unknown("a") || unknown("a") || true || unknown("b")"#,
+            r#"unknown("a") || true"#,
+        );
+        assert_simplifies_to(
+            r#"unknown("a") in [Group::"g", unknown("b"), Group::"g", unknown("b")]"#,
+            r#"unknown("a") in [Group::"g", unknown("b")]"#,
+        );
+        assert_simplifies_to("true && (1 < 2 || unknown(\"a\"))", "true");
+        // repeated operands are only removed from residuals
+        assert_optimizes_to(
+            r#"unknown("a") && unknown("a")"#,
+            r#"unknown("a") && unknown("a")"#,
+        );
+    }
+}
@@ -20,6 +20,9 @@
 pub mod cst;
 /// Step two: convert CST to package AST
 mod cst_to_ast;
+/// Expansion of named conditions and constants in the CST
+mod defs;
+pub use defs::Constants;
 /// error handling utilities
 pub mod err;
 /// implementations for formatting, like `Display`
@@ -31,6 +34,8 @@ pub use loc::Loc;
 /// Metadata wrapper for CST Nodes
 mod node;
 pub use node::Node;
+/// Desugaring of optional attribute accesses in the CST
+mod optional_attrs;
 /// Step one: Convert text to CST
 pub mod text_to_cst;
 /// Utility functions to unescape string literals
@@ -39,6 +44,7 @@ pub mod unescape;
 pub mod util;
 
 use smol_str::SmolStr;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use crate::ast;
@@ -52,16 +58,35 @@ pub fn parse_policyset(text: &str) -> Result<ast::PolicySet, err::ParseErrors> {
     cst.to_policyset()
 }
 
-/// Like `parse_policyset()`, but also returns the (lossless) original text of
-/// each individual policy.
+/// Parse a constants file, made of `const NAME = value;` definitions
+pub fn parse_constants(text: &str) -> Result<Constants, err::ParseErrors> {
+    let cst = text_to_cst::parse_constants(text)?;
+    cst.to_constants()
+}
+
+/// Like `parse_policyset()`, where the policies may reference `constants`
+pub fn parse_policyset_with_constants(
+    text: &str,
+    constants: &Constants,
+) -> Result<ast::PolicySet, err::ParseErrors> {
+    let cst = text_to_cst::parse_policies(text)?;
+    cst.to_policyset_with_constants(constants)
+}
+
+/// Like `parse_policyset_with_constants()`, but also returns the (lossless)
+/// original text of each individual policy. The text of a policy which
+/// references named conditions (`def` blocks) or constants is instead that of
+/// the expanded policy, so that it can be parsed on its own.
 /// INVARIANT: The `PolicyId` of every `Policy` and `Template` returned by the
 /// `policies()` and `templates()` methods on the returned `Policy` _must_
 /// appear as a key in the returned map.
-pub fn parse_policyset_and_also_return_policy_text(
-    text: &str,
-) -> Result<(HashMap<ast::PolicyID, &str>, ast::PolicySet), err::ParseErrors> {
+pub fn parse_policyset_and_also_return_policy_text<'a>(
+    text: &'a str,
+    constants: &Constants,
+) -> Result<(HashMap<ast::PolicyID, Cow<'a, str>>, ast::PolicySet), err::ParseErrors> {
     let cst = text_to_cst::parse_policies(text)?;
-    let pset = cst.to_policyset()?;
+    let pset = cst.to_policyset_with_constants(constants)?;
+    let defs = cst.to_defs(constants)?;
     // PANIC SAFETY Shouldn't be `none` since `parse_policies()` and `to_policyset()` didn't return `Err`
     #[allow(clippy::expect_used)]
     // PANIC SAFETY Indexing is safe because of how the `SourceSpan` is constructed
@@ -74,8 +99,22 @@ pub fn parse_policyset_and_also_return_policy_text(
     let texts = cst
         .with_generated_policyids()
         .expect("shouldn't be None since parse_policies() and to_policyset() didn't return Err")
-        .map(|(id, policy)| (id, &text[policy.loc.start()..policy.loc.end()]))
-        .collect::<HashMap<ast::PolicyID, &str>>();
+        .map(|(id, policy)| match defs.expand(policy) {
+            Cow::Borrowed(_) => (
+                id,
+                Cow::Borrowed(&text[policy.loc.start()..policy.loc.end()]),
+            ),
+            Cow::Owned(expanded) => (
+                id,
+                Cow::Owned(
+                    expanded
+                        .node
+                        .expect("shouldn't be None since to_policyset() didn't return Err")
+                        .to_string(),
+                ),
+            ),
+        })
+        .collect::<HashMap<ast::PolicyID, Cow<'_, str>>>();
     Ok((texts, pset))
 }
 
@@ -87,13 +126,18 @@ pub fn parse_policyset_to_ests_and_pset(
 ) -> Result<(HashMap<ast::PolicyID, est::Policy>, ast::PolicySet), err::ParseErrors> {
     let cst = text_to_cst::parse_policies(text)?;
     let pset = cst.to_policyset()?;
+    let defs = cst.to_defs(&Constants::default())?;
     // PANIC SAFETY Shouldn't be `None` since `parse_policies()` and `to_policyset()` didn't return `Err`
     #[allow(clippy::expect_used)]
     let ests = cst
         .with_generated_policyids()
         .expect("missing policy set node")
         .map(|(id, policy)| {
-            let p = policy.node.as_ref().expect("missing policy node").clone();
+            let p = defs
+                .expand(policy)
+                .into_owned()
+                .node
+                .expect("missing policy node");
             Ok((id, p.try_into()?))
         })
         .collect::<Result<HashMap<ast::PolicyID, est::Policy>, err::ParseErrors>>()?;
@@ -392,8 +436,8 @@ mod tests {
         "#;
         let errs = parse_policyset(src).expect_err("expected parsing to fail");
         let unrecognized_tokens = vec![
-            ("or", "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `==`, `>`, `>=`, `[`, `||`, `}`, `has`, `in`, `is`, or `like`"),
-            ("if", "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `==`, `>`, `>=`, `[`, `||`, `}`, `has`, `in`, `is`, or `like`"),
+            ("or", "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `=`, `==`, `>`, `>=`, `[`, `||`, `}`, `has`, `in`, `is`, or `like`"),
+            ("if", "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `=`, `==`, `>`, `>=`, `[`, `||`, `}`, `has`, `in`, `is`, or `like`"),
         ];
         for (token, label) in unrecognized_tokens {
             expect_some_error_matches(
@@ -635,23 +679,54 @@ mod tests {
         assert_eq!(pset.policies().count(), 2);
         assert_eq!(pset.static_policies().count(), 2);
         let (texts, pset) =
-            parse_policyset_and_also_return_policy_text(multiple_policies).expect("Should parse");
+            parse_policyset_and_also_return_policy_text(multiple_policies, &Constants::default())
+                .expect("Should parse");
         assert_eq!(pset.policies().count(), 2);
         assert_eq!(pset.static_policies().count(), 2);
         assert_eq!(texts.len(), 2);
         assert_eq!(
             texts.get(&PolicyID::from_string("policy0")),
-            Some(
-                &r#"permit(principal, action, resource)
+            Some(&Cow::Borrowed(
+                r#"permit(principal, action, resource)
             when { principal == resource.owner };"#
-            )
+            ))
         );
         assert_eq!(
             texts.get(&PolicyID::from_string("policy1")),
-            Some(
-                &r#"forbid(principal, action == Action::"modify", resource) // a comment
+            Some(&Cow::Borrowed(
+                r#"forbid(principal, action == Action::"modify", resource) // a comment
             when { resource . highSecurity };"#
-            )
+            ))
+        );
+    }
+
+    #[test]
+    fn policy_text_with_named_conditions() {
+        use crate::ast::PolicyID;
+
+        let src = r#"
+            def isOwner = principal == resource.owner;
+            permit(principal, action, resource) when { isOwner };
+            forbid(principal, action, resource) when { resource.private };
+        "#;
+        let (texts, _) = parse_policyset_and_also_return_policy_text(src, &Constants::default())
+            .expect("Should parse");
+        // the text of a policy referencing a definition is the expanded policy
+        let text = texts.get(&PolicyID::from_string("policy0")).unwrap();
+        let policy = parse_policy(None, text).expect("Should parse on its own");
+        let expected = parse_policy(
+            None,
+            "permit(principal, action, resource) when { (principal == resource.owner) };",
+        )
+        .unwrap();
+        assert!(policy
+            .non_scope_constraints()
+            .eq_shape(expected.non_scope_constraints()));
+        assert_eq!(
+            texts.get(&PolicyID::from_string("policy1")),
+            Some(&Cow::Borrowed(
+                "forbid(principal, action, resource) when { resource.private };"
+            ))
         );
     }
 
@@ -1089,7 +1164,7 @@ mod tests {
             "permit(principal, action, resource) when {",
             "unexpected end of input",
             "",
-            "expected `!`, `(`, `-`, `[`, `{`, `}`, `false`, identifier, `if`, number, `?principal`, `?resource`, string literal, or `true`",
+            "expected `!`, `(`, `-`, `[`, `{`, `}`, `false`, identifier, `if`, number, `?principal`, raw string literal, `?resource`, string literal, or `true`",
         );
         // The right operand of an `is` gets parsed as any `Expr`, so we will
         // list out all the possible expression tokens even though _only_
@@ -1099,7 +1174,7 @@ mod tests {
             "permit(principal, action, resource) when { principal is",
             "unexpected end of input",
             "",
-            "expected `!`, `(`, `-`, `[`, `{`, `false`, identifier, `if`, number, `?principal`, `?resource`, string literal, or `true`",
+            "expected `!`, `(`, `-`, `[`, `{`, `false`, identifier, `if`, number, `?principal`, raw string literal, `?resource`, string literal, or `true`",
         );
 
         // We expect binary operators, but don't claim to expect `=`, `%` or
@@ -1109,7 +1184,7 @@ mod tests {
             "permit(principal, action, resource) when { if true",
             "unexpected end of input",
             "",
-            "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `==`, `>`, `>=`, `[`, `||`, `has`, `in`, `is`, `like`, or `then`",
+            "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `=`, `==`, `>`, `>=`, `[`, `||`, `has`, `in`, `is`, `like`, or `then`",
         )
     }
 
@@ -1157,4 +1232,47 @@ mod tests {
         // invalid escape `\a` and empty unicode escape
         test_invalid(r"\aaa\u{}", vec!["\\a", "\\u{}"]);
     }
+
+    #[test]
+    fn raw_strings() {
+        // the content of raw strings is not processed for escapes
+        assert_eq!(
+            parse_literal(r#"r"C:\Users\*""#),
+            Ok(Literal::String(r"C:\Users\*".into()))
+        );
+        assert_eq!(
+            parse_literal(r##"r#"say "hi" \n"#"##),
+            Ok(Literal::String(r#"say "hi" \n"#.into()))
+        );
+        assert_eq!(
+            parse_literal(r##"r#"a"""#"##),
+            Ok(Literal::String(r#"a"""#.into()))
+        );
+        assert_eq!(parse_literal(r#"r"""#), Ok(Literal::String("".into())));
+
+        // `*` is always a wildcard in raw patterns, and backslashes are literal
+        let e = parse_expr(r#""C:\\Users\\x" like r"C:\Users\*""#).unwrap();
+        assert!(e.eq_shape(&ast::Expr::like(
+            ast::Expr::val(r"C:\Users\x"),
+            [
+                ast::PatternElem::Char('C'),
+                ast::PatternElem::Char(':'),
+                ast::PatternElem::Char('\\'),
+                ast::PatternElem::Char('U'),
+                ast::PatternElem::Char('s'),
+                ast::PatternElem::Char('e'),
+                ast::PatternElem::Char('r'),
+                ast::PatternElem::Char('s'),
+                ast::PatternElem::Char('\\'),
+                ast::PatternElem::Wildcard,
+            ]
+        )));
+
+        // raw strings are printed back as raw strings
+        let cst = text_to_cst::parse_expr(r##"r#"say "hi""# == r"a\b""##).unwrap();
+        assert_eq!(
+            cst.as_inner().unwrap().to_string(),
+            r##"r#"say "hi""# == r"a\b""##
+        );
+    }
 }
@@ -21,9 +21,29 @@ use smol_str::SmolStr;
 // still recover other parts
 type Node<N> = super::node::Node<Option<N>>;
 
-/// The set of policy statements that forms a policy set
+/// The set of policy statements that forms a policy set, and the named
+/// conditions they may reference
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Policies(pub Vec<Node<Policy>>);
+pub struct Policies(pub Vec<Node<Policy>>, pub Vec<Node<Def>>);
+
+/// Named condition, `def name = expr;`, which the policies of the policy set
+/// may reference as `name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Def {
+    /// annotations, e.g., to document the condition; they are not used
+    pub annotations: Vec<Node<Annotation>>,
+    /// initial ident, expected to be "def", or "const" in [`Constants`]
+    pub keyword: Node<Ident>,
+    /// name of the condition
+    pub name: Node<Ident>,
+    /// the condition
+    pub body: Node<Expr>,
+}
+
+/// Named constants, `const NAME = value;`, defined separately from the policy
+/// sets which reference them as `NAME`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constants(pub Vec<Node<Def>>);
 
 /// Annotations: application-defined data, as a key-value pair
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -39,6 +59,15 @@ pub struct Annotation {
 pub enum Str {
     /// regular quoted string
     String(SmolStr),
+    /// raw string, i.e., `r"..."` or `r#"..."#`, whose content is not
+    /// processed for escapes
+    Raw {
+        /// the content of the string, as written
+        raw: SmolStr,
+        /// the content of the string with backslashes and quotes escaped,
+        /// i.e., as it would be written in a regular quoted string
+        escaped: SmolStr,
+    },
     // this is not generated by the parser at time of comment,
     // but left as future improvement and to clarify the
     // validity of the above `String` form
@@ -47,6 +76,33 @@ pub enum Str {
     Invalid(SmolStr),
 }
 
+impl Str {
+    /// The string for the raw string literal `lit`, including the leading
+    /// `r`, the `#`s, and the quotes
+    pub fn from_raw_literal(lit: &str) -> Self {
+        let delimited = lit
+            .strip_prefix('r')
+            .unwrap_or(lit)
+            .trim_start_matches('#')
+            .trim_end_matches('#');
+        let raw = delimited
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .unwrap_or(delimited);
+        let mut escaped = String::with_capacity(raw.len());
+        for c in raw.chars() {
+            if c == '\\' || c == '"' {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        Self::Raw {
+            raw: raw.into(),
+            escaped: escaped.into(),
+        }
+    }
+}
+
 /// Policy statement, the main building block of the language
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Policy {
@@ -296,6 +352,9 @@ pub struct Member {
 pub enum MemAccess {
     /// field identifier
     Field(Node<Ident>),
+    /// optional field identifier, written `.field?`: the smallest enclosing
+    /// relation is guarded by a `has` test for the field
+    OptionalField(Node<Ident>),
     /// function call
     Call(Vec<Node<Expr>>),
     /// index of a member
@@ -34,11 +34,12 @@
 // cloning.
 
 use super::cst;
+use super::defs::Constants;
 use super::err::{parse_errors, ParseError, ParseErrors, ToASTError, ToASTErrorKind};
 use super::loc::Loc;
 use super::node::Node;
 use super::unescape::{to_pattern, to_unescaped_string};
-use super::util::{flatten_tuple_2, flatten_tuple_3, flatten_tuple_4};
+use super::util::{flatten_tuple_2, flatten_tuple_3, flatten_tuple_4, is_null_literal};
 use crate::ast::{
     self, ActionConstraint, CallStyle, Integer, PatternElem, PolicySetError, PrincipalConstraint,
     PrincipalOrResourceConstraint, ResourceConstraint, UnreservedId,
@@ -100,12 +101,20 @@ impl Node<Option<cst::Policies>> {
 
     /// convert `cst::Policies` to `ast::PolicySet`
     pub fn to_policyset(&self) -> Result<ast::PolicySet> {
+        self.to_policyset_with_constants(&Constants::default())
+    }
+
+    /// convert `cst::Policies` to `ast::PolicySet`, where the policies may
+    /// reference `constants`
+    pub fn to_policyset_with_constants(&self, constants: &Constants) -> Result<ast::PolicySet> {
         let mut pset = ast::PolicySet::new();
         let mut all_errs: Vec<ParseErrors> = vec![];
+        let defs = self.to_defs(constants)?;
         // Caution: `parser::parse_policyset_and_also_return_policy_text()`
         // depends on this function returning a policy set with `PolicyID`s as
         // generated by `with_generated_policyids()` to maintain an invariant.
         for (policy_id, policy) in self.with_generated_policyids()? {
+            let policy = defs.expand(policy);
             // policy may have convert error
             match policy.to_policy_or_template(policy_id) {
                 Ok(Either::Right(template)) => {
@@ -222,14 +231,20 @@ impl Node<Option<cst::Policy>> {
                 )
                 .into()
             });
+            let kind = if is_when {
+                ast::ConditionKind::When
+            } else {
+                ast::ConditionKind::Unless
+            };
             match ParseErrors::from_iter(slot_errs) {
                 Some(errs) => Err(errs),
-                None => Ok(e),
+                None => Ok((e, kind)),
             }
         }));
 
         let (effect, annotations, (principal, action, resource), conds) =
             flatten_tuple_4(maybe_effect, maybe_annotations, maybe_scope, maybe_conds)?;
+        let (conds, kinds) = conds.into_iter().unzip();
         Ok(construct_template_policy(
             id,
             annotations,
@@ -239,7 +254,8 @@ impl Node<Option<cst::Policy>> {
             resource,
             conds,
             &self.loc,
-        ))
+        )
+        .with_condition_kinds(kinds))
     }
 }
 
@@ -678,7 +694,7 @@ impl Node<Option<cst::Str>> {
         let id = self.try_as_inner()?;
 
         match id {
-            cst::Str::String(s) => Ok(s),
+            cst::Str::String(s) | cst::Str::Raw { escaped: s, .. } => Ok(s),
             // at time of comment, all strings are valid
             cst::Str::Invalid(s) => Err(self
                 .to_ast_err(ToASTErrorKind::InvalidString(s.to_string()))
@@ -722,6 +738,7 @@ impl ExprOrSpecial<'_> {
         match self {
             Self::Expr { expr, .. } => Ok(expr),
             Self::Var { var, loc } => Ok(construct_expr_var(var, loc)),
+            Self::Name { name, loc } if is_null_literal(&name) => Ok(construct_expr_null(loc)),
             Self::Name { name, loc } => Err(ToASTError::new(
                 ToASTErrorKind::ArbitraryVariable(name.to_string().into()),
                 loc,
@@ -888,6 +905,13 @@ impl Node<Option<cst::Relation>> {
     fn to_expr_or_special(&self) -> Result<ExprOrSpecial<'_>> {
         let rel = self.try_as_inner()?;
 
+        if let Some(guarded) = self.guard_optional_attrs() {
+            return Ok(ExprOrSpecial::Expr {
+                expr: guarded.to_expr()?,
+                loc: self.loc.clone(),
+            });
+        }
+
         match rel {
             cst::Relation::Common { initial, extended } => {
                 let maybe_first = initial.to_expr_or_special();
@@ -1148,9 +1172,16 @@ impl Node<Option<cst::Member>> {
                         name,
                         ast::Name::unqualified_name(ast::UnreservedId::empty()),
                     );
-                    head = nn.into_func(args, self.loc.clone()).map(|expr| Expr {
+                    // the call ends with its arguments, before any methods
+                    // called on its result
+                    let call_end = mem
+                        .access
+                        .get(mem.access.len() - rest.len() - 1)
+                        .map_or(self.loc.end(), |call| call.loc.end());
+                    let call_loc = self.loc.span(self.loc.start()..call_end);
+                    head = nn.into_func(args, call_loc.clone()).map(|expr| Expr {
                         expr,
-                        loc: self.loc.clone(),
+                        loc: call_loc,
                     })?;
                     tail = rest;
                 }
@@ -1327,6 +1358,12 @@ impl Node<Option<cst::MemAccess>> {
                 let maybe_ident = i.to_unreserved_ident();
                 maybe_ident.map(AstAccessor::Field)
             }
+            cst::MemAccess::OptionalField(i) => {
+                let id = i.to_unreserved_ident()?;
+                Err(self
+                    .to_ast_err(ToASTErrorKind::UnguardedOptionalAttr(id.to_smolstr()))
+                    .into())
+            }
             cst::MemAccess::Call(args) => {
                 let maybe_args = ParseErrors::transpose(args.iter().map(|e| e.to_expr()));
                 maybe_args.map(AstAccessor::Call)
@@ -1674,6 +1711,11 @@ fn construct_expr_string(s: SmolStr, loc: Loc) -> ast::Expr {
 fn construct_expr_bool(b: bool, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new().with_source_loc(loc).val(b)
 }
+fn construct_expr_null(loc: Loc) -> ast::Expr {
+    ast::ExprBuilder::new()
+        .with_source_loc(loc)
+        .val(ast::Literal::Null)
+}
 fn construct_expr_neg(e: ast::Expr, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new().with_source_loc(loc).neg(e)
 }
@@ -3285,6 +3327,257 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hex_and_separated_integers() {
+        for (es, expr) in [
+            ("0x1F", Expr::val(31)),
+            ("0xff_ff", Expr::val(0xffff)),
+            ("1_000_000", Expr::val(1_000_000)),
+            ("0x7FFF_FFFF_FFFF_FFFF", Expr::val(i64::MAX)),
+            ("-0x8000_0000_0000_0000", Expr::val(i64::MIN)),
+        ] {
+            let e = assert_parse_expr_succeeds(es);
+            assert!(
+                e.eq_shape(&expr),
+                "{:?} and {:?} should have the same shape.",
+                e,
+                expr
+            );
+        }
+
+        let src = "0x8000_0000_0000_0000";
+        let errs = assert_parse_expr_fails(src);
+        expect_err(
+            src,
+            &miette::Report::new(errs),
+            &ExpectedErrorMessageBuilder::error(
+                "integer literal `9223372036854775808` is too large",
+            )
+            .help("maximum allowed integer literal is `9223372036854775807`")
+            .exactly_one_underline(src)
+            .build(),
+        );
+        // hexadecimal literals need at least one digit
+        assert!(text_to_cst::parse_expr("0x_").is_err());
+    }
+
+    #[test]
+    fn named_conditions() {
+        let to_policyset = |src: &str| {
+            text_to_cst::parse_policies(src)
+                .expect("should parse")
+                .to_policyset()
+        };
+        let policyset = to_policyset(
+            r#"
+            def isOwner = resource.owner == principal;
+            permit(principal, action, resource) when { isOwner };
+            @doc("definitions may use earlier definitions")
+            def canEdit = isOwner || principal in resource.editors;
+            forbid(principal, action, resource) unless { canEdit && ip("10.0.0.1").isLoopback() };
+        "#,
+        )
+        .unwrap_or_else(|errs| panic!("failed convert to AST:\n{:?}", miette::Report::new(errs)));
+        let expected = to_policyset(
+            r#"
+            permit(principal, action, resource) when { resource.owner == principal };
+            forbid(principal, action, resource) unless {
+                (resource.owner == principal || principal in resource.editors) && ip("10.0.0.1").isLoopback()
+            };
+        "#,
+        )
+        .unwrap_or_else(|errs| panic!("failed convert to AST:\n{:?}", miette::Report::new(errs)));
+        for id in ["policy0", "policy1"] {
+            let id = ast::PolicyID::from_string(id);
+            let (p, e) = (policyset.get(&id).unwrap(), expected.get(&id).unwrap());
+            assert!(p
+                .non_scope_constraints()
+                .eq_shape(e.non_scope_constraints()));
+        }
+
+        // expanded expressions have the source locations of the definition,
+        // so that e.g. type errors in them point to the definition
+        let policyset = to_policyset(
+            r#"
+            def isAdmin = principal.isAdmin && 1;
+            permit(principal, action, resource) when { isAdmin };
+        "#,
+        )
+        .unwrap();
+        let policy = policyset
+            .get(&ast::PolicyID::from_string("policy0"))
+            .unwrap();
+        assert!(policy
+            .non_scope_constraints()
+            .subexpressions()
+            .any(|e| e.source_loc().and_then(Loc::snippet) == Some("principal.isAdmin && 1")));
+
+        for (src, error, underline) in [
+            (
+                "def isOwner = true; def isOwner = false;",
+                ExpectedErrorMessageBuilder::error("a definition named `isOwner` already exists"),
+                "isOwner",
+            ),
+            (
+                "def principal = User::\"alice\";",
+                ExpectedErrorMessageBuilder::error("invalid definition name: principal")
+                    .help("`principal`, `action`, `resource`, and `context` cannot be redefined"),
+                "principal",
+            ),
+            (
+                "let isOwner = true;",
+                ExpectedErrorMessageBuilder::error("invalid definition keyword: let")
+                    .help("definitions have the form `def name = expr;`"),
+                "let",
+            ),
+        ] {
+            let errs = to_policyset(src).unwrap_err();
+            expect_err(
+                src,
+                &miette::Report::new(errs),
+                &error.exactly_one_underline(underline).build(),
+            );
+        }
+    }
+
+    #[test]
+    fn constants() {
+        let to_constants = |src: &str| {
+            text_to_cst::parse_constants(src)
+                .expect("should parse")
+                .to_constants()
+        };
+        let constants = to_constants(
+            r#"
+            const ADMIN_GROUPS = [Group::"a", Group::"b"];
+            const LIMITS = { "max": 10, "groups": ADMIN_GROUPS };
+        "#,
+        )
+        .unwrap_or_else(|errs| {
+            panic!("failed convert constants:\n{:?}", miette::Report::new(errs))
+        });
+        let policyset = text_to_cst::parse_policies(
+            r#"
+            def isAdmin = principal in ADMIN_GROUPS;
+            permit(principal, action, resource) when { isAdmin && context.size < LIMITS.max };
+        "#,
+        )
+        .expect("should parse")
+        .to_policyset_with_constants(&constants)
+        .unwrap_or_else(|errs| panic!("failed convert to AST:\n{:?}", miette::Report::new(errs)));
+        let expected = text_to_cst::parse_policies(
+            r#"
+            permit(principal, action, resource) when {
+                (principal in [Group::"a", Group::"b"])
+                && context.size < { "max": 10, "groups": [Group::"a", Group::"b"] }.max
+            };
+        "#,
+        )
+        .expect("should parse")
+        .to_policyset()
+        .unwrap();
+        let id = ast::PolicyID::from_string("policy0");
+        assert!(policyset
+            .get(&id)
+            .unwrap()
+            .non_scope_constraints()
+            .eq_shape(expected.get(&id).unwrap().non_scope_constraints()));
+
+        // a definition of the policy set can't redefine a constant
+        let src = "def ADMIN_GROUPS = [];";
+        let errs = text_to_cst::parse_policies(src)
+            .expect("should parse")
+            .to_policyset_with_constants(&constants)
+            .unwrap_err();
+        expect_err(
+            src,
+            &miette::Report::new(errs),
+            &ExpectedErrorMessageBuilder::error("a definition named `ADMIN_GROUPS` already exists")
+                .exactly_one_underline("ADMIN_GROUPS")
+                .build(),
+        );
+
+        for (src, error, underline) in [
+            (
+                "const OWNER = resource.owner;",
+                ExpectedErrorMessageBuilder::error(
+                    "the value of constant `OWNER` is not a valid value",
+                )
+                .help("constants must be literals, entity references, extension function calls, or sets and records of these"),
+                "resource.owner",
+            ),
+            (
+                "def ADMIN = User::\"alice\";",
+                ExpectedErrorMessageBuilder::error("invalid constant keyword: def")
+                    .help("constants have the form `const NAME = value;`"),
+                "def",
+            ),
+            (
+                "const A = 1; const A = 2;",
+                ExpectedErrorMessageBuilder::error("a definition named `A` already exists"),
+                "A",
+            ),
+        ] {
+            let errs = to_constants(src).unwrap_err();
+            expect_err(
+                src,
+                &miette::Report::new(errs),
+                &error.exactly_one_underline(underline).build(),
+            );
+        }
+    }
+
+    #[test]
+    fn optional_attributes() {
+        for (es, desugared) in [
+            (
+                "resource.owner?.id == principal.id",
+                "resource has owner && resource.owner.id == principal.id",
+            ),
+            (
+                "resource.owner?.manager?.id == principal.id",
+                "resource has owner && resource.owner has manager && resource.owner.manager.id == principal.id",
+            ),
+            ("resource.public?", "resource has public && resource.public"),
+            (
+                "(context.size? + 1) * 2 < 10",
+                "context has size && (context.size + 1) * 2 < 10",
+            ),
+            (
+                "resource.tags?.contains(context.tag?)",
+                "resource has tags && context has tag && resource.tags.contains(context.tag)",
+            ),
+            (
+                "principal.age? >= 18 || principal.verified?",
+                "(principal has age && principal.age >= 18) || (principal has verified && principal.verified)",
+            ),
+            (
+                "if principal.admin? then true else resource.public?",
+                "if principal has admin && principal.admin then true else resource has public && resource.public",
+            ),
+        ] {
+            let e = assert_parse_expr_succeeds(es);
+            let expected = assert_parse_expr_succeeds(desugared);
+            assert!(
+                e.eq_shape(&expected),
+                "{:?} and {:?} should have the same shape.",
+                e,
+                expected
+            );
+        }
+
+        let src = "principal has resource.owner?";
+        let errs = assert_parse_expr_fails(src);
+        expect_err(
+            src,
+            &miette::Report::new(errs),
+            &ExpectedErrorMessageBuilder::error("optional attribute access `.owner?` is not allowed here")
+                .help("optional attribute accesses may appear in the operands of comparisons, arithmetic, and method calls")
+                .exactly_one_underline(".owner?")
+                .build(),
+        );
+    }
+
     #[test]
     fn test_is_condition_ok() {
         for (es, expr) in [
@@ -3801,7 +4094,7 @@ mod tests {
                 // `_ in _ is _` in the policy condition is an error in the text->CST parser
                 r#"permit(principal, action, resource) when { principal in Group::"friends" is User };"#,
                 ExpectedErrorMessageBuilder::error("unexpected token `is`")
-                    .exactly_one_underline_with_label(r#"is"#, "expected `!=`, `&&`, `<`, `<=`, `==`, `>`, `>=`, `||`, `}`, or `in`")
+                    .exactly_one_underline_with_label(r#"is"#, "expected `!=`, `&&`, `<`, `<=`, `=`, `==`, `>`, `>=`, `||`, `}`, or `in`")
                     .build(),
             ),
         ];
@@ -4088,7 +4381,7 @@ mod tests {
                 p_src,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error("unexpected token `::`")
-                    .exactly_one_underline_with_label("::", "expected `!=`, `)`, `,`, `:`, `<`, `<=`, `==`, `>`, `>=`, `in`, or `is`")
+                    .exactly_one_underline_with_label("::", "expected `!=`, `)`, `,`, `:`, `<`, `<=`, `=`, `==`, `>`, `>=`, `in`, or `is`")
                     .build()
             );
         });
@@ -4499,6 +4792,72 @@ mod tests {
         expect_reserved_ident("foo::is::bar::principal", "is");
     }
 
+    #[test]
+    #[cfg(feature = "null-literal")]
+    fn null_literal() {
+        assert_matches!(parse_expr("null"), Ok(e) => {
+            assert_matches!(e.expr_kind(), ast::ExprKind::Lit(ast::Literal::Null));
+        });
+        assert_matches!(parse_expr("principal.foo != null"), Ok(e) => {
+            assert_matches!(e.expr_kind(), ast::ExprKind::UnaryApp { op: ast::UnaryOp::Not, arg } => {
+                assert_matches!(arg.expr_kind(), ast::ExprKind::BinaryApp { op: ast::BinaryOp::Eq, arg2, .. } => {
+                    assert_matches!(arg2.expr_kind(), ast::ExprKind::Lit(ast::Literal::Null));
+                });
+            });
+        });
+        assert_matches!(parse_expr("[null, {a: null}]"), Ok(_));
+        // `null` is not a function
+        assert_matches!(parse_expr("null(1)"), Err(_));
+        assert_matches!(
+            crate::parser::parse_policy_or_template_to_est(
+                "permit(principal, action, resource) when { principal.foo != null };"
+            ),
+            Ok(_)
+        );
+    }
+
+    /// `null` is an identifier wherever it was before the `null` literal, with
+    /// or without the `null-literal` feature
+    #[test]
+    fn null_ident() {
+        assert_matches!(parse_expr("principal.null"), Ok(e) => {
+            assert_matches!(e.expr_kind(), ast::ExprKind::GetAttr { attr, .. } => {
+                assert_eq!(attr, "null");
+            });
+        });
+        assert_eq!(
+            text_to_cst::parse_expr("principal.null")
+                .unwrap()
+                .node
+                .unwrap()
+                .to_string(),
+            "principal.null"
+        );
+        assert_matches!(parse_expr("principal has null"), Ok(e) => {
+            assert_matches!(e.expr_kind(), ast::ExprKind::HasAttr { attr, .. } => {
+                assert_eq!(attr, "null");
+            });
+        });
+        assert_matches!(parse_expr("{null: 1}"), Ok(e) => {
+            assert_matches!(e.expr_kind(), ast::ExprKind::Record(attrs) => {
+                assert!(attrs.contains_key("null"));
+            });
+        });
+        assert_matches!(parse_expr(r#"principal == null::"a""#), Ok(_));
+        assert_matches!(parse_expr(r#"null::Foo::"a""#), Ok(_));
+        assert_matches!(parse_expr("principal is null"), Ok(e) => {
+            assert_matches!(e.expr_kind(), ast::ExprKind::Is { entity_type, .. } => {
+                assert_eq!(entity_type.to_string(), "null");
+            });
+        });
+        assert_matches!(
+            crate::parser::parse_policy_or_template_to_est(
+                "permit(principal, action, resource) when { principal.null && principal has null };"
+            ),
+            Ok(_)
+        );
+    }
+
     #[test]
     fn reserved_namespace() {
         assert_matches!(parse_expr(r#"__cedar::"""#),
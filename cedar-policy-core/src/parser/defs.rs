@@ -0,0 +1,274 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Expansion of named conditions, defined in a policy set with
+//! `def name = expr;` and referenced as `name` in the conditions of its
+//! policies and in later definitions, and of named constants, defined in a
+//! separate constants file with `const NAME = value;`.
+//!
+//! References are replaced by the parenthesized definition before converting
+//! policies to AST or EST, so the expanded expressions keep the source
+//! locations of the definition, and errors in them (e.g., validation errors)
+//! point to the definition.
+
+use super::cst::{
+    Add, Constants as CstConstants, Def, Expr, ExprData, Ident, MemAccess, Member, Policies,
+    Policy, Primary, Relation,
+};
+use super::err::{ParseErrors, ToASTErrorKind};
+use super::node::Node;
+use crate::ast::BorrowedRestrictedExpr;
+use smol_str::{SmolStr, ToSmolStr};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+type CstNode<N> = Node<Option<N>>;
+
+/// The expanded named conditions of a policy set, or named constants
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Defs(HashMap<SmolStr, CstNode<Expr>>);
+
+/// Named constants, e.g., `const ADMIN_GROUPS = [Group::"a", Group::"b"];`,
+/// defined in a constants file and referenced by the conditions of policy
+/// sets, so that the constants can be updated without updating the policies.
+///
+/// The value of a constant must be a restricted expression, i.e., a literal,
+/// an entity reference, an extension function call, or a set or record of
+/// these. Constants may reference earlier constants.
+#[derive(Debug, Clone, Default)]
+pub struct Constants(Defs);
+
+impl Constants {
+    /// The names of the constants
+    pub fn names(&self) -> impl Iterator<Item = &SmolStr> {
+        self.0 .0.keys()
+    }
+}
+
+/// Keywords of the definitions, which differ between named conditions and
+/// constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keyword {
+    Def,
+    Const,
+}
+
+impl CstNode<Policies> {
+    /// The named conditions defined in this policy set, together with
+    /// `constants`, or errors for invalid or duplicate definitions
+    pub(crate) fn to_defs(&self, constants: &Constants) -> Result<Defs, ParseErrors> {
+        let mut defs = constants.0.clone();
+        defs.extend(&self.try_as_inner()?.1, Keyword::Def)?;
+        Ok(defs)
+    }
+}
+
+impl CstNode<CstConstants> {
+    /// The constants defined in this constants file, or errors for invalid or
+    /// duplicate constants
+    pub(crate) fn to_constants(&self) -> Result<Constants, ParseErrors> {
+        let mut defs = Defs::default();
+        defs.extend(&self.try_as_inner()?.0, Keyword::Const)?;
+        Ok(Constants(defs))
+    }
+}
+
+impl Defs {
+    /// Add the expanded definitions `defs`, in order
+    fn extend(&mut self, defs: &[CstNode<Def>], keyword: Keyword) -> Result<(), ParseErrors> {
+        let mut errs = Vec::new();
+        for def in defs {
+            match self.to_expanded_def(def, keyword) {
+                Ok((name, body)) => {
+                    self.0.insert(name, body);
+                }
+                Err(e) => errs.push(e),
+            }
+        }
+        match ParseErrors::flatten(errs) {
+            Some(errs) => Err(errs),
+            None => Ok(()),
+        }
+    }
+
+    /// The name and the body of `def`, where the references to the
+    /// definitions of `self` are expanded
+    fn to_expanded_def(
+        &self,
+        def: &CstNode<Def>,
+        keyword: Keyword,
+    ) -> Result<(SmolStr, CstNode<Expr>), ParseErrors> {
+        let Def {
+            keyword: kw,
+            name,
+            body,
+            ..
+        } = def.try_as_inner()?;
+        match (kw.try_as_inner()?, keyword) {
+            (Ident::Ident(k), Keyword::Def) if k == "def" => (),
+            (Ident::Ident(k), Keyword::Const) if k == "const" => (),
+            (k, Keyword::Def) => {
+                return Err(kw
+                    .to_ast_err(ToASTErrorKind::InvalidDefKeyword(k.clone()))
+                    .into())
+            }
+            (k, Keyword::Const) => {
+                return Err(kw
+                    .to_ast_err(ToASTErrorKind::InvalidConstKeyword(k.clone()))
+                    .into())
+            }
+        }
+        let id = name.to_unreserved_ident()?.to_smolstr();
+        if matches!(
+            name.try_as_inner()?,
+            Ident::Principal | Ident::Action | Ident::Resource | Ident::Context
+        ) {
+            return Err(name.to_ast_err(ToASTErrorKind::InvalidDefName(id)).into());
+        }
+        if self.0.contains_key(&id) {
+            return Err(name.to_ast_err(ToASTErrorKind::DuplicateDef(id)).into());
+        }
+        let mut body = body.clone();
+        self.expand_expr(&mut body);
+        if keyword == Keyword::Const {
+            let expr = body.to_expr()?;
+            if BorrowedRestrictedExpr::new(&expr).is_err() {
+                return Err(body.to_ast_err(ToASTErrorKind::NonValueConstant(id)).into());
+            }
+        }
+        Ok((id, body))
+    }
+
+    /// `policy`, where the references to the definitions are expanded, or
+    /// `policy` itself if it doesn't reference any definition
+    pub(crate) fn expand<'a>(&self, policy: &'a CstNode<Policy>) -> Cow<'a, CstNode<Policy>> {
+        if self.0.is_empty() {
+            return Cow::Borrowed(policy);
+        }
+        let mut expanded = policy.clone();
+        if let Some(p) = expanded.node.as_mut() {
+            for cond in p.conds.iter_mut().filter_map(|c| c.node.as_mut()) {
+                if let Some(e) = cond.expr.as_mut() {
+                    self.expand_expr(e);
+                }
+            }
+        }
+        if expanded == *policy {
+            Cow::Borrowed(policy)
+        } else {
+            Cow::Owned(expanded)
+        }
+    }
+
+    fn expand_expr(&self, e: &mut CstNode<Expr>) {
+        let Some(e) = e.node.as_mut() else {
+            return;
+        };
+        match e.expr.as_mut() {
+            ExprData::Or(or) => {
+                let Some(or) = or.node.as_mut() else {
+                    return;
+                };
+                let ands = std::iter::once(&mut or.initial).chain(or.extended.iter_mut());
+                for and in ands.filter_map(|and| and.node.as_mut()) {
+                    let rels = std::iter::once(&mut and.initial).chain(and.extended.iter_mut());
+                    for rel in rels.filter_map(|rel| rel.node.as_mut()) {
+                        self.expand_relation(rel);
+                    }
+                }
+            }
+            ExprData::If(i, t, e) => {
+                self.expand_expr(i);
+                self.expand_expr(t);
+                self.expand_expr(e);
+            }
+        }
+    }
+
+    /// Expand the operands of `rel`, but not the attribute of `has` or the
+    /// entity type of `is`, which are not expressions
+    fn expand_relation(&self, rel: &mut Relation) {
+        match rel {
+            Relation::Common { initial, extended } => {
+                self.expand_add(initial);
+                for (_, add) in extended {
+                    self.expand_add(add);
+                }
+            }
+            Relation::Has { target, .. } | Relation::Like { target, .. } => self.expand_add(target),
+            Relation::IsIn {
+                target, in_entity, ..
+            } => {
+                self.expand_add(target);
+                if let Some(in_entity) = in_entity {
+                    self.expand_add(in_entity);
+                }
+            }
+        }
+    }
+
+    fn expand_add(&self, add: &mut CstNode<Add>) {
+        let Some(add) = add.node.as_mut() else {
+            return;
+        };
+        let mults =
+            std::iter::once(&mut add.initial).chain(add.extended.iter_mut().map(|(_, m)| m));
+        for mult in mults.filter_map(|mult| mult.node.as_mut()) {
+            let unaries =
+                std::iter::once(&mut mult.initial).chain(mult.extended.iter_mut().map(|(_, u)| u));
+            for unary in unaries.filter_map(|unary| unary.node.as_mut()) {
+                self.expand_member(&mut unary.item);
+            }
+        }
+    }
+
+    fn expand_member(&self, member: &mut CstNode<Member>) {
+        let Some(member) = member.node.as_mut() else {
+            return;
+        };
+        // a name which is called is a function, not a reference
+        let called = matches!(
+            member.access.first().and_then(|a| a.node.as_ref()),
+            Some(MemAccess::Call(_))
+        );
+        match member.item.node.as_mut() {
+            Some(Primary::Name(name)) if !called => {
+                let body = name
+                    .node
+                    .as_ref()
+                    .filter(|name| name.path.is_empty())
+                    .and_then(|name| name.name.as_inner())
+                    .and_then(|name| self.0.get(name.to_string().as_str()));
+                if let Some(body) = body {
+                    member.item.node = Some(Primary::Expr(body.clone()));
+                }
+            }
+            Some(Primary::Expr(e)) => self.expand_expr(e),
+            Some(Primary::EList(es)) => es.iter_mut().for_each(|e| self.expand_expr(e)),
+            Some(Primary::RInits(inits)) => inits
+                .iter_mut()
+                .filter_map(|init| init.node.as_mut())
+                .for_each(|init| self.expand_expr(&mut init.1)),
+            Some(Primary::Name(_) | Primary::Literal(_) | Primary::Ref(_) | Primary::Slot(_))
+            | None => (),
+        }
+        for access in member.access.iter_mut() {
+            if let Some(MemAccess::Call(args)) = access.node.as_mut() {
+                args.iter_mut().for_each(|arg| self.expand_expr(arg));
+            }
+        }
+    }
+}
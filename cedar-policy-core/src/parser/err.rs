@@ -175,6 +175,30 @@ pub enum ToASTErrorKind {
     #[error("invalid policy effect: {0}")]
     #[diagnostic(help("effect must be either `permit` or `forbid`"))]
     InvalidEffect(cst::Ident),
+    /// Returned when a definition uses a keyword other than `def`
+    #[error("invalid definition keyword: {0}")]
+    #[diagnostic(help("definitions have the form `def name = expr;`"))]
+    InvalidDefKeyword(cst::Ident),
+    /// Returned when a definition has the name of a variable
+    #[error("invalid definition name: {0}")]
+    #[diagnostic(help("`principal`, `action`, `resource`, and `context` cannot be redefined"))]
+    InvalidDefName(SmolStr),
+    /// Returned when a policy set or a constants file has several definitions
+    /// with the same name, or when a definition of a policy set has the name
+    /// of a constant
+    #[error("a definition named `{0}` already exists")]
+    DuplicateDef(SmolStr),
+    /// Returned when a constant uses a keyword other than `const`
+    #[error("invalid constant keyword: {0}")]
+    #[diagnostic(help("constants have the form `const NAME = value;`"))]
+    InvalidConstKeyword(cst::Ident),
+    /// Returned when the value of a constant is not a restricted expression,
+    /// e.g., when it references a variable
+    #[error("the value of constant `{0}` is not a valid value")]
+    #[diagnostic(help(
+        "constants must be literals, entity references, extension function calls, or sets and records of these"
+    ))]
+    NonValueConstant(SmolStr),
     /// Returned when a policy uses a condition keyword beyond `when` or `unless`
     #[error("invalid policy condition: {0}")]
     #[diagnostic(help("condition must be either `when` or `unless`"))]
@@ -314,6 +338,11 @@ pub enum ToASTErrorKind {
     /// Returned when a policy attempts to index on a fields of a value with no fields
     #[error("invalid indexing expression `{0}[\"{}\"]`, `{0}` has no fields", .1.escape_debug())]
     InvalidIndex(ast::Name, SmolStr),
+    /// Returned when an optional attribute access `.attr?` appears where it
+    /// cannot be guarded, e.g., as the field of a `has` test
+    #[error("optional attribute access `.{0}?` is not allowed here")]
+    #[diagnostic(help("optional attribute accesses may appear in the operands of comparisons, arithmetic, and method calls"))]
+    UnguardedOptionalAttr(SmolStr),
     /// Returned when the contents of an indexing expression is not a string literal
     #[error("the contents of an index expression must be a string literal")]
     NonStringIndex,
@@ -677,8 +706,9 @@ lazy_static! {
             ("IDENTIFIER", "identifier"),
             ("NUMBER", "number"),
             ("STRINGLIT", "string literal"),
+            ("RAWSTRINGLIT", "raw string literal"),
         ]),
-        impossible_tokens: HashSet::from(["\"=\"", "\"%\"", "\"/\"", "OTHER_SLOT"]),
+        impossible_tokens: HashSet::from(["\"%\"", "\"/\"", "OTHER_SLOT"]),
         special_identifier_tokens: HashSet::from([
             "PERMIT",
             "FORBID",
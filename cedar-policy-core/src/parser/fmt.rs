@@ -37,24 +37,40 @@ impl<'a, T: fmt::Display> fmt::Display for View<'a, T> {
 
 impl fmt::Display for Policies {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if f.alternate() {
-            let mut ps = self.0.iter();
-            if let Some(p) = ps.next() {
-                write!(f, "{:#}", View(p))?;
-            }
-            for p in ps {
-                write!(f, "\n\n{:#}", View(p))?;
-            }
-        } else {
-            let mut ps = self.0.iter();
-            if let Some(p) = ps.next() {
-                write!(f, "{}", View(p))?;
+        let alternate = f.alternate();
+        let show = |item: &dyn fmt::Display| {
+            if alternate {
+                format!("{item:#}")
+            } else {
+                format!("{item}")
             }
-            for p in ps {
-                write!(f, " {}", View(p))?;
+        };
+        // definitions come first, since policies reference them
+        let items: Vec<String> = self
+            .1
+            .iter()
+            .map(|d| show(&View(d)))
+            .chain(self.0.iter().map(|p| show(&View(p))))
+            .collect();
+        write!(f, "{}", items.join(if alternate { "\n\n" } else { " " }))
+    }
+}
+impl fmt::Display for Def {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for anno in self.annotations.iter() {
+            if f.alternate() {
+                writeln!(f, "{:#}", View(anno))?;
+            } else {
+                write!(f, "{} ", View(anno))?;
             }
         }
-        Ok(())
+        write!(
+            f,
+            "{} {} = {};",
+            View(&self.keyword),
+            View(&self.name),
+            View(&self.body)
+        )
     }
 }
 impl fmt::Display for Policy {
@@ -306,6 +322,7 @@ impl fmt::Display for MemAccess {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             MemAccess::Field(id) => write!(f, ".{}", View(id))?,
+            MemAccess::OptionalField(id) => write!(f, ".{}?", View(id))?,
             MemAccess::Call(exprs) => {
                 write!(f, "(")?;
                 let mut es = exprs.iter();
@@ -436,6 +453,8 @@ impl fmt::Display for Str {
             Str::String(s) | Str::Invalid(s) => {
                 write!(f, "\"{}\"", s)
             }
+            Str::Raw { raw, .. } if raw.contains('"') => write!(f, "r#\"{}\"#", raw),
+            Str::Raw { raw, .. } => write!(f, "r\"{}\"", raw),
         }
     }
 }
@@ -0,0 +1,222 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Desugaring of optional attribute accesses, like `resource.owner?.id`.
+//!
+//! An optional attribute access `e.a?` is a regular attribute access `e.a`
+//! which guards the smallest enclosing relation (comparison, `has`, `like`,
+//! `is`, or a Boolean value like `resource.public?`) with `e has a`. For
+//! instance, `resource.owner?.id == principal.id` is desugared to
+//! `resource has owner && resource.owner.id == principal.id`, which is `false`
+//! if the resource has no owner.
+//!
+//! The guard is lifted through arithmetic, negation, attribute accesses,
+//! method call arguments, set and record literals, and parentheses around such
+//! expressions. Other expressions, like the branches of an `if`, are guarded
+//! separately.
+
+use super::cst::{
+    Add, And, Expr, ExprData, MemAccess, Member, Mult, Name, Primary, Relation, Unary,
+};
+use super::node::Node;
+
+type CstNode<N> = Node<Option<N>>;
+
+impl CstNode<Relation> {
+    /// If the operands of this relation contain optional attribute accesses,
+    /// returns the conjunction of their `has` guards and of this relation
+    /// where they are replaced by regular attribute accesses
+    pub(crate) fn guard_optional_attrs(&self) -> Option<CstNode<And>> {
+        // cheap test to avoid cloning relations without optional accesses,
+        // which are written with a `?`
+        if !self.loc.snippet().map_or(true, |src| src.contains('?')) {
+            return None;
+        }
+        let mut rel = self.node.clone()?;
+        let mut guards = Vec::new();
+        match &mut rel {
+            Relation::Common { initial, extended } => {
+                take_optional_attrs(initial, &mut guards);
+                for (_, operand) in extended {
+                    take_optional_attrs(operand, &mut guards);
+                }
+            }
+            Relation::Has { target, .. } | Relation::Like { target, .. } => {
+                take_optional_attrs(target, &mut guards);
+            }
+            Relation::IsIn {
+                target, in_entity, ..
+            } => {
+                take_optional_attrs(target, &mut guards);
+                if let Some(in_entity) = in_entity {
+                    take_optional_attrs(in_entity, &mut guards);
+                }
+            }
+        }
+        let mut guards = guards.into_iter();
+        let initial = guards.next()?;
+        let extended = guards
+            .chain(std::iter::once(Node::with_source_loc(
+                Some(rel),
+                self.loc.clone(),
+            )))
+            .collect();
+        Some(Node::with_source_loc(
+            Some(And { initial, extended }),
+            self.loc.clone(),
+        ))
+    }
+}
+
+/// Replace the optional attribute accesses of `add` which are evaluated
+/// whenever `add` is by regular ones, adding their guards to `guards`
+fn take_optional_attrs(add: &mut CstNode<Add>, guards: &mut Vec<CstNode<Relation>>) {
+    let Some(add) = add.node.as_mut() else {
+        return;
+    };
+    let mults = std::iter::once(&mut add.initial).chain(add.extended.iter_mut().map(|(_, m)| m));
+    for mult in mults {
+        let Some(mult) = mult.node.as_mut() else {
+            continue;
+        };
+        let unaries =
+            std::iter::once(&mut mult.initial).chain(mult.extended.iter_mut().map(|(_, u)| u));
+        for unary in unaries.filter_map(|unary| unary.node.as_mut()) {
+            take_member_optional_attrs(&mut unary.item, guards);
+        }
+    }
+}
+
+/// Like [`take_optional_attrs`], for a member, whose optional accesses are
+/// guarded by `has` tests on the member up to the access
+fn take_member_optional_attrs(member: &mut CstNode<Member>, guards: &mut Vec<CstNode<Relation>>) {
+    let loc = member.loc.clone();
+    let Some(m) = member.node.as_mut() else {
+        return;
+    };
+    take_primary_optional_attrs(&mut m.item, guards);
+    for i in 0..m.access.len() {
+        let Some(access) = m.access.get_mut(i) else {
+            continue;
+        };
+        match access.node.as_mut() {
+            Some(MemAccess::Call(args)) => {
+                args.iter_mut()
+                    .for_each(|arg| take_expr_optional_attrs(arg, guards));
+            }
+            Some(MemAccess::OptionalField(field)) => {
+                let field = field.clone();
+                // the guard tests the prefix of the member before this access
+                let target = Member {
+                    item: m.item.clone(),
+                    access: m.access.iter().take(i).cloned().collect(),
+                };
+                guards.push(Node::with_source_loc(
+                    Some(Relation::Has {
+                        target: wrap_member(Node::with_source_loc(Some(target), loc.clone())),
+                        field: wrap_member(Node::with_source_loc(
+                            Some(Member {
+                                item: Node::with_source_loc(
+                                    Some(Primary::Name(Node::with_source_loc(
+                                        Some(Name {
+                                            path: Vec::new(),
+                                            name: field.clone(),
+                                        }),
+                                        field.loc.clone(),
+                                    ))),
+                                    field.loc.clone(),
+                                ),
+                                access: Vec::new(),
+                            }),
+                            field.loc.clone(),
+                        )),
+                    }),
+                    loc.clone(),
+                ));
+                if let Some(access) = m.access.get_mut(i) {
+                    access.node = Some(MemAccess::Field(field));
+                }
+            }
+            Some(MemAccess::Field(_) | MemAccess::Index(_)) | None => (),
+        }
+    }
+}
+
+fn take_primary_optional_attrs(
+    primary: &mut CstNode<Primary>,
+    guards: &mut Vec<CstNode<Relation>>,
+) {
+    match primary.node.as_mut() {
+        Some(Primary::Expr(e)) => take_expr_optional_attrs(e, guards),
+        Some(Primary::EList(es)) => es
+            .iter_mut()
+            .for_each(|e| take_expr_optional_attrs(e, guards)),
+        Some(Primary::RInits(inits)) => inits
+            .iter_mut()
+            .filter_map(|init| init.node.as_mut())
+            .for_each(|init| take_expr_optional_attrs(&mut init.1, guards)),
+        Some(Primary::Literal(_) | Primary::Ref(_) | Primary::Name(_) | Primary::Slot(_))
+        | None => (),
+    }
+}
+
+/// Like [`take_optional_attrs`], for an expression which is a single operand
+/// without any operator, e.g., `(resource.size? + 1)`. Other expressions are
+/// guarded separately.
+fn take_expr_optional_attrs(e: &mut CstNode<Expr>, guards: &mut Vec<CstNode<Relation>>) {
+    let operand = e
+        .node
+        .as_mut()
+        .and_then(|e| match e.expr.as_mut() {
+            ExprData::Or(or) => or.node.as_mut(),
+            ExprData::If(..) => None,
+        })
+        .filter(|or| or.extended.is_empty())
+        .and_then(|or| or.initial.node.as_mut())
+        .filter(|and| and.extended.is_empty())
+        .and_then(|and| and.initial.node.as_mut());
+    if let Some(Relation::Common { initial, extended }) = operand {
+        if extended.is_empty() {
+            take_optional_attrs(initial, guards);
+        }
+    }
+}
+
+/// The expression `member`, as an operand of a relation
+fn wrap_member(member: CstNode<Member>) -> CstNode<Add> {
+    let loc = member.loc.clone();
+    let unary = Node::with_source_loc(
+        Some(Unary {
+            op: None,
+            item: member,
+        }),
+        loc.clone(),
+    );
+    let mult = Node::with_source_loc(
+        Some(Mult {
+            initial: unary,
+            extended: Vec::new(),
+        }),
+        loc.clone(),
+    );
+    Node::with_source_loc(
+        Some(Add {
+            initial: mult,
+            extended: Vec::new(),
+        }),
+        loc,
+    )
+}
@@ -73,6 +73,7 @@ fn parse_collect_errors<'a, P, T>(
 lazy_static::lazy_static! {
     static ref POLICIES_PARSER: grammar::PoliciesParser = grammar::PoliciesParser::new();
     static ref POLICY_PARSER: grammar::PolicyParser = grammar::PolicyParser::new();
+    static ref CONSTANTS_PARSER: grammar::ConstantsParser = grammar::ConstantsParser::new();
     static ref EXPR_PARSER: grammar::ExprParser = grammar::ExprParser::new();
     static ref REF_PARSER: grammar::RefParser = grammar::RefParser::new();
     static ref PRIMARY_PARSER: grammar::PrimaryParser = grammar::PrimaryParser::new();
@@ -90,6 +91,11 @@ pub fn parse_policy(text: &str) -> Result<Node<Option<cst::Policy>>, err::ParseE
     parse_collect_errors(&*POLICY_PARSER, grammar::PolicyParser::parse, text)
 }
 
+/// Create CST for a constants file from text
+pub fn parse_constants(text: &str) -> Result<Node<Option<cst::Constants>>, err::ParseErrors> {
+    parse_collect_errors(&*CONSTANTS_PARSER, grammar::ConstantsParser::parse, text)
+}
+
 /// Create CST for one Expression from text
 pub fn parse_expr(text: &str) -> Result<Node<Option<cst::Expr>>, err::ParseErrors> {
     parse_collect_errors(&*EXPR_PARSER, grammar::ExprParser::parse, text)
@@ -293,7 +299,7 @@ mod tests {
             &ExpectedErrorMessageBuilder::error("unexpected token `6`")
                 .exactly_one_underline_with_label(
                     "6",
-                    "expected `!=`, `)`, `,`, `::`, `<`, `<=`, `==`, `>`, `>=`, `in`, or `is`",
+                    "expected `!=`, `)`, `,`, `::`, `<`, `<=`, `=`, `==`, `>`, `>=`, `in`, or `is`",
                 )
                 .build(),
         );
@@ -391,7 +397,7 @@ mod tests {
             src,
             &errs,
             &ExpectedErrorMessageBuilder::error("unexpected token `{`")
-                .exactly_one_underline_with_label("{", "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `==`, `>`, `>=`, `[`, `||`, `}`, `has`, `in`, `is`, or `like`")
+                .exactly_one_underline_with_label("{", "expected `!=`, `&&`, `(`, `*`, `+`, `-`, `.`, `::`, `<`, `<=`, `=`, `==`, `>`, `>=`, `[`, `||`, `}`, `has`, `in`, `is`, or `like`")
                 .build(),
         );
         expect_some_error_matches(
@@ -468,7 +474,7 @@ mod tests {
             src,
             &errs,
             &ExpectedErrorMessageBuilder::error("unexpected end of input")
-                .exactly_one_underline_with_label("", "expected `!`, `(`, `-`, `::`, `[`, `{`, `false`, identifier, `if`, number, `?principal`, `?resource`, string literal, or `true`")
+                .exactly_one_underline_with_label("", "expected `!`, `(`, `-`, `::`, `[`, `{`, `false`, identifier, `if`, number, `?principal`, raw string literal, `?resource`, string literal, or `true`")
                 .build(),
         );
         // other random variable names are fine at this stage, although an error
@@ -816,7 +822,7 @@ mod tests {
             src,
             &errs,
             &ExpectedErrorMessageBuilder::error("unexpected token `*`")
-                .exactly_one_underline_with_label("*", "expected `!`, `(`, `-`, `[`, `{`, `false`, identifier, `if`, number, `?principal`, `?resource`, string literal, or `true`")
+                .exactly_one_underline_with_label("*", "expected `!`, `(`, `-`, `[`, `{`, `false`, identifier, `if`, number, `?principal`, raw string literal, `?resource`, string literal, or `true`")
                 .build(),
         );
     }
@@ -1065,7 +1071,10 @@ mod tests {
             src,
             &errs,
             &ExpectedErrorMessageBuilder::error("unexpected token `bad_annotation`")
-                .exactly_one_underline_with_label("bad_annotation", "expected string literal")
+                .exactly_one_underline_with_label(
+                    "bad_annotation",
+                    "expected raw string literal or string literal",
+                )
                 .build(),
         );
 
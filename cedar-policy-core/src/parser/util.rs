@@ -17,9 +17,30 @@
 //! Utility functions used by multiple parts of the parser.
 
 use super::err::ParseErrors;
+use crate::ast;
+use std::num::ParseIntError;
 
 type Result<T> = std::result::Result<T, ParseErrors>;
 
+/// Parse the integer literal `lit`, which is decimal, or hexadecimal with a
+/// `0x` prefix, and may contain `_` digit separators
+pub fn parse_integer_literal(lit: &str) -> std::result::Result<u64, ParseIntError> {
+    let digits: String = lit.chars().filter(|c| *c != '_').collect();
+    match digits.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    }
+}
+
+/// With the `null-literal` feature, the unqualified name `null` in expression
+/// position is the `null` literal. `null` is not a keyword, so it remains a
+/// valid identifier everywhere else, e.g., as an attribute or entity type name.
+pub(crate) fn is_null_literal(name: &ast::Name) -> bool {
+    cfg!(feature = "null-literal")
+        && name.is_unqualified()
+        && name.basename_as_ref().as_ref() == "null"
+}
+
 /// Combine two `Result`s into a single `Result`
 pub fn flatten_tuple_2<T1, T2>(res1: Result<T1>, res2: Result<T2>) -> Result<(T1, T2)> {
     match (res1, res2) {
@@ -0,0 +1,1258 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains a versioned serialization of the policy AST, intended
+//! for long-term storage of parsed policies (e.g., in a database).
+//!
+//! Unlike the serde implementations on the [`ast`] types, which mirror the
+//! internal representation and may change in any release, this format is
+//! defined explicitly here and only changes together with [`VERSION`]. Unlike
+//! the EST, it mirrors the AST: conditions are a single expression, `!=`,
+//! `>`, and `>=` have already been desugared, and source locations are not
+//! stored.
+//!
+//! The format is JSON. A policy set is stored as
+//!
+//! ```json
+//! { "version": 1, "policies": [...], "templates": [...], "links": [...] }
+//! ```
+//!
+//! where each static policy and template is an object with the fields `id`,
+//! `effect` (`"permit"` or `"forbid"`), `annotations`, `principal`, `action`,
+//! `resource`, and `condition`, and each link is an object with the fields
+//! `id`, `templateId`, and `values`. See the types in this module for the
+//! encoding of scope constraints and expressions.
+//!
+//! The residuals of a partial authorization are stored in the same format,
+//! versioned together with policy sets, as
+//!
+//! ```json
+//! { "version": 1, "residuals": [...] }
+//! ```
+//!
+//! where each residual is an object with the fields `id`, `effect`, and
+//! `condition`, and unknowns in conditions are encoded as
+//! `{ "unknown": { "name": ..., "typeAnnotation": ... } }`. The optional
+//! `unknowns` field maps the names of unknowns to their [`Provenance`].
+//!
+//! Compatibility guarantees:
+//! - A version of this crate can load the format written by any older
+//!   version of this crate, after passing it through [`migrate`] (which
+//!   [`from_json_value`] does).
+//! - Loading the format written by a newer version of this crate fails with
+//!   [`StableAstError::UnsupportedVersion`], rather than silently dropping
+//!   information.
+
+use crate::ast;
+use crate::parser::err::ParseErrors;
+use crate::FromNormalizedStr;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use smol_str::{SmolStr, ToSmolStr};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The current version of the format. Bumped whenever the format changes, in
+/// which case [`migrate`] learns to upgrade the previous version.
+pub const VERSION: u32 = 1;
+
+/// A stored policy set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicySet {
+    /// Version of the format, [`VERSION`] when written by this crate
+    pub version: u32,
+    /// Static policies, sorted by id
+    pub policies: Vec<Policy>,
+    /// Templates, sorted by id
+    pub templates: Vec<Policy>,
+    /// Template-linked policies, sorted by id
+    pub links: Vec<Link>,
+}
+
+/// A stored static policy or template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Policy id
+    pub id: SmolStr,
+    /// Policy effect
+    pub effect: Effect,
+    /// Annotations, without source locations
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<SmolStr, SmolStr>,
+    /// Principal scope constraint
+    pub principal: ScopeConstraint,
+    /// Action scope constraint
+    pub action: ActionConstraint,
+    /// Resource scope constraint
+    pub resource: ScopeConstraint,
+    /// Conjunction of the `when` and `unless` clauses of the policy
+    pub condition: Expr,
+}
+
+/// Stored residuals of a partial authorization
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Residuals {
+    /// Version of the format, [`VERSION`] when written by this crate
+    pub version: u32,
+    /// Residuals, sorted by policy id
+    pub residuals: Vec<Residual>,
+    /// Where the values of the unknowns in the residuals come from, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub unknowns: BTreeMap<SmolStr, Vec<Provenance>>,
+}
+
+/// A stored residual of a policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Residual {
+    /// Policy id
+    pub id: SmolStr,
+    /// Policy effect
+    pub effect: Effect,
+    /// Condition on the unknowns under which the policy is satisfied
+    pub condition: Expr,
+}
+
+/// Where the value of an unknown comes from, tagged by `kind`, e.g.,
+/// `{ "kind": "entityAttribute", "entity": { "type": "User", "id": "alice" }, "path": ["address", "city"] }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", deny_unknown_fields)]
+pub enum Provenance {
+    /// The principal of the request
+    Principal,
+    /// The action of the request
+    Action,
+    /// The resource of the request
+    Resource,
+    /// The context of the request
+    Context,
+    /// The value at `path` in the context
+    ContextAttribute {
+        /// Attribute names, from the context
+        path: Vec<SmolStr>,
+    },
+    /// The value at `path` in the attributes of `entity`
+    EntityAttribute {
+        /// The entity
+        entity: EntityUid,
+        /// Attribute names, from the attributes of the entity
+        path: Vec<SmolStr>,
+    },
+}
+
+/// A stored template-linked policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    /// Id of the template-linked policy
+    pub id: SmolStr,
+    /// Id of the template
+    pub template_id: SmolStr,
+    /// Values of the slots of the template
+    pub values: BTreeMap<Slot, EntityUid>,
+}
+
+/// Policy effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Effect {
+    /// `permit`
+    Permit,
+    /// `forbid`
+    Forbid,
+}
+
+/// Template slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Slot {
+    /// `?principal`
+    #[serde(rename = "?principal")]
+    Principal,
+    /// `?resource`
+    #[serde(rename = "?resource")]
+    Resource,
+}
+
+/// Entity uid. The `type` is a fully qualified entity type name, and the `id`
+/// is not escaped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EntityUid {
+    /// Entity type
+    #[serde(rename = "type")]
+    pub entity_type: SmolStr,
+    /// Entity id
+    pub id: SmolStr,
+}
+
+/// The entity in a principal or resource scope constraint: either an entity
+/// uid or the slot for that scope variable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntityReference {
+    /// An entity uid, e.g., `{ "entity": { "type": "User", "id": "alice" } }`
+    Entity(EntityUid),
+    /// The slot, `"slot"`
+    Slot,
+}
+
+/// Principal or resource scope constraint, tagged by `op`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", deny_unknown_fields)]
+pub enum ScopeConstraint {
+    /// No constraint
+    All,
+    /// `principal == entity`
+    Eq {
+        /// The entity
+        entity: EntityReference,
+    },
+    /// `principal in entity`
+    In {
+        /// The entity
+        entity: EntityReference,
+    },
+    /// `principal is entity_type`
+    #[serde(rename_all = "camelCase")]
+    Is {
+        /// Fully qualified entity type name
+        entity_type: SmolStr,
+    },
+    /// `principal is entity_type in entity`
+    #[serde(rename_all = "camelCase")]
+    IsIn {
+        /// Fully qualified entity type name
+        entity_type: SmolStr,
+        /// The entity
+        entity: EntityReference,
+    },
+}
+
+/// Action scope constraint, tagged by `op`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", deny_unknown_fields)]
+pub enum ActionConstraint {
+    /// No constraint
+    All,
+    /// `action == entity`
+    Eq {
+        /// The action
+        entity: EntityUid,
+    },
+    /// `action in [entities]`
+    In {
+        /// The actions
+        entities: Vec<EntityUid>,
+    },
+}
+
+/// Request variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Var {
+    /// `principal`
+    Principal,
+    /// `action`
+    Action,
+    /// `resource`
+    Resource,
+    /// `context`
+    Context,
+}
+
+/// Unary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnaryOp {
+    /// `!`
+    Not,
+    /// Integer negation
+    Neg,
+}
+
+/// Binary operator, named after its Cedar syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinaryOp {
+    /// `==`
+    #[serde(rename = "==")]
+    Eq,
+    /// `<`
+    #[serde(rename = "<")]
+    Less,
+    /// `<=`
+    #[serde(rename = "<=")]
+    LessEq,
+    /// `+`
+    #[serde(rename = "+")]
+    Add,
+    /// `-`
+    #[serde(rename = "-")]
+    Sub,
+    /// `*`
+    #[serde(rename = "*")]
+    Mul,
+    /// `in`
+    #[serde(rename = "in")]
+    In,
+    /// `.contains()`
+    #[serde(rename = "contains")]
+    Contains,
+    /// `.containsAll()`
+    #[serde(rename = "containsAll")]
+    ContainsAll,
+    /// `.containsAny()`
+    #[serde(rename = "containsAny")]
+    ContainsAny,
+}
+
+/// Element of a `like` pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatternElem {
+    /// Literal characters, e.g., `{ "literal": "abc" }`
+    Literal(SmolStr),
+    /// The wildcard `*`, `"wildcard"`
+    Wildcard,
+}
+
+/// Type annotation of an unknown, tagged by `kind`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", deny_unknown_fields)]
+pub enum Type {
+    /// Boolean
+    Bool,
+    /// Long
+    Long,
+    /// String
+    String,
+    /// Set
+    Set,
+    /// Record
+    Record,
+    /// Entity of the given fully qualified entity type
+    Entity {
+        /// Entity type
+        name: SmolStr,
+    },
+    /// Extension type with the given name
+    Extension {
+        /// Extension type name
+        name: SmolStr,
+    },
+    /// Type of `null`
+    Null,
+}
+
+/// Expression, externally tagged by the kind of node, e.g.,
+/// `{ "getAttr": { "expr": { "var": "principal" }, "attr": "name" } }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Expr {
+    /// Boolean literal
+    Bool(bool),
+    /// Long literal
+    Long(i64),
+    /// String literal, not escaped
+    String(SmolStr),
+    /// Entity literal
+    Entity(EntityUid),
+    /// `null` literal
+    Null,
+    /// Request variable
+    Var(Var),
+    /// Template slot
+    Slot(Slot),
+    /// Unknown, for partial evaluation
+    #[serde(rename_all = "camelCase")]
+    Unknown {
+        /// Name of the unknown
+        name: SmolStr,
+        /// Type of the values which may be substituted for the unknown
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        type_annotation: Option<Type>,
+    },
+    /// `if test then then_expr else else_expr`
+    If {
+        /// Condition
+        test: Box<Expr>,
+        /// Value if the condition is true
+        #[serde(rename = "then")]
+        then_expr: Box<Expr>,
+        /// Value if the condition is false
+        #[serde(rename = "else")]
+        else_expr: Box<Expr>,
+    },
+    /// `left && right`
+    And {
+        /// Left operand
+        left: Box<Expr>,
+        /// Right operand
+        right: Box<Expr>,
+    },
+    /// `left || right`
+    Or {
+        /// Left operand
+        left: Box<Expr>,
+        /// Right operand
+        right: Box<Expr>,
+    },
+    /// Application of a unary operator
+    UnaryApp {
+        /// Operator
+        op: UnaryOp,
+        /// Operand
+        arg: Box<Expr>,
+    },
+    /// Application of a binary operator
+    BinaryApp {
+        /// Operator
+        op: BinaryOp,
+        /// Left operand
+        left: Box<Expr>,
+        /// Right operand
+        right: Box<Expr>,
+    },
+    /// Call of an extension function or method
+    Call {
+        /// Fully qualified function name
+        function: SmolStr,
+        /// Arguments, including the receiver of a method
+        args: Vec<Expr>,
+    },
+    /// `expr.attr`
+    GetAttr {
+        /// Record or entity
+        expr: Box<Expr>,
+        /// Attribute name, not escaped
+        attr: SmolStr,
+    },
+    /// `expr has attr`
+    HasAttr {
+        /// Record or entity
+        expr: Box<Expr>,
+        /// Attribute name, not escaped
+        attr: SmolStr,
+    },
+    /// `expr like pattern`
+    Like {
+        /// String
+        expr: Box<Expr>,
+        /// Pattern
+        pattern: Vec<PatternElem>,
+    },
+    /// `expr is entity_type`
+    #[serde(rename_all = "camelCase")]
+    Is {
+        /// Entity
+        expr: Box<Expr>,
+        /// Fully qualified entity type name
+        entity_type: SmolStr,
+    },
+    /// Set literal
+    Set(Vec<Expr>),
+    /// Record literal
+    Record(BTreeMap<SmolStr, Expr>),
+}
+
+/// Errors when loading a stored policy set or stored residuals
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum StableAstError {
+    /// The input has no numeric `version` field
+    #[error("stored AST has no `version` field")]
+    MissingVersion,
+    /// The input was written by a newer version of this crate
+    #[error(
+        "stored AST has version {version}, which is newer than the supported version {}",
+        VERSION
+    )]
+    #[diagnostic(help("upgrade to a version of Cedar which supports this version of the format"))]
+    UnsupportedVersion {
+        /// Version of the input
+        version: u64,
+    },
+    /// The input does not match the format
+    #[error("stored AST does not match version {} of the format: {0}", VERSION)]
+    Deserialization(#[from] serde_json::Error),
+    /// A name in the input does not parse
+    #[error("invalid name `{name}` in stored AST")]
+    InvalidName {
+        /// The name
+        name: SmolStr,
+        /// Parse errors
+        #[source]
+        #[diagnostic_source]
+        source: ParseErrors,
+    },
+    /// A static policy contains a slot
+    #[error("static policy `{0}` contains a slot")]
+    SlotInStaticPolicy(SmolStr),
+    /// The policies do not form a policy set, e.g., because of duplicate ids
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    PolicySet(#[from] ast::PolicySetError),
+    /// A link does not match its template
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Linking(#[from] ast::LinkingError),
+}
+
+/// Upgrade a stored policy set or stored residuals written by this or an older
+/// version of this crate to the current [`VERSION`] of the format
+pub fn migrate(value: serde_json::Value) -> Result<serde_json::Value, StableAstError> {
+    let version = value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .ok_or(StableAstError::MissingVersion)?;
+    if version > u64::from(VERSION) {
+        return Err(StableAstError::UnsupportedVersion { version });
+    }
+    // When the format changes, upgrade each older version to the next one
+    // here, e.g., `if version == 1 { value = upgrade_from_v1(value); }`.
+    // Version 1 is the first version of the format.
+    Ok(value)
+}
+
+/// Load a stored policy set written by this or an older version of this crate
+pub fn from_json_value(value: serde_json::Value) -> Result<ast::PolicySet, StableAstError> {
+    let stored: PolicySet = serde_json::from_value(migrate(value)?)?;
+    stored.try_into()
+}
+
+/// Load stored residuals written by this or an older version of this crate
+pub fn residuals_from_json_value(value: serde_json::Value) -> Result<Residuals, StableAstError> {
+    Ok(serde_json::from_value(migrate(value)?)?)
+}
+
+impl From<&ast::PolicySet> for PolicySet {
+    fn from(pset: &ast::PolicySet) -> Self {
+        let mut policies: Vec<Policy> = pset
+            .static_policies()
+            .map(|p| Policy::from(p.template()))
+            .collect();
+        policies.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut templates: Vec<Policy> = pset.templates().map(Policy::from).collect();
+        templates.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut links: Vec<Link> = pset
+            .policies()
+            .filter(|p| !p.is_static())
+            .map(|p| Link {
+                id: SmolStr::new(p.id().as_ref()),
+                template_id: SmolStr::new(p.template().id().as_ref()),
+                values: p
+                    .env()
+                    .iter()
+                    .map(|(slot, euid)| (Slot::from(*slot), EntityUid::from(euid)))
+                    .collect(),
+            })
+            .collect();
+        links.sort_by(|a, b| a.id.cmp(&b.id));
+        Self {
+            version: VERSION,
+            policies,
+            templates,
+            links,
+        }
+    }
+}
+
+impl TryFrom<PolicySet> for ast::PolicySet {
+    type Error = StableAstError;
+
+    fn try_from(stored: PolicySet) -> Result<Self, Self::Error> {
+        let mut pset = ast::PolicySet::new();
+        for policy in stored.policies {
+            let id = policy.id.clone();
+            let template = ast::Template::try_from(policy)?;
+            let policy = ast::StaticPolicy::try_from(template)
+                .map_err(|_| StableAstError::SlotInStaticPolicy(id))?;
+            pset.add_static(policy)?;
+        }
+        for template in stored.templates {
+            pset.add_template(template.try_into()?)?;
+        }
+        for link in stored.links {
+            let values = link
+                .values
+                .into_iter()
+                .map(|(slot, euid)| Ok((slot.into(), euid.try_into()?)))
+                .collect::<Result<HashMap<_, _>, StableAstError>>()?;
+            pset.link(
+                ast::PolicyID::from_smolstr(link.template_id),
+                ast::PolicyID::from_smolstr(link.id),
+                values,
+            )?;
+        }
+        Ok(pset)
+    }
+}
+
+impl From<&ast::Template> for Policy {
+    fn from(t: &ast::Template) -> Self {
+        Self {
+            id: SmolStr::new(t.id().as_ref()),
+            effect: t.effect().into(),
+            annotations: t
+                .annotations()
+                .map(|(k, v)| (k.to_smolstr(), v.val.clone()))
+                .collect(),
+            principal: t.principal_constraint().as_inner().into(),
+            action: t.action_constraint().into(),
+            resource: t.resource_constraint().as_inner().into(),
+            condition: t.non_scope_constraints().into(),
+        }
+    }
+}
+
+impl TryFrom<Policy> for ast::Template {
+    type Error = StableAstError;
+
+    fn try_from(p: Policy) -> Result<Self, Self::Error> {
+        let annotations = p
+            .annotations
+            .into_iter()
+            .map(|(k, v)| {
+                Ok((
+                    parse_name::<ast::AnyId>(&k)?,
+                    ast::Annotation { val: v, loc: None },
+                ))
+            })
+            .collect::<Result<ast::Annotations, StableAstError>>()?;
+        Ok(ast::Template::new(
+            ast::PolicyID::from_smolstr(p.id),
+            None,
+            annotations,
+            p.effect.into(),
+            ast::PrincipalConstraint::new(p.principal.try_into()?),
+            p.action.try_into()?,
+            ast::ResourceConstraint::new(p.resource.try_into()?),
+            p.condition.try_into()?,
+        ))
+    }
+}
+
+impl From<ast::Effect> for Effect {
+    fn from(effect: ast::Effect) -> Self {
+        match effect {
+            ast::Effect::Permit => Self::Permit,
+            ast::Effect::Forbid => Self::Forbid,
+        }
+    }
+}
+
+impl From<Effect> for ast::Effect {
+    fn from(effect: Effect) -> Self {
+        match effect {
+            Effect::Permit => Self::Permit,
+            Effect::Forbid => Self::Forbid,
+        }
+    }
+}
+
+impl From<ast::SlotId> for Slot {
+    fn from(slot: ast::SlotId) -> Self {
+        if slot.is_principal() {
+            Self::Principal
+        } else {
+            Self::Resource
+        }
+    }
+}
+
+impl From<Slot> for ast::SlotId {
+    fn from(slot: Slot) -> Self {
+        match slot {
+            Slot::Principal => Self::principal(),
+            Slot::Resource => Self::resource(),
+        }
+    }
+}
+
+impl From<&ast::EntityUID> for EntityUid {
+    fn from(euid: &ast::EntityUID) -> Self {
+        Self {
+            entity_type: euid.entity_type().to_smolstr(),
+            id: SmolStr::new(<ast::Eid as AsRef<str>>::as_ref(euid.eid())),
+        }
+    }
+}
+
+impl TryFrom<EntityUid> for ast::EntityUID {
+    type Error = StableAstError;
+
+    fn try_from(euid: EntityUid) -> Result<Self, Self::Error> {
+        Ok(Self::from_components(
+            parse_entity_type(&euid.entity_type)?,
+            ast::Eid::new(euid.id),
+            None,
+        ))
+    }
+}
+
+impl From<&ast::EntityReference> for EntityReference {
+    fn from(r: &ast::EntityReference) -> Self {
+        match r {
+            ast::EntityReference::EUID(euid) => Self::Entity(euid.as_ref().into()),
+            ast::EntityReference::Slot => Self::Slot,
+        }
+    }
+}
+
+impl TryFrom<EntityReference> for ast::EntityReference {
+    type Error = StableAstError;
+
+    fn try_from(r: EntityReference) -> Result<Self, Self::Error> {
+        Ok(match r {
+            EntityReference::Entity(euid) => Self::euid(Arc::new(euid.try_into()?)),
+            EntityReference::Slot => Self::Slot,
+        })
+    }
+}
+
+impl From<&ast::PrincipalOrResourceConstraint> for ScopeConstraint {
+    fn from(c: &ast::PrincipalOrResourceConstraint) -> Self {
+        match c {
+            ast::PrincipalOrResourceConstraint::Any => Self::All,
+            ast::PrincipalOrResourceConstraint::Eq(r) => Self::Eq { entity: r.into() },
+            ast::PrincipalOrResourceConstraint::In(r) => Self::In { entity: r.into() },
+            ast::PrincipalOrResourceConstraint::Is(ty) => Self::Is {
+                entity_type: ty.to_smolstr(),
+            },
+            ast::PrincipalOrResourceConstraint::IsIn(ty, r) => Self::IsIn {
+                entity_type: ty.to_smolstr(),
+                entity: r.into(),
+            },
+        }
+    }
+}
+
+impl TryFrom<ScopeConstraint> for ast::PrincipalOrResourceConstraint {
+    type Error = StableAstError;
+
+    fn try_from(c: ScopeConstraint) -> Result<Self, Self::Error> {
+        Ok(match c {
+            ScopeConstraint::All => Self::Any,
+            ScopeConstraint::Eq { entity } => Self::Eq(entity.try_into()?),
+            ScopeConstraint::In { entity } => Self::In(entity.try_into()?),
+            ScopeConstraint::Is { entity_type } => {
+                Self::Is(Arc::new(parse_entity_type(&entity_type)?))
+            }
+            ScopeConstraint::IsIn {
+                entity_type,
+                entity,
+            } => Self::IsIn(
+                Arc::new(parse_entity_type(&entity_type)?),
+                entity.try_into()?,
+            ),
+        })
+    }
+}
+
+impl From<&ast::ActionConstraint> for ActionConstraint {
+    fn from(c: &ast::ActionConstraint) -> Self {
+        match c {
+            ast::ActionConstraint::Any => Self::All,
+            ast::ActionConstraint::Eq(euid) => Self::Eq {
+                entity: euid.as_ref().into(),
+            },
+            ast::ActionConstraint::In(euids) => Self::In {
+                entities: euids.iter().map(|euid| euid.as_ref().into()).collect(),
+            },
+        }
+    }
+}
+
+impl TryFrom<ActionConstraint> for ast::ActionConstraint {
+    type Error = StableAstError;
+
+    fn try_from(c: ActionConstraint) -> Result<Self, Self::Error> {
+        Ok(match c {
+            ActionConstraint::All => Self::any(),
+            ActionConstraint::Eq { entity } => Self::is_eq(entity.try_into()?),
+            ActionConstraint::In { entities } => Self::is_in(
+                entities
+                    .into_iter()
+                    .map(ast::EntityUID::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        })
+    }
+}
+
+impl From<ast::Var> for Var {
+    fn from(v: ast::Var) -> Self {
+        match v {
+            ast::Var::Principal => Self::Principal,
+            ast::Var::Action => Self::Action,
+            ast::Var::Resource => Self::Resource,
+            ast::Var::Context => Self::Context,
+        }
+    }
+}
+
+impl From<Var> for ast::Var {
+    fn from(v: Var) -> Self {
+        match v {
+            Var::Principal => Self::Principal,
+            Var::Action => Self::Action,
+            Var::Resource => Self::Resource,
+            Var::Context => Self::Context,
+        }
+    }
+}
+
+impl From<ast::UnaryOp> for UnaryOp {
+    fn from(op: ast::UnaryOp) -> Self {
+        match op {
+            ast::UnaryOp::Not => Self::Not,
+            ast::UnaryOp::Neg => Self::Neg,
+        }
+    }
+}
+
+impl From<UnaryOp> for ast::UnaryOp {
+    fn from(op: UnaryOp) -> Self {
+        match op {
+            UnaryOp::Not => Self::Not,
+            UnaryOp::Neg => Self::Neg,
+        }
+    }
+}
+
+impl From<ast::BinaryOp> for BinaryOp {
+    fn from(op: ast::BinaryOp) -> Self {
+        match op {
+            ast::BinaryOp::Eq => Self::Eq,
+            ast::BinaryOp::Less => Self::Less,
+            ast::BinaryOp::LessEq => Self::LessEq,
+            ast::BinaryOp::Add => Self::Add,
+            ast::BinaryOp::Sub => Self::Sub,
+            ast::BinaryOp::Mul => Self::Mul,
+            ast::BinaryOp::In => Self::In,
+            ast::BinaryOp::Contains => Self::Contains,
+            ast::BinaryOp::ContainsAll => Self::ContainsAll,
+            ast::BinaryOp::ContainsAny => Self::ContainsAny,
+        }
+    }
+}
+
+impl From<BinaryOp> for ast::BinaryOp {
+    fn from(op: BinaryOp) -> Self {
+        match op {
+            BinaryOp::Eq => Self::Eq,
+            BinaryOp::Less => Self::Less,
+            BinaryOp::LessEq => Self::LessEq,
+            BinaryOp::Add => Self::Add,
+            BinaryOp::Sub => Self::Sub,
+            BinaryOp::Mul => Self::Mul,
+            BinaryOp::In => Self::In,
+            BinaryOp::Contains => Self::Contains,
+            BinaryOp::ContainsAll => Self::ContainsAll,
+            BinaryOp::ContainsAny => Self::ContainsAny,
+        }
+    }
+}
+
+impl From<&ast::Type> for Type {
+    fn from(ty: &ast::Type) -> Self {
+        match ty {
+            ast::Type::Bool => Self::Bool,
+            ast::Type::Long => Self::Long,
+            ast::Type::String => Self::String,
+            ast::Type::Set => Self::Set,
+            ast::Type::Record => Self::Record,
+            ast::Type::Entity { ty } => Self::Entity {
+                name: ty.to_smolstr(),
+            },
+            ast::Type::Extension { name } => Self::Extension {
+                name: name.to_smolstr(),
+            },
+            ast::Type::Null => Self::Null,
+        }
+    }
+}
+
+impl TryFrom<Type> for ast::Type {
+    type Error = StableAstError;
+
+    fn try_from(ty: Type) -> Result<Self, Self::Error> {
+        Ok(match ty {
+            Type::Bool => Self::Bool,
+            Type::Long => Self::Long,
+            Type::String => Self::String,
+            Type::Set => Self::Set,
+            Type::Record => Self::Record,
+            Type::Entity { name } => Self::Entity {
+                ty: parse_entity_type(&name)?,
+            },
+            Type::Extension { name } => Self::Extension {
+                name: parse_name(&name)?,
+            },
+            Type::Null => Self::Null,
+        })
+    }
+}
+
+fn pattern_from_ast(pattern: &ast::Pattern) -> Vec<PatternElem> {
+    let mut elems = Vec::new();
+    let mut literal = String::new();
+    for elem in pattern.iter() {
+        match elem {
+            ast::PatternElem::Char(c) => literal.push(*c),
+            ast::PatternElem::Wildcard => {
+                if !literal.is_empty() {
+                    elems.push(PatternElem::Literal(std::mem::take(&mut literal).into()));
+                }
+                elems.push(PatternElem::Wildcard);
+            }
+        }
+    }
+    if !literal.is_empty() {
+        elems.push(PatternElem::Literal(literal.into()));
+    }
+    elems
+}
+
+fn pattern_to_ast(pattern: Vec<PatternElem>) -> Vec<ast::PatternElem> {
+    pattern
+        .into_iter()
+        .flat_map(|elem| match elem {
+            PatternElem::Literal(s) => s.chars().map(ast::PatternElem::Char).collect(),
+            PatternElem::Wildcard => vec![ast::PatternElem::Wildcard],
+        })
+        .collect()
+}
+
+impl From<&ast::Expr> for Expr {
+    fn from(e: &ast::Expr) -> Self {
+        fn boxed(e: &ast::Expr) -> Box<Expr> {
+            Box::new(Expr::from(e))
+        }
+        match e.expr_kind() {
+            ast::ExprKind::Lit(ast::Literal::Bool(b)) => Self::Bool(*b),
+            ast::ExprKind::Lit(ast::Literal::Long(i)) => Self::Long(*i),
+            ast::ExprKind::Lit(ast::Literal::String(s)) => Self::String(s.clone()),
+            ast::ExprKind::Lit(ast::Literal::EntityUID(euid)) => Self::Entity(euid.as_ref().into()),
+            ast::ExprKind::Lit(ast::Literal::Null) => Self::Null,
+            ast::ExprKind::Var(v) => Self::Var((*v).into()),
+            ast::ExprKind::Slot(slot) => Self::Slot((*slot).into()),
+            ast::ExprKind::Unknown(u) => Self::Unknown {
+                name: u.name.clone(),
+                type_annotation: u.type_annotation.as_ref().map(Type::from),
+            },
+            ast::ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => Self::If {
+                test: boxed(test_expr),
+                then_expr: boxed(then_expr),
+                else_expr: boxed(else_expr),
+            },
+            ast::ExprKind::And { left, right } => Self::And {
+                left: boxed(left),
+                right: boxed(right),
+            },
+            ast::ExprKind::Or { left, right } => Self::Or {
+                left: boxed(left),
+                right: boxed(right),
+            },
+            ast::ExprKind::UnaryApp { op, arg } => Self::UnaryApp {
+                op: (*op).into(),
+                arg: boxed(arg),
+            },
+            ast::ExprKind::BinaryApp { op, arg1, arg2 } => Self::BinaryApp {
+                op: (*op).into(),
+                left: boxed(arg1),
+                right: boxed(arg2),
+            },
+            ast::ExprKind::ExtensionFunctionApp { fn_name, args } => Self::Call {
+                function: fn_name.to_smolstr(),
+                args: args.iter().map(Expr::from).collect(),
+            },
+            ast::ExprKind::GetAttr { expr, attr } => Self::GetAttr {
+                expr: boxed(expr),
+                attr: attr.clone(),
+            },
+            ast::ExprKind::HasAttr { expr, attr } => Self::HasAttr {
+                expr: boxed(expr),
+                attr: attr.clone(),
+            },
+            ast::ExprKind::Like { expr, pattern } => Self::Like {
+                expr: boxed(expr),
+                pattern: pattern_from_ast(pattern),
+            },
+            ast::ExprKind::Is { expr, entity_type } => Self::Is {
+                expr: boxed(expr),
+                entity_type: entity_type.to_smolstr(),
+            },
+            ast::ExprKind::Set(elems) => Self::Set(elems.iter().map(Expr::from).collect()),
+            ast::ExprKind::Record(fields) => Self::Record(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Expr::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<Expr> for ast::Expr {
+    type Error = StableAstError;
+
+    fn try_from(e: Expr) -> Result<Self, Self::Error> {
+        let convert = |e: Box<Expr>| ast::Expr::try_from(*e);
+        Ok(match e {
+            Expr::Bool(b) => Self::val(b),
+            Expr::Long(i) => Self::val(i),
+            Expr::String(s) => Self::val(s),
+            Expr::Entity(euid) => Self::val(ast::EntityUID::try_from(euid)?),
+            Expr::Null => Self::val(ast::Literal::Null),
+            Expr::Var(v) => Self::var(v.into()),
+            Expr::Slot(slot) => Self::slot(slot.into()),
+            Expr::Unknown {
+                name,
+                type_annotation,
+            } => Self::unknown(match type_annotation {
+                Some(ty) => ast::Unknown::new_with_type(name, ty.try_into()?),
+                None => ast::Unknown::new_untyped(name),
+            }),
+            Expr::If {
+                test,
+                then_expr,
+                else_expr,
+            } => Self::ite(convert(test)?, convert(then_expr)?, convert(else_expr)?),
+            Expr::And { left, right } => Self::and(convert(left)?, convert(right)?),
+            Expr::Or { left, right } => Self::or(convert(left)?, convert(right)?),
+            Expr::UnaryApp { op, arg } => Self::unary_app(ast::UnaryOp::from(op), convert(arg)?),
+            Expr::BinaryApp { op, left, right } => {
+                Self::binary_app(ast::BinaryOp::from(op), convert(left)?, convert(right)?)
+            }
+            Expr::Call { function, args } => Self::call_extension_fn(
+                parse_name(&function)?,
+                args.into_iter()
+                    .map(ast::Expr::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Expr::GetAttr { expr, attr } => Self::get_attr(convert(expr)?, attr),
+            Expr::HasAttr { expr, attr } => Self::has_attr(convert(expr)?, attr),
+            Expr::Like { expr, pattern } => Self::like(convert(expr)?, pattern_to_ast(pattern)),
+            Expr::Is { expr, entity_type } => {
+                Self::is_entity_type(convert(expr)?, parse_entity_type(&entity_type)?)
+            }
+            Expr::Set(elems) => Self::set(
+                elems
+                    .into_iter()
+                    .map(ast::Expr::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Expr::Record(fields) => Self::record_arc(Arc::new(
+                fields
+                    .into_iter()
+                    .map(|(k, v)| Ok((k, ast::Expr::try_from(v)?)))
+                    .collect::<Result<BTreeMap<_, _>, StableAstError>>()?,
+            )),
+        })
+    }
+}
+
+/// Parse a name, entity type, or annotation key which was written by
+/// `Display`
+fn parse_name<T: FromNormalizedStr>(name: &SmolStr) -> Result<T, StableAstError> {
+    T::from_normalized_str(name).map_err(|source| StableAstError::InvalidName {
+        name: name.clone(),
+        source,
+    })
+}
+
+fn parse_entity_type(name: &SmolStr) -> Result<ast::EntityType, StableAstError> {
+    parse_name::<ast::Name>(name).map(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_policyset;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    /// The text of the templates and static policies of `pset`, sorted
+    fn sorted_policies(pset: &ast::PolicySet) -> Vec<String> {
+        let mut policies: Vec<String> = pset.all_templates().map(ToString::to_string).collect();
+        policies.sort();
+        policies
+    }
+
+    /// The template and slot values of each template-linked policy of `pset`.
+    /// The text of a linked policy lists its slot values in no fixed order.
+    fn links(pset: &ast::PolicySet) -> HashMap<ast::PolicyID, (ast::PolicyID, ast::SlotEnv)> {
+        pset.policies()
+            .filter(|p| !p.is_static())
+            .map(|p| (p.id().clone(), (p.template().id().clone(), p.env().clone())))
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let pset = parse_policyset(
+            r#"
+            @id("a")
+            permit(principal == User::"alice", action in [Action::"view", Action::"edit"], resource is Photo in Album::"trip")
+            when { context.ip.isInRange(ip("10.0.0.0/8")) && resource.name like "*.jpg" }
+            unless { principal has banned || -(1 + 2) * 3 <= 0 };
+            forbid(principal, action == Action::"delete", resource)
+            when { if principal.admin then false else [1, "two", {three: 3}].contains(resource.owner) };
+            permit(principal in ?principal, action, resource == ?resource);
+            "#,
+        )
+        .unwrap();
+        let mut pset = pset;
+        let template_id = pset.templates().next().unwrap().id().clone();
+        pset.link(
+            template_id,
+            ast::PolicyID::from_string("link"),
+            HashMap::from([
+                (
+                    ast::SlotId::principal(),
+                    r#"Group::"admins""#.parse().unwrap(),
+                ),
+                (ast::SlotId::resource(), r#"Photo::"x\"y""#.parse().unwrap()),
+            ]),
+        )
+        .unwrap();
+
+        let stored = serde_json::to_value(PolicySet::from(&pset)).unwrap();
+        let loaded = from_json_value(stored).unwrap();
+        assert_eq!(sorted_policies(&pset), sorted_policies(&loaded));
+        assert_eq!(links(&pset), links(&loaded));
+    }
+
+    #[test]
+    fn version_1_format() {
+        let stored = json!({
+            "version": 1,
+            "policies": [{
+                "id": "policy0",
+                "effect": "permit",
+                "annotations": { "id": "a" },
+                "principal": { "op": "eq", "entity": { "entity": { "type": "User", "id": "alice" } } },
+                "action": { "op": "in", "entities": [{ "type": "Action", "id": "view" }] },
+                "resource": { "op": "isIn", "entityType": "Photo", "entity": { "entity": { "type": "Album", "id": "trip" } } },
+                "condition": { "binaryApp": {
+                    "op": "==",
+                    "left": { "getAttr": { "expr": { "var": "context" }, "attr": "mfa" } },
+                    "right": { "bool": true }
+                } }
+            }],
+            "templates": [{
+                "id": "template0",
+                "effect": "forbid",
+                "principal": { "op": "in", "entity": "slot" },
+                "action": { "op": "all" },
+                "resource": { "op": "all" },
+                "condition": { "bool": true }
+            }],
+            "links": [{
+                "id": "link0",
+                "templateId": "template0",
+                "values": { "?principal": { "type": "Group", "id": "banned" } }
+            }]
+        });
+        let pset = from_json_value(stored.clone()).unwrap();
+        let link = pset.get(&ast::PolicyID::from_string("link0")).unwrap();
+        assert_eq!(link.template().id().as_ref(), "template0");
+        let policy = pset.get(&ast::PolicyID::from_string("policy0")).unwrap();
+        assert!(policy.is_static());
+        assert_eq!(
+            policy
+                .annotation(&"id".parse().unwrap())
+                .map(|a| a.val.as_str()),
+            Some("a")
+        );
+        assert_eq!(
+            serde_json::to_value(PolicySet::from(&pset)).unwrap(),
+            stored
+        );
+    }
+
+    #[test]
+    fn newer_version() {
+        assert_matches!(
+            from_json_value(json!({ "version": 2, "policies": [], "templates": [], "links": [] })),
+            Err(StableAstError::UnsupportedVersion { version: 2 })
+        );
+        assert_matches!(
+            from_json_value(json!({ "policies": [], "templates": [], "links": [] })),
+            Err(StableAstError::MissingVersion)
+        );
+    }
+
+    #[test]
+    fn residuals_version_1_format() {
+        let stored = json!({
+            "version": 1,
+            "residuals": [{
+                "id": "policy0",
+                "effect": "forbid",
+                "condition": { "binaryApp": {
+                    "op": "in",
+                    "left": { "unknown": { "name": "principal", "typeAnnotation": { "kind": "entity", "name": "User" } } },
+                    "right": { "entity": { "type": "Group", "id": "banned" } }
+                } }
+            }],
+            "unknowns": {
+                "principal": [{ "kind": "principal" }],
+                "age": [{ "kind": "entityAttribute", "entity": { "type": "User", "id": "alice" }, "path": ["profile", "age"] }]
+            }
+        });
+        let residuals = residuals_from_json_value(stored.clone()).unwrap();
+        let condition = ast::Expr::try_from(residuals.residuals[0].condition.clone()).unwrap();
+        assert_eq!(
+            condition
+                .unknowns()
+                .map(|u| u.name.as_str())
+                .collect::<Vec<_>>(),
+            ["principal"]
+        );
+        assert_eq!(serde_json::to_value(residuals).unwrap(), stored);
+    }
+
+    #[test]
+    fn slot_in_static_policy() {
+        let stored = json!({
+            "version": 1,
+            "policies": [{
+                "id": "policy0",
+                "effect": "permit",
+                "principal": { "op": "eq", "entity": "slot" },
+                "action": { "op": "all" },
+                "resource": { "op": "all" },
+                "condition": { "bool": true }
+            }],
+            "templates": [],
+            "links": []
+        });
+        assert_matches!(
+            from_json_value(stored),
+            Err(StableAstError::SlotInStaticPolicy(id)) if id == "policy0"
+        );
+    }
+}
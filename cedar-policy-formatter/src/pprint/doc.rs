@@ -538,8 +538,14 @@ impl Doc for Node<Option<Ref>> {
 
 impl Doc for Node<Option<Literal>> {
     fn to_doc(&self, context: &mut Context<'_>) -> Option<RcDoc<'_>> {
+        let doc = match self.as_inner()? {
+            // numbers are printed as written, e.g., in hexadecimal or with `_`
+            // separators
+            Literal::Num(_) => RcDoc::as_string(self.loc.snippet()?),
+            lit => RcDoc::as_string(lit),
+        };
         Some(add_comment(
-            RcDoc::as_string(self.as_inner()?),
+            doc,
             get_comment_at_start(self.loc.span, &mut context.tokens)?,
             RcDoc::nil(),
         ))
@@ -660,6 +666,19 @@ impl Doc for Node<Option<MemAccess>> {
                 )
                 .append(f.to_doc(context)),
             ),
+            MemAccess::OptionalField(f) => Some(
+                add_comment(
+                    RcDoc::text("."),
+                    get_comment_at_start(self.loc.span, &mut context.tokens)?,
+                    RcDoc::nil(),
+                )
+                .append(f.to_doc(context))
+                .append(add_comment(
+                    RcDoc::text("?"),
+                    get_comment_at_end(self.loc.span, &mut context.tokens)?,
+                    RcDoc::nil(),
+                )),
+            ),
             MemAccess::Call(args) => Some(
                 add_comment(
                     RcDoc::text("("),
@@ -757,6 +776,47 @@ impl Doc for Node<Option<Ident>> {
     }
 }
 
+impl Doc for Node<Option<Def>> {
+    fn to_doc(&self, context: &mut Context<'_>) -> Option<RcDoc<'_>> {
+        let def = self.as_inner()?;
+
+        let anno_doc = RcDoc::intersperse(
+            def.annotations.iter().map(|a| a.to_doc(context)),
+            RcDoc::nil(),
+        );
+        let kw_leading_comment =
+            get_leading_comment_at_start(def.keyword.loc.span, &mut context.tokens)?;
+        let kw_doc = def.keyword.to_doc(context)?;
+        let name_doc = def.name.to_doc(context)?;
+        let eq_doc = add_comment(
+            RcDoc::text("="),
+            get_comment_after_end(def.name.loc.span, &mut context.tokens)?,
+            RcDoc::nil(),
+        );
+        let body_doc = def.body.to_doc(context)?;
+        Some(
+            anno_doc
+                .append(get_leading_comment_doc_from_str(&kw_leading_comment))
+                .append(kw_doc)
+                .append(RcDoc::space())
+                .append(name_doc)
+                .append(RcDoc::space())
+                .append(eq_doc)
+                .append(
+                    RcDoc::line()
+                        .append(body_doc.group())
+                        .nest(context.config.indent_width)
+                        .group(),
+                )
+                .append(add_comment(
+                    RcDoc::text(";"),
+                    get_comment_at_end(self.loc.span, &mut context.tokens)?,
+                    RcDoc::nil(),
+                )),
+        )
+    }
+}
+
 impl Doc for Node<Option<Policy>> {
     fn to_doc(&self, context: &mut Context<'_>) -> Option<RcDoc<'_>> {
         let policy = self.as_inner()?;
@@ -29,7 +29,7 @@ use super::utils::remove_empty_lines;
 use super::config::{self, Config};
 use super::doc::*;
 
-fn tree_to_pretty<T: Doc>(t: &T, context: &mut config::Context<'_>) -> Result<String> {
+fn tree_to_pretty<T: Doc + ?Sized>(t: &T, context: &mut config::Context<'_>) -> Result<String> {
     let mut w = Vec::new();
     let config = context.config;
     let doc = t.to_doc(context);
@@ -101,12 +101,21 @@ pub fn policies_str_to_pretty(ps: &str, config: &Config) -> Result<String> {
     let (tokens, end_of_file_comment) =
         get_token_stream(ps).ok_or(miette!("cannot get token stream"))?;
     let mut context = config::Context { config, tokens };
-    let mut formatted_policies = cst
+    let policies = cst
         .as_inner()
-        .ok_or(miette!("fail to get input policy CST"))?
+        .ok_or(miette!("fail to get input policy CST"))?;
+    // format policies and definitions in their order in the input, which is
+    // the order of their tokens
+    let mut items: Vec<(usize, &dyn Doc)> = policies
         .0
         .iter()
-        .map(|p| Ok(remove_empty_lines(&tree_to_pretty(p, &mut context)?)))
+        .map(|p| (p.loc.start(), p as &dyn Doc))
+        .chain(policies.1.iter().map(|d| (d.loc.start(), d as &dyn Doc)))
+        .collect();
+    items.sort_by_key(|(start, _)| *start);
+    let mut formatted_policies = items
+        .into_iter()
+        .map(|(_, item)| Ok(remove_empty_lines(&tree_to_pretty(item, &mut context)?)))
         .collect::<Result<Vec<String>>>()?
         .join("\n\n");
     // handle comment at the end of a policyset
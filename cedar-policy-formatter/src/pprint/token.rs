@@ -78,6 +78,15 @@ impl Display for Comment {
     }
 }
 
+// Lex the rest of a raw string literal `r#"..."#` after its opening `r#"`.
+// Logos does not backtrack, so it can't match these literals, whose content
+// may end with `"`, with a single regex.
+fn raw_hash_string(lex: &mut logos::Lexer<'_, Token>) -> Option<SmolStr> {
+    let end = lex.remainder().find("\"#")?;
+    lex.bump(end + 2);
+    Some(SmolStr::new(lex.slice()))
+}
+
 // Cedar tokens
 #[derive(Logos, Clone, Debug, PartialEq)]
 pub enum Token {
@@ -147,10 +156,12 @@ pub enum Token {
     #[regex(r"[_a-zA-Z][_a-zA-Z0-9]*", |lex| SmolStr::new(lex.slice()))]
     Identifier(SmolStr),
 
-    #[regex("[0-9]+", |lex| SmolStr::new(lex.slice()))]
+    #[regex("[0-9][0-9_]*|0x[0-9a-fA-F_]+", |lex| SmolStr::new(lex.slice()))]
     Number(SmolStr),
 
     #[regex(r#""(\\.|[^"\\])*""#, |lex| SmolStr::new(lex.slice()))]
+    #[regex(r#"r"[^"]*""#, |lex| SmolStr::new(lex.slice()))]
+    #[token(r##"r#""##, raw_hash_string)]
     Str(SmolStr),
 
     #[token("@")]
@@ -189,6 +200,9 @@ pub enum Token {
     #[token("]")]
     RBracket,
 
+    #[token("=")]
+    Assign,
+
     #[token("==")]
     Equal,
 
@@ -230,6 +244,9 @@ pub enum Token {
 
     #[token("!")]
     Neg,
+
+    #[token("?")]
+    Question,
 }
 
 impl fmt::Display for Token {
@@ -238,6 +255,7 @@ impl fmt::Display for Token {
             Self::Action => write!(f, "action"),
             Self::Add => write!(f, "+"),
             Self::And => write!(f, "&&"),
+            Self::Assign => write!(f, "="),
             Self::At => write!(f, "@"),
             Self::Colon => write!(f, ":"),
             Self::Comma => write!(f, ","),
@@ -275,6 +293,7 @@ impl fmt::Display for Token {
             Self::Permit => write!(f, "permit"),
             Self::Principal => write!(f, "principal"),
             Self::PrincipalSlot => write!(f, "principal?"),
+            Self::Question => write!(f, "?"),
             Self::RBrace => write!(f, "}}"),
             Self::RBracket => write!(f, "]"),
             Self::RParen => write!(f, ")"),
@@ -21,6 +21,8 @@ pub use ast::Path;
 mod err;
 pub mod fmt;
 pub mod parser;
+mod source_locs;
+pub(crate) use source_locs::collect_source_locs;
 pub(crate) mod test;
 pub mod to_json_schema;
 pub use err::ParseError;
@@ -29,7 +29,7 @@ use smol_str::ToSmolStr;
 
 use crate::json_schema;
 
-pub const BUILTIN_TYPES: [&str; 3] = ["Long", "String", "Bool"];
+pub const BUILTIN_TYPES: [&str; 4] = ["Long", "String", "Bool", "Null"];
 
 pub(super) const CEDAR_NAMESPACE: &str = "__cedar";
 
@@ -212,10 +212,19 @@ pub struct EntityDecl {
     /// Entity Type Names bound by this declaration.
     /// More than one name can be bound if they have the same definition, for convenience
     pub names: Vec<Node<Id>>,
+    /// Entity type this type extends, declared with `extends`
+    pub extends: Option<Path>,
     /// Entity Types this type is allowed to be related to via the `in` relation
     pub member_of_types: Vec<Path>,
+    /// Attributes whose values are implied parents, declared with `via`
+    pub parent_attributes: Vec<Node<Id>>,
     /// Attributes this entity has
     pub attrs: Vec<Node<AttrDecl>>,
+    /// Computed attributes, declared with `computed`, with the source of the
+    /// expressions computing them
+    pub computed_attributes: Vec<(Node<SmolStr>, Node<SmolStr>)>,
+    /// Annotations of this declaration
+    pub annotations: Vec<Annotation>,
 }
 
 /// Type definitions
@@ -227,6 +236,8 @@ pub enum Type {
     Ident(Path),
     /// A Record
     Record(Vec<Node<AttrDecl>>),
+    /// A union of two or more types, e.g., `String | Long`
+    Union(Vec<Node<Type>>),
 }
 
 /// Primitive Type Definitions
@@ -238,6 +249,8 @@ pub enum PrimitiveType {
     String,
     /// Cedar booleans
     Bool,
+    /// The type of `null`
+    Null,
 }
 
 impl<N> From<PrimitiveType> for json_schema::TypeVariant<N> {
@@ -246,6 +259,7 @@ impl<N> From<PrimitiveType> for json_schema::TypeVariant<N> {
             PrimitiveType::Long => json_schema::TypeVariant::Long,
             PrimitiveType::String => json_schema::TypeVariant::String,
             PrimitiveType::Bool => json_schema::TypeVariant::Boolean,
+            PrimitiveType::Null => json_schema::TypeVariant::Null,
         }
     }
 }
@@ -260,6 +274,18 @@ pub struct AttrDecl {
     pub required: bool,
     /// The type of this attribute
     pub ty: Node<Type>,
+    /// Annotations of this attribute
+    pub annotations: Vec<Annotation>,
+}
+
+/// An annotation of a declaration, e.g., `@deprecated("2.0")`
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    /// The annotation's key
+    pub key: Node<Id>,
+    /// The annotation's value, if any. An annotation without a value has the
+    /// empty string as its value.
+    pub value: Option<Node<SmolStr>>,
 }
 
 /// The target of a [`PRAppDecl`]
@@ -308,6 +334,11 @@ pub struct ActionDecl {
     pub parents: Option<NonEmpty<Node<QualName>>>,
     /// The constraining clauses in this declarations
     pub app_decls: Option<Node<NonEmpty<Node<AppDecl>>>>,
+    /// The location of an empty `attributes {}` clause, which is deprecated
+    /// and has no effect
+    pub attributes: Option<Loc>,
+    /// Annotations of this declaration
+    pub annotations: Vec<Annotation>,
 }
 
 impl Decl for ActionDecl {
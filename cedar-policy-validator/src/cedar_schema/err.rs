@@ -47,6 +47,8 @@ pub enum UserError {
     StringEscape(NonEmpty<UnescapeError>),
     #[error("`{0}` is a reserved identifier")]
     ReservedIdentifierUsed(SmolStr),
+    #[error("duplicate annotation `@{0}`")]
+    DuplicateAnnotation(SmolStr),
 }
 
 pub(crate) type RawLocation = usize;
@@ -71,6 +73,9 @@ lazy_static! {
             ("NAMESPACE", "`namespace`"),
             ("TYPE", "`type`"),
             ("SET", "`Set`"),
+            ("VIA", "`via`"),
+            ("COMPUTED", "`computed`"),
+            ("EXTENDS", "`extends`"),
             ("IDENTIFIER", "identifier"),
         ]),
         impossible_tokens: HashSet::new(),
@@ -85,6 +90,9 @@ lazy_static! {
             "RESOURCE",
             "CONTEXT",
             "ATTRIBUTES",
+            "VIA",
+            "COMPUTED",
+            "EXTENDS",
             "LONG",
             "STRING",
             "BOOL",
@@ -676,6 +684,71 @@ pub mod schema_warnings {
             Some(miette::Severity::Warning)
         }
     }
+
+    /// Warning when a schema uses a deprecated construct, which a future
+    /// version of the schema format may reject
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Clone, Error)]
+    #[error("{construct} is deprecated")]
+    pub struct DeprecatedConstructWarning {
+        pub(crate) construct: DeprecatedConstruct,
+        pub(crate) loc: Loc,
+    }
+
+    impl DeprecatedConstructWarning {
+        /// The deprecated construct
+        pub fn construct(&self) -> DeprecatedConstruct {
+            self.construct
+        }
+    }
+
+    impl Diagnostic for DeprecatedConstructWarning {
+        impl_diagnostic_from_source_loc_field!(loc);
+
+        fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+            Some(Box::new(self.construct.upgrade_hint()))
+        }
+
+        fn severity(&self) -> Option<miette::Severity> {
+            Some(miette::Severity::Warning)
+        }
+    }
+
+    /// A construct of the Cedar schema format which is deprecated. Parsing a
+    /// schema using one reports a [`DeprecatedConstructWarning`], with a hint
+    /// on how to upgrade the schema.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum DeprecatedConstruct {
+        /// An empty `attributes {}` clause in an action declaration. Action
+        /// attributes are not supported in the Cedar schema format, and the
+        /// clause has no effect.
+        EmptyActionAttributes,
+    }
+
+    impl DeprecatedConstruct {
+        /// How to rewrite the schema without the deprecated construct
+        pub fn upgrade_hint(self) -> &'static str {
+            match self {
+                Self::EmptyActionAttributes => {
+                    "remove the `attributes {}` clause, which has no effect"
+                }
+            }
+        }
+    }
+
+    impl std::fmt::Display for DeprecatedConstruct {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::EmptyActionAttributes => {
+                    write!(f, "the `attributes {{}}` clause of action declarations")
+                }
+            }
+        }
+    }
 }
 
 /// Warning when constructing a schema
@@ -694,4 +767,8 @@ pub enum SchemaWarning {
     #[error(transparent)]
     #[diagnostic(transparent)]
     ShadowsEntity(#[from] schema_warnings::ShadowsEntityWarning),
+    /// Warning when a schema uses a deprecated construct
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DeprecatedConstruct(#[from] schema_warnings::DeprecatedConstructWarning),
 }
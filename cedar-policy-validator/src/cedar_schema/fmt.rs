@@ -17,7 +17,10 @@
 //! `Display` implementations for formatting a [`json_schema::Fragment`] in the
 //! Cedar schema syntax
 
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+};
 
 use itertools::Itertools;
 use miette::Diagnostic;
@@ -45,15 +48,67 @@ impl<N: Display> Display for json_schema::NamespaceDefinition<N> {
             writeln!(f, "type {n} = {ty};")?
         }
         for (n, ty) in &self.entity_types {
-            writeln!(f, "entity {n}{ty};")?
+            fmt_doc_comment(f, &ty.annotations)?;
+            writeln!(
+                f,
+                "{}entity {n}{ty};",
+                fmt_decl_annotations(&ty.annotations)
+            )?
         }
         for (n, a) in &self.actions {
-            writeln!(f, "action \"{}\"{a};", n.escape_debug())?
+            fmt_doc_comment(f, &a.annotations)?;
+            writeln!(
+                f,
+                "{}action \"{}\"{a};",
+                fmt_decl_annotations(&a.annotations),
+                n.escape_debug()
+            )?
         }
         Ok(())
     }
 }
 
+/// Write the `doc` annotation of a declaration, if any, as a doc comment
+fn fmt_doc_comment(
+    f: &mut std::fmt::Formatter<'_>,
+    annotations: &BTreeMap<SmolStr, SmolStr>,
+) -> std::fmt::Result {
+    if let Some(doc) = annotations.get("doc") {
+        for line in doc.lines() {
+            writeln!(f, "{}", format!("/// {line}").trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+/// Format the annotations of an attribute, each followed by a space
+fn fmt_annotations(annotations: &BTreeMap<SmolStr, SmolStr>) -> String {
+    fmt_annotations_except(annotations, None)
+}
+
+/// Format the annotations of an entity type or action declaration, other than
+/// `doc`, which is written as a doc comment
+fn fmt_decl_annotations(annotations: &BTreeMap<SmolStr, SmolStr>) -> String {
+    fmt_annotations_except(annotations, Some("doc"))
+}
+
+fn fmt_annotations_except(
+    annotations: &BTreeMap<SmolStr, SmolStr>,
+    except: Option<&str>,
+) -> String {
+    annotations
+        .iter()
+        .filter(|(key, _)| Some(key.as_str()) != except)
+        .map(|(key, value)| {
+            if value.is_empty() {
+                format!("@{key} ")
+            } else {
+                format!("@{key}(\"{}\") ", value.escape_debug())
+            }
+        })
+        .join("")
+}
+
 impl<N: Display> Display for json_schema::Type<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -65,9 +120,13 @@ impl<N: Display> Display for json_schema::Type<N> {
                 }
                 json_schema::TypeVariant::Extension { name } => write!(f, "__cedar::{name}"),
                 json_schema::TypeVariant::Long => write!(f, "__cedar::Long"),
+                json_schema::TypeVariant::Null => write!(f, "__cedar::Null"),
                 json_schema::TypeVariant::Record(rty) => write!(f, "{rty}"),
                 json_schema::TypeVariant::Set { element } => write!(f, "Set < {element} >"),
                 json_schema::TypeVariant::String => write!(f, "__cedar::String"),
+                json_schema::TypeVariant::Union { arms } => {
+                    write!(f, "{}", arms.iter().join(" | "))
+                }
             },
             json_schema::Type::CommonTypeRef { type_name } => write!(f, "{type_name}"),
         }
@@ -80,7 +139,8 @@ impl<N: Display> Display for json_schema::RecordType<N> {
         for (i, (n, ty)) in self.attributes.iter().enumerate() {
             write!(
                 f,
-                "\"{}\"{}: {}",
+                "{}\"{}\"{}: {}",
+                fmt_annotations(&ty.annotations),
                 n.escape_debug(),
                 if ty.required { "" } else { "?" },
                 ty.ty
@@ -107,10 +167,17 @@ fn fmt_vec<T: Display>(f: &mut std::fmt::Formatter<'_>, ets: NonEmpty<T>) -> std
 
 impl<N: Display> Display for json_schema::EntityType<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(extends) = &self.extends {
+            write!(f, " extends {extends}")?;
+        }
         if let Some(non_empty) = non_empty_slice(&self.member_of_types) {
             write!(f, " in ")?;
             fmt_vec(f, non_empty)?;
         }
+        if let Some(non_empty) = non_empty_slice(&self.parent_attributes) {
+            write!(f, " via ")?;
+            fmt_vec(f, non_empty)?;
+        }
 
         let ty = &self.shape;
         // Don't print `= { }`
@@ -118,6 +185,17 @@ impl<N: Display> Display for json_schema::EntityType<N> {
             write!(f, " = {ty}")?;
         }
 
+        if !self.computed_attributes.is_empty() {
+            let contents = self
+                .computed_attributes
+                .iter()
+                .map(|(attr, expr)| {
+                    format!("\"{}\": \"{}\"", attr.escape_debug(), expr.escape_debug())
+                })
+                .join(", ");
+            write!(f, " computed {{{contents}}}")?;
+        }
+
         Ok(())
     }
 }
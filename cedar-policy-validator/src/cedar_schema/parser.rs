@@ -24,10 +24,12 @@ use thiserror::Error;
 
 use super::{
     ast::Schema,
+    collect_source_locs,
     err::{self, ParseError, ParseErrors, SchemaWarning, ToJsonSchemaErrors},
     to_json_schema::cedar_schema_to_json_schema,
 };
 use crate::json_schema;
+use crate::schema::SchemaSourceLocs;
 use cedar_policy_core::extensions::Extensions;
 
 lalrpop_mod!(
@@ -116,6 +118,25 @@ pub fn parse_cedar_schema_fragment<'a>(
     Ok(tuple)
 }
 
+/// Like [`parse_cedar_schema_fragment()`], but also returns the source
+/// locations of the declarations in the schema
+pub(crate) fn parse_cedar_schema_fragment_with_locs<'a>(
+    src: &str,
+    extensions: &Extensions<'a>,
+) -> Result<
+    (
+        json_schema::Fragment<crate::RawName>,
+        impl Iterator<Item = SchemaWarning> + 'a,
+        SchemaSourceLocs,
+    ),
+    CedarSchemaParseErrors,
+> {
+    let ast: Schema = parse_collect_errors(&*SCHEMA_PARSER, grammar::SchemaParser::parse, src)?;
+    let locs = collect_source_locs(&ast);
+    let (fragment, warnings) = cedar_schema_to_json_schema(ast, extensions)?;
+    Ok((fragment, warnings, locs))
+}
+
 /// Parse schema from text
 pub fn parse_schema(text: &str) -> Result<Schema, err::ParseErrors> {
     parse_collect_errors(&*SCHEMA_PARSER, grammar::SchemaParser::parse, text)
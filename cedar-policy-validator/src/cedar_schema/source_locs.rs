@@ -0,0 +1,74 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Collects the source locations of declarations in a Cedar-syntax schema
+
+use cedar_policy_core::ast::{Eid, EntityType, EntityUID, Name};
+use cedar_policy_core::FromNormalizedStr;
+
+use super::ast::{Declaration, Schema};
+use crate::schema::SchemaSourceLocs;
+
+/// Record the locations of the entity type, attribute, and action
+/// declarations in `schema`, keyed by their fully qualified names
+pub(crate) fn collect_source_locs(schema: &Schema) -> SchemaSourceLocs {
+    let mut locs = SchemaSourceLocs::default();
+    for ns in schema {
+        let prefix = ns.node.name.as_ref().map(|path| path.node.to_string());
+        let qualify = |basename: &str| -> Option<EntityType> {
+            let full = match &prefix {
+                Some(prefix) => format!("{prefix}::{basename}"),
+                None => basename.to_string(),
+            };
+            Name::from_normalized_str(&full).ok().map(EntityType::from)
+        };
+        let action_type = qualify("Action");
+        for decl in &ns.node.decls {
+            match &decl.node {
+                Declaration::Entity(entity) => {
+                    for name in &entity.names {
+                        let Some(ety) = qualify(name.node.as_ref()) else {
+                            continue;
+                        };
+                        for attr in &entity.attrs {
+                            locs.add_attribute(
+                                ety.clone(),
+                                attr.node.name.node.clone(),
+                                attr.loc.clone(),
+                            );
+                        }
+                        locs.add_entity_type(ety, name.loc.clone());
+                    }
+                }
+                Declaration::Action(action) => {
+                    let Some(action_type) = &action_type else {
+                        continue;
+                    };
+                    for name in action.names.iter() {
+                        let uid = EntityUID::from_components(
+                            action_type.clone(),
+                            Eid::new(name.node.clone()),
+                            None,
+                        );
+                        locs.add_action(uid, name.loc.clone());
+                    }
+                }
+                Declaration::Type(_) => {}
+            }
+        }
+    }
+    locs
+}
@@ -19,7 +19,7 @@
 #[cfg(test)]
 mod demo_tests {
     use std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         iter::{empty, once},
     };
 
@@ -336,6 +336,7 @@ mod demo_tests {
             attributes: None,
             applies_to: None,
             member_of: None,
+            annotations: BTreeMap::new(),
         };
         let namespace =
             json_schema::NamespaceDefinition::new(empty(), once(("foo".to_smolstr(), action)));
@@ -435,6 +436,10 @@ namespace Baz {action "Foo" appliesTo {
                 "a".parse().unwrap(),
                 json_schema::EntityType::<RawName> {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )]),
@@ -448,6 +453,7 @@ namespace Baz {action "Foo" appliesTo {
                         context: json_schema::AttributesOrContext::default(),
                     }),
                     member_of: None,
+                    annotations: BTreeMap::new(),
                 },
             )]),
         };
@@ -885,7 +891,7 @@ namespace Baz {action "Foo" appliesTo {
             attributes,
             additional_attributes: false,
         }))) => {
-            assert_matches!(attributes.get("tag"), Some(json_schema::TypeOfAttribute { ty, required: true }) => {
+            assert_matches!(attributes.get("tag"), Some(json_schema::TypeOfAttribute { ty, required: true, .. }) => {
                 assert_matches!(ty, json_schema::Type::Type(json_schema::TypeVariant::EntityOrCommon { type_name }) => {
                     assert_eq!(type_name, &"AWS::Tag".parse().unwrap());
                 });
@@ -916,7 +922,7 @@ namespace Baz {action "Foo" appliesTo {
         assert_labeled_span("type t =", "expected `{`, identifier, or `Set`");
         assert_labeled_span(
             "entity User {",
-            "expected `}`, identifier, or string literal",
+            "expected `@`, `}`, identifier, or string literal",
         );
         assert_labeled_span("entity User { name:", "expected `{`, identifier, or `Set`");
     }
@@ -1155,6 +1161,8 @@ mod parser_tests {
 #[allow(clippy::panic)]
 #[cfg(test)]
 mod translator_tests {
+    use std::collections::BTreeMap;
+
     use cedar_policy_core::ast as cedar_ast;
     use cedar_policy_core::extensions::Extensions;
     use cedar_policy_core::test_utils::{expect_err, ExpectedErrorMessageBuilder};
@@ -1163,8 +1171,8 @@ mod translator_tests {
 
     use crate::{
         cedar_schema::{
-            err::ToJsonSchemaError, parser::parse_schema,
-            to_json_schema::cedar_schema_to_json_schema,
+            err::ToJsonSchemaError, parser::parse_schema, schema_warnings::DeprecatedConstruct,
+            to_json_schema::cedar_schema_to_json_schema, SchemaWarning,
         },
         json_schema,
         schema::test::collect_warnings,
@@ -1193,6 +1201,171 @@ mod translator_tests {
         assert_matches!(schema, Err(_));
     }
 
+    #[test]
+    fn deprecated_action_attributes() {
+        let src = r#"
+          entity User;
+          action view appliesTo { principal: User, resource: User } attributes {};
+          action edit appliesTo { principal: User, resource: User };
+        "#;
+        let (_, warnings) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let warnings: Vec<_> = warnings.collect();
+        assert_matches!(&warnings[..], [SchemaWarning::DeprecatedConstruct(warning)] => {
+            assert_eq!(warning.construct(), DeprecatedConstruct::EmptyActionAttributes);
+            expect_err(
+                src,
+                &miette::Report::new(warning.clone()),
+                &ExpectedErrorMessageBuilder::error(
+                    "the `attributes {}` clause of action declarations is deprecated",
+                )
+                .help("remove the `attributes {}` clause, which has no effect")
+                .exactly_one_underline("attributes {}")
+                .build(),
+            );
+        });
+    }
+
+    #[test]
+    fn doc_comments() {
+        let (schema, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+          namespace Photos {
+            /// A user of the application
+            ///
+            /// Users may belong to groups.
+            entity User;
+            // Not a doc comment
+            entity Group;
+            /// Not a doc comment either, as it is followed by an empty line
+
+            /// View a photo
+            action view appliesTo { principal: User, resource: User };
+          }
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let ns = schema.0.get(&Some("Photos".parse().unwrap())).unwrap();
+        let user = ns.entity_types.get(&"User".parse().unwrap()).unwrap();
+        let group = ns.entity_types.get(&"Group".parse().unwrap()).unwrap();
+        let view = ns.actions.get("view").unwrap();
+        assert_eq!(
+            user.annotations.get("doc").map(|doc| doc.as_str()),
+            Some("A user of the application\n\nUsers may belong to groups.")
+        );
+        assert_eq!(group.annotations.get("doc"), None);
+        assert_eq!(
+            view.annotations.get("doc").map(|doc| doc.as_str()),
+            Some("View a photo")
+        );
+
+        let src = schema.to_cedarschema().unwrap();
+        assert!(
+            src.contains(
+                "/// A user of the application\n///\n/// Users may belong to groups.\nentity User;"
+            ),
+            "{src}"
+        );
+        let (round_tripped, _) =
+            json_schema::Fragment::from_cedarschema_str(&src, Extensions::all_available()).unwrap();
+        let round_tripped = round_tripped
+            .0
+            .get(&Some("Photos".parse().unwrap()))
+            .unwrap();
+        assert_eq!(
+            round_tripped
+                .entity_types
+                .get(&"User".parse().unwrap())
+                .unwrap()
+                .annotations,
+            user.annotations
+        );
+        assert_eq!(
+            round_tripped.actions.get("view").unwrap().annotations,
+            view.annotations
+        );
+    }
+
+    #[test]
+    fn annotations() {
+        let (schema, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+            /// A user
+            @deprecated("5.0")
+            entity User { @deprecated email: String, @doc("The name") name: String };
+            @deprecated("6.0") action view appliesTo { principal: User, resource: User };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let ns = schema.0.get(&None).unwrap();
+        let user = ns.entity_types.get(&"User".parse().unwrap()).unwrap();
+        let view = ns.actions.get("view").unwrap();
+        assert_eq!(
+            user.annotations,
+            BTreeMap::from([
+                ("doc".into(), "A user".into()),
+                ("deprecated".into(), "5.0".into())
+            ])
+        );
+        assert_eq!(
+            view.annotations,
+            BTreeMap::from([("deprecated".into(), "6.0".into())])
+        );
+        assert_matches!(&user.shape.0, json_schema::Type::Type(json_schema::TypeVariant::Record(rty)) => {
+            assert_eq!(
+                rty.attributes.get("email").unwrap().annotations,
+                BTreeMap::from([("deprecated".into(), "".into())])
+            );
+            assert_eq!(
+                rty.attributes.get("name").unwrap().annotations,
+                BTreeMap::from([("doc".into(), "The name".into())])
+            );
+        });
+
+        let printed = schema.to_cedarschema().unwrap();
+        let (reparsed, _) =
+            json_schema::Fragment::from_cedarschema_str(&printed, Extensions::all_available())
+                .unwrap();
+        let reparsed = reparsed.0.get(&None).unwrap();
+        assert_eq!(
+            reparsed.entity_types.get(&"User".parse().unwrap()).unwrap(),
+            user
+        );
+        assert_eq!(reparsed.actions.get("view").unwrap(), view);
+
+        let schema = ValidatorSchema::try_from(schema).unwrap();
+        let user = schema.get_entity_type(&"User".parse().unwrap()).unwrap();
+        assert_eq!(
+            user.attribute_deprecation("email")
+                .map(|d| d.removal_version()),
+            Some(None)
+        );
+        assert!(user.attribute_deprecation("name").is_none());
+        let view = schema
+            .get_action_id(&r#"Action::"view""#.parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            view.deprecation().and_then(|d| d.removal_version()),
+            Some("6.0")
+        );
+    }
+
+    #[test]
+    fn duplicate_annotations() {
+        assert_matches!(collect_warnings(json_schema::Fragment::from_cedarschema_str(
+            r#"entity User { @deprecated @deprecated("5.0") email: String };"#,
+            Extensions::all_available(),
+        )), Err(err) => {
+            assert_matches!(err, crate::CedarSchemaError::Parsing(err) => {
+                assert_matches!(err.errors(), crate::cedar_schema::parser::CedarSchemaParseErrors::SyntaxError(errs) => {
+                    assert!(errs.to_string().contains("duplicate annotation `@deprecated`"));
+                });
+            });
+        });
+    }
+
     /// Test that duplicate namespaces are not allowed
     #[test]
     fn duplicate_namespace() {
@@ -1421,13 +1594,13 @@ mod translator_tests {
             attributes,
             additional_attributes: false,
         }))) => {
-            assert_matches!(attributes.get("name"), Some(json_schema::TypeOfAttribute { ty, required: true }) => {
+            assert_matches!(attributes.get("name"), Some(json_schema::TypeOfAttribute { ty, required: true, .. }) => {
                 let expected = json_schema::Type::Type(json_schema::TypeVariant::EntityOrCommon {
                     type_name: "id".parse().unwrap(),
                 });
                 assert_eq!(ty, &expected);
             });
-            assert_matches!(attributes.get("email"), Some(json_schema::TypeOfAttribute { ty, required: true }) => {
+            assert_matches!(attributes.get("email"), Some(json_schema::TypeOfAttribute { ty, required: true, .. }) => {
                 let expected = json_schema::Type::Type(json_schema::TypeVariant::EntityOrCommon {
                     type_name: "email_address".parse().unwrap(),
                 });
@@ -1524,6 +1697,309 @@ mod translator_tests {
         assert_eq!(foo.member_of_types, vec!["namespace".parse().unwrap()]);
     }
 
+    #[test]
+    fn entity_parent_attributes() {
+        let src = r#"
+        entity Account, Album;
+        entity Photo in [Account, Album] via [account, albums] {
+            account: Account,
+            albums: Set<Album>,
+        };
+        entity Video in [Account] via account { account: Account };
+        "#;
+
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let ns = schema.0.get(&None).unwrap();
+        let photo = ns.entity_types.get(&"Photo".parse().unwrap()).unwrap();
+        assert_eq!(photo.parent_attributes, vec!["account", "albums"]);
+        let video = ns.entity_types.get(&"Video".parse().unwrap()).unwrap();
+        assert_eq!(video.parent_attributes, vec!["account"]);
+
+        let printed = schema.to_cedarschema().unwrap();
+        let (reparsed, _) =
+            json_schema::Fragment::from_cedarschema_str(&printed, Extensions::all_available())
+                .unwrap();
+        let ns = reparsed.0.get(&None).unwrap();
+        let photo = ns.entity_types.get(&"Photo".parse().unwrap()).unwrap();
+        assert_eq!(photo.parent_attributes, vec!["account", "albums"]);
+        ValidatorSchema::try_from(schema).unwrap();
+    }
+
+    #[test]
+    fn entity_parent_attributes_invalid() {
+        let src = r#"
+        entity Account, Album;
+        entity Photo in [Account] via [owner, album] {
+            owner: String,
+            album: Album,
+        };
+        "#;
+
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::InvalidParentAttribute(_))
+        );
+    }
+
+    #[test]
+    fn entity_computed_attributes() {
+        let src = r#"
+        entity User {
+            age: Long,
+            isAdult: Bool,
+        } computed { isAdult: "context.age >= 18" };
+        "#;
+
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let ns = schema.0.get(&None).unwrap();
+        let user = ns.entity_types.get(&"User".parse().unwrap()).unwrap();
+        assert_eq!(
+            user.computed_attributes
+                .get("isAdult")
+                .map(|expr| expr.as_str()),
+            Some("context.age >= 18")
+        );
+
+        let printed = schema.to_cedarschema().unwrap();
+        let (reparsed, _) =
+            json_schema::Fragment::from_cedarschema_str(&printed, Extensions::all_available())
+                .unwrap();
+        let ns = reparsed.0.get(&None).unwrap();
+        assert_eq!(ns.entity_types.get(&"User".parse().unwrap()).unwrap(), user);
+        ValidatorSchema::try_from(schema).unwrap();
+    }
+
+    #[test]
+    fn entity_computed_attributes_invalid() {
+        // not declared in the shape
+        let src = r#"
+        entity User { age: Long } computed { isAdult: "context.age >= 18" };
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::InvalidComputedAttribute(_))
+        );
+
+        // invalid expression
+        let src = r#"
+        entity User { age: Long, isAdult: Bool } computed { isAdult: "context.age >=" };
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::InvalidComputedAttribute(_))
+        );
+    }
+
+    #[test]
+    fn entity_extends() {
+        let src = r#"
+        entity User, Account, Folder;
+        entity Resource in [Account] via account {
+            account: Account,
+            owner: User,
+        };
+        entity Photo extends Resource in [Folder] { size: Long };
+        entity Thumbnail extends Photo;
+        action view appliesTo { principal: User, resource: Resource };
+        "#;
+
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let ns = schema.0.get(&None).unwrap();
+        let photo = ns.entity_types.get(&"Photo".parse().unwrap()).unwrap();
+        assert_eq!(photo.extends, Some("Resource".parse().unwrap()));
+
+        let printed = schema.to_cedarschema().unwrap();
+        let (reparsed, _) =
+            json_schema::Fragment::from_cedarschema_str(&printed, Extensions::all_available())
+                .unwrap();
+        let ns = reparsed.0.get(&None).unwrap();
+        assert_eq!(
+            ns.entity_types.get(&"Photo".parse().unwrap()).unwrap(),
+            photo
+        );
+
+        let schema = ValidatorSchema::try_from(schema).unwrap();
+        let thumbnail = schema
+            .get_entity_type(&"Thumbnail".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            thumbnail
+                .supertypes()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["Photo", "Resource"]
+        );
+        assert_eq!(
+            thumbnail
+                .attributes()
+                .map(|(attr, _)| attr.as_str())
+                .collect::<Vec<_>>(),
+            vec!["account", "owner", "size"]
+        );
+        assert_eq!(
+            thumbnail.parent_attributes().collect::<Vec<_>>(),
+            vec!["account"]
+        );
+        // inherited parent types
+        for parent in ["Account", "Folder"] {
+            assert!(schema
+                .get_entity_type(&parent.parse().unwrap())
+                .unwrap()
+                .has_descendant_entity_type(&"Thumbnail".parse().unwrap()));
+        }
+        // actions apply to the entity types extending the ones they apply to
+        let view = schema
+            .get_action_id(&r#"Action::"view""#.parse().unwrap())
+            .unwrap();
+        let mut resources: Vec<_> = view.resources().map(ToString::to_string).collect();
+        resources.sort();
+        assert_eq!(resources, vec!["Photo", "Resource", "Thumbnail"]);
+    }
+
+    #[test]
+    fn entity_extends_invalid() {
+        let src = r#"
+        entity A extends B;
+        entity B extends C;
+        entity C extends A;
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::CycleInEntityTypeInheritance(_))
+        );
+
+        let src = r#"
+        entity Resource { name: String };
+        entity Photo extends Resource { name: String };
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::InheritedAttributeRedeclared(_))
+        );
+
+        let src = "entity Photo extends Resource;";
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::TypeNotDefined(_))
+        );
+    }
+
+    #[test]
+    fn union_types() {
+        let src = r#"
+        type Code = String | Long;
+        entity User { code: Code, flag?: Bool | String | Bool };
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let ns = schema.0.get(&None).unwrap();
+        let user = ns.entity_types.get(&"User".parse().unwrap()).unwrap();
+
+        let printed = schema.to_cedarschema().unwrap();
+        let (reparsed, _) =
+            json_schema::Fragment::from_cedarschema_str(&printed, Extensions::all_available())
+                .unwrap();
+        let ns = reparsed.0.get(&None).unwrap();
+        assert_eq!(ns.entity_types.get(&"User".parse().unwrap()).unwrap(), user);
+
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            json[""]["entityTypes"]["User"]["shape"]["attributes"]["flag"]["type"],
+            "Union"
+        );
+        let reparsed: json_schema::Fragment<crate::RawName> = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            reparsed
+                .0
+                .get(&None)
+                .unwrap()
+                .entity_types
+                .get(&"User".parse().unwrap())
+                .unwrap(),
+            user
+        );
+
+        let schema = ValidatorSchema::try_from(schema).unwrap();
+        let user = schema.get_entity_type(&"User".parse().unwrap()).unwrap();
+        assert_eq!(
+            user.attr("code").unwrap().attr_type,
+            Type::union([Type::primitive_string(), Type::primitive_long()]).unwrap()
+        );
+        assert_eq!(
+            user.attr("flag").unwrap().attr_type,
+            Type::union([Type::primitive_boolean(), Type::primitive_string()]).unwrap()
+        );
+    }
+
+    #[test]
+    fn nullable_types() {
+        let src = r#"
+        entity User { age: Long | Null, manager: User | Null, nothing: Null };
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let ns = schema.0.get(&None).unwrap();
+        let user = ns.entity_types.get(&"User".parse().unwrap()).unwrap();
+
+        let printed = schema.to_cedarschema().unwrap();
+        let (reparsed, _) =
+            json_schema::Fragment::from_cedarschema_str(&printed, Extensions::all_available())
+                .unwrap();
+        let ns = reparsed.0.get(&None).unwrap();
+        assert_eq!(ns.entity_types.get(&"User".parse().unwrap()).unwrap(), user);
+
+        let schema = ValidatorSchema::try_from(schema).unwrap();
+        let user = schema.get_entity_type(&"User".parse().unwrap()).unwrap();
+        assert_eq!(
+            user.attr("age").unwrap().attr_type,
+            Type::nullable(Type::primitive_long())
+        );
+        assert_eq!(
+            user.attr("manager").unwrap().attr_type,
+            Type::nullable(Type::named_entity_reference("User".parse().unwrap()))
+        );
+        assert_eq!(user.attr("nothing").unwrap().attr_type, Type::null());
+    }
+
+    #[test]
+    fn union_types_invalid() {
+        let src = "entity User { code: Set<String> | Long };";
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        assert_matches!(
+            ValidatorSchema::try_from(schema),
+            Err(crate::SchemaError::InvalidUnionType(_))
+        );
+
+        let json = serde_json::json!({ "": {
+            "entityTypes": {
+                "User": { "shape": { "type": "Record", "attributes": {
+                    "code": { "type": "Union" }
+                }}}
+            },
+            "actions": {}
+        }});
+        assert_matches!(
+            serde_json::from_value::<json_schema::Fragment<crate::RawName>>(json),
+            Err(_)
+        );
+    }
+
     #[test]
     fn entity_named_in() {
         // This fails because `in` is reserved
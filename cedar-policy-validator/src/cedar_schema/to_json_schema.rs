@@ -16,7 +16,7 @@
 
 //! Convert a schema into the JSON format
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use cedar_policy_core::{
     ast::{Id, Name, UnreservedId},
@@ -30,8 +30,8 @@ use std::collections::hash_map::Entry;
 
 use super::{
     ast::{
-        ActionDecl, AppDecl, AttrDecl, Decl, Declaration, EntityDecl, Namespace, PRAppDecl, Path,
-        QualName, Schema, Type, TypeDecl, BUILTIN_TYPES, PR,
+        ActionDecl, Annotation, AppDecl, AttrDecl, Decl, Declaration, EntityDecl, Namespace,
+        PRAppDecl, Path, QualName, Schema, Type, TypeDecl, BUILTIN_TYPES, PR,
     },
     err::{schema_warnings, SchemaWarning, ToJsonSchemaError, ToJsonSchemaErrors},
 };
@@ -77,12 +77,11 @@ pub fn cedar_schema_to_json_schema(
         .collect::<Vec<_>>();
 
     let names = build_namespace_bindings(all_namespaces.iter())?;
-    let warnings = compute_namespace_warnings(&names, extensions);
+    let warnings = compute_namespace_warnings(&names, extensions)
+        .chain(deprecation_warnings(&all_namespaces))
+        .collect::<Vec<_>>();
     let fragment = collect_all_errors(all_namespaces.into_iter().map(convert_namespace))?.collect();
-    Ok((
-        json_schema::Fragment(fragment),
-        warnings.collect::<Vec<_>>().into_iter(),
-    ))
+    Ok((json_schema::Fragment(fragment), warnings.into_iter()))
 }
 
 /// Is the given [`Id`] the name of a valid extension type, given the currently active [`Extensions`]
@@ -111,6 +110,9 @@ pub fn cedar_type_to_json_type(ty: Node<Type>) -> json_schema::Type<RawName> {
                 additional_attributes: false,
             }))
         }
+        Type::Union(arms) => json_schema::Type::Type(json_schema::TypeVariant::Union {
+            arms: arms.into_iter().map(cedar_type_to_json_type).collect(),
+        }),
     }
 }
 
@@ -165,14 +167,22 @@ impl TryFrom<Namespace> for json_schema::NamespaceDefinition<RawName> {
         let (entity_types, action, common_types) = into_partition_decls(n.decls);
 
         // Convert entity type decls, collecting all errors
-        let entity_types = collect_all_errors(entity_types.into_iter().map(convert_entity_decl))?
-            .flatten()
-            .collect();
+        let entity_types = collect_all_errors(
+            entity_types
+                .into_iter()
+                .map(|(decl, annotations)| convert_entity_decl(decl, annotations)),
+        )?
+        .flatten()
+        .collect();
 
         // Convert action decls, collecting all errors
-        let actions = collect_all_errors(action.into_iter().map(convert_action_decl))?
-            .flatten()
-            .collect();
+        let actions = collect_all_errors(
+            action
+                .into_iter()
+                .map(|(decl, annotations)| convert_action_decl(decl, annotations)),
+        )?
+        .flatten()
+        .collect();
 
         // Convert common type decls
         let common_types = common_types
@@ -201,11 +211,14 @@ impl TryFrom<Namespace> for json_schema::NamespaceDefinition<RawName> {
 /// Converts action type decls
 fn convert_action_decl(
     a: ActionDecl,
+    annotations: BTreeMap<SmolStr, SmolStr>,
 ) -> Result<impl Iterator<Item = (SmolStr, json_schema::ActionType<RawName>)>, ToJsonSchemaErrors> {
     let ActionDecl {
         names,
         parents,
         app_decls,
+        attributes: _,
+        annotations: _,
     } = a;
     // Create the internal type from the 'applies_to' clause and 'member_of'
     let applies_to = app_decls
@@ -221,6 +234,7 @@ fn convert_action_decl(
         attributes: None, // Action attributes are currently unsupported in the Cedar schema format
         applies_to: Some(applies_to),
         member_of,
+        annotations,
     };
     // Then map that type across all of the bound names
     Ok(names.into_iter().map(move |name| (name.node, ty.clone())))
@@ -340,14 +354,27 @@ fn convert_id(node: Node<Id>) -> Result<UnreservedId, ToJsonSchemaError> {
 /// Convert Entity declarations
 fn convert_entity_decl(
     e: EntityDecl,
+    annotations: BTreeMap<SmolStr, SmolStr>,
 ) -> Result<
     impl Iterator<Item = (UnreservedId, json_schema::EntityType<RawName>)>,
     ToJsonSchemaErrors,
 > {
     // First build up the defined entity type
     let etype = json_schema::EntityType {
+        extends: e.extends.map(RawName::from),
         member_of_types: e.member_of_types.into_iter().map(RawName::from).collect(),
+        parent_attributes: e
+            .parent_attributes
+            .into_iter()
+            .map(|attr| attr.node.into_smolstr())
+            .collect(),
         shape: convert_attr_decls(e.attrs),
+        computed_attributes: e
+            .computed_attributes
+            .into_iter()
+            .map(|(attr, expr)| (attr.node, expr.node))
+            .collect(),
+        annotations,
     };
 
     // Then map over all of the bound names
@@ -401,10 +428,26 @@ fn convert_attr_decl(attr: AttrDecl) -> (SmolStr, json_schema::TypeOfAttribute<R
         json_schema::TypeOfAttribute {
             ty: cedar_type_to_json_type(attr.ty),
             required: attr.required,
+            annotations: convert_annotations(attr.annotations, BTreeMap::new()),
         },
     )
 }
 
+/// Add annotations to `annotations`, which may already contain the `doc`
+/// annotation from a doc comment. An explicit `@doc` annotation replaces it.
+fn convert_annotations(
+    anns: Vec<Annotation>,
+    mut annotations: BTreeMap<SmolStr, SmolStr>,
+) -> BTreeMap<SmolStr, SmolStr> {
+    annotations.extend(anns.into_iter().map(|ann| {
+        (
+            ann.key.node.into_smolstr(),
+            ann.value.map(|value| value.node).unwrap_or_default(),
+        )
+    }));
+    annotations
+}
+
 /// Takes a collection of results returning multiple errors
 /// Behaves similarly to `::collect()` over results, except instead of failing
 /// on the first error, keeps going to ensure all of the errors are accumulated
@@ -515,6 +558,25 @@ fn compute_namespace_warnings<'a>(
         .flat_map(move |nr| make_warning_for_shadowing(nr, extensions))
 }
 
+/// Warnings for the deprecated constructs used by the declarations of
+/// `namespaces`
+fn deprecation_warnings(namespaces: &[Namespace]) -> impl Iterator<Item = SchemaWarning> + '_ {
+    namespaces
+        .iter()
+        .flat_map(|namespace| namespace.decls.iter())
+        .filter_map(|decl| match &decl.node {
+            Declaration::Action(action) => action.attributes.clone(),
+            _ => None,
+        })
+        .map(|loc| {
+            schema_warnings::DeprecatedConstructWarning {
+                construct: schema_warnings::DeprecatedConstruct::EmptyActionAttributes,
+                loc,
+            }
+            .into()
+        })
+}
+
 fn make_warning_for_shadowing<'a>(
     n: &'a NamespaceRecord,
     extensions: &'a Extensions<'a>,
@@ -613,20 +675,58 @@ fn partition_decls(
     (entities, actions, types)
 }
 
+/// Declarations paired with their annotations
+type Annotated<T> = Vec<(T, BTreeMap<SmolStr, SmolStr>)>;
+
+/// Partition declarations into entity, action and common type declarations,
+/// keeping the annotations of entity and action declarations
 fn into_partition_decls(
     decls: Vec<Node<Declaration>>,
-) -> (Vec<EntityDecl>, Vec<ActionDecl>, Vec<TypeDecl>) {
+) -> (Annotated<EntityDecl>, Annotated<ActionDecl>, Vec<TypeDecl>) {
     let mut entities = vec![];
     let mut actions = vec![];
     let mut types = vec![];
 
     for decl in decls.into_iter() {
+        let annotations = doc_comment(&decl.loc)
+            .map(|doc| BTreeMap::from([("doc".into(), doc)]))
+            .unwrap_or_default();
         match decl.node {
-            Declaration::Entity(e) => entities.push(e),
-            Declaration::Action(a) => actions.push(a),
+            Declaration::Entity(mut e) => {
+                let annotations =
+                    convert_annotations(std::mem::take(&mut e.annotations), annotations);
+                entities.push((e, annotations))
+            }
+            Declaration::Action(mut a) => {
+                let annotations =
+                    convert_annotations(std::mem::take(&mut a.annotations), annotations);
+                actions.push((a, annotations))
+            }
             Declaration::Type(t) => types.push(t),
         }
     }
 
     (entities, actions, types)
 }
+
+/// The doc comment of the declaration at `loc`, i.e., the `///` comments on
+/// the lines immediately before the declaration, without the `///` and the
+/// following space. Returns `None` if there are no such comments, or if the
+/// declaration doesn't start its line.
+fn doc_comment(loc: &Loc) -> Option<SmolStr> {
+    let (before, indent) = loc.src.get(..loc.start())?.rsplit_once('\n')?;
+    if !indent.trim().is_empty() {
+        return None;
+    }
+    let mut lines = before
+        .lines()
+        .rev()
+        .map_while(|line| line.trim().strip_prefix("///"))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return None;
+    }
+    lines.reverse();
+    Some(lines.join("\n").into())
+}
@@ -149,6 +149,18 @@ impl entities::EntityTypeDescription for EntityTypeDescription {
         Arc::clone(&self.allowed_parent_types)
     }
 
+    fn parent_attrs<'s>(&'s self) -> Box<dyn Iterator<Item = SmolStr> + 's> {
+        Box::new(self.validator_type.parent_attributes().cloned())
+    }
+
+    fn computed_attrs<'s>(&'s self) -> Box<dyn Iterator<Item = (SmolStr, &'s ast::Expr)> + 's> {
+        Box::new(
+            self.validator_type
+                .computed_attributes()
+                .map(|(attr, expr)| (attr.clone(), expr)),
+        )
+    }
+
     fn open_attributes(&self) -> bool {
         self.validator_type.open_attributes.is_open()
     }
@@ -214,11 +226,16 @@ impl ast::RequestSchema for ValidatorSchema {
                     }
                 }
                 if let Some(context) = request.context() {
-                    let expected_context_ty = validator_action_id.context_type();
-                    if !expected_context_ty
-                        .typecheck_partial_value(&context.clone().into(), extensions)
-                        .map_err(RequestValidationError::TypeOfContext)?
-                    {
+                    let valid = match (context, self.context_plan(validator_action_id)) {
+                        (ast::Context::Value(attrs), Some(plan)) => plan.check(attrs),
+                        // contexts with unknowns are typechecked as
+                        // restricted expressions
+                        _ => validator_action_id
+                            .context_type()
+                            .typecheck_partial_value(&context.clone().into(), extensions)
+                            .map_err(RequestValidationError::TypeOfContext)?,
+                    };
+                    if !valid {
                         return Err(request_validation_errors::InvalidContextError {
                             context: context.clone(),
                             action: Arc::clone(action),
@@ -20,12 +20,13 @@
 use miette::Diagnostic;
 use thiserror::Error;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use cedar_policy_core::ast::{EntityType, PolicyID};
+use cedar_policy_core::ast::{EntityType, EntityUID, PolicyID, SlotId};
 use cedar_policy_core::parser::Loc;
+use smol_str::SmolStr;
 
-use crate::types::Type;
+use crate::{types::Type, Deprecation};
 
 pub mod validation_errors;
 pub mod validation_warnings;
@@ -38,6 +39,8 @@ pub mod validation_warnings;
 pub struct ValidationResult {
     validation_errors: Vec<ValidationError>,
     validation_warnings: Vec<ValidationWarning>,
+    truncated: bool,
+    policies_by_action: BTreeMap<EntityUID, Vec<PolicyID>>,
 }
 
 impl ValidationResult {
@@ -50,9 +53,48 @@ impl ValidationResult {
         Self {
             validation_errors: errors.into_iter().collect(),
             validation_warnings: warnings.into_iter().collect(),
+            truncated: false,
+            policies_by_action: BTreeMap::new(),
         }
     }
 
+    /// Record the policies and templates which may apply to each action of
+    /// the schema
+    pub(crate) fn with_policies_by_action(
+        mut self,
+        policies_by_action: BTreeMap<EntityUID, Vec<PolicyID>>,
+    ) -> Self {
+        self.policies_by_action = policies_by_action;
+        self
+    }
+
+    /// Create a `ValidationResult` for a validation which stopped early, after
+    /// finding the maximum number of errors. Some policies may not have been
+    /// validated.
+    pub(crate) fn new_truncated(
+        errors: impl IntoIterator<Item = ValidationError>,
+        warnings: impl IntoIterator<Item = ValidationWarning>,
+    ) -> Self {
+        Self {
+            truncated: true,
+            ..Self::new(errors, warnings)
+        }
+    }
+
+    /// True when validation stopped early after finding the maximum number of
+    /// errors, so there may be more errors than the ones reported.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The ids of the static policies and templates which may apply to each
+    /// action of the schema, i.e., whose scope is satisfied by some request
+    /// for the action which is valid for the schema. Every action of the
+    /// schema is a key, even if no policy applies to it. The ids are sorted.
+    pub fn policies_by_action(&self) -> &BTreeMap<EntityUID, Vec<PolicyID>> {
+        &self.policies_by_action
+    }
+
     /// True when validation passes. There are no errors, but there may be
     /// non-fatal warnings.
     pub fn validation_passed(&self) -> bool {
@@ -110,10 +152,20 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     UnexpectedType(#[from] validation_errors::UnexpectedType),
+    /// The typechecker expected to see a subtype of one of the types in
+    /// `expected`, but saw a union type where only some arms are expected.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidUnionArms(#[from] validation_errors::InvalidUnionArms),
     /// The typechecker could not compute a least upper bound for `types`.
     #[error(transparent)]
     #[diagnostic(transparent)]
     IncompatibleTypes(#[from] validation_errors::IncompatibleTypes),
+    /// The typechecker did not compute a least upper bound for types which
+    /// exceed the maximum type size configured for the validator.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TypeTooLarge(#[from] validation_errors::TypeTooLarge),
     /// The typechecker detected an access to a record or entity attribute
     /// that it could not statically guarantee would be present.
     #[error(transparent)]
@@ -152,6 +204,24 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     HierarchyNotRespected(#[from] validation_errors::HierarchyNotRespected),
+    /// The policy uses a language feature which is forbidden by the feature
+    /// policy of the validator
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ForbiddenFeature(#[from] validation_errors::ForbiddenFeature),
+    /// A link provides no value for a slot of its template
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingSlotBinding(#[from] validation_errors::MissingSlotBinding),
+    /// A link provides a value for a slot its template doesn't use
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnusedSlotBinding(#[from] validation_errors::UnusedSlotBinding),
+    /// A link provides a value for a slot of an entity type for which its
+    /// template never applies
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnexpectedSlotType(#[from] validation_errors::UnexpectedSlotType),
 }
 
 impl ValidationError {
@@ -166,6 +236,7 @@ impl ValidationError {
             policy_id,
             actual_entity_type,
             suggested_entity_type,
+            related: Vec::new(),
         }
         .into()
     }
@@ -197,10 +268,27 @@ impl ValidationError {
             policy_id,
             would_in_fix_principal,
             would_in_fix_resource,
+            related: Vec::new(),
         }
         .into()
     }
 
+    /// Attach the given schema declarations to this error, to be reported as
+    /// related diagnostics. Only errors about schema-dependent problems
+    /// (unrecognized entity types and invalid action applications) have
+    /// related declarations; for other errors this does nothing.
+    pub(crate) fn with_related(
+        mut self,
+        declarations: impl IntoIterator<Item = validation_errors::SchemaDeclaration>,
+    ) -> Self {
+        match &mut self {
+            Self::UnrecognizedEntityType(e) => e.related.extend(declarations),
+            Self::InvalidActionApplication(e) => e.related.extend(declarations),
+            _ => {}
+        }
+        self
+    }
+
     /// Construct a type error for when an unexpected type occurs in an expression.
     pub(crate) fn expected_one_of_types(
         source_loc: Option<Loc>,
@@ -219,6 +307,25 @@ impl ValidationError {
         .into()
     }
 
+    /// Construct a type error for when an expression has a union type where
+    /// only some arms are expected.
+    pub(crate) fn invalid_union_arms(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        expected: impl IntoIterator<Item = Type>,
+        actual: Type,
+        invalid_arms: impl IntoIterator<Item = Type>,
+    ) -> Self {
+        validation_errors::InvalidUnionArms {
+            source_loc,
+            policy_id,
+            expected: expected.into_iter().collect::<BTreeSet<_>>(),
+            actual,
+            invalid_arms: invalid_arms.into_iter().collect::<BTreeSet<_>>(),
+        }
+        .into()
+    }
+
     /// Construct a type error for when a least upper bound cannot be found for
     /// a collection of types.
     pub(crate) fn incompatible_types(
@@ -238,6 +345,23 @@ impl ValidationError {
         .into()
     }
 
+    /// Construct a type error for when types exceed the maximum type size, so
+    /// that their least upper bound is not computed.
+    pub(crate) fn type_too_large(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        limit: validation_errors::TypeSizeLimit,
+        context: validation_errors::LubContext,
+    ) -> Self {
+        validation_errors::TypeTooLarge {
+            source_loc,
+            policy_id,
+            limit,
+            context,
+        }
+        .into()
+    }
+
     pub(crate) fn unsafe_attribute_access(
         source_loc: Option<Loc>,
         policy_id: PolicyID,
@@ -341,6 +465,64 @@ impl ValidationError {
         }
         .into()
     }
+
+    pub(crate) fn forbidden_feature(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        feature: validation_errors::Feature,
+    ) -> Self {
+        validation_errors::ForbiddenFeature {
+            source_loc,
+            policy_id,
+            feature,
+        }
+        .into()
+    }
+
+    pub(crate) fn missing_slot_binding(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        slot: SlotId,
+        expected_types: Vec<EntityType>,
+    ) -> Self {
+        validation_errors::MissingSlotBinding {
+            source_loc,
+            policy_id,
+            slot,
+            expected_types,
+        }
+        .into()
+    }
+
+    pub(crate) fn unused_slot_binding(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        slot: SlotId,
+    ) -> Self {
+        validation_errors::UnusedSlotBinding {
+            source_loc,
+            policy_id,
+            slot,
+        }
+        .into()
+    }
+
+    pub(crate) fn unexpected_slot_type(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        slot: SlotId,
+        actual: EntityType,
+        expected_types: Vec<EntityType>,
+    ) -> Self {
+        validation_errors::UnexpectedSlotType {
+            source_loc,
+            policy_id,
+            slot,
+            actual,
+            expected_types,
+        }
+        .into()
+    }
 }
 
 /// Represents the different kinds of validation warnings and information
@@ -371,6 +553,64 @@ pub enum ValidationWarning {
     #[diagnostic(transparent)]
     #[error(transparent)]
     ImpossiblePolicy(#[from] validation_warnings::ImpossiblePolicy),
+    /// In permissive validation, the scope constraints of a policy match no
+    /// request environment of the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    NotApplicablePolicy(#[from] validation_warnings::NotApplicablePolicy),
+    /// An `==` comparison between entities is always false, because the
+    /// entities' types can never be equal according to the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    IncompatibleEntityEquality(#[from] validation_warnings::IncompatibleEntityEquality),
+    /// A string attribute is compared to a string which looks like an entity
+    /// uid, suggesting that the attribute should have an entity type.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    StringTypedEntityReference(#[from] validation_warnings::StringTypedEntityReference),
+    /// A `like` pattern has no wildcards, so it is equivalent to `==`.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    LikeWithoutWildcard(#[from] validation_warnings::LikeWithoutWildcard),
+    /// A policy reads the current time from the context without checking
+    /// that it is present.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnguardedCurrentTime(#[from] validation_warnings::UnguardedCurrentTime),
+    /// A policy contains arithmetic on constants which always overflows.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    GuaranteedOverflow(#[from] validation_warnings::GuaranteedOverflow),
+    /// A policy accesses an entity attribute which is deprecated in the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    DeprecatedAttribute(#[from] validation_warnings::DeprecatedAttribute),
+    /// A policy references an action which is deprecated in the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    DeprecatedAction(#[from] validation_warnings::DeprecatedAction),
+    /// Evaluating an expression in a policy with an entity of an entity
+    /// snapshot is an error.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    EntityDataError(#[from] validation_warnings::EntityDataError),
+    /// An entity type is declared in the schema but not referenced by any policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnusedEntityType(#[from] validation_warnings::UnusedEntityType),
+    /// An entity attribute is declared in the schema but not referenced by any policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnusedAttribute(#[from] validation_warnings::UnusedAttribute),
+    /// An action is declared in the schema but not referenced by any policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnusedAction(#[from] validation_warnings::UnusedAction),
+    /// A forbid policy has the same scope and condition as a permit policy,
+    /// so the permit policy never allows any request.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    IdenticalPermitAndForbid(#[from] validation_warnings::IdenticalPermitAndForbid),
 }
 
 impl ValidationWarning {
@@ -446,4 +686,178 @@ impl ValidationWarning {
         }
         .into()
     }
+
+    pub(crate) fn not_applicable_policy(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        suggested_action: Option<EntityUID>,
+    ) -> Self {
+        validation_warnings::NotApplicablePolicy {
+            source_loc,
+            policy_id,
+            suggested_action,
+        }
+        .into()
+    }
+
+    pub(crate) fn incompatible_entity_equality(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        lhs_types: BTreeSet<EntityType>,
+        rhs_types: BTreeSet<EntityType>,
+    ) -> Self {
+        validation_warnings::IncompatibleEntityEquality {
+            source_loc,
+            policy_id,
+            lhs_types,
+            rhs_types,
+        }
+        .into()
+    }
+
+    pub(crate) fn string_typed_entity_reference(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        attr: SmolStr,
+        entity: EntityUID,
+    ) -> Self {
+        validation_warnings::StringTypedEntityReference {
+            source_loc,
+            policy_id,
+            attr,
+            entity,
+        }
+        .into()
+    }
+
+    pub(crate) fn like_without_wildcard(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        pattern: String,
+    ) -> Self {
+        validation_warnings::LikeWithoutWildcard {
+            source_loc,
+            policy_id,
+            pattern,
+        }
+        .into()
+    }
+
+    pub(crate) fn unguarded_current_time(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        attr: SmolStr,
+    ) -> Self {
+        validation_warnings::UnguardedCurrentTime {
+            source_loc,
+            policy_id,
+            attr,
+        }
+        .into()
+    }
+
+    pub(crate) fn guaranteed_overflow(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        expr: String,
+    ) -> Self {
+        validation_warnings::GuaranteedOverflow {
+            source_loc,
+            policy_id,
+            expr,
+        }
+        .into()
+    }
+
+    pub(crate) fn deprecated_attribute(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        entity_type: EntityType,
+        attr: SmolStr,
+        deprecation: &Deprecation,
+    ) -> Self {
+        validation_warnings::DeprecatedAttribute {
+            source_loc,
+            policy_id,
+            entity_type,
+            attr,
+            removal_version: deprecation.removal_version().map(SmolStr::from),
+        }
+        .into()
+    }
+
+    pub(crate) fn deprecated_action(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        action: EntityUID,
+        deprecation: &Deprecation,
+    ) -> Self {
+        validation_warnings::DeprecatedAction {
+            source_loc,
+            policy_id,
+            action,
+            removal_version: deprecation.removal_version().map(SmolStr::from),
+        }
+        .into()
+    }
+
+    pub(crate) fn entity_data_error(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        entity: EntityUID,
+        expr: String,
+        error: String,
+    ) -> Self {
+        validation_warnings::EntityDataError {
+            source_loc,
+            policy_id,
+            entity,
+            expr,
+            error,
+        }
+        .into()
+    }
+
+    pub(crate) fn unused_entity_type(source_loc: Option<Loc>, entity_type: EntityType) -> Self {
+        validation_warnings::UnusedEntityType {
+            source_loc,
+            entity_type,
+        }
+        .into()
+    }
+
+    pub(crate) fn unused_attribute(
+        source_loc: Option<Loc>,
+        entity_type: EntityType,
+        attr: SmolStr,
+    ) -> Self {
+        validation_warnings::UnusedAttribute {
+            source_loc,
+            entity_type,
+            attr,
+        }
+        .into()
+    }
+
+    pub(crate) fn unused_action(source_loc: Option<Loc>, action: EntityUID) -> Self {
+        validation_warnings::UnusedAction { source_loc, action }.into()
+    }
+
+    pub(crate) fn identical_permit_and_forbid(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        permit_loc: Option<Loc>,
+        permit_id: PolicyID,
+    ) -> Self {
+        validation_warnings::IdenticalPermitAndForbid {
+            source_loc,
+            policy_id,
+            related: permit_loc.map(|source_loc| validation_warnings::RelatedPolicy {
+                source_loc,
+                policy_id: permit_id.clone(),
+            }),
+            permit_id,
+        }
+        .into()
+    }
 }
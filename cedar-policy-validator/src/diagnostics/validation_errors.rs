@@ -26,7 +26,7 @@ use cedar_policy_core::parser::Loc;
 
 use std::collections::BTreeSet;
 
-use cedar_policy_core::ast::{EntityType, EntityUID, Expr, ExprKind, PolicyID, Var};
+use cedar_policy_core::ast::{EntityType, EntityUID, Expr, ExprKind, PolicyID, SlotId, Var};
 use cedar_policy_core::parser::join_with_conjunction;
 
 use crate::types::{EntityLUB, EntityRecordKind, RequestEnv, Type};
@@ -47,6 +47,9 @@ pub struct UnrecognizedEntityType {
     /// An entity type from the schema that the user might reasonably have
     /// intended to write.
     pub suggested_entity_type: Option<String>,
+    /// The declaration of the suggested entity type, if its location in the
+    /// schema is known
+    pub related: Vec<SchemaDeclaration>,
 }
 
 impl Diagnostic for UnrecognizedEntityType {
@@ -58,6 +61,50 @@ impl Diagnostic for UnrecognizedEntityType {
             None => None,
         }
     }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        related_declarations(&self.related)
+    }
+}
+
+/// A declaration in the schema which is relevant to a validation error. It is
+/// reported as a related diagnostic of the error, pointing into the schema
+/// source, so that users see both the policy and the schema declaration.
+/// Declarations are only available for schemas parsed from the Cedar schema
+/// syntax.
+#[derive(Debug, Clone, Error, Hash, Eq, PartialEq)]
+#[error("{description}")]
+pub struct SchemaDeclaration {
+    /// Location of the declaration in the schema source
+    pub source_loc: Loc,
+    /// Description of the declaration
+    pub description: String,
+}
+
+impl Diagnostic for SchemaDeclaration {
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.source_loc.src as &dyn miette::SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(
+            miette::LabeledSpan::new_with_span(Some("declared here".into()), self.source_loc.span),
+        )))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Advice)
+    }
+}
+
+fn related_declarations(
+    related: &[SchemaDeclaration],
+) -> Option<Box<dyn Iterator<Item = &dyn Diagnostic> + '_>> {
+    if related.is_empty() {
+        None
+    } else {
+        Some(Box::new(related.iter().map(|d| d as &dyn Diagnostic)))
+    }
 }
 
 /// Structure containing details about an unrecognized action id error.
@@ -98,6 +145,10 @@ pub struct InvalidActionApplication {
     pub would_in_fix_principal: bool,
     /// `true` if changing `==` to `in` wouuld fix the resource clause
     pub would_in_fix_resource: bool,
+    /// The declarations of the actions in the policy's action scope
+    /// constraint, which determine the principals and resources those
+    /// actions apply to, if their locations in the schema are known
+    pub related: Vec<SchemaDeclaration>,
 }
 
 impl Diagnostic for InvalidActionApplication {
@@ -117,6 +168,10 @@ impl Diagnostic for InvalidActionApplication {
             (false, false) => None,
         }
     }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        related_declarations(&self.related)
+    }
 }
 
 /// Structure containing details about an unexpected type error.
@@ -148,6 +203,39 @@ impl Diagnostic for UnexpectedType {
     }
 }
 
+/// Structure containing details about an expression with a union type where
+/// some, but not all, arms of the union are valid for the operation.
+#[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[error("for policy `{policy_id}`, unexpected type: expected {} but saw {}, which may be {}",
+    match .expected.iter().next() {
+        Some(single) if .expected.len() == 1 => format!("{}", single),
+        _ => .expected.iter().join(", or ")
+    },
+    .actual,
+    .invalid_arms.iter().join(", or "))]
+pub struct InvalidUnionArms {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// Type(s) which were expected
+    pub expected: BTreeSet<Type>,
+    /// Union type which was encountered
+    pub actual: Type,
+    /// Arms of the union type which are not any of the expected types
+    pub invalid_arms: BTreeSet<Type>,
+}
+
+impl Diagnostic for InvalidUnionArms {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(
+            "narrow the type by first comparing the value to a value of an expected type, e.g., `principal.attr == 0 && ...`",
+        ))
+    }
+}
+
 /// Help for resolving a type error
 #[derive(Error, Debug, Clone, Hash, Eq, PartialEq)]
 pub enum UnexpectedTypeHelp {
@@ -180,6 +268,9 @@ pub enum UnexpectedTypeHelp {
     /// Cedar doesn't support set union, intersection, or difference
     #[error("Cedar does not support computing the union, intersection, or difference of sets")]
     SetOperationsNotSupported,
+    /// Try checking that the value is not `null`
+    #[error("try checking that the value is not `null` using `!= null`")]
+    TryCheckingForNull,
 }
 
 /// Structure containing details about an incompatible type error.
@@ -256,6 +347,44 @@ pub enum LubContext {
     ContainsAnyAll,
 }
 
+/// Structure containing details about types whose least upper bound was not
+/// computed because they exceed the maximum type size configured for the
+/// validator
+#[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[error("for policy `{policy_id}`, the types of {context} exceed the maximum {limit}")]
+pub struct TypeTooLarge {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// The limit which is exceeded
+    pub limit: TypeSizeLimit,
+    /// `LubContext` for the error
+    pub context: LubContext,
+}
+
+impl Diagnostic for TypeTooLarge {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(
+            "split the expression into smaller ones, or raise the limit of the validator",
+        ))
+    }
+}
+
+/// A limit on the size of types for which the typechecker computes least
+/// upper bounds
+#[derive(Error, Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum TypeSizeLimit {
+    /// Maximum number of attributes of a record type
+    #[error("record width of {0} attributes")]
+    RecordWidth(usize),
+    /// Maximum number of set types nested in one another
+    #[error("set nesting depth of {0}")]
+    SetDepth(usize),
+}
+
 /// Structure containing details about a missing attribute error.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Error)]
 #[error("for policy `{policy_id}`, attribute {attribute_access} not found")]
@@ -421,6 +550,117 @@ impl Diagnostic for NonLitExtConstructor {
     }
 }
 
+/// Structure containing details about the use of a language feature which is
+/// forbidden by the feature policy of the validator
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[error("for policy `{policy_id}`, {feature} not allowed")]
+pub struct ForbiddenFeature {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// The forbidden feature
+    pub feature: Feature,
+}
+
+impl Diagnostic for ForbiddenFeature {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+}
+
+/// Structure containing details about a slot of a template for which a link
+/// provides no value
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[error("for policy `{policy_id}`, no value is provided for the slot `{slot}`")]
+pub struct MissingSlotBinding {
+    /// Source location of the slot
+    pub source_loc: Option<Loc>,
+    /// Policy ID of the template
+    pub policy_id: PolicyID,
+    /// The slot without a value
+    pub slot: SlotId,
+    /// The entity types for which the template may apply. Empty if it never
+    /// applies, or if they are unknown.
+    pub expected_types: Vec<EntityType>,
+}
+
+impl Diagnostic for MissingSlotBinding {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        expected_slot_types_help(&self.expected_types)
+    }
+}
+
+/// Structure containing details about a value provided by a link for a slot
+/// which its template doesn't have
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[error("for policy `{policy_id}`, a value is provided for the slot `{slot}`, which the template doesn't use")]
+pub struct UnusedSlotBinding {
+    /// Source location of the template
+    pub source_loc: Option<Loc>,
+    /// Policy ID of the template
+    pub policy_id: PolicyID,
+    /// The slot the template doesn't use
+    pub slot: SlotId,
+}
+
+impl Diagnostic for UnusedSlotBinding {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+}
+
+/// Structure containing details about a value provided by a link for a slot
+/// of an entity type for which the template never applies
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[error("for policy `{policy_id}`, the slot `{slot}` is bound to an entity of type `{actual}`, for which the template never applies")]
+pub struct UnexpectedSlotType {
+    /// Source location of the slot
+    pub source_loc: Option<Loc>,
+    /// Policy ID of the template
+    pub policy_id: PolicyID,
+    /// The slot
+    pub slot: SlotId,
+    /// The entity type of the value provided for the slot
+    pub actual: EntityType,
+    /// The entity types for which the template may apply
+    pub expected_types: Vec<EntityType>,
+}
+
+impl Diagnostic for UnexpectedSlotType {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        expected_slot_types_help(&self.expected_types)
+    }
+}
+
+fn expected_slot_types_help<'a>(expected_types: &[EntityType]) -> Option<Box<dyn Display + 'a>> {
+    use std::fmt::Write;
+    if expected_types.is_empty() {
+        return None;
+    }
+    let mut help = "expected an entity of type ".to_string();
+    join_with_conjunction(&mut help, "or", expected_types, |f, ty| write!(f, "`{ty}`")).ok()?;
+    Some(Box::new(help))
+}
+
+/// A language feature which can be forbidden with a
+/// [`crate::FeaturePolicy`]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+pub enum Feature {
+    /// An `unless` clause
+    #[error("`unless` clauses are")]
+    Unless,
+    /// A template, i.e., a policy with slots
+    #[error("templates are")]
+    Template,
+    /// The `like` operator
+    #[error("the `like` operator is")]
+    Like,
+    /// A call of the extension function or method with this name
+    #[error("the extension function `{0}` is")]
+    ExtensionFunction(SmolStr),
+}
+
 /// Contains more detailed information about an attribute access when it occurs
 /// on an entity type expression or on the `context` variable. Track a `Vec` of
 /// attributes rather than a single attribute so that on `principal.foo.bar` can
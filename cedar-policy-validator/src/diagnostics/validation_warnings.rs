@@ -27,8 +27,15 @@ macro_rules! impl_diagnostic_warning {
     };
 }
 
-use cedar_policy_core::{ast::PolicyID, impl_diagnostic_from_source_loc_opt_field, parser::Loc};
+use cedar_policy_core::{
+    ast::{EntityType, EntityUID, PolicyID},
+    impl_diagnostic_from_source_loc_opt_field,
+    parser::Loc,
+};
+use itertools::Itertools;
 use miette::Diagnostic;
+use smol_str::SmolStr;
+use std::collections::BTreeSet;
 use thiserror::Error;
 
 /// Warning for strings containing mixed scripts
@@ -129,3 +136,359 @@ impl Diagnostic for ImpossiblePolicy {
     impl_diagnostic_from_source_loc_opt_field!(source_loc);
     impl_diagnostic_warning!();
 }
+
+/// Warning for policies whose scope constraints match no request environment
+/// of the schema, e.g., because the action in the scope never applies to the
+/// resource type in the scope, reported in permissive validation instead of
+/// an `InvalidActionApplication` error
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, policy is not applicable to any request: no action in the scope applies to both the principal and the resource in the scope")]
+pub struct NotApplicablePolicy {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The action closest to the action in the scope which applies to the
+    /// principal and the resource in the scope, if any
+    pub suggested_action: Option<EntityUID>,
+}
+
+impl Diagnostic for NotApplicablePolicy {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.suggested_action.as_ref().map(|action| {
+            Box::new(format!(
+                "did you mean `{action}`? It applies to the principal and the resource in the scope"
+            )) as Box<dyn std::fmt::Display>
+        })
+    }
+}
+
+/// Warning for `==` comparisons between entities whose types can never be
+/// equal according to the schema, so that the comparison is always `false`
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, this comparison is always false: entities of type {} can never be equal to entities of type {}", .lhs_types.iter().map(|ety| format!("`{ety}`")).join(" or "), .rhs_types.iter().map(|ety| format!("`{ety}`")).join(" or "))]
+pub struct IncompatibleEntityEquality {
+    /// Source location of the comparison
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The possible entity types of the left operand
+    pub lhs_types: BTreeSet<EntityType>,
+    /// The possible entity types of the right operand
+    pub rhs_types: BTreeSet<EntityType>,
+}
+
+impl Diagnostic for IncompatibleEntityEquality {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new("use `is` to test the type of an entity, or check that the entity types in the comparison are the intended ones"))
+    }
+}
+
+/// Warning for `==` comparisons between a string attribute and a string which
+/// looks like an entity uid, suggesting that the attribute should have an
+/// entity type
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, the string attribute `{attr}` is compared to a string which looks like the entity `{entity}`")]
+pub struct StringTypedEntityReference {
+    /// Source location of the comparison
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The string attribute
+    pub attr: SmolStr,
+    /// The entity the string refers to
+    pub entity: EntityUID,
+}
+
+impl Diagnostic for StringTypedEntityReference {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(format!(
+            "consider declaring `{}` with the entity type `{}` in the schema, and comparing it to the entity `{}`",
+            self.attr,
+            self.entity.entity_type(),
+            self.entity
+        )))
+    }
+}
+
+/// Warning for `like` patterns without wildcards, which are equivalent to `==`
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, the pattern \"{pattern}\" has no wildcards")]
+pub struct LikeWithoutWildcard {
+    /// Source location of the `like` expression
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The pattern, in Cedar syntax
+    pub pattern: String,
+}
+
+impl Diagnostic for LikeWithoutWildcard {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            "the pattern only matches itself, so consider using `==` instead of `like`",
+        ))
+    }
+}
+
+/// Warning for a policy which reads the current time from the context
+/// without first checking that the request provides it
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error(
+    "for policy `{policy_id}`, `context.{attr}` is accessed without checking `context has {attr}`"
+)]
+pub struct UnguardedCurrentTime {
+    /// Source location of the attribute access
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The name of the context attribute holding the current time
+    pub attr: SmolStr,
+}
+
+impl Diagnostic for UnguardedCurrentTime {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(format!(
+            "only requests whose application provides the current time have it; guard the access with `context has {} && ...`",
+            self.attr
+        )))
+    }
+}
+
+/// Warning for arithmetic on constants which always overflows, e.g.,
+/// `9223372036854775807 + 1`
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, `{expr}` always overflows")]
+pub struct GuaranteedOverflow {
+    /// Source location of the arithmetic expression
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The arithmetic expression, after constant folding, in Cedar syntax
+    pub expr: String,
+}
+
+impl Diagnostic for GuaranteedOverflow {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            "evaluating the expression is an error, so the policy is skipped, unless the authorizer saturates on overflow",
+        ))
+    }
+}
+
+/// Warning for accesses to entity attributes which are deprecated in the
+/// schema
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error(
+    "for policy `{policy_id}`, attribute `{attr}` of entity type `{entity_type}` is deprecated"
+)]
+pub struct DeprecatedAttribute {
+    /// Source location of the attribute access
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The entity type declaring the attribute
+    pub entity_type: EntityType,
+    /// The deprecated attribute
+    pub attr: SmolStr,
+    /// The version in which the attribute will be removed, if declared
+    pub removal_version: Option<SmolStr>,
+}
+
+impl Diagnostic for DeprecatedAttribute {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.removal_version.as_ref().map(|version| {
+            Box::new(format!("it will be removed in version {version}"))
+                as Box<dyn std::fmt::Display>
+        })
+    }
+}
+
+/// Warning for references to actions which are deprecated in the schema
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, action `{action}` is deprecated")]
+pub struct DeprecatedAction {
+    /// Source location of the reference to the action
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The deprecated action
+    pub action: EntityUID,
+    /// The version in which the action will be removed, if declared
+    pub removal_version: Option<SmolStr>,
+}
+
+impl Diagnostic for DeprecatedAction {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.removal_version.as_ref().map(|version| {
+            Box::new(format!("it will be removed in version {version}"))
+                as Box<dyn std::fmt::Display>
+        })
+    }
+}
+
+/// Warning for expressions in a policy which error when evaluated with an
+/// entity of an entity snapshot, e.g., because an attribute value is not a
+/// valid argument to an extension function
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, evaluating `{expr}` for entity `{entity}` is an error: {error}")]
+pub struct EntityDataError {
+    /// Source location of the erroring expression
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The entity, which is the `principal` or `resource` of the expression
+    pub entity: EntityUID,
+    /// The erroring expression, in Cedar syntax
+    pub expr: String,
+    /// The evaluation error
+    pub error: String,
+}
+
+impl Diagnostic for EntityDataError {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            "the policy is skipped when evaluating it errors, so check the attribute values of the entity",
+        ))
+    }
+}
+
+/// Warning for entity types which are declared in the schema but not
+/// referenced by any policy
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("entity type `{entity_type}` is declared in the schema but not referenced by any policy")]
+pub struct UnusedEntityType {
+    /// Source location of the declaration in the schema, if known
+    pub source_loc: Option<Loc>,
+    /// The unused entity type
+    pub entity_type: EntityType,
+}
+
+impl Diagnostic for UnusedEntityType {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+}
+
+/// Warning for entity attributes which are declared in the schema but not
+/// referenced by any policy
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("attribute `{attr}` of entity type `{entity_type}` is declared in the schema but not referenced by any policy")]
+pub struct UnusedAttribute {
+    /// Source location of the declaration in the schema, if known
+    pub source_loc: Option<Loc>,
+    /// The entity type declaring the attribute
+    pub entity_type: EntityType,
+    /// The unused attribute
+    pub attr: SmolStr,
+}
+
+impl Diagnostic for UnusedAttribute {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+}
+
+/// Warning for actions which are declared in the schema but not referenced by
+/// any policy
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("action `{action}` is declared in the schema but not referenced by any policy")]
+pub struct UnusedAction {
+    /// Source location of the declaration in the schema, if known
+    pub source_loc: Option<Loc>,
+    /// The unused action
+    pub action: EntityUID,
+}
+
+impl Diagnostic for UnusedAction {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+}
+
+/// Warning for a forbid policy with the same scope and condition as a permit
+/// policy, up to the order of commutative operands, set elements, and
+/// actions. The forbid policy applies to every request the permit policy
+/// applies to, so the permit policy never allows any request. This is usually
+/// a copy-paste mistake.
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("for policy `{policy_id}`, this forbid policy has the same scope and condition as the permit policy `{permit_id}`, so the permit policy never allows any request")]
+pub struct IdenticalPermitAndForbid {
+    /// Source location of the forbid policy
+    pub source_loc: Option<Loc>,
+    /// Policy ID of the forbid policy
+    pub policy_id: PolicyID,
+    /// Policy ID of the permit policy
+    pub permit_id: PolicyID,
+    /// The permit policy, if its location is known
+    pub related: Option<RelatedPolicy>,
+}
+
+impl Diagnostic for IdenticalPermitAndForbid {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            "forbid policies override permit policies; check whether one of the policies was copied from the other and not updated",
+        ))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        self.related
+            .as_ref()
+            .map(|p| Box::new(std::iter::once(p as &dyn Diagnostic)) as _)
+    }
+}
+
+/// A policy which is relevant to a validation warning about another policy.
+/// It is reported as a related diagnostic of the warning, pointing at the
+/// policy source, so that users see both policies.
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[error("policy `{policy_id}` is declared here")]
+pub struct RelatedPolicy {
+    /// Location of the policy in the policy source
+    pub source_loc: Loc,
+    /// Policy ID of the policy
+    pub policy_id: PolicyID,
+}
+
+impl Diagnostic for RelatedPolicy {
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.source_loc.src as &dyn miette::SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(
+            miette::LabeledSpan::new_with_span(Some("declared here".into()), self.source_loc.span),
+        )))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Advice)
+    }
+}
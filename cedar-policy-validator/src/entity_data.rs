@@ -0,0 +1,294 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Cross-checks an entity snapshot against a policy set, finding the
+//! attribute values which make expressions in the policies error.
+
+use std::collections::{HashMap, HashSet};
+
+use cedar_policy_core::ast::{
+    BinaryOp, EntityType, EntityUIDEntry, Expr, ExprKind, PolicySet, Request, Template, UnaryOp,
+    Var,
+};
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::evaluator::{EvaluationError, Evaluator};
+use cedar_policy_core::extensions::Extensions;
+
+use crate::typecheck::{PolicyCheck, Typechecker};
+use crate::{ValidationMode, ValidationWarning, ValidatorSchema};
+
+/// Compute warnings for the expressions in the conditions of `policies` which
+/// error when evaluated with an entity of `entities` as the `principal` or
+/// `resource`.
+///
+/// The checked expressions are the extension function calls and arithmetic
+/// operations whose only variable is `principal` or `resource`. Each is
+/// evaluated in isolation for every entity in `entities` of a principal
+/// (resp. resource) type in some request environment the policy applies to.
+/// Since the rest of the condition is not evaluated, an expression may be
+/// reported even if the policy guards it, e.g., with an `if`. Only the errors
+/// caused by attribute values are reported, i.e., failures of extension
+/// functions, such as parsing a `decimal` from a string, and integer overflow.
+/// Other errors, such as accessing a missing attribute, are either excluded
+/// by validation or guarded by a `has` check. An expression is not reported
+/// if one of its checked subexpressions already errors.
+pub(crate) fn entity_data_errors(
+    schema: &ValidatorSchema,
+    policies: &PolicySet,
+    entities: &Entities,
+) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    for t in policies.all_templates() {
+        let checked = checked_expressions(t);
+        if checked.is_empty() {
+            continue;
+        }
+        let types = applicable_entity_types(schema, t);
+        for entity in entities.iter() {
+            let uid = entity.uid();
+            for var in [Var::Principal, Var::Resource] {
+                if !types
+                    .get(&var)
+                    .is_some_and(|types| types.contains(uid.entity_type()))
+                {
+                    continue;
+                }
+                let (principal, resource) = match var {
+                    Var::Principal => (EntityUIDEntry::known(uid.clone(), None), unknown()),
+                    _ => (unknown(), EntityUIDEntry::known(uid.clone(), None)),
+                };
+                let request = Request::new_unchecked(principal, unknown(), resource, None);
+                let evaluator = Evaluator::new(request, entities, Extensions::all_available());
+                let errors = checked
+                    .iter()
+                    .filter(|(_, v)| *v == var)
+                    .filter_map(|(e, _)| {
+                        evaluator
+                            .interpret(e, &HashMap::new())
+                            .err()
+                            .filter(is_data_error)
+                            .map(|err| (*e, err))
+                    })
+                    .collect::<Vec<_>>();
+                for (e, err) in &errors {
+                    if errors
+                        .iter()
+                        .any(|(inner, _)| is_strict_subexpression(inner, e))
+                    {
+                        continue;
+                    }
+                    warnings.push(ValidationWarning::entity_data_error(
+                        e.source_loc().cloned(),
+                        t.id().clone(),
+                        uid.clone(),
+                        e.to_string(),
+                        err.to_string(),
+                    ));
+                }
+            }
+        }
+    }
+    warnings
+}
+
+fn unknown() -> EntityUIDEntry {
+    EntityUIDEntry::Unknown { loc: None }
+}
+
+/// The extension function calls and arithmetic operations in the conditions
+/// of `t`, together with their only variable, if it is `principal` or
+/// `resource`
+fn checked_expressions(t: &Template) -> Vec<(&Expr, Var)> {
+    t.non_scope_constraints()
+        .subexpressions()
+        .filter(|e| {
+            matches!(
+                e.expr_kind(),
+                ExprKind::ExtensionFunctionApp { .. }
+                    | ExprKind::UnaryApp {
+                        op: UnaryOp::Neg,
+                        ..
+                    }
+                    | ExprKind::BinaryApp {
+                        op: BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul,
+                        ..
+                    }
+            )
+        })
+        .filter_map(|e| {
+            let mut vars = HashSet::new();
+            for sub in e.subexpressions() {
+                match sub.expr_kind() {
+                    ExprKind::Var(v) => {
+                        vars.insert(*v);
+                    }
+                    ExprKind::Slot(_) | ExprKind::Unknown(_) => return None,
+                    _ => (),
+                }
+            }
+            match vars.into_iter().collect::<Vec<_>>().as_slice() {
+                [v @ (Var::Principal | Var::Resource)] => Some((e, *v)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The principal and resource entity types of the request environments
+/// which `t` applies to
+fn applicable_entity_types(
+    schema: &ValidatorSchema,
+    t: &Template,
+) -> HashMap<Var, HashSet<EntityType>> {
+    let typechecker = Typechecker::new(schema, ValidationMode::default(), t.id().clone());
+    let mut types: HashMap<Var, HashSet<EntityType>> = HashMap::new();
+    for (env, check) in typechecker.typecheck_by_request_env(t) {
+        if matches!(check, PolicyCheck::Irrelevant(_)) {
+            continue;
+        }
+        if let Some(ety) = env.principal_entity_type() {
+            types.entry(Var::Principal).or_default().insert(ety.clone());
+        }
+        if let Some(ety) = env.resource_entity_type() {
+            types.entry(Var::Resource).or_default().insert(ety.clone());
+        }
+    }
+    types
+}
+
+/// True if evaluating an expression failed because of the attribute values
+/// of the entities
+fn is_data_error(err: &EvaluationError) -> bool {
+    matches!(
+        err,
+        EvaluationError::FailedExtensionFunctionExecution(_) | EvaluationError::IntegerOverflow(_)
+    )
+}
+
+fn is_strict_subexpression(inner: &Expr, outer: &Expr) -> bool {
+    !std::ptr::eq(inner, outer) && outer.subexpressions().any(|e| std::ptr::eq(e, inner))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::entities::{EntityJsonParser, NoEntitiesSchema, TCComputation};
+    use cedar_policy_core::parser::parse_policyset;
+    use cool_asserts::assert_matches;
+
+    fn schema() -> ValidatorSchema {
+        ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { balance: String, age: Long, addr: String };
+            entity Photo = { size: Long };
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap()
+        .0
+    }
+
+    fn entities() -> Entities {
+        EntityJsonParser::<NoEntitiesSchema>::new(
+            None,
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "parents": [],
+                  "attrs": { "balance": "1.5", "age": 30, "addr": "10.0.0.1" } },
+                { "uid": { "type": "User", "id": "bob" }, "parents": [],
+                  "attrs": { "balance": "lots", "age": 9223372036854775807, "addr": "home" } },
+                { "uid": { "type": "Photo", "id": "pic" }, "parents": [],
+                  "attrs": { "size": 9223372036854775807 } }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    /// The entity and source snippet of each warning for `src`
+    fn errors(src: &str) -> Vec<(String, String)> {
+        let policies = parse_policyset(src).unwrap();
+        let mut errors = entity_data_errors(&schema(), &policies, &entities())
+            .into_iter()
+            .map(|w| {
+                assert_matches!(w, ValidationWarning::EntityDataError(w) => {
+                    let loc = w.source_loc.expect("should have a location");
+                    (w.entity.to_string(), loc.snippet().unwrap().to_string())
+                })
+            })
+            .collect::<Vec<_>>();
+        errors.sort();
+        errors
+    }
+
+    fn error(entity: &str, snippet: &str) -> (String, String) {
+        (entity.to_string(), snippet.to_string())
+    }
+
+    #[test]
+    fn extension_function_failures() {
+        assert_eq!(
+            errors(
+                r#"permit(principal, action, resource) when { decimal(principal.balance).greaterThan(decimal("1.0")) && ip(principal.addr).isLoopback() };"#
+            ),
+            vec![
+                error(r#"User::"bob""#, "decimal(principal.balance)"),
+                error(r#"User::"bob""#, "ip(principal.addr)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn overflows() {
+        assert_eq!(
+            errors(
+                r#"permit(principal, action, resource) when { principal.age + 1 > 0 && resource.size * 2 > 0 && principal.age > context.limit + 1 };"#
+            ),
+            vec![
+                error(r#"Photo::"pic""#, "resource.size * 2"),
+                error(r#"User::"bob""#, "principal.age + 1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn inapplicable_policies() {
+        assert_eq!(
+            errors(
+                r#"permit(principal, action, resource is User) when { decimal(resource.balance).greaterThan(decimal("1.0")) };"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn messages() {
+        let policies = parse_policyset(
+            r#"permit(principal, action, resource) when { decimal(principal.balance).greaterThan(decimal("1.0")) };"#,
+        )
+        .unwrap();
+        let warnings = entity_data_errors(&schema(), &policies, &entities());
+        assert_matches!(warnings.as_slice(), [w] => {
+            let msg = w.to_string();
+            assert!(msg.starts_with("for policy `policy0`, evaluating `decimal("), "{msg}");
+            assert!(msg.contains(r#"for entity `User::"bob"` is an error"#), "{msg}");
+            assert!(msg.contains("lots"), "{msg}");
+        });
+    }
+}
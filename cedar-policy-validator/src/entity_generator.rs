@@ -0,0 +1,461 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Schema-aware generation of random entity stores, for load testing,
+//! benchmarking, and demos.
+
+use std::collections::{HashMap, HashSet};
+
+use cedar_policy_core::ast::{
+    Eid, Entity, EntityAttrEvaluationError, EntityType, EntityUID, Literal, Name, RestrictedExpr,
+};
+use cedar_policy_core::entities::{err::EntitiesError, Entities, TCComputation};
+use cedar_policy_core::extensions::Extensions;
+use miette::Diagnostic;
+use smol_str::{format_smolstr, SmolStr};
+use thiserror::Error;
+
+use crate::types::{AttributeType, Attributes, EntityRecordKind, Primitive, Type};
+use crate::{CoreSchema, ValidatorSchema};
+
+/// Parameters controlling the size and shape of a generated entity store
+// CAUTION: this type is publicly exported in `cedar-policy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityGeneratorConfig {
+    /// Number of entities to generate for each (non-action) entity type
+    pub entities_per_type: usize,
+    /// Maximum number of parents to give each entity. Each entity gets between
+    /// zero and this many parents, chosen among the entity types the schema
+    /// allows as parents.
+    pub max_parents: usize,
+    /// Maximum number of elements in generated set-typed attribute values
+    pub max_set_size: usize,
+    /// Probability (in percent) that an optional attribute is present
+    pub optional_attr_percent: u8,
+    /// Seed for the random number generator. Generation is deterministic for a
+    /// given schema, configuration, and seed.
+    pub seed: u64,
+}
+
+impl Default for EntityGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            entities_per_type: 100,
+            max_parents: 3,
+            max_set_size: 4,
+            optional_attr_percent: 50,
+            seed: 0,
+        }
+    }
+}
+
+/// Errors which can occur when generating entities
+#[derive(Debug, Diagnostic, Error)]
+pub enum EntityGenerationError {
+    /// The schema uses an extension type we don't know how to generate values for
+    #[error("cannot generate values of extension type `{0}`")]
+    UnsupportedExtensionType(Name),
+    /// Error evaluating a generated attribute value
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    AttrEvaluation(#[from] EntityAttrEvaluationError),
+    /// Error constructing the entity store from the generated entities
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] EntitiesError),
+}
+
+/// Generate a random entity store conforming to `schema`.
+///
+/// The store contains `config.entities_per_type` entities of every entity type
+/// declared in the schema, plus the schema's action entities. Entity ids have
+/// the form `<basename>-<n>`. The generated hierarchy is always acyclic, and
+/// attribute values always have the types the schema declares.
+pub fn generate_entities(
+    schema: &ValidatorSchema,
+    config: &EntityGeneratorConfig,
+) -> Result<Entities, EntityGenerationError> {
+    EntityGenerator::new(schema, config).generate()
+}
+
+/// Small, fast, deterministic PRNG (`SplitMix64`). Not suitable for anything
+/// security-related, which is fine for generating test data.
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly random number in `0..bound`. `bound` must be nonzero.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniformly random number in `0..=max`
+    fn up_to(&mut self, max: usize) -> usize {
+        self.below(max.saturating_add(1))
+    }
+
+    fn percent(&mut self, p: u8) -> bool {
+        self.below(100) < usize::from(p)
+    }
+
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.below(items.len()))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct EntityGenerator<'a> {
+    schema: &'a ValidatorSchema,
+    config: &'a EntityGeneratorConfig,
+    rng: SplitMix64,
+    /// Entity types in the order in which their entities are laid out.
+    /// Types with fewer descendant types come first, so children precede
+    /// their parents.
+    types: Vec<&'a EntityType>,
+    /// UIDs of all generated (non-action) entities, by type
+    uids: HashMap<&'a EntityType, Vec<EntityUID>>,
+}
+
+impl<'a> EntityGenerator<'a> {
+    fn new(schema: &'a ValidatorSchema, config: &'a EntityGeneratorConfig) -> Self {
+        let mut types: Vec<_> = schema.entity_types().collect();
+        // If `P` is a (transitive) parent type of `T`, then `P` has strictly
+        // more descendant types than `T` (unless the type hierarchy is
+        // cyclic), so this puts children before parents. Ties are broken by
+        // name so that the output is deterministic.
+        types.sort_by(|(n1, t1), (n2, t2)| {
+            t1.descendants
+                .len()
+                .cmp(&t2.descendants.len())
+                .then_with(|| n1.cmp(n2))
+        });
+        let types: Vec<_> = types.into_iter().map(|(name, _)| name).collect();
+        let uids = types
+            .iter()
+            .map(|ty| {
+                let basename = ty.name().basename();
+                let uids = (0..config.entities_per_type)
+                    .map(|i| {
+                        EntityUID::from_components(
+                            (*ty).clone(),
+                            Eid::new(format_smolstr!("{basename}-{i}")),
+                            None,
+                        )
+                    })
+                    .collect();
+                (*ty, uids)
+            })
+            .collect();
+        Self {
+            schema,
+            config,
+            rng: SplitMix64(config.seed),
+            types,
+            uids,
+        }
+    }
+
+    fn generate(mut self) -> Result<Entities, EntityGenerationError> {
+        let extensions = Extensions::all_available();
+        let mut entities = Vec::new();
+        for (type_idx, ty) in self.types.clone().into_iter().enumerate() {
+            let Some(ety) = self.schema.get_entity_type(ty) else {
+                continue;
+            };
+            // Parent candidates: for each type allowed as a parent, the range
+            // of its entities which are laid out after the child. Only picking
+            // parents later in the layout guarantees an acyclic hierarchy.
+            let parent_types: Vec<(usize, &EntityType)> = self
+                .types
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| {
+                    self.schema
+                        .get_entity_type(p)
+                        .is_some_and(|p| p.has_descendant_entity_type(ty))
+                })
+                .map(|(idx, p)| (idx, *p))
+                .collect();
+            let uids = self.uids.get(ty).cloned().unwrap_or_default();
+            for (i, uid) in uids.into_iter().enumerate() {
+                let mut parents = HashSet::new();
+                let candidates: Vec<&EntityUID> = parent_types
+                    .iter()
+                    .flat_map(|(p_idx, p)| {
+                        let skip = match p_idx.cmp(&type_idx) {
+                            std::cmp::Ordering::Less => usize::MAX,
+                            std::cmp::Ordering::Equal => i + 1,
+                            std::cmp::Ordering::Greater => 0,
+                        };
+                        self.uids.get(p).into_iter().flatten().skip(skip)
+                    })
+                    .collect();
+                for _ in 0..self.rng.up_to(self.config.max_parents) {
+                    if let Some(parent) = self.rng.choose(&candidates) {
+                        parents.insert((*parent).clone());
+                    }
+                }
+                let attrs = self.gen_attrs(&ety.attributes)?;
+                entities.push(Entity::new(uid, attrs, parents, extensions)?);
+            }
+        }
+        Ok(Entities::from_entities(
+            entities,
+            Some(&CoreSchema::new(self.schema)),
+            TCComputation::ComputeNow,
+            extensions,
+        )?)
+    }
+
+    fn gen_attrs(
+        &mut self,
+        attrs: &Attributes,
+    ) -> Result<HashMap<SmolStr, RestrictedExpr>, EntityGenerationError> {
+        let mut generated = HashMap::new();
+        for (
+            name,
+            AttributeType {
+                attr_type,
+                is_required,
+            },
+        ) in attrs.iter()
+        {
+            if *is_required || self.rng.percent(self.config.optional_attr_percent) {
+                generated.insert(name.clone(), self.gen_value(name, attr_type)?);
+            }
+        }
+        Ok(generated)
+    }
+
+    /// Generate a value of type `ty` for the attribute `attr`
+    fn gen_value(
+        &mut self,
+        attr: &SmolStr,
+        ty: &Type,
+    ) -> Result<RestrictedExpr, EntityGenerationError> {
+        Ok(match ty {
+            Type::Never | Type::False => RestrictedExpr::val(false),
+            Type::True => RestrictedExpr::val(true),
+            Type::Primitive {
+                primitive_type: Primitive::Bool,
+            } => RestrictedExpr::val(self.rng.percent(50)),
+            Type::Primitive {
+                primitive_type: Primitive::Long,
+            } => RestrictedExpr::val(self.rng.below(1000) as i64),
+            Type::Primitive {
+                primitive_type: Primitive::String,
+            } => RestrictedExpr::val(format_smolstr!("{attr}-{}", self.rng.below(1000))),
+            Type::Union { arms } => {
+                let arms: Vec<&Primitive> = arms.iter().collect();
+                match self.rng.choose(&arms) {
+                    Some(&arm) => self.gen_value(
+                        attr,
+                        &Type::Primitive {
+                            primitive_type: arm.clone(),
+                        },
+                    )?,
+                    None => RestrictedExpr::val(false),
+                }
+            }
+            Type::Nullable { inner } => {
+                if matches!(inner.as_ref(), Type::Never) || self.rng.percent(20) {
+                    RestrictedExpr::val(Literal::Null)
+                } else {
+                    self.gen_value(attr, inner)?
+                }
+            }
+            Type::Set { element_type } => match element_type {
+                None => RestrictedExpr::set(std::iter::empty()),
+                Some(element_type) => {
+                    let len = self.rng.up_to(self.config.max_set_size);
+                    let elements = (0..len)
+                        .map(|_| self.gen_value(attr, element_type))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    RestrictedExpr::set(elements)
+                }
+            },
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => {
+                let pairs = self.gen_attrs(attrs)?;
+                // PANIC SAFETY: `pairs` comes from a `HashMap`, so there can't be duplicate keys
+                #[allow(clippy::expect_used)]
+                RestrictedExpr::record(pairs).expect("record keys should be unique")
+            }
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
+                let candidates: Vec<&EntityType> = lub.iter().collect();
+                self.gen_entity_ref(&candidates)
+            }
+            Type::EntityOrRecord(EntityRecordKind::AnyEntity) => {
+                let candidates = self.types.clone();
+                self.gen_entity_ref(&candidates)
+            }
+            Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. }) => {
+                let actions: Vec<&EntityUID> = self
+                    .schema
+                    .actions()
+                    .filter(|a| a.entity_type() == name)
+                    .collect();
+                match self.rng.choose(&actions) {
+                    Some(action) => RestrictedExpr::val((*action).clone()),
+                    None => RestrictedExpr::val(EntityUID::from_components(
+                        name.clone(),
+                        Eid::new(attr.clone()),
+                        None,
+                    )),
+                }
+            }
+            Type::ExtensionType { name } => match name.to_string().as_str() {
+                "ipaddr" => RestrictedExpr::call_extension_fn(
+                    extension_constructor("ip"),
+                    [RestrictedExpr::val(format_smolstr!(
+                        "10.{}.{}.{}",
+                        self.rng.below(256),
+                        self.rng.below(256),
+                        self.rng.below(256)
+                    ))],
+                ),
+                "decimal" => RestrictedExpr::call_extension_fn(
+                    extension_constructor("decimal"),
+                    [RestrictedExpr::val(format_smolstr!(
+                        "{}.{:04}",
+                        self.rng.below(1000),
+                        self.rng.below(10000)
+                    ))],
+                ),
+                _ => {
+                    return Err(EntityGenerationError::UnsupportedExtensionType(
+                        name.clone(),
+                    ))
+                }
+            },
+        })
+    }
+
+    /// Generate a reference to a random entity with one of the given types.
+    /// Entity types with no generated entities still get a reference (to an
+    /// entity which doesn't exist in the store), since dangling references are
+    /// allowed in entity data.
+    fn gen_entity_ref(&mut self, types: &[&EntityType]) -> RestrictedExpr {
+        let candidates: Vec<&EntityUID> = types
+            .iter()
+            .filter_map(|ty| self.uids.get(ty))
+            .flatten()
+            .collect();
+        if let Some(uid) = self.rng.choose(&candidates) {
+            return RestrictedExpr::val((*uid).clone());
+        }
+        // PANIC SAFETY: entity LUBs are nonempty, and schemas don't produce `AnyEntity`
+        // attributes unless they declare at least one entity type
+        #[allow(clippy::expect_used)]
+        let ty = self
+            .rng
+            .choose(types)
+            .expect("entity-typed attributes should have at least one candidate type");
+        RestrictedExpr::val(EntityUID::from_components(
+            (*ty).clone(),
+            Eid::new("missing"),
+            None,
+        ))
+    }
+}
+
+/// The name of the extension function constructing values of an extension type
+fn extension_constructor(name: &str) -> Name {
+    // PANIC SAFETY: all callers pass valid identifiers
+    #[allow(clippy::expect_used)]
+    Name::parse_unqualified_name(name).expect("should be a valid identifier")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::ast::PartialValue;
+
+    fn schema() -> ValidatorSchema {
+        r#"
+        entity Org;
+        entity Group in [Group, Org] {
+            name: String,
+        };
+        entity User in [Group] {
+            age: Long,
+            admin?: Bool,
+            emails: Set<String>,
+            ip: ipaddr,
+            score: decimal,
+            manager?: User,
+            address: { city: String, zip?: Long },
+        };
+        action view appliesTo { principal: User, resource: Group };
+        "#
+        .parse()
+        .expect("schema should parse")
+    }
+
+    #[test]
+    fn generates_conforming_entities() {
+        let schema = schema();
+        let config = EntityGeneratorConfig {
+            entities_per_type: 10,
+            ..Default::default()
+        };
+        // conformance with the schema is checked when constructing the store
+        let entities = generate_entities(&schema, &config).expect("generation should succeed");
+        // 10 each of `Org`, `Group`, `User`, plus the one action
+        assert_eq!(entities.iter().count(), 31);
+        let alice: EntityUID = r#"User::"User-3""#.parse().unwrap();
+        let alice = entities.entity(&alice).unwrap();
+        assert!(matches!(alice.get("age"), Some(PartialValue::Value(_))));
+        assert!(alice.get("address").is_some());
+        for ancestor in alice.ancestors() {
+            assert_ne!(ancestor.entity_type().to_string(), "User");
+        }
+    }
+
+    #[test]
+    fn deterministic_for_seed() {
+        let schema = schema();
+        let config = EntityGeneratorConfig {
+            entities_per_type: 5,
+            seed: 42,
+            ..Default::default()
+        };
+        let e1 = generate_entities(&schema, &config).unwrap();
+        let e2 = generate_entities(&schema, &config).unwrap();
+        for e in e1.iter() {
+            let other = e2.entity(e.uid()).unwrap();
+            assert_eq!(
+                e.attrs().collect::<HashMap<_, _>>(),
+                other.attrs().collect::<HashMap<_, _>>()
+            );
+            assert_eq!(
+                e.ancestors().collect::<HashSet<_>>(),
+                other.ancestors().collect::<HashSet<_>>()
+            );
+        }
+    }
+}
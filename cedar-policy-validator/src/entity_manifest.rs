@@ -259,6 +259,38 @@ impl<T: Clone> EntityManifest<T> {
     }
 }
 
+impl EntityManifest {
+    /// Serialize the manifest to JSON, e.g., to hand it to a code generator
+    /// for a data-fetching layer.
+    ///
+    /// The JSON is a list of `[requestType, trie]` pairs under the key
+    /// `perAction`. Each trie is a list of `[root, accessTrie]` pairs, where a
+    /// root is either `{ "var": <variable> }` or `{ "literal": <uid> }`, and
+    /// an access trie has the attributes to load (`children`) and whether all
+    /// ancestors of the entity are needed (`ancestorsRequired`).
+    pub fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+
+    /// Serialize the manifest to a JSON string, in the format described in
+    /// [`EntityManifest::to_json_value()`]
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Deserialize a manifest from JSON, in the format described in
+    /// [`EntityManifest::to_json_value()`]
+    pub fn from_json_value(json: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(json)
+    }
+
+    /// Deserialize a manifest from a JSON string, in the format described in
+    /// [`EntityManifest::to_json_value()`]
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
 /// Union two tries by combining the fields.
 fn union_fields<T: Clone>(first: &Fields<T>, second: &Fields<T>) -> Fields<T> {
     let mut res = first.clone();
@@ -589,11 +621,13 @@ fn type_to_access_trie(ty: &Type) -> AccessTrie {
     match ty {
         // if it's not an entity or record, slice ends here
         Type::ExtensionType { .. }
+        | Type::Union { .. }
         | Type::Never
         | Type::True
         | Type::False
         | Type::Primitive { .. }
         | Type::Set { .. } => AccessTrie::new(),
+        Type::Nullable { inner } => type_to_access_trie(inner),
         Type::EntityOrRecord(record_type) => entity_or_record_to_access_trie(record_type),
     }
 }
@@ -789,6 +823,48 @@ when {
         assert_eq!(entity_manifest, expected_manifest);
     }
 
+    #[test]
+    fn test_entity_manifest_json_roundtrip() {
+        let mut pset = PolicySet::new();
+        let policy = parse_policy(
+            None,
+            "permit(principal, action, resource)
+when {
+    principal.name == \"John\" && principal in resource
+};",
+        )
+        .expect("should succeed");
+        pset.add(policy.into()).expect("should succeed");
+
+        let schema = ValidatorSchema::from_cedarschema_str(
+            "
+entity User in [Document] = {
+  name: String,
+};
+entity Document;
+action Read appliesTo {
+  principal: [User],
+  resource: [Document]
+};
+  ",
+            Extensions::all_available(),
+        )
+        .unwrap()
+        .0;
+
+        let entity_manifest = compute_entity_manifest(&schema, &pset).expect("Should succeed");
+        let json = entity_manifest.to_json_string().expect("should serialize");
+        assert_eq!(
+            EntityManifest::from_json_str(&json).expect("should deserialize"),
+            entity_manifest
+        );
+        let value = entity_manifest.to_json_value().expect("should serialize");
+        assert_eq!(
+            EntityManifest::from_json_value(value).expect("should deserialize"),
+            entity_manifest
+        );
+    }
+
     #[test]
     fn test_entity_manifest_ancestors_required() {
         let mut pset = PolicySet::new();
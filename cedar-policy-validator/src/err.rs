@@ -215,6 +215,25 @@ pub enum SchemaError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     ContextOrShapeNotRecord(#[from] schema_errors::ContextOrShapeNotRecordError),
+    /// A parent attribute of an entity type is not declared, or its values
+    /// are not entities of a type the entity type is a member of
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidParentAttribute(#[from] schema_errors::InvalidParentAttributeError),
+    /// A computed attribute of an entity type is not declared, or the
+    /// expression computing it doesn't parse
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidComputedAttribute(#[from] schema_errors::InvalidComputedAttributeError),
+    /// An entity type (transitively) extends itself
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    CycleInEntityTypeInheritance(#[from] schema_errors::CycleInEntityTypeInheritanceError),
+    /// An entity type declares an attribute which it inherits from an entity
+    /// type it extends
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InheritedAttributeRedeclared(#[from] schema_errors::InheritedAttributeRedeclaredError),
     /// An action entity (transitively) has an attribute that is an empty set.
     /// The validator cannot assign a type to an empty set.
     /// This error variant should only be used when `PermitAttributes` is enabled.
@@ -239,6 +258,10 @@ pub enum SchemaError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     UnknownExtensionType(schema_errors::UnknownExtensionTypeError),
+    /// The schema declared a union type with an arm that is not a primitive type.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidUnionType(#[from] schema_errors::InvalidUnionTypeError),
     /// The schema used a reserved namespace or typename (as of this writing, just `__cedar`).
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -316,7 +339,7 @@ pub mod schema_errors {
 
     use cedar_policy_core::{
         ast::{EntityAttrEvaluationError, EntityType, EntityUID, InternalName, Name},
-        parser::join_with_conjunction,
+        parser::{err::ParseErrors, join_with_conjunction},
         transitive_closure,
     };
     use itertools::Itertools;
@@ -546,6 +569,64 @@ pub mod schema_errors {
 }))]
     pub struct ContextOrShapeNotRecordError(pub(crate) ContextOrShape);
 
+    /// Invalid parent attribute error
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("invalid parent attribute `{attr}` for entity type `{entity_type}`")]
+    #[diagnostic(help(
+        "parent attributes must be declared with an entity type, or a set of an entity type, which `{entity_type}` is a member of"
+    ))]
+    pub struct InvalidParentAttributeError {
+        pub(crate) entity_type: EntityType,
+        pub(crate) attr: SmolStr,
+    }
+
+    /// Invalid computed attribute error
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("invalid computed attribute `{attr}` for entity type `{entity_type}`")]
+    #[diagnostic(help(
+        "computed attributes must be declared in the shape of the entity type, and computed by a Cedar expression over the other attributes, which are the `context` of the expression"
+    ))]
+    pub struct InvalidComputedAttributeError {
+        pub(crate) entity_type: EntityType,
+        pub(crate) attr: SmolStr,
+        #[source]
+        pub(crate) parse_errs: Option<ParseErrors>,
+    }
+
+    /// Cycle in entity type inheritance error
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("entity type `{0}` extends itself")]
+    #[diagnostic(help(
+        "an entity type may not extend itself, directly or through other entity types"
+    ))]
+    pub struct CycleInEntityTypeInheritanceError(pub(crate) EntityType);
+
+    /// Inherited attribute redeclared error
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("entity type `{entity_type}` declares attribute `{attr}`, which it inherits from `{supertype}`")]
+    #[diagnostic(help("remove the declaration of `{attr}` from `{entity_type}`, since it has the attributes of the entity types it extends"))]
+    pub struct InheritedAttributeRedeclaredError {
+        pub(crate) entity_type: EntityType,
+        pub(crate) attr: SmolStr,
+        pub(crate) supertype: EntityType,
+    }
+
     /// Action attributes contain empty set error
     //
     // CAUTION: this type is publicly exported in `cedar-policy`.
@@ -714,6 +795,21 @@ pub mod schema_errors {
         }
     }
 
+    /// Union type with an arm that is not a primitive type
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Error, Debug, Diagnostic)]
+    #[error("invalid arm `{arm}` in union type")]
+    #[diagnostic(help(
+        "only the primitive types `Bool`, `Long`, and `String` may be combined in a union type, though any single type may be combined with `Null`"
+    ))]
+    pub struct InvalidUnionTypeError {
+        /// The arm which is not a primitive type
+        pub(crate) arm: crate::types::Type,
+    }
+
     /// Could not find a definition for a common type, at a point in the code
     /// where internal invariants should guarantee that we would find one.
     //
@@ -742,3 +838,23 @@ pub mod schema_errors {
         pub(crate) euids: NonEmpty<EntityUID>,
     }
 }
+
+/// Error returned by [`crate::Validator::typecheck_expression()`] and
+/// [`crate::typecheck::Typechecker::typecheck_expression()`]
+#[derive(Debug, Error, Diagnostic)]
+pub enum TypecheckExpressionError {
+    /// The expression could not be parsed
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] cedar_policy_core::parser::err::ParseErrors),
+    /// The expression is not well typed
+    #[error("expression is not well typed")]
+    Type {
+        /// The type errors found in the expression
+        #[related]
+        errors: Vec<crate::ValidationError>,
+    },
+    /// The expression is too deeply nested to typecheck
+    #[error("expression is too deeply nested to typecheck")]
+    RecursionLimit,
+}
@@ -118,6 +118,7 @@ fn text_in_lit<'a>(
     match lit {
         Literal::Bool(_) => vec![],
         Literal::Long(_) => vec![],
+        Literal::Null => vec![],
         Literal::String(s) => vec![TextKind::String(loc, s)],
         Literal::EntityUID(euid) => text_in_euid(loc, euid).collect(),
     }
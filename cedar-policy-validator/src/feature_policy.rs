@@ -0,0 +1,121 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Defines [`FeaturePolicy`], which restricts the language features policies
+//! may use.
+
+use std::collections::BTreeSet;
+
+use cedar_policy_core::ast::{ConditionKind, ExprKind, Template};
+use cedar_policy_core::parser::Loc;
+use smol_str::SmolStr;
+
+use crate::validation_errors::Feature;
+use crate::ValidationError;
+
+/// Language features which policies may not use, e.g., to enforce a style
+/// guide. Each use of a forbidden feature is reported as a
+/// [`ValidationError::ForbiddenFeature`] error. By default, all features are
+/// allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeaturePolicy {
+    unless: bool,
+    templates: bool,
+    like: bool,
+    extension_functions: BTreeSet<SmolStr>,
+}
+
+impl FeaturePolicy {
+    /// A feature policy which allows all features
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forbid `unless` clauses
+    #[must_use]
+    pub fn forbid_unless(mut self) -> Self {
+        self.unless = true;
+        self
+    }
+
+    /// Forbid templates, i.e., policies with slots
+    #[must_use]
+    pub fn forbid_templates(mut self) -> Self {
+        self.templates = true;
+        self
+    }
+
+    /// Forbid the `like` operator
+    #[must_use]
+    pub fn forbid_like(mut self) -> Self {
+        self.like = true;
+        self
+    }
+
+    /// Forbid calls of the extension function or method `name`, e.g.,
+    /// `decimal` or `isInRange`
+    #[must_use]
+    pub fn forbid_extension_function(mut self, name: impl Into<SmolStr>) -> Self {
+        self.extension_functions.insert(name.into());
+        self
+    }
+
+    /// True if this feature policy allows all features
+    pub fn allows_all(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The uses of forbidden features in the static policy or template `t`
+    pub(crate) fn forbidden_features(&self, t: &Template) -> Vec<ValidationError> {
+        if self.allows_all() {
+            return Vec::new();
+        }
+        let error = |loc: Option<&Loc>, feature: Feature| {
+            ValidationError::forbidden_feature(loc.cloned(), t.id().clone(), feature)
+        };
+        let mut errors = Vec::new();
+        if self.templates {
+            if let Some(slot) = t.slots().next() {
+                errors.push(error(slot.loc.as_ref().or(t.loc()), Feature::Template));
+            }
+        }
+        if self.unless {
+            for (kind, cond) in t.conditions().into_iter().flatten() {
+                if kind == ConditionKind::Unless {
+                    errors.push(error(cond.source_loc(), Feature::Unless));
+                }
+            }
+        }
+        for e in t.non_scope_constraints().subexpressions() {
+            match e.expr_kind() {
+                ExprKind::Like { .. } if self.like => {
+                    errors.push(error(e.source_loc(), Feature::Like));
+                }
+                ExprKind::ExtensionFunctionApp { fn_name, .. } => {
+                    let name = fn_name.to_string();
+                    if self.extension_functions.contains(name.as_str()) {
+                        errors.push(error(
+                            e.source_loc(),
+                            Feature::ExtensionFunction(name.into()),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        errors
+    }
+}
@@ -16,20 +16,27 @@
 
 /// Fuzzy string matching using the Levenshtein distance algorithm
 pub fn fuzzy_search(key: &str, lst: &[impl AsRef<str>]) -> Option<String> {
-    if key.is_empty() || lst.is_empty() {
-        None
-    } else {
-        let t = lst.iter().fold((usize::MAX, ""), |acc, word| {
-            let e = levenshtein_distance(key, word.as_ref());
-            if e < acc.0 {
-                (e, word.as_ref())
-            } else {
-                acc
-            }
-        });
-        Some(t.1.to_owned())
+    fuzzy_search_within(key, lst, None)
+}
+
+/// Like [`fuzzy_search`], but only return a word whose Levenshtein distance
+/// from `key` is at most `max_distance`, if a maximum is given
+pub fn fuzzy_search_within(
+    key: &str,
+    lst: &[impl AsRef<str>],
+    max_distance: Option<usize>,
+) -> Option<String> {
+    if key.is_empty() {
+        return None;
     }
+    lst.iter()
+        .map(|word| (levenshtein_distance(key, word.as_ref()), word.as_ref()))
+        .filter(|(e, _)| max_distance.map_or(true, |max| *e <= max))
+        // `min_by_key` returns the first of several equally close words
+        .min_by_key(|(e, _)| *e)
+        .map(|(_, word)| word.to_owned())
 }
+
 pub fn levenshtein_distance(word1: &str, word2: &str) -> usize {
     let w1 = word1.chars().collect::<Vec<_>>();
     let w2 = word2.chars().collect::<Vec<_>>();
@@ -161,6 +168,20 @@ pub mod test {
         assert_eq!(x, Some("User::\'Alice\'".to_owned()));
     }
 
+    #[test]
+    fn test_match_within() {
+        let words = vec!["principal", "resource"];
+        assert_eq!(
+            fuzzy_search_within("princpal", &words, Some(1)),
+            Some("principal".to_owned())
+        );
+        assert_eq!(fuzzy_search_within("prncpl", &words, Some(2)), None);
+        assert_eq!(
+            fuzzy_search_within("prncpl", &words, None),
+            Some("principal".to_owned())
+        );
+    }
+
     #[test]
     fn test_match_empty() {
         let word1 = "user::Alice";
@@ -0,0 +1,163 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Finds the permit policies of a policy set which are overridden by a forbid
+//! policy with the same scope and condition.
+
+use std::collections::HashMap;
+
+use cedar_policy_core::ast::{
+    ActionConstraint, Effect, Policy, PolicySet, PrincipalConstraint, ResourceConstraint,
+};
+use cedar_policy_core::canonicalizer::{canonicalize, canonicalize_action_constraint};
+use itertools::Itertools;
+
+use crate::ValidationWarning;
+
+/// The requests a policy applies to. Policies with the same applicability
+/// apply to the same requests. Conditions are compared by the text of their
+/// canonical form, which ignores source locations.
+type Applicability = (
+    PrincipalConstraint,
+    ActionConstraint,
+    ResourceConstraint,
+    String,
+);
+
+fn applicability(p: &Policy) -> Applicability {
+    (
+        p.principal_constraint(),
+        canonicalize_action_constraint(p.action_constraint()),
+        p.resource_constraint(),
+        canonicalize(p.non_scope_constraints()).to_string(),
+    )
+}
+
+/// Compute a warning for each pair of a forbid policy and a permit policy of
+/// `policies` (static policies or links) with the same scope and condition,
+/// sorted by the ids of the forbid policy and the permit policy.
+pub(crate) fn identical_permit_and_forbid(policies: &PolicySet) -> Vec<ValidationWarning> {
+    let (permits, forbids): (Vec<&Policy>, Vec<&Policy>) = policies
+        .policies()
+        .sorted_unstable_by(|p1, p2| p1.id().cmp(p2.id()))
+        .partition(|p| p.effect() == Effect::Permit);
+    if permits.is_empty() || forbids.is_empty() {
+        return Vec::new();
+    }
+    let mut permits_by_applicability: HashMap<Applicability, Vec<&Policy>> = HashMap::new();
+    for permit in permits {
+        permits_by_applicability
+            .entry(applicability(permit))
+            .or_default()
+            .push(permit);
+    }
+    forbids
+        .into_iter()
+        .flat_map(|forbid| {
+            permits_by_applicability
+                .get(&applicability(forbid))
+                .into_iter()
+                .flatten()
+                .map(move |permit| {
+                    ValidationWarning::identical_permit_and_forbid(
+                        forbid.loc().cloned(),
+                        forbid.id().clone(),
+                        permit.loc().cloned(),
+                        permit.id().clone(),
+                    )
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::parser::parse_policyset;
+    use miette::Diagnostic;
+
+    fn identical(src: &str) -> Vec<String> {
+        identical_permit_and_forbid(&parse_policyset(src).unwrap())
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reports_identical_policies() {
+        assert_eq!(
+            identical(
+                r#"
+                permit(principal == User::"alice", action in [Action::"view", Action::"edit"], resource)
+                when { resource.owner == principal && context.mfa };
+                forbid(principal == User::"alice", action in [Action::"edit", Action::"view"], resource)
+                when { principal == resource.owner && context.mfa };
+                "#
+            ),
+            vec!["for policy `policy1`, this forbid policy has the same scope and condition as the permit policy `policy0`, so the permit policy never allows any request"]
+        );
+    }
+
+    #[test]
+    fn ignores_different_policies() {
+        // different condition
+        assert_eq!(
+            identical(
+                r#"
+                permit(principal, action, resource) when { context.mfa };
+                forbid(principal, action, resource) unless { context.mfa };
+                "#
+            ),
+            Vec::<String>::new()
+        );
+        // different scope
+        assert_eq!(
+            identical(
+                r#"
+                permit(principal == User::"alice", action, resource);
+                forbid(principal == User::"bob", action, resource);
+                "#
+            ),
+            Vec::<String>::new()
+        );
+        // same effect
+        assert_eq!(
+            identical(
+                r#"
+                forbid(principal, action, resource);
+                forbid(principal, action, resource);
+                "#
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn related_permit() {
+        let src = r#"permit(principal, action, resource);
+forbid(principal, action, resource);"#;
+        let warnings = identical_permit_and_forbid(&parse_policyset(src).unwrap());
+        let [warning] = warnings.as_slice() else {
+            panic!("expected one warning: {warnings:?}");
+        };
+        assert_eq!(warning.related().unwrap().count(), 1);
+        let related = warning.related().unwrap().next().unwrap();
+        assert_eq!(related.to_string(), "policy `policy0` is declared here");
+        assert_eq!(related.labels().unwrap().count(), 1);
+        let label = related.labels().unwrap().next().unwrap();
+        assert!(src[label.offset()..label.offset() + label.len()].starts_with("permit"));
+    }
+}
@@ -0,0 +1,387 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains [`IncrementalValidator`], which only revalidates the
+//! policies affected by changes to a policy set or schema.
+
+use crate::expr_iterator::{policy_entity_type_names, policy_entity_uids};
+use crate::types::{EntityRecordKind, Type};
+use crate::{
+    confusable_string_checks, ValidationError, ValidationMode, ValidationResult, ValidationWarning,
+    Validator, ValidatorSchema,
+};
+use cedar_policy_core::ast::{ActionConstraint, EntityType, EntityUID, PolicySet, Template};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// A declaration of the schema which the result of validating a policy may
+/// depend on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Declaration {
+    EntityType(EntityType),
+    Action(EntityUID),
+    /// The set of declared actions, which policies not constraining the
+    /// action to a single one depend on
+    ActionIds,
+}
+
+/// A [`Validator`] which memoizes the result of validating each static policy
+/// and template, keyed by a hash of its content, together with the schema
+/// declarations the result depends on. Validating a policy set again only
+/// typechecks the static policies and templates which were added or changed,
+/// and those depending on a declaration which changed since they were last
+/// validated.
+///
+/// This follows incremental computation frameworks like salsa: every
+/// [`IncrementalValidator::set_schema()`] starts a new revision, each
+/// declaration records the revision in which it last changed, and each result
+/// the revision in which it was last known to be current. Results with errors
+/// or warnings, and results of validation in modes other than strict, are
+/// conservatively recomputed after any schema change, since their diagnostics
+/// may depend on any declaration, e.g., suggest a similar name.
+///
+/// Results are kept for the static policies and templates of the last
+/// validated policy set. Template-linked policies are checked anew on each
+/// validation, which is cheap.
+#[derive(Debug)]
+pub struct IncrementalValidator {
+    validator: Validator,
+    /// Incremented by each schema change
+    revision: u64,
+    /// Revision in which each declaration last changed, for declarations
+    /// which changed after the first revision
+    changed_at: HashMap<Declaration, u64>,
+    /// Revision in which the schema last changed
+    schema_changed_at: u64,
+    /// Memoized results, by hash of the static policy or template
+    memos: HashMap<u64, Vec<Memo>>,
+    /// Number of results reused by the last validation
+    reused: usize,
+    /// Number of results recomputed by the last validation
+    recomputed: usize,
+}
+
+/// The memoized result of validating a static policy or template
+#[derive(Debug)]
+struct Memo {
+    template: Template,
+    mode: ValidationMode,
+    /// The latest revision in which the result was known to be current
+    verified_at: u64,
+    /// The declarations the result depends on, or `None` if it may depend on
+    /// any declaration
+    dependencies: Option<HashSet<Declaration>>,
+    errors: Vec<ValidationError>,
+    warnings: Vec<ValidationWarning>,
+}
+
+impl Memo {
+    /// True if none of the declarations the result depends on changed since
+    /// it was last verified
+    fn is_current(&self, changed_at: &HashMap<Declaration, u64>, schema_changed_at: u64) -> bool {
+        self.verified_at >= schema_changed_at
+            || self.dependencies.as_ref().is_some_and(|deps| {
+                !deps
+                    .iter()
+                    .any(|d| changed_at.get(d).is_some_and(|r| *r > self.verified_at))
+            })
+    }
+}
+
+impl IncrementalValidator {
+    /// An incremental validator with the schema and options of `validator`,
+    /// which has no memoized results yet
+    pub fn new(validator: Validator) -> Self {
+        Self {
+            validator,
+            revision: 0,
+            changed_at: HashMap::new(),
+            schema_changed_at: 0,
+            memos: HashMap::new(),
+            reused: 0,
+            recomputed: 0,
+        }
+    }
+
+    /// The underlying validator
+    pub fn validator(&self) -> &Validator {
+        &self.validator
+    }
+
+    /// Validate against `schema` from now on. The memoized results which
+    /// depend on declarations which differ between the old and new schema
+    /// are recomputed by the next validation.
+    pub fn set_schema(&mut self, schema: ValidatorSchema) {
+        let old = std::mem::replace(&mut self.validator.schema, schema);
+        let new = &self.validator.schema;
+        self.revision += 1;
+        self.schema_changed_at = self.revision;
+        let (revision, changed_at) = (self.revision, &mut self.changed_at);
+
+        let names: HashSet<&EntityType> = old
+            .entity_types()
+            .chain(new.entity_types())
+            .map(|(name, _)| name)
+            .collect();
+        for name in names {
+            let (old_ty, new_ty) = (old.get_entity_type(name), new.get_entity_type(name));
+            if old_ty != new_ty {
+                changed_at.insert(Declaration::EntityType(name.clone()), revision);
+                // the ancestors of its descendants changed, too
+                for ty in old_ty.into_iter().chain(new_ty) {
+                    for descendant in &ty.descendants {
+                        changed_at.insert(Declaration::EntityType(descendant.clone()), revision);
+                    }
+                }
+            }
+        }
+
+        let actions: HashSet<&EntityUID> = old.actions().chain(new.actions()).collect();
+        for action in actions {
+            let (old_action, new_action) = (old.get_action_id(action), new.get_action_id(action));
+            if old_action != new_action {
+                changed_at.insert(Declaration::Action(action.clone()), revision);
+                for a in old_action.into_iter().chain(new_action) {
+                    for descendant in &a.descendants {
+                        changed_at.insert(Declaration::Action(descendant.clone()), revision);
+                    }
+                }
+                if old_action.is_none() || new_action.is_none() {
+                    changed_at.insert(Declaration::ActionIds, revision);
+                }
+            }
+        }
+    }
+
+    /// Validate all templates, links, and static policies in a policy set,
+    /// like [`Validator::validate()`], reusing the memoized results which are
+    /// still current
+    pub fn validate(&mut self, policies: &PolicySet, mode: ValidationMode) -> ValidationResult {
+        let mut memos: HashMap<u64, Vec<Memo>> = HashMap::new();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        self.reused = 0;
+        self.recomputed = 0;
+        for t in policies.all_templates() {
+            let hash = hash(t);
+            let current = self.memos.get_mut(&hash).and_then(|bucket| {
+                let i = bucket.iter().position(|memo| {
+                    memo.template == *t
+                        && memo.mode == mode
+                        && memo.is_current(&self.changed_at, self.schema_changed_at)
+                })?;
+                Some(bucket.swap_remove(i))
+            });
+            let memo = match current {
+                Some(mut memo) => {
+                    self.reused += 1;
+                    memo.verified_at = self.revision;
+                    memo
+                }
+                None => {
+                    self.recomputed += 1;
+                    self.compute(t, mode)
+                }
+            };
+            errors.extend(memo.errors.iter().cloned());
+            warnings.extend(memo.warnings.iter().cloned());
+            memos.entry(hash).or_default().push(memo);
+        }
+        // drop the results of policies which are no longer in the policy set
+        self.memos = memos;
+
+        errors.extend(
+            policies
+                .policies()
+                .filter_map(|p| self.validator.validate_slots(p, mode))
+                .flatten(),
+        );
+        warnings.extend(confusable_string_checks(policies.all_templates()));
+        warnings.extend(crate::identical_policies::identical_permit_and_forbid(
+            policies,
+        ));
+        ValidationResult::new(errors, warnings)
+            .with_policies_by_action(self.validator.policies_by_action(policies))
+    }
+
+    /// Number of static policies and templates whose memoized results the
+    /// last validation reused
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+
+    /// Number of static policies and templates which the last validation
+    /// validated anew
+    pub fn recomputed(&self) -> usize {
+        self.recomputed
+    }
+
+    fn compute(&self, t: &Template, mode: ValidationMode) -> Memo {
+        let (errors, warnings) = self.validator.validate_policy(t, mode);
+        let errors: Vec<_> = errors.collect();
+        let warnings: Vec<_> = warnings.collect();
+        let dependencies = (mode.is_strict() && errors.is_empty() && warnings.is_empty())
+            .then(|| self.dependencies(t));
+        Memo {
+            template: t.clone(),
+            mode,
+            verified_at: self.revision,
+            dependencies,
+            errors,
+            warnings,
+        }
+    }
+
+    /// The declarations which validating `t` depends on: the actions it may
+    /// apply to, the entity types it mentions or which those actions apply
+    /// to, and, transitively, the entity types of their attributes and their
+    /// descendants
+    fn dependencies(&self, t: &Template) -> HashSet<Declaration> {
+        let schema = &self.validator.schema;
+        let mut deps = HashSet::new();
+        if !matches!(t.action_constraint(), ActionConstraint::Eq(_)) {
+            deps.insert(Declaration::ActionIds);
+        }
+        let mut types: Vec<&EntityType> = policy_entity_type_names(t).collect();
+        let actions = self
+            .validator
+            .get_actions_satisfying_constraint(t.action_constraint())
+            .chain(policy_entity_uids(t).filter(|uid| uid.is_action()));
+        for action in actions {
+            if !deps.insert(Declaration::Action(action.clone())) {
+                continue;
+            }
+            if let Some(a) = schema.get_action_id(action) {
+                types.extend(a.principals().chain(a.resources()));
+                entity_types_in(a.context_type(), &mut types);
+                for (_, attr) in a.attribute_types.iter() {
+                    entity_types_in(&attr.attr_type, &mut types);
+                }
+            }
+        }
+        while let Some(ty) = types.pop() {
+            if !deps.insert(Declaration::EntityType(ty.clone())) {
+                continue;
+            }
+            if let Some(ety) = schema.get_entity_type(ty) {
+                types.extend(ety.descendants.iter());
+                for (_, attr) in ety.attributes() {
+                    entity_types_in(&attr.attr_type, &mut types);
+                }
+            }
+        }
+        deps
+    }
+}
+
+/// Push the entity types occurring in `ty` to `types`
+fn entity_types_in<'a>(ty: &'a Type, types: &mut Vec<&'a EntityType>) {
+    match ty {
+        Type::Set {
+            element_type: Some(element_type),
+        } => entity_types_in(element_type, types),
+        Type::Nullable { inner } => entity_types_in(inner, types),
+        Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => types.extend(lub.iter()),
+        Type::EntityOrRecord(
+            EntityRecordKind::Record { attrs, .. } | EntityRecordKind::ActionEntity { attrs, .. },
+        ) => {
+            for (_, attr) in attrs.iter() {
+                entity_types_in(&attr.attr_type, types);
+            }
+        }
+        Type::Never
+        | Type::True
+        | Type::False
+        | Type::Primitive { .. }
+        | Type::Set { element_type: None }
+        | Type::ExtensionType { .. }
+        | Type::Union { .. }
+        | Type::EntityOrRecord(EntityRecordKind::AnyEntity) => {}
+    }
+}
+
+fn hash(t: &Template) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    t.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::{extensions::Extensions, parser::parse_policyset};
+
+    fn schema(src: &str) -> ValidatorSchema {
+        ValidatorSchema::from_cedarschema_str(src, Extensions::all_available())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn revalidates_affected_policies() {
+        let schema1 = r#"
+            entity User;
+            entity Photo = { owner: User };
+            entity Album;
+            action view appliesTo { principal: User, resource: Photo };
+            action list appliesTo { principal: User, resource: Album };
+        "#;
+        let policies = parse_policyset(
+            r#"
+            permit(principal, action == Action::"view", resource) when { resource.owner == principal };
+            permit(principal, action == Action::"list", resource);
+            "#,
+        )
+        .unwrap();
+        let mut validator = IncrementalValidator::new(Validator::new(schema(schema1)));
+        let check = |validator: &mut IncrementalValidator, passed: bool| {
+            let result = validator.validate(&policies, ValidationMode::Strict);
+            let expected = Validator::new(validator.validator().schema().clone())
+                .validate(&policies, ValidationMode::Strict);
+            assert_eq!(result.validation_passed(), passed);
+            assert_eq!(
+                result.validation_errors().count(),
+                expected.validation_errors().count()
+            );
+            (validator.reused(), validator.recomputed())
+        };
+        assert_eq!(check(&mut validator, true), (0, 2));
+        assert_eq!(check(&mut validator, true), (2, 0));
+
+        // only the policy for `list` depends on `Album`
+        validator.set_schema(schema(
+            &schema1.replace("entity Album;", "entity Album = { name: String };"),
+        ));
+        assert_eq!(check(&mut validator, true), (1, 1));
+
+        // only the policy for `view` depends on `Photo`
+        validator.set_schema(schema(
+            &schema1.replace("entity Photo = { owner: User };", "entity Photo;"),
+        ));
+        assert_eq!(check(&mut validator, false), (0, 2));
+        // invalid policies are reused while the schema doesn't change
+        assert_eq!(check(&mut validator, false), (2, 0));
+
+        // adding an action doesn't affect the policy for `list`, which
+        // constrains the action
+        validator.set_schema(schema(&format!(
+            "{}\naction edit appliesTo {{ principal: User, resource: Photo }};",
+            schema1.replace("entity Photo = { owner: User };", "entity Photo;")
+        )));
+        assert_eq!(check(&mut validator, false), (1, 1));
+    }
+}
@@ -214,7 +214,16 @@ impl CommonTypeId {
     fn is_reserved_schema_keyword(id: &UnreservedId) -> bool {
         matches!(
             id.as_ref(),
-            "Bool" | "Boolean" | "Entity" | "Extension" | "Long" | "Record" | "Set" | "String"
+            "Bool"
+                | "Boolean"
+                | "Entity"
+                | "Extension"
+                | "Long"
+                | "Null"
+                | "Record"
+                | "Set"
+                | "String"
+                | "Union"
         )
     }
 
@@ -384,16 +393,45 @@ impl NamespaceDefinition<ConditionalName> {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 #[cfg_attr(feature = "wasm", tsify(into_wasm_abi, from_wasm_abi))]
+#[non_exhaustive]
 pub struct EntityType<N> {
     /// Entities of this [`EntityType`] are allowed to be members of entities of
     /// these types.
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub member_of_types: Vec<N>,
+    /// Entity type which this [`EntityType`] extends. Entities of this
+    /// [`EntityType`] have the attributes, including parent and computed
+    /// attributes, of the extended type, and may be members of entities of
+    /// the types that entities of the extended type may be members of.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<N>,
+    /// Attributes whose values, entities or sets of entities, are parents of
+    /// entities of this [`EntityType`], even if the entity data doesn't list
+    /// them as parents. E.g., with `"parentAttributes": ["account"]`, every
+    /// `Photo` is in its `account`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parent_attributes: Vec<SmolStr>,
+    /// Attributes whose values are computed when loading entity data, by
+    /// Cedar expressions over the other attributes of the entity, which are
+    /// the `context` of the expressions. E.g., with
+    /// `"computedAttributes": { "isAdult": "context.age >= 18" }`, every
+    /// entity of this [`EntityType`] has an `isAdult` attribute. Computed
+    /// attributes must also be declared in the `shape`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed_attributes: BTreeMap<SmolStr, SmolStr>,
     /// Description of the attributes for entities of this [`EntityType`].
     #[serde(default)]
     #[serde(skip_serializing_if = "AttributesOrContext::is_empty_record")]
     pub shape: AttributesOrContext<N>,
+    /// Annotations of this [`EntityType`]. The `doc` annotation holds the
+    /// doc comment of the declaration in the Cedar schema syntax.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<SmolStr, SmolStr>,
 }
 
 impl EntityType<RawName> {
@@ -408,7 +446,13 @@ impl EntityType<RawName> {
                 .into_iter()
                 .map(|rname| rname.conditionally_qualify_with(ns, ReferenceType::Entity)) // Only entity, not common, here for now; see #1064
                 .collect(),
+            extends: self
+                .extends
+                .map(|rname| rname.conditionally_qualify_with(ns, ReferenceType::Entity)),
+            parent_attributes: self.parent_attributes,
+            computed_attributes: self.computed_attributes,
             shape: self.shape.conditionally_qualify_type_references(ns),
+            annotations: self.annotations,
         }
     }
 }
@@ -430,7 +474,14 @@ impl EntityType<ConditionalName> {
                 .into_iter()
                 .map(|cname| cname.resolve(all_defs))
                 .collect::<std::result::Result<_, _>>()?,
+            extends: self
+                .extends
+                .map(|cname| cname.resolve(all_defs))
+                .transpose()?,
+            parent_attributes: self.parent_attributes,
+            computed_attributes: self.computed_attributes,
             shape: self.shape.fully_qualify_type_references(all_defs)?,
+            annotations: self.annotations,
         })
     }
 }
@@ -537,6 +588,11 @@ pub struct ActionType<N> {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub member_of: Option<Vec<ActionEntityUID<N>>>,
+    /// Annotations of this action. The `doc` annotation holds the doc comment
+    /// of the declaration in the Cedar schema syntax.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<SmolStr, SmolStr>,
 }
 
 impl ActionType<RawName> {
@@ -555,6 +611,7 @@ impl ActionType<RawName> {
                     .map(|aeuid| aeuid.conditionally_qualify_type_references(ns))
                     .collect()
             }),
+            annotations: self.annotations,
         }
     }
 }
@@ -584,6 +641,7 @@ impl ActionType<ConditionalName> {
                         .collect::<std::result::Result<_, ActionNotDefinedError>>()
                 })
                 .transpose()?,
+            annotations: self.annotations,
         })
     }
 }
@@ -905,6 +963,9 @@ impl<N> Type<N> {
                     Box::new(it.chain(tys))
                 }),
             Type::Type(TypeVariant::Set { element }) => element.common_type_references(),
+            Type::Type(TypeVariant::Union { arms }) => {
+                Box::new(arms.iter().flat_map(|arm| arm.common_type_references()))
+            }
             Type::Type(TypeVariant::EntityOrCommon { type_name }) => {
                 Box::new(std::iter::once(type_name))
             }
@@ -922,6 +983,14 @@ impl<N> Type<N> {
         match self {
             Self::Type(TypeVariant::Extension { .. }) => Some(true),
             Self::Type(TypeVariant::Set { element }) => element.is_extension(),
+            Self::Type(TypeVariant::Union { arms }) => {
+                arms.iter()
+                    .try_fold(false, |a, arm| match arm.is_extension() {
+                        Some(true) => Some(true),
+                        Some(false) => Some(a),
+                        None => None,
+                    })
+            }
             Self::Type(TypeVariant::Record(RecordType { attributes, .. })) => attributes
                 .values()
                 .try_fold(false, |a, e| match e.ty.is_extension() {
@@ -1007,6 +1076,7 @@ enum TypeFields {
     Attributes,
     AdditionalAttributes,
     Name,
+    Arms,
 }
 
 // This macro is used to avoid duplicating the fields names when calling
@@ -1028,6 +1098,9 @@ macro_rules! type_field_name {
     (Name) => {
         "name"
     };
+    (Arms) => {
+        "arms"
+    };
 }
 
 impl TypeFields {
@@ -1038,6 +1111,7 @@ impl TypeFields {
             TypeFields::Attributes => type_field_name!(Attributes),
             TypeFields::AdditionalAttributes => type_field_name!(AdditionalAttributes),
             TypeFields::Name => type_field_name!(Name),
+            TypeFields::Arms => type_field_name!(Arms),
         }
     }
 }
@@ -1067,7 +1141,9 @@ impl<'de, N: Deserialize<'de> + From<RawName>> Visitor<'de> for TypeVisitor<N> {
     where
         M: MapAccess<'de>,
     {
-        use TypeFields::{AdditionalAttributes, Attributes, Element, Name, Type as TypeField};
+        use TypeFields::{
+            AdditionalAttributes, Arms, Attributes, Element, Name, Type as TypeField,
+        };
 
         // We keep field values wrapped in a `Result` initially so that we do
         // not report errors due the contents of a field when the field is not
@@ -1079,6 +1155,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> Visitor<'de> for TypeVisitor<N> {
         let mut attributes: Option<std::result::Result<AttributesTypeMap, M::Error>> = None;
         let mut additional_attributes: Option<std::result::Result<bool, M::Error>> = None;
         let mut name: Option<std::result::Result<SmolStr, M::Error>> = None;
+        let mut arms: Option<std::result::Result<Vec<Type<N>>, M::Error>> = None;
 
         // Gather all the fields in the object. Any fields that are not one of
         // the possible fields for some schema type will have been reported by
@@ -1117,10 +1194,23 @@ impl<'de, N: Deserialize<'de> + From<RawName>> Visitor<'de> for TypeVisitor<N> {
                     }
                     name = Some(map.next_value());
                 }
+                Arms => {
+                    if arms.is_some() {
+                        return Err(serde::de::Error::duplicate_field(Arms.as_str()));
+                    }
+                    arms = Some(map.next_value());
+                }
             }
         }
 
-        Self::build_schema_type::<M>(type_name, element, attributes, additional_attributes, name)
+        Self::build_schema_type::<M>(
+            type_name,
+            element,
+            attributes,
+            additional_attributes,
+            name,
+            arms,
+        )
     }
 }
 
@@ -1135,11 +1225,14 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
         attributes: Option<std::result::Result<AttributesTypeMap, M::Error>>,
         additional_attributes: Option<std::result::Result<bool, M::Error>>,
         name: Option<std::result::Result<SmolStr, M::Error>>,
+        arms: Option<std::result::Result<Vec<Type<N>>, M::Error>>,
     ) -> std::result::Result<Type<N>, M::Error>
     where
         M: MapAccess<'de>,
     {
-        use TypeFields::{AdditionalAttributes, Attributes, Element, Name, Type as TypeField};
+        use TypeFields::{
+            AdditionalAttributes, Arms, Attributes, Element, Name, Type as TypeField,
+        };
         // Fields that remain to be parsed
         let mut remaining_fields = [
             (TypeField, type_name.is_some()),
@@ -1147,6 +1240,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
             (Attributes, attributes.is_some()),
             (AdditionalAttributes, additional_attributes.is_some()),
             (Name, name.is_some()),
+            (Arms, arms.is_some()),
         ]
         .into_iter()
         .filter(|(_, present)| *present)
@@ -1170,7 +1264,10 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                     Ok(())
                 };
                 let error_if_any_fields = || -> std::result::Result<(), M::Error> {
-                    error_if_fields(&[Element, Attributes, AdditionalAttributes, Name], &[])
+                    error_if_fields(
+                        &[Element, Attributes, AdditionalAttributes, Name, Arms],
+                        &[],
+                    )
                 };
                 match s.as_str() {
                     "String" => {
@@ -1185,9 +1282,13 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                         error_if_any_fields()?;
                         Ok(Type::Type(TypeVariant::Boolean))
                     }
+                    "Null" => {
+                        error_if_any_fields()?;
+                        Ok(Type::Type(TypeVariant::Null))
+                    }
                     "Set" => {
                         error_if_fields(
-                            &[Attributes, AdditionalAttributes, Name],
+                            &[Attributes, AdditionalAttributes, Name, Arms],
                             &[type_field_name!(Element)],
                         )?;
 
@@ -1200,7 +1301,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                     }
                     "Record" => {
                         error_if_fields(
-                            &[Element, Name],
+                            &[Element, Name, Arms],
                             &[
                                 type_field_name!(Attributes),
                                 type_field_name!(AdditionalAttributes),
@@ -1214,15 +1315,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                                 attributes: attributes?
                                     .0
                                     .into_iter()
-                                    .map(|(k, TypeOfAttribute { ty, required })| {
-                                        (
-                                            k,
-                                            TypeOfAttribute {
-                                                ty: ty.into_n(),
-                                                required,
-                                            },
-                                        )
-                                    })
+                                    .map(|(k, attr)| (k, attr.into_n()))
                                     .collect(),
                                 additional_attributes: additional_attributes?,
                             })))
@@ -1232,7 +1325,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                     }
                     "Entity" => {
                         error_if_fields(
-                            &[Element, Attributes, AdditionalAttributes],
+                            &[Element, Attributes, AdditionalAttributes, Arms],
                             &[type_field_name!(Name)],
                         )?;
                         match name {
@@ -1253,7 +1346,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                     }
                     "EntityOrCommon" => {
                         error_if_fields(
-                            &[Element, Attributes, AdditionalAttributes],
+                            &[Element, Attributes, AdditionalAttributes, Arms],
                             &[type_field_name!(Name)],
                         )?;
                         match name {
@@ -1274,7 +1367,7 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                     }
                     "Extension" => {
                         error_if_fields(
-                            &[Element, Attributes, AdditionalAttributes],
+                            &[Element, Attributes, AdditionalAttributes, Arms],
                             &[type_field_name!(Name)],
                         )?;
 
@@ -1294,6 +1387,23 @@ impl<'de, N: Deserialize<'de> + From<RawName>> TypeVisitor<N> {
                             None => Err(serde::de::Error::missing_field(Name.as_str())),
                         }
                     }
+                    "Union" => {
+                        error_if_fields(
+                            &[Element, Attributes, AdditionalAttributes, Name],
+                            &[type_field_name!(Arms)],
+                        )?;
+
+                        match arms {
+                            Some(arms) => match arms? {
+                                arms if arms.is_empty() => Err(serde::de::Error::invalid_length(
+                                    0,
+                                    &"at least one arm in a union type",
+                                )),
+                                arms => Ok(Type::Type(TypeVariant::Union { arms })),
+                            },
+                            None => Err(serde::de::Error::missing_field(Arms.as_str())),
+                        }
+                    }
                     type_name => {
                         error_if_any_fields()?;
                         Ok(Type::CommonTypeRef {
@@ -1412,6 +1522,9 @@ pub enum TypeVariant<N> {
     Long,
     /// Boolean
     Boolean,
+    /// The type of `null`, usually used as an arm of a
+    /// [`TypeVariant::Union`] to make a type nullable
+    Null,
     /// Set
     Set {
         /// Element type
@@ -1457,6 +1570,13 @@ pub enum TypeVariant<N> {
         /// Name of the extension type
         name: UnreservedId,
     },
+    /// Union of types, e.g., `String | Long`. Arms which resolve to `Null`
+    /// make the type nullable; of the other arms, either there must be only
+    /// one, or each must resolve to a primitive type.
+    Union {
+        /// Types of which a value may have any one
+        arms: Vec<Type<N>>,
+    },
 }
 
 impl TypeVariant<RawName> {
@@ -1467,6 +1587,7 @@ impl TypeVariant<RawName> {
     ) -> TypeVariant<ConditionalName> {
         match self {
             Self::Boolean => TypeVariant::Boolean,
+            Self::Null => TypeVariant::Null,
             Self::Long => TypeVariant::Long,
             Self::String => TypeVariant::String,
             Self::Extension { name } => TypeVariant::Extension { name },
@@ -1479,21 +1600,21 @@ impl TypeVariant<RawName> {
             Self::Set { element } => TypeVariant::Set {
                 element: Box::new(element.conditionally_qualify_type_references(ns)),
             },
+            Self::Union { arms } => TypeVariant::Union {
+                arms: arms
+                    .into_iter()
+                    .map(|arm| arm.conditionally_qualify_type_references(ns))
+                    .collect(),
+            },
             Self::Record(RecordType {
                 attributes,
                 additional_attributes,
             }) => TypeVariant::Record(RecordType {
-                attributes: BTreeMap::from_iter(attributes.into_iter().map(
-                    |(attr, TypeOfAttribute { ty, required })| {
-                        (
-                            attr,
-                            TypeOfAttribute {
-                                ty: ty.conditionally_qualify_type_references(ns),
-                                required,
-                            },
-                        )
-                    },
-                )),
+                attributes: BTreeMap::from_iter(
+                    attributes
+                        .into_iter()
+                        .map(|(attr, ty)| (attr, ty.conditionally_qualify_type_references(ns))),
+                ),
                 additional_attributes,
             }),
         }
@@ -1502,6 +1623,7 @@ impl TypeVariant<RawName> {
     fn into_n<N: From<RawName>>(self) -> TypeVariant<N> {
         match self {
             Self::Boolean => TypeVariant::Boolean,
+            Self::Null => TypeVariant::Null,
             Self::Long => TypeVariant::Long,
             Self::String => TypeVariant::String,
             Self::Entity { name } => TypeVariant::Entity { name: name.into() },
@@ -1521,6 +1643,9 @@ impl TypeVariant<RawName> {
             Self::Set { element } => TypeVariant::Set {
                 element: Box::new(element.into_n()),
             },
+            Self::Union { arms } => TypeVariant::Union {
+                arms: arms.into_iter().map(Type::into_n).collect(),
+            },
             Self::Extension { name } => TypeVariant::Extension { name },
         }
     }
@@ -1539,6 +1664,7 @@ impl TypeVariant<ConditionalName> {
     ) -> std::result::Result<TypeVariant<InternalName>, TypeNotDefinedError> {
         match self {
             Self::Boolean => Ok(TypeVariant::Boolean),
+            Self::Null => Ok(TypeVariant::Null),
             Self::Long => Ok(TypeVariant::Long),
             Self::String => Ok(TypeVariant::String),
             Self::Extension { name } => Ok(TypeVariant::Extension { name }),
@@ -1551,21 +1677,19 @@ impl TypeVariant<ConditionalName> {
             Self::Set { element } => Ok(TypeVariant::Set {
                 element: Box::new(element.fully_qualify_type_references(all_defs)?),
             }),
+            Self::Union { arms } => Ok(TypeVariant::Union {
+                arms: arms
+                    .into_iter()
+                    .map(|arm| arm.fully_qualify_type_references(all_defs))
+                    .collect::<std::result::Result<_, TypeNotDefinedError>>()?,
+            }),
             Self::Record(RecordType {
                 attributes,
                 additional_attributes,
             }) => Ok(TypeVariant::Record(RecordType {
                 attributes: attributes
                     .into_iter()
-                    .map(|(attr, TypeOfAttribute { ty, required })| {
-                        Ok((
-                            attr,
-                            TypeOfAttribute {
-                                ty: ty.fully_qualify_type_references(all_defs)?,
-                                required,
-                            },
-                        ))
-                    })
+                    .map(|(attr, ty)| Ok((attr, ty.fully_qualify_type_references(all_defs)?)))
                     .collect::<std::result::Result<BTreeMap<_, _>, TypeNotDefinedError>>()?,
                 additional_attributes,
             })),
@@ -1654,6 +1778,11 @@ pub struct TypeOfAttribute<N> {
     #[serde(default = "record_attribute_required_default")]
     #[serde(skip_serializing_if = "is_record_attribute_required_default")]
     pub required: bool,
+    /// Annotations of the attribute, e.g., `deprecated` (see
+    /// [`crate::Deprecation`])
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<SmolStr, SmolStr>,
 }
 
 impl TypeOfAttribute<RawName> {
@@ -1661,6 +1790,7 @@ impl TypeOfAttribute<RawName> {
         TypeOfAttribute {
             ty: self.ty.into_n(),
             required: self.required,
+            annotations: self.annotations,
         }
     }
 
@@ -1672,6 +1802,7 @@ impl TypeOfAttribute<RawName> {
         TypeOfAttribute {
             ty: self.ty.conditionally_qualify_type_references(ns),
             required: self.required,
+            annotations: self.annotations,
         }
     }
 }
@@ -1690,6 +1821,7 @@ impl TypeOfAttribute<ConditionalName> {
         Ok(TypeOfAttribute {
             ty: self.ty.fully_qualify_type_references(all_defs)?,
             required: self.required,
+            annotations: self.annotations,
         })
     }
 }
@@ -1700,6 +1832,7 @@ impl<'a> arbitrary::Arbitrary<'a> for TypeOfAttribute<RawName> {
         Ok(Self {
             ty: u.arbitrary()?,
             required: u.arbitrary()?,
+            annotations: BTreeMap::new(),
         })
     }
 
@@ -2481,6 +2614,10 @@ mod test_json_roundtrip {
                     "a".parse().unwrap(),
                     EntityType {
                         member_of_types: vec!["a".parse().unwrap()],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: AttributesOrContext(Type::Type(TypeVariant::Record(RecordType {
                             attributes: BTreeMap::new(),
                             additional_attributes: false,
@@ -2502,6 +2639,7 @@ mod test_json_roundtrip {
                             ))),
                         }),
                         member_of: None,
+                        annotations: BTreeMap::new(),
                     },
                 )]),
             },
@@ -2520,6 +2658,10 @@ mod test_json_roundtrip {
                         "a".parse().unwrap(),
                         EntityType {
                             member_of_types: vec!["a".parse().unwrap()],
+                            extends: None,
+                            parent_attributes: vec![],
+                            computed_attributes: BTreeMap::new(),
+                            annotations: BTreeMap::new(),
                             shape: AttributesOrContext(Type::Type(TypeVariant::Record(
                                 RecordType {
                                     attributes: BTreeMap::new(),
@@ -2551,6 +2693,7 @@ mod test_json_roundtrip {
                                 ))),
                             }),
                             member_of: None,
+                            annotations: BTreeMap::new(),
                         },
                     )]),
                 },
@@ -31,10 +31,16 @@
 #![allow(clippy::result_large_err, clippy::large_enum_variant)] // see #878
 #![cfg_attr(feature = "wasm", allow(non_snake_case))]
 
-use cedar_policy_core::ast::{Policy, PolicySet, Template};
+use cedar_policy_core::ast::{
+    EntityType, EntityUID, Expr, Policy, PolicyID, PolicySet, SlotEnv, SlotId, Template,
+};
+use cedar_policy_core::entities::Entities;
 use serde::Serialize;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::Instant;
 
+pub mod entity_generator;
 #[cfg(feature = "entity-manifest")]
 pub mod entity_manifest;
 mod err;
@@ -43,20 +49,34 @@ mod coreschema;
 pub use coreschema::*;
 mod diagnostics;
 pub use diagnostics::*;
+mod entity_data;
 mod expr_iterator;
 mod extension_schema;
 mod extensions;
+mod feature_policy;
+pub use feature_policy::FeaturePolicy;
 mod fuzzy_match;
+mod identical_policies;
+mod incremental;
+mod link_slots;
+pub use incremental::IncrementalValidator;
+mod profile;
+pub use profile::{PolicyProfile, RequestEnvProfile, ValidationProfile};
 mod rbac;
 mod schema;
 pub use schema::*;
 pub mod json_schema;
 mod str_checks;
+mod typed_policy;
+pub use typed_policy::ValidatedPolicy;
+mod unused_schema;
 pub use str_checks::confusable_string_checks;
 pub mod cedar_schema;
 pub mod typecheck;
 use typecheck::Typechecker;
+use types::{RequestEnv, Type};
 pub mod types;
+pub mod typescript;
 
 /// Used to select how a policy will be validated.
 #[derive(Default, Eq, PartialEq, Copy, Clone, Debug, Serialize)]
@@ -70,6 +90,13 @@ pub enum ValidationMode {
     /// providing no formal guarantees
     #[cfg(feature = "partial-validate")]
     Partial,
+    /// Strict validation, except that entity types which are not declared in
+    /// the schema are treated as opaque rather than reported as unrecognized:
+    /// they may have any attributes and may be members of any entity. This
+    /// allows adopting a schema incrementally, but provides no formal
+    /// guarantees for policies using undeclared entity types.
+    #[cfg(feature = "partial-validate")]
+    OpaqueEntityTypes,
 }
 
 impl ValidationMode {
@@ -80,6 +107,18 @@ impl ValidationMode {
             ValidationMode::Strict | ValidationMode::Permissive => false,
             #[cfg(feature = "partial-validate")]
             ValidationMode::Partial => true,
+            #[cfg(feature = "partial-validate")]
+            ValidationMode::OpaqueEntityTypes => false,
+        }
+    }
+
+    /// Does this mode accept entity types which are not declared in the
+    /// schema, treating them as opaque.
+    fn allows_undeclared_entity_types(self) -> bool {
+        match self {
+            ValidationMode::Strict | ValidationMode::Permissive => false,
+            #[cfg(feature = "partial-validate")]
+            ValidationMode::Partial | ValidationMode::OpaqueEntityTypes => true,
         }
     }
 
@@ -90,6 +129,8 @@ impl ValidationMode {
             ValidationMode::Permissive => false,
             #[cfg(feature = "partial-validate")]
             ValidationMode::Partial => false,
+            #[cfg(feature = "partial-validate")]
+            ValidationMode::OpaqueEntityTypes => true,
         }
     }
 }
@@ -99,12 +140,108 @@ impl ValidationMode {
 #[derive(Debug)]
 pub struct Validator {
     schema: ValidatorSchema,
+    max_suggestion_distance: Option<usize>,
+    max_record_width: Option<usize>,
+    max_set_depth: Option<usize>,
+    feature_policy: FeaturePolicy,
 }
 
 impl Validator {
     /// Construct a new Validator from a schema file.
     pub fn new(schema: ValidatorSchema) -> Validator {
-        Self { schema }
+        Self {
+            schema,
+            max_suggestion_distance: None,
+            max_record_width: None,
+            max_set_depth: None,
+            feature_policy: FeaturePolicy::default(),
+        }
+    }
+
+    /// Only suggest corrections for misspelled attributes, entity types, and
+    /// actions in validation errors when they are within `max_distance`
+    /// edits of the misspelling. By default, the closest declared name is
+    /// suggested however far it is, except for names found outside the
+    /// accessed type, which are suggested only if they are within one edit
+    /// per three characters.
+    #[must_use]
+    pub fn with_max_suggestion_distance(mut self, max_distance: usize) -> Self {
+        self.max_suggestion_distance = Some(max_distance);
+        self
+    }
+
+    /// Report a [`ValidationError::TypeTooLarge`] error, rather than
+    /// computing the least upper bound, for types with a record of more than
+    /// `max_width` attributes, e.g., the types of the elements of a set
+    /// literal or of the branches of an `if`. This bounds the time and memory
+    /// used to validate adversarial or machine-generated policies. By
+    /// default, there is no limit.
+    #[must_use]
+    pub fn with_max_record_width(mut self, max_width: usize) -> Self {
+        self.max_record_width = Some(max_width);
+        self
+    }
+
+    /// Like [`Validator::with_max_record_width()`], for types with more than
+    /// `max_depth` set types nested in one another.
+    #[must_use]
+    pub fn with_max_set_depth(mut self, max_depth: usize) -> Self {
+        self.max_set_depth = Some(max_depth);
+        self
+    }
+
+    /// Report a [`ValidationError::ForbiddenFeature`] error for each use of a
+    /// language feature which `feature_policy` forbids. By default, all
+    /// features are allowed.
+    #[must_use]
+    pub fn with_feature_policy(mut self, feature_policy: FeaturePolicy) -> Self {
+        self.feature_policy = feature_policy;
+        self
+    }
+
+    /// The schema this validator validates against
+    pub fn schema(&self) -> &ValidatorSchema {
+        &self.schema
+    }
+
+    /// Parse `expr_src` as a standalone Cedar expression and typecheck it in
+    /// `request_env`, returning its inferred type. This allows tools to check
+    /// expressions written in Cedar syntax outside of a policy, e.g., attribute
+    /// mapping rules. Type errors are reported against the placeholder policy
+    /// id `expression`.
+    ///
+    /// ```
+    /// # use cedar_policy_validator::{types::RequestEnv, Validator, ValidationMode, ValidatorSchema};
+    /// # use cedar_policy_core::{ast::{EntityType, EntityUID}, extensions::Extensions};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = ValidatorSchema::from_cedarschema_str(
+    ///     "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };",
+    ///     Extensions::all_available(),
+    /// ).unwrap();
+    /// let validator = Validator::new(schema);
+    /// let user = EntityType::from_str("User").unwrap();
+    /// let view = EntityUID::from_str(r#"Action::"view""#).unwrap();
+    /// let env = RequestEnv::DeclaredAction {
+    ///     principal: &user,
+    ///     action: &view,
+    ///     resource: &user,
+    ///     context: validator.schema().get_action_id(&view).unwrap().context_type(),
+    ///     principal_slot: None,
+    ///     resource_slot: None,
+    /// };
+    /// let ty = validator.typecheck_expression("principal.age + 1", &env, ValidationMode::Strict).unwrap();
+    /// assert_eq!(ty.to_string(), "Long");
+    /// assert!(validator.typecheck_expression("principal.name", &env, ValidationMode::Strict).is_err());
+    /// ```
+    pub fn typecheck_expression(
+        &self,
+        expr_src: &str,
+        request_env: &RequestEnv<'_>,
+        mode: ValidationMode,
+    ) -> std::result::Result<Type, TypecheckExpressionError> {
+        let expr: Expr = expr_src.parse()?;
+        Typechecker::new(&self.schema, mode, PolicyID::from_string("expression"))
+            .typecheck_expression(&expr, request_env)
     }
 
     /// Validate all templates, links, and static policies in a policy set.
@@ -123,8 +260,232 @@ impl Validator {
         ValidationResult::new(
             template_and_static_policy_errs.chain(link_errs),
             template_and_static_policy_warnings
-                .chain(confusable_string_checks(policies.all_templates())),
+                .chain(confusable_string_checks(policies.all_templates()))
+                .chain(identical_policies::identical_permit_and_forbid(policies)),
         )
+        .with_policies_by_action(self.policies_by_action(policies))
+    }
+
+    /// Validate a single template-linked policy, checking that the values of
+    /// its slots are declared entity types which its actions apply to. When
+    /// its template was already validated, this is all [`Validator::validate()`]
+    /// would check for the link. Returns no errors for static policies.
+    pub fn validate_link(&self, p: &Policy, mode: ValidationMode) -> Vec<ValidationError> {
+        self.validate_slots(p, mode).into_iter().flatten().collect()
+    }
+
+    /// Like [`Validator::validate()`], but stop validating policies once
+    /// `max_errors` errors have been found, and report at most that many
+    /// errors. This bounds the work done for policy sets with many invalid
+    /// policies. [`ValidationResult::is_truncated()`] reports whether any
+    /// errors were left out.
+    pub fn validate_with_max_errors(
+        &self,
+        policies: &PolicySet,
+        mode: ValidationMode,
+        max_errors: usize,
+    ) -> ValidationResult {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        // Once the cap is reached, keep validating only until an error would
+        // be dropped, so that the result is not marked as truncated when the
+        // remaining policies are valid.
+        let mut keep = |errs: &mut dyn Iterator<Item = ValidationError>| {
+            for e in errs {
+                if errors.len() == max_errors {
+                    return false;
+                }
+                errors.push(e);
+            }
+            true
+        };
+        let mut truncated = false;
+        for p in policies.all_templates() {
+            let (mut errs, warns) = self.validate_policy(p, mode);
+            warnings.extend(warns);
+            if !keep(&mut errs) {
+                truncated = true;
+                break;
+            }
+        }
+        if !truncated {
+            for mut link_errs in policies
+                .policies()
+                .filter_map(|p| self.validate_slots(p, mode))
+            {
+                if !keep(&mut link_errs) {
+                    truncated = true;
+                    break;
+                }
+            }
+        }
+        warnings.extend(confusable_string_checks(policies.all_templates()));
+        warnings.extend(identical_policies::identical_permit_and_forbid(policies));
+        let result = if truncated {
+            ValidationResult::new_truncated(errors, warnings)
+        } else {
+            ValidationResult::new(errors, warnings)
+        };
+        result.with_policies_by_action(self.policies_by_action(policies))
+    }
+
+    /// Like [`Validator::validate()`], but also report the time spent
+    /// validating each policy and template, broken down by request
+    /// environment, together with the number of least upper bounds computed.
+    /// This is intended for diagnosing slow validation of large schemas or
+    /// policy sets; timing each request environment makes validation slightly
+    /// slower.
+    pub fn validate_with_profile(
+        &self,
+        policies: &PolicySet,
+        mode: ValidationMode,
+    ) -> (ValidationResult, ValidationProfile) {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut profile = ValidationProfile::default();
+        for p in policies.all_templates() {
+            let request_envs = RefCell::new(Vec::new());
+            let start = Instant::now();
+            let lubs = profile::lub_computations();
+            let (errs, warns) = self.validate_policy_with_profile(p, mode, Some(&request_envs));
+            errors.extend(errs);
+            warnings.extend(warns);
+            profile.push(PolicyProfile::new(
+                p.id().clone(),
+                start.elapsed(),
+                profile::lub_computations().wrapping_sub(lubs),
+                request_envs.into_inner(),
+            ));
+        }
+        errors.extend(
+            policies
+                .policies()
+                .filter_map(|p| self.validate_slots(p, mode))
+                .flatten(),
+        );
+        warnings.extend(confusable_string_checks(policies.all_templates()));
+        warnings.extend(identical_policies::identical_permit_and_forbid(policies));
+        (
+            ValidationResult::new(errors, warnings)
+                .with_policies_by_action(self.policies_by_action(policies)),
+            profile,
+        )
+    }
+
+    /// The ids of the static policies and templates of `policies` which may
+    /// apply to each action of the schema
+    fn policies_by_action(&self, policies: &PolicySet) -> BTreeMap<EntityUID, Vec<PolicyID>> {
+        let mut by_action: BTreeMap<EntityUID, Vec<PolicyID>> = self
+            .schema
+            .actions()
+            .map(|action| (action.clone(), Vec::new()))
+            .collect();
+        for t in policies.all_templates() {
+            for action in self.template_actions(t).collect::<BTreeSet<_>>() {
+                if let Some(ids) = by_action.get_mut(action) {
+                    ids.push(t.id().clone());
+                }
+            }
+        }
+        for ids in by_action.values_mut() {
+            ids.sort();
+        }
+        by_action
+    }
+
+    /// Report the entity types, entity attributes, and actions which are
+    /// declared in the schema but never referenced by any policy in
+    /// `policies`, as warnings. This check is not part of
+    /// [`Validator::validate()`] because it is only meaningful for the
+    /// complete set of policies using a schema.
+    ///
+    /// Warnings point at the declaration in the schema when the schema was
+    /// parsed from the Cedar schema syntax.
+    pub fn unused_schema_elements(
+        &self,
+        policies: &PolicySet,
+    ) -> impl Iterator<Item = ValidationWarning> {
+        unused_schema::unused_schema_elements(&self.schema, policies).into_iter()
+    }
+
+    /// Cross-check the entity snapshot `entities` against `policies`,
+    /// reporting as warnings the expressions in the policies which error when
+    /// evaluated with an entity of the snapshot, e.g., because an attribute
+    /// value is not a valid argument to an extension function, or is so large
+    /// that arithmetic overflows. This catches data issues before the
+    /// policies and entities are deployed. Extension function calls and
+    /// arithmetic operations whose only variable is `principal` or `resource`
+    /// are checked for each entity of a type the policy applies to.
+    pub fn entity_data_errors(
+        &self,
+        policies: &PolicySet,
+        entities: &Entities,
+    ) -> impl Iterator<Item = ValidationWarning> {
+        entity_data::entity_data_errors(&self.schema, policies, entities).into_iter()
+    }
+
+    /// The entity types each slot of the template `t` may be linked to such
+    /// that a link of `t` applies to some request environment of the schema.
+    /// A slot has no entity types if no link of `t` ever applies, and `None`
+    /// if they are unknown, which may happen in partial validation.
+    pub fn expected_slot_types(
+        &self,
+        t: &Template,
+        mode: ValidationMode,
+    ) -> HashMap<SlotId, Option<BTreeSet<EntityType>>> {
+        link_slots::expected_slot_types(&self.schema, t, mode)
+    }
+
+    /// Check the values `values` which a link of the template `t` would bind
+    /// its slots to, before linking. Reports a
+    /// [`ValidationError::MissingSlotBinding`] for each slot of `t` without a
+    /// value, a [`ValidationError::UnusedSlotBinding`] for each value of a
+    /// slot `t` doesn't have, and a [`ValidationError::UnexpectedSlotType`]
+    /// for each value of an entity type for which `t` never applies. The
+    /// errors list the entity types expected for the slot, as returned by
+    /// [`Validator::expected_slot_types()`].
+    pub fn validate_link_bindings(
+        &self,
+        t: &Template,
+        values: &SlotEnv,
+        mode: ValidationMode,
+    ) -> impl Iterator<Item = ValidationError> {
+        link_slots::link_binding_errors(&self.schema, t, values, mode).into_iter()
+    }
+
+    /// Validate `policies` and, if validation passes, return each policy and
+    /// template annotated with the types inferred by the typechecker. This lets
+    /// tools such as IDEs or compilers of residual policies query types
+    /// without reimplementing the typechecker. Returns the validation result
+    /// if validation fails.
+    pub fn typed_policies(
+        &self,
+        policies: &PolicySet,
+        mode: ValidationMode,
+    ) -> std::result::Result<Vec<ValidatedPolicy>, ValidationResult> {
+        let result = self.validate(policies, mode);
+        if !result.validation_passed() {
+            return Err(result);
+        }
+        Ok(policies
+            .all_templates()
+            .map(|t| {
+                let typechecker = Typechecker::new(&self.schema, mode, t.id().clone());
+                let typed_conditions = typechecker
+                    .typecheck_by_request_env(t)
+                    .into_iter()
+                    .filter_map(|(env, check)| match check {
+                        typecheck::PolicyCheck::Success(expr) => {
+                            Some((env.to_request_type(), expr))
+                        }
+                        typecheck::PolicyCheck::Irrelevant(_) | typecheck::PolicyCheck::Fail(_) => {
+                            None
+                        }
+                    })
+                    .collect();
+                ValidatedPolicy::new(t.id().clone(), typed_conditions)
+            })
+            .collect())
     }
 
     /// Run all validations against a single static policy or template (note
@@ -137,6 +498,20 @@ impl Validator {
     ) -> (
         impl Iterator<Item = ValidationError> + 'a,
         impl Iterator<Item = ValidationWarning> + 'a,
+    ) {
+        self.validate_policy_with_profile(p, mode, None)
+    }
+
+    /// Like [`Validator::validate_policy()`], recording the time spent
+    /// typechecking in each request environment to `profile`, if provided.
+    fn validate_policy_with_profile<'a>(
+        &'a self,
+        p: &'a Template,
+        mode: ValidationMode,
+        profile: Option<&RefCell<Vec<RequestEnvProfile>>>,
+    ) -> (
+        impl Iterator<Item = ValidationError> + 'a,
+        impl Iterator<Item = ValidationWarning> + 'a,
     ) {
         let validation_errors = if mode.is_partial() {
             // We skip `validate_entity_types`, `validate_action_ids`, and
@@ -145,20 +520,59 @@ impl Validator {
             // actions, so we can never claim that one doesn't exist.
             None
         } else {
+            // Undeclared entity types are expected when they are treated as
+            // opaque, but actions must still be declared.
+            let entity_type_errors = if mode.allows_undeclared_entity_types() {
+                None
+            } else {
+                Some(self.validate_entity_types(p))
+            };
+            let action_id_errors = self.validate_action_ids(p).collect::<Vec<_>>();
+            // An undeclared action applies to nothing, which is already
+            // reported when undeclared entity types are opaque. In permissive
+            // mode, this is reported with a warning below instead.
+            let action_application_errors = if !mode.is_strict()
+                || (mode.allows_undeclared_entity_types() && !action_id_errors.is_empty())
+            {
+                None
+            } else {
+                // We could usefully update this pass to apply to partial
+                // schema if it only failed when there is a known action
+                // applied to known principal/resource entity types that
+                // are not in its `appliesTo`.
+                Some(self.validate_template_action_application(p))
+            };
             Some(
-                self.validate_entity_types(p)
-                    .chain(self.validate_action_ids(p))
-                    // We could usefully update this pass to apply to partial
-                    // schema if it only failed when there is a known action
-                    // applied to known principal/resource entity types that are
-                    // not in its `appliesTo`.
-                    .chain(self.validate_template_action_application(p)),
+                entity_type_errors
+                    .into_iter()
+                    .flatten()
+                    .chain(action_id_errors)
+                    .chain(action_application_errors.into_iter().flatten()),
             )
         }
         .into_iter()
         .flatten();
-        let (type_errors, warnings) = self.typecheck_policy(p, mode);
-        (validation_errors.chain(type_errors), warnings)
+        // In permissive mode, policies which apply to no request environment
+        // are reported with a warning instead.
+        let not_applicable_warning = if mode.is_partial() || mode.is_strict() {
+            None
+        } else {
+            self.not_applicable_template_warning(p)
+        };
+        let forbidden_features = self.feature_policy.forbidden_features(p);
+        let (type_errors, warnings) = self.typecheck_policy(p, mode, profile);
+        // A policy which applies to no request environment is also
+        // impossible, so we only report it once.
+        let not_applicable = not_applicable_warning.is_some();
+        let warnings = warnings.filter(move |w| {
+            !(not_applicable && matches!(w, ValidationWarning::ImpossiblePolicy(_)))
+        });
+        (
+            validation_errors
+                .chain(forbidden_features)
+                .chain(type_errors),
+            not_applicable_warning.into_iter().chain(warnings),
+        )
     }
 
     /// Run relevant validations against a single template-linked policy,
@@ -181,8 +595,15 @@ impl Validator {
         // For template-linked policies `Policy::principal_constraint()` and
         // `Policy::resource_constraint()` return a copy of the constraint with
         // the slot filled by the appropriate value.
+        let entity_type_errors = if mode.allows_undeclared_entity_types() {
+            None
+        } else {
+            Some(self.validate_entity_types_in_slots(p.id(), p.env()))
+        };
         Some(
-            self.validate_entity_types_in_slots(p.id(), p.env())
+            entity_type_errors
+                .into_iter()
+                .flatten()
                 .chain(self.validate_linked_action_application(p)),
         )
     }
@@ -196,11 +617,17 @@ impl Validator {
         &'a self,
         t: &'a Template,
         mode: ValidationMode,
+        profile: Option<&RefCell<Vec<RequestEnvProfile>>>,
     ) -> (
         impl Iterator<Item = ValidationError> + 'a,
         impl Iterator<Item = ValidationWarning> + 'a,
     ) {
-        let typecheck = Typechecker::new(&self.schema, mode, t.id().clone());
+        let mut typecheck = Typechecker::new(&self.schema, mode, t.id().clone())
+            .with_max_suggestion_distance(self.max_suggestion_distance)
+            .with_type_size_limits(self.max_record_width, self.max_set_depth);
+        if let Some(profile) = profile {
+            typecheck = typecheck.with_profile(profile);
+        }
         let mut type_errors = HashSet::new();
         let mut warnings = HashSet::new();
         typecheck.typecheck_policy(t, &mut type_errors, &mut warnings);
@@ -211,7 +638,10 @@ impl Validator {
 #[cfg(test)]
 mod test {
     use itertools::Itertools;
-    use std::{collections::HashMap, sync::Arc};
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+    };
 
     use crate::types::Type;
     use crate::Result;
@@ -234,6 +664,10 @@ mod test {
                     foo_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -241,6 +675,10 @@ mod test {
                     bar_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -254,6 +692,7 @@ mod test {
                         context: json_schema::AttributesOrContext::default(),
                     }),
                     member_of: None,
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -391,6 +830,11 @@ mod test {
         .expect("Linking failed!");
         let result = validator.validate(&set, ValidationMode::default());
         assert!(result.validation_passed());
+        let link1 = set.get(&PolicyID::from_string("link1")).unwrap();
+        assert_eq!(
+            validator.validate_link(link1, ValidationMode::default()),
+            Vec::new()
+        );
 
         // an invalid link results in an error
         let mut values = HashMap::new();
@@ -453,6 +897,11 @@ mod test {
             false,
         );
         assert!(result.validation_errors().contains(&invalid_action_err));
+        let link3 = set.get(&PolicyID::from_string("link3")).unwrap();
+        assert_eq!(
+            validator.validate_link(link3, ValidationMode::default()),
+            vec![invalid_action_err]
+        );
 
         Ok(())
     }
@@ -508,4 +957,534 @@ mod test {
             )]
         );
     }
+
+    #[test]
+    fn typecheck_expression() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User = { name: String }; action view appliesTo { principal: User, resource: User, context: { n: Long } };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let user: ast::EntityType = "User".parse().unwrap();
+        let view: ast::EntityUID = r#"Action::"view""#.parse().unwrap();
+        let env = RequestEnv::DeclaredAction {
+            principal: &user,
+            action: &view,
+            resource: &user,
+            context: validator
+                .schema()
+                .get_action_id(&view)
+                .unwrap()
+                .context_type(),
+            principal_slot: None,
+            resource_slot: None,
+        };
+        let check = |src| validator.typecheck_expression(src, &env, ValidationMode::Strict);
+
+        assert_eq!(check("context.n + 1").unwrap(), Type::primitive_long());
+        assert_eq!(
+            check("[principal.name, resource.name]").unwrap(),
+            Type::set(Type::primitive_string())
+        );
+        assert_eq!(
+            check("principal").unwrap(),
+            Type::named_entity_reference(user.clone())
+        );
+        cool_asserts::assert_matches!(
+            check("principal.age"),
+            Err(TypecheckExpressionError::Type { errors }) => {
+                assert_eq!(errors.len(), 1);
+            }
+        );
+        cool_asserts::assert_matches!(check("1 +"), Err(TypecheckExpressionError::Parse(_)));
+    }
+
+    #[test]
+    fn suggestions() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            namespace Acme {
+                type Address = { street: String, city: String };
+                entity User = { name: String, address: Address };
+                entity Doc = { title: String };
+                action view appliesTo { principal: User, resource: Doc };
+            }
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let suggestions = |validator: &Validator, cond: &str| {
+            let src = format!(
+                r#"permit(principal, action == Acme::Action::"view", resource) when {{ {cond} }};"#
+            );
+            let mut set = PolicySet::new();
+            set.add_static(parser::parse_policy(None, &src).unwrap())
+                .unwrap();
+            validator
+                .validate(&set, ValidationMode::Strict)
+                .validation_errors()
+                .map(|e| match e {
+                    ValidationError::UnsafeAttributeAccess(e) => e.suggestion.clone(),
+                    ValidationError::UnrecognizedEntityType(e) => e.suggested_entity_type.clone(),
+                    e => panic!("unexpected error {e:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let validator = Validator::new(schema.clone());
+        assert_eq!(
+            suggestions(&validator, "principal.nme == \"\""),
+            vec![Some("name".to_string())]
+        );
+        // the namespace is misspelled
+        assert_eq!(
+            suggestions(&validator, r#"principal in Acm::User::"alice""#),
+            vec![Some("Acme::User".to_string())]
+        );
+
+        let validator = Validator::new(schema).with_max_suggestion_distance(1);
+        assert_eq!(
+            suggestions(&validator, "principal.nme == \"\""),
+            vec![Some("name".to_string())]
+        );
+        // `city` is an attribute of the `Address` common type, not of `Doc`
+        assert_eq!(
+            suggestions(&validator, "resource.cty == \"\""),
+            vec![Some("city".to_string())]
+        );
+        assert_eq!(suggestions(&validator, "resource.xyzzy"), vec![None]);
+    }
+
+    #[test]
+    fn validate_with_max_errors() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let mut set = PolicySet::new();
+        for i in 0..5 {
+            set.add_static(
+                parser::parse_policy(
+                    Some(PolicyID::from_string(format!("p{i}"))),
+                    r#"permit(principal, action, resource) when { principal.missing };"#,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        }
+        let validator = Validator::new(schema);
+
+        let result = validator.validate(&set, ValidationMode::Strict);
+        assert_eq!(result.validation_errors().count(), 5);
+        assert!(!result.is_truncated());
+
+        let result = validator.validate_with_max_errors(&set, ValidationMode::Strict, 2);
+        assert_eq!(result.validation_errors().count(), 2);
+        assert!(result.is_truncated());
+
+        let result = validator.validate_with_max_errors(&set, ValidationMode::Strict, 5);
+        assert_eq!(result.validation_errors().count(), 5);
+        assert!(!result.is_truncated());
+
+        // reaching the cap does not truncate the result if no error is dropped
+        set.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("valid")),
+                r#"permit(principal, action, resource);"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let result = validator.validate_with_max_errors(&set, ValidationMode::Strict, 5);
+        assert_eq!(result.validation_errors().count(), 5);
+        assert!(!result.is_truncated());
+    }
+
+    #[test]
+    fn policies_by_action() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User;
+            entity Photo;
+            entity Album;
+            action read;
+            action view in read appliesTo { principal: User, resource: Photo };
+            action list in read appliesTo { principal: User, resource: Album };
+            action delete appliesTo { principal: User, resource: [Photo, Album] };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let mut set = PolicySet::new();
+        for (id, src) in [
+            ("any", "permit(principal, action, resource is Photo);"),
+            (
+                "read",
+                r#"permit(principal, action in Action::"read", resource);"#,
+            ),
+            (
+                "template",
+                r#"permit(principal == ?principal, action == Action::"delete", resource);"#,
+            ),
+        ] {
+            let t = parser::parse_policy_or_template(Some(PolicyID::from_string(id)), src).unwrap();
+            set.add_template(t).unwrap();
+        }
+        let result = Validator::new(schema).validate(&set, ValidationMode::Strict);
+        let by_action = result
+            .policies_by_action()
+            .iter()
+            .map(|(action, ids)| {
+                (
+                    action.to_string(),
+                    ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            by_action,
+            vec![
+                (
+                    r#"Action::"delete""#.to_string(),
+                    vec!["any".to_string(), "template".to_string()]
+                ),
+                (r#"Action::"list""#.to_string(), vec!["read".to_string()]),
+                (r#"Action::"read""#.to_string(), vec![]),
+                (
+                    r#"Action::"view""#.to_string(),
+                    vec!["any".to_string(), "read".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_applicable_policy_warning() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User;
+            entity Photo;
+            entity Album;
+            action viewPhoto appliesTo { principal: User, resource: Photo };
+            action viewAlbum appliesTo { principal: User, resource: Album };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let mut set = PolicySet::new();
+        set.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("p")),
+                r#"permit(principal, action == Action::"viewPhoto", resource is Album);"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let result = validator.validate(&set, ValidationMode::Strict);
+        cool_asserts::assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::InvalidActionApplication(_)]
+        );
+        assert!(!result
+            .validation_warnings()
+            .any(|w| matches!(w, ValidationWarning::NotApplicablePolicy(_))));
+
+        let result = validator.validate(&set, ValidationMode::Permissive);
+        assert!(result.validation_passed());
+        cool_asserts::assert_matches!(
+            result.validation_warnings().collect::<Vec<_>>().as_slice(),
+            [ValidationWarning::NotApplicablePolicy(w)] => {
+                assert_eq!(w.policy_id, PolicyID::from_string("p"));
+                assert_eq!(
+                    w.suggested_action,
+                    Some(r#"Action::"viewAlbum""#.parse().unwrap())
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn validate_with_type_size_limits() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let unlimited = Validator::new(schema.clone());
+        let validator = Validator::new(schema)
+            .with_max_record_width(2)
+            .with_max_set_depth(2);
+        let errors_with = |validator: &Validator, policy: &str| {
+            let mut set = PolicySet::new();
+            set.add_static(parser::parse_policy(None, policy).unwrap())
+                .unwrap();
+            validator
+                .validate(&set, ValidationMode::Strict)
+                .into_errors_and_warnings()
+                .0
+                .collect::<Vec<_>>()
+        };
+        let errors = |policy: &str| errors_with(&validator, policy);
+
+        assert_eq!(
+            errors(
+                r#"permit(principal, action, resource) when { [{a: 1, b: 2}, {a: 3, b: 4}].contains({a: 1, b: 2}) };"#
+            ),
+            vec![]
+        );
+        assert_eq!(
+            errors(r#"permit(principal, action, resource) when { [[1], [2]].contains([1]) };"#),
+            vec![]
+        );
+
+        let errs = errors(
+            r#"permit(principal, action, resource) when { [{a: 1, b: 2, c: 3}].contains({a: 1, b: 2, c: 3}) };"#,
+        );
+        cool_asserts::assert_matches!(
+            errs.as_slice(),
+            [ValidationError::TypeTooLarge(e)] => {
+                assert_eq!(e.limit, validation_errors::TypeSizeLimit::RecordWidth(2));
+                assert_eq!(e.context, validation_errors::LubContext::Set);
+            }
+        );
+
+        let errs = errors(r#"permit(principal, action, resource) when { [[[1]]] == [[[1]]] };"#);
+        cool_asserts::assert_matches!(
+            errs.as_slice(),
+            [ValidationError::TypeTooLarge(e)] => {
+                assert_eq!(e.limit, validation_errors::TypeSizeLimit::SetDepth(2));
+                assert_eq!(e.context, validation_errors::LubContext::Equality);
+            }
+        );
+
+        // without limits, the same policy is valid
+        assert_eq!(
+            errors_with(
+                &unlimited,
+                r#"permit(principal, action, resource) when { [[[1]]] == [[[1]]] };"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn validate_with_feature_policy() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User = { name: String }; action view appliesTo { principal: User, resource: User };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema).with_feature_policy(
+            FeaturePolicy::new()
+                .forbid_unless()
+                .forbid_templates()
+                .forbid_like()
+                .forbid_extension_function("isInRange"),
+        );
+        let template_features = |t: ast::Template| {
+            let mut set = PolicySet::new();
+            set.add_template(t).unwrap();
+            validator
+                .validate(&set, ValidationMode::Strict)
+                .into_errors_and_warnings()
+                .0
+                .map(|e| match e {
+                    ValidationError::ForbiddenFeature(e) => e.feature,
+                    e => panic!("unexpected error: {e:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let features =
+            |src: &str| template_features(parser::parse_policy_or_template(None, src).unwrap());
+
+        assert_eq!(
+            features(r#"permit(principal, action, resource) when { !(principal.name == "a") };"#),
+            vec![]
+        );
+        assert_eq!(
+            features(r#"permit(principal, action, resource) unless { principal.name == "a" };"#),
+            vec![validation_errors::Feature::Unless]
+        );
+        assert_eq!(
+            features(
+                r#"permit(principal, action, resource) when { true } unless { false } when { !false };"#
+            ),
+            vec![validation_errors::Feature::Unless]
+        );
+        // `unless` clauses are also detected in policies from the JSON format
+        let est = parser::parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) unless { principal.name == "a" };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            template_features(est.try_into_ast_policy_or_template(None).unwrap()),
+            vec![validation_errors::Feature::Unless]
+        );
+        assert_eq!(
+            features(r#"permit(principal == ?principal, action, resource);"#),
+            vec![validation_errors::Feature::Template]
+        );
+        assert_eq!(
+            features(r#"permit(principal, action, resource) when { principal.name like "a*" };"#),
+            vec![validation_errors::Feature::Like]
+        );
+        assert_eq!(
+            features(
+                r#"permit(principal, action, resource) when { ip("10.0.0.1").isInRange(ip("10.0.0.0/8")) };"#
+            ),
+            vec![validation_errors::Feature::ExtensionFunction(
+                "isInRange".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_with_profile() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User; entity Photo; action view appliesTo { principal: User, resource: [User, Photo] };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let mut set = PolicySet::new();
+        set.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("scope")),
+                r#"permit(principal == User::"alice", action, resource);"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        set.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("lub")),
+                r#"permit(principal, action, resource) when { [principal, resource].contains(principal) };"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        set.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("invalid")),
+                r#"permit(principal, action, resource) when { principal.name == "alice" };"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+
+        let (result, profile) = validator.validate_with_profile(&set, ValidationMode::Strict);
+        let expected = validator.validate(&set, ValidationMode::Strict);
+        assert_eq!(
+            result.validation_errors().count(),
+            expected.validation_errors().count()
+        );
+        assert_eq!(result.validation_errors().count(), 2);
+
+        let policies: HashMap<_, _> = profile.policies().map(|p| (p.id().clone(), p)).collect();
+        assert_eq!(policies.len(), 3);
+        for policy in policies.values() {
+            assert_eq!(policy.request_envs().count(), 2);
+            assert!(policy.duration() >= policy.request_envs().map(|e| e.duration()).sum());
+            assert!(
+                policy.lub_computations()
+                    >= policy
+                        .request_envs()
+                        .map(|e| e.lub_computations())
+                        .sum::<usize>()
+            );
+        }
+        let lub = policies.get(&PolicyID::from_string("lub")).unwrap();
+        assert!(lub.request_envs().all(|e| e.lub_computations() > 0));
+        let resource_types: HashSet<_> = lub
+            .request_envs()
+            .map(|e| e.request_type().unwrap().resource.to_string())
+            .collect();
+        assert_eq!(
+            resource_types,
+            HashSet::from(["User".to_string(), "Photo".to_string()])
+        );
+        assert_eq!(
+            profile.lub_computations(),
+            policies
+                .values()
+                .map(|p| p.lub_computations())
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn related_schema_declarations() {
+        let src = "entity User; entity Photo;\naction view appliesTo { principal: User, resource: Photo };";
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            src,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        // the related declarations of the error of the given kind
+        let related = |policy: &str, is_kind: fn(&ValidationError) -> bool| {
+            let mut set = PolicySet::new();
+            set.add_static(parser::parse_policy(None, policy).unwrap())
+                .unwrap();
+            let result = validator.validate(&set, ValidationMode::Strict);
+            let err = result.validation_errors().find(|e| is_kind(e)).unwrap();
+            miette::Diagnostic::related(err)
+                .map(|related| {
+                    related
+                        .map(|d| {
+                            let labels = d.labels().unwrap().collect::<Vec<_>>();
+                            assert_eq!(labels.len(), 1);
+                            let label = &labels[0];
+                            (
+                                d.to_string(),
+                                &src[label.offset()..label.offset() + label.len()],
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let decls = related(
+            r#"permit(principal == Usr::"alice", action, resource);"#,
+            |e| matches!(e, ValidationError::UnrecognizedEntityType(_)),
+        );
+        assert_eq!(decls.len(), 1);
+        assert_eq!(decls[0].0, "entity type `User` is declared here");
+        assert!(decls[0].1.contains("User"));
+
+        let decls = related(
+            r#"permit(principal == Photo::"p", action == Action::"view", resource);"#,
+            |e| matches!(e, ValidationError::InvalidActionApplication(_)),
+        );
+        assert_eq!(decls.len(), 1);
+        assert_eq!(decls[0].0, r#"action `Action::"view"` is declared here"#);
+        assert!(decls[0].1.contains("view"));
+
+        // no declarations are known for schemas not parsed from Cedar syntax
+        let schema = ValidatorSchema::from_json_value(
+            serde_json::json!({ "": { "entityTypes": { "User": {} }, "actions": {} } }),
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let mut set = PolicySet::new();
+        set.add_static(
+            parser::parse_policy(
+                None,
+                r#"permit(principal == Usr::"alice", action, resource);"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&set, ValidationMode::Strict);
+        let err = result
+            .validation_errors()
+            .find(|e| matches!(e, ValidationError::UnrecognizedEntityType(_)))
+            .unwrap();
+        assert!(miette::Diagnostic::related(err).is_none());
+    }
 }
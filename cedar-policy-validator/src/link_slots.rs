@@ -0,0 +1,204 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Analyzes the slots of templates, and checks the values a link would bind
+//! them to before linking.
+
+use std::collections::{BTreeSet, HashMap};
+
+use cedar_policy_core::ast::{EntityType, SlotEnv, SlotId, Template};
+
+use crate::typecheck::{PolicyCheck, Typechecker};
+use crate::types::RequestEnv;
+use crate::{ValidationError, ValidationMode, ValidatorSchema};
+
+/// The entity types each slot of `t` may be linked to such that `t` applies
+/// to some request environment of `schema`. A slot has no entity types if no
+/// link of `t` ever applies. Returns `None` for a slot whose entity types are
+/// unknown, which may happen in partial validation.
+pub(crate) fn expected_slot_types(
+    schema: &ValidatorSchema,
+    t: &Template,
+    mode: ValidationMode,
+) -> HashMap<SlotId, Option<BTreeSet<EntityType>>> {
+    let mut types: HashMap<_, _> = t
+        .slots()
+        .map(|slot| (slot.id, Some(BTreeSet::new())))
+        .collect();
+    let typechecker = Typechecker::new(schema, mode, t.id().clone());
+    for (env, check) in typechecker.typecheck_by_request_env(t) {
+        if matches!(check, PolicyCheck::Irrelevant(_)) {
+            continue;
+        }
+        for (slot, ty) in [
+            (SlotId::principal(), env.principal_slot()),
+            (SlotId::resource(), env.resource_slot()),
+        ] {
+            let Some(slot_types) = types.get_mut(&slot) else {
+                continue;
+            };
+            match (ty, &env) {
+                (Some(ty), _) => {
+                    if let Some(slot_types) = slot_types {
+                        slot_types.insert(ty.clone());
+                    }
+                }
+                (None, RequestEnv::UndeclaredAction) => *slot_types = None,
+                (None, RequestEnv::DeclaredAction { .. }) => (),
+            }
+        }
+    }
+    types
+}
+
+/// Check the values `values` a link of `t` would bind its slots to: every
+/// slot of `t` must have a value, there must be no value for a slot `t`
+/// doesn't have, and the value of a slot must have one of the entity types
+/// for which `t` may apply, if `t` applies to any. The errors list the entity
+/// types expected for each slot.
+pub(crate) fn link_binding_errors(
+    schema: &ValidatorSchema,
+    t: &Template,
+    values: &SlotEnv,
+    mode: ValidationMode,
+) -> Vec<ValidationError> {
+    let expected = expected_slot_types(schema, t, mode);
+    let mut errors = Vec::new();
+    for slot in t.slots() {
+        let expected_types = expected
+            .get(&slot.id)
+            .cloned()
+            .flatten()
+            .unwrap_or_default();
+        match values.get(&slot.id) {
+            None => errors.push(ValidationError::missing_slot_binding(
+                slot.loc.clone(),
+                t.id().clone(),
+                slot.id,
+                expected_types.into_iter().collect(),
+            )),
+            Some(value) => {
+                if !expected_types.is_empty() && !expected_types.contains(value.entity_type()) {
+                    errors.push(ValidationError::unexpected_slot_type(
+                        slot.loc.clone(),
+                        t.id().clone(),
+                        slot.id,
+                        value.entity_type().clone(),
+                        expected_types.into_iter().collect(),
+                    ));
+                }
+            }
+        }
+    }
+    let mut extra = values
+        .keys()
+        .filter(|slot| !expected.contains_key(slot))
+        .collect::<Vec<_>>();
+    extra.sort();
+    errors.extend(
+        extra.into_iter().map(|slot| {
+            ValidationError::unused_slot_binding(t.loc().cloned(), t.id().clone(), *slot)
+        }),
+    );
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::ast::{EntityUID, PolicyID};
+    use cedar_policy_core::extensions::Extensions;
+    use cedar_policy_core::parser::parse_policy_or_template;
+    use cool_asserts::assert_matches;
+
+    fn schema() -> ValidatorSchema {
+        ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity Group;
+            entity User in [Group];
+            entity Folder;
+            entity Photo in [Folder];
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap()
+        .0
+    }
+
+    fn template(src: &str) -> Template {
+        parse_policy_or_template(Some(PolicyID::from_string("t")), src).unwrap()
+    }
+
+    fn types(names: &[&str]) -> Option<BTreeSet<EntityType>> {
+        Some(names.iter().map(|n| n.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn slot_types() {
+        let t = template(r#"permit(principal in ?principal, action, resource == ?resource);"#);
+        let expected = expected_slot_types(&schema(), &t, ValidationMode::Strict);
+        assert_eq!(
+            expected,
+            HashMap::from([
+                (SlotId::principal(), types(&["Group", "User"])),
+                (SlotId::resource(), types(&["Photo"])),
+            ])
+        );
+    }
+
+    #[test]
+    fn binding_errors() {
+        let t = template(r#"permit(principal in ?principal, action, resource);"#);
+        let uid = |s: &str| s.parse::<EntityUID>().unwrap();
+        assert_eq!(
+            link_binding_errors(
+                &schema(),
+                &t,
+                &SlotEnv::from([(SlotId::principal(), uid(r#"Group::"g""#))]),
+                ValidationMode::Strict
+            ),
+            vec![]
+        );
+
+        let errors = link_binding_errors(
+            &schema(),
+            &t,
+            &SlotEnv::from([(SlotId::resource(), uid(r#"Photo::"p""#))]),
+            ValidationMode::Strict,
+        );
+        assert_matches!(errors.as_slice(), [ValidationError::MissingSlotBinding(missing), ValidationError::UnusedSlotBinding(unused)] => {
+            assert_eq!(missing.slot, SlotId::principal());
+            assert_eq!(missing.expected_types, types(&["Group", "User"]).unwrap().into_iter().collect::<Vec<_>>());
+            assert_eq!(unused.slot, SlotId::resource());
+        });
+
+        let errors = link_binding_errors(
+            &schema(),
+            &t,
+            &SlotEnv::from([(SlotId::principal(), uid(r#"Folder::"f""#))]),
+            ValidationMode::Strict,
+        );
+        assert_matches!(errors.as_slice(), [ValidationError::UnexpectedSlotType(e)] => {
+            assert_eq!(e.slot, SlotId::principal());
+            assert_eq!(e.actual, "Folder".parse().unwrap());
+            assert_eq!(
+                e.to_string(),
+                "for policy `t`, the slot `?principal` is bound to an entity of type `Folder`, for which the template never applies"
+            );
+        });
+    }
+}
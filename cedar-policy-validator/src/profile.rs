@@ -0,0 +1,157 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Defines [`ValidationProfile`], which reports where the validator spent its
+//! time, for diagnosing slow validation of large schemas or policy sets.
+
+use std::cell::Cell;
+use std::time::Duration;
+
+use cedar_policy_core::ast::{PolicyID, RequestType};
+
+thread_local! {
+    /// Number of least upper bounds computed on this thread
+    static LUB_COMPUTATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Record that a least upper bound was computed
+pub(crate) fn count_lub_computation() {
+    LUB_COMPUTATIONS.with(|c| c.set(c.get().wrapping_add(1)));
+}
+
+/// The number of least upper bounds computed on this thread so far
+pub(crate) fn lub_computations() -> usize {
+    LUB_COMPUTATIONS.with(Cell::get)
+}
+
+/// Time spent by [`crate::Validator::validate_with_profile()`], for each
+/// policy and template.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationProfile {
+    policies: Vec<PolicyProfile>,
+}
+
+impl ValidationProfile {
+    pub(crate) fn push(&mut self, policy: PolicyProfile) {
+        self.policies.push(policy);
+    }
+
+    /// The profile of each policy and template, in the order they were
+    /// validated
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyProfile> {
+        self.policies.iter()
+    }
+
+    /// Total time spent validating policies and templates
+    pub fn total_duration(&self) -> Duration {
+        self.policies.iter().map(PolicyProfile::duration).sum()
+    }
+
+    /// Total number of least upper bounds computed while validating policies
+    /// and templates
+    pub fn lub_computations(&self) -> usize {
+        self.policies
+            .iter()
+            .map(PolicyProfile::lub_computations)
+            .sum()
+    }
+}
+
+/// Time spent validating a single policy or template
+#[derive(Debug, Clone)]
+pub struct PolicyProfile {
+    id: PolicyID,
+    duration: Duration,
+    lub_computations: usize,
+    request_envs: Vec<RequestEnvProfile>,
+}
+
+impl PolicyProfile {
+    pub(crate) fn new(
+        id: PolicyID,
+        duration: Duration,
+        lub_computations: usize,
+        request_envs: Vec<RequestEnvProfile>,
+    ) -> Self {
+        Self {
+            id,
+            duration,
+            lub_computations,
+            request_envs,
+        }
+    }
+
+    /// The id of the policy or template
+    pub fn id(&self) -> &PolicyID {
+        &self.id
+    }
+
+    /// Total time spent validating the policy, including the checks which are
+    /// not specific to a request environment
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Number of least upper bounds computed while validating the policy
+    pub fn lub_computations(&self) -> usize {
+        self.lub_computations
+    }
+
+    /// Time spent typechecking the policy in each request environment
+    pub fn request_envs(&self) -> impl Iterator<Item = &RequestEnvProfile> {
+        self.request_envs.iter()
+    }
+}
+
+/// Time spent typechecking a policy in a single request environment
+#[derive(Debug, Clone)]
+pub struct RequestEnvProfile {
+    request_type: Option<RequestType>,
+    duration: Duration,
+    lub_computations: usize,
+}
+
+impl RequestEnvProfile {
+    pub(crate) fn new(
+        request_type: Option<RequestType>,
+        duration: Duration,
+        lub_computations: usize,
+    ) -> Self {
+        Self {
+            request_type,
+            duration,
+            lub_computations,
+        }
+    }
+
+    /// The request type of the environment. This is `None` for the
+    /// environment representing undeclared actions in partial schema
+    /// validation.
+    pub fn request_type(&self) -> Option<&RequestType> {
+        self.request_type.as_ref()
+    }
+
+    /// Time spent typechecking the policy in this environment
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Number of least upper bounds computed while typechecking the policy in
+    /// this environment
+    pub fn lub_computations(&self) -> usize {
+        self.lub_computations
+    }
+}
@@ -28,10 +28,15 @@ use std::{collections::HashSet, sync::Arc};
 
 use crate::{
     expr_iterator::{policy_entity_type_names, policy_entity_uids},
-    ValidationError,
+    validation_errors::SchemaDeclaration,
+    ValidationError, ValidationWarning,
 };
 
-use super::{fuzzy_match::fuzzy_search, schema::*, Validator};
+use super::{
+    fuzzy_match::{fuzzy_search, fuzzy_search_within},
+    schema::*,
+    Validator,
+};
 
 impl Validator {
     /// Generate `UnrecognizedEntityType` error for every entity type in the
@@ -52,15 +57,18 @@ impl Validator {
             let is_known_entity_type = self.schema.is_known_entity_type(name);
 
             if !name.is_action() && !is_known_entity_type {
-                let actual_entity_type = name.to_string();
                 let suggested_entity_type =
-                    fuzzy_search(&actual_entity_type, known_entity_types.as_slice());
-                Some(ValidationError::unrecognized_entity_type(
-                    name.loc().cloned(),
-                    template.id().clone(),
-                    actual_entity_type,
-                    suggested_entity_type,
-                ))
+                    self.suggest_entity_type(name, known_entity_types.as_slice());
+                let declaration = self.entity_type_declaration(suggested_entity_type.as_deref());
+                Some(
+                    ValidationError::unrecognized_entity_type(
+                        name.loc().cloned(),
+                        template.id().clone(),
+                        name.to_string(),
+                        suggested_entity_type,
+                    )
+                    .with_related(declaration),
+                )
             } else {
                 None
             }
@@ -88,7 +96,11 @@ impl Validator {
                     euid.loc().cloned(),
                     template.id().clone(),
                     euid.to_string(),
-                    fuzzy_search(euid.eid().as_ref(), known_action_ids.as_slice()),
+                    fuzzy_search_within(
+                        euid.eid().as_ref(),
+                        known_action_ids.as_slice(),
+                        self.max_suggestion_distance,
+                    ),
                 ))
             } else {
                 None
@@ -114,21 +126,88 @@ impl Validator {
         slots.values().filter_map(move |euid| {
             let entity_type = euid.entity_type();
             if !self.schema.is_known_entity_type(entity_type) {
-                let actual_entity_type = entity_type.to_string();
                 let suggested_entity_type =
-                    fuzzy_search(&actual_entity_type, known_entity_types.as_slice());
-                Some(ValidationError::unrecognized_entity_type(
-                    None,
-                    policy_id.clone(),
-                    actual_entity_type,
-                    suggested_entity_type,
-                ))
+                    self.suggest_entity_type(entity_type, known_entity_types.as_slice());
+                let declaration = self.entity_type_declaration(suggested_entity_type.as_deref());
+                Some(
+                    ValidationError::unrecognized_entity_type(
+                        None,
+                        policy_id.clone(),
+                        entity_type.to_string(),
+                        suggested_entity_type,
+                    )
+                    .with_related(declaration),
+                )
             } else {
                 None
             }
         })
     }
 
+    /// Suggest a declared entity type which `entity_type` may be a
+    /// misspelling of. If declared entity types have the same basename, the
+    /// namespace was likely misspelled or omitted, so we suggest the one with
+    /// the closest namespace. Otherwise, we suggest the closest of
+    /// `known_entity_types`.
+    fn suggest_entity_type(
+        &self,
+        entity_type: &ast::EntityType,
+        known_entity_types: &[String],
+    ) -> Option<String> {
+        let actual_entity_type = entity_type.to_string();
+        let basename = entity_type.name().basename_as_ref();
+        let same_basename = self
+            .schema
+            .known_entity_types()
+            .filter(|ety| ety.name().basename_as_ref() == basename)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        fuzzy_search_within(&actual_entity_type, &same_basename, None).or_else(|| {
+            fuzzy_search_within(
+                &actual_entity_type,
+                known_entity_types,
+                self.max_suggestion_distance,
+            )
+        })
+    }
+
+    /// The declaration of the entity type named `name` in the schema, if its
+    /// location is known
+    fn entity_type_declaration(&self, name: Option<&str>) -> Option<SchemaDeclaration> {
+        let name = name?;
+        let entity_type = self
+            .schema
+            .known_entity_types()
+            .find(|ety| ety.to_string() == name)?;
+        let loc = self.schema.source_locs().entity_type(entity_type)?;
+        Some(SchemaDeclaration {
+            source_loc: loc.clone(),
+            description: format!("entity type `{name}` is declared here"),
+        })
+    }
+
+    /// The declarations of the actions satisfying `action_constraint` whose
+    /// locations in the schema are known, in order of their locations. We do
+    /// not report declarations for unconstrained actions, since that would
+    /// list every action in the schema.
+    fn action_declarations(&self, action_constraint: &ActionConstraint) -> Vec<SchemaDeclaration> {
+        if matches!(action_constraint, ActionConstraint::Any) {
+            return Vec::new();
+        }
+        let mut declarations = self
+            .get_actions_satisfying_constraint(action_constraint)
+            .filter_map(|action| {
+                let loc = self.schema.source_locs().action(action)?;
+                Some(SchemaDeclaration {
+                    source_loc: loc.clone(),
+                    description: format!("action `{action}` is declared here"),
+                })
+            })
+            .collect::<Vec<_>>();
+        declarations.sort_by_key(|d| d.source_loc.span.offset());
+        declarations
+    }
+
     fn check_if_in_fixes_principal(
         &self,
         principal_constraint: &PrincipalConstraint,
@@ -253,6 +332,81 @@ impl Validator {
         )
     }
 
+    /// In permissive mode, a static policy or template whose scope constraints
+    /// match no request environment of the schema is reported with this
+    /// warning rather than an `InvalidActionApplication` error, since it
+    /// never applies to a request valid for the schema. The warning suggests
+    /// the action closest to the action in the scope which applies to the
+    /// principal and resource in the scope, if there is one.
+    pub(crate) fn not_applicable_template_warning(
+        &self,
+        t: &Template,
+    ) -> Option<ValidationWarning> {
+        self.validate_template_action_application(t).next()?;
+        let applicable_actions = self
+            .actions_applicable_to_scope(t, self.schema.known_action_ids())
+            .collect::<Vec<_>>();
+        let scope_action = match t.action_constraint() {
+            ActionConstraint::Any => None,
+            ActionConstraint::In(euids) => euids.first(),
+            ActionConstraint::Eq(euid) => Some(euid),
+        };
+        let suggested_action = scope_action.and_then(|scope_action| {
+            let names = applicable_actions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let suggestion = fuzzy_search(&scope_action.to_string(), &names)?;
+            applicable_actions
+                .into_iter()
+                .find(|action| action.to_string() == suggestion)
+                .cloned()
+        });
+        Some(ValidationWarning::not_applicable_policy(
+            t.loc().cloned(),
+            t.id().clone(),
+            suggested_action,
+        ))
+    }
+
+    /// The actions of the schema which requests satisfying the scope
+    /// constraints of `t` may have
+    pub(crate) fn template_actions<'a>(
+        &'a self,
+        t: &'a Template,
+    ) -> impl Iterator<Item = &'a EntityUID> + 'a {
+        self.actions_applicable_to_scope(
+            t,
+            self.get_actions_satisfying_constraint(t.action_constraint()),
+        )
+    }
+
+    /// The actions among `actions` which are declared in the schema and apply
+    /// to some principal type and some resource type satisfying the principal
+    /// and resource scope constraints of `t`
+    fn actions_applicable_to_scope<'a>(
+        &'a self,
+        t: &'a Template,
+        actions: impl Iterator<Item = &'a EntityUID> + 'a,
+    ) -> impl Iterator<Item = &'a EntityUID> + 'a {
+        let principals: HashSet<&ast::EntityType> = self
+            .get_principals_satisfying_constraint(t.principal_constraint())
+            .collect();
+        let resources: HashSet<&ast::EntityType> = self
+            .get_resources_satisfying_constraint(t.resource_constraint())
+            .collect();
+        actions.filter(move |action| {
+            self.schema.get_action_id(action).is_some_and(|action| {
+                action
+                    .applies_to_principals()
+                    .any(|ty| principals.contains(ty))
+                    && action
+                        .applies_to_resources()
+                        .any(|ty| resources.contains(ty))
+            })
+        })
+    }
+
     // Check that there exists a (action id, principal type, resource type)
     // entity type pair where the action can be applied to both the principal
     // and resource. This function takes the three scope constraints as input
@@ -293,6 +447,7 @@ impl Validator {
                 matching_principal && matching_resource
             })
         })
+        .map(|e| e.with_related(self.action_declarations(action_constraint)))
         .into_iter()
     }
 
@@ -310,7 +465,7 @@ impl Validator {
 
     /// Get the set of actions (action entity id strings) that satisfy the
     /// action scope constraint of the policy.
-    fn get_actions_satisfying_constraint<'a>(
+    pub(crate) fn get_actions_satisfying_constraint<'a>(
         &'a self,
         action_constraint: &'a ActionConstraint,
     ) -> Box<dyn Iterator<Item = &'a EntityUID> + 'a> {
@@ -395,7 +550,7 @@ impl Validator {
 #[allow(clippy::indexing_slicing)]
 #[cfg(test)]
 mod test {
-    use std::collections::{HashMap, HashSet};
+    use std::collections::{BTreeMap, HashMap, HashSet};
 
     use cedar_policy_core::{
         ast::{
@@ -487,6 +642,10 @@ mod test {
                 foo_type.parse().unwrap(),
                 json_schema::EntityType {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )],
@@ -521,6 +680,10 @@ mod test {
                 "foo_type".parse().unwrap(),
                 json_schema::EntityType {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )],
@@ -573,6 +736,7 @@ mod test {
                 json_schema::ActionType {
                     applies_to: None,
                     member_of: None,
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -606,6 +770,10 @@ mod test {
                 p_name.parse().unwrap(),
                 json_schema::EntityType {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )],
@@ -630,6 +798,10 @@ mod test {
                 p_name.parse().unwrap(),
                 json_schema::EntityType {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )],
@@ -654,6 +826,10 @@ mod test {
                 p_name.parse().unwrap(),
                 json_schema::EntityType {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )],
@@ -699,6 +875,7 @@ mod test {
                 json_schema::ActionType {
                     applies_to: None,
                     member_of: None,
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -865,6 +1042,7 @@ mod test {
                 json_schema::ActionType {
                     applies_to: None,
                     member_of: None,
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -892,6 +1070,7 @@ mod test {
                 json_schema::ActionType {
                     applies_to: None,
                     member_of: None,
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -919,6 +1098,7 @@ mod test {
                 json_schema::ActionType {
                     applies_to: None,
                     member_of: None,
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -944,6 +1124,10 @@ mod test {
                 foo_type.parse().unwrap(),
                 json_schema::EntityType {
                     member_of_types: vec![],
+                    extends: None,
+                    parent_attributes: vec![],
+                    computed_attributes: BTreeMap::new(),
+                    annotations: BTreeMap::new(),
                     shape: json_schema::AttributesOrContext::default(),
                 },
             )],
@@ -977,6 +1161,10 @@ mod test {
                     principal_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -984,6 +1172,10 @@ mod test {
                     resource_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -997,6 +1189,7 @@ mod test {
                         context: json_schema::AttributesOrContext::default(),
                     }),
                     member_of: Some(vec![]),
+                    annotations: BTreeMap::new(),
                     attributes: None,
                 },
             )],
@@ -1366,6 +1559,10 @@ mod test {
                     principal_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -1373,6 +1570,10 @@ mod test {
                     resource_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![resource_parent_type.parse().unwrap()],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -1380,6 +1581,10 @@ mod test {
                     resource_parent_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![resource_grandparent_type.parse().unwrap()],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -1387,6 +1592,10 @@ mod test {
                     resource_grandparent_type.parse().unwrap(),
                     json_schema::EntityType {
                         member_of_types: vec![],
+                        extends: None,
+                        parent_attributes: vec![],
+                        computed_attributes: BTreeMap::new(),
+                        annotations: BTreeMap::new(),
                         shape: json_schema::AttributesOrContext::default(),
                     },
                 ),
@@ -1404,6 +1613,7 @@ mod test {
                             None,
                             action_parent_name.into(),
                         )]),
+                        annotations: BTreeMap::new(),
                         attributes: None,
                     },
                 ),
@@ -1415,6 +1625,7 @@ mod test {
                             None,
                             action_grandparent_name.into(),
                         )]),
+                        annotations: BTreeMap::new(),
                         attributes: None,
                     },
                 ),
@@ -1423,6 +1634,7 @@ mod test {
                     json_schema::ActionType {
                         applies_to: None,
                         member_of: Some(vec![]),
+                        annotations: BTreeMap::new(),
                         attributes: None,
                     },
                 ),
@@ -1528,3 +1740,69 @@ mod partial_schema {
         assert_validates_with_empty_schema(policy);
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "partial-validate")]
+mod opaque_entity_types {
+    use cedar_policy_core::{
+        ast::{PolicyID, Template},
+        extensions::Extensions,
+        parser::parse_policy,
+    };
+    use cool_asserts::assert_matches;
+
+    use crate::{ValidationError, ValidationMode, Validator, ValidatorSchema};
+
+    fn validator() -> Validator {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { name: String };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[track_caller] // report the caller's location as the location of the panic, not the location in this function
+    fn errors(src: &str, mode: ValidationMode) -> Vec<ValidationError> {
+        let policy = parse_policy(Some(PolicyID::from_string("0")), src).unwrap();
+        let (template, _) = Template::link_static_policy(policy);
+        let validator = validator();
+        let errors = validator.validate_policy(&template, mode).0.collect();
+        errors
+    }
+
+    #[test]
+    fn undeclared_entity_types_are_opaque() {
+        let src = r#"permit(principal, action == Action::"view", resource)
+            when { principal in Team::"eng" && Team::"eng".lead == principal.name && Team::"eng" in Org::"acme" };"#;
+        assert_eq!(errors(src, ValidationMode::OpaqueEntityTypes), vec![]);
+        let strict = errors(src, ValidationMode::Strict);
+        assert!(!strict.is_empty());
+        assert!(strict
+            .iter()
+            .all(|e| matches!(e, ValidationError::UnrecognizedEntityType(_))));
+    }
+
+    #[test]
+    fn actions_must_be_declared() {
+        let src = r#"permit(principal, action == Action::"edit", resource) when { principal in Team::"eng" };"#;
+        assert_matches!(
+            errors(src, ValidationMode::OpaqueEntityTypes).as_slice(),
+            [ValidationError::UnrecognizedActionId(_)]
+        );
+    }
+
+    #[test]
+    fn declared_entity_types_are_checked() {
+        let src =
+            r#"permit(principal, action == Action::"view", resource) when { principal.age > 3 };"#;
+        assert_matches!(
+            errors(src, ValidationMode::OpaqueEntityTypes).as_slice(),
+            [ValidationError::UnsafeAttributeAccess(_)]
+        );
+    }
+}
@@ -22,9 +22,10 @@
 
 use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use cedar_policy_core::{
-    ast::{Entity, EntityType, EntityUID, InternalName, Name, UnreservedId},
+    ast::{Entity, EntityType, EntityUID, Expr, InternalName, Name, UnreservedId},
     entities::{err::EntitiesError, Entities, TCComputation},
     extensions::Extensions,
     transitive_closure::compute_tc,
@@ -33,19 +34,24 @@ use itertools::Itertools;
 use nonempty::NonEmpty;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use smol_str::ToSmolStr;
+use smol_str::{SmolStr, ToSmolStr};
 
 use crate::{
-    cedar_schema::SchemaWarning,
+    cedar_schema::{parser::parse_cedar_schema_fragment_with_locs, SchemaWarning},
     err::schema_errors::*,
     err::*,
     json_schema,
-    types::{Attributes, EntityRecordKind, OpenTag, Type},
+    types::{Attributes, EntityRecordKind, OpenTag, RequestEnv, Type},
 };
 
 mod action;
 pub use action::ValidatorActionId;
 pub(crate) use action::ValidatorApplySpec;
+mod context_plan;
+pub(crate) use context_plan::ContextPlan;
+use context_plan::ContextPlans;
+mod deprecation;
+pub use deprecation::Deprecation;
 mod entity_type;
 pub use entity_type::ValidatorEntityType;
 mod namespace_def;
@@ -53,6 +59,8 @@ pub(crate) use namespace_def::try_jsonschema_type_into_validator_type;
 pub use namespace_def::ValidatorNamespaceDef;
 mod raw_name;
 pub use raw_name::{ConditionalName, RawName, ReferenceType};
+mod source_locs;
+pub(crate) use source_locs::SchemaSourceLocs;
 
 /// Configurable validator behaviors regarding actions
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
@@ -153,17 +161,28 @@ impl ValidatorSchemaFragment<ConditionalName, ConditionalName> {
 ///
 /// In this representation, all common types are fully expanded, and all entity
 /// type names are fully disambiguated (fully qualified).
+///
+/// A schema is immutable once constructed, so clones share its definitions.
 #[serde_as]
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidatorSchema {
     /// Map from entity type names to the [`ValidatorEntityType`] object.
-    #[serde_as(as = "Vec<(_, _)>")]
-    entity_types: HashMap<EntityType, ValidatorEntityType>,
+    #[serde_as(as = "Arc<Vec<(_, _)>>")]
+    entity_types: Arc<HashMap<EntityType, ValidatorEntityType>>,
 
     /// Map from action id names to the [`ValidatorActionId`] object.
-    #[serde_as(as = "Vec<(_, _)>")]
-    action_ids: HashMap<EntityUID, ValidatorActionId>,
+    #[serde_as(as = "Arc<Vec<(_, _)>>")]
+    action_ids: Arc<HashMap<EntityUID, ValidatorActionId>>,
+
+    /// Source locations of the declarations in this schema, if known.
+    #[serde(skip)]
+    source_locs: Arc<SchemaSourceLocs>,
+
+    /// Plans checking the contexts of requests, compiled for each action the
+    /// first time a request for the action is validated.
+    #[serde(skip)]
+    context_plans: Arc<ContextPlans>,
 }
 
 /// Construct [`ValidatorSchema`] from a string containing a schema formatted
@@ -276,12 +295,38 @@ impl ValidatorSchema {
         self.action_ids.keys()
     }
 
+    /// Returns an iterator over the request environments of this schema, i.e.,
+    /// every combination of an action with a principal type and a resource
+    /// type it applies to, together with its context type. These are the
+    /// environments in which the validator typechecks policies. The
+    /// environments are in no particular order, and never have slots linked.
+    pub fn request_envs(&self) -> impl Iterator<Item = RequestEnv<'_>> {
+        // For every action compute the cross product of the principal and
+        // resource applies_to sets.
+        self.action_ids.values().flat_map(|action| {
+            action.applies_to_principals().flat_map(move |principal| {
+                action
+                    .applies_to_resources()
+                    .map(move |resource| RequestEnv::DeclaredAction {
+                        principal,
+                        action: &action.name,
+                        resource,
+                        context: &action.context,
+                        principal_slot: None,
+                        resource_slot: None,
+                    })
+            })
+        })
+    }
+
     /// Create a [`ValidatorSchema`] without any definitions (of entity types,
     /// common types, or actions).
     pub fn empty() -> ValidatorSchema {
         Self {
-            entity_types: HashMap::new(),
-            action_ids: HashMap::new(),
+            entity_types: Arc::default(),
+            action_ids: Arc::default(),
+            source_locs: Arc::default(),
+            context_plans: Arc::default(),
         }
     }
 
@@ -318,15 +363,13 @@ impl ValidatorSchema {
     /// Construct a [`ValidatorSchema`] directly from a file containing the
     /// Cedar schema syntax.
     pub fn from_cedarschema_file<'a>(
-        r: impl std::io::Read,
+        mut r: impl std::io::Read,
         extensions: &'a Extensions<'a>,
     ) -> std::result::Result<(Self, impl Iterator<Item = SchemaWarning> + 'a), CedarSchemaError>
     {
-        let (fragment, warnings) = json_schema::Fragment::from_cedarschema_file(r, extensions)?;
-        let schema_and_warnings =
-            Self::from_schema_frag(fragment, ActionBehavior::default(), extensions)
-                .map(|schema| (schema, warnings))?;
-        Ok(schema_and_warnings)
+        let mut src = String::new();
+        r.read_to_string(&mut src)?;
+        Self::from_cedarschema_str(&src, extensions)
     }
 
     /// Construct a [`ValidatorSchema`] from a string containing the Cedar
@@ -336,11 +379,12 @@ impl ValidatorSchema {
         extensions: &Extensions<'a>,
     ) -> std::result::Result<(Self, impl Iterator<Item = SchemaWarning> + 'a), CedarSchemaError>
     {
-        let (fragment, warnings) = json_schema::Fragment::from_cedarschema_str(src, extensions)?;
-        let schema_and_warnings =
-            Self::from_schema_frag(fragment, ActionBehavior::default(), extensions)
-                .map(|schema| (schema, warnings))?;
-        Ok(schema_and_warnings)
+        let (fragment, warnings, source_locs) =
+            parse_cedar_schema_fragment_with_locs(src, extensions)
+                .map_err(|e| CedarSchemaParseError::new(e, src))?;
+        let mut schema = Self::from_schema_frag(fragment, ActionBehavior::default(), extensions)?;
+        schema.source_locs = Arc::new(source_locs);
+        Ok((schema, warnings))
     }
 
     /// Helper function to construct a [`ValidatorSchema`] from a single [`json_schema::Fragment`].
@@ -466,6 +510,82 @@ impl ValidatorSchema {
         let resolver = CommonTypeResolver::new(&common_types);
         let common_types = resolver.resolve(extensions)?;
 
+        // Entity types inherit the parent types, parent attributes, computed
+        // attributes, and attribute deprecations of the entity types they
+        // (transitively) extend. Their attributes are inherited below, once
+        // they are resolved.
+        let supertypes = Self::entity_supertypes(&entity_type_fragments)?;
+        let inherited = supertypes
+            .iter()
+            .map(|(name, supertypes)| {
+                let mut parents = HashSet::new();
+                let mut parent_attributes = Vec::new();
+                let mut computed_attributes = BTreeMap::new();
+                let mut deprecated_attributes = BTreeMap::new();
+                for fragment in supertypes
+                    .iter()
+                    .filter_map(|ty| entity_type_fragments.get(ty))
+                {
+                    parents.extend(fragment.parents.iter().cloned());
+                    parent_attributes.extend(fragment.parent_attributes.iter().cloned());
+                    for (attr, src) in fragment.computed_attributes.iter() {
+                        computed_attributes
+                            .entry(attr.clone())
+                            .or_insert_with(|| src.clone());
+                    }
+                    for (attr, deprecation) in fragment.deprecated_attributes.iter() {
+                        deprecated_attributes
+                            .entry(attr.clone())
+                            .or_insert_with(|| deprecation.clone());
+                    }
+                }
+                (
+                    name.clone(),
+                    parents,
+                    parent_attributes,
+                    computed_attributes,
+                    deprecated_attributes,
+                )
+            })
+            .collect::<Vec<_>>();
+        for (name, parents, parent_attributes, computed_attributes, deprecated_attributes) in
+            inherited
+        {
+            if let Some(fragment) = entity_type_fragments.get_mut(&name) {
+                fragment.parents.extend(parents);
+                for attr in parent_attributes {
+                    if !fragment.parent_attributes.contains(&attr) {
+                        fragment.parent_attributes.push(attr);
+                    }
+                }
+                for (attr, src) in computed_attributes {
+                    fragment.computed_attributes.entry(attr).or_insert(src);
+                }
+                for (attr, deprecation) in deprecated_attributes {
+                    fragment
+                        .deprecated_attributes
+                        .entry(attr)
+                        .or_insert(deprecation);
+                }
+            }
+        }
+        let declared_attributes = entity_type_fragments
+            .iter_mut()
+            .map(|(name, entity_type)| -> Result<_> {
+                let unresolved = try_jsonschema_type_into_validator_type(
+                    std::mem::take(&mut entity_type.attributes).0,
+                    extensions,
+                )?;
+                let attributes = Self::record_attributes_or_none(
+                    unresolved.resolve_common_type_refs(&common_types)?,
+                )
+                .ok_or(ContextOrShapeNotRecordError(
+                    ContextOrShape::EntityTypeShape(name.clone()),
+                ))?;
+                Ok((name.clone(), attributes))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
         // Invert the `parents` relation defined by entities and action so far
         // to get a `children` relation.
         let mut entity_children: HashMap<EntityType, HashSet<EntityType>> = HashMap::new();
@@ -491,18 +611,80 @@ impl ValidatorSchema {
                 // error for any other undeclared entity types by
                 // `check_for_undeclared`.
                 let descendants = entity_children.remove(&name).unwrap_or_default();
-                let (attributes, open_attributes) = {
-                    let unresolved = try_jsonschema_type_into_validator_type(
-                        entity_type.attributes.0,
-                        extensions,
-                    )?;
-                    Self::record_attributes_or_none(
-                        unresolved.resolve_common_type_refs(&common_types)?,
-                    )
-                    .ok_or(ContextOrShapeNotRecordError(
-                        ContextOrShape::EntityTypeShape(name.clone()),
-                    ))?
-                };
+                let supertypes = supertypes.get(&name).cloned().unwrap_or_default();
+                let (mut attributes, mut open_attributes) = declared_attributes
+                    .get(&name)
+                    .cloned()
+                    .unwrap_or_else(|| (Attributes::default(), OpenTag::ClosedAttributes));
+                // Inherited attributes may not be declared again
+                for supertype in supertypes.iter() {
+                    let Some((inherited, open)) = declared_attributes.get(supertype) else {
+                        continue;
+                    };
+                    for (attr, ty) in inherited.iter() {
+                        if attributes.attrs.contains_key(attr) {
+                            return Err(InheritedAttributeRedeclaredError {
+                                entity_type: name,
+                                attr: attr.clone(),
+                                supertype: supertype.clone(),
+                            }
+                            .into());
+                        }
+                        attributes.attrs.insert(attr.clone(), ty.clone());
+                    }
+                    if open.is_open() {
+                        open_attributes = OpenTag::OpenAttributes;
+                    }
+                }
+                // Parent attributes must have an entity type, or a set of an
+                // entity type, which is a declared parent type
+                for attr in entity_type.parent_attributes.iter() {
+                    let parent_type = attributes.get_attr(attr).and_then(|attr| {
+                        let ty = match &attr.attr_type {
+                            Type::Set {
+                                element_type: Some(element_type),
+                            } => element_type.as_ref(),
+                            ty => ty,
+                        };
+                        match ty {
+                            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
+                                lub.get_single_entity()
+                            }
+                            _ => None,
+                        }
+                    });
+                    let is_declared_parent = parent_type.is_some_and(|parent_type| {
+                        entity_type.parents.iter().any(|parent| {
+                            internal_name_to_entity_type(parent.clone())
+                                .is_ok_and(|parent| &parent == parent_type)
+                        })
+                    });
+                    if !is_declared_parent {
+                        return Err(InvalidParentAttributeError {
+                            entity_type: name,
+                            attr: attr.clone(),
+                        }
+                        .into());
+                    }
+                }
+                // Computed attributes must be declared, and their expressions
+                // must parse
+                let computed_attributes = entity_type
+                    .computed_attributes
+                    .into_iter()
+                    .map(|(attr, src)| {
+                        let invalid = |parse_errs| InvalidComputedAttributeError {
+                            entity_type: name.clone(),
+                            attr: attr.clone(),
+                            parse_errs,
+                        };
+                        if attributes.get_attr(&attr).is_none() {
+                            return Err(invalid(None));
+                        }
+                        let expr = src.parse::<Expr>().map_err(|errs| invalid(Some(errs)))?;
+                        Ok((attr, expr))
+                    })
+                    .collect::<std::result::Result<Vec<_>, InvalidComputedAttributeError>>()?;
                 Ok((
                     name.clone(),
                     ValidatorEntityType {
@@ -510,11 +692,27 @@ impl ValidatorSchema {
                         descendants,
                         attributes,
                         open_attributes,
+                        parent_attributes: entity_type.parent_attributes,
+                        computed_attributes,
+                        supertypes,
+                        deprecated_attributes: entity_type.deprecated_attributes,
                     },
                 ))
             })
             .collect::<Result<HashMap<_, _>>>()?;
 
+        // Actions which apply to an entity type also apply to the entity
+        // types extending it
+        let mut subtypes: HashMap<EntityType, HashSet<EntityType>> = HashMap::new();
+        for (name, supertypes) in supertypes.iter() {
+            for supertype in supertypes {
+                subtypes
+                    .entry(supertype.clone())
+                    .or_default()
+                    .insert(name.clone());
+            }
+        }
+
         let mut action_children = HashMap::new();
         for (euid, action) in action_fragments.iter() {
             for parent in action.parents.iter() {
@@ -542,7 +740,7 @@ impl ValidatorSchema {
                     name.clone(),
                     ValidatorActionId {
                         name,
-                        applies_to: action.applies_to,
+                        applies_to: action.applies_to.with_subtypes(&subtypes),
                         descendants,
                         context: Type::record_with_attributes(
                             context.attrs,
@@ -550,6 +748,7 @@ impl ValidatorSchema {
                         ),
                         attribute_types: action.attribute_types,
                         attributes: action.attributes,
+                        deprecation: action.deprecation,
                     },
                 ))
             })
@@ -578,8 +777,10 @@ impl ValidatorSchema {
         )?;
 
         Ok(ValidatorSchema {
-            entity_types,
-            action_ids,
+            entity_types: Arc::new(entity_types),
+            context_plans: Arc::new(ContextPlans::new(action_ids.keys())),
+            action_ids: Arc::new(action_ids),
+            source_locs: Arc::default(),
         })
     }
 
@@ -653,6 +854,32 @@ impl ValidatorSchema {
         Ok(())
     }
 
+    /// The entity types each entity type transitively extends, nearest
+    /// first, for the entity types which extend another
+    fn entity_supertypes(
+        fragments: &HashMap<EntityType, namespace_def::EntityTypeFragment<InternalName>>,
+    ) -> Result<HashMap<EntityType, Vec<EntityType>>> {
+        let mut supertypes = HashMap::new();
+        for (name, fragment) in fragments.iter() {
+            let mut chain = Vec::new();
+            let mut extends = fragment.extends.clone();
+            while let Some(supertype) = extends {
+                let supertype = internal_name_to_entity_type(supertype)?;
+                if &supertype == name || chain.contains(&supertype) {
+                    return Err(CycleInEntityTypeInheritanceError(name.clone()).into());
+                }
+                extends = fragments
+                    .get(&supertype)
+                    .and_then(|fragment| fragment.extends.clone());
+                chain.push(supertype);
+            }
+            if !chain.is_empty() {
+                supertypes.insert(name.clone(), chain);
+            }
+        }
+        Ok(supertypes)
+    }
+
     fn record_attributes_or_none(ty: Type) -> Option<(Attributes, OpenTag)> {
         match ty {
             Type::EntityOrRecord(EntityRecordKind::Record {
@@ -698,6 +925,17 @@ impl ValidatorSchema {
         }
     }
 
+    /// Source locations of the declarations in this schema, if known
+    pub(crate) fn source_locs(&self) -> &SchemaSourceLocs {
+        &self.source_locs
+    }
+
+    /// The plan checking the contexts of requests for `action`, if it is an
+    /// action of this schema
+    pub(crate) fn context_plan(&self, action: &ValidatorActionId) -> Option<&ContextPlan> {
+        self.context_plans.get(action)
+    }
+
     /// Lookup the [`ValidatorActionId`] object in the schema with the given name.
     pub fn get_action_id(&self, action_id: &EntityUID) -> Option<&ValidatorActionId> {
         self.action_ids.get(action_id)
@@ -736,6 +974,23 @@ impl ValidatorSchema {
         self.entity_types.keys()
     }
 
+    /// The names of all attributes declared anywhere in the schema: attributes
+    /// of entity types, of action contexts, and of the record types nested in
+    /// them (including records defined as common types).
+    pub(crate) fn all_attribute_names(&self) -> BTreeSet<SmolStr> {
+        let mut names = BTreeSet::new();
+        for entity_type in self.entity_types.values() {
+            for (name, attr_ty) in entity_type.attributes.iter() {
+                names.insert(name.clone());
+                attr_ty.attr_type.collect_record_attribute_names(&mut names);
+            }
+        }
+        for action in self.action_ids.values() {
+            action.context.collect_record_attribute_names(&mut names);
+        }
+        names
+    }
+
     /// An iterator matching the entity Types to their Validator Types
     pub fn entity_types(&self) -> impl Iterator<Item = (&EntityType, &ValidatorEntityType)> {
         self.entity_types.iter()
@@ -808,7 +1063,7 @@ impl ValidatorSchema {
         // structures through some complicated bits of schema construction code,
         // and avoids computing the TC twice.
         let mut action_ancestors: HashMap<&EntityUID, HashSet<EntityUID>> = HashMap::new();
-        for (action_euid, action_def) in &self.action_ids {
+        for (action_euid, action_def) in self.action_ids.iter() {
             for descendant in &action_def.descendants {
                 action_ancestors
                     .entry(descendant)
@@ -934,6 +1189,10 @@ fn primitive_types<N>() -> impl Iterator<Item = (UnreservedId, json_schema::Type
             UnreservedId::from_str("String").unwrap(),
             json_schema::Type::Type(json_schema::TypeVariant::String),
         ),
+        (
+            UnreservedId::from_str("Null").unwrap(),
+            json_schema::Type::Type(json_schema::TypeVariant::Null),
+        ),
     ]
     .into_iter()
 }
@@ -1243,6 +1502,14 @@ impl<'a> CommonTypeResolver<'a> {
                     element: Box::new(Self::resolve_type(resolve_table, *element)?),
                 }))
             }
+            json_schema::Type::Type(json_schema::TypeVariant::Union { arms }) => {
+                Ok(json_schema::Type::Type(json_schema::TypeVariant::Union {
+                    arms: arms
+                        .into_iter()
+                        .map(|arm| Self::resolve_type(resolve_table, arm))
+                        .collect::<Result<_>>()?,
+                }))
+            }
             json_schema::Type::Type(json_schema::TypeVariant::Record(
                 json_schema::RecordType {
                     attributes,
@@ -1259,6 +1526,7 @@ impl<'a> CommonTypeResolver<'a> {
                                     json_schema::TypeOfAttribute {
                                         required: attr_ty.required,
                                         ty: Self::resolve_type(resolve_table, attr_ty.ty)?,
+                                        annotations: attr_ty.annotations,
                                     },
                                 ))
                             })
@@ -4942,6 +5210,34 @@ action CreateList in Create appliesTo {
         assert!(schema.principals_for_action(&delete_user).is_none());
     }
 
+    #[test]
+    fn request_envs() {
+        let schema = schema();
+        let get_list: EntityUID = r#"Action::"GetList""#.parse().unwrap();
+        let envs = schema.request_envs().collect::<Vec<_>>();
+        // actions without `appliesTo` have no request environments
+        assert_eq!(envs.len(), 10);
+        let got = envs
+            .iter()
+            .filter(|env| env.action_entity_uid() == Some(&get_list))
+            .map(|env| {
+                (
+                    env.principal_entity_type().unwrap().to_string(),
+                    env.resource_entity_type().unwrap().to_string(),
+                    env.context_type(),
+                )
+            })
+            .collect::<HashSet<_>>();
+        let context = schema.context_type(&get_list).unwrap().clone();
+        assert_eq!(
+            got,
+            HashSet::from([
+                ("User".into(), "List".into(), context.clone()),
+                ("User".into(), "CoolList".into(), context),
+            ])
+        );
+    }
+
     #[test]
     fn principal_parents() {
         let schema = schema();
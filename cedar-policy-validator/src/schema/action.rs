@@ -24,19 +24,20 @@ use itertools::Itertools;
 use nonempty::NonEmpty;
 use serde::Serialize;
 use smol_str::SmolStr;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
 
 use super::internal_name_to_entity_type;
 use crate::{
     schema::{AllDefs, SchemaError},
     types::{Attributes, Type},
-    ConditionalName,
+    ConditionalName, Deprecation,
 };
 
 /// Contains information about actions used by the validator.  The contents of
 /// the struct are the same as the schema entity type structure, but the
 /// `member_of` relation is reversed to instead be `descendants`.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidatorActionId {
     /// The name of the action.
@@ -64,6 +65,9 @@ pub struct ValidatorActionId {
     /// Attributes are serialized as `RestrictedExpr`s, so that roundtripping
     /// works seamlessly.
     pub(crate) attributes: BTreeMap<SmolStr, PartialValueSerializedAsExpr>,
+
+    /// The deprecation of this action, if it is deprecated.
+    pub(crate) deprecation: Option<Deprecation>,
 }
 
 impl ValidatorActionId {
@@ -103,6 +107,17 @@ impl ValidatorActionId {
     pub fn is_applicable_resource_type(&self, ty: &ast::EntityType) -> bool {
         self.applies_to.is_applicable_resource_type(ty)
     }
+
+    /// Return `true` if `action` is a member of this action, directly or
+    /// transitively
+    pub fn has_descendant(&self, action: &EntityUID) -> bool {
+        self.descendants.contains(action)
+    }
+
+    /// The deprecation of this action, if it is deprecated
+    pub fn deprecation(&self) -> Option<&Deprecation> {
+        self.deprecation.as_ref()
+    }
 }
 
 impl TCNode<EntityUID> for ValidatorActionId {
@@ -143,6 +158,13 @@ pub(crate) struct ValidatorApplySpec<N> {
     resource_apply_spec: HashSet<N>,
 }
 
+impl<N: Eq + Hash> PartialEq for ValidatorApplySpec<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.principal_apply_spec == other.principal_apply_spec
+            && self.resource_apply_spec == other.resource_apply_spec
+    }
+}
+
 impl<N> ValidatorApplySpec<N> {
     /// Create an apply spec for an action that can only be applied to some
     /// specific entities.
@@ -174,6 +196,28 @@ impl ValidatorApplySpec<ast::EntityType> {
     pub fn applicable_resource_types(&self) -> impl Iterator<Item = &ast::EntityType> {
         self.resource_apply_spec.iter()
     }
+
+    /// Extend this spec to the entity types extending the applicable
+    /// principal and resource types, given the entity types extending each
+    /// entity type.
+    pub(crate) fn with_subtypes(
+        mut self,
+        subtypes: &HashMap<ast::EntityType, HashSet<ast::EntityType>>,
+    ) -> Self {
+        for spec in [
+            &mut self.principal_apply_spec,
+            &mut self.resource_apply_spec,
+        ] {
+            let inherited: Vec<ast::EntityType> = spec
+                .iter()
+                .filter_map(|ty| subtypes.get(ty))
+                .flatten()
+                .cloned()
+                .collect();
+            spec.extend(inherited);
+        }
+        self
+    }
 }
 
 impl ValidatorApplySpec<ConditionalName> {
@@ -245,6 +289,7 @@ mod test {
             context: Type::any_record(),
             attribute_types: Attributes::default(),
             attributes: BTreeMap::default(),
+            deprecation: None,
         }
     }
 
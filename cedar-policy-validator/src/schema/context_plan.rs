@@ -0,0 +1,292 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Plans which check that request contexts have the context type of their
+//! action, compiled once per action and reused by every request.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use cedar_policy_core::ast::{EntityType, EntityUID, Literal, Name, Value, ValueKind};
+use smol_str::SmolStr;
+
+use crate::types::{EntityRecordKind, OpenTag, Primitive, Type};
+
+use super::ValidatorActionId;
+
+/// The context plan of each action of a schema, compiled the first time a
+/// context is checked for the action
+#[derive(Debug, Default)]
+pub(crate) struct ContextPlans(HashMap<EntityUID, OnceLock<ContextPlan>>);
+
+impl ContextPlans {
+    /// No plan is compiled yet for `actions`
+    pub(crate) fn new<'a>(actions: impl IntoIterator<Item = &'a EntityUID>) -> Self {
+        Self(
+            actions
+                .into_iter()
+                .map(|action| (action.clone(), OnceLock::new()))
+                .collect(),
+        )
+    }
+
+    /// The plan of `action`, compiling it if this is the first time it is
+    /// used. `None` if `action` is not one of the actions of the schema.
+    pub(crate) fn get(&self, action: &ValidatorActionId) -> Option<&ContextPlan> {
+        self.0
+            .get(&action.name)
+            .map(|plan| plan.get_or_init(|| ContextPlan::compile(&action.context)))
+    }
+}
+
+/// Checks that a context has a context type, directly on the values of its
+/// attributes. This is equivalent to typechecking the context, which
+/// converts it to a restricted expression and looks up each of its
+/// attributes in the type.
+#[derive(Debug)]
+pub(crate) struct ContextPlan(RecordPlan);
+
+impl ContextPlan {
+    fn compile(context_type: &Type) -> Self {
+        match ValuePlan::compile(context_type) {
+            ValuePlan::Record(plan) => Self(plan),
+            // context types are always records, see
+            // `ValidatorActionId::context_type`
+            _ => Self(RecordPlan {
+                attrs: Vec::new(),
+                open: true,
+            }),
+        }
+    }
+
+    /// Does the context with the attributes `attrs` have the context type
+    pub(crate) fn check(&self, attrs: &BTreeMap<SmolStr, Value>) -> bool {
+        self.0.check(attrs)
+    }
+}
+
+/// The checks of the attributes of a record type, sorted by attribute name
+/// like the attributes of record values, so that a record is checked by
+/// walking both in order
+#[derive(Debug)]
+struct RecordPlan {
+    /// The name of each attribute, whether it is required, and the check of
+    /// its values
+    attrs: Vec<(SmolStr, bool, ValuePlan)>,
+    /// Whether the record may have other attributes
+    open: bool,
+}
+
+impl RecordPlan {
+    fn check(&self, attrs: &BTreeMap<SmolStr, Value>) -> bool {
+        let mut expected = self.attrs.iter().peekable();
+        for (attr, value) in attrs {
+            // skip the expected attributes before `attr`, which must be
+            // optional
+            while let Some((_, required, _)) = expected.next_if(|(name, _, _)| name < attr) {
+                if *required {
+                    return false;
+                }
+            }
+            match expected.next_if(|(name, _, _)| name == attr) {
+                Some((_, _, plan)) => {
+                    if !plan.check(value) {
+                        return false;
+                    }
+                }
+                None => {
+                    if !self.open {
+                        return false;
+                    }
+                }
+            }
+        }
+        expected.all(|(_, required, _)| !required)
+    }
+}
+
+/// Checks that a value has a type
+#[derive(Debug)]
+enum ValuePlan {
+    /// No value has the type
+    Never,
+    /// The value is a boolean, `true`, or `false`
+    Bool(Option<bool>),
+    /// The value is one of these primitive types
+    Primitive(BTreeSet<Primitive>),
+    /// The value is a set whose elements, if known, pass the check
+    Set(Option<Box<ValuePlan>>),
+    /// The value is an entity of one of these types, or of any type
+    Entity(Option<BTreeSet<EntityType>>),
+    /// The value is an action of this action entity type
+    Action(EntityType),
+    /// The value is a record
+    Record(RecordPlan),
+    /// The value is an extension value of this type
+    Extension(Name),
+    /// The value is `null` or passes the check
+    Nullable(Box<ValuePlan>),
+}
+
+impl ValuePlan {
+    fn compile(ty: &Type) -> Self {
+        match ty {
+            Type::Never => Self::Never,
+            Type::True => Self::Bool(Some(true)),
+            Type::False => Self::Bool(Some(false)),
+            Type::Primitive { primitive_type } => {
+                Self::Primitive(BTreeSet::from([primitive_type.clone()]))
+            }
+            Type::Union { arms } => Self::Primitive(arms.clone()),
+            Type::Set { element_type } => Self::Set(
+                element_type
+                    .as_ref()
+                    .map(|element_type| Box::new(Self::compile(element_type))),
+            ),
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
+                Self::Entity(Some(lub.iter().cloned().collect()))
+            }
+            Type::EntityOrRecord(EntityRecordKind::AnyEntity) => Self::Entity(None),
+            Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. }) => {
+                Self::Action(name.clone())
+            }
+            Type::EntityOrRecord(EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            }) => Self::Record(RecordPlan {
+                attrs: attrs
+                    .iter()
+                    .map(|(attr, ty)| (attr.clone(), ty.is_required, Self::compile(&ty.attr_type)))
+                    .collect(),
+                open: open_attributes == &OpenTag::OpenAttributes,
+            }),
+            Type::ExtensionType { name } => Self::Extension(name.clone()),
+            Type::Nullable { inner } => Self::Nullable(Box::new(Self::compile(inner))),
+        }
+    }
+
+    fn check(&self, value: &Value) -> bool {
+        match (self, value.value_kind()) {
+            (Self::Never, _) => false,
+            (Self::Bool(None), ValueKind::Lit(Literal::Bool(_))) => true,
+            (Self::Bool(Some(expected)), ValueKind::Lit(Literal::Bool(b))) => b == expected,
+            (Self::Primitive(types), ValueKind::Lit(lit)) => match lit {
+                Literal::Bool(_) => types.contains(&Primitive::Bool),
+                Literal::Long(_) => types.contains(&Primitive::Long),
+                Literal::String(_) => types.contains(&Primitive::String),
+                Literal::EntityUID(_) | Literal::Null => false,
+            },
+            (Self::Set(None), ValueKind::Set(_)) => true,
+            (Self::Set(Some(plan)), ValueKind::Set(set)) => set.iter().all(|v| plan.check(v)),
+            (Self::Entity(types), ValueKind::Lit(Literal::EntityUID(uid))) => types
+                .as_ref()
+                .map_or(true, |types| types.contains(uid.entity_type())),
+            (Self::Action(name), ValueKind::Lit(Literal::EntityUID(uid))) => {
+                uid.is_action() && uid.entity_type() == name
+            }
+            (Self::Record(plan), ValueKind::Record(attrs)) => plan.check(attrs),
+            (Self::Extension(name), ValueKind::ExtensionValue(ev)) => &ev.typename() == name,
+            (Self::Nullable(_), ValueKind::Lit(Literal::Null)) => true,
+            (Self::Nullable(plan), _) => plan.check(value),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ipaddr", feature = "decimal"))]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::ast::{Context, RestrictedExpr};
+    use cedar_policy_core::extensions::Extensions;
+
+    #[test]
+    fn plan_agrees_with_typechecking() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User;
+            action view appliesTo {
+                principal: User,
+                resource: User,
+                context: {
+                    mfa: Bool,
+                    level?: Long,
+                    tags: Set<String>,
+                    owner: User,
+                    source: { ip: ipaddr, trusted?: Bool },
+                }
+            };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let action = schema
+            .get_action_id(&r#"Action::"view""#.parse().unwrap())
+            .unwrap();
+        let plan = ContextPlan::compile(action.context_type());
+        for (src, expected) in [
+            (
+                r#"{ mfa: true, tags: ["a"], owner: User::"alice", source: { ip: ip("10.0.0.1") } }"#,
+                true,
+            ),
+            (
+                r#"{ mfa: true, level: 3, tags: [], owner: User::"alice", source: { ip: ip("10.0.0.1"), trusted: false } }"#,
+                true,
+            ),
+            // missing required attribute
+            (
+                r#"{ mfa: true, tags: [], source: { ip: ip("10.0.0.1") } }"#,
+                false,
+            ),
+            // undeclared attribute
+            (
+                r#"{ mfa: true, tags: [], owner: User::"alice", source: { ip: ip("10.0.0.1") }, extra: 1 }"#,
+                false,
+            ),
+            // wrong types
+            (
+                r#"{ mfa: true, tags: [1], owner: User::"alice", source: { ip: ip("10.0.0.1") } }"#,
+                false,
+            ),
+            (
+                r#"{ mfa: true, tags: [], owner: Group::"g", source: { ip: ip("10.0.0.1") } }"#,
+                false,
+            ),
+            (
+                r#"{ mfa: true, tags: [], owner: User::"alice", source: { ip: decimal("1.0") } }"#,
+                false,
+            ),
+        ] {
+            let context = Context::from_expr(
+                src.parse::<RestrictedExpr>().unwrap().as_borrowed(),
+                Extensions::all_available(),
+            )
+            .unwrap();
+            let Context::Value(attrs) = &context else {
+                panic!("context should be a value: {src}");
+            };
+            assert_eq!(plan.check(attrs), expected, "{src}");
+            assert_eq!(
+                action
+                    .context_type()
+                    .typecheck_partial_value(&context.clone().into(), Extensions::all_available())
+                    .unwrap(),
+                expected,
+                "{src}"
+            );
+        }
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the definition of `Deprecation`
+
+use serde::Serialize;
+use smol_str::SmolStr;
+use std::collections::BTreeMap;
+
+/// The deprecation of an entity attribute or action, declared in the schema
+/// with the `deprecated` annotation, e.g., `@deprecated` or
+/// `@deprecated("5.0")` in the Cedar schema syntax. The validator warns about
+/// policies referencing deprecated attributes and actions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deprecation {
+    /// The version in which the attribute or action will be removed, which is
+    /// the value of the annotation, if it is not empty
+    removal_version: Option<SmolStr>,
+}
+
+impl Deprecation {
+    /// The key of the annotation declaring a deprecation
+    pub const ANNOTATION: &'static str = "deprecated";
+
+    /// A deprecation with the given removal version, if any
+    pub(crate) fn new(removal_version: Option<SmolStr>) -> Self {
+        Self { removal_version }
+    }
+
+    /// The deprecation declared by the given annotations, if any
+    pub(crate) fn from_annotations(annotations: &BTreeMap<SmolStr, SmolStr>) -> Option<Self> {
+        annotations
+            .get(Self::ANNOTATION)
+            .map(|value| Self::new((!value.is_empty()).then(|| value.clone())))
+    }
+
+    /// The version in which the attribute or action will be removed, if
+    /// declared
+    pub fn removal_version(&self) -> Option<&str> {
+        self.removal_version.as_deref()
+    }
+}
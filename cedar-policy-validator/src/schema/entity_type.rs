@@ -18,16 +18,22 @@
 
 use serde::Serialize;
 use smol_str::SmolStr;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
-use cedar_policy_core::{ast::EntityType, transitive_closure::TCNode};
+use cedar_policy_core::{
+    ast::{EntityType, Expr},
+    transitive_closure::TCNode,
+};
 
-use crate::types::{AttributeType, Attributes, OpenTag};
+use crate::{
+    types::{AttributeType, Attributes, OpenTag},
+    Deprecation,
+};
 
 /// Contains entity type information for use by the validator. The contents of
 /// the struct are the same as the schema entity type structure, but the
 /// `member_of` relation is reversed to instead be `descendants`.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct ValidatorEntityType {
     /// The name of the entity type.
     pub(crate) name: EntityType,
@@ -47,6 +53,21 @@ pub struct ValidatorEntityType {
     /// their type when they are present. Attempting to access an undeclared
     /// attribute under standard validation is an error regardless of this flag.
     pub(crate) open_attributes: OpenTag,
+
+    /// Attributes whose values, entities or sets of entities, are implied
+    /// parents of entities of this type.
+    pub(crate) parent_attributes: Vec<SmolStr>,
+
+    /// Attributes whose values are computed when loading entity data, with
+    /// the expressions computing them.
+    pub(crate) computed_attributes: Vec<(SmolStr, Expr)>,
+
+    /// The entity types this entity type (transitively) extends, nearest
+    /// first. Its attributes include theirs.
+    pub(crate) supertypes: Vec<EntityType>,
+
+    /// Attributes which are deprecated, including inherited ones.
+    pub(crate) deprecated_attributes: BTreeMap<SmolStr, Deprecation>,
 }
 
 impl ValidatorEntityType {
@@ -60,6 +81,33 @@ impl ValidatorEntityType {
         self.attributes.iter()
     }
 
+    /// The attributes whose values are implied parents of entities of this
+    /// type, so that entity data doesn't need to list them as parents
+    pub fn parent_attributes(&self) -> impl Iterator<Item = &SmolStr> {
+        self.parent_attributes.iter()
+    }
+
+    /// The computed attributes of this entity type, with the expressions
+    /// computing their values from the other attributes, which are the
+    /// `context` of the expressions
+    pub fn computed_attributes(&self) -> impl Iterator<Item = (&SmolStr, &Expr)> {
+        self.computed_attributes
+            .iter()
+            .map(|(attr, expr)| (attr, expr))
+    }
+
+    /// The entity types this entity type (transitively) extends, nearest
+    /// first
+    pub fn supertypes(&self) -> impl Iterator<Item = &EntityType> {
+        self.supertypes.iter()
+    }
+
+    /// The deprecation of the attribute with the given name, if it is
+    /// deprecated
+    pub fn attribute_deprecation(&self, attr: &str) -> Option<&Deprecation> {
+        self.deprecated_attributes.get(attr)
+    }
+
     /// Return `true` if this entity type has an [`EntityType`] declared as a
     /// possible descendant in the schema.
     pub fn has_descendant_entity_type(&self, ety: &EntityType) -> bool {
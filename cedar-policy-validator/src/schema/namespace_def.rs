@@ -38,7 +38,7 @@ use crate::{
     fuzzy_match::fuzzy_search,
     json_schema::{self, CommonTypeId},
     types::{AttributeType, Attributes, OpenTag, Type},
-    ActionBehavior, ConditionalName, RawName, ReferenceType,
+    ActionBehavior, ConditionalName, Deprecation, RawName, ReferenceType,
 };
 
 /// A single namespace definition from the schema JSON or Cedar syntax,
@@ -454,6 +454,24 @@ pub struct EntityTypeFragment<N> {
     /// We will check for undeclared parent types when combining fragments into
     /// a [`crate::ValidatorSchema`].
     pub(super) parents: HashSet<N>,
+    /// Entity type this entity type extends, if any. It may be declared in a
+    /// different namespace or schema fragment. We will check that it is
+    /// declared, and that no entity type (transitively) extends itself, when
+    /// combining fragments into a [`crate::ValidatorSchema`].
+    pub(super) extends: Option<N>,
+    /// Attributes whose values are implied parents of entities of this type.
+    /// We will check that they are declared with entity types in `parents`
+    /// when combining fragments into a [`crate::ValidatorSchema`].
+    pub(super) parent_attributes: Vec<SmolStr>,
+    /// Computed attributes of this entity type, with the source of the
+    /// expressions computing them. We will parse the expressions and check
+    /// that the attributes are declared in `attributes` when combining
+    /// fragments into a [`crate::ValidatorSchema`].
+    pub(super) computed_attributes: BTreeMap<SmolStr, SmolStr>,
+    /// Deprecated attributes of this entity type. Only attributes declared
+    /// directly in the entity type's shape, rather than in a common type, may
+    /// be deprecated.
+    pub(super) deprecated_attributes: BTreeMap<SmolStr, Deprecation>,
 }
 
 impl EntityTypeFragment<ConditionalName> {
@@ -464,6 +482,17 @@ impl EntityTypeFragment<ConditionalName> {
         schema_file_type: json_schema::EntityType<RawName>,
         schema_namespace: Option<&InternalName>,
     ) -> Self {
+        let deprecated_attributes = match &schema_file_type.shape.0 {
+            json_schema::Type::Type(json_schema::TypeVariant::Record(rty)) => rty
+                .attributes
+                .iter()
+                .filter_map(|(attr, ty)| {
+                    Deprecation::from_annotations(&ty.annotations)
+                        .map(|deprecation| (attr.clone(), deprecation))
+                })
+                .collect(),
+            _ => BTreeMap::new(),
+        };
         Self {
             attributes: schema_file_type
                 .shape
@@ -476,6 +505,12 @@ impl EntityTypeFragment<ConditionalName> {
                     raw_name.conditionally_qualify_with(schema_namespace, ReferenceType::Entity)
                 })
                 .collect(),
+            extends: schema_file_type.extends.map(|raw_name| {
+                raw_name.conditionally_qualify_with(schema_namespace, ReferenceType::Entity)
+            }),
+            parent_attributes: schema_file_type.parent_attributes,
+            computed_attributes: schema_file_type.computed_attributes,
+            deprecated_attributes,
         }
     }
 
@@ -497,12 +532,18 @@ impl EntityTypeFragment<ConditionalName> {
             .into_iter()
             .map(|parent| parent.resolve(all_defs))
             .collect::<Result<_, TypeNotDefinedError>>()?;
-        // Now is the time to check whether any parents are dangling, i.e.,
-        // refer to entity types that are not declared in any fragment (since we
-        // now have the set of typenames that are declared in all fragments).
+        let extends = self
+            .extends
+            .map(|extends| extends.resolve(all_defs))
+            .transpose()?;
+        // Now is the time to check whether any parents, or the extended type,
+        // are dangling, i.e., refer to entity types that are not declared in
+        // any fragment (since we now have the set of typenames that are
+        // declared in all fragments).
         let undeclared_parents: Option<NonEmpty<ConditionalName>> = NonEmpty::collect(
             parents
                 .iter()
+                .chain(extends.iter())
                 .filter(|ety| !all_defs.is_defined_as_entity(ety))
                 .map(|ety| ConditionalName::unconditional(ety.clone(), ReferenceType::Entity)),
         );
@@ -510,6 +551,10 @@ impl EntityTypeFragment<ConditionalName> {
             (Ok(attributes), None) => Ok(EntityTypeFragment {
                 attributes,
                 parents,
+                extends,
+                parent_attributes: self.parent_attributes,
+                computed_attributes: self.computed_attributes,
+                deprecated_attributes: self.deprecated_attributes,
             }),
             (Ok(_), Some(undeclared_parents)) => Err(TypeNotDefinedError(undeclared_parents)),
             (Err(e), None) => Err(e),
@@ -630,6 +675,8 @@ pub struct ActionFragment<N, A> {
     /// separately so that we can later extract these values to construct the
     /// actual `Entity` objects defined by the schema.
     pub(super) attributes: BTreeMap<SmolStr, PartialValueSerializedAsExpr>,
+    /// The deprecation of this action, if it is deprecated.
+    pub(super) deprecation: Option<Deprecation>,
 }
 
 impl ActionFragment<ConditionalName, ConditionalName> {
@@ -654,6 +701,7 @@ impl ActionFragment<ConditionalName, ConditionalName> {
             action_uid,
             extensions,
         )?;
+        let deprecation = Deprecation::from_annotations(&action_type.annotations);
         Ok(Self {
             context: context
                 .into_inner()
@@ -680,6 +728,7 @@ impl ActionFragment<ConditionalName, ConditionalName> {
                 .collect(),
             attribute_types,
             attributes,
+            deprecation,
         })
     }
 
@@ -707,6 +756,7 @@ impl ActionFragment<ConditionalName, ConditionalName> {
                 .collect::<Result<_, SchemaError>>()?,
             attribute_types: self.attribute_types,
             attributes: self.attributes,
+            deprecation: self.deprecation,
         })
     }
 
@@ -917,6 +967,7 @@ pub(crate) fn try_jsonschema_type_into_validator_type(
         json_schema::Type::Type(json_schema::TypeVariant::Boolean) => {
             Ok(Type::primitive_boolean().into())
         }
+        json_schema::Type::Type(json_schema::TypeVariant::Null) => Ok(Type::null().into()),
         json_schema::Type::Type(json_schema::TypeVariant::Set { element }) => {
             Ok(try_jsonschema_type_into_validator_type(*element, extensions)?.map(Type::set))
         }
@@ -946,6 +997,22 @@ pub(crate) fn try_jsonschema_type_into_validator_type(
                 ))
             }
         }
+        json_schema::Type::Type(json_schema::TypeVariant::Union { arms }) => {
+            let arms = arms
+                .into_iter()
+                .map(|arm| try_jsonschema_type_into_validator_type(arm, extensions))
+                .collect::<crate::err::Result<Vec<_>>>()?;
+            Ok(WithUnresolvedCommonTypeRefs::new(move |common_type_defs| {
+                let arms = arms
+                    .into_iter()
+                    .map(|arm| arm.resolve_common_type_refs(common_type_defs))
+                    .collect::<crate::err::Result<Vec<_>>>()?;
+                // Only primitive types can be unioned, since the values of
+                // distinct primitive types are distinguished in entity data.
+                // Any type can be unioned with `Null`, though.
+                Type::union(arms).map_err(|arm| InvalidUnionTypeError { arm }.into())
+            }))
+        }
         json_schema::Type::CommonTypeRef { type_name } => {
             Ok(WithUnresolvedCommonTypeRefs::new(move |common_type_defs| {
                 common_type_defs
@@ -0,0 +1,64 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Source locations of the declarations in a schema, used to point
+//! diagnostics about the schema itself at the relevant declaration.
+
+use std::collections::HashMap;
+
+use cedar_policy_core::ast::{EntityType, EntityUID};
+use cedar_policy_core::parser::Loc;
+use smol_str::SmolStr;
+
+/// Source locations of entity type, attribute, and action declarations.
+///
+/// Locations are only known for schemas parsed from the Cedar schema syntax;
+/// for other schemas this table is empty.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SchemaSourceLocs {
+    entity_types: HashMap<EntityType, Loc>,
+    attributes: HashMap<(EntityType, SmolStr), Loc>,
+    actions: HashMap<EntityUID, Loc>,
+}
+
+impl SchemaSourceLocs {
+    pub(crate) fn add_entity_type(&mut self, ety: EntityType, loc: Loc) {
+        self.entity_types.insert(ety, loc);
+    }
+
+    pub(crate) fn add_attribute(&mut self, ety: EntityType, attr: SmolStr, loc: Loc) {
+        self.attributes.insert((ety, attr), loc);
+    }
+
+    pub(crate) fn add_action(&mut self, action: EntityUID, loc: Loc) {
+        self.actions.insert(action, loc);
+    }
+
+    /// Location of the declaration of `ety`, if known
+    pub(crate) fn entity_type(&self, ety: &EntityType) -> Option<&Loc> {
+        self.entity_types.get(ety)
+    }
+
+    /// Location of the declaration of attribute `attr` of `ety`, if known
+    pub(crate) fn attribute(&self, ety: &EntityType, attr: &SmolStr) -> Option<&Loc> {
+        self.attributes.get(&(ety.clone(), attr.clone()))
+    }
+
+    /// Location of the declaration of `action`, if known
+    pub(crate) fn action(&self, action: &EntityUID) -> Option<&Loc> {
+        self.actions.get(action)
+    }
+}
@@ -23,25 +23,35 @@ pub(crate) mod test;
 mod typecheck_answer;
 pub(crate) use typecheck_answer::TypecheckAnswer;
 
-use std::{borrow::Cow, collections::HashSet, iter::zip};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{BTreeSet, HashSet},
+    iter::zip,
+    time::Instant,
+};
 
 use crate::{
     extension_schema::ExtensionFunctionType,
     extensions::ExtensionSchemas,
-    fuzzy_match::fuzzy_search,
+    fuzzy_match::fuzzy_search_within,
+    profile::{self, RequestEnvProfile},
     schema::ValidatorSchema,
     types::{
         AttributeType, Capability, CapabilitySet, EntityRecordKind, OpenTag, Primitive, RequestEnv,
         Type,
     },
-    validation_errors::{AttributeAccess, LubContext, UnexpectedTypeHelp},
-    ValidationError, ValidationMode, ValidationWarning,
+    validation_errors::{AttributeAccess, LubContext, TypeSizeLimit, UnexpectedTypeHelp},
+    TypecheckExpressionError, ValidationError, ValidationMode, ValidationWarning,
 };
 
 use cedar_policy_core::ast::{
-    BinaryOp, EntityType, EntityUID, Expr, ExprBuilder, ExprKind, Literal, Name, PolicyID,
+    BinaryOp, Context, EntityType, EntityUID, Expr, ExprBuilder, ExprKind, Literal, Name, PolicyID,
     PrincipalOrResourceConstraint, SlotId, Template, UnaryOp, Var,
 };
+use cedar_policy_core::optimizer;
+use cedar_policy_core::parser::Loc;
+use smol_str::SmolStr;
 
 #[cfg(not(target_arch = "wasm32"))]
 const REQUIRED_STACK_SPACE: usize = 1024 * 100;
@@ -65,6 +75,10 @@ pub struct Typechecker<'a> {
     extensions: &'static ExtensionSchemas<'static>,
     mode: ValidationMode,
     policy_id: PolicyID,
+    max_suggestion_distance: Option<usize>,
+    max_record_width: Option<usize>,
+    max_set_depth: Option<usize>,
+    profile: Option<&'a RefCell<Vec<RequestEnvProfile>>>,
 }
 
 impl<'a> Typechecker<'a> {
@@ -81,9 +95,77 @@ impl<'a> Typechecker<'a> {
             extensions,
             mode,
             policy_id,
+            max_suggestion_distance: None,
+            max_record_width: None,
+            max_set_depth: None,
+            profile: None,
         }
     }
 
+    /// Only suggest corrections for misspelled attributes which are within
+    /// this edit distance of the misspelling. By default, the closest
+    /// attribute of the accessed type is always suggested, and attributes
+    /// found elsewhere are suggested only if they are within one edit per
+    /// three characters of the misspelling.
+    pub(crate) fn with_max_suggestion_distance(mut self, max_distance: Option<usize>) -> Self {
+        self.max_suggestion_distance = max_distance;
+        self
+    }
+
+    /// Do not compute least upper bounds of types with records of more than
+    /// `max_record_width` attributes or with more than `max_set_depth` nested
+    /// sets, and report a type error instead. This bounds the work done to
+    /// typecheck adversarial policies. By default, there is no limit.
+    pub(crate) fn with_type_size_limits(
+        mut self,
+        max_record_width: Option<usize>,
+        max_set_depth: Option<usize>,
+    ) -> Self {
+        self.max_record_width = max_record_width;
+        self.max_set_depth = max_set_depth;
+        self
+    }
+
+    /// Record the time spent and the least upper bounds computed typechecking
+    /// in each request environment to `profile`.
+    pub(crate) fn with_profile(mut self, profile: &'a RefCell<Vec<RequestEnvProfile>>) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Suggest an attribute which `attr` may be a misspelling of. We search,
+    /// in order, the attributes of the accessed type `ty`; the attributes of
+    /// any of the entity types in `ty`, if it is a least upper bound of
+    /// several; and the attributes declared anywhere in the schema, including
+    /// in common types.
+    fn suggest_attribute(&self, ty: &Type, attr: &str) -> Option<String> {
+        let close_distance = self
+            .max_suggestion_distance
+            .unwrap_or(std::cmp::max(attr.chars().count(), 3) / 3);
+        // An attribute with the same name is not a useful suggestion
+        let others =
+            |attrs: Vec<SmolStr>| attrs.into_iter().filter(|a| a != attr).collect::<Vec<_>>();
+        fuzzy_search_within(
+            attr,
+            &ty.all_attributes(self.schema),
+            self.max_suggestion_distance,
+        )
+        .or_else(|| {
+            fuzzy_search_within(
+                attr,
+                &others(ty.attributes_of_any_element(self.schema)),
+                Some(close_distance),
+            )
+        })
+        .or_else(|| {
+            fuzzy_search_within(
+                attr,
+                &others(self.schema.all_attribute_names().into_iter().collect()),
+                Some(close_distance),
+            )
+        })
+    }
+
     /// The main entry point for typechecking policies. Checks that the policy
     /// expression has type boolean. If typechecking succeeds, then the method
     /// will return true, and no items will be added to the output list.
@@ -127,9 +209,340 @@ impl<'a> Typechecker<'a> {
             ));
         }
 
+        // Only consider the request environments which the scope of `t` may
+        // match.
+        let scope = Expr::and(
+            Expr::and(
+                t.principal_constraint().as_expr(),
+                t.action_constraint().as_expr(),
+            ),
+            t.resource_constraint().as_expr(),
+        );
+        let request_envs = self
+            .unlinked_request_envs()
+            .flat_map(|env| self.link_request_env(env, t))
+            .filter(|env| !matches!(self.typecheck_expression(&scope, env), Ok(Type::False)))
+            .collect::<Vec<_>>();
+        warnings.extend(self.incompatible_entity_equalities(t, &request_envs));
+        warnings.extend(self.string_typed_entity_references(t, &request_envs));
+        warnings.extend(self.like_patterns_without_wildcards(t));
+        warnings.extend(self.unguarded_current_time_accesses(t));
+        warnings.extend(self.guaranteed_overflows(t));
+        warnings.extend(self.deprecated_references(t, &request_envs));
+
         all_succ
     }
 
+    /// Find the `==` comparisons in the conditions of `t` whose operands are
+    /// entities of disjoint types in every request environment where both
+    /// operands typecheck, so that the comparison is always false. Operands
+    /// are typechecked in isolation, so we conservatively ignore request
+    /// environments where they only typecheck given the capabilities
+    /// established by other parts of the condition.
+    fn incompatible_entity_equalities(
+        &self,
+        t: &Template,
+        request_envs: &[RequestEnv<'_>],
+    ) -> Vec<ValidationWarning> {
+        let entity_types = |env: &RequestEnv<'_>, e: &Expr| match self.typecheck_expression(e, env)
+        {
+            Ok(Type::EntityOrRecord(EntityRecordKind::Entity(lub))) => {
+                Some(lub.iter().cloned().collect::<BTreeSet<_>>())
+            }
+            Ok(Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. })) => {
+                Some(BTreeSet::from([name]))
+            }
+            _ => None,
+        };
+        t.non_scope_constraints()
+            .subexpressions()
+            .filter_map(|e| {
+                let ExprKind::BinaryApp {
+                    op: BinaryOp::Eq,
+                    arg1,
+                    arg2,
+                } = e.expr_kind()
+                else {
+                    return None;
+                };
+                let mut lhs_types = BTreeSet::new();
+                let mut rhs_types = BTreeSet::new();
+                for env in request_envs {
+                    if let (Some(lhs), Some(rhs)) =
+                        (entity_types(env, arg1), entity_types(env, arg2))
+                    {
+                        if !lhs.is_disjoint(&rhs) {
+                            return None;
+                        }
+                        lhs_types.extend(lhs);
+                        rhs_types.extend(rhs);
+                    }
+                }
+                (!lhs_types.is_empty()).then(|| {
+                    ValidationWarning::incompatible_entity_equality(
+                        e.source_loc().cloned(),
+                        self.policy_id.clone(),
+                        lhs_types,
+                        rhs_types,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Find the `==` comparisons in the conditions of `t` between a string
+    /// attribute and a string literal which looks like an entity uid, e.g.,
+    /// `resource.owner == "User::\"alice\""`. These usually indicate that the
+    /// attribute should be declared with an entity type instead.
+    fn string_typed_entity_references(
+        &self,
+        t: &Template,
+        request_envs: &[RequestEnv<'_>],
+    ) -> Vec<ValidationWarning> {
+        let as_entity_reference = |e: &Expr| match e.expr_kind() {
+            ExprKind::Lit(Literal::String(s)) => s.parse::<EntityUID>().ok(),
+            _ => None,
+        };
+        t.non_scope_constraints()
+            .subexpressions()
+            .filter_map(|e| {
+                let ExprKind::BinaryApp {
+                    op: BinaryOp::Eq,
+                    arg1,
+                    arg2,
+                } = e.expr_kind()
+                else {
+                    return None;
+                };
+                let (access, euid) = match (as_entity_reference(arg1), as_entity_reference(arg2)) {
+                    (None, Some(euid)) => (arg1, euid),
+                    (Some(euid), None) => (arg2, euid),
+                    _ => return None,
+                };
+                let ExprKind::GetAttr { attr, .. } = access.expr_kind() else {
+                    return None;
+                };
+                let is_string = request_envs.iter().any(|env| {
+                    matches!(
+                        self.typecheck_expression(access, env),
+                        Ok(Type::Primitive {
+                            primitive_type: Primitive::String
+                        })
+                    )
+                });
+                is_string.then(|| {
+                    ValidationWarning::string_typed_entity_reference(
+                        e.source_loc().cloned(),
+                        self.policy_id.clone(),
+                        attr.clone(),
+                        euid,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Find the `like` expressions in the conditions of `t` whose patterns
+    /// have no wildcards, which are equivalent to `==`. (Malformed patterns,
+    /// e.g., with invalid escapes, are already rejected by the parser.)
+    fn like_patterns_without_wildcards(&self, t: &Template) -> Vec<ValidationWarning> {
+        t.non_scope_constraints()
+            .subexpressions()
+            .filter_map(|e| match e.expr_kind() {
+                ExprKind::Like { pattern, .. } if !pattern.has_wildcard() => {
+                    Some(ValidationWarning::like_without_wildcard(
+                        e.source_loc().cloned(),
+                        self.policy_id.clone(),
+                        pattern.to_string(),
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Find the accesses to the current time in the context (see
+    /// [`Context::CURRENT_TIME_ATTR`]) in the conditions of `t` which are not
+    /// guarded by a check that it is present. The check is syntactic: an
+    /// access is guarded if it is on the right of a `&&` whose left operand
+    /// checks `context has now`, in the `then` branch of an `if` testing it,
+    /// or the `else` branch of an `if` (or right of a `||`) testing its
+    /// negation. This is independent of whether the schema declares the
+    /// attribute as optional.
+    fn unguarded_current_time_accesses(&self, t: &Template) -> Vec<ValidationWarning> {
+        let mut warnings = Vec::new();
+        self.find_unguarded_current_time(t.non_scope_constraints(), false, &mut warnings);
+        warnings
+    }
+
+    /// Find the arithmetic operations in the conditions of `t` which always
+    /// overflow. These are the operations whose operands are Long literals
+    /// after constant folding (see [`optimizer::optimize`]), which are not
+    /// folded since evaluating them fails. Operations in branches which
+    /// constant folding shows are never evaluated are not reported.
+    fn guaranteed_overflows(&self, t: &Template) -> Vec<ValidationWarning> {
+        let is_long = |e: &Expr| matches!(e.expr_kind(), ExprKind::Lit(Literal::Long(_)));
+        optimizer::optimize(t.non_scope_constraints())
+            .subexpressions()
+            .filter(|e| match e.expr_kind() {
+                ExprKind::UnaryApp {
+                    op: UnaryOp::Neg,
+                    arg,
+                } => is_long(arg),
+                ExprKind::BinaryApp {
+                    op: BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul,
+                    arg1,
+                    arg2,
+                } => is_long(arg1) && is_long(arg2),
+                _ => false,
+            })
+            .map(|e| {
+                ValidationWarning::guaranteed_overflow(
+                    e.source_loc().cloned(),
+                    self.policy_id.clone(),
+                    e.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Find the references in `t` to entity attributes and actions which are
+    /// deprecated in the schema. An attribute access (or `has` check) is
+    /// reported if, in some request environment, the accessed expression may
+    /// be an entity of a type whose attribute is deprecated. An action is
+    /// reported if it is named in the action scope constraint or an entity
+    /// literal in the conditions, but not if it is only a member of a named
+    /// action group.
+    fn deprecated_references(
+        &self,
+        t: &Template,
+        request_envs: &[RequestEnv<'_>],
+    ) -> Vec<ValidationWarning> {
+        // The accessed expression is typechecked in isolation, so its type may
+        // only be known with type errors, e.g., when it accesses an optional
+        // attribute checked with `has` elsewhere in the condition. We still
+        // use the type to find the entity types it may have.
+        let entity_types = |env: &RequestEnv<'_>, e: &Expr| {
+            let ans = self.typecheck(env, &CapabilitySet::new(), e, &mut Vec::new());
+            match ans.into_typed_expr().and_then(|typed| typed.into_data()) {
+                Some(ty) => match ty.non_null() {
+                    Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
+                        lub.iter().cloned().collect()
+                    }
+                    _ => Vec::new(),
+                },
+                None => Vec::new(),
+            }
+        };
+        let deprecated_action = |source_loc: Option<&Loc>, action: &EntityUID| {
+            let deprecation = self.schema.get_action_id(action)?.deprecation()?;
+            Some(ValidationWarning::deprecated_action(
+                source_loc.cloned(),
+                self.policy_id.clone(),
+                action.clone(),
+                deprecation,
+            ))
+        };
+        let scope_warnings = t
+            .action_constraint()
+            .iter_euids()
+            .filter_map(|action| deprecated_action(t.loc(), action));
+        let condition_warnings =
+            t.non_scope_constraints()
+                .subexpressions()
+                .flat_map(|e| match e.expr_kind() {
+                    ExprKind::Lit(Literal::EntityUID(action)) => {
+                        deprecated_action(e.source_loc(), action)
+                            .into_iter()
+                            .collect()
+                    }
+                    ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr } => {
+                        request_envs
+                            .iter()
+                            .flat_map(|env| entity_types(env, expr))
+                            .collect::<BTreeSet<_>>()
+                            .into_iter()
+                            .filter_map(|entity_type| {
+                                let deprecation = self
+                                    .schema
+                                    .get_entity_type(&entity_type)?
+                                    .attribute_deprecation(attr)?;
+                                Some(ValidationWarning::deprecated_attribute(
+                                    e.source_loc().cloned(),
+                                    self.policy_id.clone(),
+                                    entity_type,
+                                    attr.clone(),
+                                    deprecation,
+                                ))
+                            })
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                });
+        scope_warnings.chain(condition_warnings).collect()
+    }
+
+    fn find_unguarded_current_time(
+        &self,
+        e: &Expr,
+        guarded: bool,
+        warnings: &mut Vec<ValidationWarning>,
+    ) {
+        let mut find =
+            |e: &Expr, guarded: bool| self.find_unguarded_current_time(e, guarded, warnings);
+        match e.expr_kind() {
+            ExprKind::GetAttr { expr, attr }
+                if attr == Context::CURRENT_TIME_ATTR
+                    && matches!(expr.expr_kind(), ExprKind::Var(Var::Context)) =>
+            {
+                if !guarded {
+                    warnings.push(ValidationWarning::unguarded_current_time(
+                        e.source_loc().cloned(),
+                        self.policy_id.clone(),
+                        attr.clone(),
+                    ));
+                }
+            }
+            ExprKind::And { left, right } => {
+                find(left, guarded);
+                find(right, guarded || checks_current_time(left));
+            }
+            ExprKind::Or { left, right } => {
+                find(left, guarded);
+                find(right, guarded || refutes_current_time(left));
+            }
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => {
+                find(test_expr, guarded);
+                find(then_expr, guarded || checks_current_time(test_expr));
+                find(else_expr, guarded || refutes_current_time(test_expr));
+            }
+            ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => (),
+            ExprKind::UnaryApp { arg: expr, .. }
+            | ExprKind::GetAttr { expr, .. }
+            | ExprKind::HasAttr { expr, .. }
+            | ExprKind::Like { expr, .. }
+            | ExprKind::Is { expr, .. } => find(expr, guarded),
+            ExprKind::BinaryApp { arg1, arg2, .. } => {
+                find(arg1, guarded);
+                find(arg2, guarded);
+            }
+            ExprKind::ExtensionFunctionApp { args: elems, .. } | ExprKind::Set(elems) => {
+                for e in elems.iter() {
+                    find(e, guarded);
+                }
+            }
+            ExprKind::Record(attrs) => {
+                for e in attrs.values() {
+                    find(e, guarded);
+                }
+            }
+        }
+    }
+
     /// Secondary entry point for typechecking requests. This method takes a policy and
     /// typechecks it under every schema-defined request environment. The result contains
     /// these environments and the individual typechecking response for each, in no
@@ -139,6 +552,9 @@ impl<'a> Typechecker<'a> {
         t: &'b Template,
     ) -> Vec<(RequestEnv<'_>, PolicyCheck)> {
         self.apply_typecheck_fn_by_request_env(t, |request, expr| {
+            if Self::scope_excludes(t, request) {
+                return PolicyCheck::Irrelevant(Vec::new());
+            }
             let mut type_errors = Vec::new();
             let empty_prior_capability = CapabilitySet::new();
             let ty = self.expect_type(
@@ -160,6 +576,29 @@ impl<'a> Typechecker<'a> {
         })
     }
 
+    /// Typecheck a standalone expression, rather than a policy, in
+    /// `request_env` and return its inferred type. Unlike a policy condition,
+    /// the expression may have any type. Type errors are reported against the
+    /// policy id this typechecker was constructed with.
+    pub fn typecheck_expression(
+        &self,
+        e: &Expr,
+        request_env: &RequestEnv<'_>,
+    ) -> Result<Type, TypecheckExpressionError> {
+        let mut type_errors = Vec::new();
+        let ans = self.typecheck(request_env, &CapabilitySet::new(), e, &mut type_errors);
+        let typechecked = ans.typechecked();
+        match ans.into_typed_expr() {
+            None => Err(TypecheckExpressionError::RecursionLimit),
+            Some(typed) => match typed.into_data() {
+                Some(ty) if typechecked => Ok(ty),
+                _ => Err(TypecheckExpressionError::Type {
+                    errors: type_errors,
+                }),
+            },
+        }
+    }
+
     /// Utility abstracting the common logic for strict and regular typechecking
     /// by request environment.
     fn apply_typecheck_fn_by_request_env<'b, F, C>(
@@ -181,7 +620,20 @@ impl<'a> Typechecker<'a> {
             .unlinked_request_envs()
             .flat_map(|env| self.link_request_env(env, t))
         {
-            let check = typecheck_fn(&requeste, policy_condition);
+            let check = match self.profile {
+                Some(profile) => {
+                    let start = Instant::now();
+                    let lubs = profile::lub_computations();
+                    let check = typecheck_fn(&requeste, policy_condition);
+                    profile.borrow_mut().push(RequestEnvProfile::new(
+                        requeste.to_request_type(),
+                        start.elapsed(),
+                        profile::lub_computations().wrapping_sub(lubs),
+                    ));
+                    check
+                }
+                None => typecheck_fn(&requeste, policy_condition),
+            };
             result_checks.push((requeste, check))
         }
         result_checks
@@ -202,6 +654,10 @@ impl<'a> Typechecker<'a> {
             for t in policy_templates.iter() {
                 let condition_expr = t.condition();
                 for linked_env in self.link_request_env(request.clone(), t) {
+                    if Self::scope_excludes(t, &linked_env) {
+                        policy_checks.push(PolicyCheck::Irrelevant(Vec::new()));
+                        continue;
+                    }
                     let mut type_errors = Vec::new();
                     let empty_prior_capability = CapabilitySet::new();
                     let ty = self.expect_type(
@@ -228,38 +684,15 @@ impl<'a> Typechecker<'a> {
     }
 
     fn unlinked_request_envs(&self) -> impl Iterator<Item = RequestEnv<'_>> + '_ {
-        // Gather all of the actions declared in the schema.
-        let all_actions = self
-            .schema
-            .known_action_ids()
-            .filter_map(|a| self.schema.get_action_id(a));
-
-        // For every action compute the cross product of the principal and
-        // resource applies_to sets.
-        all_actions
-            .flat_map(|action| {
-                action.applies_to_principals().flat_map(|principal| {
-                    action
-                        .applies_to_resources()
-                        .map(|resource| RequestEnv::DeclaredAction {
-                            principal,
-                            action: &action.name,
-                            resource,
-                            context: &action.context,
-                            principal_slot: None,
-                            resource_slot: None,
-                        })
-                })
-            })
-            .chain(if self.mode.is_partial() {
-                // A partial schema might not list all actions, and may not
-                // include all principal and resource types for the listed ones.
-                // So we typecheck with a fully unknown request to handle these
-                // missing cases.
-                Some(RequestEnv::UndeclaredAction)
-            } else {
-                None
-            })
+        self.schema.request_envs().chain(if self.mode.is_partial() {
+            // A partial schema might not list all actions, and may not
+            // include all principal and resource types for the listed ones.
+            // So we typecheck with a fully unknown request to handle these
+            // missing cases.
+            Some(RequestEnv::UndeclaredAction)
+        } else {
+            None
+        })
     }
 
     /// Given a request environment and a template, return new environments
@@ -350,6 +783,27 @@ impl<'a> Typechecker<'a> {
         }
     }
 
+    /// Whether an `is` constraint in the scope of `t`, like `resource is Photo`
+    /// or `resource is Photo in ?folder`, excludes the principal or resource
+    /// type of `request_env`. The condition of `t` starts with its scope
+    /// constraints, so it would typecheck as `false` without errors in this
+    /// environment, and we skip typechecking it.
+    fn scope_excludes(t: &Template, request_env: &RequestEnv<'_>) -> bool {
+        let excludes = |constraint: &PrincipalOrResourceConstraint, ety: Option<&EntityType>| {
+            matches!(
+                (constraint.get_entity_type(), ety),
+                (Some(required), Some(ety)) if required != ety
+            )
+        };
+        excludes(
+            t.principal_constraint().as_inner(),
+            request_env.principal_entity_type(),
+        ) || excludes(
+            t.resource_constraint().as_inner(),
+            request_env.resource_entity_type(),
+        )
+    }
+
     /// This method handles the majority of the work. Given an expression,
     /// the type for the request, and the prior capability, return the result of
     /// typechecking the expression, and add any errors encountered into the
@@ -449,6 +903,11 @@ impl<'a> Typechecker<'a> {
                     .with_same_source_loc(e)
                     .val(val.clone()),
             ),
+            ExprKind::Lit(Literal::Null) => TypecheckAnswer::success(
+                ExprBuilder::with_data(Some(Type::null()))
+                    .with_same_source_loc(e)
+                    .val(Literal::Null),
+            ),
 
             // Literal entity reference have a type based on the entity type
             // that can be looked up in the schema.
@@ -462,7 +921,7 @@ impl<'a> Typechecker<'a> {
                     // partial schema. The attributes record will be empty if we
                     // try to access it later, so all attributes will have the
                     // bottom type.
-                    None if self.mode.is_partial() => TypecheckAnswer::success(
+                    None if self.mode.allows_undeclared_entity_types() => TypecheckAnswer::success(
                         ExprBuilder::with_data(Some(Type::named_entity_reference(
                             euid.entity_type().clone(),
                         )))
@@ -795,8 +1254,8 @@ impl<'a> Typechecker<'a> {
 
                 actual.then_typecheck(|typ_expr_actual, _| match typ_expr_actual.data() {
                     Some(typ_actual) => {
-                        let all_attrs = typ_actual.all_attributes(self.schema);
-                        let attr_ty = Type::lookup_attribute_type(self.schema, typ_actual, attr);
+                        let attr_ty = Type::lookup_attribute_type(self.schema, typ_actual, attr)
+                            .map(|ty| Self::narrow_attribute(prior_capability, expr, attr, ty));
                         let annot_expr = ExprBuilder::with_data(
                             attr_ty.clone().map(|attr_ty| attr_ty.attr_type),
                         )
@@ -833,9 +1292,12 @@ impl<'a> Typechecker<'a> {
                             // In partial schema validation, if we can't find
                             // the attribute but there may be additional
                             // attributes, we do not fail and instead return the
-                            // bottom type (`Never`).
-                            None if self.mode.is_partial()
-                                && Type::may_have_attr(self.schema, typ_actual, attr) =>
+                            // bottom type (`Never`). The same applies to
+                            // attributes of opaque entity types.
+                            None if (self.mode.is_partial()
+                                && Type::may_have_attr(self.schema, typ_actual, attr))
+                                || (self.mode.allows_undeclared_entity_types()
+                                    && typ_actual.has_undeclared_entity_type(self.schema)) =>
                             {
                                 TypecheckAnswer::success(
                                     ExprBuilder::with_data(Some(Type::Never))
@@ -844,9 +1306,7 @@ impl<'a> Typechecker<'a> {
                                 )
                             }
                             None => {
-                                let borrowed =
-                                    all_attrs.iter().map(|s| s.as_str()).collect::<Vec<_>>();
-                                let suggestion = fuzzy_search(attr, &borrowed);
+                                let suggestion = self.suggest_attribute(typ_actual, attr);
                                 type_errors.push(ValidationError::unsafe_attribute_access(
                                     e.source_loc().cloned(),
                                     self.policy_id.clone(),
@@ -1202,6 +1662,19 @@ impl<'a> Typechecker<'a> {
                             arg2,
                             rhs_ty.data(),
                         );
+                        // Comparing an attribute with a union or nullable
+                        // type to a value of one of its arms narrows the
+                        // attribute to that arm when the comparison is true.
+                        let narrowing = Self::attribute_narrowing(
+                            arg1,
+                            lhs_ty.data().as_ref(),
+                            rhs_ty.data().as_ref(),
+                        )
+                        .union(&Self::attribute_narrowing(
+                            arg2,
+                            rhs_ty.data().as_ref(),
+                            lhs_ty.data().as_ref(),
+                        ));
 
                         if self.mode.is_strict() {
                             let annotated_eq = ExprBuilder::with_data(Some(type_of_eq))
@@ -1215,11 +1688,13 @@ impl<'a> Typechecker<'a> {
                                 type_errors,
                                 LubContext::Equality,
                             )
+                            .map_capability(|capability| capability.union(&narrowing))
                         } else {
-                            TypecheckAnswer::success(
+                            TypecheckAnswer::success_with_capability(
                                 ExprBuilder::with_data(Some(type_of_eq))
                                     .with_same_source_loc(bin_expr)
                                     .binary_app(*op, lhs_ty, rhs_ty),
+                                narrowing,
                             )
                         }
                     })
@@ -1438,7 +1913,15 @@ impl<'a> Typechecker<'a> {
             }
             _ => match (lhs_ty, rhs_ty) {
                 (Some(lhs_ty), Some(rhs_ty)) => {
-                    if let Err(lub_hint) =
+                    if let Some(limit) = self.exceeded_type_size_limit([lhs_ty, rhs_ty]) {
+                        type_errors.push(ValidationError::type_too_large(
+                            unannotated_expr.source_loc().cloned(),
+                            self.policy_id.clone(),
+                            limit,
+                            context,
+                        ));
+                        TypecheckAnswer::fail(annotated_expr)
+                    } else if let Err(lub_hint) =
                         Type::least_upper_bound(self.schema, lhs_ty, rhs_ty, self.mode)
                     {
                         type_errors.push(ValidationError::incompatible_types(
@@ -1665,9 +2148,14 @@ impl<'a> Typechecker<'a> {
                                         // regardless of their exact types (i.e., their namespaces), so we shouldn't treat it as an error.
                                         let action_in_action =
                                             lhs_name.is_action() && rhs_name.is_action();
+                                        // The hierarchy of opaque entity types is unknown.
+                                        let opaque = self.mode.allows_undeclared_entity_types()
+                                            && (!self.schema.is_known_entity_type(&lhs_name)
+                                                || !self.schema.is_known_entity_type(&rhs_name));
                                         if lhs_name == rhs_name
                                             || action_in_action
                                             || lhs_ty_in_rhs_ty
+                                            || opaque
                                         {
                                             TypecheckAnswer::success(type_of_in)
                                         } else {
@@ -1800,7 +2288,7 @@ impl<'a> Typechecker<'a> {
                             ExprBuilder::with_data(Some(Type::primitive_boolean()))
                                 .with_same_source_loc(in_expr)
                                 .is_in(lhs_expr, rhs_expr);
-                        if self.mode.is_partial() {
+                        if self.mode.allows_undeclared_entity_types() {
                             // In partial schema mode, undeclared entity types are
                             // expected.
                             TypecheckAnswer::success(annotated_expr)
@@ -1941,7 +2429,7 @@ impl<'a> Typechecker<'a> {
             let annotated_expr = ExprBuilder::with_data(Some(Type::primitive_boolean()))
                 .with_same_source_loc(in_expr)
                 .is_in(lhs_expr, rhs_expr);
-            if self.mode.is_partial() {
+            if self.mode.allows_undeclared_entity_types() {
                 TypecheckAnswer::success(annotated_expr)
             } else {
                 TypecheckAnswer::fail(annotated_expr)
@@ -1998,9 +2486,12 @@ impl<'a> Typechecker<'a> {
                     type_errors,
                     |_| None,
                 );
+                // `!(e.attr == null)`, i.e., `e.attr != null`, means the
+                // attribute is not `null` when it evaluates to `true`.
+                let non_null = Self::non_null_narrowing(arg);
                 ans_arg.then_typecheck(|typ_expr_arg, _| match typ_expr_arg.data() {
-                    Some(typ_arg) => {
-                        TypecheckAnswer::success(if typ_arg == &Type::singleton_boolean(true) {
+                    Some(typ_arg) => TypecheckAnswer::success_with_capability(
+                        if typ_arg == &Type::singleton_boolean(true) {
                             ExprBuilder::with_data(Some(Type::singleton_boolean(false)))
                                 .with_same_source_loc(unary_expr)
                                 .not(typ_expr_arg)
@@ -2012,8 +2503,9 @@ impl<'a> Typechecker<'a> {
                             ExprBuilder::with_data(Some(Type::primitive_boolean()))
                                 .with_same_source_loc(unary_expr)
                                 .not(typ_expr_arg)
-                        })
-                    }
+                        },
+                        non_null,
+                    ),
                     None => TypecheckAnswer::fail(
                         ExprBuilder::with_data(Some(Type::primitive_boolean()))
                             .with_same_source_loc(unary_expr)
@@ -2041,6 +2533,128 @@ impl<'a> Typechecker<'a> {
         }
     }
 
+    /// If `actual` is a union type where some, but not all, arms are subtypes
+    /// of one of the `expected` types, return the arms which are not.
+    fn invalid_union_arms(&self, actual: &Type, expected: &[Type]) -> Option<Vec<Type>> {
+        let Type::Union { arms } = actual else {
+            return None;
+        };
+        let (valid_arms, invalid_arms): (Vec<_>, Vec<_>) = arms
+            .iter()
+            .map(|arm| Type::Primitive {
+                primitive_type: arm.clone(),
+            })
+            .partition(|arm| {
+                expected.iter().any(|expected_ty| {
+                    Type::is_subtype(self.schema, arm, expected_ty, ValidationMode::Permissive)
+                })
+            });
+        if valid_arms.is_empty() {
+            None
+        } else {
+            Some(invalid_arms)
+        }
+    }
+
+    /// Narrow the type of the attribute `attr` of `expr` using what the prior
+    /// capability records about it. If `attr_ty` is nullable and the attribute
+    /// is known not to be `null`, remove `null` from its type. If the
+    /// (remaining) type is a union and the attribute is known to have one of
+    /// its arms, narrow it to that arm.
+    fn narrow_attribute(
+        prior_capability: &CapabilitySet<'_>,
+        expr: &Expr,
+        attr: &str,
+        attr_ty: AttributeType,
+    ) -> AttributeType {
+        let attr_ty = match attr_ty.attr_type {
+            Type::Nullable { inner }
+                if prior_capability.contains(&Capability::non_null(expr, attr)) =>
+            {
+                AttributeType::new(*inner, attr_ty.is_required)
+            }
+            _ => attr_ty,
+        };
+        let Type::Union { arms } = &attr_ty.attr_type else {
+            return attr_ty;
+        };
+        match arms.iter().find(|arm| {
+            prior_capability.contains(&Capability::narrowed(expr, attr, (*arm).clone()))
+        }) {
+            Some(arm) => AttributeType::new(
+                Type::Primitive {
+                    primitive_type: arm.clone(),
+                },
+                attr_ty.is_required,
+            ),
+            None => attr_ty,
+        }
+    }
+
+    /// If `operand` is an attribute access with a union or nullable type, and
+    /// `other_ty` is one of the arms of that union or a non-null type, return
+    /// capabilities narrowing the attribute accordingly. Otherwise, return an
+    /// empty capability set.
+    fn attribute_narrowing<'b>(
+        operand: &'b Expr,
+        operand_ty: Option<&Type>,
+        other_ty: Option<&Type>,
+    ) -> CapabilitySet<'b> {
+        let (ExprKind::GetAttr { expr, attr }, Some(operand_ty)) =
+            (operand.expr_kind(), operand_ty)
+        else {
+            return CapabilitySet::new();
+        };
+        let non_null = match (operand_ty, other_ty) {
+            (Type::Nullable { .. }, Some(other_ty))
+                if !matches!(other_ty, Type::Nullable { .. } | Type::Never) =>
+            {
+                CapabilitySet::singleton(Capability::non_null(expr, attr))
+            }
+            _ => CapabilitySet::new(),
+        };
+        let Type::Union { arms } = operand_ty.non_null() else {
+            return non_null;
+        };
+        let arm = match other_ty {
+            Some(Type::True | Type::False) => Primitive::Bool,
+            Some(Type::Primitive { primitive_type }) => primitive_type.clone(),
+            _ => return non_null,
+        };
+        if arms.contains(&arm) {
+            non_null.union(&CapabilitySet::singleton(Capability::narrowed(
+                expr, attr, arm,
+            )))
+        } else {
+            non_null
+        }
+    }
+
+    /// If `arg` is a comparison of an attribute access with `null`, return a
+    /// capability recording that the attribute is not `null` when `!arg`
+    /// evaluates to `true`. Otherwise, return an empty capability set.
+    fn non_null_narrowing(arg: &Expr) -> CapabilitySet<'_> {
+        let ExprKind::BinaryApp {
+            op: BinaryOp::Eq,
+            arg1,
+            arg2,
+        } = arg.expr_kind()
+        else {
+            return CapabilitySet::new();
+        };
+        let attr_access = match (arg1.expr_kind(), arg2.expr_kind()) {
+            (ExprKind::Lit(Literal::Null), _) => arg2,
+            (_, ExprKind::Lit(Literal::Null)) => arg1,
+            _ => return CapabilitySet::new(),
+        };
+        match attr_access.expr_kind() {
+            ExprKind::GetAttr { expr, attr } => {
+                CapabilitySet::singleton(Capability::non_null(expr, attr))
+            }
+            _ => CapabilitySet::new(),
+        }
+    }
+
     /// Check that an expression has a type that is a subtype of one of the
     /// given types. If not, generate a type error and return `TypecheckFail`.
     /// Return `TypecheckSuccess` with the type otherwise.
@@ -2075,13 +2689,42 @@ impl<'a> Typechecker<'a> {
                         ValidationMode::Permissive,
                     )
                 }) {
-                    type_errors.push(ValidationError::expected_one_of_types(
-                        expr.source_loc().cloned(),
-                        self.policy_id.clone(),
-                        expected.to_vec(),
-                        actual_ty.clone(),
-                        type_error_help(actual_ty),
-                    ));
+                    match self.invalid_union_arms(actual_ty, expected) {
+                        // Some arms of the union are expected, so the
+                        // expression can be narrowed to one of those arms.
+                        Some(invalid_arms) => {
+                            type_errors.push(ValidationError::invalid_union_arms(
+                                expr.source_loc().cloned(),
+                                self.policy_id.clone(),
+                                expected.to_vec(),
+                                actual_ty.clone(),
+                                invalid_arms,
+                            ))
+                        }
+                        None => type_errors.push(ValidationError::expected_one_of_types(
+                            expr.source_loc().cloned(),
+                            self.policy_id.clone(),
+                            expected.to_vec(),
+                            actual_ty.clone(),
+                            match actual_ty {
+                                // The value would be fine if it were known
+                                // not to be `null`.
+                                Type::Nullable { inner }
+                                    if expected.iter().any(|expected_ty| {
+                                        Type::is_subtype(
+                                            self.schema,
+                                            inner,
+                                            expected_ty,
+                                            ValidationMode::Permissive,
+                                        )
+                                    }) =>
+                                {
+                                    Some(UnexpectedTypeHelp::TryCheckingForNull)
+                                }
+                                _ => type_error_help(actual_ty),
+                            },
+                        )),
+                    }
                     // Some code (e.g., typechecking And) depends on
                     // `expect_type` not returning an expression with a type
                     // other than one of the expected types. At the same time,
@@ -2144,6 +2787,15 @@ impl<'a> Typechecker<'a> {
             // defined.
             .collect::<Option<Vec<_>>>()
             .and_then(|typechecked_types| {
+                if let Some(limit) = self.exceeded_type_size_limit(&typechecked_types) {
+                    type_errors.push(ValidationError::type_too_large(
+                        expr.source_loc().cloned(),
+                        self.policy_id.clone(),
+                        limit,
+                        context,
+                    ));
+                    return None;
+                }
                 let lub =
                     Type::reduce_to_least_upper_bound(self.schema, &typechecked_types, self.mode);
                 match lub {
@@ -2166,6 +2818,24 @@ impl<'a> Typechecker<'a> {
             })
     }
 
+    /// The type size limit exceeded by any of `types`, if any, in which case
+    /// their least upper bound should not be computed
+    fn exceeded_type_size_limit<'t>(
+        &self,
+        types: impl IntoIterator<Item = &'t Type>,
+    ) -> Option<TypeSizeLimit> {
+        types.into_iter().find_map(|ty| {
+            self.max_record_width
+                .filter(|max| ty.max_record_width() > *max)
+                .map(TypeSizeLimit::RecordWidth)
+                .or_else(|| {
+                    self.max_set_depth
+                        .filter(|max| ty.set_depth() > *max)
+                        .map(TypeSizeLimit::SetDepth)
+                })
+        })
+    }
+
     /// If the `maybe_action_var` expression is `Expr::Var(Var::Action)`, return
     /// a expression for the entity uid for the action variable in the request
     /// environment. Otherwise, return the expression unchanged.
@@ -2306,3 +2976,29 @@ impl<'a> Typechecker<'a> {
         }
     }
 }
+
+/// Whether `e` is true only if the current time is in the context, i.e., it
+/// is `context has now`, or a conjunction with it as an operand
+fn checks_current_time(e: &Expr) -> bool {
+    match e.expr_kind() {
+        ExprKind::HasAttr { expr, attr } => {
+            attr == Context::CURRENT_TIME_ATTR
+                && matches!(expr.expr_kind(), ExprKind::Var(Var::Context))
+        }
+        ExprKind::And { left, right } => checks_current_time(left) || checks_current_time(right),
+        _ => false,
+    }
+}
+
+/// Whether `e` is false only if the current time is in the context, i.e.,
+/// it is the negation of an expression for which [`checks_current_time`]
+/// holds
+fn refutes_current_time(e: &Expr) -> bool {
+    match e.expr_kind() {
+        ExprKind::UnaryApp {
+            op: UnaryOp::Not,
+            arg,
+        } => checks_current_time(arg),
+        _ => false,
+    }
+}
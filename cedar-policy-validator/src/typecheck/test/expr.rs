@@ -18,7 +18,7 @@
 //! policy and without a schema.
 // GRCOV_STOP_COVERAGE
 
-use std::{str::FromStr, vec};
+use std::{collections::BTreeMap, str::FromStr, vec};
 
 use cedar_policy_core::ast::{BinaryOp, EntityUID, Expr, PatternElem, SlotId, Var};
 use serde_json::json;
@@ -60,6 +60,10 @@ fn slot_typechecks() {
 fn slot_in_typechecks() {
     let etype = json_schema::EntityType {
         member_of_types: vec![],
+        extends: None,
+        parent_attributes: vec![],
+        computed_attributes: BTreeMap::new(),
+        annotations: BTreeMap::new(),
         shape: json_schema::AttributesOrContext::default(),
     };
     let schema = json_schema::NamespaceDefinition::new([("typename".parse().unwrap(), etype)], []);
@@ -89,6 +93,10 @@ fn slot_in_typechecks() {
 fn slot_equals_typechecks() {
     let etype = json_schema::EntityType {
         member_of_types: vec![],
+        extends: None,
+        parent_attributes: vec![],
+        computed_attributes: BTreeMap::new(),
+        annotations: BTreeMap::new(),
         shape: json_schema::AttributesOrContext::default(),
     };
     // These don't typecheck in strict mode because the test_util expression
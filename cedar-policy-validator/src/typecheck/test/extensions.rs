@@ -178,3 +178,28 @@ fn decimal_extension_typecheck_fails() {
         )],
     );
 }
+
+#[test]
+#[cfg(feature = "bitwise")]
+fn bitwise_extension_typechecks() {
+    let expr = Expr::from_str("7.bitAnd(3).bitOr(8).shiftLeft(2)").expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_long());
+    let expr = Expr::from_str("7.bitXor(3).bitNot()").expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_long());
+    let expr = Expr::from_str("7.hasBits(3)").expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_boolean());
+
+    let src = "7.hasBits(\"3\")";
+    let expr = Expr::from_str(src).expect("parsing should succeed");
+    assert_typecheck_fails_empty_schema(
+        expr,
+        Type::primitive_boolean(),
+        [ValidationError::expected_type(
+            get_loc(src, "\"3\""),
+            expr_id_placeholder(),
+            Type::primitive_long(),
+            Type::primitive_string(),
+            None,
+        )],
+    );
+}
@@ -20,6 +20,7 @@
 
 use cool_asserts::assert_matches;
 use serde_json::json;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 use std::vec;
 
@@ -572,18 +573,24 @@ fn multi_namespace_action_eq() {
         .unwrap(),
     );
 
-    let policy = parse_policy(
-        None,
-        r#"permit(principal, action, resource) when { NS1::Action::"B" == NS2::Action::"B" };"#,
-    )
-    .unwrap();
+    let src =
+        r#"permit(principal, action, resource) when { NS1::Action::"B" == NS2::Action::"B" };"#;
+    let policy = parse_policy(None, src).unwrap();
     assert_policy_typecheck_warns(
         schema.clone(),
         policy.clone(),
-        [ValidationWarning::impossible_policy(
-            policy.loc().cloned(),
-            PolicyID::from_string("policy0"),
-        )],
+        [
+            ValidationWarning::impossible_policy(
+                policy.loc().cloned(),
+                PolicyID::from_string("policy0"),
+            ),
+            ValidationWarning::incompatible_entity_equality(
+                get_loc(src, r#"NS1::Action::"B" == NS2::Action::"B""#),
+                PolicyID::from_string("policy0"),
+                BTreeSet::from(["NS1::Action".parse().unwrap()]),
+                BTreeSet::from(["NS2::Action".parse().unwrap()]),
+            ),
+        ],
     );
 }
 
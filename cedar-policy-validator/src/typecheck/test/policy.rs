@@ -18,10 +18,12 @@
 //! files.
 // GRCOV_STOP_COVERAGE
 
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
 use cedar_policy_core::{
     ast::{EntityUID, Expr, PolicyID, Template},
+    extensions::Extensions,
     parser::{parse_policy, parse_policy_or_template},
 };
 
@@ -36,7 +38,7 @@ use crate::{
     typecheck::{PolicyCheck, Typechecker},
     types::{EntityLUB, Type},
     validation_errors::{AttributeAccess, LubContext, LubHelp},
-    RawName, ValidationMode, ValidationWarning,
+    Deprecation, RawName, ValidationMode, ValidationWarning,
 };
 
 fn simple_schema_file() -> json_schema::NamespaceDefinition<RawName> {
@@ -163,6 +165,237 @@ fn assert_policy_typecheck_permissive_warns_simple_schema(
     )
 }
 
+#[test]
+fn incompatible_entity_equality() {
+    let src = r#"permit(principal, action == Action::"view_photo", resource) when { principal == resource };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns_simple_schema(
+        p.clone(),
+        [
+            ValidationWarning::impossible_policy(
+                get_loc(src, src),
+                PolicyID::from_string("policy0"),
+            ),
+            ValidationWarning::incompatible_entity_equality(
+                get_loc(src, "principal == resource"),
+                PolicyID::from_string("policy0"),
+                BTreeSet::from(["User".parse().unwrap(), "Group".parse().unwrap()]),
+                BTreeSet::from(["Photo".parse().unwrap()]),
+            ),
+        ],
+    );
+
+    // The comparison may be true when the principal is a `User`
+    let p = parse_policy(
+        None,
+        r#"permit(principal, action == Action::"view_photo", resource) when { principal == resource.owner };"#,
+    )
+    .unwrap();
+    assert_policy_typecheck_warns_simple_schema(p, []);
+}
+
+#[test]
+fn string_typed_entity_reference() {
+    let src = r#"permit(principal, action == Action::"view_photo", resource) when { principal.name == "User::\"alice\"" };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns_simple_schema(
+        p,
+        [ValidationWarning::string_typed_entity_reference(
+            get_loc(src, r#"principal.name == "User::\"alice\"""#),
+            PolicyID::from_string("policy0"),
+            "name".into(),
+            r#"User::"alice""#.parse().unwrap(),
+        )],
+    );
+
+    // Strings which are not entity uids are not flagged
+    let p = parse_policy(
+        None,
+        r#"permit(principal, action == Action::"view_photo", resource) when { principal.name == "alice" };"#,
+    )
+    .unwrap();
+    assert_policy_typecheck_warns_simple_schema(p, []);
+}
+
+#[test]
+fn like_without_wildcard() {
+    let src = r#"permit(principal, action == Action::"view_photo", resource) when { resource.file_type like "png" && principal.name like "a*" };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns_simple_schema(
+        p,
+        [ValidationWarning::like_without_wildcard(
+            get_loc(src, r#"resource.file_type like "png""#),
+            PolicyID::from_string("policy0"),
+            "png".to_string(),
+        )],
+    );
+}
+
+#[test]
+fn guaranteed_overflow() {
+    let src = r#"permit(principal is User, action == Action::"view_photo", resource) when { principal.age < 9223372036854775807 + (2 - 1) };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns_simple_schema(
+        p,
+        [ValidationWarning::guaranteed_overflow(
+            get_loc(src, "9223372036854775807 + (2 - 1)"),
+            PolicyID::from_string("policy0"),
+            "9223372036854775807 + 1".to_string(),
+        )],
+    );
+
+    // Arithmetic which does not overflow, or which is never evaluated, is not
+    // flagged
+    let p = parse_policy(
+        None,
+        r#"permit(principal is User, action == Action::"view_photo", resource) when { principal.age < 9223372036854775806 + 1 || (true || principal.age < 9223372036854775807 + 1) };"#,
+    )
+    .unwrap();
+    assert_policy_typecheck_warns_simple_schema(p, []);
+}
+
+#[test]
+fn unguarded_current_time() {
+    let schema: json_schema::NamespaceDefinition<RawName> = serde_json::from_str(
+        r#"
+        {
+            "entityTypes": { "User": {} },
+            "actions": {
+                "view": {
+                    "appliesTo": {
+                        "principalTypes": ["User"],
+                        "resourceTypes": ["User"],
+                        "context": {
+                            "type": "Record",
+                            "attributes": { "now": { "type": "Long" } }
+                        }
+                    }
+                }
+            }
+        }"#,
+    )
+    .expect("Expected valid schema");
+
+    let src = r#"permit(principal, action, resource) when { context.now < 100 };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns(
+        schema.clone(),
+        p,
+        [ValidationWarning::unguarded_current_time(
+            get_loc(src, "context.now"),
+            PolicyID::from_string("policy0"),
+            "now".into(),
+        )],
+    );
+
+    for src in [
+        r#"permit(principal, action, resource) when { context has now && context.now < 100 };"#,
+        r#"permit(principal, action, resource) when { if context has now then context.now < 100 else false };"#,
+        r#"permit(principal, action, resource) unless { !(context has now) || context.now > 100 };"#,
+    ] {
+        let p = parse_policy(None, src).unwrap();
+        assert_policy_typecheck_warns(schema.clone(), p, []);
+    }
+}
+
+#[test]
+fn deprecated_references() {
+    let schema: json_schema::NamespaceDefinition<RawName> = serde_json::from_str(
+        r#"
+        {
+            "entityTypes": {
+                "User": {
+                    "shape": {
+                        "type": "Record",
+                        "attributes": {
+                            "name": { "type": "String" },
+                            "email": { "type": "String", "annotations": { "deprecated": "5.0" } },
+                            "nickname": { "type": "String", "required": false, "annotations": { "deprecated": "" } }
+                        }
+                    }
+                }
+            },
+            "actions": {
+                "view": {
+                    "annotations": { "deprecated": "" },
+                    "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] }
+                },
+                "edit": {
+                    "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] }
+                }
+            }
+        }"#,
+    )
+    .expect("Expected valid schema");
+
+    let src = r#"permit(principal, action == Action::"view", resource) when { principal.email like "*@example.com" && resource has nickname };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns(
+        schema.clone(),
+        p,
+        [
+            ValidationWarning::deprecated_action(
+                get_loc(src, src),
+                PolicyID::from_string("policy0"),
+                r#"Action::"view""#.parse().unwrap(),
+                &Deprecation::new(None),
+            ),
+            ValidationWarning::deprecated_attribute(
+                get_loc(src, "principal.email"),
+                PolicyID::from_string("policy0"),
+                "User".parse().unwrap(),
+                "email".into(),
+                &Deprecation::new(Some("5.0".into())),
+            ),
+            ValidationWarning::deprecated_attribute(
+                get_loc(src, "resource has nickname"),
+                PolicyID::from_string("policy0"),
+                "User".parse().unwrap(),
+                "nickname".into(),
+                &Deprecation::new(None),
+            ),
+        ],
+    );
+
+    let p = parse_policy(
+        None,
+        r#"permit(principal, action == Action::"edit", resource) when { principal.name == "alice" };"#,
+    )
+    .unwrap();
+    assert_policy_typecheck_warns(schema, p, []);
+
+    // Deprecations are inherited by entity types extending the declaring type
+    let (schema, _) = json_schema::Fragment::from_cedarschema_str(
+        r#"
+        entity User { @deprecated("5.0") email: String, name: String };
+        entity Admin extends User;
+        @deprecated("6.0") action edit appliesTo { principal: Admin, resource: Admin };"#,
+        Extensions::all_available(),
+    )
+    .unwrap();
+    let src = r#"permit(principal, action, resource) when { resource.email == principal.name || action == Action::"edit" };"#;
+    let p = parse_policy(None, src).unwrap();
+    assert_policy_typecheck_warns(
+        schema,
+        p,
+        [
+            ValidationWarning::deprecated_attribute(
+                get_loc(src, "resource.email"),
+                PolicyID::from_string("policy0"),
+                "Admin".parse().unwrap(),
+                "email".into(),
+                &Deprecation::new(Some("5.0".into())),
+            ),
+            ValidationWarning::deprecated_action(
+                get_loc(src, r#"Action::"edit""#),
+                PolicyID::from_string("policy0"),
+                r#"Action::"edit""#.parse().unwrap(),
+                &Deprecation::new(Some("6.0".into())),
+            ),
+        ],
+    );
+}
+
 #[test]
 fn entity_literal_typechecks() {
     assert_typechecks_simple_schema(
@@ -235,6 +468,47 @@ fn policy_checked_in_multiple_envs() {
     );
 }
 
+#[test]
+fn scope_is_excludes_envs() {
+    let schema = simple_schema_file()
+        .try_into()
+        .expect("Failed to construct schema.");
+    let typechecker = Typechecker::new(
+        &schema,
+        ValidationMode::default(),
+        PolicyID::from_string("0"),
+    );
+    for (src, irrelevant) in [
+        // only `User, "delete_group", Group` is excluded
+        (
+            r#"permit(principal, action, resource is Photo) when { resource.file_type == "jpg" };"#,
+            1,
+        ),
+        // only `User, "view_photo", Photo` remains. `Group, "view_photo", Photo`
+        // is excluded once for each type of `?resource`, `Photo` and `Album`.
+        (
+            r#"permit(principal is User, action, resource is Photo in ?resource) when { resource.file_type == "jpg" };"#,
+            3,
+        ),
+    ] {
+        let t = parse_policy_or_template(Some(PolicyID::from_string("0")), src)
+            .expect("Policy should parse.");
+        let env_checks = typechecker.typecheck_by_request_env(&t);
+        assert_eq!(
+            env_checks
+                .iter()
+                .filter(
+                    |(_, check)| matches!(check, PolicyCheck::Irrelevant(errs) if errs.is_empty())
+                )
+                .count(),
+            irrelevant
+        );
+        assert!(env_checks
+            .iter()
+            .any(|(_, check)| matches!(check, PolicyCheck::Success(_))));
+    }
+}
+
 #[test]
 fn policy_single_action_attribute_access() {
     assert_policy_typechecks_simple_schema(parse_policy(
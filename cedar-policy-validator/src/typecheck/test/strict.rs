@@ -34,10 +34,13 @@ use crate::{
     types::{AttributeType, CapabilitySet, OpenTag, RequestEnv, Type},
     validation_errors::LubContext,
     validation_errors::LubHelp,
+    validation_errors::UnexpectedTypeHelp,
     RawName, ValidationError, ValidationMode,
 };
 
-use super::test_utils::{assert_policy_typecheck_fails, expr_id_placeholder, get_loc};
+use super::test_utils::{
+    assert_policy_typecheck_fails, assert_policy_typechecks, expr_id_placeholder, get_loc,
+};
 
 #[track_caller] // report the caller's location as the location of the panic, not the location in this function
 fn assert_typechecks_strict(
@@ -746,3 +749,103 @@ fn qualified_record_attr() {
         )],
     );
 }
+
+#[test]
+fn extended_entity_types_lub() {
+    let (schema, _) = json_schema::Fragment::from_cedarschema_str(
+        r#"
+        entity User;
+        entity Album;
+        entity Resource { owner: User };
+        entity Photo extends Resource { size: Long };
+        entity Document extends Resource;
+        action view appliesTo { principal: User, resource: Resource, context: { flag: Bool } };"#,
+        Extensions::all_available(),
+    )
+    .unwrap();
+
+    // entity types extending a common entity type have a least upper bound,
+    // with the attributes of that entity type
+    let src = r#"permit(principal, action, resource) when { (if context.flag then Photo::"p" else Document::"d").owner == principal && [resource, Photo::"p"].contains(Document::"d") };"#;
+    assert_policy_typechecks(schema.clone(), parse_policy_or_template(None, src).unwrap());
+
+    let src = r#"permit(principal, action, resource) when { if context.flag then Photo::"p" else Album::"a" };"#;
+    let p = parse_policy_or_template(None, src).unwrap();
+    assert_policy_typecheck_fails(
+        schema,
+        p,
+        [ValidationError::incompatible_types(
+            get_loc(src, r#"if context.flag then Photo::"p" else Album::"a""#),
+            PolicyID::from_string("policy0"),
+            [
+                Type::named_entity_reference_from_str("Photo"),
+                Type::named_entity_reference_from_str("Album"),
+            ],
+            LubHelp::EntityType,
+            LubContext::Conditional,
+        )],
+    );
+}
+
+#[test]
+fn union_attribute_narrowing() {
+    let (schema, _) = json_schema::Fragment::from_cedarschema_str(
+        r#"
+        entity User { code: String | Long };
+        action view appliesTo { principal: User, resource: User };"#,
+        Extensions::all_available(),
+    )
+    .unwrap();
+
+    // comparing the attribute to a value of one of its arms narrows it to
+    // that arm in the rest of the conjunction
+    let src = r#"permit(principal, action, resource) when { (principal.code == 0 && principal.code < 10) || (principal.code == "admin" && principal.code like "adm*") || principal.code == resource.code };"#;
+    assert_policy_typechecks(schema.clone(), parse_policy_or_template(None, src).unwrap());
+
+    let src = r#"permit(principal, action, resource) when { principal.code < 10 };"#;
+    let p = parse_policy_or_template(None, src).unwrap();
+    assert_policy_typecheck_fails(
+        schema,
+        p,
+        [ValidationError::invalid_union_arms(
+            get_loc(src, "principal.code"),
+            PolicyID::from_string("policy0"),
+            [Type::primitive_long()],
+            Type::union([Type::primitive_string(), Type::primitive_long()]).unwrap(),
+            [Type::primitive_string()],
+        )],
+    );
+}
+
+#[test]
+fn nullable_attribute_narrowing() {
+    let (schema, _) = json_schema::Fragment::from_cedarschema_str(
+        r#"
+        entity User { age: Long | Null, manager: User | Null };
+        action view appliesTo { principal: User, resource: User };"#,
+        Extensions::all_available(),
+    )
+    .unwrap();
+
+    // checking that the attribute is not `null` (or equal to some non-null
+    // value) removes `null` from its type in the rest of the conjunction
+    #[cfg(feature = "null-literal")]
+    {
+        let src = r#"permit(principal, action, resource) when { (principal.age != null && principal.age < 10) || (null != principal.manager && principal.manager.age == null) || (principal.age == 3 && principal.age > 2) || principal.age == resource.age };"#;
+        assert_policy_typechecks(schema.clone(), parse_policy_or_template(None, src).unwrap());
+    }
+
+    let src = r#"permit(principal, action, resource) when { principal.age < 10 };"#;
+    let p = parse_policy_or_template(None, src).unwrap();
+    assert_policy_typecheck_fails(
+        schema,
+        p,
+        [ValidationError::expected_one_of_types(
+            get_loc(src, "principal.age"),
+            PolicyID::from_string("policy0"),
+            [Type::primitive_long()],
+            Type::nullable(Type::primitive_long()),
+            Some(UnexpectedTypeHelp::TryCheckingForNull),
+        )],
+    );
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Defines [`ValidatedPolicy`], which exposes the types the typechecker
+//! inferred for the expressions in a policy.
+
+use cedar_policy_core::ast::{Expr, PolicyID, RequestType};
+use cedar_policy_core::parser::Loc;
+
+use crate::types::Type;
+
+/// A policy (or template) which passed validation, together with the types
+/// inferred for each of its subexpressions.
+///
+/// A policy is typechecked separately for each request environment (i.e.,
+/// each combination of principal type, action, and resource type) it may
+/// apply to, so a subexpression may have a different type in each request
+/// environment.
+#[derive(Debug, Clone)]
+pub struct ValidatedPolicy {
+    id: PolicyID,
+    typed_conditions: Vec<(Option<RequestType>, Expr<Option<Type>>)>,
+}
+
+impl ValidatedPolicy {
+    pub(crate) fn new(
+        id: PolicyID,
+        typed_conditions: Vec<(Option<RequestType>, Expr<Option<Type>>)>,
+    ) -> Self {
+        Self {
+            id,
+            typed_conditions,
+        }
+    }
+
+    /// The id of the policy
+    pub fn id(&self) -> &PolicyID {
+        &self.id
+    }
+
+    /// The policy condition (including the scope constraints), annotated with
+    /// types, for each request environment in which the policy may apply. The
+    /// request type is `None` for the environment representing undeclared
+    /// actions in partial schema validation.
+    pub fn typed_conditions(
+        &self,
+    ) -> impl Iterator<Item = (Option<&RequestType>, &Expr<Option<Type>>)> {
+        self.typed_conditions
+            .iter()
+            .map(|(request_type, expr)| (request_type.as_ref(), expr))
+    }
+
+    /// The type of the innermost subexpression whose source location contains
+    /// `loc`, in each request environment in which the policy may apply.
+    /// This is intended for queries like IDE hovers, where `loc` may be a
+    /// single position (an empty span) in the policy source.
+    pub fn type_at<'a>(
+        &'a self,
+        loc: &'a Loc,
+    ) -> impl Iterator<Item = (Option<&'a RequestType>, &'a Type)> + 'a {
+        self.typed_conditions()
+            .filter_map(move |(request_type, expr)| {
+                expr.subexpressions()
+                    .filter(|e| {
+                        e.source_loc()
+                            .is_some_and(|l| l.start() <= loc.start() && loc.end() <= l.end())
+                    })
+                    .min_by_key(|e| e.source_loc().map(|l| l.end() - l.start()))
+                    .and_then(|e| e.data().as_ref())
+                    .map(|ty| (request_type, ty))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ValidationMode, Validator, ValidatorSchema};
+    use cedar_policy_core::{extensions::Extensions, parser::parse_policyset};
+
+    #[test]
+    fn type_at() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { age: Long, name: String };
+            entity Admin = { age: Long };
+            action view appliesTo { principal: [User, Admin], resource: User };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let src = r#"permit(principal, action, resource) when { principal.age > resource.age && resource.name like "a*" };"#;
+        let policies = parse_policyset(src).unwrap();
+        let typed = Validator::new(schema)
+            .typed_policies(&policies, ValidationMode::Strict)
+            .unwrap();
+        assert_eq!(typed.len(), 1);
+        assert_eq!(typed[0].typed_conditions().count(), 2);
+
+        let loc_of = |snippet: &str| {
+            let start = src.find(snippet).unwrap();
+            typed[0]
+                .typed_conditions()
+                .next()
+                .unwrap()
+                .1
+                .source_loc()
+                .unwrap()
+                .span((start, snippet.len()))
+        };
+        let types_at = |snippet: &str| {
+            let loc = loc_of(snippet);
+            let mut types = typed[0]
+                .type_at(&loc)
+                .map(|(_, ty)| ty.to_string())
+                .collect::<Vec<_>>();
+            types.sort();
+            types
+        };
+        assert_eq!(types_at("principal.age"), vec!["Long", "Long"]);
+        assert_eq!(types_at("principal").len(), 2);
+        assert_eq!(types_at("resource.name"), vec!["String", "String"]);
+        assert_eq!(types_at(r#"resource.name like "a*""#), vec!["Bool", "Bool"]);
+    }
+
+    #[test]
+    fn invalid_policies() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let policies =
+            parse_policyset("permit(principal, action, resource) when { principal.age > 1 };")
+                .unwrap();
+        assert!(Validator::new(schema)
+            .typed_policies(&policies, ValidationMode::Strict)
+            .is_err());
+    }
+}
@@ -82,6 +82,25 @@ pub enum Type {
         /// Name of the extension type
         name: Name,
     },
+
+    /// Union of two or more primitive types, e.g., `String | Long`, for
+    /// attributes whose values may have any of these types
+    Union {
+        /// The primitive types of the values of this type
+        //
+        // INVARIANT: Set of at least two types.
+        arms: BTreeSet<Primitive>,
+    },
+
+    /// Values of the type `inner`, or `null`. The type of the `null` literal
+    /// is `Nullable` with `inner` being `Never`.
+    Nullable {
+        /// The type of the non-null values of this type
+        //
+        // INVARIANT: Not itself `Nullable`.
+        #[serde(rename = "innerType")]
+        inner: Box<Type>,
+    },
 }
 
 impl Type {
@@ -186,6 +205,85 @@ impl Type {
         Type::ExtensionType { name }
     }
 
+    /// The type of the `null` literal
+    pub(crate) fn null() -> Type {
+        Type::Nullable {
+            inner: Box::new(Type::Never),
+        }
+    }
+
+    /// Construct the type of values of type `ty` or `null`
+    pub(crate) fn nullable(ty: Type) -> Type {
+        match ty {
+            ty @ Type::Nullable { .. } => ty,
+            ty => Type::Nullable {
+                inner: Box::new(ty),
+            },
+        }
+    }
+
+    /// The type of the non-null values of this type
+    pub(crate) fn non_null(&self) -> &Type {
+        match self {
+            Type::Nullable { inner } => inner,
+            ty => ty,
+        }
+    }
+
+    /// Construct the union of `arms`. Arms which are `null` or nullable make
+    /// the union nullable. Of the remaining arms, either there is only one, or
+    /// all must be primitive types, in which case the union is just the
+    /// primitive type if all arms are the same type. Returns the first arm
+    /// which is not allowed in a union, if any.
+    pub(crate) fn union(arms: impl IntoIterator<Item = Type>) -> Result<Type, Type> {
+        let mut nullable = false;
+        let arms = arms
+            .into_iter()
+            .filter_map(|arm| match arm {
+                Type::Nullable { inner } => {
+                    nullable = true;
+                    match *inner {
+                        Type::Never => None,
+                        inner => Some(inner),
+                    }
+                }
+                arm => Some(arm),
+            })
+            .collect::<Vec<_>>();
+        let ty = match arms.as_slice() {
+            [] => Type::Never,
+            [arm] => arm.clone(),
+            _ => {
+                let arms = arms
+                    .into_iter()
+                    .map(|arm| match arm {
+                        Type::Primitive { primitive_type } => Ok(primitive_type),
+                        arm => Err(arm),
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+                let mut iter = arms.iter();
+                match (iter.next(), iter.next()) {
+                    (Some(primitive_type), None) => Type::Primitive {
+                        primitive_type: primitive_type.clone(),
+                    },
+                    _ => Type::Union { arms },
+                }
+            }
+        };
+        Ok(if nullable { Type::nullable(ty) } else { ty })
+    }
+
+    /// The primitive types of the values of this type, if it is a primitive
+    /// type or a union of primitive types
+    pub(crate) fn primitive_arms(&self) -> Option<BTreeSet<Primitive>> {
+        match self {
+            Type::True | Type::False => Some(BTreeSet::from([Primitive::Bool])),
+            Type::Primitive { primitive_type } => Some(BTreeSet::from([primitive_type.clone()])),
+            Type::Union { arms } => Some(arms.clone()),
+            _ => None,
+        }
+    }
+
     /// Implements a subtype relation for the type structure. This requires a
     /// `schema` so that the declared attributes for named entity types can be
     /// retrieved. This is used to determine subtyping between a named entity
@@ -238,6 +336,24 @@ impl Type {
             // types are the same.
             (Type::ExtensionType { .. }, Type::ExtensionType { .. }) => ty0 == ty1,
 
+            // A primitive type or union is a subtype of a union with all its
+            // arms. A union is never a subtype of a primitive type, since it
+            // has at least two arms.
+            (
+                Type::True | Type::False | Type::Primitive { .. } | Type::Union { .. },
+                Type::Union { arms: arms1 },
+            ) => ty0
+                .primitive_arms()
+                .is_some_and(|arms0| arms0.is_subset(arms1)),
+
+            // A nullable type is a subtype of another nullable type when its
+            // non-null type is a subtype, and any other type is a subtype of a
+            // nullable type when it is a subtype of its non-null type.
+            (Type::Nullable { inner: inner0 }, Type::Nullable { inner: inner1 }) => {
+                Type::is_subtype(schema, inner0, inner1, mode)
+            }
+            (_, Type::Nullable { inner: inner1 }) => Type::is_subtype(schema, ty0, inner1, mode),
+
             // If none of the above apply, then ty0 is not a subtype of ty1.
             _ => false,
         }
@@ -251,6 +367,7 @@ impl Type {
         ty1: &Type,
         mode: ValidationMode,
     ) -> Result<Type, LubHelp> {
+        crate::profile::count_lub_computation();
         match (ty0, ty1) {
             _ if Type::is_subtype(schema, ty0, ty1, mode) => Ok(ty1.clone()),
             _ if Type::is_subtype(schema, ty1, ty0, mode) => Ok(ty0.clone()),
@@ -283,6 +400,12 @@ impl Type {
                 EntityRecordKind::least_upper_bound(schema, rk0, rk1, mode)?,
             )),
 
+            // The least upper bound of a nullable type and another type is
+            // nullable, with the least upper bound of the non-null types.
+            (Type::Nullable { .. }, _) | (_, Type::Nullable { .. }) => Ok(Type::nullable(
+                Type::least_upper_bound(schema, ty0.non_null(), ty1.non_null(), mode)?,
+            )),
+
             _ => Err(LubHelp::None),
         }
     }
@@ -327,6 +450,42 @@ impl Type {
         })
     }
 
+    /// The largest number of attributes of a record or action entity type
+    /// in this type, including nested types. Attributes of other entity
+    /// types are declared in the schema, so they are not counted.
+    pub(crate) fn max_record_width(&self) -> usize {
+        match self {
+            Type::Set {
+                element_type: Some(ty),
+            } => ty.max_record_width(),
+            Type::EntityOrRecord(
+                EntityRecordKind::Record { attrs, .. }
+                | EntityRecordKind::ActionEntity { attrs, .. },
+            ) => attrs
+                .iter()
+                .map(|(_, ty)| ty.attr_type.max_record_width())
+                .fold(attrs.attrs.len(), std::cmp::max),
+            _ => 0,
+        }
+    }
+
+    /// The largest number of set types nested in one another in this type,
+    /// e.g., 2 for `Set<{ a: Set<Long> }>`
+    pub(crate) fn set_depth(&self) -> usize {
+        match self {
+            Type::Set { element_type } => 1 + element_type.as_ref().map_or(0, |ty| ty.set_depth()),
+            Type::EntityOrRecord(
+                EntityRecordKind::Record { attrs, .. }
+                | EntityRecordKind::ActionEntity { attrs, .. },
+            ) => attrs
+                .iter()
+                .map(|(_, ty)| ty.attr_type.set_depth())
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
     /// Get the type of the specified attribute of an entity or record type,
     /// if it is known.
     ///
@@ -358,6 +517,40 @@ impl Type {
         }
     }
 
+    /// Get the attributes of any of the entity types in an entity least upper
+    /// bound, or all statically known attributes of any other entity or
+    /// record type. Unlike [`Type::all_attributes`], this includes attributes
+    /// which only some of the entity types in a least upper bound have.
+    pub(crate) fn attributes_of_any_element(&self, schema: &ValidatorSchema) -> Vec<SmolStr> {
+        match self {
+            Type::EntityOrRecord(EntityRecordKind::Entity(entity_lub)) => entity_lub
+                .iter()
+                .filter_map(|entity| schema.get_entity_type(entity))
+                .flat_map(|entity_type| entity_type.attributes.iter().map(|(k, _)| k.clone()))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            _ => self.all_attributes(schema),
+        }
+    }
+
+    /// Add the names of all attributes of record types nested anywhere in
+    /// this type to `names`
+    pub(crate) fn collect_record_attribute_names(&self, names: &mut BTreeSet<SmolStr>) {
+        match self {
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => {
+                for (name, attr_ty) in attrs.iter() {
+                    names.insert(name.clone());
+                    attr_ty.attr_type.collect_record_attribute_names(names);
+                }
+            }
+            Type::Set {
+                element_type: Some(element_type),
+            } => element_type.collect_record_attribute_names(names),
+            _ => {}
+        }
+    }
+
     /// Return true if the Type `ty` could possibly contain the attribute
     /// `attr`. Record and entity types can contain attributes, so we check if
     /// the type can contain the specific attribute. Other types cannot have
@@ -400,10 +593,33 @@ impl Type {
         }
     }
 
+    /// Is this an entity type (or least upper bound of entity types) which
+    /// includes an entity type that is not declared in the schema.
+    pub(crate) fn has_undeclared_entity_type(&self, schema: &ValidatorSchema) -> bool {
+        match self {
+            Type::EntityOrRecord(EntityRecordKind::Entity(entity_lub)) => entity_lub
+                .iter()
+                .any(|entity| !schema.is_known_entity_type(entity)),
+            _ => false,
+        }
+    }
+
     /// Is this validator type "consistent with" the given Core `SchemaType`.
     /// Meaning, is there at least some value that could have this `SchemaType` and
     /// this validator type simultaneously.
     pub(crate) fn is_consistent_with(&self, core_type: &CoreSchemaType) -> bool {
+        if let Type::Nullable { inner } = self {
+            return core_type.includes(&CoreSchemaType::Null)
+                || inner.is_consistent_with(core_type);
+        }
+        if let Type::Union { arms } = self {
+            return arms.iter().any(|arm| {
+                Type::Primitive {
+                    primitive_type: arm.clone(),
+                }
+                .is_consistent_with(core_type)
+            });
+        }
         match core_type {
             CoreSchemaType::Bool => matches!(
                 self,
@@ -428,6 +644,8 @@ impl Type {
             CoreSchemaType::Set { element_ty } => {
                 matches!(self, Type::Set { element_type: Some(element_type) } if element_type.is_consistent_with(element_ty))
             }
+            // Nullable types were handled above
+            CoreSchemaType::Null => false,
             CoreSchemaType::EmptySet => {
                 // for any given validator Set type, there is some value (namely, the empty set)
                 // that could have the EmptySet CoreSchemaType and that validator Set type.
@@ -498,6 +716,7 @@ impl Type {
             CoreSchemaType::Extension { name } => {
                 matches!(self, Type::ExtensionType { name: n } if name == n)
             }
+            CoreSchemaType::Union { arms } => arms.iter().any(|arm| self.is_consistent_with(arm)),
         }
     }
 
@@ -652,6 +871,19 @@ impl Type {
                 }
                 None => Ok(false), // no other kinds of restricted expr (other than fn calls) can produce extension-typed values
             },
+            Type::Union { arms } => {
+                for arm in arms {
+                    let arm = Type::Primitive {
+                        primitive_type: arm.clone(),
+                    };
+                    if arm.typecheck_restricted_expr(restricted_expr, extensions)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Type::Nullable { inner } => Ok(restricted_expr.is_null()
+                || inner.typecheck_restricted_expr(restricted_expr, extensions)?),
         }
     }
 }
@@ -713,6 +945,19 @@ impl Display for Type {
                 write!(f, "}}")
             }
             Type::ExtensionType { name } => write!(f, "{name}"),
+            Type::Union { arms } => write!(
+                f,
+                "{}",
+                arms.iter()
+                    .map(|arm| Type::Primitive {
+                        primitive_type: arm.clone()
+                    })
+                    .join(" | ")
+            ),
+            Type::Nullable { inner } => match inner.as_ref() {
+                Type::Never => write!(f, "Null"),
+                inner => write!(f, "{inner} | Null"),
+            },
         }
     }
 }
@@ -772,6 +1017,20 @@ impl TryFrom<Type> for CoreSchemaType {
                 ),
             },
             Type::ExtensionType { name } => Ok(CoreSchemaType::Extension { name }),
+            Type::Union { arms } => Ok(CoreSchemaType::Union {
+                arms: arms
+                    .into_iter()
+                    .map(|arm| {
+                        CoreSchemaType::try_from(Type::Primitive {
+                            primitive_type: arm,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?,
+            }),
+            Type::Nullable { inner } => match *inner {
+                Type::Never => Ok(CoreSchemaType::Null),
+                inner => Ok(CoreSchemaType::nullable(CoreSchemaType::try_from(inner)?)),
+            },
         }
     }
 }
@@ -865,10 +1124,10 @@ impl EntityLUB {
             // reasons. First, when in permissive mode, the attributes least
             // upper bound can never fail. We could call the main lub function
             // with an unwrap, but this avoids a chance at a panic. Second, when
-            // in strict mode, an entity LUB will only ever have a single
-            // element, so that LUB can never fail, and the strict
-            // attributes lub is the same as permissive if there is only one
-            // attribute.
+            // in strict mode, an entity LUB only has several elements if they
+            // extend a common entity type, and they have the attributes of
+            // that entity type with the same types, so those attributes are
+            // kept by the permissive lub as well.
             Attributes::permissive_least_upper_bound(
                 schema,
                 &acc,
@@ -890,6 +1149,31 @@ impl EntityLUB {
         }
     }
 
+    /// Return true if some entity type is, or is (transitively) extended by,
+    /// every entity type in this [`EntityLUB`] and `other`
+    pub(crate) fn has_common_supertype(&self, other: &EntityLUB, schema: &ValidatorSchema) -> bool {
+        let self_and_supertypes = |ty: &EntityType| -> Vec<EntityType> {
+            std::iter::once(ty.clone())
+                .chain(
+                    schema
+                        .get_entity_type(ty)
+                        .into_iter()
+                        .flat_map(|ety| ety.supertypes().cloned()),
+                )
+                .collect()
+        };
+        let mut types = self.lub_elements.iter().chain(other.lub_elements.iter());
+        let Some(first) = types.next() else {
+            return true;
+        };
+        let mut common = self_and_supertypes(first);
+        for ty in types {
+            let supertypes = self_and_supertypes(ty);
+            common.retain(|common| supertypes.contains(common));
+        }
+        !common.is_empty()
+    }
+
     /// Return true if the set of entity types composing this [`EntityLUB`] is
     /// disjoint from th entity types composing another [`EntityLUB`].
     pub(crate) fn is_disjoint(&self, other: &EntityLUB) -> bool {
@@ -1282,7 +1566,9 @@ impl EntityRecordKind {
                 }
             }
             (Entity(lub0), Entity(lub1)) => {
-                if mode.is_strict() && lub0 != lub1 {
+                // Strict validation only allows the least upper bound of
+                // distinct entity types which extend a common entity type
+                if mode.is_strict() && lub0 != lub1 && !lub0.has_common_supertype(lub1, schema) {
                     Err(LubHelp::EntityType)
                 } else {
                     Ok(Entity(lub0.least_upper_bound(lub1)))
@@ -1361,6 +1647,7 @@ impl EntityRecordKind {
             (Entity(lub0), Entity(lub1)) => {
                 if mode.is_strict() {
                     lub0 == lub1
+                        || (lub0.is_subtype(lub1) && lub0.has_common_supertype(lub1, schema))
                 } else {
                     lub0.is_subtype(lub1)
                 }
@@ -18,6 +18,8 @@ use std::collections::HashSet;
 
 use cedar_policy_core::ast::{Expr, ExprShapeOnly};
 
+use super::Primitive;
+
 /// A set of capabilities. Used to represent knowledge about attribute existence
 /// before and after evaluating an expression.
 #[derive(Eq, PartialEq, Debug, Clone, Default)]
@@ -53,13 +55,28 @@ impl<'a> CapabilitySet<'a> {
 }
 
 /// Represent a single capability, which is an expression and some attribute that is
-/// known to exist for that expression.
+/// known to exist for that expression, or, for an attribute with a union type,
+/// known to have one arm of the union, or, for an attribute with a nullable
+/// type, known not to be `null`.
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub struct Capability<'a> {
     /// For this expression
     on_expr: ExprShapeOnly<'a>,
     /// This attribute is known to exist on that expression
     attribute: &'a str,
+    /// What is known about the attribute
+    kind: CapabilityKind,
+}
+
+/// What a [`Capability`] records about an attribute
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+enum CapabilityKind {
+    /// The attribute exists
+    Exists,
+    /// The attribute has this primitive type
+    Narrowed(Primitive),
+    /// The attribute is not `null`
+    NonNull,
 }
 
 impl<'a> Capability<'a> {
@@ -69,6 +86,29 @@ impl<'a> Capability<'a> {
         Self {
             on_expr: ExprShapeOnly::new(on_expr),
             attribute,
+            kind: CapabilityKind::Exists,
+        }
+    }
+
+    /// Construct a new [`Capability`] stating that the attribute `attribute`
+    /// of the expression `on_expr`, which has a union type, is known to have
+    /// the type `primitive_type`
+    pub fn narrowed(on_expr: &'a Expr, attribute: &'a str, primitive_type: Primitive) -> Self {
+        Self {
+            on_expr: ExprShapeOnly::new(on_expr),
+            attribute,
+            kind: CapabilityKind::Narrowed(primitive_type),
+        }
+    }
+
+    /// Construct a new [`Capability`] stating that the attribute `attribute`
+    /// of the expression `on_expr`, which has a nullable type, is known not to
+    /// be `null`
+    pub fn non_null(on_expr: &'a Expr, attribute: &'a str) -> Self {
+        Self {
+            on_expr: ExprShapeOnly::new(on_expr),
+            attribute,
+            kind: CapabilityKind::NonNull,
         }
     }
 }
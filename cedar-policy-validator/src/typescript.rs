@@ -0,0 +1,316 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Generation of TypeScript types for the entity attributes and action
+//! contexts of a schema, for applications which assemble authorization
+//! requests in TypeScript.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use crate::types::{AttributeType, EntityRecordKind, Primitive, Type};
+use crate::ValidatorSchema;
+
+/// Errors which can occur when generating TypeScript types
+#[derive(Debug, Diagnostic, Error)]
+pub enum TypeScriptGenerationError {
+    /// Two entity types or actions map to the same TypeScript name
+    #[error("{first} and {second} both map to the TypeScript name `{name}`")]
+    #[diagnostic(help("rename one of them in the schema"))]
+    NameCollision {
+        /// The TypeScript name
+        name: String,
+        /// What the name was first generated for
+        first: String,
+        /// What the name was generated for again
+        second: String,
+    },
+}
+
+/// Generate TypeScript declarations for the entities and action contexts of
+/// `schema`.
+///
+/// The types describe the Cedar JSON format as parsed with a schema, where
+/// entity references and extension values don't need `__entity` or
+/// `__extn` escapes. For each entity type, e.g., `App::User`, the output
+/// declares `AppUserAttributes` and `AppUserEntity`, the shape of the entity
+/// in an entities JSON file. For each action which applies to some principal
+/// type, e.g., `App::Action::"view"`, it declares `AppViewContext`. Optional
+/// attributes are optional properties. `Long`s are `number`s, which can only
+/// represent integers up to 2^53 exactly.
+pub fn generate_typescript(schema: &ValidatorSchema) -> Result<String, TypeScriptGenerationError> {
+    let mut out = String::from(
+        "// Generated from a Cedar schema. Do not edit.
+
+export interface EntityUid<T extends string = string> {
+  type: T;
+  id: string;
+}
+
+export interface ExtensionValue {
+  fn: string;
+  arg: string;
+}
+",
+    );
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut claim = |name: String, origin: String| match names.get(&name) {
+        Some(first) => Err(TypeScriptGenerationError::NameCollision {
+            name,
+            first: first.clone(),
+            second: origin,
+        }),
+        None => {
+            names.insert(name.clone(), origin);
+            Ok(name)
+        }
+    };
+    for (name, entity_type) in schema
+        .entity_types()
+        .sorted_by_cached_key(|(name, _)| name.to_string())
+    {
+        let name = name.to_string();
+        let base = pascal_case(name.split("::"));
+        let attrs_name = claim(format!("{base}Attributes"), format!("`{name}`"))?;
+        let entity_name = claim(format!("{base}Entity"), format!("`{name}`"))?;
+        let _ = write!(
+            out,
+            "
+/** Attributes of entity type `{name}` */
+export interface {attrs_name} {}
+
+/** Entity of type `{name}` */
+export interface {entity_name} {{
+  uid: EntityUid<{name:?}>;
+  attrs: {attrs_name};
+  parents: EntityUid[];
+}}
+",
+            record(entity_type.attributes(), 0)
+        );
+    }
+    for action in schema.actions().sorted_by_cached_key(ToString::to_string) {
+        let Some(action_id) = schema.get_action_id(action) else {
+            continue;
+        };
+        if action_id.principals().next().is_none() {
+            continue;
+        }
+        let type_name = action.entity_type().to_string();
+        let namespace = type_name.split("::").filter(|c| *c != "Action");
+        let context_name = claim(
+            format!(
+                "{}Context",
+                pascal_case(namespace.chain([action.eid().as_ref()]))
+            ),
+            format!("`{action}`"),
+        )?;
+        let context = match action_id.context_type() {
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => record(attrs.iter(), 0),
+            _ => "{}".to_string(),
+        };
+        let _ = write!(
+            out,
+            "
+/** Context of action `{action}` */
+export interface {context_name} {context}
+"
+        );
+    }
+    Ok(out)
+}
+
+/// TypeScript object type for a record with `attrs`, whose closing brace is
+/// at `indent`
+fn record<'a>(
+    attrs: impl IntoIterator<Item = (&'a SmolStr, &'a AttributeType)>,
+    indent: usize,
+) -> String {
+    let mut out = String::from("{\n");
+    for (attr, attr_type) in attrs {
+        let _ = writeln!(
+            out,
+            "{:width$}{}{}: {};",
+            "",
+            property_name(attr),
+            if attr_type.is_required { "" } else { "?" },
+            type_expr(&attr_type.attr_type, indent + 2),
+            width = indent + 2,
+        );
+    }
+    let _ = write!(out, "{:indent$}}}", "");
+    out
+}
+
+/// TypeScript type for `ty`, in a record whose properties are at `indent`
+fn type_expr(ty: &Type, indent: usize) -> String {
+    match ty {
+        Type::True
+        | Type::False
+        | Type::Primitive {
+            primitive_type: Primitive::Bool,
+        } => "boolean".into(),
+        Type::Primitive {
+            primitive_type: Primitive::Long,
+        } => "number".into(),
+        Type::Primitive {
+            primitive_type: Primitive::String,
+        } => "string".into(),
+        Type::Set {
+            element_type: Some(element_type),
+        } => format!("Array<{}>", type_expr(element_type, indent)),
+        Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => {
+            record(attrs.iter(), indent)
+        }
+        Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => lub
+            .iter()
+            .map(|ty| format!("EntityUid<{:?}>", ty.to_string()))
+            .join(" | "),
+        Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. }) => {
+            format!("EntityUid<{:?}>", name.to_string())
+        }
+        Type::EntityOrRecord(EntityRecordKind::AnyEntity) => "EntityUid".into(),
+        Type::ExtensionType { .. } => "ExtensionValue".into(),
+        Type::Union { arms } => arms
+            .iter()
+            .map(|arm| {
+                type_expr(
+                    &Type::Primitive {
+                        primitive_type: arm.clone(),
+                    },
+                    indent,
+                )
+            })
+            .join(" | "),
+        Type::Nullable { inner } => match inner.as_ref() {
+            Type::Never => "null".into(),
+            inner => format!("{} | null", type_expr(inner, indent)),
+        },
+        Type::Never | Type::Set { element_type: None } => "unknown".into(),
+    }
+}
+
+/// `attr` as a TypeScript property name, quoted unless it is an identifier
+fn property_name(attr: &str) -> String {
+    let is_identifier = attr
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && attr
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        attr.to_string()
+    } else {
+        format!("{attr:?}")
+    }
+}
+
+/// Join `components` into an identifier in `PascalCase`
+fn pascal_case<'a>(components: impl IntoIterator<Item = &'a str>) -> String {
+    let name: String = components
+        .into_iter()
+        .flat_map(|component| component.split(|c: char| !c.is_ascii_alphanumeric()))
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("T{name}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn generate() {
+        let schema: ValidatorSchema = r#"
+            namespace App {
+                entity Group;
+                entity User in [Group] = {
+                    name: String,
+                    manager?: User,
+                    address: { city: String, zip?: Long },
+                    "home-page": String,
+                    tags: Set<String>,
+                    ip: ipaddr,
+                };
+                action "read file" appliesTo {
+                    principal: User,
+                    resource: Group,
+                    context: { mfa: Bool },
+                };
+            }
+        "#
+        .parse()
+        .unwrap();
+        let ts = generate_typescript(&schema).unwrap();
+        let expected = r#"
+/** Attributes of entity type `App::User` */
+export interface AppUserAttributes {
+  address: {
+    city: string;
+    zip?: number;
+  };
+  "home-page": string;
+  ip: ExtensionValue;
+  manager?: EntityUid<"App::User">;
+  name: string;
+  tags: Array<string>;
+}
+
+/** Entity of type `App::User` */
+export interface AppUserEntity {
+  uid: EntityUid<"App::User">;
+  attrs: AppUserAttributes;
+  parents: EntityUid[];
+}
+"#;
+        assert!(ts.contains(expected), "{ts}");
+        let expected = r#"
+/** Context of action `App::Action::"read file"` */
+export interface AppReadFileContext {
+  mfa: boolean;
+}
+"#;
+        assert!(ts.contains(expected), "{ts}");
+    }
+
+    #[test]
+    fn collision() {
+        let schema: ValidatorSchema = "namespace A { entity BUser; } namespace AB { entity User; }"
+            .parse()
+            .unwrap();
+        assert_matches!(
+            generate_typescript(&schema),
+            Err(TypeScriptGenerationError::NameCollision { .. })
+        );
+    }
+}
@@ -0,0 +1,228 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Finds the entity types, attributes, and actions declared in a schema which
+//! are never referenced by a policy set.
+
+use std::collections::HashSet;
+
+use cedar_policy_core::ast::{ActionConstraint, EntityType, EntityUID, ExprKind, PolicySet};
+use itertools::Itertools;
+use smol_str::SmolStr;
+
+use crate::expr_iterator::{policy_entity_type_names, policy_entity_uids};
+use crate::{ValidationWarning, ValidatorSchema};
+
+/// The schema elements referenced by a policy set
+#[derive(Debug, Default)]
+struct References<'a> {
+    entity_types: HashSet<&'a EntityType>,
+    /// Attribute names accessed (with `.` or `has`) on any value
+    attributes: HashSet<&'a SmolStr>,
+    actions: HashSet<&'a EntityUID>,
+    /// Some policy does not constrain the action, so it applies to all actions
+    all_actions: bool,
+}
+
+impl<'a> References<'a> {
+    fn new(policies: &'a PolicySet) -> Self {
+        let mut refs = Self::default();
+        for template in policies.all_templates() {
+            refs.entity_types.extend(policy_entity_type_names(template));
+            for uid in policy_entity_uids(template) {
+                refs.entity_types.insert(uid.entity_type());
+                if uid.entity_type().is_action() {
+                    refs.actions.insert(uid);
+                }
+            }
+            if matches!(template.action_constraint(), ActionConstraint::Any) {
+                refs.all_actions = true;
+            }
+            refs.attributes.extend(
+                template
+                    .non_scope_constraints()
+                    .subexpressions()
+                    .filter_map(|e| match e.expr_kind() {
+                        ExprKind::GetAttr { attr, .. } | ExprKind::HasAttr { attr, .. } => {
+                            Some(attr)
+                        }
+                        _ => None,
+                    }),
+            );
+        }
+        for policy in policies.policies() {
+            refs.entity_types
+                .extend(policy.env().values().map(EntityUID::entity_type));
+        }
+        refs
+    }
+}
+
+/// Compute warnings for the entity types, attributes, and actions declared in
+/// `schema` but never referenced by `policies`.
+///
+/// An action is referenced if a policy mentions it or an action group
+/// containing it, or if a policy does not constrain the action. An entity type
+/// is referenced if a policy mentions it, or if it is a principal or resource
+/// type of a referenced action. An attribute of a referenced entity type is
+/// referenced if any policy accesses an attribute with that name. Attributes
+/// of unreferenced entity types are not reported separately.
+pub(crate) fn unused_schema_elements(
+    schema: &ValidatorSchema,
+    policies: &PolicySet,
+) -> Vec<ValidationWarning> {
+    let refs = References::new(policies);
+    let locs = schema.source_locs();
+
+    let used_actions: HashSet<&EntityUID> = if refs.all_actions {
+        schema.actions().collect()
+    } else {
+        refs.actions
+            .iter()
+            .flat_map(|action| {
+                let descendants = schema
+                    .get_action_id(action)
+                    .into_iter()
+                    .flat_map(|a| a.descendants.iter());
+                std::iter::once(*action).chain(descendants)
+            })
+            .collect()
+    };
+    let mut used_entity_types = refs.entity_types.clone();
+    for action in &used_actions {
+        if let Some(action) = schema.get_action_id(action) {
+            used_entity_types.extend(action.principals());
+            used_entity_types.extend(action.resources());
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for (ety, validator_ety) in schema
+        .entity_types()
+        .sorted_by_key(|(ety, _)| ety.to_string())
+    {
+        if !used_entity_types.contains(ety) {
+            warnings.push(ValidationWarning::unused_entity_type(
+                locs.entity_type(ety).cloned(),
+                ety.clone(),
+            ));
+            continue;
+        }
+        for (attr, _) in validator_ety.attributes().sorted_by_key(|(attr, _)| *attr) {
+            if !refs.attributes.contains(attr) {
+                warnings.push(ValidationWarning::unused_attribute(
+                    locs.attribute(ety, attr).cloned(),
+                    ety.clone(),
+                    attr.clone(),
+                ));
+            }
+        }
+    }
+    for action in schema
+        .actions()
+        .filter(|action| !used_actions.contains(action))
+        .sorted_by_key(|action| action.to_string())
+    {
+        warnings.push(ValidationWarning::unused_action(
+            locs.action(action).cloned(),
+            action.clone(),
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::{extensions::Extensions, parser::parse_policyset};
+    use cool_asserts::assert_matches;
+
+    fn schema() -> ValidatorSchema {
+        ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { name: String, age: Long };
+            entity Group;
+            entity Photo = { owner: User };
+            entity Album;
+            action view appliesTo { principal: User, resource: Photo };
+            action edit appliesTo { principal: User, resource: Photo };
+            action manage appliesTo { principal: Group, resource: Album };
+            action share in [manage] appliesTo { principal: User, resource: Album };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap()
+        .0
+    }
+
+    fn unused(src: &str) -> Vec<String> {
+        let policies = parse_policyset(src).unwrap();
+        unused_schema_elements(&schema(), &policies)
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reports_unused_elements() {
+        assert_eq!(
+            unused(r#"permit(principal, action == Action::"view", resource) when { resource.owner == principal };"#),
+            vec![
+                "entity type `Album` is declared in the schema but not referenced by any policy",
+                "entity type `Group` is declared in the schema but not referenced by any policy",
+                "attribute `age` of entity type `User` is declared in the schema but not referenced by any policy",
+                "attribute `name` of entity type `User` is declared in the schema but not referenced by any policy",
+                r#"action `Action::"edit"` is declared in the schema but not referenced by any policy"#,
+                r#"action `Action::"manage"` is declared in the schema but not referenced by any policy"#,
+                r#"action `Action::"share"` is declared in the schema but not referenced by any policy"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn action_groups_and_unconstrained_actions() {
+        assert_eq!(
+            unused(
+                r#"permit(principal, action in Action::"manage", resource) when { principal has name && principal.age > 3 && resource.owner == principal };"#
+            ),
+            vec![
+                "entity type `Photo` is declared in the schema but not referenced by any policy",
+                r#"action `Action::"edit"` is declared in the schema but not referenced by any policy"#,
+                r#"action `Action::"view"` is declared in the schema but not referenced by any policy"#,
+            ]
+        );
+        assert_eq!(
+            unused(
+                r#"permit(principal, action, resource) when { principal.name == "" && principal.age > 3 && resource.owner == principal };"#
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn source_locations() {
+        let policies = parse_policyset(r#"permit(principal, action, resource);"#).unwrap();
+        let warnings = unused_schema_elements(&schema(), &policies);
+        assert_matches!(
+            warnings.first(),
+            Some(ValidationWarning::UnusedAttribute(w)) => {
+                assert_eq!(w.attr, "owner");
+                let loc = w.source_loc.as_ref().expect("should have a location");
+                assert!(loc.snippet().unwrap().starts_with("owner"));
+            }
+        );
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// PANIC SAFETY benchmarking
+#![allow(clippy::unwrap_used)]
+
+//! Benchmarks of authorization with and without compiling set literals of
+//! entity uids to bitsets (`Authorizer::with_compiled_entity_sets()`), on
+//! policies with large allowlists.
+
+use std::str::FromStr;
+
+use cedar_policy::{Authorizer, Context, Entities, EntityUid, PolicySet, Request};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use itertools::Itertools;
+
+const ALLOWLIST_LEN: usize = 10_000;
+const POLICIES: usize = 10;
+
+fn users(range: impl Iterator<Item = usize>) -> String {
+    format!("[{}]", range.map(|i| format!("User::\"u{i}\"")).join(", "))
+}
+
+pub fn allowlists_benchmark(c: &mut Criterion) {
+    let mut policies = String::new();
+    for i in 0..POLICIES {
+        let allowlist = users((0..ALLOWLIST_LEN).map(|u| u * POLICIES + i));
+        policies.push_str(&format!(
+            "permit(principal, action == Action::\"view\", resource) when {{ {allowlist}.contains(principal) }};\n"
+        ));
+        policies.push_str(&format!(
+            "permit(principal, action == Action::\"edit\", resource) when {{ {allowlist}.containsAny([principal, resource.owner]) }};\n"
+        ));
+    }
+    let policies = PolicySet::from_str(&policies).unwrap();
+    let entities = Entities::from_json_value(
+        serde_json::json!([{
+            "uid": { "type": "Document", "id": "d" },
+            "attrs": { "owner": { "__entity": { "type": "User", "id": "u7" } } },
+            "parents": [],
+        }]),
+        None,
+    )
+    .unwrap();
+    let requests: Vec<Request> = (0..100)
+        .map(|i| {
+            let action = if i % 2 == 0 { "view" } else { "edit" };
+            Request::new(
+                EntityUid::from_str(&format!("User::\"u{}\"", i * 997)).unwrap(),
+                EntityUid::from_str(&format!("Action::\"{action}\"")).unwrap(),
+                EntityUid::from_str("Document::\"d\"").unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("allowlists");
+    for (name, auth) in [
+        ("plain", Authorizer::new()),
+        (
+            "compiled",
+            Authorizer::new().with_compiled_entity_sets(&policies),
+        ),
+    ] {
+        group.bench_function(BenchmarkId::new("is_authorized", name), |b| {
+            b.iter(|| {
+                for request in &requests {
+                    black_box(auth.is_authorized(request, &policies, &entities));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, allowlists_benchmark);
+criterion_main!(benches);
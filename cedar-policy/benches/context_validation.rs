@@ -0,0 +1,105 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// PANIC SAFETY benchmarking
+#![allow(clippy::unwrap_used)]
+
+//! Benchmarks of building requests with and without validating them against
+//! a schema, on actions whose contexts have many attributes.
+
+use std::str::FromStr;
+
+use cedar_policy::{Context, EntityUid, Request, Schema};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::json;
+
+const ACTIONS: usize = 10;
+
+const SCHEMA: &str = r#"
+entity User, Document;
+type Source = { ip: ipaddr, country: String, trusted?: Bool };
+action "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "a8", "a9" appliesTo {
+    principal: User,
+    resource: Document,
+    context: {
+        mfa: Bool,
+        level: Long,
+        session: String,
+        groups: Set<String>,
+        owner: User,
+        source: Source,
+        hops: Set<Source>,
+        amount?: decimal,
+    }
+};
+"#;
+
+pub fn context_validation_benchmark(c: &mut Criterion) {
+    let (schema, _) = Schema::from_cedarschema_str(SCHEMA).unwrap();
+
+    let requests: Vec<_> = (0..100)
+        .map(|i| {
+            let source = |n: usize| {
+                json!({
+                    "ip": { "__extn": { "fn": "ip", "arg": format!("10.0.{}.{n}", i % 256) } },
+                    "country": "US",
+                })
+            };
+            let context = Context::from_json_value(
+                json!({
+                    "mfa": i % 2 == 0,
+                    "level": i % 10,
+                    "session": format!("s{i}"),
+                    "groups": (0..10).map(|g| format!("g{g}")).collect::<Vec<_>>(),
+                    "owner": { "__entity": { "type": "User", "id": format!("u{i}") } },
+                    "source": source(0),
+                    "hops": (1..5).map(source).collect::<Vec<_>>(),
+                }),
+                None,
+            )
+            .unwrap();
+            (
+                EntityUid::from_str(&format!("User::\"u{i}\"")).unwrap(),
+                EntityUid::from_str(&format!("Action::\"a{}\"", i % ACTIONS)).unwrap(),
+                EntityUid::from_str(&format!("Document::\"d{i}\"")).unwrap(),
+                context,
+            )
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("context_validation");
+    for (name, schema) in [("unchecked", None), ("validated", Some(&schema))] {
+        group.bench_function(BenchmarkId::new("request_new", name), |b| {
+            b.iter(|| {
+                for (principal, action, resource, context) in &requests {
+                    black_box(
+                        Request::new(
+                            principal.clone(),
+                            action.clone(),
+                            resource.clone(),
+                            context.clone(),
+                            schema,
+                        )
+                        .unwrap(),
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, context_validation_benchmark);
+criterion_main!(benches);
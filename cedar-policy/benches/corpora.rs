@@ -0,0 +1,369 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// PANIC SAFETY benchmarking
+#![allow(clippy::unwrap_used)]
+// PANIC SAFETY benchmarking
+#![allow(clippy::expect_used)]
+
+//! Benchmarks of parsing, validation, and authorization on representative
+//! corpora of policies and entities. The corpora are generated
+//! deterministically, so that results are comparable across runs; see
+//! `benches/README.md` for tracking them against a baseline.
+
+use std::str::FromStr;
+
+use cedar_policy::{
+    Authorizer, Context, Entities, EntityUid, PolicyId, PolicySet, Request, Schema, SlotId,
+    ValidationMode, Validator,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+
+/// A policy set, with the schema and entities it is evaluated against, and
+/// the requests to authorize
+struct Corpus {
+    name: &'static str,
+    policies_src: String,
+    policies: PolicySet,
+    schema: Schema,
+    entities: Entities,
+    requests: Vec<Request>,
+}
+
+impl Corpus {
+    fn new(
+        name: &'static str,
+        schema_src: &str,
+        policies_src: String,
+        entities: Vec<Value>,
+        requests: Vec<(String, String, String, Value)>,
+    ) -> Self {
+        let (schema, _) = Schema::from_cedarschema_str(schema_src).unwrap();
+        let policies = PolicySet::from_str(&policies_src).unwrap();
+        let entities = Entities::from_json_value(Value::Array(entities), Some(&schema)).unwrap();
+        let requests = requests
+            .into_iter()
+            .map(|(principal, action, resource, context)| {
+                let action = EntityUid::from_str(&action).unwrap();
+                let context = Context::from_json_value(context, Some((&schema, &action))).unwrap();
+                Request::new(
+                    EntityUid::from_str(&principal).unwrap(),
+                    action,
+                    EntityUid::from_str(&resource).unwrap(),
+                    context,
+                    Some(&schema),
+                )
+                .unwrap()
+            })
+            .collect();
+        Self {
+            name,
+            policies_src,
+            policies,
+            schema,
+            entities,
+            requests,
+        }
+    }
+}
+
+fn uid(ty: &str, id: impl std::fmt::Display) -> Value {
+    json!({ "type": ty, "id": id.to_string() })
+}
+
+/// Role-based access: users in roles, documents in folders, and one policy
+/// per role
+fn rbac() -> Corpus {
+    const ROLES: usize = 50;
+    const USERS: usize = 1000;
+    const FOLDERS: usize = 20;
+    const DOCUMENTS: usize = 500;
+    let schema = r#"
+        entity Role;
+        entity User in [Role] = { suspended: Bool };
+        entity Folder;
+        entity Document in [Folder];
+        action view, edit appliesTo { principal: User, resource: Document };
+    "#;
+    let mut policies = String::new();
+    for role in 0..ROLES {
+        let folder = role % FOLDERS;
+        let action = if role % 5 == 0 { "edit" } else { "view" };
+        policies.push_str(&format!(
+            "permit(principal in Role::\"r{role}\", action == Action::\"{action}\", resource in Folder::\"f{folder}\");\n"
+        ));
+    }
+    policies.push_str("forbid(principal, action, resource) when { principal.suspended };\n");
+    let mut entities: Vec<Value> = (0..ROLES)
+        .map(|role| json!({ "uid": uid("Role", format!("r{role}")), "attrs": {}, "parents": [] }))
+        .collect();
+    entities.extend((0..FOLDERS).map(
+        |folder| json!({ "uid": uid("Folder", format!("f{folder}")), "attrs": {}, "parents": [] }),
+    ));
+    entities.extend((0..USERS).map(|user| {
+        json!({
+            "uid": uid("User", format!("u{user}")),
+            "attrs": { "suspended": user % 97 == 0 },
+            "parents": (0..3).map(|i| uid("Role", format!("r{}", (user * 7 + i * 13) % ROLES))).collect::<Vec<_>>(),
+        })
+    }));
+    entities.extend((0..DOCUMENTS).map(|doc| {
+        json!({
+            "uid": uid("Document", format!("d{doc}")),
+            "attrs": {},
+            "parents": [uid("Folder", format!("f{}", doc % FOLDERS))],
+        })
+    }));
+    let requests = (0..100)
+        .map(|i| {
+            (
+                format!("User::\"u{}\"", i * 11 % USERS),
+                format!("Action::\"{}\"", if i % 3 == 0 { "edit" } else { "view" }),
+                format!("Document::\"d{}\"", i * 17 % DOCUMENTS),
+                json!({}),
+            )
+        })
+        .collect();
+    Corpus::new("rbac", schema, policies, entities, requests)
+}
+
+/// Attribute-based access: policies with conditions on attributes of the
+/// principal, resource, and context, including extension functions
+fn abac() -> Corpus {
+    const POLICIES: usize = 200;
+    const USERS: usize = 200;
+    const DOCUMENTS: usize = 200;
+    const DEPARTMENTS: usize = 20;
+    let schema = r#"
+        entity User = { department: String, level: Long };
+        entity Document = { department: String, classification: Long, owner: User };
+        action view appliesTo {
+            principal: User,
+            resource: Document,
+            context: { ip: ipaddr, mfa: Bool },
+        };
+    "#;
+    let mut policies = String::new();
+    for i in 0..POLICIES {
+        let department = i % DEPARTMENTS;
+        policies.push_str(&format!(
+            r#"permit(principal, action == Action::"view", resource) when {{
+                principal.department == "dept{department}" &&
+                resource.department == principal.department &&
+                principal.level >= resource.classification &&
+                context.ip.isInRange(ip("10.{i}.0.0/16"))
+            }};
+            "#
+        ));
+    }
+    policies.push_str(
+        r#"permit(principal, action, resource) when { resource.owner == principal };
+        forbid(principal, action, resource) unless { context.mfa };
+        "#,
+    );
+    let mut entities: Vec<Value> = (0..USERS)
+        .map(|user| {
+            json!({
+                "uid": uid("User", format!("u{user}")),
+                "attrs": { "department": format!("dept{}", user % DEPARTMENTS), "level": (user % 10) as i64 },
+                "parents": [],
+            })
+        })
+        .collect();
+    entities.extend((0..DOCUMENTS).map(|doc| {
+        json!({
+            "uid": uid("Document", format!("d{doc}")),
+            "attrs": {
+                "department": format!("dept{}", doc * 3 % DEPARTMENTS),
+                "classification": (doc % 7) as i64,
+                "owner": { "__entity": uid("User", format!("u{}", doc * 5 % USERS)) },
+            },
+            "parents": [],
+        })
+    }));
+    let requests = (0..100)
+        .map(|i| {
+            (
+                format!("User::\"u{}\"", i * 13 % USERS),
+                "Action::\"view\"".to_string(),
+                format!("Document::\"d{}\"", i * 7 % DOCUMENTS),
+                json!({
+                    "ip": { "__extn": { "fn": "ip", "arg": format!("10.{}.1.1", i * 31 % 256) } },
+                    "mfa": i % 4 != 0,
+                }),
+            )
+        })
+        .collect();
+    Corpus::new("abac", schema, policies, entities, requests)
+}
+
+/// Many links of a few templates, as in per-resource sharing
+fn templates() -> Corpus {
+    const LINKS: usize = 2000;
+    const USERS: usize = 500;
+    const DOCUMENTS: usize = 500;
+    let schema = r#"
+        entity User;
+        entity Document;
+        action view, edit appliesTo { principal: User, resource: Document };
+    "#;
+    let policies = r#"
+        permit(principal == ?principal, action == Action::"view", resource == ?resource);
+        permit(principal == ?principal, action, resource == ?resource);
+    "#;
+    let mut entities: Vec<Value> = (0..USERS)
+        .map(|user| json!({ "uid": uid("User", format!("u{user}")), "attrs": {}, "parents": [] }))
+        .collect();
+    entities.extend((0..DOCUMENTS).map(
+        |doc| json!({ "uid": uid("Document", format!("d{doc}")), "attrs": {}, "parents": [] }),
+    ));
+    let requests = (0..100)
+        .map(|i| {
+            (
+                format!("User::\"u{}\"", i * 7 % USERS),
+                format!("Action::\"{}\"", if i % 2 == 0 { "edit" } else { "view" }),
+                format!("Document::\"d{}\"", i * 11 % DOCUMENTS),
+                json!({}),
+            )
+        })
+        .collect();
+    let mut corpus = Corpus::new(
+        "templates",
+        schema,
+        policies.to_string(),
+        entities,
+        requests,
+    );
+    let templates: Vec<PolicyId> = corpus
+        .policies
+        .templates()
+        .map(|t| t.id().clone())
+        .collect();
+    for i in 0..LINKS {
+        corpus
+            .policies
+            .link(
+                templates[i % templates.len()].clone(),
+                PolicyId::new(format!("link{i}")),
+                [
+                    (
+                        SlotId::principal(),
+                        EntityUid::from_str(&format!("User::\"u{}\"", i % USERS)).unwrap(),
+                    ),
+                    (
+                        SlotId::resource(),
+                        EntityUid::from_str(&format!("Document::\"d{}\"", i * 3 % DOCUMENTS))
+                            .unwrap(),
+                    ),
+                ]
+                .into(),
+            )
+            .unwrap();
+    }
+    corpus
+}
+
+/// A deep hierarchy of folders, stressing the transitive closure of entity
+/// ancestors
+fn deep_hierarchy() -> Corpus {
+    const DEPTH: usize = 200;
+    const GROUPS: usize = 20;
+    let schema = r#"
+        entity Group in [Group];
+        entity User in [Group];
+        entity Folder in [Folder];
+        action view appliesTo { principal: User, resource: Folder };
+    "#;
+    let policies = (0..GROUPS)
+        .map(|group| {
+            format!(
+                "permit(principal in Group::\"g{group}\", action, resource in Folder::\"f{}\");\n",
+                group * 7
+            )
+        })
+        .collect();
+    let mut entities: Vec<Value> = (0..DEPTH)
+        .map(|depth| {
+            let parents: Vec<Value> = if depth == 0 {
+                vec![]
+            } else {
+                vec![uid("Folder", format!("f{}", depth - 1))]
+            };
+            json!({ "uid": uid("Folder", format!("f{depth}")), "attrs": {}, "parents": parents })
+        })
+        .collect();
+    entities.extend((0..GROUPS).map(|group| {
+        let parents: Vec<Value> = if group == 0 {
+            vec![]
+        } else {
+            vec![uid("Group", format!("g{}", group - 1))]
+        };
+        json!({ "uid": uid("Group", format!("g{group}")), "attrs": {}, "parents": parents })
+    }));
+    entities.extend((0..GROUPS).map(|group| {
+        json!({
+            "uid": uid("User", format!("u{group}")),
+            "attrs": {},
+            "parents": [uid("Group", format!("g{group}"))],
+        })
+    }));
+    let requests = (0..100)
+        .map(|i| {
+            (
+                format!("User::\"u{}\"", i % GROUPS),
+                "Action::\"view\"".to_string(),
+                format!("Folder::\"f{}\"", i * 37 % DEPTH),
+                json!({}),
+            )
+        })
+        .collect();
+    Corpus::new("deep_hierarchy", schema, policies, entities, requests)
+}
+
+pub fn corpora_benchmark(c: &mut Criterion) {
+    let auth = Authorizer::new();
+    let mut group = c.benchmark_group("corpora");
+    for corpus in [rbac(), abac(), templates(), deep_hierarchy()] {
+        group.bench_with_input(
+            BenchmarkId::new("parse", corpus.name),
+            &corpus,
+            |b, corpus| b.iter(|| PolicySet::from_str(black_box(&corpus.policies_src)).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("validate", corpus.name),
+            &corpus,
+            |b, corpus| {
+                let validator = Validator::new(corpus.schema.clone());
+                b.iter(|| validator.validate(black_box(&corpus.policies), ValidationMode::Strict))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("is_authorized", corpus.name),
+            &corpus,
+            |b, corpus| {
+                b.iter(|| {
+                    for request in &corpus.requests {
+                        black_box(auth.is_authorized(request, &corpus.policies, &corpus.entities));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, corpora_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,110 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// PANIC SAFETY benchmarking
+#![allow(clippy::unwrap_used)]
+
+//! Benchmarks of authorization with and without deciding policies with
+//! trivial conditions by matching their scope (`Authorizer::with_fast_paths()`),
+//! on policies which are mostly role-based.
+
+use std::str::FromStr;
+
+use cedar_policy::{Authorizer, Context, Entities, EntityUid, PolicySet, Request};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+
+const ROLES: usize = 40;
+const USERS: usize = 200;
+const FOLDERS: usize = 20;
+/// One in `ATTRIBUTE_POLICY_EVERY` policies has a condition on attributes
+const ATTRIBUTE_POLICY_EVERY: usize = 5;
+const POLICIES: usize = 200;
+
+fn uid(ty: &str, id: impl std::fmt::Display) -> Value {
+    json!({ "type": ty, "id": id.to_string() })
+}
+
+pub fn fast_paths_benchmark(c: &mut Criterion) {
+    let mut policies = String::new();
+    for i in 0..POLICIES {
+        let (role, folder) = (i % ROLES, i % FOLDERS);
+        let action = ["view", "edit", "delete"][i % 3];
+        if i % ATTRIBUTE_POLICY_EVERY == 0 {
+            policies.push_str(&format!(
+                "permit(principal in Role::\"r{role}\", action == Action::\"{action}\", resource in Folder::\"f{folder}\") when {{ resource.level <= principal.level }};\n"
+            ));
+        } else {
+            policies.push_str(&format!(
+                "permit(principal in Role::\"r{role}\", action == Action::\"{action}\", resource in Folder::\"f{folder}\");\n"
+            ));
+        }
+    }
+    policies.push_str("forbid(principal in Role::\"suspended\", action, resource);\n");
+    let policies = PolicySet::from_str(&policies).unwrap();
+
+    let mut entities: Vec<Value> = (0..USERS)
+        .map(|user| {
+            json!({
+                "uid": uid("User", format!("u{user}")),
+                "attrs": { "level": user % 10 },
+                "parents": [uid("Role", format!("r{}", user % ROLES)), uid("Role", format!("r{}", user * 7 % ROLES))],
+            })
+        })
+        .collect();
+    entities.extend((0..USERS).map(|doc| {
+        json!({
+            "uid": uid("Document", format!("d{doc}")),
+            "attrs": { "level": doc % 10 },
+            "parents": [uid("Folder", format!("f{}", doc % FOLDERS))],
+        })
+    }));
+    let entities = Entities::from_json_value(Value::Array(entities), None).unwrap();
+
+    let requests: Vec<Request> = (0..100)
+        .map(|i| {
+            Request::new(
+                EntityUid::from_str(&format!("User::\"u{}\"", i * 13 % USERS)).unwrap(),
+                EntityUid::from_str(&format!(
+                    "Action::\"{}\"",
+                    ["view", "edit", "delete"][i % 3]
+                ))
+                .unwrap(),
+                EntityUid::from_str(&format!("Document::\"d{}\"", i * 7 % USERS)).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("fast_paths");
+    for (name, auth) in [
+        ("evaluated", Authorizer::new().with_fast_paths(false)),
+        ("fast_paths", Authorizer::new()),
+    ] {
+        group.bench_function(BenchmarkId::new("is_authorized", name), |b| {
+            b.iter(|| {
+                for request in &requests {
+                    black_box(auth.is_authorized(request, &policies, &entities));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fast_paths_benchmark);
+criterion_main!(benches);
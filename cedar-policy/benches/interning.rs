@@ -0,0 +1,111 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// PANIC SAFETY benchmarking
+#![allow(clippy::unwrap_used)]
+
+//! Benchmarks of authorization with and without interning of values
+//! (`Authorizer::with_interned_values()`), on policies which compare large
+//! group sets.
+
+use std::str::FromStr;
+
+use cedar_policy::{Authorizer, Context, Entities, EntityUid, PolicySet, Request};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+
+const GROUPS: usize = 500;
+const USERS: usize = 20;
+const DOCUMENTS: usize = 20;
+const POLICIES: usize = 50;
+
+fn uid(ty: &str, id: impl std::fmt::Display) -> Value {
+    json!({ "type": ty, "id": id.to_string() })
+}
+
+/// The groups of user or document `i`, which overlap for all users and
+/// documents with the same `i % 4`
+fn groups(i: usize) -> Vec<Value> {
+    (0..GROUPS)
+        .filter(|g| g % 4 != i % 4)
+        .map(|g| uid("Group", format!("g{g}")))
+        .collect()
+}
+
+pub fn interning_benchmark(c: &mut Criterion) {
+    let mut policies = String::new();
+    for i in 0..POLICIES {
+        policies.push_str(&format!(
+            "permit(principal, action, resource) when {{ principal.groups == resource.groups && resource.level == {i} }};\n"
+        ));
+        policies.push_str(&format!(
+            "permit(principal, action, resource) when {{ resource.teams.contains(principal.groups) && resource.level == {i} }};\n"
+        ));
+    }
+    let policies = PolicySet::from_str(&policies).unwrap();
+
+    let mut entities: Vec<Value> = (0..USERS)
+        .map(|user| {
+            json!({
+                "uid": uid("User", format!("u{user}")),
+                "attrs": { "groups": groups(user) },
+                "parents": [],
+            })
+        })
+        .collect();
+    entities.extend((0..DOCUMENTS).map(|doc| {
+        json!({
+            "uid": uid("Document", format!("d{doc}")),
+            "attrs": {
+                "level": doc % POLICIES,
+                "groups": groups(doc),
+                "teams": [groups(doc + 1), groups(doc + 2), groups(doc)],
+            },
+            "parents": [],
+        })
+    }));
+    let entities = Entities::from_json_value(Value::Array(entities), None).unwrap();
+
+    let requests: Vec<Request> = (0..100)
+        .map(|i| {
+            Request::new(
+                EntityUid::from_str(&format!("User::\"u{}\"", i % USERS)).unwrap(),
+                EntityUid::from_str("Action::\"view\"").unwrap(),
+                EntityUid::from_str(&format!("Document::\"d{}\"", i * 7 % DOCUMENTS)).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("interning");
+    for (name, auth) in [
+        ("plain", Authorizer::new()),
+        ("interned", Authorizer::new().with_interned_values(true)),
+    ] {
+        group.bench_function(BenchmarkId::new("is_authorized", name), |b| {
+            b.iter(|| {
+                for request in &requests {
+                    black_box(auth.is_authorized(request, &policies, &entities));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, interning_benchmark);
+criterion_main!(benches);
@@ -26,6 +26,7 @@ mod id;
 #[cfg(feature = "entity-manifest")]
 use cedar_policy_validator::entity_manifest;
 // TODO (#1157) implement wrappers for these structs before they become public
+pub use cedar_policy_validator::entity_generator::EntityGeneratorConfig;
 #[cfg(feature = "entity-manifest")]
 pub use cedar_policy_validator::entity_manifest::{
     AccessTrie, EntityManifest, EntityRoot, Fields, RootAccessTrie,
@@ -36,6 +37,70 @@ pub use id::*;
 mod err;
 pub use err::*;
 
+mod bulk_link;
+pub use bulk_link::{BulkLinkError, BulkLinkFailure, BulkLinkReport, BulkTemplateLink};
+mod claims_mapping;
+pub use claims_mapping::{ClaimsMapping, ClaimsMappingError, GroupClaim, MappedClaims};
+mod constants;
+pub use constants::Constants;
+mod decision_cache;
+pub use decision_cache::DecisionCache;
+mod duplicates;
+pub use duplicates::PolicyEquivalence;
+mod entity_migration;
+pub use entity_migration::{EntityMigrationIssue, EntityMigrationReport};
+mod entity_source;
+pub use entity_source::{EntitySource, ScimEntitySource, ScimError, ScimMapping};
+mod evaluation_context;
+pub use evaluation_context::EvaluationContext;
+mod expression_sandbox;
+pub use expression_sandbox::{evaluate_expression, ExpressionBindings, ExpressionEvaluationError};
+mod feature_policy;
+pub use feature_policy::FeaturePolicy;
+mod incremental_validator;
+pub use incremental_validator::IncrementalValidator;
+mod layered;
+pub use layered::{LayeredResponse, LayeredValidationResult, OverriddenPermit, PolicyLayers};
+mod like_pattern;
+pub use like_pattern::LikePattern;
+mod message_catalog;
+pub use message_catalog::{LocalizedDiagnostic, MessageCatalog};
+mod policy_cache;
+pub use policy_cache::{CachedPolicySet, PolicyCache};
+mod policy_events;
+use policy_events::Observers;
+pub use policy_events::PolicySetObserver;
+mod policy_json;
+pub use policy_json::{ExprJson, PolicyJson};
+mod policy_summary;
+pub use policy_summary::{Condition, PolicySummary};
+mod prefilter;
+pub use prefilter::{PrefilterStats, PrefilteredPolicySet};
+mod redaction;
+pub use redaction::{Redacted, Redaction};
+mod request_limits;
+pub use request_limits::{RequestLimitError, RequestLimits};
+#[cfg(feature = "partial-eval")]
+mod residuals;
+#[cfg(feature = "partial-eval")]
+pub use residuals::{Residual, Residuals, UnknownProvenance};
+mod schema_registry;
+pub use schema_registry::{
+    AsyncSchemaProvider, SchemaDocument, SchemaKey, SchemaProvider, SchemaRegistry,
+    SchemaRegistryError,
+};
+mod shared_policy_set;
+pub use shared_policy_set::SharedPolicySet;
+mod validation_baseline;
+pub use validation_baseline::ValidationBaseline;
+mod validation_profile;
+pub use validation_profile::{PolicyProfile, RequestEnvProfile, ValidationProfile};
+mod versioned_schema;
+pub use versioned_schema::{
+    PinnedValidationError, PinnedValidationResult, VersionedSchema, VersionedSchemaError,
+    SCHEMA_VERSIONS_ANNOTATION,
+};
+
 pub use ast::Effect;
 pub use authorizer::Decision;
 use cedar_policy_core::ast;
@@ -44,11 +109,13 @@ use cedar_policy_core::ast::BorrowedRestrictedExpr;
 use cedar_policy_core::authorizer;
 use cedar_policy_core::entities::{ContextSchema, Dereference};
 use cedar_policy_core::est::{self, TemplateLink};
-use cedar_policy_core::evaluator::Evaluator;
 #[cfg(feature = "partial-eval")]
 use cedar_policy_core::evaluator::RestrictedEvaluator;
+use cedar_policy_core::evaluator::{CompiledEntitySets, Evaluator};
+pub use cedar_policy_core::evaluator::{OverflowMode, UnknownFunctionMode};
 use cedar_policy_core::extensions::Extensions;
 use cedar_policy_core::parser;
+pub use cedar_policy_core::stable_ast::VERSION as STABLE_AST_VERSION;
 use cedar_policy_core::FromNormalizedStr;
 use itertools::{Either, Itertools};
 use miette::Diagnostic;
@@ -58,6 +125,8 @@ use smol_str::SmolStr;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::io::Read;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 /// Entity datatype
 #[repr(transparent)]
@@ -300,8 +369,47 @@ impl std::fmt::Display for Entity {
     }
 }
 
+impl Entity {
+    /// Display this entity with the attribute values not allowed by
+    /// `redaction` masked, e.g., for logging
+    pub fn redacted<'a>(&'a self, redaction: &'a Redaction) -> Redacted<'a, Self> {
+        Redacted::new(self, redaction)
+    }
+}
+
+/// When the transitive closure of the entity hierarchy is computed, see
+/// [`Entities::from_entities_with_tc()`] and the other `_with_tc`
+/// constructors of [`Entities`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TCComputation {
+    /// Compute the transitive closure when constructing the `Entities`, and
+    /// report an error if the hierarchy has a cycle
+    #[default]
+    ComputeNow,
+    /// Only store the parents given for each entity, and compute the
+    /// ancestors of an entity when a hierarchy query (e.g., `in` during
+    /// authorization) first needs them. This makes constructing a large
+    /// `Entities`, in which most entities are never queried, much faster.
+    /// Cycles in the hierarchy are not reported.
+    ComputeOnDemand,
+}
+
+impl From<TCComputation> for cedar_policy_core::entities::TCComputation {
+    fn from(tc: TCComputation) -> Self {
+        match tc {
+            TCComputation::ComputeNow => Self::ComputeNow,
+            TCComputation::ComputeOnDemand => Self::ComputeOnDemand,
+        }
+    }
+}
+
 /// Represents an entity hierarchy, and allows looking up `Entity` objects by
 /// Uid.
+///
+/// `Entities` is `Send + Sync`, so it can be shared between threads without
+/// a lock. Cloning it is cheap: clones share their entities, which are only
+/// copied when a clone is modified, e.g., with [`Entities::add_entities`].
 #[repr(transparent)]
 #[derive(Debug, Clone, Default, PartialEq, Eq, RefCast)]
 pub struct Entities(pub(crate) cedar_policy_core::entities::Entities);
@@ -341,6 +449,16 @@ impl Entities {
         self.0.iter().map(Entity::ref_cast)
     }
 
+    /// How to compute the transitive closure when adding entities to this
+    /// store: on demand if the store already computes it on demand
+    fn tc_for_additions(&self) -> cedar_policy_core::entities::TCComputation {
+        if self.0.computes_tc_on_demand() {
+            cedar_policy_core::entities::TCComputation::ComputeOnDemand
+        } else {
+            cedar_policy_core::entities::TCComputation::ComputeNow
+        }
+    }
+
     /// Create an `Entities` object with the given entities.
     ///
     /// `schema` represents a source of `Action` entities, which will be added
@@ -362,13 +480,36 @@ impl Entities {
     pub fn from_entities(
         entities: impl IntoIterator<Item = Entity>,
         schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesError> {
+        Self::from_entities_with_tc(entities, schema, TCComputation::ComputeNow)
+    }
+
+    /// Like [`Entities::from_entities()`], but computing the transitive
+    /// closure of the entity hierarchy according to `tc`. Entities added to
+    /// a store which computes it on demand, e.g., with
+    /// [`Entities::add_entities()`], are added the same way.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, Entity, EntityUid, TCComputation};
+    /// # use std::collections::HashSet;
+    /// # use std::str::FromStr;
+    /// let uid = |s| EntityUid::from_str(s).unwrap();
+    /// let alice = Entity::new_no_attrs(uid(r#"User::"alice""#), HashSet::from([uid(r#"Group::"eng""#)]));
+    /// let eng = Entity::new_no_attrs(uid(r#"Group::"eng""#), HashSet::from([uid(r#"Group::"all""#)]));
+    /// let entities = Entities::from_entities_with_tc([alice, eng], None, TCComputation::ComputeOnDemand).unwrap();
+    /// assert!(entities.is_ancestor_of(&uid(r#"Group::"all""#), &uid(r#"User::"alice""#)));
+    /// ```
+    pub fn from_entities_with_tc(
+        entities: impl IntoIterator<Item = Entity>,
+        schema: Option<&Schema>,
+        tc: TCComputation,
     ) -> Result<Self, EntitiesError> {
         cedar_policy_core::entities::Entities::from_entities(
             entities.into_iter().map(|e| e.0),
             schema
                 .map(|s| cedar_policy_validator::CoreSchema::new(&s.0))
                 .as_ref(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc.into(),
             Extensions::all_available(),
         )
         .map(Entities)
@@ -394,13 +535,14 @@ impl Entities {
         entities: impl IntoIterator<Item = Entity>,
         schema: Option<&Schema>,
     ) -> Result<Self, EntitiesError> {
+        let tc = self.tc_for_additions();
         Ok(Self(
             self.0.add_entities(
                 entities.into_iter().map(|e| e.0),
                 schema
                     .map(|s| cedar_policy_validator::CoreSchema::new(&s.0))
                     .as_ref(),
-                cedar_policy_core::entities::TCComputation::ComputeNow,
+                tc,
                 Extensions::all_available(),
             )?,
         ))
@@ -436,10 +578,11 @@ impl Entities {
             cedar_policy_core::entities::TCComputation::ComputeNow,
         );
         let new_entities = eparser.iter_from_json_str(json)?;
+        let tc = self.tc_for_additions();
         Ok(Self(self.0.add_entities(
             new_entities,
             schema.as_ref(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc,
             Extensions::all_available(),
         )?))
     }
@@ -474,10 +617,11 @@ impl Entities {
             cedar_policy_core::entities::TCComputation::ComputeNow,
         );
         let new_entities = eparser.iter_from_json_value(json)?;
+        let tc = self.tc_for_additions();
         Ok(Self(self.0.add_entities(
             new_entities,
             schema.as_ref(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc,
             Extensions::all_available(),
         )?))
     }
@@ -513,10 +657,11 @@ impl Entities {
             cedar_policy_core::entities::TCComputation::ComputeNow,
         );
         let new_entities = eparser.iter_from_json_file(json)?;
+        let tc = self.tc_for_additions();
         Ok(Self(self.0.add_entities(
             new_entities,
             schema.as_ref(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc,
             Extensions::all_available(),
         )?))
     }
@@ -572,11 +717,21 @@ impl Entities {
     /// # assert_eq!(ip, EvalResult::ExtensionValue("10.0.1.101/32".to_string()));
     /// ```
     pub fn from_json_str(json: &str, schema: Option<&Schema>) -> Result<Self, EntitiesError> {
+        Self::from_json_str_with_tc(json, schema, TCComputation::ComputeNow)
+    }
+
+    /// Like [`Entities::from_json_str()`], but computing the transitive closure of
+    /// the entity hierarchy according to `tc`
+    pub fn from_json_str_with_tc(
+        json: &str,
+        schema: Option<&Schema>,
+        tc: TCComputation,
+    ) -> Result<Self, EntitiesError> {
         let schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
         let eparser = cedar_policy_core::entities::EntityJsonParser::new(
             schema.as_ref(),
             Extensions::all_available(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc.into(),
         );
         eparser.from_json_str(json).map(Entities)
     }
@@ -629,12 +784,22 @@ impl Entities {
     pub fn from_json_value(
         json: serde_json::Value,
         schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesError> {
+        Self::from_json_value_with_tc(json, schema, TCComputation::ComputeNow)
+    }
+
+    /// Like [`Entities::from_json_value()`], but computing the transitive closure of
+    /// the entity hierarchy according to `tc`
+    pub fn from_json_value_with_tc(
+        json: serde_json::Value,
+        schema: Option<&Schema>,
+        tc: TCComputation,
     ) -> Result<Self, EntitiesError> {
         let schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
         let eparser = cedar_policy_core::entities::EntityJsonParser::new(
             schema.as_ref(),
             Extensions::all_available(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc.into(),
         );
         eparser.from_json_value(json).map(Entities)
     }
@@ -665,21 +830,55 @@ impl Entities {
     pub fn from_json_file(
         json: impl std::io::Read,
         schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesError> {
+        Self::from_json_file_with_tc(json, schema, TCComputation::ComputeNow)
+    }
+
+    /// Like [`Entities::from_json_file()`], but computing the transitive closure of
+    /// the entity hierarchy according to `tc`
+    pub fn from_json_file_with_tc(
+        json: impl std::io::Read,
+        schema: Option<&Schema>,
+        tc: TCComputation,
     ) -> Result<Self, EntitiesError> {
         let schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
         let eparser = cedar_policy_core::entities::EntityJsonParser::new(
             schema.as_ref(),
             Extensions::all_available(),
-            cedar_policy_core::entities::TCComputation::ComputeNow,
+            tc.into(),
         );
         eparser.from_json_file(json).map(Entities)
     }
 
+    /// Check which of these entities would not conform to the schema `new`
+    /// which is to replace the schema `old`, e.g., because they lack a
+    /// required attribute or an attribute changed type. This helps to fix the
+    /// entity data before switching to the new schema. Action entities are
+    /// not checked, since they are defined by the schema.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, EntityTypeName, Schema};
+    /// # use std::str::FromStr;
+    /// let old: Schema = "entity User { name: String };".parse().unwrap();
+    /// let new: Schema = "entity User { name: String, email: String };".parse().unwrap();
+    /// let entities = Entities::from_json_value(serde_json::json!([
+    ///     { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice" }, "parents": [] },
+    /// ]), Some(&old)).unwrap();
+    /// let report = entities.check_migration(&old, &new);
+    /// let (ty, issues) = report.by_entity_type().next().unwrap();
+    /// assert_eq!(ty, &EntityTypeName::from_str("User").unwrap());
+    /// assert_eq!(issues[0].uid().to_string(), r#"User::"alice""#);
+    /// assert!(issues[0].conformed_to_old());
+    /// ```
+    pub fn check_migration(&self, old: &Schema, new: &Schema) -> EntityMigrationReport {
+        EntityMigrationReport::new(old, new, self)
+    }
+
     /// Is entity `a` an ancestor of entity `b`?
     /// Same semantics as `b in a` in the Cedar language
     pub fn is_ancestor_of(&self, a: &EntityUid, b: &EntityUid) -> bool {
         match self.0.entity(b.as_ref()) {
-            Dereference::Data(b) => b.is_descendant_of(a.as_ref()),
+            Dereference::Data(b) => self.0.is_descendant_of(b, a.as_ref()),
             _ => a == b, // if b doesn't exist, `b in a` is only true if `b == a`
         }
     }
@@ -694,7 +893,7 @@ impl Entities {
             Dereference::Residual(_) | Dereference::NoSuchEntity => None,
             Dereference::Data(e) => Some(e),
         }?;
-        Some(entity.ancestors().map(EntityUid::ref_cast))
+        Some(self.0.ancestors_of(entity).map(EntityUid::ref_cast))
     }
 
     /// Dump an `Entities` object into an entities JSON file.
@@ -715,6 +914,17 @@ impl Entities {
     pub fn to_dot_str(&self) -> String {
         self.0.to_dot_str()
     }
+
+    /// Approximate number of bytes of heap memory used by this `Entities`,
+    /// e.g., for capacity planning.
+    ///
+    /// This is an estimate: it does not include allocator overhead, and
+    /// counts ancestor sets shared between entities once per entity.
+    #[cfg(feature = "heap-size")]
+    pub fn approximate_heap_size(&self) -> usize {
+        use cedar_policy_core::heap_size::HeapSize;
+        self.0.heap_size()
+    }
 }
 
 /// Utilities for defining `IntoIterator` over `Entities`
@@ -750,10 +960,24 @@ impl IntoIterator for Entities {
 }
 
 /// Authorizer object, which provides responses to authorization queries
+///
+/// `Authorizer` is `Send + Sync` and cheap to clone, and authorizing doesn't
+/// modify it, so one `Authorizer` can serve requests on many threads.
 #[repr(transparent)]
-#[derive(Debug, RefCast)]
+#[derive(Debug, Clone, RefCast)]
 pub struct Authorizer(authorizer::Authorizer);
 
+// The types shared between threads by services are guaranteed to be
+// `Send + Sync`: this fails to compile if one of them isn't.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Authorizer>();
+    assert_send_sync::<PolicySet>();
+    assert_send_sync::<Entities>();
+    assert_send_sync::<Schema>();
+    assert_send_sync::<Validator>();
+};
+
 impl Default for Authorizer {
     fn default() -> Self {
         Self::new()
@@ -816,6 +1040,142 @@ impl Authorizer {
         Self(authorizer::Authorizer::new())
     }
 
+    /// Set what happens when Long arithmetic overflows while evaluating
+    /// policies. By default ([`OverflowMode::Error`]), an overflow is an
+    /// evaluation error, so the policy is skipped.
+    ///
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Decision, Entities, EntityUid, OverflowMode, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let policies: PolicySet = r#"
+    ///     permit(principal, action, resource) when { 9223372036854775807 + 1 > 0 };
+    /// "#.parse().unwrap();
+    /// let request = Request::new(
+    ///     EntityUid::from_str(r#"User::"alice""#).unwrap(),
+    ///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+    ///     EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+    ///     Context::empty(),
+    ///     None,
+    /// ).unwrap();
+    /// let entities = Entities::empty();
+    /// let response = Authorizer::new().is_authorized(&request, &policies, &entities);
+    /// assert_eq!(response.decision(), Decision::Deny);
+    /// let response = Authorizer::new()
+    ///     .with_overflow_mode(OverflowMode::Saturate)
+    ///     .is_authorized(&request, &policies, &entities);
+    /// assert_eq!(response.decision(), Decision::Allow);
+    /// ```
+    #[must_use]
+    pub fn with_overflow_mode(self, overflow_mode: OverflowMode) -> Self {
+        Self(self.0.with_overflow_mode(overflow_mode))
+    }
+
+    /// Evaluate subexpressions which occur in the conditions of several
+    /// policies only once per request, e.g., guards repeated across generated
+    /// policies. This doesn't change any decision or diagnostic, but finding
+    /// the shared subexpressions takes time for each request, so it only pays
+    /// off for large policy sets with expensive shared conditions. Disabled by
+    /// default.
+    #[must_use]
+    pub fn with_shared_subexpressions(self, share: bool) -> Self {
+        Self(self.0.with_shared_subexpressions(share))
+    }
+
+    /// Intern the sets and records produced while evaluating a request, e.g.,
+    /// attributes of entities, so that repeated comparisons of equal sets and
+    /// records are pointer comparisons. This doesn't change any decision or
+    /// diagnostic, but interning hashes each distinct set or record once per
+    /// request, so it only pays off for policies which compare large sets or
+    /// records repeatedly. Disabled by default.
+    #[must_use]
+    pub fn with_interned_values(self, intern: bool) -> Self {
+        Self(self.0.with_interned_values(intern))
+    }
+
+    /// Compile the set literals of at least 64 entity uids in `policies`,
+    /// e.g., large allowlists, to bitsets, so that evaluating them doesn't
+    /// rebuild the set and `contains`, `containsAll`, and `containsAny` test
+    /// bits. Compiling takes time proportional to the size of the set
+    /// literals, so compile once and reuse this `Authorizer`. It can still
+    /// authorize requests against other policy sets, including modified
+    /// clones of `policies`: set literals which weren't compiled are evaluated
+    /// as usual.
+    #[must_use]
+    pub fn with_compiled_entity_sets(self, policies: &PolicySet) -> Self {
+        let entity_sets = CompiledEntitySets::new(&policies.ast);
+        Self(self.0.with_compiled_entity_sets(Arc::new(entity_sets)))
+    }
+
+    /// Decide the policies whose conditions are trivially `true` or `false`,
+    /// e.g., role-based policies with only a scope, by matching their scope
+    /// against the request, without evaluating their condition. This doesn't
+    /// change any decision or diagnostic. Enabled by default; disabling it is
+    /// mostly useful to measure its effect.
+    #[must_use]
+    pub fn with_fast_paths(self, enabled: bool) -> Self {
+        Self(self.0.with_fast_paths(enabled))
+    }
+
+    /// Evaluate the forbid policies first, and stop evaluating policies once
+    /// a forbid is satisfied, since the decision is then `Deny` whatever the
+    /// other policies evaluate to. This cuts the latency of requests which are
+    /// denied, but changes the diagnostics of their responses: the `reason()`
+    /// only has the first satisfied forbid, and the `errors()` only have the
+    /// errors of the policies evaluated before it, so erroring policies may
+    /// not be reported. Disabled by default.
+    #[must_use]
+    pub fn with_stop_at_first_forbid(self, stop: bool) -> Self {
+        Self(self.0.with_stop_at_first_forbid(stop))
+    }
+
+    /// Set what happens when a policy calls an extension function which
+    /// doesn't exist, e.g., a policy loaded with
+    /// [`PolicySet::from_stable_ast()`] which calls a function of a newer
+    /// version of Cedar. By default, [`UnknownFunctionMode::Error`]: the call
+    /// is an evaluation error, so the policy is skipped. Otherwise, the call
+    /// evaluates to `false` or is left as a residual, and is reported in the
+    /// [`Diagnostics::unknown_function_calls()`] of responses.
+    ///
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Decision, Entities, PolicySet, Request, UnknownFunctionMode};
+    /// # use std::str::FromStr;
+    /// // a policy stored by a newer version of Cedar, with the function `geo::inRegion`
+    /// let stored = PolicySet::from_str(r#"
+    ///     forbid(principal, action, resource) when { decimal("1.0").lessThan(decimal("2.0")) };
+    /// "#).unwrap().to_stable_ast().unwrap();
+    /// let stored = stored.to_string().replace(r#""lessThan""#, r#""geo::inRegion""#);
+    /// let pset = PolicySet::from_stable_ast(serde_json::from_str(&stored).unwrap()).unwrap();
+    /// let request = Request::new(
+    ///     r#"User::"alice""#.parse().unwrap(),
+    ///     r#"Action::"view""#.parse().unwrap(),
+    ///     r#"Photo::"trip""#.parse().unwrap(),
+    ///     Context::empty(),
+    ///     None,
+    /// ).unwrap();
+    /// let authorizer = Authorizer::new().with_unknown_function_mode(UnknownFunctionMode::False);
+    /// let response = authorizer.is_authorized(&request, &pset, &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Deny);
+    /// assert_eq!(response.diagnostics().errors().count(), 0);
+    /// assert_eq!(response.diagnostics().unknown_function_calls().count(), 1);
+    /// ```
+    #[must_use]
+    pub fn with_unknown_function_mode(self, mode: UnknownFunctionMode) -> Self {
+        Self(self.0.with_unknown_function_mode(mode))
+    }
+
+    /// Bound the total cost of the extension function calls evaluated for
+    /// each request to `budget`, e.g., so that a policy calling `ip()`
+    /// thousands of times doesn't degrade latency. Most functions cost 1, and
+    /// the constructors parsing a string, `ip()` and `decimal()`, cost 10,
+    /// unless the string is a literal, which is parsed with the policy.
+    /// Once the budget is spent, the policies calling an extension function
+    /// are skipped with an [`EvaluationError::LimitExceeded`] error. Unbounded
+    /// by default.
+    #[must_use]
+    pub fn with_extension_budget(self, budget: u64) -> Self {
+        Self(self.0.with_extension_budget(budget))
+    }
+
     /// Returns an authorization response for `r` with respect to the given
     /// `PolicySet` and `Entities`.
     ///
@@ -873,6 +1233,38 @@ impl Authorizer {
         self.0.is_authorized(r.0.clone(), &p.ast, &e.0).into()
     }
 
+    /// Returns an authorization response for `r` with respect to the union of
+    /// the policy sets of `layers` and `e`, without merging the policy sets.
+    /// See [`PolicyLayers`].
+    pub fn is_authorized_layered(
+        &self,
+        r: &Request,
+        layers: &PolicyLayers,
+        e: &Entities,
+    ) -> LayeredResponse {
+        LayeredResponse::new(
+            layers
+                .layers()
+                .map(|(name, p)| (name.to_string(), self.is_authorized(r, p, e)))
+                .collect(),
+        )
+    }
+
+    /// Evaluates every policy of `p` for `r` and `e`, returning the result of
+    /// each policy, in the order of their ids.
+    ///
+    /// Unlike [`Authorizer::is_authorized`], this never skips a policy, even
+    /// when the decision is already known, and reports the result of the
+    /// policies which don't contribute to the decision, e.g., to detect
+    /// policies which error on real requests.
+    pub fn evaluate_all(&self, r: &Request, p: &PolicySet, e: &Entities) -> Vec<PolicyEvaluation> {
+        self.0
+            .evaluate_all(r.0.clone(), &p.ast, &e.0)
+            .into_iter()
+            .map(PolicyEvaluation)
+            .collect()
+    }
+
     /// A partially evaluated authorization request.
     /// The Authorizer will attempt to make as much progress as possible in the presence of unknowns.
     /// If the Authorizer can reach a response, it will return that response.
@@ -892,6 +1284,29 @@ impl Authorizer {
     }
 }
 
+/// Result of evaluating a single policy, returned by
+/// [`Authorizer::evaluate_all`]
+#[derive(Debug, Clone)]
+pub struct PolicyEvaluation(authorizer::PolicyEvaluation);
+
+impl PolicyEvaluation {
+    /// Id of the policy
+    pub fn id(&self) -> &PolicyId {
+        PolicyId::ref_cast(&self.0.id)
+    }
+
+    /// Effect of the policy
+    pub fn effect(&self) -> Effect {
+        self.0.effect
+    }
+
+    /// Whether the policy is satisfied, or the error its evaluation
+    /// encountered
+    pub fn result(&self) -> Result<bool, &EvaluationError> {
+        self.0.result.as_ref().copied()
+    }
+}
+
 /// Authorization response returned from the `Authorizer`
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Response {
@@ -924,6 +1339,19 @@ impl PartialResponse {
         self.0.concretize().into()
     }
 
+    /// Simplify the residuals of this response, to make them easier to
+    /// convert to, e.g., query filters: constant subexpressions are folded,
+    /// `true &&` and `false ||` are removed, and repeated conditions on the
+    /// same unknowns are deduplicated.
+    ///
+    /// The simplification preserves the result of evaluating the residuals
+    /// (including errors) for every value of the unknowns only if the policies
+    /// pass validation, so don't simplify the residuals of unvalidated policies.
+    #[must_use]
+    pub fn simplified(self) -> Self {
+        Self(self.0.simplified())
+    }
+
     /// Returns the set of [`Policy`]s that were definitely satisfied.
     /// This will be the set of policies (both `permit` and `forbid`) that evaluated to `true`
     pub fn definitely_satisfied(&self) -> impl Iterator<Item = Policy> + '_ {
@@ -1011,6 +1439,9 @@ pub struct Diagnostics {
     /// Errors that occurred during authorization. The errors should be
     /// treated as unordered, since policies may be evaluated in any order.
     errors: Vec<AuthorizationError>,
+    /// Calls of extension functions which don't exist, which weren't errors
+    /// due to the [`UnknownFunctionMode`] of the authorizer
+    unknown_function_calls: Vec<AuthorizationError>,
 }
 
 #[doc(hidden)]
@@ -1019,6 +1450,11 @@ impl From<authorizer::Diagnostics> for Diagnostics {
         Self {
             reason: diagnostics.reason.into_iter().map(PolicyId::new).collect(),
             errors: diagnostics.errors.into_iter().map(Into::into).collect(),
+            unknown_function_calls: diagnostics
+                .unknown_function_calls
+                .into_iter()
+                .map(Into::into)
+                .collect(),
         }
     }
 }
@@ -1143,6 +1579,13 @@ impl Diagnostics {
         self.errors.iter()
     }
 
+    /// Get the calls of extension functions which don't exist, which weren't
+    /// errors due to the
+    /// [`Authorizer::with_unknown_function_mode()`] of the authorizer
+    pub fn unknown_function_calls(&self) -> impl Iterator<Item = &AuthorizationError> + '_ {
+        self.unknown_function_calls.iter()
+    }
+
     /// Consume the `Diagnostics`, producing owned versions of `reason()` and `errors()`
     pub(crate) fn into_components(
         self,
@@ -1163,7 +1606,11 @@ impl Response {
     ) -> Self {
         Self {
             decision,
-            diagnostics: Diagnostics { reason, errors },
+            diagnostics: Diagnostics {
+                reason,
+                errors,
+                unknown_function_calls: Vec::new(),
+            },
         }
     }
 
@@ -1207,6 +1654,13 @@ pub enum ValidationMode {
     #[doc = include_str!("../experimental_warning.md")]
     #[cfg(feature = "partial-validate")]
     Partial,
+    /// Validate as in [`ValidationMode::Strict`], but treat entity types which
+    /// are not declared in the schema as opaque (with unknown attributes and
+    /// hierarchy) instead of reporting them as unrecognized. This is intended
+    /// for adopting a schema incrementally.
+    #[doc = include_str!("../experimental_warning.md")]
+    #[cfg(feature = "partial-validate")]
+    OpaqueEntityTypes,
 }
 
 #[doc(hidden)]
@@ -1218,6 +1672,8 @@ impl From<ValidationMode> for cedar_policy_validator::ValidationMode {
             ValidationMode::Permissive => Self::Permissive,
             #[cfg(feature = "partial-validate")]
             ValidationMode::Partial => Self::Partial,
+            #[cfg(feature = "partial-validate")]
+            ValidationMode::OpaqueEntityTypes => Self::OpaqueEntityTypes,
         }
     }
 }
@@ -1234,6 +1690,70 @@ impl Validator {
         Self(cedar_policy_validator::Validator::new(schema.0))
     }
 
+    /// Only suggest corrections for misspelled attributes, entity types, and
+    /// actions in validation errors when they are within `max_distance` edits
+    /// of the misspelling. By default, the closest declared name is suggested
+    /// however far it is, except for attributes found outside the accessed
+    /// type, which are suggested only if they are close.
+    #[must_use]
+    pub fn with_max_suggestion_distance(self, max_distance: usize) -> Self {
+        Self(self.0.with_max_suggestion_distance(max_distance))
+    }
+
+    /// Report a [`ValidationError::TypeTooLarge`] error, instead of computing
+    /// their least upper bound, for types containing a record with more than
+    /// `max_width` attributes, e.g., the types of the elements of a set
+    /// literal or of the branches of an `if`. This bounds the time and memory
+    /// spent validating adversarial or machine-generated policies. By
+    /// default, there is no limit.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationError, ValidationMode, Validator};
+    /// let schema: Schema = "entity User; action view appliesTo { principal: User, resource: User };".parse().unwrap();
+    /// let pset: PolicySet = r#"
+    ///     permit(principal, action, resource) when { [{a: 1, b: 2}, {a: 3, b: 4}].contains({a: 1, b: 2}) };
+    /// "#.parse().unwrap();
+    /// let result = Validator::new(schema).with_max_record_width(1).validate(&pset, ValidationMode::Strict);
+    /// assert!(matches!(
+    ///     result.validation_errors().next(),
+    ///     Some(ValidationError::TypeTooLarge(_))
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn with_max_record_width(self, max_width: usize) -> Self {
+        Self(self.0.with_max_record_width(max_width))
+    }
+
+    /// Like [`Validator::with_max_record_width`], for types with more than
+    /// `max_depth` set types nested in one another.
+    #[must_use]
+    pub fn with_max_set_depth(self, max_depth: usize) -> Self {
+        Self(self.0.with_max_set_depth(max_depth))
+    }
+
+    /// Report a [`ValidationError::ForbiddenFeature`] error for each use of a
+    /// language feature which `feature_policy` forbids. By default, all
+    /// features are allowed.
+    ///
+    /// ```
+    /// # use cedar_policy::{FeaturePolicy, PolicySet, Schema, ValidationError, ValidationMode, Validator};
+    /// let schema: Schema = "entity User = { name: String }; action view appliesTo { principal: User, resource: User };".parse().unwrap();
+    /// let pset: PolicySet = r#"
+    ///     permit(principal, action, resource) unless { principal.name like "bot-*" };
+    /// "#.parse().unwrap();
+    /// let validator = Validator::new(schema)
+    ///     .with_feature_policy(FeaturePolicy::new().forbid_unless().forbid_like());
+    /// let result = validator.validate(&pset, ValidationMode::Strict);
+    /// assert_eq!(result.validation_errors().count(), 2);
+    /// assert!(result
+    ///     .validation_errors()
+    ///     .all(|e| matches!(e, ValidationError::ForbiddenFeature(_))));
+    /// ```
+    #[must_use]
+    pub fn with_feature_policy(self, feature_policy: FeaturePolicy) -> Self {
+        Self(self.0.with_feature_policy(feature_policy.0))
+    }
+
     /// Validate all policies in a policy set, collecting all validation errors
     /// found into the returned `ValidationResult`. Each error is returned together with the
     /// policy id of the policy where the error was found. If a policy id
@@ -1244,6 +1764,245 @@ impl Validator {
     pub fn validate(&self, pset: &PolicySet, mode: ValidationMode) -> ValidationResult {
         ValidationResult::from(self.0.validate(&pset.ast, mode.into()))
     }
+
+    /// Validate each layer of `layers`, and find the permit policies of each
+    /// layer which are always overridden by a forbid policy of a lower layer,
+    /// e.g., a tenant permit policy which a global forbid policy without
+    /// conditions overrides. See [`PolicyLayers`].
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicyLayers, PolicySet, Schema, ValidationMode, Validator};
+    /// # use std::sync::Arc;
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Doc;
+    ///     action view, delete appliesTo { principal: User, resource: Doc };
+    /// "#.parse().unwrap();
+    /// let global: PolicySet = r#"forbid(principal, action == Action::"delete", resource);"#.parse().unwrap();
+    /// let tenant: PolicySet = r#"
+    ///     permit(principal == User::"alice", action == Action::"delete", resource);
+    ///     permit(principal == User::"alice", action, resource);
+    /// "#.parse().unwrap();
+    /// let layers = PolicyLayers::new()
+    ///     .with_layer("global", Arc::new(global))
+    ///     .with_layer("tenant", Arc::new(tenant));
+    /// let result = Validator::new(schema).validate_layers(&layers, ValidationMode::Strict);
+    /// assert!(result.validation_passed());
+    /// let overridden = result.overridden_permits().collect::<Vec<_>>();
+    /// assert_eq!(overridden.len(), 1);
+    /// assert_eq!(overridden[0].policy_id().to_string(), "policy0");
+    /// ```
+    pub fn validate_layers(
+        &self,
+        layers: &PolicyLayers,
+        mode: ValidationMode,
+    ) -> LayeredValidationResult {
+        LayeredValidationResult::new(self, layers, mode)
+    }
+
+    /// Like [`Validator::validate`], but stop validating once `max_errors`
+    /// errors have been found, and report at most that many errors. This
+    /// bounds the work done when validating large policy sets with many
+    /// invalid policies. Use [`ValidationResult::is_truncated`] to check
+    /// whether validation stopped early.
+    pub fn validate_with_max_errors(
+        &self,
+        pset: &PolicySet,
+        mode: ValidationMode,
+        max_errors: usize,
+    ) -> ValidationResult {
+        ValidationResult::from(
+            self.0
+                .validate_with_max_errors(&pset.ast, mode.into(), max_errors),
+        )
+    }
+
+    /// Like [`Validator::validate`], but also report the time spent
+    /// validating each policy and template, broken down by request
+    /// environment, and the number of least upper bounds computed. This
+    /// helps find the policies or schema types which make validation slow.
+    /// Profiling is opt-in since timing each request environment adds some
+    /// overhead.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: [User, Photo] };
+    /// "#.parse().unwrap();
+    /// let pset: PolicySet = "permit(principal, action, resource);".parse().unwrap();
+    /// let (result, profile) = Validator::new(schema).validate_with_profile(&pset, ValidationMode::Strict);
+    /// assert!(result.validation_passed());
+    /// let policy = profile.policies().next().unwrap();
+    /// assert_eq!(policy.id().to_string(), "policy0");
+    /// assert_eq!(policy.request_envs().count(), 2);
+    /// ```
+    pub fn validate_with_profile(
+        &self,
+        pset: &PolicySet,
+        mode: ValidationMode,
+    ) -> (ValidationResult, ValidationProfile) {
+        let (result, profile) = self.0.validate_with_profile(&pset.ast, mode.into());
+        (ValidationResult::from(result), ValidationProfile(profile))
+    }
+
+    /// Report the entity types, entity attributes, and actions which are
+    /// declared in the schema but never referenced by any policy in `pset`,
+    /// to help keep schemas from accumulating dead declarations. This check
+    /// is not part of [`Validator::validate`], because it is only meaningful
+    /// for the complete set of policies using a schema.
+    ///
+    /// The warnings point at the relevant declaration when the schema was
+    /// parsed from the Cedar schema syntax.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationWarning, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(r#"
+    ///     entity User = { name: String };
+    ///     entity Photo;
+    ///     entity Album;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    ///     action delete appliesTo { principal: User, resource: Album };
+    /// "#).unwrap();
+    /// let pset = PolicySet::from_str(r#"permit(principal, action == Action::"view", resource);"#).unwrap();
+    /// let warnings: Vec<_> = Validator::new(schema).unused_schema_elements(&pset).collect();
+    /// assert!(matches!(warnings.as_slice(), [
+    ///     ValidationWarning::UnusedEntityType(_),
+    ///     ValidationWarning::UnusedAttribute(_),
+    ///     ValidationWarning::UnusedAction(_),
+    /// ]));
+    /// ```
+    pub fn unused_schema_elements(
+        &self,
+        pset: &PolicySet,
+    ) -> impl Iterator<Item = ValidationWarning> {
+        self.0
+            .unused_schema_elements(&pset.ast)
+            .map(ValidationWarning::from)
+    }
+
+    /// Cross-check the entity snapshot `entities` against the policies in
+    /// `pset`, reporting the expressions which error when evaluated with an
+    /// entity of the snapshot, e.g., because an attribute value fails to parse
+    /// as an extension value or makes arithmetic overflow. This catches data
+    /// issues before the policies and entities are deployed. The extension
+    /// function calls and arithmetic operations whose only variable is
+    /// `principal` or `resource` are checked, for each entity of a type the
+    /// policy applies to, independently of the rest of the policy.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, PolicySet, Schema, ValidationWarning, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(r#"
+    ///     entity User = { balance: String };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#).unwrap();
+    /// let pset = PolicySet::from_str(r#"
+    ///     permit(principal, action, resource) when {
+    ///         decimal(principal.balance).greaterThan(decimal("0.0"))
+    ///     };
+    /// "#).unwrap();
+    /// let entities = Entities::from_json_str(r#"[
+    ///     { "uid": { "type": "User", "id": "alice" }, "attrs": { "balance": "1.5" }, "parents": [] },
+    ///     { "uid": { "type": "User", "id": "bob" }, "attrs": { "balance": "lots" }, "parents": [] }
+    /// ]"#, Some(&schema)).unwrap();
+    /// let warnings: Vec<_> = Validator::new(schema).entity_data_errors(&pset, &entities).collect();
+    /// assert!(matches!(warnings.as_slice(), [ValidationWarning::EntityDataError(_)]));
+    /// ```
+    pub fn entity_data_errors(
+        &self,
+        pset: &PolicySet,
+        entities: &Entities,
+    ) -> impl Iterator<Item = ValidationWarning> {
+        self.0
+            .entity_data_errors(&pset.ast, &entities.0)
+            .map(ValidationWarning::from)
+    }
+
+    /// The entity types each slot of `template` may be linked to such that a
+    /// link of `template` applies to some request valid for the schema. A slot
+    /// has no entity types if no link of `template` ever applies, and `None`
+    /// if they are unknown, which may happen in partial validation.
+    ///
+    /// ```
+    /// # use cedar_policy::{EntityTypeName, Schema, SlotId, Template, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(r#"
+    ///     entity Group;
+    ///     entity User in [Group];
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#).unwrap();
+    /// let template = Template::parse(None, "permit(principal in ?principal, action, resource);").unwrap();
+    /// let types = Validator::new(schema).expected_slot_types(&template, ValidationMode::Strict);
+    /// let group = EntityTypeName::from_str("Group").unwrap();
+    /// let user = EntityTypeName::from_str("User").unwrap();
+    /// assert_eq!(types[&SlotId::principal()], Some(vec![group, user]));
+    /// ```
+    pub fn expected_slot_types(
+        &self,
+        template: &Template,
+        mode: ValidationMode,
+    ) -> HashMap<SlotId, Option<Vec<EntityTypeName>>> {
+        self.0
+            .expected_slot_types(&template.ast, mode.into())
+            .into_iter()
+            .map(|(slot, types)| {
+                (
+                    slot.into(),
+                    types.map(|types| types.into_iter().map(EntityTypeName).collect()),
+                )
+            })
+            .collect()
+    }
+
+    /// Check the values `vals` which a link of `template` would bind its slots
+    /// to, before linking it with [`PolicySet::link`]. Unlike the
+    /// [`PolicySetError`] returned by linking, the errors describe each
+    /// problem with a slot: [`ValidationError::MissingSlotBinding`] for a
+    /// slot without a value, [`ValidationError::UnusedSlotBinding`] for a
+    /// value of a slot the template doesn't use, and
+    /// [`ValidationError::UnexpectedSlotType`] for a value of an entity type
+    /// for which the template never applies. They list the entity types
+    /// expected for the slot, as returned by
+    /// [`Validator::expected_slot_types()`].
+    ///
+    /// ```
+    /// # use cedar_policy::{EntityUid, Schema, SlotId, Template, ValidationError, ValidationMode, Validator};
+    /// # use std::collections::HashMap;
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#).unwrap();
+    /// let template = Template::parse(None, "permit(principal == ?principal, action, resource);").unwrap();
+    /// let vals = HashMap::from([(SlotId::resource(), EntityUid::from_str(r#"Photo::"p""#).unwrap())]);
+    /// let errors: Vec<_> = Validator::new(schema)
+    ///     .validate_link_bindings(&template, &vals, ValidationMode::Strict)
+    ///     .collect();
+    /// assert!(matches!(errors.as_slice(), [
+    ///     ValidationError::MissingSlotBinding(_),
+    ///     ValidationError::UnusedSlotBinding(_),
+    /// ]));
+    /// ```
+    pub fn validate_link_bindings(
+        &self,
+        template: &Template,
+        vals: &HashMap<SlotId, EntityUid>,
+        mode: ValidationMode,
+    ) -> impl Iterator<Item = ValidationError> {
+        let vals: ast::SlotEnv = vals
+            .iter()
+            .map(|(slot, uid)| (slot.clone().into(), uid.clone().into()))
+            .collect();
+        self.0
+            .validate_link_bindings(&template.ast, &vals, mode.into())
+            .map(ValidationError::from)
+    }
 }
 
 /// Contains all the type information used to construct a `Schema` that can be
@@ -1392,6 +2151,9 @@ impl FromStr for SchemaFragment {
 }
 
 /// Object containing schema information used by the validator.
+///
+/// `Schema` is `Send + Sync`, and cheap to clone: a schema can't be modified,
+/// so clones share its definitions.
 #[repr(transparent)]
 #[derive(Debug, Clone, RefCast)]
 pub struct Schema(pub(crate) cedar_policy_validator::ValidatorSchema);
@@ -1570,6 +2332,37 @@ impl Schema {
             .map(|iter| iter.map(RefCast::ref_cast))
     }
 
+    /// Returns an iterator over the request environments of this schema,
+    /// i.e., every combination of an action with a principal type and a
+    /// resource type it applies to. These are the environments in which the
+    /// validator typechecks policies, which is useful, e.g., to generate
+    /// requests for tests. The environments are in no particular order.
+    ///
+    /// ```
+    /// # use cedar_policy::Schema;
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     entity Album;
+    ///     action view appliesTo { principal: User, resource: [Photo, Album] };
+    /// "#.parse().unwrap();
+    /// let mut envs = schema
+    ///     .request_envs()
+    ///     .map(|env| format!("{} {} {}", env.principal(), env.action(), env.resource()))
+    ///     .collect::<Vec<_>>();
+    /// envs.sort();
+    /// assert_eq!(envs, [r#"User Action::"view" Album"#, r#"User Action::"view" Photo"#]);
+    /// ```
+    pub fn request_envs(&self) -> impl Iterator<Item = RequestEnv> + '_ {
+        self.0.request_envs().filter_map(|env| {
+            Some(RequestEnv {
+                principal: env.principal_entity_type()?.clone().into(),
+                action: env.action_entity_uid()?.clone().into(),
+                resource: env.resource_entity_type()?.clone().into(),
+            })
+        })
+    }
+
     /// Returns an iterator over all the entity types that can be an ancestor of `ty`
     ///
     /// ## Errors
@@ -1610,6 +2403,8 @@ impl Schema {
 pub struct ValidationResult {
     validation_errors: Vec<ValidationError>,
     validation_warnings: Vec<ValidationWarning>,
+    truncated: bool,
+    policies_by_action: BTreeMap<EntityUid, Vec<PolicyId>>,
 }
 
 impl ValidationResult {
@@ -1636,6 +2431,106 @@ impl ValidationResult {
         self.validation_warnings.iter()
     }
 
+    /// True when validation stopped early because it found the maximum number
+    /// of errors passed to [`Validator::validate_with_max_errors`], so there
+    /// may be more errors than the ones reported.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Get the errors found by the validator, grouped by the id of the policy
+    /// or template containing them. Within each group, errors are in the
+    /// order they were found.
+    pub fn errors_by_policy(&self) -> HashMap<&PolicyId, Vec<&ValidationError>> {
+        let mut by_policy: HashMap<&PolicyId, Vec<&ValidationError>> = HashMap::new();
+        for error in &self.validation_errors {
+            by_policy.entry(error.policy_id()).or_default().push(error);
+        }
+        by_policy
+    }
+
+    /// Summarize the errors and warnings found by the validator as counts
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str("entity User; action view appliesTo { principal: User, resource: User };").unwrap();
+    /// let pset = PolicySet::from_str(r#"
+    ///     permit(principal == Usr::"alice", action, resource);
+    ///     permit(principal, action == Action::"edit", resource);
+    /// "#).unwrap();
+    /// let summary = Validator::new(schema).validate(&pset, ValidationMode::Strict).summary();
+    /// assert_eq!(summary.errors_of_kind("UnrecognizedEntityType"), 1);
+    /// assert_eq!(summary.errors_of_kind("UnrecognizedActionId"), 1);
+    /// assert_eq!(summary.policies_with_errors(), 2);
+    /// ```
+    pub fn summary(&self) -> ValidationSummary {
+        let mut errors_by_kind = BTreeMap::new();
+        let mut errors_by_policy = HashMap::new();
+        for error in &self.validation_errors {
+            *errors_by_kind.entry(error.kind()).or_insert(0) += 1;
+            *errors_by_policy
+                .entry(error.policy_id().clone())
+                .or_insert(0) += 1;
+        }
+        let mut warnings_by_kind = BTreeMap::new();
+        for warning in &self.validation_warnings {
+            *warnings_by_kind.entry(warning.kind()).or_insert(0) += 1;
+        }
+        ValidationSummary {
+            error_count: self.validation_errors.len(),
+            warning_count: self.validation_warnings.len(),
+            errors_by_kind,
+            warnings_by_kind,
+            errors_by_policy,
+            truncated: self.truncated,
+        }
+    }
+
+    /// Report, for each action of the schema in order, the static policies
+    /// and templates which may apply to it, i.e., whose scope is satisfied by
+    /// some request for the action which is valid for the schema, and whether
+    /// they passed validation. Actions without any policy are included, which
+    /// helps find gaps in the coverage of a schema by a policy set.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     action view, edit, delete appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let pset: PolicySet = r#"
+    ///     permit(principal, action == Action::"view", resource);
+    ///     permit(principal, action in [Action::"view", Action::"edit"], resource) when { resource.owner == principal };
+    /// "#.parse().unwrap();
+    /// let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+    /// let report = result
+    ///     .by_action()
+    ///     .iter()
+    ///     .map(|a| (a.action().to_string(), a.policies().map(|(id, ok)| (id.to_string(), ok)).collect::<Vec<_>>()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(report, [
+    ///     (r#"Action::"delete""#.to_string(), vec![]),
+    ///     (r#"Action::"edit""#.to_string(), vec![("policy1".to_string(), false)]),
+    ///     (r#"Action::"view""#.to_string(), vec![("policy0".to_string(), true), ("policy1".to_string(), false)]),
+    /// ]);
+    /// ```
+    pub fn by_action(&self) -> Vec<ActionValidationReport<'_>> {
+        let invalid: HashSet<&PolicyId> = self
+            .validation_errors
+            .iter()
+            .map(ValidationError::policy_id)
+            .collect();
+        self.policies_by_action
+            .iter()
+            .map(|(action, ids)| ActionValidationReport {
+                action,
+                policies: ids.iter().map(|id| (id, !invalid.contains(id))).collect(),
+            })
+            .collect()
+    }
+
     fn first_error_or_warning(&self) -> Option<&dyn Diagnostic> {
         self.validation_errors
             .first()
@@ -1658,16 +2553,126 @@ impl ValidationResult {
             self.validation_warnings.into_iter(),
         )
     }
-}
+}
+
+#[doc(hidden)]
+impl From<cedar_policy_validator::ValidationResult> for ValidationResult {
+    fn from(r: cedar_policy_validator::ValidationResult) -> Self {
+        let truncated = r.is_truncated();
+        let policies_by_action = r
+            .policies_by_action()
+            .iter()
+            .map(|(action, ids)| {
+                (
+                    EntityUid::from(action.clone()),
+                    ids.iter()
+                        .map(|id| PolicyId::ref_cast(id).clone())
+                        .collect(),
+                )
+            })
+            .collect();
+        let (errors, warnings) = r.into_errors_and_warnings();
+        Self {
+            validation_errors: errors.map(ValidationError::from).collect(),
+            validation_warnings: warnings.map(ValidationWarning::from).collect(),
+            truncated,
+            policies_by_action,
+        }
+    }
+}
+
+/// The static policies and templates which may apply to an action of the
+/// schema and whether they passed validation, reported by
+/// [`ValidationResult::by_action`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionValidationReport<'a> {
+    action: &'a EntityUid,
+    policies: Vec<(&'a PolicyId, bool)>,
+}
+
+impl<'a> ActionValidationReport<'a> {
+    /// The action
+    pub fn action(&self) -> &'a EntityUid {
+        self.action
+    }
+
+    /// The ids of the static policies and templates which may apply to the
+    /// action, in order of id, each with whether it passed
+    /// validation
+    pub fn policies(&self) -> impl Iterator<Item = (&'a PolicyId, bool)> + '_ {
+        self.policies.iter().copied()
+    }
+
+    /// True when no policy or template may apply to the action
+    pub fn is_uncovered(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    /// True when all the policies and templates which may apply to the action
+    /// passed validation
+    pub fn validation_passed(&self) -> bool {
+        self.policies.iter().all(|(_, valid)| *valid)
+    }
+}
+
+/// Counts of the errors and warnings in a [`ValidationResult`], by kind and by
+/// policy, for reporting on the validation of large policy sets. Kinds are the
+/// names returned by [`ValidationError::kind`] and [`ValidationWarning::kind`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationSummary {
+    error_count: usize,
+    warning_count: usize,
+    errors_by_kind: BTreeMap<&'static str, usize>,
+    warnings_by_kind: BTreeMap<&'static str, usize>,
+    errors_by_policy: HashMap<PolicyId, usize>,
+    truncated: bool,
+}
+
+impl ValidationSummary {
+    /// The total number of errors
+    pub fn error_count(&self) -> usize {
+        self.error_count
+    }
+
+    /// The total number of warnings
+    pub fn warning_count(&self) -> usize {
+        self.warning_count
+    }
+
+    /// The number of errors of the given kind
+    pub fn errors_of_kind(&self, kind: &str) -> usize {
+        self.errors_by_kind.get(kind).copied().unwrap_or(0)
+    }
+
+    /// The number of errors of each kind that occurs, in order of kind
+    pub fn errors_by_kind(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.errors_by_kind.iter().map(|(k, n)| (*k, *n))
+    }
+
+    /// The number of warnings of each kind that occurs, in order of kind
+    pub fn warnings_by_kind(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.warnings_by_kind.iter().map(|(k, n)| (*k, *n))
+    }
+
+    /// The number of errors in the policy or template with the given id
+    pub fn errors_in_policy(&self, id: &PolicyId) -> usize {
+        self.errors_by_policy.get(id).copied().unwrap_or(0)
+    }
 
-#[doc(hidden)]
-impl From<cedar_policy_validator::ValidationResult> for ValidationResult {
-    fn from(r: cedar_policy_validator::ValidationResult) -> Self {
-        let (errors, warnings) = r.into_errors_and_warnings();
-        Self {
-            validation_errors: errors.map(ValidationError::from).collect(),
-            validation_warnings: warnings.map(ValidationWarning::from).collect(),
-        }
+    /// The number of errors in each policy or template which has errors
+    pub fn errors_by_policy(&self) -> impl Iterator<Item = (&PolicyId, usize)> {
+        self.errors_by_policy.iter().map(|(id, n)| (id, *n))
+    }
+
+    /// The number of policies and templates with errors
+    pub fn policies_with_errors(&self) -> usize {
+        self.errors_by_policy.len()
+    }
+
+    /// Whether validation stopped early, so that the counts may be incomplete.
+    /// See [`ValidationResult::is_truncated`].
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
     }
 }
 
@@ -1795,15 +2800,25 @@ impl std::fmt::Display for EntityNamespace {
 }
 
 /// Represents a set of `Policy`s
+///
+/// Cloning a `PolicySet` is cheap: clones share their policies and
+/// templates, and the first modification of a clone copies only pointers to
+/// them. So, e.g., a per-request copy of a large policy set with an extra
+/// policy added does not copy the other policies.
+///
+/// `PolicySet` is `Send + Sync`, so it can be shared between threads without
+/// a lock.
 #[derive(Debug, Clone, Default)]
 pub struct PolicySet {
     /// AST representation. Technically partially redundant with the other fields.
     /// Internally, we ensure that the duplicated information remains consistent.
     pub(crate) ast: ast::PolicySet,
     /// Policies in the set (this includes both static policies and template linked-policies)
-    policies: HashMap<PolicyId, Policy>,
+    policies: Arc<HashMap<PolicyId, Arc<Policy>>>,
     /// Templates in the set
-    templates: HashMap<PolicyId, Template>,
+    templates: Arc<HashMap<PolicyId, Arc<Template>>>,
+    /// Notified of each change to the set
+    observers: Observers,
 }
 
 impl PartialEq for PolicySet {
@@ -1824,13 +2839,27 @@ impl FromStr for PolicySet {
     ///
     /// See [`Policy`] for more.
     fn from_str(policies: &str) -> Result<Self, Self::Err> {
-        let (texts, pset) = parser::parse_policyset_and_also_return_policy_text(policies)?;
+        Self::from_str_with_constants(policies, &Constants::default())
+    }
+}
+
+impl PolicySet {
+    /// Like [`PolicySet::from_str`], where the conditions of the policies may
+    /// reference `constants` by name. References are expanded when parsing,
+    /// so the text of a policy which references constants is that of the
+    /// expanded policy.
+    pub fn from_str_with_constants(
+        policies: &str,
+        constants: &Constants,
+    ) -> Result<Self, ParseErrors> {
+        let (texts, pset) =
+            parser::parse_policyset_and_also_return_policy_text(policies, &constants.0)?;
         // PANIC SAFETY: By the invariant on `parse_policyset_and_also_return_policy_text(policies)`, every `PolicyId` in `pset.policies()` occurs as a key in `text`.
         #[allow(clippy::expect_used)]
         let policies = pset.policies().map(|p|
             (
                 PolicyId::new(p.id().clone()),
-                Policy { lossless: LosslessPolicy::policy_or_template_text(*texts.get(p.id()).expect("internal invariant violation: policy id exists in asts but not texts")), ast: p.clone() }
+                Arc::new(Policy { lossless: LosslessPolicy::policy_or_template_text(texts.get(p.id()).expect("internal invariant violation: policy id exists in asts but not texts").to_string()), ast: p.clone() })
             )
         ).collect();
         // PANIC SAFETY: By the same invariant, every `PolicyId` in `pset.templates()` also occurs as a key in `text`.
@@ -1838,18 +2867,17 @@ impl FromStr for PolicySet {
         let templates = pset.templates().map(|t|
             (
                 PolicyId::new(t.id().clone()),
-                Template { lossless: LosslessPolicy::policy_or_template_text(*texts.get(t.id()).expect("internal invariant violation: template id exists in asts but not ests")), ast: t.clone() }
+                Arc::new(Template { lossless: LosslessPolicy::policy_or_template_text(texts.get(t.id()).expect("internal invariant violation: template id exists in asts but not ests").to_string()), ast: t.clone() })
             )
         ).collect();
         Ok(Self {
             ast: pset,
-            policies,
-            templates,
+            policies: Arc::new(policies),
+            templates: Arc::new(templates),
+            observers: Observers::default(),
         })
     }
-}
 
-impl PolicySet {
     /// Build the policy set AST from the EST
     fn from_est(est: &est::PolicySet) -> Result<Self, PolicySetError> {
         let ast: ast::PolicySet = est.clone().try_into()?;
@@ -1860,12 +2888,12 @@ impl PolicySet {
             .map(|p| {
                 (
                     PolicyId::new(p.id().clone()),
-                    Policy {
+                    Arc::new(Policy {
                         lossless: LosslessPolicy::Est(est.get_policy(p.id()).expect(
                             "internal invariant violation: policy id exists in asts but not ests",
                         )),
                         ast: p.clone(),
-                    },
+                    }),
                 )
             })
             .collect();
@@ -1876,19 +2904,20 @@ impl PolicySet {
             .map(|t| {
                 (
                     PolicyId::new(t.id().clone()),
-                    Template {
+                    Arc::new(Template {
                         lossless: LosslessPolicy::Est(est.get_template(t.id()).expect(
                             "internal invariant violation: template id exists in asts but not ests",
                         )),
                         ast: t.clone(),
-                    },
+                    }),
                 )
             })
             .collect();
         Ok(Self {
             ast,
-            policies,
-            templates,
+            policies: Arc::new(policies),
+            templates: Arc::new(templates),
+            observers: Observers::default(),
         })
     }
 
@@ -1921,15 +2950,91 @@ impl PolicySet {
         Ok(value)
     }
 
+    /// Serialize the [`PolicySet`] in the stable AST format, intended for
+    /// long-term storage of parsed policies.
+    ///
+    /// Unlike [`PolicySet::to_json`], which produces the EST, this format
+    /// mirrors the parsed AST, so loading it with
+    /// [`PolicySet::from_stable_ast`] does not parse policies again. The
+    /// format is versioned by [`STABLE_AST_VERSION`]: later versions of this
+    /// crate can load it, while loading a format written by a later version
+    /// fails with an error. Source locations and the original policy text are
+    /// not stored.
+    pub fn to_stable_ast(&self) -> Result<serde_json::Value, PolicySetError> {
+        let stored = cedar_policy_core::stable_ast::PolicySet::from(&self.ast);
+        let value = serde_json::to_value(stored)
+            .map_err(|e| policy_set_errors::JsonPolicySetError { inner: e })?;
+        Ok(value)
+    }
+
+    /// Load a [`PolicySet`] serialized with [`PolicySet::to_stable_ast`] by
+    /// this or an earlier version of this crate.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicyId, PolicySet};
+    /// # use std::str::FromStr;
+    /// let pset = PolicySet::from_str(r#"permit(principal, action, resource) when { context.mfa };"#).unwrap();
+    /// let stored = pset.to_stable_ast().unwrap();
+    /// let loaded = PolicySet::from_stable_ast(stored).unwrap();
+    /// assert!(loaded.policy(&PolicyId::new("policy0")).is_some());
+    /// ```
+    pub fn from_stable_ast(value: serde_json::Value) -> Result<Self, PolicySetError> {
+        let ast = cedar_policy_core::stable_ast::from_json_value(value)?;
+        Ok(Self::from_ast(ast))
+    }
+
+    /// Upgrade a [`PolicySet`] serialized with [`PolicySet::to_stable_ast`] by
+    /// this or an earlier version of this crate to the current
+    /// [`STABLE_AST_VERSION`], e.g., to rewrite stored policy sets after
+    /// upgrading this crate. [`PolicySet::from_stable_ast`] does this
+    /// implicitly.
+    pub fn migrate_stable_ast(
+        value: serde_json::Value,
+    ) -> Result<serde_json::Value, StableAstError> {
+        cedar_policy_core::stable_ast::migrate(value)
+    }
+
+    /// Build the [`PolicySet`] from the AST, using the pretty-printed AST as
+    /// the text of each policy and template
+    fn from_ast(ast: ast::PolicySet) -> Self {
+        let policies = ast
+            .policies()
+            .map(|p| {
+                (
+                    PolicyId::new(p.id().clone()),
+                    Arc::new(Policy::from_ast(p.clone())),
+                )
+            })
+            .collect();
+        let templates = ast
+            .templates()
+            .map(|t| {
+                (
+                    PolicyId::new(t.id().clone()),
+                    Arc::new(Template {
+                        lossless: LosslessPolicy::policy_or_template_text(t.to_string()),
+                        ast: t.clone(),
+                    }),
+                )
+            })
+            .collect();
+        Self {
+            ast,
+            policies: Arc::new(policies),
+            templates: Arc::new(templates),
+            observers: Observers::default(),
+        }
+    }
+
     /// Get the EST representation of the [`PolicySet`]
     fn est(self) -> Result<est::PolicySet, PolicyToJsonError> {
         let (static_policies, template_links): (Vec<_>, Vec<_>) =
-            fold_partition(self.policies, is_static_or_link)?;
+            fold_partition(Arc::unwrap_or_clone(self.policies), is_static_or_link)?;
         let static_policies = static_policies.into_iter().collect::<HashMap<_, _>>();
         let templates = self
             .templates
-            .into_iter()
-            .map(|(id, template)| template.lossless.est().map(|est| (id.into(), est)))
+            .iter()
+            .map(|(id, template)| template.lossless.est().map(|est| (id.clone().into(), est)))
             .collect::<Result<HashMap<_, _>, _>>()?;
         let est = est::PolicySet {
             templates,
@@ -1944,11 +3049,18 @@ impl PolicySet {
     pub fn new() -> Self {
         Self {
             ast: ast::PolicySet::new(),
-            policies: HashMap::new(),
-            templates: HashMap::new(),
+            policies: Arc::default(),
+            templates: Arc::default(),
+            observers: Observers::default(),
         }
     }
 
+    /// Register `observer` to be notified of each change made to this
+    /// `PolicySet` from now on. Clones of the set don't notify it.
+    pub fn add_observer(&mut self, observer: Arc<dyn PolicySetObserver>) {
+        self.observers.push(observer);
+    }
+
     /// Create a `PolicySet` from the given policies
     pub fn from_policies(
         policies: impl IntoIterator<Item = Policy>,
@@ -1967,7 +3079,8 @@ impl PolicySet {
         if policy.is_static() {
             let id = PolicyId::new(policy.ast.id().clone());
             self.ast.add(policy.ast.clone())?;
-            self.policies.insert(id, policy);
+            self.observers.notify(|o| o.on_add(&policy));
+            Arc::make_mut(&mut self.policies).insert(id, Arc::new(policy));
             Ok(())
         } else {
             Err(PolicySetError::ExpectedStatic(
@@ -1980,7 +3093,7 @@ impl PolicySet {
     ///
     /// This will error if the policy is not a static policy.
     pub fn remove_static(&mut self, policy_id: PolicyId) -> Result<Policy, PolicySetError> {
-        let Some(policy) = self.policies.remove(&policy_id) else {
+        let Some(policy) = Arc::make_mut(&mut self.policies).remove(&policy_id) else {
             return Err(PolicySetError::PolicyNonexistent(
                 policy_set_errors::PolicyNonexistentError { policy_id },
             ));
@@ -1990,10 +3103,11 @@ impl PolicySet {
             .remove_static(&ast::PolicyID::from_string(&policy_id))
             .is_ok()
         {
-            Ok(policy)
+            self.observers.notify(|o| o.on_remove(&policy));
+            Ok(Arc::unwrap_or_clone(policy))
         } else {
             //Restore self.policies
-            self.policies.insert(policy_id.clone(), policy);
+            Arc::make_mut(&mut self.policies).insert(policy_id.clone(), policy);
             Err(PolicySetError::PolicyNonexistent(
                 policy_set_errors::PolicyNonexistentError { policy_id },
             ))
@@ -2004,7 +3118,8 @@ impl PolicySet {
     pub fn add_template(&mut self, template: Template) -> Result<(), PolicySetError> {
         let id = PolicyId::new(template.ast.id().clone());
         self.ast.add_template(template.ast.clone())?;
-        self.templates.insert(id, template);
+        self.observers.notify(|o| o.on_add_template(&template));
+        Arc::make_mut(&mut self.templates).insert(id, Arc::new(template));
         Ok(())
     }
 
@@ -2013,7 +3128,7 @@ impl PolicySet {
     /// This will error if any policy is linked to the template.
     /// This will error if `policy_id` is not a template.
     pub fn remove_template(&mut self, template_id: PolicyId) -> Result<Template, PolicySetError> {
-        let Some(template) = self.templates.remove(&template_id) else {
+        let Some(template) = Arc::make_mut(&mut self.templates).remove(&template_id) else {
             return Err(PolicySetError::TemplateNonexistent(
                 policy_set_errors::TemplateNonexistentError { template_id },
             ));
@@ -2025,15 +3140,18 @@ impl PolicySet {
             .ast
             .remove_template(&ast::PolicyID::from_string(&template_id))
         {
-            Ok(_) => Ok(template),
+            Ok(_) => {
+                self.observers.notify(|o| o.on_remove_template(&template));
+                Ok(Arc::unwrap_or_clone(template))
+            }
             Err(ast::PolicySetTemplateRemovalError::RemoveTemplateWithLinksError(_)) => {
-                self.templates.insert(template_id.clone(), template);
+                Arc::make_mut(&mut self.templates).insert(template_id.clone(), template);
                 Err(PolicySetError::RemoveTemplateWithActiveLinks(
                     policy_set_errors::RemoveTemplateWithActiveLinksError { template_id },
                 ))
             }
             Err(ast::PolicySetTemplateRemovalError::NotTemplateError(_)) => {
-                self.templates.insert(template_id.clone(), template);
+                Arc::make_mut(&mut self.templates).insert(template_id.clone(), template);
                 Err(PolicySetError::RemoveTemplateNotTemplate(
                     policy_set_errors::RemoveTemplateNotTemplateError { template_id },
                 ))
@@ -2066,22 +3184,22 @@ impl PolicySet {
     ///
     /// This will include both static and template-linked policies.
     pub fn policies(&self) -> impl Iterator<Item = &Policy> {
-        self.policies.values()
+        self.policies.values().map(Arc::as_ref)
     }
 
     /// Iterate over the `Template`'s in the `PolicySet`.
     pub fn templates(&self) -> impl Iterator<Item = &Template> {
-        self.templates.values()
+        self.templates.values().map(Arc::as_ref)
     }
 
     /// Get a `Template` by its `PolicyId`
     pub fn template(&self, id: &PolicyId) -> Option<&Template> {
-        self.templates.get(id)
+        self.templates.get(id).map(Arc::as_ref)
     }
 
     /// Get a `Policy` by its `PolicyId`
     pub fn policy(&self, id: &PolicyId) -> Option<&Policy> {
-        self.policies.get(id)
+        self.policies.get(id).map(Arc::as_ref)
     }
 
     /// Extract annotation data from a `Policy` by its `PolicyId` and annotation key
@@ -2109,6 +3227,43 @@ impl PolicySet {
         self.ast.is_empty()
     }
 
+    /// Approximate number of bytes of heap memory used by this `PolicySet`,
+    /// e.g., for capacity planning.
+    ///
+    /// This is an estimate: it does not include allocator overhead, and
+    /// counts data shared between policies (other than templates and policy
+    /// text) once per policy.
+    #[cfg(feature = "heap-size")]
+    pub fn approximate_heap_size(&self) -> usize {
+        use cedar_policy_core::heap_size::HeapSize;
+        use std::mem::size_of;
+        let policies = self
+            .policies
+            .iter()
+            .map(|(id, policy)| {
+                size_of::<(PolicyId, Arc<Policy>)>()
+                    + 1
+                    + 2 * size_of::<usize>()
+                    + size_of::<Policy>()
+                    + AsRef::<ast::PolicyID>::as_ref(id).heap_size()
+                    + policy.lossless.approximate_heap_size(policy.ast.template())
+            })
+            .sum::<usize>();
+        let templates = self
+            .templates
+            .iter()
+            .map(|(id, template)| {
+                size_of::<(PolicyId, Arc<Template>)>()
+                    + 1
+                    + 2 * size_of::<usize>()
+                    + size_of::<Template>()
+                    + AsRef::<ast::PolicyID>::as_ref(id).heap_size()
+                    + template.lossless.approximate_heap_size(&template.ast)
+            })
+            .sum::<usize>();
+        self.ast.heap_size() + policies + templates
+    }
+
     /// Returns the number of `Policy`s in the `PolicySet`.
     ///
     /// This will include both static and template-linked policies.
@@ -2121,6 +3276,156 @@ impl PolicySet {
         self.templates.len()
     }
 
+    /// Find the groups of duplicate policies in this policy set, including
+    /// template-linked policies, according to `equivalence`. Each group
+    /// contains the ids of at least two policies, sorted, and the groups are
+    /// sorted by their first id. The policies of a group may be replaced by
+    /// any one of them without changing any authorization decision (for
+    /// requests conforming to `schema`, when comparing with
+    /// [`PolicyEquivalence::Semantic`]), although the reasons for a decision
+    /// may change.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicyEquivalence, PolicyId, PolicySet, Schema};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo { owner: User };
+    ///     action readOnly;
+    ///     action view, list in [readOnly]
+    ///         appliesTo { principal: User, resource: Photo, context: { mfa: Bool } };
+    /// "#.parse().unwrap();
+    /// let policies: PolicySet = r#"
+    ///     @id("a") permit(principal, action in [Action::"view", Action::"list"], resource)
+    ///         when { principal == resource.owner && !!context.mfa };
+    ///     @id("b") permit(principal, action in [Action::"list", Action::"view"], resource)
+    ///         when { resource.owner == principal && !!context.mfa };
+    ///     @id("c") permit(principal, action in Action::"readOnly", resource)
+    ///         when { resource.owner == principal && context.mfa };
+    /// "#.parse().unwrap();
+    /// let group = |ids: &[&str]| ids.iter().map(PolicyId::new).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     policies.find_duplicates(&schema, PolicyEquivalence::Canonical),
+    ///     vec![group(&["policy0", "policy1"])]
+    /// );
+    /// assert_eq!(
+    ///     policies.find_duplicates(&schema, PolicyEquivalence::Semantic),
+    ///     vec![group(&["policy0", "policy1", "policy2"])]
+    /// );
+    /// ```
+    pub fn find_duplicates(
+        &self,
+        schema: &Schema,
+        equivalence: PolicyEquivalence,
+    ) -> Vec<Vec<PolicyId>> {
+        duplicates::duplicate_groups(self.policies(), schema, equivalence)
+    }
+
+    /// Find the permit policies of this policy set which never allow a
+    /// request, since a forbid policy of the policy set without conditions
+    /// applies whenever they do. Each permit policy is returned with the id
+    /// of such a forbid policy, sorted by the id of the permit policy. See
+    /// also [`Validator::validate_layers`].
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicyId, PolicySet, Schema};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Doc;
+    ///     action view, delete appliesTo { principal: User, resource: Doc };
+    /// "#.parse().unwrap();
+    /// let policies: PolicySet = r#"
+    ///     forbid(principal, action == Action::"delete", resource);
+    ///     permit(principal == User::"alice", action == Action::"delete", resource);
+    ///     permit(principal == User::"alice", action, resource);
+    /// "#.parse().unwrap();
+    /// assert_eq!(
+    ///     policies.shadowed_permits(&schema),
+    ///     vec![(PolicyId::new("policy1"), PolicyId::new("policy0"))]
+    /// );
+    /// ```
+    pub fn shadowed_permits(&self, schema: &Schema) -> Vec<(PolicyId, PolicyId)> {
+        let mut shadowed = self
+            .policies()
+            .filter(|p| p.effect() == Effect::Permit)
+            .filter_map(|permit| {
+                self.policies()
+                    .find(|forbid| layered::always_overrides(forbid, permit, schema))
+                    .map(|forbid| (permit.id().clone(), forbid.id().clone()))
+            })
+            .collect::<Vec<_>>();
+        shadowed.sort_by(|(a, _), (b, _)| AsRef::<str>::as_ref(a).cmp(b.as_ref()));
+        shadowed
+    }
+
+    /// Enumerate the pairs of an action and a resource type for which some
+    /// permit policy of this policy set may allow a request with the
+    /// principal `principal`, without evaluating any request. This is useful
+    /// to show what a user can do, e.g., in an administration screen.
+    ///
+    /// A pair is included if the scope of a permit policy matches `principal`
+    /// (using the hierarchy of `entities`) and the action, and the policy's
+    /// condition is not always false for requests with this action and type
+    /// of resource, according to `schema` or because it does not depend on
+    /// the request (e.g., `when { 1 > 2 }`). Forbid policies, the values of
+    /// the context, and the attributes of entities are not taken into
+    /// account, so a request for a pair may still be denied; a request for
+    /// any other pair conforming to `schema` is always denied.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, EntityTypeName, EntityUid, PolicySet, Schema};
+    /// # use std::str::FromStr;
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo, Album;
+    ///     action view appliesTo { principal: User, resource: [Photo, Album] };
+    ///     action delete appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let policies: PolicySet = r#"
+    ///     permit(principal == User::"alice", action == Action::"view", resource is Photo);
+    ///     permit(principal == User::"bob", action, resource);
+    /// "#.parse().unwrap();
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let permitted = policies.permitted_actions(&alice, &Entities::empty(), &schema);
+    /// assert_eq!(
+    ///     permitted.into_iter().collect::<Vec<_>>(),
+    ///     vec![(
+    ///         EntityUid::from_str(r#"Action::"view""#).unwrap(),
+    ///         EntityTypeName::from_str("Photo").unwrap()
+    ///     )]
+    /// );
+    /// ```
+    pub fn permitted_actions(
+        &self,
+        principal: &EntityUid,
+        entities: &Entities,
+        schema: &Schema,
+    ) -> BTreeSet<(EntityUid, EntityTypeName)> {
+        let principal_in = |e: &EntityUid| e == principal || entities.is_ancestor_of(e, principal);
+        self.policies()
+            .filter(|p| p.effect() == Effect::Permit)
+            .filter(|p| match p.principal_constraint() {
+                PrincipalConstraint::Any => true,
+                PrincipalConstraint::In(e) => principal_in(&e),
+                PrincipalConstraint::Eq(e) => &e == principal,
+                PrincipalConstraint::Is(ty) => &ty == principal.type_name(),
+                PrincipalConstraint::IsIn(ty, e) => {
+                    &ty == principal.type_name() && principal_in(&e)
+                }
+            })
+            .filter(|p| {
+                let condition =
+                    cedar_policy_core::optimizer::optimize(p.ast.non_scope_constraints());
+                !matches!(
+                    condition.expr_kind(),
+                    ast::ExprKind::Lit(ast::Literal::Bool(false))
+                )
+            })
+            .flat_map(|p| p.get_valid_request_envs(schema))
+            .filter(|env| env.principal() == principal.type_name())
+            .map(|env| (env.action, env.resource))
+            .collect()
+    }
+
     /// Attempt to link a template and add the new template-linked policy to the policy set.
     /// If link fails, the `PolicySet` is not modified.
     /// Failure can happen for three reasons
@@ -2175,16 +3480,60 @@ impl PolicySet {
             // will have already errored if there are any unfilled slots in the
             // template.
             .expect("ast.link() didn't fail above, so this shouldn't fail");
-        self.policies.insert(
-            new_id,
-            Policy {
-                ast: linked_ast.clone(),
-                lossless: linked_lossless,
-            },
-        );
+        let policy = Policy {
+            ast: linked_ast.clone(),
+            lossless: linked_lossless,
+        };
+        self.observers.notify(|o| o.on_link(&policy));
+        Arc::make_mut(&mut self.policies).insert(new_id, Arc::new(policy));
         Ok(())
     }
 
+    /// Link many templates at once, e.g., from the rows of a parameters
+    /// file, adding each new template-linked policy to the policy set. If
+    /// `validator` is given, each link is also validated in strict mode, and
+    /// links which do not validate are not added. A link which fails does not
+    /// prevent the next ones from being added; the returned report gives the
+    /// failures of each link. Observers of the set are notified of a link
+    /// which does not validate being added, then unlinked.
+    ///
+    /// ```
+    /// # use cedar_policy::{BulkTemplateLink, PolicyId, PolicySet, Schema, SlotId, Template, Validator};
+    /// # use std::collections::HashMap;
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let template = Template::parse(
+    ///     Some(PolicyId::new("viewer")),
+    ///     r#"permit(principal == ?principal, action == Action::"view", resource);"#,
+    /// ).unwrap();
+    /// let mut pset = PolicySet::new();
+    /// pset.add_template(template).unwrap();
+    /// let link = |id: &str, principal: &str| BulkTemplateLink {
+    ///     template_id: PolicyId::new("viewer"),
+    ///     link_id: PolicyId::new(id),
+    ///     values: HashMap::from([(SlotId::principal(), principal.parse().unwrap())]),
+    /// };
+    /// let validator = Validator::new(schema);
+    /// let report = pset.link_all(
+    ///     [link("alice", r#"User::"alice""#), link("photo", r#"Photo::"p""#)],
+    ///     Some(&validator),
+    /// );
+    /// assert_eq!(report.linked().collect::<Vec<_>>(), [&PolicyId::new("alice")]);
+    /// let failure = report.failures().next().unwrap();
+    /// assert_eq!((failure.index(), failure.link_id()), (1, &PolicyId::new("photo")));
+    /// assert!(pset.policy(&PolicyId::new("photo")).is_none());
+    /// ```
+    pub fn link_all(
+        &mut self,
+        links: impl IntoIterator<Item = BulkTemplateLink>,
+        validator: Option<&Validator>,
+    ) -> BulkLinkReport {
+        BulkLinkReport::new(self, links, validator)
+    }
+
     /// Get all the unknown entities from the policy set
     #[doc = include_str!("../experimental_warning.md")]
     #[cfg(feature = "partial-eval")]
@@ -2199,7 +3548,7 @@ impl PolicySet {
     /// Unlink a template-linked policy from the policy set.
     /// Returns the policy that was unlinked.
     pub fn unlink(&mut self, policy_id: PolicyId) -> Result<Policy, PolicySetError> {
-        let Some(policy) = self.policies.remove(&policy_id) else {
+        let Some(policy) = Arc::make_mut(&mut self.policies).remove(&policy_id) else {
             return Err(PolicySetError::LinkNonexistent(
                 policy_set_errors::LinkNonexistentError { policy_id },
             ));
@@ -2208,10 +3557,13 @@ impl PolicySet {
         // PANIC SAFETY: We just found the policy in self.policies.
         #[allow(clippy::panic)]
         match self.ast.unlink(&ast::PolicyID::from_string(&policy_id)) {
-            Ok(_) => Ok(policy),
+            Ok(_) => {
+                self.observers.notify(|o| o.on_unlink(&policy));
+                Ok(Arc::unwrap_or_clone(policy))
+            }
             Err(ast::PolicySetUnlinkError::NotLinkError(_)) => {
                 //Restore self.policies
-                self.policies.insert(policy_id.clone(), policy);
+                Arc::make_mut(&mut self.policies).insert(policy_id.clone(), policy);
                 Err(PolicySetError::UnlinkLinkNotLink(
                     policy_set_errors::UnlinkLinkNotLinkError { policy_id },
                 ))
@@ -2233,7 +3585,7 @@ impl std::fmt::Display for PolicySet {
 /// Given a [`PolicyId`] and a [`Policy`], determine if the policy represents a static policy or a
 /// link
 fn is_static_or_link(
-    (id, policy): (PolicyId, Policy),
+    (id, policy): (PolicyId, Arc<Policy>),
 ) -> Result<Either<(ast::PolicyID, est::Policy), TemplateLink>, PolicyToJsonError> {
     match policy.template_id() {
         Some(template_id) => {
@@ -2532,6 +3884,27 @@ impl Template {
         serde_json::to_value(est).map_err(Into::into)
     }
 
+    /// Get the JSON representation of this `Template` in lossless mode,
+    /// retaining the original template text if there is one.
+    /// See [`Policy::to_json_lossless()`].
+    pub fn to_json_lossless(&self) -> Result<serde_json::Value, PolicyToJsonError> {
+        self.lossless.to_lossless_json()
+    }
+
+    /// Create a [`Template`] from the lossless JSON representation produced
+    /// by [`Template::to_json_lossless()`].
+    /// See [`Policy::from_json_lossless()`].
+    pub fn from_json_lossless(
+        id: Option<PolicyId>,
+        json: serde_json::Value,
+    ) -> Result<Self, PolicyFromJsonError> {
+        let (est, source) = LosslessPolicy::from_lossless_json(json)?;
+        if let Some(Ok(template)) = source.map(|src| Self::parse(id.clone(), src)) {
+            return Ok(template);
+        }
+        Self::from_est(id, est)
+    }
+
     /// Get valid [`RequestEnv`]s.
     /// A [`RequestEnv`] is valid when the template type checks w.r.t requests
     /// that satisfy it.
@@ -2741,6 +4114,114 @@ impl Policy {
         self.ast.is_static()
     }
 
+    /// Get a structured summary of this policy, from which documentation
+    /// generators can render a human-readable description
+    pub fn summary(&self) -> PolicySummary {
+        PolicySummary::new(self)
+    }
+
+    /// Get an optimized version of this policy, which is equivalent to it
+    /// if it passes validation: constant subexpressions are evaluated, e.g.,
+    /// `1 + 1` becomes `2` and `[1, 1]` becomes `[1]`, and redundant boolean
+    /// operators are removed, e.g., `!!e` and `true && e` become `e`.
+    /// Subexpressions which depend on the request or on the entity store, or
+    /// whose evaluation fails, are not evaluated. This reduces the cost of
+    /// evaluating machine-generated policies.
+    ///
+    /// If the policy cannot be optimized, it is returned unchanged; otherwise
+    /// its text is the normalized text of the optimized policy.
+    ///
+    /// ```
+    /// # use cedar_policy::Policy;
+    /// let policy = Policy::parse(None, r#"
+    ///     permit(principal, action, resource)
+    ///     when { true && !!(principal.level > 2 * 3) };"#).unwrap();
+    /// let expected = Policy::parse(None, r#"
+    ///     permit(principal, action, resource)
+    ///     when { principal.level > 6 };"#).unwrap();
+    /// let conditions = |p: &Policy| {
+    ///     p.summary().conditions().map(|c| c.to_string()).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(conditions(&policy.optimized()), conditions(&expected));
+    /// ```
+    #[must_use]
+    pub fn optimized(&self) -> Self {
+        let ast = self
+            .ast
+            .map_non_scope_constraints(cedar_policy_core::optimizer::optimize);
+        if ast
+            .non_scope_constraints()
+            .eq_shape(self.ast.non_scope_constraints())
+        {
+            return self.clone();
+        }
+        Self {
+            lossless: LosslessPolicy::Text {
+                text: ast.template().to_string(),
+                slots: ast.env().clone(),
+            },
+            ast,
+        }
+    }
+
+    /// Get the canonical form of this policy, which is equivalent to it:
+    /// the elements of set literals and of the action list are sorted and
+    /// deduplicated, the operands of `==`, `!=`, `+`, and `*` are put in a
+    /// fixed order, and chains of `&&` and `||` are regrouped to the right.
+    /// The operands of `&&` and `||` are not reordered, since they
+    /// short-circuit.
+    ///
+    /// Policies which differ only in these respects have the same canonical
+    /// form, so comparing the text of canonical forms is a more reliable way
+    /// to find duplicate policies or to diff generated policy sets than
+    /// comparing the original text. The text of the canonical form is
+    /// normalized, and the policy id and annotations are unchanged.
+    ///
+    /// ```
+    /// # use cedar_policy::Policy;
+    /// let p1 = Policy::parse(None, r#"
+    ///     permit(principal, action in [Action::"view", Action::"edit"], resource)
+    ///     when { principal.level == 3 && (context.tags.containsAll(["b", "a"]) && context.mfa) };"#).unwrap();
+    /// let p2 = Policy::parse(None, r#"
+    ///     permit(principal, action in [Action::"edit", Action::"view"], resource)
+    ///     when { (3 == principal.level && context.tags.containsAll(["a", "b"])) && context.mfa };"#).unwrap();
+    /// assert_eq!(p1.canonicalize().to_string(), p2.canonicalize().to_string());
+    /// ```
+    #[must_use]
+    pub fn canonicalize(&self) -> Self {
+        let ast = self
+            .ast
+            .map_action_constraint(cedar_policy_core::canonicalizer::canonicalize_action_constraint)
+            .map_non_scope_constraints(cedar_policy_core::canonicalizer::canonicalize);
+        Self {
+            lossless: LosslessPolicy::Text {
+                text: ast.template().to_string(),
+                slots: ast.env().clone(),
+            },
+            ast,
+        }
+    }
+
+    /// Get the patterns of the `like` expressions in this policy's
+    /// conditions, in no particular order
+    ///
+    /// ```
+    /// # use cedar_policy::{LikePattern, Policy};
+    /// let policy = Policy::parse(None, r#"
+    ///     permit(principal, action, resource)
+    ///     when { resource.name like "*.jpg" };"#).unwrap();
+    /// assert_eq!(policy.like_patterns().collect::<Vec<_>>(), vec![LikePattern::new("*.jpg")]);
+    /// ```
+    pub fn like_patterns(&self) -> impl Iterator<Item = LikePattern> + '_ {
+        self.ast
+            .non_scope_constraints()
+            .subexpressions()
+            .filter_map(|e| match e.expr_kind() {
+                ast::ExprKind::Like { pattern, .. } => Some(LikePattern::from_ast(pattern)),
+                _ => None,
+            })
+    }
+
     /// Get the scope constraint on this policy's principal
     pub fn principal_constraint(&self) -> PrincipalConstraint {
         let slot_id = ast::SlotId::principal();
@@ -2950,6 +4431,54 @@ impl Policy {
         serde_json::to_value(est).map_err(Into::into)
     }
 
+    /// Get the JSON representation of this `Policy` in lossless mode.
+    ///
+    /// In addition to the JSON policy format produced by [`Policy::to_json()`]
+    /// (under the `"policy"` key), this retains the original policy text
+    /// (under the `"source"` key) when the policy was parsed from text.
+    /// [`Policy::from_json_lossless()`] uses that text to recover the
+    /// original source locations, so diagnostics for the converted policy
+    /// still point into the original text. Linked policies and policies
+    /// created from JSON have no original text, so only `"policy"` is
+    /// emitted for them.
+    ///
+    /// ```
+    /// # use cedar_policy::Policy;
+    /// let src = r#"
+    ///   @id("adults")
+    ///   permit(principal, action == Action::"view", resource)
+    ///   when { principal.age > 18 };"#;
+    /// let policy = Policy::parse(None, src).unwrap();
+    /// let json = policy.to_json_lossless().unwrap();
+    /// assert_eq!(json["policy"], policy.to_json().unwrap());
+    /// let round_tripped = Policy::from_json_lossless(None, json).unwrap();
+    /// assert_eq!(round_tripped.to_string(), src);
+    /// ```
+    pub fn to_json_lossless(&self) -> Result<serde_json::Value, PolicyToJsonError> {
+        self.lossless.to_lossless_json()
+    }
+
+    /// Create a `Policy` from the lossless JSON representation produced by
+    /// [`Policy::to_json_lossless()`].
+    ///
+    /// The `"policy"` value is authoritative. The `"source"` text, if present,
+    /// is used only when it parses to exactly that policy; in that case the
+    /// resulting `Policy` is equivalent to one parsed from the text, including
+    /// its source locations. Otherwise (e.g., because the JSON was edited
+    /// after export) the text is ignored and the `Policy` is created from the
+    /// `"policy"` value alone, as in [`Policy::from_json()`].
+    /// If `id` is Some, the policy will be given that Policy Id.
+    pub fn from_json_lossless(
+        id: Option<PolicyId>,
+        json: serde_json::Value,
+    ) -> Result<Self, PolicyFromJsonError> {
+        let (est, source) = LosslessPolicy::from_lossless_json(json)?;
+        if let Some(Ok(policy)) = source.map(|src| Self::parse(id.clone(), src)) {
+            return Ok(policy);
+        }
+        Self::from_est(id, est)
+    }
+
     /// Get all the unknown entities from the policy
     #[doc = include_str!("../experimental_warning.md")]
     #[cfg(feature = "partial-eval")]
@@ -3027,6 +4556,18 @@ enum LosslessPolicy {
 }
 
 impl LosslessPolicy {
+    /// Approximate number of bytes of heap memory used by this
+    /// `LosslessPolicy`, which represents `template`
+    #[cfg(feature = "heap-size")]
+    fn approximate_heap_size(&self, template: &ast::Template) -> usize {
+        use cedar_policy_core::heap_size::HeapSize;
+        match self {
+            // the EST is about as large as the AST it was converted to
+            Self::Est(_) => template.heap_size(),
+            Self::Text { text, slots } => text.heap_size() + slots.heap_size(),
+        }
+    }
+
     /// Create a new `LosslessPolicy` from the text of a policy or template.
     fn policy_or_template_text(text: impl Into<String>) -> Self {
         Self::Text {
@@ -3052,6 +4593,34 @@ impl LosslessPolicy {
         }
     }
 
+    /// Get the lossless JSON representation of this static policy, linked
+    /// policy, or template. See `Policy::to_json_lossless()`.
+    fn to_lossless_json(&self) -> Result<serde_json::Value, PolicyToJsonError> {
+        let source = match self {
+            Self::Text { text, slots } if slots.is_empty() => Some(text.clone()),
+            _ => None,
+        };
+        let json = LosslessJson {
+            policy: self.est()?,
+            source,
+        };
+        serde_json::to_value(json).map_err(Into::into)
+    }
+
+    /// Parse the lossless JSON representation, returning the EST and the
+    /// original text. The text is only returned if it parses to the EST.
+    fn from_lossless_json(
+        json: serde_json::Value,
+    ) -> Result<(est::Policy, Option<String>), PolicyFromJsonError> {
+        let LosslessJson { policy, source } = serde_json::from_value(json)
+            .map_err(|e| entities_json_errors::JsonDeserializationError::Serde(e.into()))
+            .map_err(cedar_policy_core::est::FromJsonError::from)?;
+        let source = source.filter(|src| {
+            parser::parse_policy_or_template_to_est(src).is_ok_and(|est| est == policy)
+        });
+        Ok((policy, source))
+    }
+
     fn link<'a>(
         self,
         vals: impl IntoIterator<Item = (ast::SlotId, &'a ast::EntityUID)>,
@@ -3076,6 +4645,16 @@ impl LosslessPolicy {
     }
 }
 
+/// Lossless JSON representation of a policy or template: the JSON policy
+/// format, plus the original text when there is one
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LosslessJson {
+    policy: est::Policy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
 impl std::fmt::Display for LosslessPolicy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -3120,6 +4699,11 @@ impl Expression {
         Self(ast::Expr::val(value))
     }
 
+    /// Create an expression representing the `null` literal.
+    pub fn new_null() -> Self {
+        Self(ast::Expr::val(ast::Literal::Null))
+    }
+
     /// Create an expression representing a record.
     ///
     /// Error if any key appears two or more times in `fields`.
@@ -3212,6 +4796,11 @@ impl RestrictedExpression {
         Self(ast::RestrictedExpr::val(value))
     }
 
+    /// Create an expression representing the `null` literal.
+    pub fn new_null() -> Self {
+        Self(ast::RestrictedExpr::val(ast::Literal::Null))
+    }
+
     /// Create an expression representing a literal `EntityUid`.
     pub fn new_entity_uid(value: EntityUid) -> Self {
         Self(ast::RestrictedExpr::val(ast::EntityUID::from(value)))
@@ -3466,6 +5055,20 @@ impl Request {
         )?))
     }
 
+    /// Like [`Request::new`], but first check that the context is within
+    /// `limits`, returning [`RequestValidationError::LimitExceeded`] otherwise.
+    pub fn new_with_limits(
+        principal: EntityUid,
+        action: EntityUid,
+        resource: EntityUid,
+        context: Context,
+        schema: Option<&Schema>,
+        limits: &RequestLimits,
+    ) -> Result<Self, RequestValidationError> {
+        limits.check(&context)?;
+        Self::new(principal, action, resource, context, schema)
+    }
+
     /// Get the principal component of the request. Returns `None` if the principal is
     /// "unknown" (i.e., constructed using the partial evaluation APIs).
     pub fn principal(&self) -> Option<&EntityUid> {
@@ -3578,6 +5181,18 @@ impl Context {
         Ok(Self(context))
     }
 
+    /// Like [`Context::from_json_str`], but check that the context is within
+    /// `limits`, returning [`ContextJsonError::LimitExceeded`] otherwise.
+    pub fn from_json_str_with_limits(
+        json: &str,
+        schema: Option<(&Schema, &EntityUid)>,
+        limits: &RequestLimits,
+    ) -> Result<Self, ContextJsonError> {
+        let context = Self::from_json_str(json, schema)?;
+        limits.check(&context)?;
+        Ok(context)
+    }
+
     /// Create a `Context` from a `serde_json::Value` (which must be a JSON object,
     /// not any other JSON type, or you will get an error here).
     /// JSON here must use the `__entity` and `__extn` escapes for entity
@@ -3697,6 +5312,31 @@ impl Context {
             .ok_or_else(|| ContextJsonError::missing_action(action.clone()))
     }
 
+    /// Add the time `now` to this context as the well-known attribute
+    /// `now`, a number of seconds since the Unix epoch (negative for earlier
+    /// times), returning an error if the context already has this attribute.
+    ///
+    /// Policies can then grant time-limited access, e.g., with
+    /// `context has now && context.now < 1735689600`. The schema should
+    /// declare the attribute as optional (`now?: Long`) in the context of
+    /// the actions whose requests may carry it, and the validator warns about
+    /// policies which read `context.now` without checking `context has now`.
+    /// To record the time for replaying the request later, use an
+    /// [`EvaluationContext`] instead.
+    ///
+    /// ```
+    /// # use cedar_policy::Context;
+    /// # use std::time::{Duration, UNIX_EPOCH};
+    /// let context = Context::empty()
+    ///     .with_current_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    ///     .unwrap();
+    /// let names: Vec<String> = context.into_iter().map(|(name, _)| name).collect();
+    /// assert_eq!(names, ["now"]);
+    /// ```
+    pub fn with_current_time(self, now: SystemTime) -> Result<Self, ContextCreationError> {
+        EvaluationContext::at(now).bind(self)
+    }
+
     /// Merge this [`Context`] with another context (or iterator over
     /// `(String, RestrictedExpression)` pairs), returning an error if the two
     /// contain overlapping keys
@@ -3754,12 +5394,28 @@ impl std::fmt::Display for Request {
     }
 }
 
+impl Request {
+    /// Display this request with the context attribute values not allowed by
+    /// `redaction` masked, e.g., for logging
+    pub fn redacted<'a>(&'a self, redaction: &'a Redaction) -> Redacted<'a, Self> {
+        Redacted::new(self, redaction)
+    }
+}
+
 impl std::fmt::Display for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+impl Context {
+    /// Display this context with the attribute values not allowed by
+    /// `redaction` masked, e.g., for logging
+    pub fn redacted<'a>(&'a self, redaction: &'a Redaction) -> Redacted<'a, Self> {
+        Redacted::new(self, redaction)
+    }
+}
+
 /// Result of Evaluation
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum EvalResult {
@@ -3771,6 +5427,8 @@ pub enum EvalResult {
     String(String),
     /// Entity Uid
     EntityUid(EntityUid),
+    /// The `null` value
+    Null,
     /// A first-class set
     Set(Set),
     /// A first-class anonymous record
@@ -3847,6 +5505,7 @@ impl From<ast::Value> for EvalResult {
             ast::ValueKind::Lit(ast::Literal::EntityUID(e)) => {
                 Self::EntityUid(ast::EntityUID::clone(&e).into())
             }
+            ast::ValueKind::Lit(ast::Literal::Null) => Self::Null,
             ast::ValueKind::Set(set) => Self::Set(Set(set
                 .authoritative
                 .iter()
@@ -3869,6 +5528,7 @@ impl std::fmt::Display for EvalResult {
             Self::Long(l) => write!(f, "{l}"),
             Self::String(s) => write!(f, "\"{}\"", s.escape_debug()),
             Self::EntityUid(uid) => write!(f, "{uid}"),
+            Self::Null => write!(f, "null"),
             Self::Set(s) => {
                 write!(f, "[")?;
                 for (i, ev) in s.iter().enumerate() {
@@ -4356,3 +6016,51 @@ pub fn compute_entity_manifest(
 ) -> Result<EntityManifest, EntityManifestError> {
     entity_manifest::compute_entity_manifest(&schema.0, &pset.ast).map_err(|e| e.into())
 }
+
+/// Generate a random entity store conforming to `schema`, e.g., for load
+/// testing or benchmarking.
+///
+/// The store contains `config.entities_per_type` entities of every entity type
+/// declared in the schema, with attribute values of the declared types and a
+/// random acyclic hierarchy respecting the schema's `memberOf` declarations,
+/// plus the schema's action entities. Generation is deterministic for a given
+/// schema, configuration, and seed.
+/// ```
+/// # use cedar_policy::{generate_entities, EntityGeneratorConfig, Schema};
+/// # use std::str::FromStr;
+/// let schema = Schema::from_str("entity Group; entity User in [Group];").unwrap();
+/// let config = EntityGeneratorConfig {
+///     entities_per_type: 10,
+///     ..Default::default()
+/// };
+/// let entities = generate_entities(&schema, &config).unwrap();
+/// assert_eq!(entities.iter().count(), 20);
+/// ```
+pub fn generate_entities(
+    schema: &Schema,
+    config: &EntityGeneratorConfig,
+) -> Result<Entities, EntityGenerationError> {
+    cedar_policy_validator::entity_generator::generate_entities(&schema.0, config).map(Entities)
+}
+
+/// Generate TypeScript declarations for the attributes of each entity type
+/// and the context of each action in `schema`, e.g., for applications which
+/// assemble authorization requests in a browser.
+///
+/// The declared types describe the JSON format of entities and contexts when
+/// parsed with `schema`, in which `__entity` and `__extn` escapes are
+/// optional.
+/// ```
+/// # use cedar_policy::{generate_typescript, Schema};
+/// # use std::str::FromStr;
+/// let schema = Schema::from_str(r#"
+///     entity User = { name: String };
+///     action view appliesTo { principal: User, resource: User, context: { mfa: Bool } };
+/// "#).unwrap();
+/// let ts = generate_typescript(&schema).unwrap();
+/// assert!(ts.contains("export interface UserAttributes {\n  name: string;\n}"));
+/// assert!(ts.contains("export interface ViewContext {\n  mfa: boolean;\n}"));
+/// ```
+pub fn generate_typescript(schema: &Schema) -> Result<String, TypeScriptGenerationError> {
+    cedar_policy_validator::typescript::generate_typescript(&schema.0)
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`BulkLinkReport`], which reports the outcome of
+//! linking many templates at once with [`PolicySet::link_all`].
+
+use super::{EntityUid, PolicyId, PolicySet, PolicySetError, SlotId, ValidationError, Validator};
+use cedar_policy_core::ast;
+use cedar_policy_validator::ValidationMode;
+use miette::Diagnostic;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// A template link to add to a policy set with [`PolicySet::link_all`], e.g.,
+/// a row of a parameters file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkTemplateLink {
+    /// The id of the template to link
+    pub template_id: PolicyId,
+    /// The id of the new template-linked policy
+    pub link_id: PolicyId,
+    /// The values of the slots of the template
+    pub values: HashMap<SlotId, EntityUid>,
+}
+
+/// The outcome of [`PolicySet::link_all`]: which links were added to the
+/// policy set, and why the others were not
+#[derive(Debug, Default)]
+pub struct BulkLinkReport {
+    linked: Vec<PolicyId>,
+    failures: Vec<BulkLinkFailure>,
+}
+
+impl BulkLinkReport {
+    /// Add each of `links` to `pset`, validating each new link with
+    /// `validator`, if any
+    pub(super) fn new(
+        pset: &mut PolicySet,
+        links: impl IntoIterator<Item = BulkTemplateLink>,
+        validator: Option<&Validator>,
+    ) -> Self {
+        let mut report = Self::default();
+        for (index, link) in links.into_iter().enumerate() {
+            let link_id = link.link_id;
+            if let Err(err) = pset.link(link.template_id, link_id.clone(), link.values) {
+                report.failures.push(BulkLinkFailure {
+                    index,
+                    link_id,
+                    error: BulkLinkError::Link(err),
+                });
+                continue;
+            }
+            let policy = pset.ast.get(AsRef::<ast::PolicyID>::as_ref(&link_id));
+            let errors: Vec<ValidationError> = match (validator, policy) {
+                (Some(validator), Some(policy)) => validator
+                    .0
+                    .validate_link(policy, ValidationMode::Strict)
+                    .into_iter()
+                    .map(ValidationError::from)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if errors.is_empty() {
+                report.linked.push(link_id);
+            } else {
+                // PANIC SAFETY: the link was added to the policy set above
+                #[allow(clippy::expect_used)]
+                pset.unlink(link_id.clone())
+                    .expect("link was just added to the policy set");
+                report.failures.push(BulkLinkFailure {
+                    index,
+                    error: BulkLinkError::Validation {
+                        link_id: link_id.clone(),
+                        errors,
+                    },
+                    link_id,
+                });
+            }
+        }
+        report
+    }
+
+    /// Whether every link was added to the policy set
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The ids of the links which were added to the policy set, in the order
+    /// they were given
+    pub fn linked(&self) -> impl Iterator<Item = &PolicyId> {
+        self.linked.iter()
+    }
+
+    /// The links which were not added to the policy set, in the order they
+    /// were given
+    pub fn failures(&self) -> impl Iterator<Item = &BulkLinkFailure> {
+        self.failures.iter()
+    }
+}
+
+/// A link which [`PolicySet::link_all`] did not add to the policy set
+#[derive(Debug)]
+pub struct BulkLinkFailure {
+    index: usize,
+    link_id: PolicyId,
+    error: BulkLinkError,
+}
+
+impl BulkLinkFailure {
+    /// The position of the link among the links given to
+    /// [`PolicySet::link_all`], starting from 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The id of the link
+    pub fn link_id(&self) -> &PolicyId {
+        &self.link_id
+    }
+
+    /// Why the link was not added to the policy set
+    pub fn error(&self) -> &BulkLinkError {
+        &self.error
+    }
+}
+
+/// Why [`PolicySet::link_all`] did not add a link to the policy set
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum BulkLinkError {
+    /// Linking the template failed, e.g., because the template does not
+    /// exist, the link id is already used, or slot values are missing
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Link(PolicySetError),
+    /// The template-linked policy does not validate against the schema, e.g.,
+    /// because a slot value has an entity type which the actions of the
+    /// template do not apply to
+    #[error("template-linked policy `{link_id}` does not validate against the schema")]
+    Validation {
+        /// The id of the link
+        link_id: PolicyId,
+        /// The validation errors
+        #[related]
+        errors: Vec<ValidationError>,
+    },
+}
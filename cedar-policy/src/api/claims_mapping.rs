@@ -0,0 +1,252 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`ClaimsMapping`], which converts the claims of a JWT
+//! (e.g., an OIDC ID token) into a principal entity and a context.
+
+use super::entities_errors::EntitiesError;
+use super::{Context, ContextJsonError, Entity, EntityUid, Schema};
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// How to convert the claims of a JWT into a principal entity and a context,
+/// e.g., loaded from a JSON mapping spec:
+///
+/// ```json
+/// {
+///   "principalType": "User",
+///   "idClaim": "sub",
+///   "attributes": { "email": "email", "department": "/org/department" },
+///   "groups": [{ "claim": "groups", "entityType": "Group" }],
+///   "context": { "authTime": "auth_time", "mfa": "/amr/0" }
+/// }
+/// ```
+///
+/// Claims are named either by their key in the claims object, or by a JSON
+/// pointer (starting with `/`) for nested claims. Claims which are missing
+/// from a token are left out of the principal and context, which fails
+/// validation if the schema requires them.
+///
+/// The claims must already be verified and decoded; this does not check
+/// signatures or expiry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct ClaimsMapping {
+    /// Entity type of the principal
+    pub principal_type: String,
+    /// Claim holding the entity id of the principal, `sub` by default
+    #[serde(default = "default_id_claim")]
+    pub id_claim: String,
+    /// Claims to use as attributes of the principal, by attribute name
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    /// Claims listing the groups of the principal, which become its parents
+    #[serde(default)]
+    pub groups: Vec<GroupClaim>,
+    /// Claims to use as attributes of the context, by attribute name
+    #[serde(default)]
+    pub context: BTreeMap<String, String>,
+}
+
+fn default_id_claim() -> String {
+    "sub".to_string()
+}
+
+/// A claim listing groups of the principal, as a string or an array of
+/// strings, e.g., `{ "claim": "groups", "entityType": "Group" }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct GroupClaim {
+    /// The claim
+    pub claim: String,
+    /// Entity type of the groups
+    pub entity_type: String,
+}
+
+impl ClaimsMapping {
+    /// Parse a mapping spec from JSON
+    pub fn from_json_value(json: Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(json)
+    }
+
+    /// Parse a mapping spec from a JSON string
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Convert `claims`, the JSON payload of a JWT, into a principal entity
+    /// and a context. If `schema` is given, the principal must conform to
+    /// the schema, and the context to the context type of the given action.
+    ///
+    /// ```
+    /// # use cedar_policy::{ClaimsMapping, EntityUid, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_cedarschema_str(r#"
+    ///     entity Group;
+    ///     entity User in Group = { email: String };
+    ///     action view appliesTo { principal: User, resource: User, context: { mfa: Bool } };
+    /// "#).unwrap();
+    /// let mapping = ClaimsMapping::from_json_value(serde_json::json!({
+    ///     "principalType": "User",
+    ///     "attributes": { "email": "email" },
+    ///     "groups": [{ "claim": "groups", "entityType": "Group" }],
+    ///     "context": { "mfa": "mfa" },
+    /// })).unwrap();
+    /// let claims = serde_json::json!({
+    ///     "sub": "alice",
+    ///     "email": "alice@example.com",
+    ///     "groups": ["admins", "staff"],
+    ///     "mfa": true,
+    /// });
+    /// let action = EntityUid::from_str(r#"Action::"view""#).unwrap();
+    /// let mapped = mapping.map(&claims, Some((&schema, &action))).unwrap();
+    /// assert_eq!(mapped.principal().uid().to_string(), r#"User::"alice""#);
+    /// let admins = EntityUid::from_str(r#"Group::"admins""#).unwrap();
+    /// let (principal, _context) = mapped.into_parts();
+    /// assert!(principal.into_inner().2.contains(&admins));
+    /// ```
+    pub fn map(
+        &self,
+        claims: &Value,
+        schema: Option<(&Schema, &EntityUid)>,
+    ) -> Result<MappedClaims, ClaimsMappingError> {
+        let id = match lookup(claims, &self.id_claim) {
+            Some(Value::String(id)) => id,
+            Some(_) => return Err(ClaimsMappingError::invalid_claim(&self.id_claim)),
+            None => {
+                return Err(ClaimsMappingError::MissingClaim {
+                    claim: self.id_claim.clone(),
+                })
+            }
+        };
+        let attrs = claims_by_name(claims, &self.attributes);
+        let mut parents = Vec::new();
+        for group in &self.groups {
+            let ids: Vec<&str> = match lookup(claims, &group.claim) {
+                None => continue,
+                Some(Value::String(id)) => vec![id.as_str()],
+                Some(Value::Array(ids)) => ids
+                    .iter()
+                    .map(|id| {
+                        id.as_str()
+                            .ok_or_else(|| ClaimsMappingError::invalid_claim(&group.claim))
+                    })
+                    .collect::<Result<_, _>>()?,
+                Some(_) => return Err(ClaimsMappingError::invalid_claim(&group.claim)),
+            };
+            parents.extend(
+                ids.into_iter()
+                    .map(|id| json!({ "type": group.entity_type, "id": id })),
+            );
+        }
+        let principal = Entity::from_json_value(
+            json!({
+                "uid": { "type": self.principal_type, "id": id },
+                "attrs": attrs,
+                "parents": parents,
+            }),
+            schema.map(|(schema, _)| schema),
+        )?;
+        let context =
+            Context::from_json_value(Value::Object(claims_by_name(claims, &self.context)), schema)?;
+        Ok(MappedClaims { principal, context })
+    }
+}
+
+/// The claims in `names` which are present in `claims`, by name
+fn claims_by_name(claims: &Value, names: &BTreeMap<String, String>) -> Map<String, Value> {
+    names
+        .iter()
+        .filter_map(|(name, claim)| Some((name.clone(), lookup(claims, claim)?.clone())))
+        .collect()
+}
+
+/// Look up a claim by key or, if it starts with `/`, by JSON pointer
+fn lookup<'a>(claims: &'a Value, claim: &str) -> Option<&'a Value> {
+    if claim.starts_with('/') {
+        claims.pointer(claim)
+    } else {
+        claims.get(claim)
+    }
+}
+
+/// The principal entity and the context obtained from the claims of a JWT
+/// with [`ClaimsMapping::map`]
+#[derive(Debug, Clone)]
+pub struct MappedClaims {
+    principal: Entity,
+    context: Context,
+}
+
+impl MappedClaims {
+    /// The principal, whose parents are the groups listed in the claims
+    pub fn principal(&self) -> &Entity {
+        &self.principal
+    }
+
+    /// The context
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Consume `self`, returning the principal and the context
+    pub fn into_parts(self) -> (Entity, Context) {
+        (self.principal, self.context)
+    }
+}
+
+/// Errors when converting the claims of a JWT with [`ClaimsMapping::map`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum ClaimsMappingError {
+    /// The claim holding the entity id of the principal is missing
+    #[error("claim `{claim}` is missing")]
+    MissingClaim {
+        /// The claim
+        claim: String,
+    },
+    /// The claim holding the entity id of the principal is not a string, or
+    /// a group claim is not a string or an array of strings
+    #[error("claim `{claim}` has an unexpected type")]
+    #[diagnostic(help(
+        "the entity id claim must be a string, and group claims must be a string or an array of strings"
+    ))]
+    InvalidClaim {
+        /// The claim
+        claim: String,
+    },
+    /// The principal is not a valid entity, or does not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Principal(#[from] EntitiesError),
+    /// The context is not valid, or does not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Context(#[from] ContextJsonError),
+}
+
+impl ClaimsMappingError {
+    fn invalid_claim(claim: &str) -> Self {
+        Self::InvalidClaim {
+            claim: claim.to_string(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`Constants`], named values which policy sets can
+//! reference.
+
+use crate::ParseErrors;
+use cedar_policy_core::parser;
+use smol_str::SmolStr;
+use std::str::FromStr;
+
+/// Named constants, defined in a constants file separate from the policy sets
+/// which reference them, so that e.g. a list of groups can be updated without
+/// updating every policy which uses it.
+///
+/// A constants file is made of `const NAME = value;` definitions, where the
+/// value is a literal, an entity reference, an extension function call, or a
+/// set or record of these, and may reference earlier constants. The conditions
+/// of a policy set parsed with [`crate::PolicySet::from_str_with_constants`]
+/// can reference the constants by name.
+///
+/// ```
+/// # use cedar_policy::{Constants, PolicySet};
+/// # use std::str::FromStr;
+/// let constants = Constants::from_str(
+///     r#"const ADMIN_GROUPS = [Group::"admins", Group::"ops"];"#,
+/// ).unwrap();
+/// let policies = PolicySet::from_str_with_constants(
+///     "permit(principal, action, resource) when { principal in ADMIN_GROUPS };",
+///     &constants,
+/// ).unwrap();
+/// assert_eq!(policies.policies().count(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Constants(pub(crate) parser::Constants);
+
+impl Constants {
+    /// The names of the constants
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.names().map(SmolStr::as_str)
+    }
+}
+
+impl FromStr for Constants {
+    type Err = ParseErrors;
+
+    /// Parse a constants file. Fails if a constant is defined twice, or if the
+    /// value of a constant is not a valid value, e.g., if it references
+    /// `principal`.
+    fn from_str(constants: &str) -> Result<Self, Self::Err> {
+        Ok(Self(parser::parse_constants(constants)?))
+    }
+}
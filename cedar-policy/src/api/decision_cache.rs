@@ -0,0 +1,536 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`DecisionCache`], a cache of authorization responses
+//! which is invalidated by changes to the entities or policies they depend on.
+
+use crate::{Authorizer, Entities, EntityUid, PolicySet, Request, Response};
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Dereference;
+use cedar_policy_core::evaluator::DependencyTracer;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A cache of the authorization responses for a policy set, keyed by the
+/// principal, action, resource, and a hash of the context of the request, for
+/// read-heavy services which authorize the same requests repeatedly.
+///
+/// Each response records the entities whose data the policies read while
+/// computing it. The entities are not part of the key: instead, when the
+/// data of some entities changes, [`DecisionCache::invalidate_entities()`]
+/// evicts the responses which depend on them. Since ancestors are stored
+/// transitively, changing the parents of an entity also changes the data of
+/// its descendants, which must be invalidated too. Replacing the policies with
+/// [`DecisionCache::set_policies()`] evicts the responses for the actions
+/// which the changed policies may apply to.
+///
+/// The cache can be shared between threads. It holds at most `capacity`
+/// responses, and evicts the least recently added one when full. Requests
+/// with unknown components are never cached.
+///
+/// ```
+/// # use cedar_policy::{Context, Decision, DecisionCache, Entities, EntityUid, PolicySet, Request};
+/// # use std::str::FromStr;
+/// let policies = PolicySet::from_str(
+///     "permit(principal, action, resource) when { principal.level > 3 };",
+/// ).unwrap();
+/// let cache = DecisionCache::new(policies, 1000);
+/// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+/// let request = Request::new(
+///     alice.clone(),
+///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+///     EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+///     Context::empty(),
+///     None,
+/// ).unwrap();
+/// let entities = |level: i64| Entities::from_json_value(serde_json::json!([
+///     { "uid": { "type": "User", "id": "alice" }, "attrs": { "level": level }, "parents": [] }
+/// ]), None).unwrap();
+/// assert_eq!(cache.is_authorized(&request, &entities(5)).decision(), Decision::Allow);
+///
+/// // alice's level changes, so her cached responses are invalid
+/// cache.invalidate_entities([&alice]);
+/// assert_eq!(cache.is_authorized(&request, &entities(1)).decision(), Decision::Deny);
+/// ```
+#[derive(Debug)]
+pub struct DecisionCache {
+    capacity: usize,
+    authorizer: Authorizer,
+    state: Mutex<State>,
+}
+
+/// The policies and cached responses
+#[derive(Debug)]
+struct State {
+    policies: Arc<PolicySet>,
+    /// Incremented by each invalidation, so that responses computed
+    /// concurrently with an invalidation are not cached
+    generation: u64,
+    /// Sequence number of the next cached response
+    next_seq: u64,
+    entries: HashMap<Key, Entry>,
+    /// Keys of the cached responses in the order they were added, by
+    /// sequence number
+    order: BTreeMap<u64, Key>,
+    /// Keys of the cached responses depending on the data of each entity
+    by_entity: HashMap<ast::EntityUID, HashSet<Key>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    principal: ast::EntityUID,
+    action: ast::EntityUID,
+    resource: ast::EntityUID,
+    context_hash: u64,
+}
+
+/// A cached response
+#[derive(Debug)]
+struct Entry {
+    seq: u64,
+    /// The context of the request, since contexts with the same hash may
+    /// differ
+    context: ast::Context,
+    response: Response,
+    /// The entities whose data the response depends on, including the action
+    entities: HashSet<ast::EntityUID>,
+    /// The ancestors of the action of the request, which determine the
+    /// policies which may apply to it
+    action_ancestors: HashSet<ast::EntityUID>,
+}
+
+impl DecisionCache {
+    /// A cache of at most `capacity` responses for `policies`, using the
+    /// default [`Authorizer`]
+    pub fn new(policies: PolicySet, capacity: usize) -> Self {
+        Self {
+            capacity,
+            authorizer: Authorizer::new(),
+            state: Mutex::new(State {
+                policies: Arc::new(policies),
+                generation: 0,
+                next_seq: 0,
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                by_entity: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Compute responses with `authorizer`, e.g., to set its overflow mode
+    #[must_use]
+    pub fn with_authorizer(mut self, authorizer: Authorizer) -> Self {
+        self.authorizer = authorizer;
+        self.clear();
+        self
+    }
+
+    /// The current policies
+    pub fn policies(&self) -> Arc<PolicySet> {
+        Arc::clone(&self.state().policies)
+    }
+
+    /// The response for `request` with respect to the current policies and
+    /// `entities`. This authorizes `request` unless its response is cached.
+    /// The cached response is returned as long as the entities it depends on
+    /// are not invalidated, even if `entities` differ.
+    pub fn is_authorized(&self, request: &Request, entities: &Entities) -> Response {
+        let Some((key, context)) = Key::new(&request.0) else {
+            let policies = self.policies();
+            return self.authorizer.is_authorized(request, &policies, entities);
+        };
+        let (policies, generation) = {
+            let state = self.state();
+            if let Some(entry) = state.lookup(&key, context) {
+                return entry.response.clone();
+            }
+            (Arc::clone(&state.policies), state.generation)
+        };
+        // authorize without holding the lock, so that other threads can use
+        // the cache meanwhile
+        let tracer = DependencyTracer::new();
+        let response: Response = self
+            .authorizer
+            .0
+            .is_authorized_with_dependencies(request.0.clone(), &policies.ast, &entities.0, &tracer)
+            .into();
+        let mut dependencies = tracer.into_entities();
+        dependencies.insert(key.action.clone());
+        let action_ancestors = match entities.0.entity(&key.action) {
+            Dereference::Data(action) => entities.0.ancestors_of(action).cloned().collect(),
+            Dereference::NoSuchEntity | Dereference::Residual(_) => HashSet::new(),
+        };
+        let mut state = self.state();
+        // the policies or entities may have changed meanwhile
+        if state.generation == generation && self.capacity > 0 {
+            if state.entries.len() >= self.capacity && !state.entries.contains_key(&key) {
+                state.evict();
+            }
+            state.insert(
+                key,
+                Entry {
+                    seq: 0,
+                    context: context.clone(),
+                    response: response.clone(),
+                    entities: dependencies,
+                    action_ancestors,
+                },
+            );
+        }
+        response
+    }
+
+    /// Evict the cached responses which depend on the data of any of
+    /// `entities`, e.g., because their attributes or parents changed, or
+    /// because they were added or removed
+    pub fn invalidate_entities<'a>(&self, entities: impl IntoIterator<Item = &'a EntityUid>) {
+        let mut state = self.state();
+        state.generation += 1;
+        for uid in entities {
+            for key in state.by_entity.remove(uid.as_ref()).unwrap_or_default() {
+                state.remove(&key);
+            }
+        }
+    }
+
+    /// Replace the policies, evicting the cached responses for the actions
+    /// which a policy added, removed, or changed may apply to, i.e., which
+    /// its action scope constraint matches. Responses for other actions are
+    /// kept, since the changed policies don't apply to them.
+    pub fn set_policies(&self, policies: PolicySet) {
+        let mut state = self.state();
+        state.generation += 1;
+        let old = Arc::clone(&state.policies);
+        let ids: HashSet<&ast::PolicyID> = old
+            .ast
+            .policies()
+            .chain(policies.ast.policies())
+            .map(ast::Policy::id)
+            .collect();
+        let changed: Vec<&ast::ActionConstraint> = ids
+            .into_iter()
+            .filter_map(|id| {
+                let (before, after) = (old.ast.get(id), policies.ast.get(id));
+                // compare the text rather than the policies, which include
+                // their source locations
+                (before.map(ToString::to_string) != after.map(ToString::to_string))
+                    .then(|| before.into_iter().chain(after))
+            })
+            .flatten()
+            .map(ast::Policy::action_constraint)
+            .collect();
+        let stale: Vec<Key> = state
+            .entries
+            .iter()
+            .filter(|(key, entry)| {
+                changed
+                    .iter()
+                    .any(|constraint| may_apply(constraint, &key.action, &entry.action_ancestors))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &stale {
+            state.remove(key);
+        }
+        state.policies = Arc::new(policies);
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    /// True if no response is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached responses
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.generation += 1;
+        state.entries.clear();
+        state.order.clear();
+        state.by_entity.clear();
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        // the state is only modified by operations which don't panic (barring
+        // allocation failure), so keep using it after a panic in another
+        // thread
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Key {
+    /// The key of `request` and its context, or `None` if some component of
+    /// `request` is unknown
+    fn new(request: &ast::Request) -> Option<(Self, &ast::Context)> {
+        let context = request.context()?;
+        let mut hasher = DefaultHasher::new();
+        context.to_string().hash(&mut hasher);
+        Some((
+            Self {
+                principal: request.principal().uid()?.clone(),
+                action: request.action().uid()?.clone(),
+                resource: request.resource().uid()?.clone(),
+                context_hash: hasher.finish(),
+            },
+            context,
+        ))
+    }
+}
+
+impl State {
+    fn lookup(&self, key: &Key, context: &ast::Context) -> Option<&Entry> {
+        self.entries
+            .get(key)
+            .filter(|entry| &entry.context == context)
+    }
+
+    fn insert(&mut self, key: Key, mut entry: Entry) {
+        self.remove(&key);
+        entry.seq = self.next_seq;
+        self.next_seq += 1;
+        self.order.insert(entry.seq, key.clone());
+        for uid in &entry.entities {
+            self.by_entity
+                .entry(uid.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &Key) {
+        let Some(entry) = self.entries.remove(key) else {
+            return;
+        };
+        self.order.remove(&entry.seq);
+        for uid in &entry.entities {
+            if let Some(keys) = self.by_entity.get_mut(uid) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.by_entity.remove(uid);
+                }
+            }
+        }
+    }
+
+    /// Remove the least recently added response
+    fn evict(&mut self) {
+        if let Some(key) = self.order.values().next().cloned() {
+            self.remove(&key);
+        }
+    }
+}
+
+/// True if a policy with the action scope constraint `constraint` may apply
+/// to requests for `action`, which has the ancestors `action_ancestors`
+fn may_apply(
+    constraint: &ast::ActionConstraint,
+    action: &ast::EntityUID,
+    action_ancestors: &HashSet<ast::EntityUID>,
+) -> bool {
+    match constraint {
+        ast::ActionConstraint::Any => true,
+        ast::ActionConstraint::Eq(a) => a.as_ref() == action,
+        ast::ActionConstraint::In(actions) => actions
+            .iter()
+            .any(|a| a.as_ref() == action || action_ancestors.contains(a.as_ref())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Decision, TCComputation};
+    use std::str::FromStr;
+
+    fn uid(s: &str) -> EntityUid {
+        EntityUid::from_str(s).unwrap()
+    }
+
+    fn request(principal: &str, action: &str) -> Request {
+        Request::new(
+            uid(principal),
+            uid(action),
+            uid(r#"Photo::"p""#),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn entities(alice_level: i64, bob_level: i64) -> Entities {
+        Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "level": alice_level }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "level": bob_level }, "parents": [] },
+                { "uid": { "type": "Action", "id": "view" }, "attrs": {}, "parents": [{ "type": "Action", "id": "read" }] },
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn policies(src: &str) -> PolicySet {
+        PolicySet::from_str(src).unwrap()
+    }
+
+    #[test]
+    fn invalidates_dependent_entities() {
+        let cache = DecisionCache::new(
+            policies(r#"permit(principal, action, resource) when { principal.level > 3 };"#),
+            10,
+        );
+        let (alice, bob) = (
+            request(r#"User::"alice""#, r#"Action::"view""#),
+            request(r#"User::"bob""#, r#"Action::"view""#),
+        );
+        assert_eq!(
+            cache.is_authorized(&alice, &entities(5, 1)).decision(),
+            Decision::Allow
+        );
+        assert_eq!(
+            cache.is_authorized(&bob, &entities(5, 1)).decision(),
+            Decision::Deny
+        );
+        assert_eq!(cache.len(), 2);
+
+        // cached responses are returned until invalidated
+        assert_eq!(
+            cache.is_authorized(&alice, &entities(1, 5)).decision(),
+            Decision::Allow
+        );
+        cache.invalidate_entities([&uid(r#"User::"alice""#)]);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.is_authorized(&alice, &entities(1, 5)).decision(),
+            Decision::Deny
+        );
+        assert_eq!(
+            cache.is_authorized(&bob, &entities(1, 5)).decision(),
+            Decision::Deny
+        );
+
+        // invalidating an entity no response depends on keeps the responses
+        cache.invalidate_entities([&uid(r#"User::"carol""#)]);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn invalidates_actions_of_changed_policies() {
+        let src = r#"
+            permit(principal, action == Action::"view", resource);
+            permit(principal, action == Action::"edit", resource) when { principal.level > 3 };
+        "#;
+        let cache = DecisionCache::new(policies(src), 10);
+        let (view, edit) = (
+            request(r#"User::"bob""#, r#"Action::"view""#),
+            request(r#"User::"bob""#, r#"Action::"edit""#),
+        );
+        assert_eq!(
+            cache.is_authorized(&view, &entities(5, 1)).decision(),
+            Decision::Allow
+        );
+        assert_eq!(
+            cache.is_authorized(&edit, &entities(5, 1)).decision(),
+            Decision::Deny
+        );
+
+        // changing the policy for `edit` keeps the response for `view`
+        cache.set_policies(policies(
+            r#"
+            permit(principal, action == Action::"view", resource);
+            permit(principal, action == Action::"edit", resource);
+        "#,
+        ));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.is_authorized(&edit, &entities(5, 1)).decision(),
+            Decision::Allow
+        );
+
+        // a policy for an action group evicts the responses for its members
+        cache.set_policies(policies(
+            r#"
+            permit(principal, action == Action::"view", resource);
+            permit(principal, action == Action::"edit", resource);
+            forbid(principal, action in Action::"read", resource);
+        "#,
+        ));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.is_authorized(&view, &entities(5, 1)).decision(),
+            Decision::Deny
+        );
+
+        // an unconstrained policy evicts every response
+        cache.set_policies(policies(r#"permit(principal, action, resource);"#));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn invalidates_action_groups_with_tc_on_demand() {
+        let entities = Entities::from_json_value_with_tc(
+            serde_json::json!([
+                { "uid": { "type": "Action", "id": "view" }, "attrs": {}, "parents": [{ "type": "Action", "id": "read" }] },
+                { "uid": { "type": "Action", "id": "read" }, "attrs": {}, "parents": [{ "type": "Action", "id": "all" }] },
+                { "uid": { "type": "Action", "id": "all" }, "attrs": {}, "parents": [] },
+            ]),
+            None,
+            TCComputation::ComputeOnDemand,
+        )
+        .unwrap();
+        let cache = DecisionCache::new(policies(r#"permit(principal, action, resource);"#), 10);
+        let view = request(r#"User::"bob""#, r#"Action::"view""#);
+        assert_eq!(
+            cache.is_authorized(&view, &entities).decision(),
+            Decision::Allow
+        );
+
+        // a policy for an indirect action group evicts the responses for its
+        // members, though the store holds only their direct parents
+        cache.set_policies(policies(
+            r#"
+            permit(principal, action, resource);
+            forbid(principal, action in Action::"all", resource);
+        "#,
+        ));
+        assert!(cache.is_empty());
+        assert_eq!(
+            cache.is_authorized(&view, &entities).decision(),
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn evicts_least_recently_added() {
+        let cache = DecisionCache::new(policies(r#"permit(principal, action, resource);"#), 2);
+        for principal in [r#"User::"alice""#, r#"User::"bob""#, r#"User::"carol""#] {
+            cache.is_authorized(&request(principal, r#"Action::"view""#), &entities(5, 1));
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache
+            .state()
+            .entries
+            .keys()
+            .all(|key| key.principal.to_string() != r#"User::"alice""#));
+    }
+}
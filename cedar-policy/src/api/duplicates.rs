@@ -0,0 +1,168 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the detection of duplicate policies in a policy set,
+//! see [`super::PolicySet::find_duplicates`].
+
+use super::{Policy, PolicyId, Schema};
+use cedar_policy_core::ast::{
+    self, ActionConstraint, EntityReference, EntityType, PrincipalOrResourceConstraint,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How [`super::PolicySet::find_duplicates`] decides that two policies are
+/// duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PolicyEquivalence {
+    /// The policies have the same effect, scope, and canonical conditions
+    /// (see [`Policy::canonicalize`]). Ids and annotations are ignored.
+    Canonical,
+    /// The policies are equivalent for requests and entities which conform to
+    /// the schema, as shown by comparing their canonical forms after the
+    /// following rewrites:
+    ///
+    /// - the conditions are optimized (see [`Policy::optimized`]);
+    /// - the action scope is replaced by the set of actions of the schema it
+    ///   matches which apply to some principals and resources, so that e.g.
+    ///   `action in Action::"readOnly"` is the same as
+    ///   `action in [Action::"view", Action::"list"]` if these are the
+    ///   members of the action group;
+    /// - `principal in E` (and `resource in E`) is replaced by
+    ///   `principal == E` if no entity type may be a member of the type of
+    ///   `E`.
+    ///
+    /// The policies in a group are equivalent if they pass validation against
+    /// the schema, but some equivalent policies may not be detected, since
+    /// deciding equivalence in general requires a solver.
+    Semantic,
+}
+
+/// The groups of duplicates among `policies`, see
+/// [`super::PolicySet::find_duplicates`]
+pub(super) fn duplicate_groups<'a>(
+    policies: impl IntoIterator<Item = &'a Policy>,
+    schema: &Schema,
+    equivalence: PolicyEquivalence,
+) -> Vec<Vec<PolicyId>> {
+    let mut groups: HashMap<String, Vec<PolicyId>> = HashMap::new();
+    for policy in policies {
+        let key = match equivalence {
+            PolicyEquivalence::Canonical => canonical_key(&policy.canonicalize().ast, None),
+            PolicyEquivalence::Semantic => {
+                canonical_key(&policy.optimized().canonicalize().ast, Some(schema))
+            }
+        };
+        groups.entry(key).or_default().push(policy.id().clone());
+    }
+    let mut groups: Vec<Vec<PolicyId>> = groups
+        .into_values()
+        .filter(|ids| ids.len() > 1)
+        .map(|mut ids| {
+            ids.sort_by(|a, b| AsRef::<str>::as_ref(a).cmp(b.as_ref()));
+            ids
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        a.first()
+            .map(AsRef::<str>::as_ref)
+            .cmp(&b.first().map(AsRef::<str>::as_ref))
+    });
+    groups
+}
+
+/// A string which is the same for two canonical policies iff they are
+/// duplicates. If `schema` is given, the scope is normalized according to it.
+fn canonical_key(policy: &ast::Policy, schema: Option<&Schema>) -> String {
+    let mut principal = policy.principal_constraint().into_inner();
+    let mut resource = policy.resource_constraint().into_inner();
+    if let Some(schema) = schema {
+        principal = without_redundant_in(principal, schema);
+        resource = without_redundant_in(resource, schema);
+    }
+    let actions = schema
+        .and_then(|schema| matched_actions(policy.action_constraint(), schema))
+        .map_or_else(|| policy.action_constraint().clone(), ActionConstraint::In);
+    format!(
+        "{}({}, {}, {}) when {{ {} }}",
+        policy.effect(),
+        ast::PrincipalConstraint::new(principal),
+        actions,
+        ast::ResourceConstraint::new(resource),
+        policy.non_scope_constraints()
+    )
+}
+
+/// The actions of `schema` which `constraint` matches and which may appear in
+/// requests, sorted, or `None` if `constraint` refers to an action which is
+/// not in the schema
+pub(super) fn matched_actions(
+    constraint: &ActionConstraint,
+    schema: &Schema,
+) -> Option<Vec<Arc<ast::EntityUID>>> {
+    let matches = |action: &ast::EntityUID| match constraint {
+        ActionConstraint::Any => Some(true),
+        ActionConstraint::Eq(euid) => Some(action == euid.as_ref()),
+        ActionConstraint::In(euids) => euids.iter().try_fold(false, |found, euid| {
+            let group = schema.0.get_action_id(euid)?;
+            Some(found || action == euid.as_ref() || group.has_descendant(action))
+        }),
+    };
+    if let ActionConstraint::Eq(euid) = constraint {
+        schema.0.get_action_id(euid)?;
+    }
+    let mut actions = Vec::new();
+    let infos = schema
+        .0
+        .actions()
+        .filter_map(|action| Some((action, schema.0.get_action_id(action)?)));
+    for (action, info) in infos {
+        let applies = info.applies_to_principals().next().is_some()
+            && info.applies_to_resources().next().is_some();
+        if matches(action)? && applies {
+            actions.push(Arc::new(action.clone()));
+        }
+    }
+    actions.sort();
+    Some(actions)
+}
+
+/// `constraint`, where `in E` is replaced by `== E` if no entity type of
+/// `schema` may be a member of the type of `E`
+fn without_redundant_in(
+    constraint: PrincipalOrResourceConstraint,
+    schema: &Schema,
+) -> PrincipalOrResourceConstraint {
+    match constraint {
+        PrincipalOrResourceConstraint::In(EntityReference::EUID(euid))
+            if !may_have_members(euid.entity_type(), schema) =>
+        {
+            PrincipalOrResourceConstraint::Eq(EntityReference::EUID(euid))
+        }
+        constraint => constraint,
+    }
+}
+
+/// Whether an entity of type `ty` may have members according to `schema`.
+/// This is the case if `ty` is not in the schema.
+fn may_have_members(ty: &EntityType, schema: &Schema) -> bool {
+    schema.0.get_entity_type(ty).map_or(true, |ety| {
+        schema
+            .0
+            .entity_types()
+            .any(|(member, _)| ety.has_descendant_entity_type(member))
+    })
+}
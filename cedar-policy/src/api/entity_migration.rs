@@ -0,0 +1,119 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`EntityMigrationReport`], which reports the entities
+//! of an entity store which do not conform to a new version of a schema.
+
+use super::conformance_errors::EntitySchemaConformanceError;
+use super::{Entities, EntityTypeName, EntityUid, Schema};
+use cedar_policy_core::entities::conformance::EntitySchemaConformanceChecker;
+use cedar_policy_core::extensions::Extensions;
+use std::collections::BTreeMap;
+
+/// The entities of an entity store which do not conform to a new schema,
+/// grouped by entity type. See [`Entities::check_migration`].
+#[derive(Debug, Default)]
+pub struct EntityMigrationReport {
+    issues: BTreeMap<EntityTypeName, Vec<EntityMigrationIssue>>,
+}
+
+impl EntityMigrationReport {
+    /// Check the entities of `entities`, except action entities (which are
+    /// defined by the schema), against `old` and `new`
+    pub(super) fn new(old: &Schema, new: &Schema, entities: &Entities) -> Self {
+        let old = cedar_policy_validator::CoreSchema::new(&old.0);
+        let new = cedar_policy_validator::CoreSchema::new(&new.0);
+        let old = EntitySchemaConformanceChecker::new(&old, Extensions::all_available());
+        let new = EntitySchemaConformanceChecker::new(&new, Extensions::all_available());
+        let mut issues: BTreeMap<EntityTypeName, Vec<EntityMigrationIssue>> = BTreeMap::new();
+        for entity in entities.0.iter() {
+            if entity.uid().is_action() {
+                continue;
+            }
+            if let Err(error) = new.validate_entity(entity) {
+                let uid = EntityUid::from(entity.uid().clone());
+                issues
+                    .entry(uid.type_name().clone())
+                    .or_default()
+                    .push(EntityMigrationIssue {
+                        conformed_to_old: old.validate_entity(entity).is_ok(),
+                        uid,
+                        error,
+                    });
+            }
+        }
+        for issues in issues.values_mut() {
+            issues.sort_by(|a, b| a.uid.cmp(&b.uid));
+        }
+        Self { issues }
+    }
+
+    /// Whether every entity conforms to the new schema
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The number of entities which do not conform to the new schema
+    pub fn len(&self) -> usize {
+        self.issues.values().map(Vec::len).sum()
+    }
+
+    /// The entity types with entities which do not conform to the new
+    /// schema, with these entities, sorted by entity type and uid
+    pub fn by_entity_type(
+        &self,
+    ) -> impl Iterator<Item = (&EntityTypeName, &[EntityMigrationIssue])> {
+        self.issues
+            .iter()
+            .map(|(ty, issues)| (ty, issues.as_slice()))
+    }
+
+    /// The entities which conformed to the old schema but do not conform to
+    /// the new one, i.e., the entities the migration breaks
+    pub fn regressions(&self) -> impl Iterator<Item = &EntityMigrationIssue> {
+        self.issues
+            .values()
+            .flatten()
+            .filter(|issue| issue.conformed_to_old)
+    }
+}
+
+/// An entity which does not conform to the new schema of a migration
+#[derive(Debug)]
+pub struct EntityMigrationIssue {
+    uid: EntityUid,
+    error: EntitySchemaConformanceError,
+    conformed_to_old: bool,
+}
+
+impl EntityMigrationIssue {
+    /// The uid of the entity
+    pub fn uid(&self) -> &EntityUid {
+        &self.uid
+    }
+
+    /// Why the entity does not conform to the new schema, e.g., it lacks a
+    /// required attribute or an attribute has a different type
+    pub fn error(&self) -> &EntitySchemaConformanceError {
+        &self.error
+    }
+
+    /// Whether the entity conformed to the old schema. If not, the entity
+    /// was already invalid before the migration.
+    pub fn conformed_to_old(&self) -> bool {
+        self.conformed_to_old
+    }
+}
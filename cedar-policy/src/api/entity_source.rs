@@ -0,0 +1,447 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`EntitySource`], the interface of adapters which sync
+//! entities from an identity system, and [`ScimEntitySource`], which syncs
+//! users and groups from SCIM payloads.
+
+use super::entities_errors::EntitiesError;
+use super::{Entities, Schema};
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// A source of entities kept in sync with an identity system, e.g., a SCIM
+/// or LDAP directory of users and groups.
+///
+/// How updates reach the source depends on the identity system; the source
+/// exposes the entities reflecting all updates applied so far, and a
+/// generation number which changes whenever they do, so that consumers can
+/// cheaply check whether to pick up new entities.
+pub trait EntitySource {
+    /// The current entities of the source
+    fn entities(&self) -> &Entities;
+
+    /// A number which increases whenever [`EntitySource::entities`] changes
+    fn generation(&self) -> u64;
+}
+
+/// How SCIM users and groups become entities, e.g., loaded from JSON:
+///
+/// ```json
+/// {
+///   "userType": "User",
+///   "groupType": "Group",
+///   "userAttributes": { "userName": "/userName", "email": "/emails/0/value" },
+///   "groupAttributes": { "name": "/displayName" }
+/// }
+/// ```
+///
+/// Attributes are taken from the SCIM resource by JSON pointer, and are left
+/// out when the resource does not have them. Group membership is taken from
+/// the `members` of groups: each member becomes a child of the group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct ScimMapping {
+    /// Entity type of users
+    pub user_type: String,
+    /// Entity type of groups
+    pub group_type: String,
+    /// Attributes of users, by attribute name
+    #[serde(default)]
+    pub user_attributes: BTreeMap<String, String>,
+    /// Attributes of groups, by attribute name
+    #[serde(default)]
+    pub group_attributes: BTreeMap<String, String>,
+}
+
+/// A reference [`EntitySource`] for SCIM (RFC 7643 and RFC 7644) users and
+/// groups, which are pushed to it resource by resource or in bulk requests.
+///
+/// Entity ids are the SCIM `id`s of resources, which must be unique across
+/// users and groups. Every update is applied atomically: if the resulting
+/// entities are invalid (e.g., do not conform to the schema), the update is
+/// rejected and the entities are unchanged.
+///
+/// ```
+/// # use cedar_policy::{EntitySource, EntityUid, ScimEntitySource, ScimMapping};
+/// # use std::str::FromStr;
+/// let mapping = ScimMapping {
+///     user_type: "User".to_string(),
+///     group_type: "Group".to_string(),
+///     user_attributes: [("userName".to_string(), "/userName".to_string())].into(),
+///     group_attributes: Default::default(),
+/// };
+/// let mut source = ScimEntitySource::new(mapping, None);
+/// source.put_user(serde_json::json!({ "id": "2819c223", "userName": "bjensen" })).unwrap();
+/// source.put_group(serde_json::json!({
+///     "id": "e9e30dba",
+///     "displayName": "Tour Guides",
+///     "members": [{ "value": "2819c223", "type": "User" }],
+/// })).unwrap();
+///
+/// let user = EntityUid::from_str(r#"User::"2819c223""#).unwrap();
+/// let group = EntityUid::from_str(r#"Group::"e9e30dba""#).unwrap();
+/// assert!(source.entities().is_ancestor_of(&group, &user));
+///
+/// source.delete_group("e9e30dba").unwrap();
+/// assert!(!source.entities().is_ancestor_of(&group, &user));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScimEntitySource {
+    mapping: ScimMapping,
+    schema: Option<Schema>,
+    users: HashMap<String, Value>,
+    groups: HashMap<String, Value>,
+    entities: Entities,
+    generation: u64,
+}
+
+impl EntitySource for ScimEntitySource {
+    fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// The kind of a SCIM resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceKind {
+    User,
+    Group,
+}
+
+impl ScimEntitySource {
+    /// A source with no users or groups, whose entities are validated
+    /// against `schema`, if given
+    pub fn new(mapping: ScimMapping, schema: Option<Schema>) -> Self {
+        Self {
+            mapping,
+            schema,
+            users: HashMap::new(),
+            groups: HashMap::new(),
+            entities: Entities::empty(),
+            generation: 0,
+        }
+    }
+
+    /// Create or replace a user from its SCIM resource
+    pub fn put_user(&mut self, user: Value) -> Result<(), ScimError> {
+        self.update(|users, _| put(users, None, user))
+    }
+
+    /// Create or replace a group from its SCIM resource
+    pub fn put_group(&mut self, group: Value) -> Result<(), ScimError> {
+        self.update(|_, groups| put(groups, None, group))
+    }
+
+    /// Delete the user with SCIM id `id`
+    pub fn delete_user(&mut self, id: &str) -> Result<(), ScimError> {
+        self.update(|users, _| delete(users, id))
+    }
+
+    /// Delete the group with SCIM id `id`
+    pub fn delete_group(&mut self, id: &str) -> Result<(), ScimError> {
+        self.update(|_, groups| delete(groups, id))
+    }
+
+    /// Apply the operations of a SCIM bulk request, i.e., an object with an
+    /// `Operations` array. `POST` and `PUT` operations create or replace
+    /// resources, and `DELETE` operations delete them. `PATCH` operations are
+    /// not supported; send the full resource with `PUT` instead. Either all
+    /// operations are applied, or none are.
+    pub fn apply_bulk(&mut self, request: &Value) -> Result<(), ScimError> {
+        let operations = request
+            .get("Operations")
+            .and_then(Value::as_array)
+            .ok_or(ScimError::InvalidBulkRequest)?;
+        self.update(|users, groups| {
+            for op in operations {
+                let method = op.get("method").and_then(Value::as_str).unwrap_or("");
+                let path = op.get("path").and_then(Value::as_str).unwrap_or("");
+                let (kind, id) = parse_path(path)?;
+                let resources = match kind {
+                    ResourceKind::User => &mut *users,
+                    ResourceKind::Group => &mut *groups,
+                };
+                match (method.to_ascii_uppercase().as_str(), id) {
+                    ("POST", None) | ("PUT", Some(_)) => {
+                        let data = op.get("data").cloned().unwrap_or(Value::Null);
+                        put(resources, id, data)?;
+                    }
+                    ("DELETE", Some(id)) => delete(resources, id)?,
+                    _ => {
+                        return Err(ScimError::UnsupportedOperation {
+                            method: method.to_string(),
+                            path: path.to_string(),
+                        })
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Apply `f` to copies of the users and groups, and keep the result only
+    /// if it succeeds and gives valid entities
+    fn update(
+        &mut self,
+        f: impl FnOnce(
+            &mut HashMap<String, Value>,
+            &mut HashMap<String, Value>,
+        ) -> Result<(), ScimError>,
+    ) -> Result<(), ScimError> {
+        let mut users = self.users.clone();
+        let mut groups = self.groups.clone();
+        f(&mut users, &mut groups)?;
+        self.entities = self.build_entities(&users, &groups)?;
+        self.users = users;
+        self.groups = groups;
+        self.generation += 1;
+        Ok(())
+    }
+
+    fn build_entities(
+        &self,
+        users: &HashMap<String, Value>,
+        groups: &HashMap<String, Value>,
+    ) -> Result<Entities, ScimError> {
+        let mut parents: HashMap<&str, Vec<Value>> = HashMap::new();
+        for (group_id, group) in groups {
+            let members = group.get("members").and_then(Value::as_array);
+            for member in members.into_iter().flatten() {
+                if let Some(member_id) = member.get("value").and_then(Value::as_str) {
+                    parents
+                        .entry(member_id)
+                        .or_default()
+                        .push(json!({ "type": self.mapping.group_type, "id": group_id }));
+                }
+            }
+        }
+        let entity =
+            |ty: &str, id: &str, resource: &Value, attributes: &BTreeMap<String, String>| {
+                let attrs: Map<String, Value> = attributes
+                    .iter()
+                    .filter_map(|(attr, pointer)| {
+                        Some((attr.clone(), resource.pointer(pointer)?.clone()))
+                    })
+                    .collect();
+                json!({
+                    "uid": { "type": ty, "id": id },
+                    "attrs": attrs,
+                    "parents": parents.get(id).cloned().unwrap_or_default(),
+                })
+            };
+        let entities: Vec<Value> = users
+            .iter()
+            .map(|(id, user)| {
+                entity(
+                    &self.mapping.user_type,
+                    id,
+                    user,
+                    &self.mapping.user_attributes,
+                )
+            })
+            .chain(groups.iter().map(|(id, group)| {
+                entity(
+                    &self.mapping.group_type,
+                    id,
+                    group,
+                    &self.mapping.group_attributes,
+                )
+            }))
+            .collect();
+        Entities::from_json_value(Value::Array(entities), self.schema.as_ref())
+            .map_err(ScimError::from)
+    }
+}
+
+/// Create or replace `resource`, whose id is `id` if given, and otherwise its
+/// `id` attribute
+fn put(
+    resources: &mut HashMap<String, Value>,
+    id: Option<&str>,
+    resource: Value,
+) -> Result<(), ScimError> {
+    let id = match id {
+        Some(id) => id.to_string(),
+        None => resource
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or(ScimError::MissingId)?
+            .to_string(),
+    };
+    resources.insert(id, resource);
+    Ok(())
+}
+
+fn delete(resources: &mut HashMap<String, Value>, id: &str) -> Result<(), ScimError> {
+    resources
+        .remove(id)
+        .map(|_| ())
+        .ok_or_else(|| ScimError::UnknownResource { id: id.to_string() })
+}
+
+/// Parse the path of a bulk operation, e.g., `/Users` or `/Groups/e9e30dba`
+fn parse_path(path: &str) -> Result<(ResourceKind, Option<&str>), ScimError> {
+    let invalid = || ScimError::InvalidPath {
+        path: path.to_string(),
+    };
+    let mut segments = path.strip_prefix('/').ok_or_else(invalid)?.splitn(2, '/');
+    let kind = match segments.next() {
+        Some("Users") => ResourceKind::User,
+        Some("Groups") => ResourceKind::Group,
+        _ => return Err(invalid()),
+    };
+    match segments.next() {
+        None => Ok((kind, None)),
+        Some(id) if !id.is_empty() && !id.contains('/') => Ok((kind, Some(id))),
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// Errors when updating a [`ScimEntitySource`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum ScimError {
+    /// A resource has no `id`
+    #[error("SCIM resource has no `id`")]
+    MissingId,
+    /// A resource to delete does not exist
+    #[error("SCIM resource `{id}` does not exist")]
+    UnknownResource {
+        /// The id of the resource
+        id: String,
+    },
+    /// A bulk request has no `Operations` array
+    #[error("SCIM bulk request has no `Operations` array")]
+    InvalidBulkRequest,
+    /// The path of a bulk operation is not a user or group endpoint
+    #[error("invalid SCIM bulk operation path `{path}`")]
+    #[diagnostic(help("expected `/Users`, `/Users/<id>`, `/Groups`, or `/Groups/<id>`"))]
+    InvalidPath {
+        /// The path
+        path: String,
+    },
+    /// The method of a bulk operation is not supported for its path
+    #[error("unsupported SCIM bulk operation `{method} {path}`")]
+    #[diagnostic(help(
+        "use `POST` to create resources, `PUT` to replace them, and `DELETE` to delete them"
+    ))]
+    UnsupportedOperation {
+        /// The method
+        method: String,
+        /// The path
+        path: String,
+    },
+    /// The updated entities are not valid, or do not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] EntitiesError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EntityUid;
+    use cool_asserts::assert_matches;
+    use std::str::FromStr;
+
+    fn source() -> ScimEntitySource {
+        let (schema, _) = Schema::from_cedarschema_str(
+            "entity Group in Group = { name: String }; entity User in Group = { userName: String };",
+        )
+        .unwrap();
+        let mapping = ScimMapping::deserialize(json!({
+            "userType": "User",
+            "groupType": "Group",
+            "userAttributes": { "userName": "/userName" },
+            "groupAttributes": { "name": "/displayName" },
+        }))
+        .unwrap();
+        ScimEntitySource::new(mapping, Some(schema))
+    }
+
+    fn uid(s: &str) -> EntityUid {
+        EntityUid::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn bulk() {
+        let mut source = source();
+        source
+            .apply_bulk(&json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:BulkRequest"],
+                "Operations": [
+                    { "method": "POST", "path": "/Users", "data": { "id": "u1", "userName": "bjensen" } },
+                    { "method": "PUT", "path": "/Groups/g1", "data": { "displayName": "Guides", "members": [{ "value": "u1" }] } },
+                    { "method": "POST", "path": "/Groups", "data": { "id": "g2", "displayName": "Staff", "members": [{ "value": "g1", "type": "Group" }] } },
+                ],
+            }))
+            .unwrap();
+        assert_eq!(source.generation(), 1);
+        assert!(source
+            .entities()
+            .is_ancestor_of(&uid(r#"Group::"g2""#), &uid(r#"User::"u1""#)));
+
+        source
+            .apply_bulk(&json!({
+                "Operations": [{ "method": "DELETE", "path": "/Groups/g1" }],
+            }))
+            .unwrap();
+        assert_eq!(source.generation(), 2);
+        assert!(!source
+            .entities()
+            .is_ancestor_of(&uid(r#"Group::"g2""#), &uid(r#"User::"u1""#)));
+    }
+
+    #[test]
+    fn atomic_updates() {
+        let mut source = source();
+        source
+            .put_user(json!({ "id": "u1", "userName": "bjensen" }))
+            .unwrap();
+
+        // the second operation is invalid, so the first is not applied either
+        assert_matches!(
+            source.apply_bulk(&json!({
+                "Operations": [
+                    { "method": "DELETE", "path": "/Users/u1" },
+                    { "method": "PATCH", "path": "/Users/u1", "data": {} },
+                ],
+            })),
+            Err(ScimError::UnsupportedOperation { .. })
+        );
+        // `userName` is required by the schema
+        assert_matches!(
+            source.put_user(json!({ "id": "u2" })),
+            Err(ScimError::Entities(_))
+        );
+        assert_matches!(
+            source.delete_group("g1"),
+            Err(ScimError::UnknownResource { id }) if id == "g1"
+        );
+        assert_eq!(source.generation(), 1);
+        assert!(source.entities().get(&uid(r#"User::"u1""#)).is_some());
+        assert!(source.entities().get(&uid(r#"User::"u2""#)).is_none());
+    }
+}
@@ -20,19 +20,25 @@ use crate::{EntityUid, PolicyId};
 pub use cedar_policy_core::ast::{
     expression_construction_errors, restricted_expr_errors, ContainsUnknown,
     ExpressionConstructionError, PartialValueToValueError, RestrictedExpressionError,
+    SuspiciousEidError as SuspiciousEntityIdError,
 };
+#[cfg(feature = "codegen")]
+pub use cedar_policy_core::codegen::CodegenError;
 #[cfg(feature = "entity-manifest")]
 use cedar_policy_core::entities::err::EntitiesError;
 pub use cedar_policy_core::evaluator::{evaluation_errors, EvaluationError};
 pub use cedar_policy_core::extensions::{
     extension_function_lookup_errors, ExtensionFunctionLookupError,
 };
+pub use cedar_policy_core::stable_ast::StableAstError;
 use cedar_policy_core::{ast, authorizer, est};
 pub use cedar_policy_validator::cedar_schema::{schema_warnings, SchemaWarning};
+pub use cedar_policy_validator::entity_generator::EntityGenerationError;
 #[cfg(feature = "entity-manifest")]
 use cedar_policy_validator::entity_manifest::{
     self, FailedAnalysisError, PartialExpressionError, PartialRequestError,
 };
+pub use cedar_policy_validator::typescript::TypeScriptGenerationError;
 pub use cedar_policy_validator::{schema_errors, SchemaError};
 use miette::Diagnostic;
 use ref_cast::RefCast;
@@ -361,10 +367,22 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     UnexpectedType(#[from] validation_errors::UnexpectedType),
+    /// The typechecker expected to see a subtype of one of the types in
+    /// `expected`, but saw a union type where only some arms are expected.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidUnionArms(#[from] validation_errors::InvalidUnionArms),
     /// The typechecker could not compute a least upper bound for `types`.
     #[error(transparent)]
     #[diagnostic(transparent)]
     IncompatibleTypes(#[from] validation_errors::IncompatibleTypes),
+    /// The typechecker did not compute a least upper bound for types which
+    /// exceed the maximum type size configured with
+    /// [`crate::Validator::with_max_record_width`] or
+    /// [`crate::Validator::with_max_set_depth`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TypeTooLarge(#[from] validation_errors::TypeTooLarge),
     /// The typechecker detected an access to a record or entity attribute
     /// that it could not statically guarantee would be present.
     #[error(transparent)]
@@ -401,6 +419,27 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     HierarchyNotRespected(#[from] validation_errors::HierarchyNotRespected),
+    /// The policy uses a language feature which is forbidden by the
+    /// [`crate::FeaturePolicy`] of the validator
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ForbiddenFeature(#[from] validation_errors::ForbiddenFeature),
+    /// The values for the slots of a link provide no value for a slot of its
+    /// template. See [`crate::Validator::validate_link_bindings`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingSlotBinding(#[from] validation_errors::MissingSlotBinding),
+    /// The values for the slots of a link provide a value for a slot its
+    /// template doesn't use. See [`crate::Validator::validate_link_bindings`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnusedSlotBinding(#[from] validation_errors::UnusedSlotBinding),
+    /// The values for the slots of a link provide a value of an entity type
+    /// for which its template never applies. See
+    /// [`crate::Validator::validate_link_bindings`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnexpectedSlotType(#[from] validation_errors::UnexpectedSlotType),
 }
 
 impl ValidationError {
@@ -411,7 +450,9 @@ impl ValidationError {
             Self::UnrecognizedActionId(e) => e.policy_id(),
             Self::InvalidActionApplication(e) => e.policy_id(),
             Self::UnexpectedType(e) => e.policy_id(),
+            Self::InvalidUnionArms(e) => e.policy_id(),
             Self::IncompatibleTypes(e) => e.policy_id(),
+            Self::TypeTooLarge(e) => e.policy_id(),
             Self::UnsafeAttributeAccess(e) => e.policy_id(),
             Self::UnsafeOptionalAttributeAccess(e) => e.policy_id(),
             Self::UndefinedFunction(e) => e.policy_id(),
@@ -420,6 +461,36 @@ impl ValidationError {
             Self::EmptySetForbidden(e) => e.policy_id(),
             Self::NonLitExtConstructor(e) => e.policy_id(),
             Self::HierarchyNotRespected(e) => e.policy_id(),
+            Self::ForbiddenFeature(e) => e.policy_id(),
+            Self::MissingSlotBinding(e) => e.policy_id(),
+            Self::UnusedSlotBinding(e) => e.policy_id(),
+            Self::UnexpectedSlotType(e) => e.policy_id(),
+        }
+    }
+
+    /// The kind of this error, i.e., the name of its variant, e.g.,
+    /// `"UnrecognizedEntityType"`. This is useful for summarizing errors.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::UnrecognizedEntityType(_) => "UnrecognizedEntityType",
+            Self::UnrecognizedActionId(_) => "UnrecognizedActionId",
+            Self::InvalidActionApplication(_) => "InvalidActionApplication",
+            Self::UnexpectedType(_) => "UnexpectedType",
+            Self::InvalidUnionArms(_) => "InvalidUnionArms",
+            Self::IncompatibleTypes(_) => "IncompatibleTypes",
+            Self::TypeTooLarge(_) => "TypeTooLarge",
+            Self::UnsafeAttributeAccess(_) => "UnsafeAttributeAccess",
+            Self::UnsafeOptionalAttributeAccess(_) => "UnsafeOptionalAttributeAccess",
+            Self::UndefinedFunction(_) => "UndefinedFunction",
+            Self::WrongNumberArguments(_) => "WrongNumberArguments",
+            Self::FunctionArgumentValidation(_) => "FunctionArgumentValidation",
+            Self::EmptySetForbidden(_) => "EmptySetForbidden",
+            Self::NonLitExtConstructor(_) => "NonLitExtConstructor",
+            Self::HierarchyNotRespected(_) => "HierarchyNotRespected",
+            Self::ForbiddenFeature(_) => "ForbiddenFeature",
+            Self::MissingSlotBinding(_) => "MissingSlotBinding",
+            Self::UnusedSlotBinding(_) => "UnusedSlotBinding",
+            Self::UnexpectedSlotType(_) => "UnexpectedSlotType",
         }
     }
 }
@@ -440,9 +511,15 @@ impl From<cedar_policy_validator::ValidationError> for ValidationError {
             cedar_policy_validator::ValidationError::UnexpectedType(e) => {
                 Self::UnexpectedType(e.into())
             }
+            cedar_policy_validator::ValidationError::InvalidUnionArms(e) => {
+                Self::InvalidUnionArms(e.into())
+            }
             cedar_policy_validator::ValidationError::IncompatibleTypes(e) => {
                 Self::IncompatibleTypes(e.into())
             }
+            cedar_policy_validator::ValidationError::TypeTooLarge(e) => {
+                Self::TypeTooLarge(e.into())
+            }
             cedar_policy_validator::ValidationError::UnsafeAttributeAccess(e) => {
                 Self::UnsafeAttributeAccess(e.into())
             }
@@ -467,6 +544,18 @@ impl From<cedar_policy_validator::ValidationError> for ValidationError {
             cedar_policy_validator::ValidationError::HierarchyNotRespected(e) => {
                 Self::HierarchyNotRespected(e.into())
             }
+            cedar_policy_validator::ValidationError::ForbiddenFeature(e) => {
+                Self::ForbiddenFeature(e.into())
+            }
+            cedar_policy_validator::ValidationError::MissingSlotBinding(e) => {
+                Self::MissingSlotBinding(e.into())
+            }
+            cedar_policy_validator::ValidationError::UnusedSlotBinding(e) => {
+                Self::UnusedSlotBinding(e.into())
+            }
+            cedar_policy_validator::ValidationError::UnexpectedSlotType(e) => {
+                Self::UnexpectedSlotType(e.into())
+            }
         }
     }
 }
@@ -505,18 +594,119 @@ pub enum ValidationWarning {
     #[diagnostic(transparent)]
     #[error(transparent)]
     ImpossiblePolicy(#[from] validation_warnings::ImpossiblePolicy),
+    /// In permissive validation, the scope constraints of a policy match no
+    /// request environment of the schema. Strict validation reports a
+    /// [`ValidationError::InvalidActionApplication`] error instead.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    NotApplicablePolicy(#[from] validation_warnings::NotApplicablePolicy),
+    /// An `==` comparison between entities is always false, because the
+    /// entities' types can never be equal according to the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    IncompatibleEntityEquality(#[from] validation_warnings::IncompatibleEntityEquality),
+    /// A string attribute is compared to a string which looks like an entity
+    /// uid, suggesting that the attribute should have an entity type.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    StringTypedEntityReference(#[from] validation_warnings::StringTypedEntityReference),
+    /// A `like` pattern has no wildcards, so it is equivalent to `==`.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    LikeWithoutWildcard(#[from] validation_warnings::LikeWithoutWildcard),
+    /// A policy reads the current time from the context without checking
+    /// that it is present.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnguardedCurrentTime(#[from] validation_warnings::UnguardedCurrentTime),
+    /// A policy contains arithmetic on constants which always overflows.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    GuaranteedOverflow(#[from] validation_warnings::GuaranteedOverflow),
+    /// A policy accesses an entity attribute which is deprecated in the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    DeprecatedAttribute(#[from] validation_warnings::DeprecatedAttribute),
+    /// A policy references an action which is deprecated in the schema.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    DeprecatedAction(#[from] validation_warnings::DeprecatedAction),
+    /// Evaluating an expression in a policy with an entity of an entity
+    /// snapshot is an error.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    EntityDataError(#[from] validation_warnings::EntityDataError),
+    /// An entity type is declared in the schema but not referenced by any policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnusedEntityType(#[from] validation_warnings::UnusedEntityType),
+    /// An entity attribute is declared in the schema but not referenced by any policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnusedAttribute(#[from] validation_warnings::UnusedAttribute),
+    /// An action is declared in the schema but not referenced by any policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnusedAction(#[from] validation_warnings::UnusedAction),
+    /// A forbid policy has the same scope and condition as a permit policy,
+    /// up to the order of commutative operands, set elements, and actions, so
+    /// the permit policy never allows any request. The warning is reported
+    /// for the forbid policy, and its related diagnostic points at the permit
+    /// policy.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    IdenticalPermitAndForbid(#[from] validation_warnings::IdenticalPermitAndForbid),
 }
 
 impl ValidationWarning {
     /// Extract the policy id of the policy where the validator found the issue.
-    pub fn policy_id(&self) -> &PolicyId {
+    /// Returns `None` for warnings about the schema, which are not associated
+    /// with a policy.
+    pub fn policy_id(&self) -> Option<&PolicyId> {
+        match self {
+            Self::MixedScriptString(w) => Some(w.policy_id()),
+            Self::BidiCharsInString(w) => Some(w.policy_id()),
+            Self::BidiCharsInIdentifier(w) => Some(w.policy_id()),
+            Self::MixedScriptIdentifier(w) => Some(w.policy_id()),
+            Self::ConfusableIdentifier(w) => Some(w.policy_id()),
+            Self::ImpossiblePolicy(w) => Some(w.policy_id()),
+            Self::NotApplicablePolicy(w) => Some(w.policy_id()),
+            Self::IncompatibleEntityEquality(w) => Some(w.policy_id()),
+            Self::StringTypedEntityReference(w) => Some(w.policy_id()),
+            Self::LikeWithoutWildcard(w) => Some(w.policy_id()),
+            Self::UnguardedCurrentTime(w) => Some(w.policy_id()),
+            Self::GuaranteedOverflow(w) => Some(w.policy_id()),
+            Self::DeprecatedAttribute(w) => Some(w.policy_id()),
+            Self::DeprecatedAction(w) => Some(w.policy_id()),
+            Self::EntityDataError(w) => Some(w.policy_id()),
+            Self::IdenticalPermitAndForbid(w) => Some(w.policy_id()),
+            Self::UnusedEntityType(_) | Self::UnusedAttribute(_) | Self::UnusedAction(_) => None,
+        }
+    }
+
+    /// The kind of this warning, i.e., the name of its variant, e.g.,
+    /// `"ImpossiblePolicy"`. This is useful for summarizing warnings.
+    pub fn kind(&self) -> &'static str {
         match self {
-            Self::MixedScriptString(w) => w.policy_id(),
-            Self::BidiCharsInString(w) => w.policy_id(),
-            Self::BidiCharsInIdentifier(w) => w.policy_id(),
-            Self::MixedScriptIdentifier(w) => w.policy_id(),
-            Self::ConfusableIdentifier(w) => w.policy_id(),
-            Self::ImpossiblePolicy(w) => w.policy_id(),
+            Self::MixedScriptString(_) => "MixedScriptString",
+            Self::BidiCharsInString(_) => "BidiCharsInString",
+            Self::BidiCharsInIdentifier(_) => "BidiCharsInIdentifier",
+            Self::MixedScriptIdentifier(_) => "MixedScriptIdentifier",
+            Self::ConfusableIdentifier(_) => "ConfusableIdentifier",
+            Self::ImpossiblePolicy(_) => "ImpossiblePolicy",
+            Self::NotApplicablePolicy(_) => "NotApplicablePolicy",
+            Self::IncompatibleEntityEquality(_) => "IncompatibleEntityEquality",
+            Self::StringTypedEntityReference(_) => "StringTypedEntityReference",
+            Self::LikeWithoutWildcard(_) => "LikeWithoutWildcard",
+            Self::UnguardedCurrentTime(_) => "UnguardedCurrentTime",
+            Self::GuaranteedOverflow(_) => "GuaranteedOverflow",
+            Self::DeprecatedAttribute(_) => "DeprecatedAttribute",
+            Self::DeprecatedAction(_) => "DeprecatedAction",
+            Self::EntityDataError(_) => "EntityDataError",
+            Self::UnusedEntityType(_) => "UnusedEntityType",
+            Self::UnusedAttribute(_) => "UnusedAttribute",
+            Self::UnusedAction(_) => "UnusedAction",
+            Self::IdenticalPermitAndForbid(_) => "IdenticalPermitAndForbid",
         }
     }
 }
@@ -543,6 +733,45 @@ impl From<cedar_policy_validator::ValidationWarning> for ValidationWarning {
             cedar_policy_validator::ValidationWarning::ImpossiblePolicy(w) => {
                 Self::ImpossiblePolicy(w.into())
             }
+            cedar_policy_validator::ValidationWarning::NotApplicablePolicy(w) => {
+                Self::NotApplicablePolicy(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::IncompatibleEntityEquality(w) => {
+                Self::IncompatibleEntityEquality(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::StringTypedEntityReference(w) => {
+                Self::StringTypedEntityReference(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::LikeWithoutWildcard(w) => {
+                Self::LikeWithoutWildcard(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::UnguardedCurrentTime(w) => {
+                Self::UnguardedCurrentTime(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::GuaranteedOverflow(w) => {
+                Self::GuaranteedOverflow(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::DeprecatedAttribute(w) => {
+                Self::DeprecatedAttribute(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::DeprecatedAction(w) => {
+                Self::DeprecatedAction(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::EntityDataError(w) => {
+                Self::EntityDataError(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::UnusedEntityType(w) => {
+                Self::UnusedEntityType(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::UnusedAttribute(w) => {
+                Self::UnusedAttribute(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::UnusedAction(w) => {
+                Self::UnusedAction(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::IdenticalPermitAndForbid(w) => {
+                Self::IdenticalPermitAndForbid(w.into())
+            }
         }
     }
 }
@@ -762,6 +991,10 @@ pub enum PolicySetError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     JsonPolicySet(#[from] policy_set_errors::JsonPolicySetError),
+    /// Error when loading a policy set from its stable AST serialization
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    StableAst(#[from] StableAstError),
 }
 
 #[doc(hidden)]
@@ -832,6 +1065,20 @@ pub struct ParseError {
     inner: cedar_policy_core::parser::err::ParseError,
 }
 
+/// Errors that can occur when parsing an [`EntityUid`] with
+/// [`EntityUid::from_str_strict`]
+#[derive(Debug, Diagnostic, Error)]
+pub enum StrictEntityUidParseError {
+    /// The input was not a valid (normalized) entity uid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] ParseErrors),
+    /// The entity id contains a suspicious character
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    SuspiciousEntityId(#[from] SuspiciousEntityIdError),
+}
+
 /// Errors that can happen when getting the JSON representation of a policy
 #[derive(Debug, Diagnostic, Error)]
 pub enum PolicyToJsonError {
@@ -911,6 +1158,10 @@ pub enum ContextJsonError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     MissingAction(#[from] context_json_errors::MissingActionError),
+    /// The context exceeds the [`crate::RequestLimits`] it was parsed with
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    LimitExceeded(#[from] crate::RequestLimitError),
 }
 
 impl ContextJsonError {
@@ -1017,6 +1268,11 @@ pub enum RequestValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     TypeOfContext(#[from] request_validation_errors::TypeOfContextError),
+    /// The context exceeds the [`crate::RequestLimits`] the request was
+    /// constructed with
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    LimitExceeded(#[from] crate::RequestLimitError),
 }
 
 #[doc(hidden)]
@@ -1220,3 +1476,157 @@ impl From<entity_manifest::EntityManifestError> for EntityManifestError {
         }
     }
 }
+
+/// An error returned by [`crate::iam_import::import_iam_policy()`]
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+#[cfg(feature = "iam-import")]
+pub enum IamImportError {
+    /// The input is not a well-formed IAM policy document
+    #[error("invalid IAM policy document: {0}")]
+    InvalidDocument(#[from] serde_json::Error),
+}
+
+/// An error returned by the conversions in [`crate::avp`]
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+#[cfg(feature = "avp")]
+pub enum AvpError {
+    /// The input does not have the expected AVP shape
+    #[error("invalid AVP input: expected {expected}")]
+    InvalidShape {
+        /// Description of the expected shape
+        expected: &'static str,
+    },
+    /// The input is not well-formed JSON of the expected AVP shape
+    #[error("invalid AVP input: {0}")]
+    Json(#[source] serde_json::Error),
+    /// A policy or template statement failed to parse
+    #[error("failed to parse policy or template `{id}`")]
+    Parse {
+        /// Id of the policy or template
+        id: PolicyId,
+        /// The parse errors
+        #[source]
+        #[diagnostic_source]
+        errors: ParseErrors,
+    },
+    /// The policies and templates do not form a valid policy set
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    PolicySet(#[from] PolicySetError),
+    /// An entity uid is not valid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    EntityUid(#[from] entities_json_errors::JsonDeserializationError),
+    /// The entities are not valid, or do not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] entities_errors::EntitiesError),
+    /// The context is not valid, or does not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Context(#[from] ContextJsonError),
+    /// The schema is not valid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Schema(#[from] SchemaError),
+}
+
+#[cfg(feature = "avp")]
+impl AvpError {
+    pub(crate) fn invalid_shape(expected: &'static str) -> Self {
+        Self::InvalidShape { expected }
+    }
+}
+
+/// An error returned by [`crate::envoy::EnvoyMapping`] when converting a
+/// `CheckRequest`
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+#[cfg(feature = "envoy")]
+pub enum EnvoyError {
+    /// An attribute holding an entity id is missing from the `CheckRequest`
+    #[error("attribute `{attribute}` is missing from the `CheckRequest`")]
+    MissingAttribute {
+        /// The attribute
+        attribute: String,
+    },
+    /// An attribute holding an entity id is not a string
+    #[error("attribute `{attribute}` of the `CheckRequest` is not a string")]
+    InvalidAttribute {
+        /// The attribute
+        attribute: String,
+    },
+    /// An entity uid is not valid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    EntityUid(#[from] entities_json_errors::JsonDeserializationError),
+    /// The context is not valid, or does not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Context(#[from] ContextJsonError),
+    /// The request does not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Request(#[from] RequestValidationError),
+}
+
+#[cfg(feature = "envoy")]
+impl EnvoyError {
+    pub(crate) fn missing_attribute(attribute: impl std::fmt::Display) -> Self {
+        Self::MissingAttribute {
+            attribute: attribute.to_string(),
+        }
+    }
+
+    pub(crate) fn invalid_attribute(attribute: impl std::fmt::Display) -> Self {
+        Self::InvalidAttribute {
+            attribute: attribute.to_string(),
+        }
+    }
+}
+
+/// An error returned by [`crate::codegen::compile_to_rust()`]
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+#[cfg(feature = "codegen")]
+pub enum CompileToRustError {
+    /// The policies failed to validate in strict mode
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Validation(#[from] ValidationResult),
+    /// The policies use a construct which cannot be compiled
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Codegen(#[from] CodegenError),
+}
+
+/// An error returned by [`crate::codegen::generate_types()`]
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+#[cfg(feature = "codegen")]
+pub enum GenerateTypesError {
+    /// Two entity types, actions, or attributes map to the same Rust name
+    #[error("{first} and {second} both map to the Rust name `{name}`")]
+    #[diagnostic(help("rename one of them in the schema"))]
+    NameCollision {
+        /// The Rust name
+        name: String,
+        /// What the name was first generated for
+        first: String,
+        /// What the name was generated for again
+        second: String,
+    },
+}
+
+#[cfg(feature = "codegen")]
+impl GenerateTypesError {
+    pub(crate) fn name_collision(name: &str, first: &str, second: &str) -> Self {
+        Self::NameCollision {
+            name: name.to_string(),
+            first: first.to_string(),
+            second: second.to_string(),
+        }
+    }
+}
@@ -60,7 +60,9 @@ wrap_core_error!(UnrecognizedEntityType);
 wrap_core_error!(UnrecognizedActionId);
 wrap_core_error!(InvalidActionApplication);
 wrap_core_error!(UnexpectedType);
+wrap_core_error!(InvalidUnionArms);
 wrap_core_error!(IncompatibleTypes);
+wrap_core_error!(TypeTooLarge);
 wrap_core_error!(UnsafeAttributeAccess);
 wrap_core_error!(UnsafeOptionalAttributeAccess);
 wrap_core_error!(UndefinedFunction);
@@ -69,3 +71,7 @@ wrap_core_error!(FunctionArgumentValidation);
 wrap_core_error!(HierarchyNotRespected);
 wrap_core_error!(EmptySetForbidden);
 wrap_core_error!(NonLitExtConstructor);
+wrap_core_error!(ForbiddenFeature);
+wrap_core_error!(MissingSlotBinding);
+wrap_core_error!(UnusedSlotBinding);
+wrap_core_error!(UnexpectedSlotType);
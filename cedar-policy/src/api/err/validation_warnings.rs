@@ -62,3 +62,44 @@ wrap_core_warning!(BidiCharsInIdentifier);
 wrap_core_warning!(MixedScriptIdentifier);
 wrap_core_warning!(ConfusableIdentifier);
 wrap_core_warning!(ImpossiblePolicy);
+wrap_core_warning!(NotApplicablePolicy);
+wrap_core_warning!(IncompatibleEntityEquality);
+wrap_core_warning!(StringTypedEntityReference);
+wrap_core_warning!(LikeWithoutWildcard);
+wrap_core_warning!(UnguardedCurrentTime);
+wrap_core_warning!(GuaranteedOverflow);
+wrap_core_warning!(DeprecatedAttribute);
+wrap_core_warning!(DeprecatedAction);
+wrap_core_warning!(EntityDataError);
+wrap_core_warning!(IdenticalPermitAndForbid);
+
+impl IdenticalPermitAndForbid {
+    /// Access the [`PolicyId`] of the permit policy, which the forbid policy
+    /// overrides
+    pub fn permit_id(&self) -> &PolicyId {
+        PolicyId::ref_cast(&self.0.permit_id)
+    }
+}
+
+// Like `wrap_core_warning`, but for warnings about the schema, which are not
+// associated with a policy.
+macro_rules! wrap_core_schema_warning {
+    ($s:ident) => {
+        #[derive(Debug, Clone, Error, Diagnostic)]
+        #[error(transparent)]
+        #[diagnostic(transparent)]
+        #[doc=concat!("Structure containing details about a [`ValidationWarning::", stringify!($s), "`].")]
+        pub struct $s(cedar_policy_validator::validation_warnings::$s);
+
+        #[doc(hidden)]
+        impl From<cedar_policy_validator::validation_warnings::$s> for $s {
+            fn from(e: cedar_policy_validator::validation_warnings::$s) -> Self {
+                Self(e)
+            }
+        }
+    };
+}
+
+wrap_core_schema_warning!(UnusedEntityType);
+wrap_core_schema_warning!(UnusedAttribute);
+wrap_core_schema_warning!(UnusedAction);
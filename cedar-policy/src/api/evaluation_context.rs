@@ -0,0 +1,91 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`EvaluationContext`], the environmental inputs of an
+//! evaluation, which can be recorded and injected again to replay it.
+
+use super::{ast, Context, ContextCreationError, RestrictedExpression};
+use std::time::SystemTime;
+
+/// The environmental inputs of an evaluation, i.e., the inputs which do not
+/// come from the request, the entities, or the policies.
+///
+/// Authorization is a deterministic function of the request, the entities,
+/// and the policies: authorizing the same request against the same entities
+/// and policies gives the same decision, reasons, and errors (in the same
+/// order). Cedar itself never reads the clock or any other source of
+/// randomness. The only environmental input is the current time, which
+/// [`EvaluationContext::bind`] adds to the context of requests. Recording the
+/// [`EvaluationContext`] of a request along with the request is therefore
+/// enough to replay its decision exactly, e.g., during an incident
+/// investigation.
+///
+/// ```
+/// # use cedar_policy::{Context, EvaluationContext};
+/// // when authorizing the request
+/// let env = EvaluationContext::from_system_clock();
+/// let context = env.bind(Context::empty()).unwrap();
+/// let recorded = env.unix_seconds();
+///
+/// // when replaying it
+/// let replayed = EvaluationContext::from_unix_seconds(recorded);
+/// assert_eq!(replayed, env);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EvaluationContext {
+    /// The current time, as a number of seconds since the Unix epoch
+    now: i64,
+}
+
+impl EvaluationContext {
+    /// The environment at the time of the call, reading the system clock once
+    pub fn from_system_clock() -> Self {
+        Self::at(SystemTime::now())
+    }
+
+    /// The environment at the time `now`, which is truncated to seconds
+    pub fn at(now: SystemTime) -> Self {
+        let now = match now.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => i64::try_from(since.as_secs()).unwrap_or(i64::MAX),
+            Err(before) => i64::try_from(before.duration().as_secs()).map_or(i64::MIN, |s| -s),
+        };
+        Self { now }
+    }
+
+    /// The environment at the time `now`, a number of seconds since the Unix
+    /// epoch (negative for earlier times), e.g., as recorded with
+    /// [`EvaluationContext::unix_seconds`]
+    pub fn from_unix_seconds(now: i64) -> Self {
+        Self { now }
+    }
+
+    /// The current time of this environment, as a number of seconds since
+    /// the Unix epoch
+    pub fn unix_seconds(&self) -> i64 {
+        self.now
+    }
+
+    /// Add the inputs of this environment to `context`, i.e., the current
+    /// time as the well-known attribute `now` (see
+    /// [`Context::with_current_time`]), returning an error if `context`
+    /// already has this attribute
+    pub fn bind(&self, context: Context) -> Result<Context, ContextCreationError> {
+        context.merge([(
+            ast::Context::CURRENT_TIME_ATTR.to_string(),
+            RestrictedExpression::new_long(self.now),
+        )])
+    }
+}
@@ -0,0 +1,204 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`evaluate_expression`], which evaluates a standalone
+//! expression outside of authorization, e.g., a rule mapping identity claims
+//! to entity attributes.
+
+use super::{
+    ast, Context, EntityUid, EvalResult, EvaluationError, Expression, RequestValidationError,
+    Schema, ValidationError,
+};
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::evaluator::Evaluator;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_validator::typecheck::Typechecker;
+use cedar_policy_validator::types::RequestEnv;
+use cedar_policy_validator::{TypecheckExpressionError, ValidationMode};
+use miette::Diagnostic;
+use thiserror::Error;
+
+/// The values which a standalone expression is evaluated with by
+/// [`evaluate_expression`]. The values are bound as the attributes of
+/// `context`; the other variables are not bound.
+///
+/// When the bindings are typed by a schema, i.e., constructed with
+/// [`ExpressionBindings::typed`], the context must conform to the context
+/// type of the given action, and the expression is typechecked in strict mode
+/// against this type before it is evaluated. Otherwise, the expression is
+/// evaluated without typechecking.
+#[derive(Debug, Clone)]
+pub struct ExpressionBindings<'a> {
+    context: Context,
+    schema: Option<(&'a Schema, EntityUid)>,
+}
+
+impl<'a> ExpressionBindings<'a> {
+    /// Bind the attributes of `context`, without typing them
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            schema: None,
+        }
+    }
+
+    /// Bind the attributes of `context`, typed by the context type of
+    /// `action` in `schema`
+    pub fn typed(context: Context, schema: &'a Schema, action: EntityUid) -> Self {
+        Self {
+            context,
+            schema: Some((schema, action)),
+        }
+    }
+}
+
+/// Evaluate `expr` with `bindings`, independently of any request, entities,
+/// or policies.
+///
+/// ```
+/// # use cedar_policy::{evaluate_expression, Context, EntityUid, EvalResult, ExpressionBindings, Expression, Schema};
+/// # use std::str::FromStr;
+/// let (schema, _) = Schema::from_cedarschema_str(r#"
+///     entity User;
+///     action mapClaims appliesTo { principal: User, resource: User, context: { email: String, groups: Set<String> } };
+/// "#).unwrap();
+/// let action = EntityUid::from_str(r#"Action::"mapClaims""#).unwrap();
+/// let claims = Context::from_json_value(
+///     serde_json::json!({ "email": "alice@example.com", "groups": ["admins"] }),
+///     Some((&schema, &action)),
+/// ).unwrap();
+/// let bindings = ExpressionBindings::typed(claims, &schema, action);
+///
+/// let rule = Expression::from_str(r#"context.groups.contains("admins") && context.email like "*@example.com""#).unwrap();
+/// assert_eq!(evaluate_expression(&rule, &bindings).unwrap(), EvalResult::Bool(true));
+///
+/// // not well typed
+/// let rule = Expression::from_str(r#"context.email.contains("admins")"#).unwrap();
+/// assert!(evaluate_expression(&rule, &bindings).is_err());
+/// ```
+pub fn evaluate_expression(
+    expr: &Expression,
+    bindings: &ExpressionBindings<'_>,
+) -> Result<EvalResult, ExpressionEvaluationError> {
+    if let Some(var) = expr.0.subexpressions().find_map(|e| match e.expr_kind() {
+        ast::ExprKind::Var(var) if *var != ast::Var::Context => Some(*var),
+        _ => None,
+    }) {
+        return Err(ExpressionEvaluationError::UnboundVariable {
+            var: var.to_string(),
+        });
+    }
+    let extensions = Extensions::all_available();
+    let unknown = || ast::EntityUIDEntry::Unknown { loc: None };
+    let request = match &bindings.schema {
+        None => ast::Request::new_with_unknowns(
+            unknown(),
+            unknown(),
+            unknown(),
+            Some(bindings.context.0.clone()),
+            None::<&ast::RequestSchemaAllPass>,
+            extensions,
+        )
+        .map_err(|e| -> ExpressionEvaluationError { match e.0 {} })?,
+        Some((schema, action)) => {
+            let request = ast::Request::new_with_unknowns(
+                unknown(),
+                ast::EntityUIDEntry::known(action.as_ref().clone(), None),
+                unknown(),
+                Some(bindings.context.0.clone()),
+                Some(&schema.0),
+                extensions,
+            )
+            .map_err(RequestValidationError::from)?;
+            typecheck(expr, schema, action)?;
+            request
+        }
+    };
+    let entities = Entities::new();
+    let eval = Evaluator::new(request, &entities, extensions);
+    Ok(EvalResult::from(
+        eval.interpret(&expr.0, &ast::SlotEnv::new())?,
+    ))
+}
+
+/// Typecheck `expr` against the context type of `action`, which the request
+/// validation already checked is declared in `schema`
+fn typecheck(
+    expr: &Expression,
+    schema: &Schema,
+    action: &EntityUid,
+) -> Result<(), ExpressionEvaluationError> {
+    let Some(action_id) = schema.0.get_action_id(action.as_ref()) else {
+        return Ok(());
+    };
+    // `principal` and `resource` are not bound, so their types do not matter
+    let unbound = action.as_ref().entity_type();
+    let env = RequestEnv::DeclaredAction {
+        principal: unbound,
+        action: action.as_ref(),
+        resource: unbound,
+        context: action_id.context_type(),
+        principal_slot: None,
+        resource_slot: None,
+    };
+    match Typechecker::new(
+        &schema.0,
+        ValidationMode::Strict,
+        ast::PolicyID::from_string("expression"),
+    )
+    .typecheck_expression(&expr.0, &env)
+    {
+        Ok(_) => Ok(()),
+        Err(TypecheckExpressionError::Type { errors }) => Err(ExpressionEvaluationError::Type {
+            errors: errors.into_iter().map(ValidationError::from).collect(),
+        }),
+        // the expression is already parsed, so the typechecker can only
+        // otherwise fail on deeply nested expressions
+        Err(_) => Err(ExpressionEvaluationError::RecursionLimit),
+    }
+}
+
+/// Errors returned by [`evaluate_expression`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum ExpressionEvaluationError {
+    /// The expression references a variable other than `context`
+    #[error("`{var}` is not bound when evaluating a standalone expression")]
+    #[diagnostic(help("only `context` is bound, to the values of the bindings"))]
+    UnboundVariable {
+        /// The variable
+        var: String,
+    },
+    /// The context does not conform to the context type of the action, or
+    /// the action is not declared in the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidBindings(#[from] RequestValidationError),
+    /// The expression is not well typed
+    #[error("expression is not well typed")]
+    Type {
+        /// The type errors
+        #[related]
+        errors: Vec<ValidationError>,
+    },
+    /// The expression is too deeply nested to typecheck
+    #[error("expression is too deeply nested to typecheck")]
+    RecursionLimit,
+    /// Evaluating the expression failed
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Evaluation(#[from] EvaluationError),
+}
@@ -0,0 +1,58 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`FeaturePolicy`], which restricts the language
+//! features policies may use.
+
+/// Language features which policies may not use, enforced by
+/// [`super::Validator::with_feature_policy`]. This allows enforcing a style
+/// guide which bans some features at validation time, with a
+/// [`super::ValidationError::ForbiddenFeature`] error pointing at each use.
+/// By default, all features are allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeaturePolicy(pub(crate) cedar_policy_validator::FeaturePolicy);
+
+impl FeaturePolicy {
+    /// A feature policy which allows all features
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forbid `unless` clauses
+    #[must_use]
+    pub fn forbid_unless(self) -> Self {
+        Self(self.0.forbid_unless())
+    }
+
+    /// Forbid templates, i.e., policies with slots
+    #[must_use]
+    pub fn forbid_templates(self) -> Self {
+        Self(self.0.forbid_templates())
+    }
+
+    /// Forbid the `like` operator
+    #[must_use]
+    pub fn forbid_like(self) -> Self {
+        Self(self.0.forbid_like())
+    }
+
+    /// Forbid calls of the extension function or method `name`, e.g.,
+    /// `decimal` or `isInRange`
+    #[must_use]
+    pub fn forbid_extension_function(self, name: &str) -> Self {
+        Self(self.0.forbid_extension_function(name))
+    }
+}
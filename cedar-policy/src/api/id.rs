@@ -18,7 +18,7 @@
 //! `EntityUid` and `PolicyId`.
 
 use crate::entities_json_errors::JsonDeserializationError;
-use crate::ParseErrors;
+use crate::{ParseErrors, StrictEntityUidParseError, SuspiciousEntityIdError};
 use cedar_policy_core::ast;
 use cedar_policy_core::entities::json::err::JsonDeserializationErrorContext;
 use cedar_policy_core::FromNormalizedStr;
@@ -64,6 +64,33 @@ impl EntityId {
     pub fn escaped(&self) -> SmolStr {
         self.0.escaped()
     }
+
+    /// Get the contents of the `EntityId` as an escaped string containing only
+    /// printable ASCII characters. Unlike [`EntityId::escaped`], this also
+    /// escapes all non-ASCII characters.
+    /// ```
+    /// # use cedar_policy::EntityId;
+    /// let id = EntityId::new("caf\u{e9}\n");
+    /// assert_eq!(id.escaped_ascii(), r"caf\u{e9}\n");
+    /// ```
+    pub fn escaped_ascii(&self) -> SmolStr {
+        self.0.escaped_ascii()
+    }
+
+    /// Construct an [`EntityId`] from a source string, rejecting strings which
+    /// contain characters that are likely to cause confusion when displayed:
+    /// control characters (including newlines), Unicode line and paragraph
+    /// separators, and bidirectional-text formatting characters.
+    /// ```
+    /// # use cedar_policy::EntityId;
+    /// assert!(EntityId::new_strict("alice").is_ok());
+    /// assert!(EntityId::new_strict("alice\nUser::\"admin\"").is_err());
+    /// ```
+    pub fn new_strict(src: impl AsRef<str>) -> Result<Self, SuspiciousEntityIdError> {
+        let eid = ast::Eid::new(src.as_ref());
+        eid.check_not_suspicious()?;
+        Ok(Self(eid))
+    }
 }
 
 impl FromStr for EntityId {
@@ -236,6 +263,57 @@ impl EntityUid {
             .into())
     }
 
+    /// Render this [`EntityUid`] as a string which contains only printable
+    /// ASCII characters, suitable for logs and audit trails.
+    ///
+    /// Quotes, backslashes, control characters (including newlines), and all
+    /// non-ASCII characters in the [`EntityId`] are escaped, so an attacker
+    /// controlling the id can't forge additional log lines or visually
+    /// spoof another id. The result can be parsed back with
+    /// [`EntityUid::from_safe_str`].
+    /// ```
+    /// # use cedar_policy::{EntityId, EntityTypeName, EntityUid};
+    /// # use std::str::FromStr;
+    /// let euid = EntityUid::from_type_name_and_id(
+    ///     EntityTypeName::from_str("User").unwrap(),
+    ///     EntityId::new("alice\"\nUser::\"admin"),
+    /// );
+    /// assert_eq!(euid.to_safe_string(), r#"User::"alice\"\nUser::\"admin""#);
+    /// assert_eq!(EntityUid::from_safe_str(&euid.to_safe_string()).unwrap(), euid);
+    /// ```
+    pub fn to_safe_string(&self) -> String {
+        self.0.to_safe_string()
+    }
+
+    /// Parse an [`EntityUid`] rendered by either [`EntityUid::to_safe_string`]
+    /// or the `Display` implementation.
+    ///
+    /// Like [`EntityUid::from_str`], this requires the input to be normalized,
+    /// i.e., free of spurious whitespace and comments.
+    pub fn from_safe_str(s: &str) -> Result<Self, ParseErrors> {
+        ast::EntityUID::from_safe_str(s)
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    /// Parse an [`EntityUid`] like [`EntityUid::from_safe_str`], but
+    /// additionally reject it if its [`EntityId`] contains characters that are
+    /// likely to cause confusion when displayed (see [`EntityId::new_strict`]).
+    ///
+    /// Note that escaped suspicious characters (e.g., `\n`) are rejected just
+    /// like unescaped ones, since it is the resulting [`EntityId`] that is
+    /// checked.
+    /// ```
+    /// # use cedar_policy::EntityUid;
+    /// assert!(EntityUid::from_str_strict(r#"User::"alice""#).is_ok());
+    /// assert!(EntityUid::from_str_strict(r#"User::"alice\n""#).is_err());
+    /// ```
+    pub fn from_str_strict(s: &str) -> Result<Self, StrictEntityUidParseError> {
+        let euid = Self::from_safe_str(s)?;
+        euid.0.eid().check_not_suspicious()?;
+        Ok(euid)
+    }
+
     /// Testing utility for creating `EntityUids` a bit easier
     #[cfg(test)]
     pub(crate) fn from_strs(typename: &str, id: &str) -> Self {
@@ -0,0 +1,91 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`IncrementalValidator`], which revalidates only the
+//! policies affected by changes to a policy set or schema.
+
+use super::{PolicySet, Schema, ValidationMode, ValidationResult, Validator};
+
+/// A [`Validator`] which remembers the result of validating each static
+/// policy and template, with the schema declarations it depends on, for
+/// editors and services which validate slowly changing policy sets against a
+/// slowly changing schema. Validating a policy set again only typechecks the
+/// policies which were added or changed, and those depending on entity types
+/// or actions whose declarations changed.
+///
+/// ```
+/// # use cedar_policy::{IncrementalValidator, PolicySet, Schema, ValidationMode, Validator};
+/// let schema: Schema = r#"
+///     entity User;
+///     entity Album = { name: String };
+///     action view appliesTo { principal: User, resource: User };
+///     action list appliesTo { principal: User, resource: Album };
+/// "#.parse().unwrap();
+/// let pset: PolicySet = r#"
+///     permit(principal, action == Action::"view", resource);
+///     permit(principal, action == Action::"list", resource) when { resource.name == "a" };
+/// "#.parse().unwrap();
+/// let mut validator = IncrementalValidator::new(Validator::new(schema));
+/// assert!(validator.validate(&pset, ValidationMode::Strict).validation_passed());
+/// assert_eq!(validator.recomputed(), 2);
+///
+/// let schema: Schema = r#"
+///     entity User;
+///     entity Album = { title: String };
+///     action view appliesTo { principal: User, resource: User };
+///     action list appliesTo { principal: User, resource: Album };
+/// "#.parse().unwrap();
+/// validator.set_schema(schema);
+/// assert!(!validator.validate(&pset, ValidationMode::Strict).validation_passed());
+/// assert_eq!((validator.reused(), validator.recomputed()), (1, 1));
+/// ```
+#[derive(Debug)]
+pub struct IncrementalValidator(cedar_policy_validator::IncrementalValidator);
+
+impl IncrementalValidator {
+    /// An incremental validator with the schema and options of `validator`
+    pub fn new(validator: Validator) -> Self {
+        Self(cedar_policy_validator::IncrementalValidator::new(
+            validator.0,
+        ))
+    }
+
+    /// Validate against `schema` from now on. Only the policies depending on
+    /// declarations which differ between the old and new schema are
+    /// revalidated by the next validation.
+    pub fn set_schema(&mut self, schema: Schema) {
+        self.0.set_schema(schema.0);
+    }
+
+    /// Validate all policies in a policy set, like [`Validator::validate`],
+    /// reusing the results of the previous validations which are still
+    /// current
+    pub fn validate(&mut self, pset: &PolicySet, mode: ValidationMode) -> ValidationResult {
+        ValidationResult::from(self.0.validate(&pset.ast, mode.into()))
+    }
+
+    /// Number of static policies and templates whose previous results the
+    /// last validation reused
+    pub fn reused(&self) -> usize {
+        self.0.reused()
+    }
+
+    /// Number of static policies and templates which the last validation
+    /// validated anew
+    pub fn recomputed(&self) -> usize {
+        self.0.recomputed()
+    }
+}
@@ -0,0 +1,350 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`PolicyLayers`], a stack of policy sets which are
+//! authorized against as if they were a single policy set, e.g., global
+//! policies shared by every tenant and the policies of one tenant.
+
+use super::duplicates::matched_actions;
+use super::{
+    AuthorizationError, Decision, Effect, Policy, PolicyId, PolicySet, Response, Schema,
+    ValidationMode, ValidationResult, Validator,
+};
+use cedar_policy_core::ast::{self, EntityReference, PrincipalOrResourceConstraint};
+use miette::Diagnostic;
+use ref_cast::RefCast;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A stack of named policy sets, or layers, which are authorized against
+/// together with [`super::Authorizer::is_authorized_layered`].
+///
+/// The decision for a request is the decision for the union of the layers: it
+/// is `Allow` iff a permit policy of some layer is satisfied and no forbid
+/// policy of any layer is satisfied. The layers are not merged, so a layer
+/// (e.g., the global policies) can be shared by many stacks (e.g., one per
+/// tenant) through an [`Arc`] without being copied.
+///
+/// Policy ids are scoped by layer: policies of different layers may have the
+/// same id, and policies are identified by the name of their layer and their
+/// id in responses. Layers should therefore have distinct names.
+///
+/// ```
+/// # use cedar_policy::{Authorizer, Context, Decision, Entities, EntityUid, PolicyId, PolicyLayers, PolicySet, Request, RestrictedExpression};
+/// # use std::str::FromStr;
+/// # use std::sync::Arc;
+/// let global: Arc<PolicySet> = Arc::new(r#"
+///     forbid(principal, action, resource) when { context.suspended };
+/// "#.parse().unwrap());
+/// let tenant: PolicySet = r#"
+///     permit(principal == User::"alice", action, resource);
+/// "#.parse().unwrap();
+/// let layers = PolicyLayers::new()
+///     .with_layer("global", Arc::clone(&global))
+///     .with_layer("tenant-42", Arc::new(tenant));
+/// let request = Request::new(
+///     EntityUid::from_str(r#"User::"alice""#).unwrap(),
+///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+///     EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+///     Context::from_pairs([("suspended".into(), RestrictedExpression::new_bool(false))]).unwrap(),
+///     None,
+/// ).unwrap();
+/// let response = Authorizer::new().is_authorized_layered(&request, &layers, &Entities::empty());
+/// assert_eq!(response.decision(), Decision::Allow);
+/// assert_eq!(
+///     response.reason().collect::<Vec<_>>(),
+///     vec![("tenant-42", &PolicyId::new("policy0"))]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PolicyLayers {
+    layers: Vec<(String, Arc<PolicySet>)>,
+}
+
+impl PolicyLayers {
+    /// An empty stack of layers, for which every request is denied
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the layer `policies` named `name` on top of the stack
+    #[must_use]
+    pub fn with_layer(mut self, name: impl Into<String>, policies: Arc<PolicySet>) -> Self {
+        self.layers.push((name.into(), policies));
+        self
+    }
+
+    /// Iterate over the names and policy sets of the layers, from the bottom
+    /// of the stack to the top
+    pub fn layers(&self) -> impl Iterator<Item = (&str, &PolicySet)> {
+        self.layers
+            .iter()
+            .map(|(name, policies)| (name.as_str(), policies.as_ref()))
+    }
+}
+
+/// Authorization response for [`PolicyLayers`], returned by
+/// [`super::Authorizer::is_authorized_layered`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayeredResponse {
+    decision: Decision,
+    /// The response for each layer, in the order of the layers
+    layers: Vec<(String, Response)>,
+}
+
+impl LayeredResponse {
+    /// Combine the responses for each layer into the response for their union
+    pub(super) fn new(layers: Vec<(String, Response)>) -> Self {
+        // the reasons of a `Deny` are the satisfied forbid policies, so a
+        // layer denies with reasons iff one of its forbid policies is
+        // satisfied
+        let decision = if layers.iter().any(|(_, r)| is_forbidden(r)) {
+            Decision::Deny
+        } else if layers.iter().any(|(_, r)| r.decision() == Decision::Allow) {
+            Decision::Allow
+        } else {
+            Decision::Deny
+        };
+        Self { decision, layers }
+    }
+
+    /// The authorization decision for the union of the layers
+    pub fn decision(&self) -> Decision {
+        self.decision
+    }
+
+    /// The policies which determined the decision, identified by the name of
+    /// their layer and their id: the satisfied forbid policies if the
+    /// decision is `Deny` (if any), or the satisfied permit policies if it
+    /// is `Allow`
+    pub fn reason(&self) -> impl Iterator<Item = (&str, &PolicyId)> {
+        self.layers
+            .iter()
+            .filter(|(_, r)| r.decision() == self.decision)
+            .flat_map(|(name, r)| r.diagnostics().reason().map(move |id| (name.as_str(), id)))
+    }
+
+    /// The errors which occurred while evaluating the policies of each layer,
+    /// with the name of the layer
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &AuthorizationError)> {
+        self.layers.iter().flat_map(|(name, r)| {
+            r.diagnostics()
+                .errors()
+                .map(move |err| (name.as_str(), err))
+        })
+    }
+
+    /// The response for each layer on its own, with the name of the layer
+    pub fn layer_responses(&self) -> impl Iterator<Item = (&str, &Response)> {
+        self.layers.iter().map(|(name, r)| (name.as_str(), r))
+    }
+}
+
+/// Whether a forbid policy is satisfied for `response`
+fn is_forbidden(response: &Response) -> bool {
+    response.decision() == Decision::Deny && response.diagnostics().reason().next().is_some()
+}
+
+/// Validation result for [`PolicyLayers`], returned by
+/// [`super::Validator::validate_layers`]
+#[derive(Debug)]
+pub struct LayeredValidationResult {
+    /// The validation result of each layer, in the order of the layers
+    layers: Vec<(String, ValidationResult)>,
+    overridden_permits: Vec<OverriddenPermit>,
+}
+
+impl LayeredValidationResult {
+    /// Validate each layer, and find the permit policies which are always
+    /// overridden by a forbid policy of a lower layer
+    pub(super) fn new(validator: &Validator, layers: &PolicyLayers, mode: ValidationMode) -> Self {
+        let schema = Schema::ref_cast(validator.0.schema());
+        let mut overridden_permits = Vec::new();
+        for (i, (layer, policies)) in layers.layers().enumerate() {
+            for permit in policies.policies().filter(|p| p.effect() == Effect::Permit) {
+                let forbid = layers.layers().take(i).find_map(|(base, base_policies)| {
+                    base_policies
+                        .policies()
+                        .find(|forbid| always_overrides(forbid, permit, schema))
+                        .map(|forbid| (base, forbid))
+                });
+                if let Some((base, forbid)) = forbid {
+                    overridden_permits.push(OverriddenPermit {
+                        layer: layer.to_string(),
+                        policy_id: permit.id().clone(),
+                        forbid_layer: base.to_string(),
+                        forbid_id: forbid.id().clone(),
+                    });
+                }
+            }
+        }
+        Self {
+            layers: layers
+                .layers()
+                .map(|(name, policies)| (name.to_string(), validator.validate(policies, mode)))
+                .collect(),
+            overridden_permits,
+        }
+    }
+
+    /// True when every layer passes validation. There may still be warnings,
+    /// including overridden permit policies.
+    pub fn validation_passed(&self) -> bool {
+        self.layers.iter().all(|(_, r)| r.validation_passed())
+    }
+
+    /// The validation result of each layer on its own, with the name of the
+    /// layer
+    pub fn layer_results(&self) -> impl Iterator<Item = (&str, &ValidationResult)> {
+        self.layers.iter().map(|(name, r)| (name.as_str(), r))
+    }
+
+    /// The permit policies which never allow a request, since a forbid
+    /// policy of a lower layer applies whenever they do
+    pub fn overridden_permits(&self) -> impl Iterator<Item = &OverriddenPermit> {
+        self.overridden_permits.iter()
+    }
+}
+
+/// Warning for a permit policy of a layer which is always overridden by a
+/// forbid policy of a lower layer, e.g., a tenant policy which is overridden
+/// by a global policy
+#[derive(Debug, Clone, PartialEq, Eq, Error, Diagnostic)]
+#[error("permit policy `{policy_id}` of layer `{layer}` is always overridden by forbid policy `{forbid_id}` of layer `{forbid_layer}`")]
+#[diagnostic(
+    severity(Warning),
+    help("the forbid policy applies to every request the permit policy applies to, so the permit policy never allows a request")
+)]
+pub struct OverriddenPermit {
+    layer: String,
+    policy_id: PolicyId,
+    forbid_layer: String,
+    forbid_id: PolicyId,
+}
+
+impl OverriddenPermit {
+    /// The name of the layer of the permit policy
+    pub fn layer(&self) -> &str {
+        &self.layer
+    }
+
+    /// The id of the permit policy
+    pub fn policy_id(&self) -> &PolicyId {
+        &self.policy_id
+    }
+
+    /// The name of the layer of the forbid policy
+    pub fn forbid_layer(&self) -> &str {
+        &self.forbid_layer
+    }
+
+    /// The id of the forbid policy
+    pub fn forbid_id(&self) -> &PolicyId {
+        &self.forbid_id
+    }
+}
+
+/// Whether `forbid` is a forbid policy which is satisfied for every request
+/// `permit` applies to. This only holds if `forbid` has no condition (after
+/// optimization), and its scope includes the scope of `permit`.
+pub(super) fn always_overrides(forbid: &Policy, permit: &Policy, schema: &Schema) -> bool {
+    if forbid.effect() != Effect::Forbid
+        || !matches!(
+            cedar_policy_core::optimizer::optimize(forbid.ast.non_scope_constraints()).expr_kind(),
+            ast::ExprKind::Lit(ast::Literal::Bool(true))
+        )
+    {
+        return false;
+    }
+    let actions = |p: &Policy| matched_actions(p.ast.action_constraint(), schema);
+    let actions_included = match (actions(forbid), actions(permit)) {
+        (Some(forbidden), Some(permitted)) => {
+            permitted.iter().all(|action| forbidden.contains(action))
+        }
+        _ => false,
+    };
+    actions_included
+        && scope_includes(
+            &forbid.ast.principal_constraint().into_inner(),
+            &permit.ast.principal_constraint().into_inner(),
+        )
+        && scope_includes(
+            &forbid.ast.resource_constraint().into_inner(),
+            &permit.ast.resource_constraint().into_inner(),
+        )
+}
+
+/// Whether every entity which satisfies the principal or resource scope
+/// `inner` satisfies `outer`, whatever the entity hierarchy
+fn scope_includes(
+    outer: &PrincipalOrResourceConstraint,
+    inner: &PrincipalOrResourceConstraint,
+) -> bool {
+    use PrincipalOrResourceConstraint::{Any, Eq, In, Is, IsIn};
+    let entity = |r: &EntityReference| match r {
+        EntityReference::EUID(euid) => Some(Arc::clone(euid)),
+        EntityReference::Slot => None,
+    };
+    match (outer, inner) {
+        (Any, _) => true,
+        (Eq(o), Eq(i)) | (In(o), Eq(i) | In(i) | IsIn(_, i)) => {
+            entity(o).is_some() && entity(o) == entity(i)
+        }
+        (Is(o), Is(i) | IsIn(i, _)) => o == i,
+        (Is(o), Eq(i)) => entity(i).is_some_and(|i| i.entity_type() == o.as_ref()),
+        (IsIn(ot, o), IsIn(it, i)) => ot == it && entity(o).is_some() && entity(o) == entity(i),
+        (IsIn(ot, o), Eq(i)) => {
+            entity(o).is_some_and(|o| o.entity_type() == ot.as_ref()) && entity(o) == entity(i)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scope_inclusion() {
+        let euid = |s: &str| Arc::new(s.parse::<ast::EntityUID>().unwrap());
+        let ty = |s: &str| Arc::new(s.parse::<ast::EntityType>().unwrap());
+        let eq = |s| PrincipalOrResourceConstraint::Eq(EntityReference::EUID(euid(s)));
+        let is_in = |s| PrincipalOrResourceConstraint::In(EntityReference::EUID(euid(s)));
+        let is = |s| PrincipalOrResourceConstraint::Is(ty(s));
+
+        let alice = r#"User::"alice""#;
+        let admins = r#"Group::"admins""#;
+        assert!(scope_includes(
+            &PrincipalOrResourceConstraint::Any,
+            &eq(alice)
+        ));
+        assert!(scope_includes(&is_in(admins), &eq(admins)));
+        assert!(scope_includes(&is_in(admins), &is_in(admins)));
+        assert!(scope_includes(&is("User"), &eq(alice)));
+        assert!(scope_includes(
+            &PrincipalOrResourceConstraint::IsIn(ty("User"), EntityReference::EUID(euid(alice))),
+            &eq(alice)
+        ));
+        // `alice` may or may not be a member of `admins`
+        assert!(!scope_includes(&is_in(admins), &eq(alice)));
+        assert!(!scope_includes(&eq(alice), &is("User")));
+        assert!(!scope_includes(&is("Group"), &eq(alice)));
+        assert!(!scope_includes(
+            &PrincipalOrResourceConstraint::Eq(EntityReference::Slot),
+            &PrincipalOrResourceConstraint::Eq(EntityReference::Slot)
+        ));
+    }
+}
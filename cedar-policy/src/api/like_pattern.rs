@@ -0,0 +1,86 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`LikePattern`], the pattern of a `like` expression,
+//! and analyses of patterns.
+
+use cedar_policy_core::ast;
+
+/// The pattern of a `like` expression, where a wildcard matches any sequence
+/// of characters.
+///
+/// ```
+/// # use cedar_policy::LikePattern;
+/// let jpg = LikePattern::new("*.jpg");
+/// let vacation = LikePattern::new("vacation*");
+/// let png = LikePattern::new("*.png");
+/// assert!(jpg.matches("vacation.jpg"));
+/// // `vacation.jpg` matches both patterns
+/// assert!(jpg.overlaps(&vacation));
+/// // no string ends with both `.jpg` and `.png`
+/// assert!(!jpg.overlaps(&png));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LikePattern(ast::Pattern);
+
+impl LikePattern {
+    /// Construct a pattern from a string where `*` is a wildcard and `\*` is a
+    /// literal star, as in Cedar syntax. No other escapes are interpreted.
+    pub fn new(pattern: &str) -> Self {
+        let mut elems = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    elems.push(ast::PatternElem::Char('*'));
+                }
+                '*' => elems.push(ast::PatternElem::Wildcard),
+                c => elems.push(ast::PatternElem::Char(c)),
+            }
+        }
+        Self(ast::Pattern::new(elems))
+    }
+
+    pub(super) fn from_ast(pattern: &ast::Pattern) -> Self {
+        Self(pattern.clone())
+    }
+
+    /// Whether `text` matches this pattern, i.e., whether `text like pattern`
+    /// evaluates to `true`
+    pub fn matches(&self, text: &str) -> bool {
+        self.0.wildcard_match(text)
+    }
+
+    /// Whether the pattern contains a wildcard. A pattern without wildcards
+    /// only matches itself, so `like` could be replaced by `==`.
+    pub fn has_wildcard(&self) -> bool {
+        self.0.has_wildcard()
+    }
+
+    /// Whether some string matches both this pattern and `other`. If not, the
+    /// conditions `e like p1` and `e like p2` are mutually exclusive.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.0.overlaps(&other.0)
+    }
+}
+
+/// Renders the pattern as in Cedar syntax, without the enclosing quotes
+impl std::fmt::Display for LikePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
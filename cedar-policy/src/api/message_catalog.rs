@@ -0,0 +1,281 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`MessageCatalog`], which lets embedders override or
+//! translate the messages of validation diagnostics.
+
+use super::{ValidationError, ValidationWarning};
+use miette::Diagnostic;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A catalog of messages overriding the default (English) messages of
+/// validation errors and warnings.
+///
+/// Messages are keyed by the stable code of the diagnostic, which is the name
+/// of its variant as returned by [`ValidationError::kind`] and
+/// [`ValidationWarning::kind`], e.g., `"UnrecognizedEntityType"`. A message is
+/// a template which may contain the placeholders `{policy_id}`, replaced by
+/// the id of the policy where the issue was found (or the empty string for
+/// warnings about the schema), and `{message}`, replaced by the default
+/// message. The placeholders `{{` and `}}` render literal braces.
+///
+/// Diagnostics without an entry in the catalog keep their default message and
+/// help. Everything else about the diagnostic (its source locations, labels,
+/// and severity) is unaffected.
+///
+/// ```
+/// # use cedar_policy::{MessageCatalog, PolicySet, Schema, ValidationMode, Validator};
+/// let catalog = MessageCatalog::new()
+///     .with_message("UnrecognizedEntityType", "política `{policy_id}`: tipo de entidad desconocido")
+///     .with_help("UnrecognizedEntityType", "declare el tipo en el esquema");
+/// let schema: Schema = "entity User; action view appliesTo { principal: User, resource: User };"
+///     .parse()
+///     .unwrap();
+/// let policies: PolicySet = r#"permit(principal == Usr::"alice", action, resource);"#
+///     .parse()
+///     .unwrap();
+/// let result = Validator::new(schema).validate(&policies, ValidationMode::Strict);
+/// let err = catalog.localize_error(result.validation_errors().next().unwrap());
+/// assert_eq!(err.to_string(), "política `policy0`: tipo de entidad desconocido");
+/// assert_eq!(
+///     miette::Diagnostic::help(&err).unwrap().to_string(),
+///     "declare el tipo en el esquema"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MessageCatalog {
+    #[serde(default)]
+    messages: HashMap<String, String>,
+    #[serde(default)]
+    help: HashMap<String, String>,
+}
+
+impl MessageCatalog {
+    /// An empty catalog, which keeps the default message of every diagnostic
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the message of diagnostics with the stable code `code`
+    #[must_use]
+    pub fn with_message(mut self, code: impl Into<String>, template: impl Into<String>) -> Self {
+        self.messages.insert(code.into(), template.into());
+        self
+    }
+
+    /// Override the help text of diagnostics with the stable code `code`. The
+    /// help text is a template with the same placeholders as messages, where
+    /// `{message}` is replaced by the default message.
+    #[must_use]
+    pub fn with_help(mut self, code: impl Into<String>, template: impl Into<String>) -> Self {
+        self.help.insert(code.into(), template.into());
+        self
+    }
+
+    /// Parse a catalog from JSON of the form
+    /// `{ "messages": { <code>: <template>, ... }, "help": { <code>: <template>, ... } }`,
+    /// where both fields are optional. This is convenient for catalogs
+    /// maintained by translators outside of the code.
+    pub fn from_json_value(json: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(json)
+    }
+
+    /// Parse a catalog from a JSON string, in the format described in
+    /// [`MessageCatalog::from_json_value`]
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// `error`, with its message and help replaced according to this catalog
+    pub fn localize_error<'a>(&self, error: &'a ValidationError) -> LocalizedDiagnostic<'a> {
+        let policy_id = error.policy_id().to_string();
+        self.localize(error, error.kind(), &policy_id)
+    }
+
+    /// `warning`, with its message and help replaced according to this catalog
+    pub fn localize_warning<'a>(&self, warning: &'a ValidationWarning) -> LocalizedDiagnostic<'a> {
+        let policy_id = warning
+            .policy_id()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        self.localize(warning, warning.kind(), &policy_id)
+    }
+
+    fn localize<'a>(
+        &self,
+        diagnostic: &'a dyn Diagnostic,
+        code: &str,
+        policy_id: &str,
+    ) -> LocalizedDiagnostic<'a> {
+        let default_message = diagnostic.to_string();
+        let render = |template: &String| render_template(template, policy_id, &default_message);
+        LocalizedDiagnostic {
+            message: self
+                .messages
+                .get(code)
+                .map_or_else(|| default_message.clone(), render),
+            help: self.help.get(code).map(render),
+            inner: diagnostic,
+        }
+    }
+}
+
+/// Substitute the placeholders of `template`. Unknown placeholders are left as
+/// they are, so that a typo in a translation remains visible.
+fn render_template(template: &str, policy_id: &str, message: &str) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(r) = rest.strip_prefix("{{") {
+            rendered.push('{');
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("}}") {
+            rendered.push('}');
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("{policy_id}") {
+            rendered.push_str(policy_id);
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("{message}") {
+            rendered.push_str(message);
+            rest = r;
+        } else {
+            rendered.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// A validation error or warning whose message and help were replaced
+/// according to a [`MessageCatalog`]. All other parts of the diagnostic are
+/// those of the original diagnostic, so it can be rendered with `miette` as
+/// usual.
+#[derive(Debug)]
+pub struct LocalizedDiagnostic<'a> {
+    message: String,
+    help: Option<String>,
+    inner: &'a dyn Diagnostic,
+}
+
+impl std::fmt::Display for LocalizedDiagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for LocalizedDiagnostic<'_> {}
+
+impl Diagnostic for LocalizedDiagnostic<'_> {
+    fn code<'b>(&'b self) -> Option<Box<dyn std::fmt::Display + 'b>> {
+        self.inner.code()
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.inner.severity()
+    }
+
+    fn help<'b>(&'b self) -> Option<Box<dyn std::fmt::Display + 'b>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn std::fmt::Display + 'b>)
+            .or_else(|| self.inner.help())
+    }
+
+    fn url<'b>(&'b self) -> Option<Box<dyn std::fmt::Display + 'b>> {
+        self.inner.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.inner.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        self.inner.labels()
+    }
+
+    fn related<'b>(&'b self) -> Option<Box<dyn Iterator<Item = &'b dyn Diagnostic> + 'b>> {
+        self.inner.related()
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.inner.diagnostic_source()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PolicySet, Schema, ValidationMode, Validator};
+
+    #[test]
+    fn templates() {
+        assert_eq!(render_template("plain", "p", "m"), "plain");
+        assert_eq!(
+            render_template("{policy_id}: {message}", "p0", "bad"),
+            "p0: bad"
+        );
+        assert_eq!(render_template("{{policy_id}}", "p0", "m"), "{policy_id}");
+        assert_eq!(render_template("{unknown} }", "p0", "m"), "{unknown} }");
+    }
+
+    #[test]
+    fn localize() {
+        let schema: Schema =
+            "entity User { name: String }; action view appliesTo { principal: User, resource: User };"
+                .parse()
+                .unwrap();
+        let policies: PolicySet =
+            r#"permit(principal, action, resource) when { principal.nme == "" };"#
+                .parse()
+                .unwrap();
+        let result = Validator::new(schema).validate(&policies, ValidationMode::Strict);
+        let err = result.validation_errors().next().unwrap();
+        assert_eq!(err.kind(), "UnsafeAttributeAccess");
+
+        // without an entry, the message and help are unchanged
+        let localized = MessageCatalog::new().localize_error(err);
+        assert_eq!(localized.to_string(), err.to_string());
+        assert_eq!(
+            localized.help().map(|h| h.to_string()),
+            err.help().map(|h| h.to_string())
+        );
+
+        let catalog = MessageCatalog::from_json_value(serde_json::json!({
+            "messages": { "UnsafeAttributeAccess": "[{policy_id}] attribut inconnu ({message})" }
+        }))
+        .unwrap();
+        let localized = catalog.localize_error(err);
+        assert_eq!(
+            localized.to_string(),
+            format!("[policy0] attribut inconnu ({err})")
+        );
+        assert_eq!(
+            localized.help().map(|h| h.to_string()),
+            err.help().map(|h| h.to_string())
+        );
+        assert_eq!(
+            localized.labels().map(Iterator::count),
+            err.labels().map(Iterator::count)
+        );
+
+        assert!(MessageCatalog::from_json_str(r#"{ "mesages": {} }"#).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`PolicyCache`], a cache of parsed and validated
+//! policy sets keyed by the hash of their text.
+
+use crate::{ParseErrors, PolicySet, ValidationMode, ValidationResult, Validator};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A cache of the policy sets parsed from texts, and optionally their
+/// validation results, for services which repeatedly load the same policies,
+/// e.g., from a database for each request. Looking up a text hashes it and,
+/// if it was parsed before, returns the cached policy set without parsing or
+/// validating it again.
+///
+/// The cache can be shared between threads. It holds at most `capacity`
+/// policy sets, and evicts the least recently added one when full. Texts
+/// which fail to parse are not cached.
+///
+/// ```
+/// # use cedar_policy::PolicyCache;
+/// # use std::sync::Arc;
+/// let cache = PolicyCache::new(100);
+/// let text = "permit(principal, action, resource);";
+/// let cached = cache.get(text).unwrap();
+/// assert_eq!(cached.policies().policies().count(), 1);
+/// assert!(Arc::ptr_eq(&cached, &cache.get(text).unwrap()));
+/// ```
+#[derive(Debug)]
+pub struct PolicyCache {
+    capacity: usize,
+    validator: Option<(Validator, ValidationMode)>,
+    entries: Mutex<Entries>,
+}
+
+/// The cached policy sets
+#[derive(Debug, Default)]
+struct Entries {
+    /// Cached policy sets by hash of their text
+    by_hash: HashMap<u64, Vec<Arc<CachedPolicySet>>>,
+    /// Hashes of the cached policy sets in the order they were added
+    order: VecDeque<u64>,
+}
+
+/// A policy set in a [`PolicyCache`], with its validation result if the
+/// cache validates policies
+#[derive(Debug)]
+pub struct CachedPolicySet {
+    text: String,
+    policies: PolicySet,
+    validation_result: Option<ValidationResult>,
+}
+
+impl CachedPolicySet {
+    /// The policy set
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// The result of validating the policy set, if the cache validates
+    /// policies
+    pub fn validation_result(&self) -> Option<&ValidationResult> {
+        self.validation_result.as_ref()
+    }
+}
+
+impl PolicyCache {
+    /// A cache of at most `capacity` policy sets which only parses policies
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            validator: None,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Also validate the policies with `validator` in `mode`, and cache the
+    /// validation results
+    #[must_use]
+    pub fn with_validator(mut self, validator: Validator, mode: ValidationMode) -> Self {
+        self.validator = Some((validator, mode));
+        self
+    }
+
+    /// The policy set parsed from `text`, and its validation result if the
+    /// cache validates policies. This parses and validates `text` unless it
+    /// is cached. Policies get the ids `policy0`, `policy1`, etc., like
+    /// [`PolicySet::from_str()`].
+    pub fn get(&self, text: &str) -> Result<Arc<CachedPolicySet>, ParseErrors> {
+        let hash = hash(text);
+        let cached = self.entries().lookup(hash, text);
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+        // parse and validate without holding the lock, so that other threads
+        // can use the cache meanwhile
+        let policies = PolicySet::from_str(text)?;
+        let validation_result = self
+            .validator
+            .as_ref()
+            .map(|(validator, mode)| validator.validate(&policies, *mode));
+        let cached = Arc::new(CachedPolicySet {
+            text: text.to_string(),
+            policies,
+            validation_result,
+        });
+        let mut entries = self.entries();
+        // another thread may have cached `text` meanwhile
+        if let Some(cached) = entries.lookup(hash, text) {
+            return Ok(cached);
+        }
+        if self.capacity > 0 {
+            if entries.order.len() >= self.capacity {
+                entries.evict();
+            }
+            entries
+                .by_hash
+                .entry(hash)
+                .or_default()
+                .push(Arc::clone(&cached));
+            entries.order.push_back(hash);
+        }
+        drop(entries);
+        Ok(cached)
+    }
+
+    /// Number of cached policy sets
+    pub fn len(&self) -> usize {
+        self.entries().order.len()
+    }
+
+    /// True if no policy set is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached policy sets, e.g., after the schema changed
+    pub fn clear(&self) {
+        *self.entries() = Entries::default();
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // the entries are only modified by operations which don't panic
+        // (barring allocation failure), so keep using them after a panic in
+        // another thread
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Entries {
+    fn lookup(&self, hash: u64, text: &str) -> Option<Arc<CachedPolicySet>> {
+        self.by_hash
+            .get(&hash)?
+            .iter()
+            .find(|cached| cached.text == text)
+            .cloned()
+    }
+
+    /// Remove the least recently added policy set
+    fn evict(&mut self) {
+        let Some(hash) = self.order.pop_front() else {
+            return;
+        };
+        if let Some(bucket) = self.by_hash.get_mut(&hash) {
+            // policy sets with the same hash are added in order, too
+            if !bucket.is_empty() {
+                bucket.remove(0);
+            }
+            if bucket.is_empty() {
+                self.by_hash.remove(&hash);
+            }
+        }
+    }
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Schema;
+
+    #[test]
+    fn caches_and_evicts() {
+        let cache = PolicyCache::new(2);
+        let texts = [
+            "permit(principal, action, resource);",
+            "forbid(principal, action, resource);",
+            "permit(principal == User::\"alice\", action, resource);",
+        ];
+        let first = cache.get(texts[0]).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.get(texts[0]).unwrap()));
+        assert!(first.validation_result().is_none());
+        cache.get(texts[1]).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // the first policy set is evicted
+        cache.get(texts[2]).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(!Arc::ptr_eq(&first, &cache.get(texts[0]).unwrap()));
+
+        assert!(cache.get("permit(").is_err());
+        assert_eq!(cache.len(), 2);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn caches_validation_results() {
+        let (schema, _) = Schema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let cache =
+            PolicyCache::new(10).with_validator(Validator::new(schema), ValidationMode::Strict);
+        let valid = cache
+            .get("permit(principal == User::\"alice\", action == Action::\"view\", resource);")
+            .unwrap();
+        assert!(valid.validation_result().unwrap().validation_passed());
+        let invalid = cache
+            .get("permit(principal == Photo::\"p\", action == Action::\"view\", resource);")
+            .unwrap();
+        assert!(!invalid.validation_result().unwrap().validation_passed());
+    }
+}
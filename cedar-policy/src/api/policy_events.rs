@@ -0,0 +1,222 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`PolicySetObserver`], which is notified of the
+//! changes made to a [`PolicySet`](crate::PolicySet).
+
+use crate::{Policy, Template};
+use std::sync::Arc;
+
+/// Notified of each change made to a [`PolicySet`](crate::PolicySet) it is
+/// registered with, with [`add_observer()`](crate::PolicySet::add_observer),
+/// so that applications can maintain indexes derived from the policies, e.g.,
+/// the policies of each action, without comparing the whole set before and
+/// after each change.
+///
+/// Each method is called after the change is made, and only if it succeeds.
+/// All methods do nothing by default.
+///
+/// ```
+/// # use cedar_policy::{Policy, PolicyId, PolicySet, PolicySetObserver};
+/// # use std::sync::{Arc, Mutex};
+/// #[derive(Default)]
+/// struct Index(Mutex<Vec<PolicyId>>);
+///
+/// impl PolicySetObserver for Index {
+///     fn on_add(&self, policy: &Policy) {
+///         self.0.lock().unwrap().push(policy.id().clone());
+///     }
+///     fn on_remove(&self, policy: &Policy) {
+///         self.0.lock().unwrap().retain(|id| id != policy.id());
+///     }
+/// }
+///
+/// let index = Arc::new(Index::default());
+/// let mut pset = PolicySet::new();
+/// pset.add_observer(index.clone());
+/// let policy = Policy::parse(Some(PolicyId::new("p")), "permit(principal, action, resource);").unwrap();
+/// pset.add(policy).unwrap();
+/// assert_eq!(*index.0.lock().unwrap(), [PolicyId::new("p")]);
+/// pset.remove_static(PolicyId::new("p")).unwrap();
+/// assert!(index.0.lock().unwrap().is_empty());
+/// ```
+pub trait PolicySetObserver: Send + Sync {
+    /// The static policy `policy` was added to the set
+    fn on_add(&self, policy: &Policy) {
+        let _ = policy;
+    }
+
+    /// The static policy `policy` was removed from the set
+    fn on_remove(&self, policy: &Policy) {
+        let _ = policy;
+    }
+
+    /// The template-linked policy `policy` was added to the set. Its template
+    /// is [`Policy::template_id()`].
+    fn on_link(&self, policy: &Policy) {
+        let _ = policy;
+    }
+
+    /// The template-linked policy `policy` was removed from the set
+    fn on_unlink(&self, policy: &Policy) {
+        let _ = policy;
+    }
+
+    /// The template `template` was added to the set
+    fn on_add_template(&self, template: &Template) {
+        let _ = template;
+    }
+
+    /// The template `template` was removed from the set
+    fn on_remove_template(&self, template: &Template) {
+        let _ = template;
+    }
+}
+
+/// The observers registered with a [`PolicySet`](crate::PolicySet).
+///
+/// A clone of a policy set is a different set, which the indexes of the
+/// original shouldn't follow, so cloning gives no observers.
+#[derive(Default)]
+pub struct Observers(Vec<Arc<dyn PolicySetObserver>>);
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Arc<dyn PolicySetObserver>) {
+        self.0.push(observer);
+    }
+
+    /// Call `f` on each observer, in the order they were registered
+    pub(crate) fn notify(&self, f: impl Fn(&dyn PolicySetObserver)) {
+        for observer in &self.0 {
+            f(observer.as_ref());
+        }
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} observers", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EntityUid, PolicyId, PolicySet, SlotId};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Records the events it is notified of
+    #[derive(Default)]
+    struct Log(Mutex<Vec<String>>);
+
+    impl Log {
+        fn record(&self, event: &str, id: &PolicyId) {
+            self.0.lock().unwrap().push(format!("{event} {id}"));
+        }
+
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    impl PolicySetObserver for Log {
+        fn on_add(&self, policy: &Policy) {
+            self.record("add", policy.id());
+        }
+        fn on_remove(&self, policy: &Policy) {
+            self.record("remove", policy.id());
+        }
+        fn on_link(&self, policy: &Policy) {
+            self.record("link", policy.id());
+        }
+        fn on_unlink(&self, policy: &Policy) {
+            self.record("unlink", policy.id());
+        }
+        fn on_add_template(&self, template: &Template) {
+            self.record("add_template", template.id());
+        }
+        fn on_remove_template(&self, template: &Template) {
+            self.record("remove_template", template.id());
+        }
+    }
+
+    #[test]
+    fn notified_of_changes() {
+        let log = Arc::new(Log::default());
+        let mut pset = PolicySet::new();
+        pset.add_observer(log.clone());
+
+        let policy = Policy::parse(
+            Some(PolicyId::new("p")),
+            "permit(principal, action, resource);",
+        )
+        .unwrap();
+        pset.add(policy.clone()).unwrap();
+        // a failed change is not notified
+        assert!(pset.add(policy).is_err());
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            "permit(principal == ?principal, action, resource);",
+        )
+        .unwrap();
+        pset.add_template(template).unwrap();
+        let alice: EntityUid = r#"User::"alice""#.parse().unwrap();
+        pset.link(
+            PolicyId::new("t"),
+            PolicyId::new("l"),
+            HashMap::from([(SlotId::principal(), alice)]),
+        )
+        .unwrap();
+        assert_eq!(
+            log.take(),
+            ["add p", "add_template t", "link l"].map(String::from)
+        );
+
+        assert!(pset.remove_template(PolicyId::new("t")).is_err());
+        pset.unlink(PolicyId::new("l")).unwrap();
+        pset.remove_template(PolicyId::new("t")).unwrap();
+        pset.remove_static(PolicyId::new("p")).unwrap();
+        assert_eq!(
+            log.take(),
+            ["unlink l", "remove_template t", "remove p"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn clones_have_no_observers() {
+        let log = Arc::new(Log::default());
+        let mut pset = PolicySet::new();
+        pset.add_observer(log.clone());
+        let mut clone = pset.clone();
+        clone
+            .add(
+                Policy::parse(
+                    Some(PolicyId::new("p")),
+                    "permit(principal, action, resource);",
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        assert!(log.take().is_empty());
+    }
+}
@@ -0,0 +1,496 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`PolicyJson`] and [`ExprJson`], typed builders for
+//! policies in the JSON policy format.
+
+use super::{
+    ActionConstraint, Effect, EntityTypeName, EntityUid, ParseErrors, Policy, PolicyFromJsonError,
+    PolicyId, PrincipalConstraint, ResourceConstraint,
+};
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::json::EntityUidJson;
+use cedar_policy_core::est;
+use serde::{Deserialize, Serialize};
+use smol_str::{SmolStr, ToSmolStr};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A policy in the JSON policy format, built with typed methods instead of
+/// by assembling a `serde_json::Value`.
+///
+/// `PolicyJson` serializes to, and deserializes from, the JSON policy format
+/// documented at <https://docs.cedarpolicy.com/policies/json-format.html>.
+/// Convert it to a [`Policy`] with [`PolicyJson::into_policy()`] or
+/// `try_into()`.
+///
+/// ```
+/// # use cedar_policy::{EntityUid, ExprJson, Policy, PolicyJson, PrincipalConstraint};
+/// # use std::str::FromStr;
+/// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+/// let policy: Policy = PolicyJson::permit()
+///     .principal(PrincipalConstraint::Eq(alice))
+///     .when(ExprJson::resource().get_attr("public").eq(ExprJson::bool(true)))
+///     .annotation("id", "public-photos")
+///     .unwrap()
+///     .try_into()
+///     .unwrap();
+/// let text = Policy::parse(None, r#"@id("public-photos")
+///     permit(principal == User::"alice", action, resource)
+///     when { resource.public == true };"#).unwrap();
+/// assert_eq!(policy.to_json().unwrap(), text.to_json().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PolicyJson(est::Policy);
+
+impl PolicyJson {
+    /// A `permit` policy with no scope constraints and no conditions
+    pub fn permit() -> Self {
+        Self(est::Policy::new(ast::Effect::Permit))
+    }
+
+    /// A `forbid` policy with no scope constraints and no conditions
+    pub fn forbid() -> Self {
+        Self(est::Policy::new(ast::Effect::Forbid))
+    }
+
+    /// A policy with the given effect, no scope constraints, and no conditions
+    pub fn new(effect: Effect) -> Self {
+        Self(est::Policy::new(effect))
+    }
+
+    /// Set the principal scope constraint
+    #[must_use]
+    pub fn principal(self, constraint: PrincipalConstraint) -> Self {
+        let constraint = match constraint {
+            PrincipalConstraint::Any => est::PrincipalConstraint::All,
+            PrincipalConstraint::Eq(uid) => est::PrincipalConstraint::Eq(eq_constraint(&uid)),
+            PrincipalConstraint::In(uid) => est::PrincipalConstraint::In(in_constraint(&uid)),
+            PrincipalConstraint::Is(ty) => est::PrincipalConstraint::Is(is_constraint(&ty, None)),
+            PrincipalConstraint::IsIn(ty, uid) => {
+                est::PrincipalConstraint::Is(is_constraint(&ty, Some(&uid)))
+            }
+        };
+        Self(self.0.with_principal(constraint))
+    }
+
+    /// Set the action scope constraint
+    #[must_use]
+    pub fn action(self, constraint: ActionConstraint) -> Self {
+        let constraint = match constraint {
+            ActionConstraint::Any => est::ActionConstraint::All,
+            ActionConstraint::Eq(uid) => est::ActionConstraint::Eq(eq_constraint(&uid)),
+            ActionConstraint::In(uids) => est::ActionConstraint::In(est::ActionInConstraint::Set {
+                entities: uids.iter().map(uid_json).collect(),
+            }),
+        };
+        Self(self.0.with_action(constraint))
+    }
+
+    /// Set the resource scope constraint
+    #[must_use]
+    pub fn resource(self, constraint: ResourceConstraint) -> Self {
+        let constraint = match constraint {
+            ResourceConstraint::Any => est::ResourceConstraint::All,
+            ResourceConstraint::Eq(uid) => est::ResourceConstraint::Eq(eq_constraint(&uid)),
+            ResourceConstraint::In(uid) => est::ResourceConstraint::In(in_constraint(&uid)),
+            ResourceConstraint::Is(ty) => est::ResourceConstraint::Is(is_constraint(&ty, None)),
+            ResourceConstraint::IsIn(ty, uid) => {
+                est::ResourceConstraint::Is(is_constraint(&ty, Some(&uid)))
+            }
+        };
+        Self(self.0.with_resource(constraint))
+    }
+
+    /// Add a `when` clause
+    #[must_use]
+    pub fn when(self, condition: ExprJson) -> Self {
+        Self(self.0.with_condition(est::Clause::When(condition.0)))
+    }
+
+    /// Add an `unless` clause
+    #[must_use]
+    pub fn unless(self, condition: ExprJson) -> Self {
+        Self(self.0.with_condition(est::Clause::Unless(condition.0)))
+    }
+
+    /// Add an annotation. Returns an error if `key` is not a valid annotation
+    /// key, i.e., an identifier.
+    pub fn annotation(self, key: &str, value: impl Into<SmolStr>) -> Result<Self, ParseErrors> {
+        let key: ast::AnyId = key.parse()?;
+        Ok(Self(self.0.with_annotation(key, value.into())))
+    }
+
+    /// Convert to a [`Policy`] with the given id, or `policy0` if `id` is
+    /// `None`. This fails if the condition is malformed, e.g., if it calls an
+    /// unknown extension function.
+    pub fn into_policy(self, id: Option<PolicyId>) -> Result<Policy, PolicyFromJsonError> {
+        Policy::from_est(id, self.0)
+    }
+
+    /// Serialize to the JSON policy format
+    pub fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+}
+
+impl TryFrom<PolicyJson> for Policy {
+    type Error = PolicyFromJsonError;
+
+    fn try_from(json: PolicyJson) -> Result<Self, Self::Error> {
+        json.into_policy(None)
+    }
+}
+
+/// Scope constraints refer to entities as `{ "type": .., "id": .. }`, without
+/// the `__entity` escape, as in the JSON the parser produces
+fn uid_json(uid: &EntityUid) -> EntityUidJson {
+    EntityUidJson::ImplicitEntityEscape((&uid.0).into())
+}
+
+fn eq_constraint(uid: &EntityUid) -> est::EqConstraint {
+    est::EqConstraint::Entity {
+        entity: uid_json(uid),
+    }
+}
+
+fn in_constraint(uid: &EntityUid) -> est::PrincipalOrResourceInConstraint {
+    est::PrincipalOrResourceInConstraint::Entity {
+        entity: uid_json(uid),
+    }
+}
+
+fn is_constraint(
+    ty: &EntityTypeName,
+    in_entity: Option<&EntityUid>,
+) -> est::PrincipalOrResourceIsConstraint {
+    est::PrincipalOrResourceIsConstraint::new(ty.to_string().into(), in_entity.map(in_constraint))
+}
+
+/// An expression in the JSON policy format, for use in the conditions of a
+/// [`PolicyJson`].
+///
+/// Expressions are built from the variables and literals using methods named
+/// after the Cedar operators, e.g.,
+/// `ExprJson::principal().get_attr("age").greater_eq(ExprJson::long(18))`
+/// for `principal.age >= 18`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExprJson(est::Expr);
+
+impl ExprJson {
+    /// The `principal` variable
+    pub fn principal() -> Self {
+        Self(est::Expr::var(ast::Var::Principal))
+    }
+
+    /// The `action` variable
+    pub fn action() -> Self {
+        Self(est::Expr::var(ast::Var::Action))
+    }
+
+    /// The `resource` variable
+    pub fn resource() -> Self {
+        Self(est::Expr::var(ast::Var::Resource))
+    }
+
+    /// The `context` variable
+    pub fn context() -> Self {
+        Self(est::Expr::var(ast::Var::Context))
+    }
+
+    /// A boolean literal
+    pub fn bool(value: bool) -> Self {
+        Self(ast::Literal::from(value).into())
+    }
+
+    /// An integer literal
+    pub fn long(value: i64) -> Self {
+        Self(ast::Literal::from(value).into())
+    }
+
+    /// A string literal
+    pub fn string(value: impl Into<SmolStr>) -> Self {
+        Self(ast::Literal::from(value.into()).into())
+    }
+
+    /// An entity literal
+    pub fn entity(uid: EntityUid) -> Self {
+        Self(ast::Literal::from(Arc::new(uid.0)).into())
+    }
+
+    /// A set literal
+    pub fn set(elements: impl IntoIterator<Item = Self>) -> Self {
+        Self(est::Expr::set(elements.into_iter().map(|e| e.0).collect()))
+    }
+
+    /// A record literal
+    pub fn record(fields: impl IntoIterator<Item = (String, Self)>) -> Self {
+        Self(est::Expr::record(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.0))
+                .collect::<HashMap<_, _>>(),
+        ))
+    }
+
+    /// A call of the extension function `name`, e.g., `ip` or `decimal`. For
+    /// extension methods, the receiver is the first argument.
+    pub fn call(name: &str, args: impl IntoIterator<Item = Self>) -> Self {
+        Self(est::Expr::ext_call(
+            name.into(),
+            args.into_iter().map(|e| e.0).collect(),
+        ))
+    }
+
+    /// `if test then then_expr else else_expr`
+    pub fn if_then_else(test: Self, then_expr: Self, else_expr: Self) -> Self {
+        Self(est::Expr::ite(test.0, then_expr.0, else_expr.0))
+    }
+
+    /// `self.attr`
+    #[must_use]
+    pub fn get_attr(self, attr: &str) -> Self {
+        Self(est::Expr::get_attr(self.0, attr.into()))
+    }
+
+    /// `self has attr`
+    #[must_use]
+    pub fn has_attr(self, attr: &str) -> Self {
+        Self(est::Expr::has_attr(self.0, attr.into()))
+    }
+
+    /// `self == other`
+    #[must_use]
+    pub fn eq(self, other: Self) -> Self {
+        Self(est::Expr::eq(self.0, other.0))
+    }
+
+    /// `self != other`
+    #[must_use]
+    pub fn not_eq(self, other: Self) -> Self {
+        Self(est::Expr::noteq(self.0, other.0))
+    }
+
+    /// `self < other`
+    #[must_use]
+    pub fn less(self, other: Self) -> Self {
+        Self(est::Expr::less(self.0, other.0))
+    }
+
+    /// `self <= other`
+    #[must_use]
+    pub fn less_eq(self, other: Self) -> Self {
+        Self(est::Expr::lesseq(self.0, other.0))
+    }
+
+    /// `self > other`
+    #[must_use]
+    pub fn greater(self, other: Self) -> Self {
+        Self(est::Expr::greater(self.0, other.0))
+    }
+
+    /// `self >= other`
+    #[must_use]
+    pub fn greater_eq(self, other: Self) -> Self {
+        Self(est::Expr::greatereq(self.0, other.0))
+    }
+
+    /// `self && other`
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        Self(est::Expr::and(self.0, other.0))
+    }
+
+    /// `self || other`
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self(est::Expr::or(self.0, other.0))
+    }
+
+    /// `!self`
+    #[allow(clippy::should_implement_trait)]
+    #[must_use]
+    pub fn not(self) -> Self {
+        Self(est::Expr::not(self.0))
+    }
+
+    /// `-self`
+    #[allow(clippy::should_implement_trait)]
+    #[must_use]
+    pub fn neg(self) -> Self {
+        Self(est::Expr::neg(self.0))
+    }
+
+    /// `self + other`
+    #[allow(clippy::should_implement_trait)]
+    #[must_use]
+    pub fn add(self, other: Self) -> Self {
+        Self(est::Expr::add(self.0, other.0))
+    }
+
+    /// `self - other`
+    #[allow(clippy::should_implement_trait)]
+    #[must_use]
+    pub fn sub(self, other: Self) -> Self {
+        Self(est::Expr::sub(self.0, other.0))
+    }
+
+    /// `self * other`
+    #[allow(clippy::should_implement_trait)]
+    #[must_use]
+    pub fn mul(self, other: Self) -> Self {
+        Self(est::Expr::mul(self.0, other.0))
+    }
+
+    /// `self in other`
+    #[must_use]
+    pub fn is_in(self, other: Self) -> Self {
+        Self(est::Expr::_in(self.0, other.0))
+    }
+
+    /// `self.contains(element)`
+    #[must_use]
+    pub fn contains(self, element: Self) -> Self {
+        Self(est::Expr::contains(Arc::new(self.0), element.0))
+    }
+
+    /// `self.containsAll(other)`
+    #[must_use]
+    pub fn contains_all(self, other: Self) -> Self {
+        Self(est::Expr::contains_all(Arc::new(self.0), other.0))
+    }
+
+    /// `self.containsAny(other)`
+    #[must_use]
+    pub fn contains_any(self, other: Self) -> Self {
+        Self(est::Expr::contains_any(Arc::new(self.0), other.0))
+    }
+
+    /// `self like pattern`, where `*` in `pattern` is a wildcard and `\*` is
+    /// a literal star, as in Cedar syntax. No other escapes are interpreted.
+    #[must_use]
+    pub fn like(self, pattern: &str) -> Self {
+        // one literal per character, as in the JSON the parser produces
+        let mut elems = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    elems.push(est::PatternElem::Literal("*".into()));
+                }
+                '*' => elems.push(est::PatternElem::Wildcard),
+                c => elems.push(est::PatternElem::Literal(c.to_smolstr())),
+            }
+        }
+        Self(est::Expr::like(self.0, elems))
+    }
+
+    /// `self is entity_type`
+    #[must_use]
+    pub fn is_entity_type(self, entity_type: &EntityTypeName) -> Self {
+        Self(est::Expr::is_entity_type(
+            self.0,
+            entity_type.to_string().into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::str::FromStr;
+
+    #[track_caller]
+    fn assert_same(built: PolicyJson, src: &str) {
+        let built: Policy = built.try_into().unwrap();
+        let parsed = Policy::parse(None, src).unwrap();
+        assert_eq!(built.to_json().unwrap(), parsed.to_json().unwrap());
+    }
+
+    #[test]
+    fn scope_constraints() {
+        let group = EntityUid::from_str(r#"Group::"admins""#).unwrap();
+        let view = EntityUid::from_str(r#"Action::"view""#).unwrap();
+        let edit = EntityUid::from_str(r#"Action::"edit""#).unwrap();
+        assert_same(
+            PolicyJson::forbid()
+                .principal(PrincipalConstraint::IsIn(
+                    EntityTypeName::from_str("User").unwrap(),
+                    group,
+                ))
+                .action(ActionConstraint::In(vec![view, edit]))
+                .resource(ResourceConstraint::Is(
+                    EntityTypeName::from_str("Photo").unwrap(),
+                )),
+            r#"forbid(principal is User in Group::"admins", action in [Action::"view", Action::"edit"], resource is Photo);"#,
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        assert_same(
+            PolicyJson::permit()
+                .when(
+                    ExprJson::principal()
+                        .get_attr("age")
+                        .greater_eq(ExprJson::long(18))
+                        .and(ExprJson::resource().get_attr("name").like(r"*.jpg\*")),
+                )
+                .when(
+                    ExprJson::context()
+                        .get_attr("tags")
+                        .contains(ExprJson::string("a"))
+                        .or(ExprJson::set([ExprJson::long(1)])
+                            .contains_any(ExprJson::context().get_attr("nums"))),
+                )
+                .unless(
+                    ExprJson::principal().eq(ExprJson::entity(alice)).not().and(
+                        ExprJson::call("ip", [ExprJson::string("127.0.0.1")])
+                            .eq(ExprJson::context().get_attr("ip")),
+                    ),
+                ),
+            r#"permit(principal, action, resource)
+            when { principal.age >= 18 && resource.name like "*.jpg\*" }
+            when { context.tags.contains("a") || [1].containsAny(context.nums) }
+            unless { !(principal == User::"alice") && ip("127.0.0.1") == context.ip };"#,
+        );
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let built = PolicyJson::permit()
+            .when(ExprJson::record([("a".to_string(), ExprJson::bool(true))]).get_attr("a"))
+            .annotation("doc", "x")
+            .unwrap();
+        let json = built.to_json().unwrap();
+        assert_eq!(json["effect"], "permit");
+        assert_eq!(serde_json::from_value::<PolicyJson>(json).unwrap(), built);
+    }
+
+    #[test]
+    fn errors() {
+        assert_matches!(PolicyJson::permit().annotation("not an id", "x"), Err(_));
+        assert_matches!(
+            Policy::try_from(PolicyJson::permit().when(ExprJson::call("noSuchFunction", []))),
+            Err(_)
+        );
+    }
+}
@@ -0,0 +1,281 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`PolicySummary`], a structured description of a
+//! policy from which documentation generators can render human-readable text.
+
+use super::{
+    ActionConstraint, Effect, Expression, Policy, PolicyId, PrincipalConstraint, ResourceConstraint,
+};
+use cedar_policy_core::ast;
+use ref_cast::RefCast;
+use std::collections::BTreeMap;
+
+/// A structured summary of a [`Policy`]: its effect, the principals, actions,
+/// and resources it applies to, and the conditions under which it applies.
+///
+/// The summary is derived from the parsed policy rather than its text, so it
+/// does not depend on formatting, and the conditions are normalized: the
+/// `when` and `unless` clauses are flattened into a list of [`Condition`]s
+/// which must all hold for the policy to apply.
+///
+/// ```
+/// # use cedar_policy::{Effect, Policy, PrincipalConstraint};
+/// let policy = Policy::parse(None, r#"
+///     @doc("admins may view public photos")
+///     permit(principal in Group::"admins", action == Action::"view", resource)
+///     when { resource.public && context.mfa }
+///     unless { resource.archived };"#).unwrap();
+/// let summary = policy.summary();
+/// assert_eq!(summary.effect(), Effect::Permit);
+/// assert_eq!(summary.annotation("doc"), Some("admins may view public photos"));
+/// assert!(matches!(summary.principal(), PrincipalConstraint::In(_)));
+/// let conditions: Vec<_> = summary
+///     .conditions()
+///     .map(|c| (c.is_negated(), c.to_string()))
+///     .collect();
+/// assert_eq!(conditions, vec![
+///     (false, "resource[\"public\"]".to_string()),
+///     (false, "context[\"mfa\"]".to_string()),
+///     (true, "resource[\"archived\"]".to_string()),
+/// ]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PolicySummary {
+    id: PolicyId,
+    effect: Effect,
+    annotations: BTreeMap<String, String>,
+    principal: PrincipalConstraint,
+    action: ActionConstraint,
+    resource: ResourceConstraint,
+    conditions: Vec<Condition>,
+}
+
+impl PolicySummary {
+    pub(super) fn new(policy: &Policy) -> Self {
+        let mut conditions = Vec::new();
+        collect_conditions(policy.ast.non_scope_constraints(), &mut conditions);
+        Self {
+            id: policy.id().clone(),
+            effect: policy.effect(),
+            annotations: policy
+                .annotations()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            principal: policy.principal_constraint(),
+            action: policy.action_constraint(),
+            resource: policy.resource_constraint(),
+            conditions,
+        }
+    }
+
+    /// The id of the summarized policy
+    pub fn id(&self) -> &PolicyId {
+        &self.id
+    }
+
+    /// Whether the policy permits or forbids
+    pub fn effect(&self) -> Effect {
+        self.effect
+    }
+
+    /// The value of the annotation with the given key, if present
+    pub fn annotation(&self, key: impl AsRef<str>) -> Option<&str> {
+        self.annotations.get(key.as_ref()).map(String::as_str)
+    }
+
+    /// All annotations of the policy, in order of their keys
+    pub fn annotations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.annotations
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// The principals the policy applies to. For template-linked policies,
+    /// slots are replaced with the linked entities.
+    pub fn principal(&self) -> &PrincipalConstraint {
+        &self.principal
+    }
+
+    /// The actions the policy applies to
+    pub fn action(&self) -> &ActionConstraint {
+        &self.action
+    }
+
+    /// The resources the policy applies to. For template-linked policies,
+    /// slots are replaced with the linked entities.
+    pub fn resource(&self) -> &ResourceConstraint {
+        &self.resource
+    }
+
+    /// The conditions which must all hold for the policy to apply, in the
+    /// order they appear in the policy. A policy without `when` or `unless`
+    /// clauses has no conditions.
+    pub fn conditions(&self) -> impl Iterator<Item = &Condition> {
+        self.conditions.iter()
+    }
+}
+
+/// One of the conditions of a [`PolicySummary`].
+///
+/// Conditions are the conjuncts of the policy's `when` and `unless` clauses:
+/// `when { a && b } unless { c }` has the conditions `a`, `b`, and the
+/// negated condition `c`. `Display` renders the condition's expression
+/// (without the negation) in normalized Cedar syntax.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    negated: bool,
+    expr: ast::Expr,
+}
+
+impl Condition {
+    /// Whether the policy requires the expression to be `false` rather than
+    /// `true`, e.g., because it came from an `unless` clause
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    /// The expression of the condition, without the negation
+    pub fn expression(&self) -> &Expression {
+        Expression::ref_cast(&self.expr)
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+/// Flatten the conjunction `expr` into `conditions`, dropping the trivially
+/// true conjuncts which the parser inserts for policies without conditions
+fn collect_conditions(expr: &ast::Expr, conditions: &mut Vec<Condition>) {
+    match expr.expr_kind() {
+        ast::ExprKind::And { left, right } => {
+            collect_conditions(left, conditions);
+            collect_conditions(right, conditions);
+        }
+        ast::ExprKind::Lit(ast::Literal::Bool(true)) => {}
+        ast::ExprKind::UnaryApp {
+            op: ast::UnaryOp::Not,
+            arg,
+        } if !is_desugared_comparison(expr, arg) => conditions.push(Condition {
+            negated: true,
+            expr: arg.as_ref().clone(),
+        }),
+        _ => conditions.push(Condition {
+            negated: false,
+            expr: expr.clone(),
+        }),
+    }
+}
+
+/// The parser represents `a > b` as `!(a <= b)` and `a >= b` as `!(a < b)`,
+/// giving the negation the same source location as the comparison. A source
+/// level `!` or `unless` negation instead covers more of the source than its
+/// operand.
+fn is_desugared_comparison(not: &ast::Expr, arg: &ast::Expr) -> bool {
+    matches!(
+        arg.expr_kind(),
+        ast::ExprKind::BinaryApp {
+            op: ast::BinaryOp::Less | ast::BinaryOp::LessEq,
+            ..
+        }
+    ) && not.source_loc() == arg.source_loc()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EntityUid, Template};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn unconditional() {
+        let summary = Policy::parse(None, "forbid(principal, action, resource);")
+            .unwrap()
+            .summary();
+        assert_eq!(summary.effect(), Effect::Forbid);
+        assert_eq!(summary.principal(), &PrincipalConstraint::Any);
+        assert_eq!(summary.action(), &ActionConstraint::Any);
+        assert_eq!(summary.resource(), &ResourceConstraint::Any);
+        assert_eq!(summary.conditions().count(), 0);
+        assert_eq!(summary.annotations().count(), 0);
+    }
+
+    #[test]
+    fn conditions_are_flattened() {
+        let summary = Policy::parse(
+            None,
+            r#"permit(principal, action in [Action::"a", Action::"b"], resource is Photo)
+            when { principal.level > 3 && (context.x || context.y) }
+            when { !principal.suspended }
+            unless { resource.private && principal != resource.owner };"#,
+        )
+        .unwrap()
+        .summary();
+        assert_eq!(
+            summary.action(),
+            &ActionConstraint::In(vec![
+                EntityUid::from_str(r#"Action::"a""#).unwrap(),
+                EntityUid::from_str(r#"Action::"b""#).unwrap(),
+            ])
+        );
+        assert_eq!(
+            summary.resource(),
+            &ResourceConstraint::Is("Photo".parse().unwrap())
+        );
+        let conditions = summary
+            .conditions()
+            .map(|c| (c.is_negated(), c.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(conditions.len(), 4);
+        // `>` is represented with a negation, but is not a negated condition
+        assert!(!conditions[0].0);
+        assert!(conditions[0].1.contains("level"));
+        assert!(!conditions[1].0);
+        // `when { !e }` and `unless { e }` have the same normalized form
+        assert!(conditions[2].0);
+        assert!(conditions[2].1.contains("suspended"));
+        // `unless` clauses are not split, since their negation is a disjunction
+        assert!(conditions[3].0);
+        assert!(conditions[3].1.contains("private"));
+        assert!(conditions[3].1.contains("owner"));
+    }
+
+    #[test]
+    fn linked_policy() {
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            "permit(principal == ?principal, action, resource) when { context.ok };",
+        )
+        .unwrap();
+        let mut pset = crate::PolicySet::new();
+        pset.add_template(template).unwrap();
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        pset.link(
+            PolicyId::new("t"),
+            PolicyId::new("linked"),
+            HashMap::from([(crate::SlotId::principal(), alice.clone())]),
+        )
+        .unwrap();
+        let summary = pset.policy(&PolicyId::new("linked")).unwrap().summary();
+        assert_eq!(summary.id(), &PolicyId::new("linked"));
+        assert_eq!(summary.principal(), &PrincipalConstraint::Eq(alice));
+        assert_eq!(summary.conditions().count(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`PrefilteredPolicySet`], a policy set compiled with a
+//! pre-filter which denies requests without evaluating any permit policy
+//! when no permit policy's scope matches the request.
+
+use super::{Entities, PolicySet, Request, Response, Schema};
+use cedar_policy_core::ast::{
+    self, ActionConstraint, EntityReference, EntityType, EntityUID, PrincipalOrResourceConstraint,
+};
+use cedar_policy_core::authorizer;
+use cedar_policy_core::entities::Dereference;
+use cedar_policy_validator::ValidatorActionId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A policy set compiled for a schema into a pre-filter per action.
+///
+/// For each action of the schema, the pre-filter keeps the policies whose
+/// action scope matches the action, and the principal and resource scopes of
+/// the permit policies among them. These scopes only depend on the principal,
+/// the resource, and the entity hierarchy, not on the context or on
+/// attributes. A request which matches none of these scopes is denied
+/// after evaluating only the forbid policies of its action, which determine
+/// the reasons and errors of the response; other requests are evaluated
+/// against the policies of their action whose scope allows the type of their
+/// resource, using the `is` constraint of resource scopes, e.g.,
+/// `resource is Photo in ?folder`. Either way, the response is the same as
+/// that of evaluating the request against the whole policy set.
+///
+/// Requests for actions which are not in the schema, and requests with
+/// unknowns, are evaluated against the whole policy set.
+///
+/// ```
+/// # use cedar_policy::{Context, Decision, Entities, EntityUid, PolicySet, PrefilteredPolicySet, Request, Schema};
+/// # use std::str::FromStr;
+/// let schema: Schema = r#"
+///     entity User;
+///     entity Photo { public: Bool };
+///     action view appliesTo { principal: User, resource: Photo };
+/// "#.parse().unwrap();
+/// let policies: PolicySet = r#"
+///     permit(principal == User::"alice", action, resource) when { resource.public };
+/// "#.parse().unwrap();
+/// let prefiltered = PrefilteredPolicySet::new(&policies, &schema);
+/// let request = |principal: &str| Request::new(
+///     EntityUid::from_str(principal).unwrap(),
+///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+///     EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+///     Context::empty(),
+///     None,
+/// ).unwrap();
+/// let entities = Entities::empty();
+/// let response = prefiltered.is_authorized(&request(r#"User::"bob""#), &entities);
+/// assert_eq!(response.decision(), Decision::Deny);
+/// // evaluating `resource.public` fails since `Photo::"p"` is not an entity
+/// let response = prefiltered.is_authorized(&request(r#"User::"alice""#), &entities);
+/// assert_eq!(response.decision(), Decision::Deny);
+/// assert_eq!(response.diagnostics().errors().count(), 1);
+/// let stats = prefiltered.stats();
+/// assert_eq!((stats.requests(), stats.skipped()), (2, 1));
+/// ```
+#[derive(Debug)]
+pub struct PrefilteredPolicySet {
+    /// All policies, for requests the pre-filter does not apply to
+    policies: ast::PolicySet,
+    /// Pre-filter for each action of the schema
+    by_action: HashMap<EntityUID, ActionFilter>,
+    authorizer: authorizer::Authorizer,
+    requests: AtomicU64,
+    skipped: AtomicU64,
+}
+
+#[derive(Debug)]
+struct ActionFilter {
+    /// The principal and resource scopes of the permit policies applicable to
+    /// the action
+    permits: Vec<(PrincipalOrResourceConstraint, PrincipalOrResourceConstraint)>,
+    /// The policies applicable to the action
+    policies: ast::PolicySet,
+    /// The forbid policies applicable to the action
+    forbids: ast::PolicySet,
+    /// For each resource type of the action, the policies applicable to the
+    /// action whose resource scope allows that type
+    by_resource_type: HashMap<EntityType, ast::PolicySet>,
+}
+
+impl PrefilteredPolicySet {
+    /// Compile the pre-filter of `policies` for the actions of `schema`
+    pub fn new(policies: &PolicySet, schema: &Schema) -> Self {
+        let mut by_action = HashMap::new();
+        for action in schema.0.actions() {
+            let mut filter = ActionFilter {
+                permits: Vec::new(),
+                policies: ast::PolicySet::new(),
+                forbids: ast::PolicySet::new(),
+                by_resource_type: HashMap::new(),
+            };
+            for policy in policies.ast.policies() {
+                let applies = match policy.action_constraint() {
+                    ActionConstraint::Any => true,
+                    ActionConstraint::Eq(euid) => euid.as_ref() == action,
+                    ActionConstraint::In(euids) => euids.iter().any(|euid| {
+                        euid.as_ref() == action
+                            || schema
+                                .0
+                                .get_action_id(euid)
+                                .is_some_and(|group| group.has_descendant(action))
+                    }),
+                };
+                if !applies {
+                    continue;
+                }
+                // PANIC SAFETY: the policies come from a valid policy set, so
+                // they have distinct ids and consistent templates
+                #[allow(clippy::expect_used)]
+                match policy.effect() {
+                    ast::Effect::Permit => filter.permits.push((
+                        policy.principal_constraint().into_inner(),
+                        policy.resource_constraint().into_inner(),
+                    )),
+                    ast::Effect::Forbid => filter
+                        .forbids
+                        .add(policy.clone())
+                        .expect("a subset of a policy set is a valid policy set"),
+                }
+                // PANIC SAFETY: as above
+                #[allow(clippy::expect_used)]
+                filter
+                    .policies
+                    .add(policy.clone())
+                    .expect("a subset of a policy set is a valid policy set");
+            }
+            let resource_types = schema
+                .0
+                .get_action_id(action)
+                .into_iter()
+                .flat_map(ValidatorActionId::applies_to_resources);
+            for resource_type in resource_types {
+                let mut policies = ast::PolicySet::new();
+                for policy in filter.policies.policies().filter(|policy| {
+                    policy
+                        .resource_constraint()
+                        .as_inner()
+                        .get_entity_type()
+                        .map_or(true, |ty| ty == resource_type)
+                }) {
+                    // PANIC SAFETY: as above
+                    #[allow(clippy::expect_used)]
+                    policies
+                        .add(policy.clone())
+                        .expect("a subset of a policy set is a valid policy set");
+                }
+                filter
+                    .by_resource_type
+                    .insert(resource_type.clone(), policies);
+            }
+            by_action.insert(action.clone(), filter);
+        }
+        Self {
+            policies: policies.ast.clone(),
+            by_action,
+            authorizer: authorizer::Authorizer::new(),
+            requests: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns an authorization response for `r` with respect to the policies
+    /// and `e`, which is the same as that of [`super::Authorizer::is_authorized`].
+    pub fn is_authorized(&self, r: &Request, e: &Entities) -> Response {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let (Some(principal), Some(action), Some(resource)) = (
+            r.0.principal().uid(),
+            r.0.action().uid(),
+            r.0.resource().uid(),
+        ) else {
+            return self
+                .authorizer
+                .is_authorized(r.0.clone(), &self.policies, &e.0)
+                .into();
+        };
+        let Some(filter) = self.by_action.get(action) else {
+            return self
+                .authorizer
+                .is_authorized(r.0.clone(), &self.policies, &e.0)
+                .into();
+        };
+        let matched = filter
+            .permits
+            .iter()
+            .any(|(p, r)| scope_matches(p, principal, &e.0) && scope_matches(r, resource, &e.0));
+        let policies = if matched {
+            // policies whose resource scope requires another type don't apply
+            filter
+                .by_resource_type
+                .get(resource.entity_type())
+                .unwrap_or(&filter.policies)
+        } else {
+            // no permit policy applies, so the request is denied, but forbid
+            // policies still determine the reasons and errors
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            &filter.forbids
+        };
+        self.authorizer
+            .is_authorized(r.0.clone(), policies, &e.0)
+            .into()
+    }
+
+    /// Statistics on the requests authorized so far
+    pub fn stats(&self) -> PrefilterStats {
+        PrefilterStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Whether the principal or resource scope `constraint` of a policy matches
+/// `euid`. `constraint` comes from a static or linked policy, so it has no
+/// slots.
+fn scope_matches(
+    constraint: &PrincipalOrResourceConstraint,
+    euid: &EntityUID,
+    entities: &cedar_policy_core::entities::Entities,
+) -> bool {
+    let is_in = |ancestor: &EntityUID| {
+        euid == ancestor
+            || match entities.entity(euid) {
+                Dereference::Data(entity) => entities.is_descendant_of(entity, ancestor),
+                Dereference::NoSuchEntity => false,
+                // the hierarchy is not known, so the scope may match
+                Dereference::Residual(_) => true,
+            }
+    };
+    match constraint {
+        PrincipalOrResourceConstraint::Eq(EntityReference::EUID(e)) => euid == e.as_ref(),
+        PrincipalOrResourceConstraint::In(EntityReference::EUID(e)) => is_in(e),
+        PrincipalOrResourceConstraint::Is(ty) => euid.entity_type() == ty.as_ref(),
+        PrincipalOrResourceConstraint::IsIn(ty, EntityReference::EUID(e)) => {
+            euid.entity_type() == ty.as_ref() && is_in(e)
+        }
+        PrincipalOrResourceConstraint::Any
+        | PrincipalOrResourceConstraint::Eq(EntityReference::Slot)
+        | PrincipalOrResourceConstraint::In(EntityReference::Slot)
+        | PrincipalOrResourceConstraint::IsIn(_, EntityReference::Slot) => true,
+    }
+}
+
+/// Statistics of a [`PrefilteredPolicySet`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefilterStats {
+    requests: u64,
+    skipped: u64,
+}
+
+impl PrefilterStats {
+    /// The number of requests authorized
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// The number of requests denied by the pre-filter, without evaluating
+    /// any permit policy
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// The fraction of requests denied by the pre-filter, or `0.0` if no
+    /// request was authorized
+    // precision is only lost for counts above 2^53, which is fine for a rate
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.skipped as f64 / self.requests as f64
+        }
+    }
+}
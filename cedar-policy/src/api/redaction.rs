@@ -0,0 +1,292 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`Redaction`] and [`Redacted`], a display mode for
+//! requests, contexts, and entities which masks attribute values.
+
+use super::{ast, Context, Entity, Request};
+use itertools::Itertools;
+use smol_str::SmolStr;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+
+/// Placeholder displayed instead of a masked value
+const REDACTED: &str = "<redacted>";
+
+/// Sets with more elements are displayed as their number of elements
+const MAX_DISPLAYED_SET_ELEMENTS: usize = 5;
+
+/// The attribute values which the redacted display of requests, contexts,
+/// and entities shows, e.g., in debug logs and error messages which must not
+/// leak personal data from context or entity attributes. All other values are
+/// masked, while records and sets keep their structure. Entity uids are
+/// always shown.
+///
+/// An attribute is identified by its path from the context or the entity's
+/// attributes, e.g., `ip` or `user.department`. Allowing an attribute shows
+/// its whole value.
+///
+/// ```
+/// # use cedar_policy::{Context, Redaction};
+/// let context = Context::from_json_str(
+///     r#"{ "ip": "10.0.0.1", "user": { "email": "alice@example.com", "department": "sales" } }"#,
+///     None,
+/// )
+/// .unwrap();
+/// let redaction = Redaction::new().allow("ip").allow("user.department");
+/// assert_eq!(
+///     context.redacted(&redaction).to_string(),
+///     r#"{"ip": "10.0.0.1", "user": {"department": "sales", "email": <redacted>}}"#,
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redaction {
+    allowed: BTreeSet<String>,
+}
+
+impl Redaction {
+    /// Mask all attribute values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the value of the attribute at `path`, a `.`-separated sequence
+    /// of attribute names
+    #[must_use]
+    pub fn allow(mut self, path: impl Into<String>) -> Self {
+        self.allowed.insert(path.into());
+        self
+    }
+
+    fn is_allowed(&self, path: &str) -> bool {
+        self.allowed.contains(path)
+    }
+
+    fn write_value(&self, f: &mut Formatter<'_>, path: &str, v: &ast::Value) -> fmt::Result {
+        if self.is_allowed(path) {
+            return write!(f, "{v}");
+        }
+        match v.value_kind() {
+            ast::ValueKind::Lit(ast::Literal::EntityUID(uid)) => write!(f, "{uid}"),
+            ast::ValueKind::Record(attrs) => {
+                Self::write_record(f, path, attrs.iter(), |f, p, v| self.write_value(f, p, v))
+            }
+            ast::ValueKind::Set(set) => {
+                Self::write_set(f, path, set.len(), set.iter(), |f, p, v| {
+                    self.write_value(f, p, v)
+                })
+            }
+            ast::ValueKind::Lit(_) | ast::ValueKind::ExtensionValue(_) => write!(f, "{REDACTED}"),
+        }
+    }
+
+    /// `e` is a residual, i.e., a restricted expression containing unknowns
+    fn write_expr(&self, f: &mut Formatter<'_>, path: &str, e: &ast::Expr) -> fmt::Result {
+        if self.is_allowed(path) {
+            return write!(f, "{e}");
+        }
+        match e.expr_kind() {
+            ast::ExprKind::Lit(ast::Literal::EntityUID(uid)) => write!(f, "{uid}"),
+            ast::ExprKind::Unknown(_) => write!(f, "{e}"),
+            ast::ExprKind::Record(attrs) => {
+                Self::write_record(f, path, attrs.iter(), |f, p, e| self.write_expr(f, p, e))
+            }
+            ast::ExprKind::Set(elems) => {
+                Self::write_set(f, path, elems.len(), elems.iter(), |f, p, e| {
+                    self.write_expr(f, p, e)
+                })
+            }
+            _ => write!(f, "{REDACTED}"),
+        }
+    }
+
+    fn write_partial_value(
+        &self,
+        f: &mut Formatter<'_>,
+        path: &str,
+        v: &ast::PartialValue,
+    ) -> fmt::Result {
+        match v {
+            ast::PartialValue::Value(v) => self.write_value(f, path, v),
+            ast::PartialValue::Residual(e) => self.write_expr(f, path, e),
+        }
+    }
+
+    /// Write the attributes `attrs` of the record at `path` in order of their
+    /// names
+    fn write_record<'a, T: 'a>(
+        f: &mut Formatter<'_>,
+        path: &str,
+        attrs: impl Iterator<Item = (&'a SmolStr, &'a T)>,
+        write: impl Fn(&mut Formatter<'_>, &str, &T) -> fmt::Result,
+    ) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (attr, v)) in attrs.sorted_by_key(|(attr, _)| *attr).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{attr:?}: ")?;
+            if path.is_empty() {
+                write(f, attr, v)?;
+            } else {
+                write(f, &format!("{path}.{attr}"), v)?;
+            }
+        }
+        write!(f, "}}")
+    }
+
+    /// Write the `len` elements `elems` of the set at `path`. Elements have the
+    /// same path as the set.
+    fn write_set<'a, T: 'a>(
+        f: &mut Formatter<'_>,
+        path: &str,
+        len: usize,
+        elems: impl Iterator<Item = &'a T>,
+        write: impl Fn(&mut Formatter<'_>, &str, &T) -> fmt::Result,
+    ) -> fmt::Result {
+        if len > MAX_DISPLAYED_SET_ELEMENTS {
+            return write!(f, "<set with {len} elements>");
+        }
+        write!(f, "[")?;
+        for (i, v) in elems.enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write(f, path, v)?;
+        }
+        write!(f, "]")
+    }
+
+    fn write_context(&self, f: &mut Formatter<'_>, context: &ast::Context) -> fmt::Result {
+        match context {
+            ast::Context::Value(attrs) => {
+                Self::write_record(f, "", attrs.iter(), |f, p, v| self.write_value(f, p, v))
+            }
+            ast::Context::RestrictedResidual(attrs) => {
+                Self::write_record(f, "", attrs.iter(), |f, p, e| self.write_expr(f, p, e))
+            }
+        }
+    }
+}
+
+/// Display of a [`Request`], [`Context`], or [`Entity`] which masks the
+/// attribute values not allowed by a [`Redaction`]
+#[derive(Debug, Clone, Copy)]
+pub struct Redacted<'a, T> {
+    inner: &'a T,
+    redaction: &'a Redaction,
+}
+
+impl<'a, T> Redacted<'a, T> {
+    pub(crate) fn new(inner: &'a T, redaction: &'a Redaction) -> Self {
+        Self { inner, redaction }
+    }
+}
+
+impl Display for Redacted<'_, Request> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let request = &self.inner.0;
+        let euid = |entry: &ast::EntityUIDEntry| match entry {
+            ast::EntityUIDEntry::Known { euid, .. } => euid.to_string(),
+            ast::EntityUIDEntry::Unknown { .. } => "unknown".to_string(),
+        };
+        write!(
+            f,
+            "request with principal {}, action {}, resource {}, and context ",
+            euid(request.principal()),
+            euid(request.action()),
+            euid(request.resource()),
+        )?;
+        match request.context() {
+            Some(context) => self.redaction.write_context(f, context),
+            None => write!(f, "unknown"),
+        }
+    }
+}
+
+impl Display for Redacted<'_, Context> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.redaction.write_context(f, &self.inner.0)
+    }
+}
+
+impl Display for Redacted<'_, Entity> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let entity = &self.inner.0;
+        write!(f, "{}:\n  attrs:", entity.uid())?;
+        for (i, (attr, v)) in entity.attrs().sorted_by_key(|(attr, _)| *attr).enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, " {attr}: ")?;
+            self.redaction.write_partial_value(f, attr, v)?;
+        }
+        write!(
+            f,
+            "\n  ancestors:{}",
+            entity.ancestors().sorted().join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EntityUid, RestrictedExpression};
+    use std::collections::{HashMap, HashSet};
+    use std::str::FromStr;
+
+    #[test]
+    fn redacted_entity_and_request() {
+        let entity = Entity::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            HashMap::from([
+                (
+                    "ssn".to_string(),
+                    RestrictedExpression::new_string("123-45-6789".into()),
+                ),
+                ("age".to_string(), RestrictedExpression::new_long(42)),
+                (
+                    "manager".to_string(),
+                    RestrictedExpression::from_str(r#"User::"bob""#).unwrap(),
+                ),
+                (
+                    "tags".to_string(),
+                    RestrictedExpression::from_str(r#"["a", "b"]"#).unwrap(),
+                ),
+            ]),
+            HashSet::from([EntityUid::from_str(r#"Group::"admins""#).unwrap()]),
+        )
+        .unwrap();
+        assert_eq!(
+            entity.redacted(&Redaction::new().allow("age")).to_string(),
+            "User::\"alice\":\n  attrs: age: 42; manager: User::\"bob\"; ssn: <redacted>; tags: [<redacted>, <redacted>]\n  ancestors:Group::\"admins\""
+        );
+
+        let request = Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"Photo::"beach""#).unwrap(),
+            Context::from_json_str(r#"{"token": "secret", "mfa": true}"#, None).unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            request.redacted(&Redaction::new().allow("mfa")).to_string(),
+            r#"request with principal User::"alice", action Action::"view", resource Photo::"beach", and context {"mfa": true, "token": <redacted>}"#
+        );
+    }
+}
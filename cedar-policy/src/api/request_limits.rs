@@ -0,0 +1,270 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`RequestLimits`], which bound the size of the
+//! contexts of requests.
+
+use super::{ast, Context};
+use miette::Diagnostic;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+/// Limits on the size of the context of a request, which protect a policy
+/// decision point from abusive callers: requests exceeding them are rejected
+/// when constructed with [`super::Request::new_with_limits`] or
+/// [`super::Context::from_json_str_with_limits`], before evaluation starts.
+/// By default, there are no limits.
+///
+/// ```
+/// # use cedar_policy::{Context, RequestLimitError, RequestLimits};
+/// let limits = RequestLimits::new()
+///     .with_max_context_attributes(16)
+///     .with_max_string_length(8);
+/// assert!(Context::from_json_str_with_limits(r#"{"ip": "10.0.0.1"}"#, None, &limits).is_ok());
+/// let err = limits
+///     .check(&Context::from_json_str(r#"{"user": {"name": "a very long name"}}"#, None).unwrap())
+///     .unwrap_err();
+/// assert!(matches!(err, RequestLimitError::StringTooLong { .. }));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestLimits {
+    context_attributes: Option<usize>,
+    string_length: Option<usize>,
+    set_size: Option<usize>,
+}
+
+impl RequestLimits {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of top-level attributes of the context
+    #[must_use]
+    pub fn with_max_context_attributes(mut self, max: usize) -> Self {
+        self.context_attributes = Some(max);
+        self
+    }
+
+    /// Limit the length in bytes of each string anywhere in the context
+    #[must_use]
+    pub fn with_max_string_length(mut self, max: usize) -> Self {
+        self.string_length = Some(max);
+        self
+    }
+
+    /// Limit the number of elements of each set anywhere in the context
+    #[must_use]
+    pub fn with_max_set_size(mut self, max: usize) -> Self {
+        self.set_size = Some(max);
+        self
+    }
+
+    /// Check that `context` is within these limits, returning the first limit
+    /// it exceeds otherwise
+    pub fn check(&self, context: &Context) -> Result<(), RequestLimitError> {
+        let attrs: Vec<(&SmolStr, Item<'_>)> = match &context.0 {
+            ast::Context::Value(attrs) => attrs.iter().map(|(k, v)| (k, Item::Value(v))).collect(),
+            ast::Context::RestrictedResidual(attrs) => {
+                attrs.iter().map(|(k, e)| (k, Item::Expr(e))).collect()
+            }
+        };
+        if let Some(limit) = self.context_attributes {
+            if attrs.len() > limit {
+                return Err(RequestLimitError::TooManyContextAttributes {
+                    actual: attrs.len(),
+                    limit,
+                });
+            }
+        }
+        if self.string_length.is_none() && self.set_size.is_none() {
+            return Ok(());
+        }
+        for (attr, item) in attrs {
+            let mut stack = vec![item];
+            while let Some(item) = stack.pop() {
+                match item {
+                    Item::Value(v) => match v.value_kind() {
+                        ast::ValueKind::Lit(ast::Literal::String(s)) => {
+                            self.check_string(attr, s)?;
+                        }
+                        ast::ValueKind::Set(set) => {
+                            self.check_set(attr, set.len())?;
+                            stack.extend(set.iter().map(Item::Value));
+                        }
+                        ast::ValueKind::Record(attrs) => {
+                            stack.extend(attrs.values().map(Item::Value));
+                        }
+                        ast::ValueKind::Lit(_) | ast::ValueKind::ExtensionValue(_) => {}
+                    },
+                    Item::Expr(e) => {
+                        for e in e.subexpressions() {
+                            match e.expr_kind() {
+                                ast::ExprKind::Lit(ast::Literal::String(s)) => {
+                                    self.check_string(attr, s)?;
+                                }
+                                ast::ExprKind::Set(elems) => {
+                                    self.check_set(attr, elems.len())?;
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_string(&self, attr: &SmolStr, s: &str) -> Result<(), RequestLimitError> {
+        match self.string_length {
+            Some(limit) if s.len() > limit => Err(RequestLimitError::StringTooLong {
+                attr: attr.to_string(),
+                actual: s.len(),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_set(&self, attr: &SmolStr, len: usize) -> Result<(), RequestLimitError> {
+        match self.set_size {
+            Some(limit) if len > limit => Err(RequestLimitError::SetTooLarge {
+                attr: attr.to_string(),
+                actual: len,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A value in a concrete context, or an expression in a residual one
+enum Item<'a> {
+    Value(&'a ast::Value),
+    Expr(&'a ast::Expr),
+}
+
+/// A request exceeds a [`RequestLimits`]
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum RequestLimitError {
+    /// The context has too many attributes
+    #[error("context has {actual} attributes, more than the limit of {limit}")]
+    TooManyContextAttributes {
+        /// Number of attributes of the context
+        actual: usize,
+        /// The limit
+        limit: usize,
+    },
+    /// A string in the context is too long
+    #[error(
+        "context attribute `{attr}` contains a string of {actual} bytes, more than the limit of {limit}"
+    )]
+    StringTooLong {
+        /// The top-level context attribute containing the string
+        attr: String,
+        /// Length of the string in bytes
+        actual: usize,
+        /// The limit
+        limit: usize,
+    },
+    /// A set in the context has too many elements
+    #[error(
+        "context attribute `{attr}` contains a set of {actual} elements, more than the limit of {limit}"
+    )]
+    SetTooLarge {
+        /// The top-level context attribute containing the set
+        attr: String,
+        /// Number of elements of the set
+        actual: usize,
+        /// The limit
+        limit: usize,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn limits() {
+        let context = Context::from_json_value(
+            serde_json::json!({
+                "tags": ["a", "b", "c"],
+                "user": { "name": "alice", "groups": [["x", "yyyy"]] },
+            }),
+            None,
+        )
+        .unwrap();
+        assert_matches!(RequestLimits::new().check(&context), Ok(()));
+        assert_matches!(
+            RequestLimits::new()
+                .with_max_context_attributes(2)
+                .with_max_string_length(5)
+                .with_max_set_size(3)
+                .check(&context),
+            Ok(())
+        );
+        assert_matches!(
+            RequestLimits::new()
+                .with_max_context_attributes(1)
+                .check(&context),
+            Err(RequestLimitError::TooManyContextAttributes {
+                actual: 2,
+                limit: 1
+            })
+        );
+        assert_matches!(
+            RequestLimits::new().with_max_string_length(4).check(&context),
+            Err(RequestLimitError::StringTooLong { attr, actual: 5, limit: 4 }) => {
+                assert_eq!(attr, "user");
+            }
+        );
+        assert_matches!(
+            RequestLimits::new().with_max_set_size(2).check(&context),
+            Err(RequestLimitError::SetTooLarge { attr, actual: 3, limit: 2 }) => {
+                assert_eq!(attr, "tags");
+            }
+        );
+    }
+
+    #[cfg(feature = "partial-eval")]
+    #[test]
+    fn residual_limits() {
+        use crate::RestrictedExpression;
+
+        let context = Context::from_pairs([(
+            "tags".to_string(),
+            RestrictedExpression::new_set([
+                RestrictedExpression::new_string("aaaaaa".into()),
+                RestrictedExpression::new_unknown("u"),
+            ]),
+        )])
+        .unwrap();
+        assert_matches!(
+            RequestLimits::new()
+                .with_max_string_length(5)
+                .check(&context),
+            Err(RequestLimitError::StringTooLong { actual: 6, .. })
+        );
+        assert_matches!(
+            RequestLimits::new().with_max_set_size(1).check(&context),
+            Err(RequestLimitError::SetTooLarge { actual: 2, .. })
+        );
+    }
+}
@@ -0,0 +1,511 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`Residuals`], a stable serialization of the residuals
+//! of partial authorization.
+
+use super::{
+    ast, Effect, Entities, EntityUid, Expression, PartialResponse, PolicyId, StableAstError,
+};
+use cedar_policy_core::stable_ast;
+use itertools::Itertools;
+use ref_cast::RefCast;
+use smol_str::SmolStr;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The residuals of a partial authorization, in a stable JSON format for
+/// shipping them from the policy decision point to services which apply them,
+/// e.g., as query filters.
+///
+/// The format is versioned together with the stable AST format of
+/// [`super::PolicySet::to_stable_ast`], by [`super::STABLE_AST_VERSION`]:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "residuals": [{ "id": "policy0", "effect": "permit", "condition": ... }],
+///   "unknowns": { "principal": [{ "kind": "principal" }] }
+/// }
+/// ```
+///
+/// Conditions are encoded like the conditions of policies in the stable AST
+/// format, and unknowns as
+/// `{ "unknown": { "name": "principal", "typeAnnotation": { "kind": "entity", "name": "User" } } }`,
+/// where the type annotation is optional. Residuals are sorted by policy id.
+/// The optional `unknowns` field records where the values of the unknowns
+/// come from, see [`UnknownProvenance`].
+///
+/// Only the policies which may be satisfied are included, with a condition of
+/// `{ "bool": true }` for the policies which are satisfied. So, the request is
+/// allowed if and only if the condition of some `permit` residual holds and
+/// the condition of no `forbid` residual does, where a condition whose
+/// evaluation fails does not hold.
+///
+/// ```
+/// # use cedar_policy::{Authorizer, Context, Entities, EntityUid, PolicySet, RequestBuilder, Residuals, UnknownProvenance};
+/// # use std::str::FromStr;
+/// let pset = PolicySet::from_str(r#"
+///     permit(principal in Group::"admins", action, resource);
+///     forbid(principal, action, resource) when { false };
+/// "#).unwrap();
+/// let request = RequestBuilder::default()
+///     .action(EntityUid::from_str(r#"Action::"view""#).unwrap())
+///     .resource(EntityUid::from_str(r#"Photo::"beach""#).unwrap())
+///     .context(Context::empty())
+///     .build();
+/// let entities = Entities::empty();
+/// let response = Authorizer::new().is_authorized_partial(&request, &pset, &entities);
+///
+/// let json = Residuals::from_response(&response, &entities).to_json_value().unwrap();
+/// let residuals = Residuals::from_json_value(json).unwrap();
+/// assert_eq!(residuals.iter().count(), 1);
+/// assert_eq!(
+///     residuals.provenance("principal").collect::<Vec<_>>(),
+///     [&UnknownProvenance::Principal],
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Residuals {
+    residuals: Vec<Residual>,
+    unknowns: BTreeMap<SmolStr, Vec<UnknownProvenance>>,
+}
+
+/// A residual of a policy in [`Residuals`]
+#[derive(Debug, Clone)]
+pub struct Residual {
+    id: PolicyId,
+    effect: Effect,
+    condition: Expression,
+}
+
+impl Residual {
+    /// The id of the policy
+    pub fn id(&self) -> &PolicyId {
+        &self.id
+    }
+
+    /// The effect of the policy
+    pub fn effect(&self) -> Effect {
+        self.effect
+    }
+
+    /// The condition on the unknowns under which the policy is satisfied
+    pub fn condition(&self) -> &Expression {
+        &self.condition
+    }
+
+    /// The names of the unknowns in the condition, without duplicates
+    pub fn unknowns(&self) -> impl Iterator<Item = &str> {
+        self.condition
+            .0
+            .unknowns()
+            .map(|u| u.name.as_str())
+            .unique()
+    }
+}
+
+/// Where the value of an unknown in [`Residuals`] comes from, e.g., to map it
+/// to a database column
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnknownProvenance {
+    /// The unknown principal of the request
+    Principal,
+    /// The unknown action of the request
+    Action,
+    /// The unknown resource of the request
+    Resource,
+    /// The unknown context of the request
+    Context,
+    /// The value at `path` in the context. The elements of a set have the
+    /// path of the set.
+    ContextAttribute {
+        /// Attribute names, from the context
+        path: Vec<String>,
+    },
+    /// The value at `path` in the attributes of `entity`. The elements of a
+    /// set have the path of the set.
+    EntityAttribute {
+        /// The entity
+        entity: EntityUid,
+        /// Attribute names, from the attributes of the entity
+        path: Vec<String>,
+    },
+}
+
+impl From<&UnknownProvenance> for stable_ast::Provenance {
+    fn from(provenance: &UnknownProvenance) -> Self {
+        match provenance {
+            UnknownProvenance::Principal => Self::Principal,
+            UnknownProvenance::Action => Self::Action,
+            UnknownProvenance::Resource => Self::Resource,
+            UnknownProvenance::Context => Self::Context,
+            UnknownProvenance::ContextAttribute { path } => Self::ContextAttribute {
+                path: path.iter().map(SmolStr::new).collect(),
+            },
+            UnknownProvenance::EntityAttribute { entity, path } => Self::EntityAttribute {
+                entity: entity.as_ref().into(),
+                path: path.iter().map(SmolStr::new).collect(),
+            },
+        }
+    }
+}
+
+impl TryFrom<stable_ast::Provenance> for UnknownProvenance {
+    type Error = StableAstError;
+
+    fn try_from(provenance: stable_ast::Provenance) -> Result<Self, Self::Error> {
+        Ok(match provenance {
+            stable_ast::Provenance::Principal => Self::Principal,
+            stable_ast::Provenance::Action => Self::Action,
+            stable_ast::Provenance::Resource => Self::Resource,
+            stable_ast::Provenance::Context => Self::Context,
+            stable_ast::Provenance::ContextAttribute { path } => Self::ContextAttribute {
+                path: path.into_iter().map(String::from).collect(),
+            },
+            stable_ast::Provenance::EntityAttribute { entity, path } => Self::EntityAttribute {
+                entity: ast::EntityUID::try_from(entity)?.into(),
+                path: path.into_iter().map(String::from).collect(),
+            },
+        })
+    }
+}
+
+impl Residuals {
+    /// The residuals of the policies of `response` which may be satisfied,
+    /// with the provenance of their unknowns in the request of `response` and
+    /// in `entities`, the entities it was computed with
+    pub fn from_response(response: &PartialResponse, entities: &Entities) -> Self {
+        let response = &response.0;
+        let mut residuals = Vec::new();
+        for (effect, satisfied, residual) in [
+            (
+                Effect::Permit,
+                &response.satisfied_permits,
+                &response.residual_permits,
+            ),
+            (
+                Effect::Forbid,
+                &response.satisfied_forbids,
+                &response.residual_forbids,
+            ),
+        ] {
+            residuals.extend(satisfied.keys().map(|id| Residual {
+                id: PolicyId::ref_cast(id).clone(),
+                effect,
+                condition: Expression(ast::Expr::val(true)),
+            }));
+            residuals.extend(residual.iter().map(|(id, (condition, _))| Residual {
+                id: PolicyId::ref_cast(id).clone(),
+                effect,
+                condition: Expression(condition.as_ref().clone()),
+            }));
+        }
+        residuals.sort_by(|a, b| AsRef::<str>::as_ref(&a.id).cmp(b.id.as_ref()));
+        let unknowns = provenance(&residuals, response.request(), &entities.0);
+        Self {
+            residuals,
+            unknowns,
+        }
+    }
+
+    /// Iterate over the residuals, in order of their policy ids
+    pub fn iter(&self) -> impl Iterator<Item = &Residual> {
+        self.residuals.iter()
+    }
+
+    /// Where the value of the unknown named `unknown` comes from. There may be
+    /// several places if the same name is used for several unknowns, and none
+    /// if the unknown doesn't occur in the residuals or its provenance is not
+    /// known, e.g., for an unknown entity.
+    pub fn provenance(&self, unknown: &str) -> impl Iterator<Item = &UnknownProvenance> {
+        self.unknowns.get(unknown).into_iter().flatten()
+    }
+
+    /// Load residuals serialized with [`Residuals::to_json_value`] by this or
+    /// an earlier version of this crate
+    pub fn from_json_value(value: serde_json::Value) -> Result<Self, StableAstError> {
+        let stored = stable_ast::residuals_from_json_value(value)?;
+        let residuals = stored
+            .residuals
+            .into_iter()
+            .map(|r| {
+                Ok(Residual {
+                    id: PolicyId::new(&r.id),
+                    effect: r.effect.into(),
+                    condition: Expression(r.condition.try_into()?),
+                })
+            })
+            .collect::<Result<Vec<_>, StableAstError>>()?;
+        let unknowns = stored
+            .unknowns
+            .into_iter()
+            .map(|(name, provenance)| {
+                Ok((
+                    name,
+                    provenance
+                        .into_iter()
+                        .map(UnknownProvenance::try_from)
+                        .collect::<Result<Vec<_>, _>>()?,
+                ))
+            })
+            .collect::<Result<_, StableAstError>>()?;
+        Ok(Self {
+            residuals,
+            unknowns,
+        })
+    }
+
+    /// Load residuals from a JSON string, as described in
+    /// [`Residuals::from_json_value`]
+    pub fn from_json_str(json: &str) -> Result<Self, StableAstError> {
+        Self::from_json_value(serde_json::from_str(json)?)
+    }
+
+    /// Serialize the residuals in the format described in [`Residuals`]
+    pub fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self.to_stable())
+    }
+
+    /// Serialize the residuals as a JSON string
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.to_stable())
+    }
+
+    fn to_stable(&self) -> stable_ast::Residuals {
+        stable_ast::Residuals {
+            version: stable_ast::VERSION,
+            residuals: self
+                .residuals
+                .iter()
+                .map(|r| stable_ast::Residual {
+                    id: SmolStr::new(AsRef::<str>::as_ref(&r.id)),
+                    effect: r.effect.into(),
+                    condition: (&r.condition.0).into(),
+                })
+                .collect(),
+            unknowns: self
+                .unknowns
+                .iter()
+                .map(|(name, provenance)| (name.clone(), provenance.iter().map_into().collect()))
+                .collect(),
+        }
+    }
+}
+
+/// Where the values of the unknowns in `residuals` come from, in `request`
+/// and `entities`
+fn provenance(
+    residuals: &[Residual],
+    request: &ast::Request,
+    entities: &cedar_policy_core::entities::Entities,
+) -> BTreeMap<SmolStr, Vec<UnknownProvenance>> {
+    let names: BTreeSet<&str> = residuals.iter().flat_map(Residual::unknowns).collect();
+    let mut unknowns: BTreeMap<SmolStr, Vec<UnknownProvenance>> = BTreeMap::new();
+    let mut add = |name: &str, provenance: UnknownProvenance| {
+        if names.contains(name) {
+            let found = unknowns.entry(SmolStr::new(name)).or_default();
+            if !found.contains(&provenance) {
+                found.push(provenance);
+            }
+        }
+    };
+    for (entry, var, provenance) in [
+        (
+            request.principal(),
+            "principal",
+            UnknownProvenance::Principal,
+        ),
+        (request.action(), "action", UnknownProvenance::Action),
+        (request.resource(), "resource", UnknownProvenance::Resource),
+    ] {
+        if entry.uid().is_none() {
+            add(var, provenance);
+        }
+    }
+    match request.context() {
+        None => add("context", UnknownProvenance::Context),
+        Some(ast::Context::RestrictedResidual(attrs)) => {
+            for (attr, e) in attrs.iter() {
+                visit_unknowns(e, &mut vec![attr.clone()], &mut |name, path| {
+                    add(name, UnknownProvenance::ContextAttribute { path });
+                });
+            }
+        }
+        Some(ast::Context::Value(_)) => {}
+    }
+    for entity in entities.iter() {
+        for (attr, v) in entity.attrs() {
+            if let ast::PartialValue::Residual(e) = v {
+                visit_unknowns(e, &mut vec![attr.clone()], &mut |name, path| {
+                    add(
+                        name,
+                        UnknownProvenance::EntityAttribute {
+                            entity: entity.uid().clone().into(),
+                            path,
+                        },
+                    );
+                });
+            }
+        }
+    }
+    unknowns
+}
+
+/// Call `found` with the name and the path of each unknown in the restricted
+/// expression `e`, which is at `path`
+fn visit_unknowns(
+    e: &ast::Expr,
+    path: &mut Vec<SmolStr>,
+    found: &mut impl FnMut(&str, Vec<String>),
+) {
+    match e.expr_kind() {
+        ast::ExprKind::Unknown(u) => found(&u.name, path.iter().map(ToString::to_string).collect()),
+        ast::ExprKind::Record(attrs) => {
+            for (attr, e) in attrs.iter() {
+                path.push(attr.clone());
+                visit_unknowns(e, path, found);
+                path.pop();
+            }
+        }
+        ast::ExprKind::Set(elems) => {
+            for e in elems.iter() {
+                visit_unknowns(e, path, found);
+            }
+        }
+        ast::ExprKind::ExtensionFunctionApp { args, .. } => {
+            for e in args.iter() {
+                visit_unknowns(e, path, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        Authorizer, Context, Entity, EntityUid, PolicySet, RequestBuilder, RestrictedExpression,
+    };
+    use std::collections::{HashMap, HashSet};
+    use std::str::FromStr;
+
+    #[test]
+    fn roundtrip() {
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { true };
+            permit(principal, action, resource) when { false };
+            forbid(principal == User::"mallory", action, resource);
+            permit(principal, action, resource) when { resource.public || context.mfa };
+            "#,
+        )
+        .unwrap();
+        let request = RequestBuilder::default()
+            .principal(EntityUid::from_str(r#"User::"alice""#).unwrap())
+            .action(EntityUid::from_str(r#"Action::"view""#).unwrap())
+            .context(Context::from_json_str(r#"{"mfa": false}"#, None).unwrap())
+            .build();
+        let entities = Entities::empty();
+        let response = Authorizer::new().is_authorized_partial(&request, &pset, &entities);
+        let residuals = Residuals::from_response(&response, &entities);
+        let json = residuals.to_json_string().unwrap();
+        let loaded = Residuals::from_json_str(&json).unwrap();
+        let summary = |residuals: &Residuals| {
+            residuals
+                .iter()
+                .map(|r| (r.id().to_string(), r.effect(), r.condition().0.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summary(&loaded), summary(&residuals));
+        assert_eq!(
+            summary(&loaded)
+                .into_iter()
+                .map(|(id, effect, _)| (id, effect))
+                .collect::<Vec<_>>(),
+            [
+                ("policy0".to_string(), Effect::Permit),
+                ("policy3".to_string(), Effect::Permit)
+            ]
+        );
+        assert_eq!(
+            loaded.provenance("resource").collect::<Vec<_>>(),
+            [&UnknownProvenance::Resource]
+        );
+    }
+
+    #[test]
+    fn provenance() {
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal in Group::"admins", action, resource);
+            permit(principal, action, resource) when { resource.ownerAge > 18 };
+            permit(principal, action, resource) when { context.device.trusted };
+            "#,
+        )
+        .unwrap();
+        let photo = EntityUid::from_str(r#"Photo::"beach""#).unwrap();
+        let request = RequestBuilder::default()
+            .action(EntityUid::from_str(r#"Action::"view""#).unwrap())
+            .resource(photo.clone())
+            .context(
+                Context::from_pairs([(
+                    "device".to_string(),
+                    RestrictedExpression::new_record([(
+                        "trusted".to_string(),
+                        RestrictedExpression::new_unknown("trusted"),
+                    )])
+                    .unwrap(),
+                )])
+                .unwrap(),
+            )
+            .build();
+        let entities = Entities::from_entities(
+            [Entity::new(
+                photo.clone(),
+                HashMap::from([(
+                    "ownerAge".to_string(),
+                    RestrictedExpression::new_unknown("age"),
+                )]),
+                HashSet::new(),
+            )
+            .unwrap()],
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized_partial(&request, &pset, &entities);
+        let residuals = Residuals::from_response(&response, &entities);
+        let residuals = Residuals::from_json_value(residuals.to_json_value().unwrap()).unwrap();
+        assert_eq!(
+            residuals.provenance("principal").collect::<Vec<_>>(),
+            [&UnknownProvenance::Principal]
+        );
+        assert_eq!(
+            residuals.provenance("age").collect::<Vec<_>>(),
+            [&UnknownProvenance::EntityAttribute {
+                entity: photo,
+                path: vec!["ownerAge".to_string()]
+            }]
+        );
+        assert_eq!(
+            residuals.provenance("trusted").collect::<Vec<_>>(),
+            [&UnknownProvenance::ContextAttribute {
+                path: vec!["device".to_string(), "trusted".to_string()]
+            }]
+        );
+    }
+}
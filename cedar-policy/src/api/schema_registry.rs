@@ -0,0 +1,368 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`SchemaProvider`] and [`AsyncSchemaProvider`], the
+//! interfaces of clients of a schema registry, and [`SchemaRegistry`], which
+//! caches and validates the schemas they fetch.
+
+use super::{CedarSchemaError, EntityNamespace, Schema, SchemaFragment};
+use miette::Diagnostic;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use thiserror::Error;
+
+/// Identifies a schema in a registry: the version `version` of the schema
+/// declaring the namespace `namespace`, or the empty namespace if `None`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaKey {
+    /// The namespace the schema declares
+    pub namespace: Option<EntityNamespace>,
+    /// The version of the schema, in the format of the registry
+    pub version: String,
+}
+
+impl std::fmt::Display for SchemaKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{namespace}")?;
+        }
+        write!(f, "@{}", self.version)
+    }
+}
+
+/// A schema as stored in a registry, in either schema format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDocument {
+    /// A schema in the Cedar schema format
+    CedarSchema(String),
+    /// A schema in the JSON schema format
+    Json(serde_json::Value),
+}
+
+/// A client of a schema registry which fetches schemas synchronously
+pub trait SchemaProvider {
+    /// The error when fetching a schema fails
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Fetch the schema `key` from the registry
+    fn fetch(&self, key: &SchemaKey) -> Result<SchemaDocument, Self::Error>;
+}
+
+/// A client of a schema registry which fetches schemas asynchronously, e.g.,
+/// over HTTP. The futures it returns may run on any executor.
+pub trait AsyncSchemaProvider {
+    /// The error when fetching a schema fails
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Fetch the schema `key` from the registry
+    fn fetch(
+        &self,
+        key: &SchemaKey,
+    ) -> impl Future<Output = Result<SchemaDocument, Self::Error>> + Send;
+}
+
+/// Fetches schemas from a registry with a [`SchemaProvider`] or an
+/// [`AsyncSchemaProvider`], and caches them, so that each schema is fetched
+/// and parsed only once. A fetched schema is only cached if it is valid and
+/// declares the namespace of its key.
+///
+/// The registry can be shared between threads. Its lock is not held while
+/// fetching, so concurrent requests for a schema which isn't cached yet may
+/// each fetch it.
+///
+/// ```
+/// # use cedar_policy::{SchemaDocument, SchemaKey, SchemaProvider, SchemaRegistry, Validator};
+/// # use std::collections::HashMap;
+/// struct Files(HashMap<SchemaKey, String>);
+///
+/// impl SchemaProvider for Files {
+///     type Error = std::io::Error;
+///     fn fetch(&self, key: &SchemaKey) -> Result<SchemaDocument, Self::Error> {
+///         match self.0.get(key) {
+///             Some(src) => Ok(SchemaDocument::CedarSchema(src.clone())),
+///             None => Err(std::io::ErrorKind::NotFound.into()),
+///         }
+///     }
+/// }
+///
+/// let key = SchemaKey { namespace: Some("Photos".parse().unwrap()), version: "1".to_string() };
+/// let registry = SchemaRegistry::new(Files(HashMap::from([(
+///     key.clone(),
+///     "namespace Photos { entity User; }".to_string(),
+/// )])));
+/// let validator = Validator::new(registry.schema(&key).unwrap());
+/// assert!(registry.is_cached(&key));
+/// ```
+#[derive(Debug)]
+pub struct SchemaRegistry<P> {
+    provider: P,
+    cache: Mutex<HashMap<SchemaKey, Schema>>,
+}
+
+impl<P> SchemaRegistry<P> {
+    /// A registry fetching schemas with `provider`, with no schemas cached yet
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The provider schemas are fetched with
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Whether the schema `key` is cached
+    pub fn is_cached(&self, key: &SchemaKey) -> bool {
+        self.lock().contains_key(key)
+    }
+
+    /// Evict the schema `key` from the cache, e.g., if it changed in the
+    /// registry, so that it is fetched again next time. Returns whether it
+    /// was cached.
+    pub fn invalidate(&self, key: &SchemaKey) -> bool {
+        self.lock().remove(key).is_some()
+    }
+
+    /// Evict all schemas from the cache
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SchemaKey, Schema>> {
+        // The cache is consistent even if a thread panicked while holding the
+        // lock, since every update is a single insertion or removal.
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Parse and validate the fetched `document` for `key`, and cache it
+    fn insert(
+        &self,
+        key: &SchemaKey,
+        document: SchemaDocument,
+    ) -> Result<Schema, SchemaRegistryError> {
+        let fragment = match document {
+            SchemaDocument::CedarSchema(src) => SchemaFragment::from_cedarschema_str(&src)?.0,
+            SchemaDocument::Json(json) => {
+                SchemaFragment::from_json_value(json).map_err(CedarSchemaError::from)?
+            }
+        };
+        if !fragment.namespaces().any(|ns| ns == key.namespace) {
+            return Err(SchemaRegistryError::MissingNamespace { key: key.clone() });
+        }
+        let schema = Schema::from_schema_fragments([fragment]).map_err(CedarSchemaError::from)?;
+        self.lock().insert(key.clone(), schema.clone());
+        Ok(schema)
+    }
+}
+
+impl<P: SchemaProvider> SchemaRegistry<P> {
+    /// The schema `key`, fetched from the registry if it isn't cached
+    pub fn schema(&self, key: &SchemaKey) -> Result<Schema, SchemaRegistryError> {
+        let cached = self.lock().get(key).cloned();
+        if let Some(schema) = cached {
+            return Ok(schema);
+        }
+        let document = self
+            .provider
+            .fetch(key)
+            .map_err(|err| SchemaRegistryError::Fetch {
+                key: key.clone(),
+                source: Box::new(err),
+            })?;
+        self.insert(key, document)
+    }
+}
+
+impl<P: AsyncSchemaProvider + Sync> SchemaRegistry<P> {
+    /// The schema `key`, fetched from the registry if it isn't cached
+    pub async fn schema_async(&self, key: &SchemaKey) -> Result<Schema, SchemaRegistryError> {
+        let cached = self.lock().get(key).cloned();
+        if let Some(schema) = cached {
+            return Ok(schema);
+        }
+        let document =
+            self.provider
+                .fetch(key)
+                .await
+                .map_err(|err| SchemaRegistryError::Fetch {
+                    key: key.clone(),
+                    source: Box::new(err),
+                })?;
+        self.insert(key, document)
+    }
+}
+
+/// Errors when getting a schema from a [`SchemaRegistry`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum SchemaRegistryError {
+    /// The provider failed to fetch the schema
+    #[error("failed to fetch schema `{key}` from the registry")]
+    Fetch {
+        /// The schema which was fetched
+        key: SchemaKey,
+        /// The error of the provider
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The fetched schema is invalid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Schema(#[from] CedarSchemaError),
+    /// The fetched schema does not declare the namespace it was fetched for
+    #[error("schema `{key}` does not declare its namespace")]
+    MissingNamespace {
+        /// The schema which was fetched
+        key: SchemaKey,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    /// A registry serving schemas from memory, counting the fetches
+    #[derive(Default)]
+    struct Registry {
+        schemas: HashMap<SchemaKey, SchemaDocument>,
+        fetches: AtomicUsize,
+    }
+
+    impl Registry {
+        fn get(&self, key: &SchemaKey) -> Result<SchemaDocument, std::io::Error> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            self.schemas
+                .get(key)
+                .cloned()
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }
+    }
+
+    impl SchemaProvider for Registry {
+        type Error = std::io::Error;
+        fn fetch(&self, key: &SchemaKey) -> Result<SchemaDocument, Self::Error> {
+            self.get(key)
+        }
+    }
+
+    impl AsyncSchemaProvider for Registry {
+        type Error = std::io::Error;
+        async fn fetch(&self, key: &SchemaKey) -> Result<SchemaDocument, Self::Error> {
+            self.get(key)
+        }
+    }
+
+    fn key(namespace: &str, version: &str) -> SchemaKey {
+        SchemaKey {
+            namespace: Some(namespace.parse().unwrap()),
+            version: version.to_string(),
+        }
+    }
+
+    fn registry() -> SchemaRegistry<Registry> {
+        SchemaRegistry::new(Registry {
+            schemas: HashMap::from([
+                (
+                    key("Photos", "1"),
+                    SchemaDocument::CedarSchema("namespace Photos { entity User; }".to_string()),
+                ),
+                (
+                    key("Photos", "2"),
+                    SchemaDocument::Json(serde_json::json!({
+                        "Photos": { "entityTypes": { "User": {}, "Photo": {} }, "actions": {} }
+                    })),
+                ),
+                (
+                    key("Docs", "1"),
+                    SchemaDocument::CedarSchema("namespace Photos { entity User; }".to_string()),
+                ),
+                (
+                    key("Docs", "2"),
+                    SchemaDocument::CedarSchema(
+                        "namespace Docs { entity User in Team; }".to_string(),
+                    ),
+                ),
+            ]),
+            fetches: AtomicUsize::new(0),
+        })
+    }
+
+    /// Run `future` to completion; the futures of `Registry` never wait
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        struct Noop;
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Waker::from(Arc::new(Noop));
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(value) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                return value;
+            }
+        }
+    }
+
+    #[test]
+    fn caches_schemas() {
+        let registry = registry();
+        for key in [key("Photos", "1"), key("Photos", "2")] {
+            assert!(registry.schema(&key).is_ok());
+            assert!(registry.schema(&key).is_ok());
+            assert!(registry.is_cached(&key));
+        }
+        assert_eq!(registry.provider().fetches.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            registry
+                .schema(&key("Photos", "2"))
+                .unwrap()
+                .entity_types()
+                .count(),
+            2
+        );
+
+        assert!(registry.invalidate(&key("Photos", "1")));
+        assert!(!registry.is_cached(&key("Photos", "1")));
+        assert!(block_on(registry.schema_async(&key("Photos", "1"))).is_ok());
+        assert_eq!(registry.provider().fetches.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn invalid_schemas_are_not_cached() {
+        let registry = registry();
+        assert_matches!(
+            registry.schema(&key("Photos", "3")),
+            Err(SchemaRegistryError::Fetch { .. })
+        );
+        assert_matches!(
+            registry.schema(&key("Docs", "1")),
+            Err(SchemaRegistryError::MissingNamespace { .. })
+        );
+        assert_matches!(
+            block_on(registry.schema_async(&key("Docs", "2"))),
+            Err(SchemaRegistryError::Schema(_))
+        );
+        assert!(!registry.is_cached(&key("Docs", "1")));
+        assert!(!registry.is_cached(&key("Docs", "2")));
+    }
+}
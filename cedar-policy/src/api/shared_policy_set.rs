@@ -0,0 +1,235 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`SharedPolicySet`], a policy set which can be read
+//! concurrently by many authorization threads while being replaced atomically.
+
+use crate::{Authorizer, Entities, PolicySet, Request, Response};
+use arc_swap::{ArcSwap, Guard};
+use std::sync::Arc;
+
+/// A [`PolicySet`] shared between threads, which supports cheap concurrent
+/// reads and atomic replacement.
+///
+/// Readers never take a lock: [`SharedPolicySet::is_authorized()`] and
+/// [`SharedPolicySet::load()`] perform a (usually wait-free) atomic load of the
+/// current policy set, so a writer replacing the policies never stalls an
+/// in-flight authorization, and a slow authorization never stalls a writer.
+/// Each authorization sees exactly one version of the policies: either the
+/// one before or the one after a concurrent replacement, never a mix.
+///
+/// A replaced policy set is dropped once the last reader using it finishes.
+///
+/// ```
+/// # use cedar_policy::{Authorizer, Decision, Entities, EntityUid, PolicySet, Request, Context, SharedPolicySet};
+/// # use std::str::FromStr;
+/// let shared = SharedPolicySet::new(PolicySet::new());
+/// let request = Request::new(
+///     EntityUid::from_str(r#"User::"alice""#).unwrap(),
+///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+///     EntityUid::from_str(r#"Photo::"vacation.jpg""#).unwrap(),
+///     Context::empty(),
+///     None,
+/// ).unwrap();
+/// let authorizer = Authorizer::new();
+/// let entities = Entities::empty();
+/// assert_eq!(shared.is_authorized(&authorizer, &request, &entities).decision(), Decision::Deny);
+///
+/// // e.g., from a background thread which watches the policy store
+/// shared.store(PolicySet::from_str("permit(principal, action, resource);").unwrap());
+/// assert_eq!(shared.is_authorized(&authorizer, &request, &entities).decision(), Decision::Allow);
+/// ```
+#[derive(Debug)]
+pub struct SharedPolicySet {
+    current: ArcSwap<PolicySet>,
+}
+
+impl SharedPolicySet {
+    /// Create a new `SharedPolicySet` containing `policies`
+    pub fn new(policies: PolicySet) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(policies),
+        }
+    }
+
+    /// Authorize `request` against the current policy set. This does not take
+    /// a lock.
+    pub fn is_authorized(
+        &self,
+        authorizer: &Authorizer,
+        request: &Request,
+        entities: &Entities,
+    ) -> Response {
+        authorizer.is_authorized(request, &self.current.load(), entities)
+    }
+
+    /// Get a snapshot of the current policy set. The snapshot is unaffected by
+    /// later replacements.
+    ///
+    /// Snapshots keep their policy set alive, so avoid holding them for longer
+    /// than necessary.
+    pub fn load(&self) -> Arc<PolicySet> {
+        self.current.load_full()
+    }
+
+    /// Atomically replace the current policy set with `policies`. Readers
+    /// which have already loaded the previous policy set continue to use it.
+    pub fn store(&self, policies: PolicySet) {
+        self.current.store(Arc::new(policies));
+    }
+
+    /// Atomically replace the current policy set with `policies`, returning
+    /// the previous policy set.
+    pub fn swap(&self, policies: PolicySet) -> Arc<PolicySet> {
+        self.current.swap(Arc::new(policies))
+    }
+
+    /// Atomically replace the current policy set with the result of applying
+    /// `f` to it, e.g., to add or remove a policy.
+    ///
+    /// If another thread replaces the policy set while `f` is running, `f` is
+    /// called again on the new policy set, so no concurrent update is lost.
+    /// If `f` returns an error, the policy set is left unchanged.
+    pub fn update<E>(
+        &self,
+        mut f: impl FnMut(&PolicySet) -> Result<PolicySet, E>,
+    ) -> Result<(), E> {
+        let mut current = self.current.load_full();
+        loop {
+            let new = Arc::new(f(&current)?);
+            let prev = self.current.compare_and_swap(&current, new);
+            if Arc::ptr_eq(&prev, &current) {
+                return Ok(());
+            }
+            current = Guard::into_inner(prev);
+        }
+    }
+}
+
+impl Default for SharedPolicySet {
+    fn default() -> Self {
+        Self::new(PolicySet::new())
+    }
+}
+
+impl From<PolicySet> for SharedPolicySet {
+    fn from(policies: PolicySet) -> Self {
+        Self::new(policies)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Decision, EntityUid, Policy, PolicyId, PolicySetError};
+    use std::str::FromStr;
+
+    fn request() -> Request {
+        Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn swap_and_update() {
+        let shared = SharedPolicySet::default();
+        let authorizer = Authorizer::new();
+        let entities = Entities::empty();
+        let snapshot = shared.load();
+
+        shared
+            .update(|pset| -> Result<PolicySet, PolicySetError> {
+                let mut pset = pset.clone();
+                pset.add(
+                    Policy::parse(
+                        Some(PolicyId::new("p0")),
+                        "permit(principal, action, resource);",
+                    )
+                    .unwrap(),
+                )?;
+                Ok(pset)
+            })
+            .unwrap();
+        assert_eq!(
+            shared
+                .is_authorized(&authorizer, &request(), &entities)
+                .decision(),
+            Decision::Allow
+        );
+        // earlier snapshots are unaffected
+        assert_eq!(snapshot.policies().count(), 0);
+
+        // failed updates leave the policy set unchanged
+        let result = shared.update(|pset| {
+            let mut pset = pset.clone();
+            pset.add(
+                Policy::parse(
+                    Some(PolicyId::new("p0")),
+                    "forbid(principal, action, resource);",
+                )
+                .unwrap(),
+            )?;
+            Ok::<_, PolicySetError>(pset)
+        });
+        assert!(result.is_err());
+        assert_eq!(shared.load().policies().count(), 1);
+
+        let prev = shared.swap(PolicySet::new());
+        assert_eq!(prev.policies().count(), 1);
+        assert_eq!(
+            shared
+                .is_authorized(&authorizer, &request(), &entities)
+                .decision(),
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn concurrent_updates() {
+        let shared = SharedPolicySet::default();
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let shared = &shared;
+                s.spawn(move || {
+                    let authorizer = Authorizer::new();
+                    let entities = Entities::empty();
+                    for j in 0..16 {
+                        shared
+                            .update(|pset| {
+                                let mut pset = pset.clone();
+                                pset.add(
+                                    Policy::parse(
+                                        Some(PolicyId::new(format!("p{i}_{j}"))),
+                                        "permit(principal, action, resource);",
+                                    )
+                                    .unwrap(),
+                                )?;
+                                Ok::<_, PolicySetError>(pset)
+                            })
+                            .unwrap();
+                        shared.is_authorized(&authorizer, &request(), &entities);
+                    }
+                });
+            }
+        });
+        assert_eq!(shared.load().policies().count(), 8 * 16);
+    }
+}
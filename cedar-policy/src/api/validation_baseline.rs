@@ -0,0 +1,224 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`ValidationBaseline`], which records the known
+//! findings of validation so that only new ones are reported.
+
+use super::ValidationResult;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A record of the errors and warnings validation found in a policy set,
+/// e.g., checked in next to a large legacy policy set, so that validation can
+/// be made to fail only on new findings while the recorded ones are fixed
+/// incrementally.
+///
+/// A finding is identified by its stable code (see
+/// [`super::ValidationError::kind`]), the id of its policy, and a hash of the
+/// source text it points to. So, recorded findings still match after
+/// unrelated edits move them around in the policy file, but a finding in
+/// edited code is new.
+///
+/// ```
+/// # use cedar_policy::{PolicySet, Schema, ValidationBaseline, ValidationMode, Validator};
+/// let schema: Schema = "entity User; action view appliesTo { principal: User, resource: User };"
+///     .parse()
+///     .unwrap();
+/// let validator = Validator::new(schema);
+/// let old: PolicySet = r#"permit(principal == Usr::"alice", action, resource);"#
+///     .parse()
+///     .unwrap();
+/// let baseline = ValidationBaseline::from_result(&validator.validate(&old, ValidationMode::Strict));
+///
+/// let new: PolicySet = r#"
+///     permit(principal == Usr::"alice", action, resource);
+///     permit(principal == Usr::"bob", action, resource);
+/// "#.parse().unwrap();
+/// let result = baseline.filter(validator.validate(&new, ValidationMode::Strict));
+/// // only the findings of the new policy remain: its unrecognized entity type,
+/// // and that no action applies to it
+/// assert_eq!(result.validation_errors().count(), 2);
+/// assert!(result.validation_errors().all(|e| e.policy_id().to_string() == "policy1"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct ValidationBaseline {
+    /// The recorded findings, sorted. A finding may occur several times.
+    findings: Vec<Finding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+struct Finding {
+    code: String,
+    /// `None` for warnings about the schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    policy_id: Option<String>,
+    /// Hash of the source text the finding points to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    span_hash: Option<String>,
+}
+
+impl Finding {
+    fn new(code: &str, policy_id: Option<&super::PolicyId>, diagnostic: &dyn Diagnostic) -> Self {
+        Self {
+            code: code.to_string(),
+            policy_id: policy_id.map(|id| AsRef::<str>::as_ref(id).to_string()),
+            span_hash: span_hash(diagnostic),
+        }
+    }
+}
+
+/// FNV-1a hash of the source text of the first label of `diagnostic`, which is
+/// stable across platforms and versions, unlike `std`'s hashers
+fn span_hash(diagnostic: &dyn Diagnostic) -> Option<String> {
+    let label = diagnostic.labels()?.next()?;
+    let contents = diagnostic
+        .source_code()?
+        .read_span(label.inner(), 0, 0)
+        .ok()?;
+    // `contents` may extend to the whole lines around the span
+    let start = label.offset().checked_sub(contents.span().offset())?;
+    let hash = contents
+        .data()
+        .get(start..start + label.len())?
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    Some(format!("{hash:016x}"))
+}
+
+impl ValidationBaseline {
+    /// A baseline recording all the errors and warnings of `result`
+    pub fn from_result(result: &ValidationResult) -> Self {
+        let mut findings: Vec<Finding> = result
+            .validation_errors()
+            .map(|e| Finding::new(e.kind(), Some(e.policy_id()), e))
+            .chain(
+                result
+                    .validation_warnings()
+                    .map(|w| Finding::new(w.kind(), w.policy_id(), w)),
+            )
+            .collect();
+        findings.sort();
+        Self { findings }
+    }
+
+    /// The number of recorded findings
+    pub fn len(&self) -> usize {
+        self.findings.len()
+    }
+
+    /// True if no findings are recorded
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// `result`, without the errors and warnings recorded in this baseline.
+    /// Each recorded finding suppresses one occurrence of a matching error or
+    /// warning.
+    pub fn filter(&self, mut result: ValidationResult) -> ValidationResult {
+        let mut remaining: HashMap<&Finding, usize> = HashMap::new();
+        for finding in &self.findings {
+            *remaining.entry(finding).or_default() += 1;
+        }
+        let mut is_new = |finding: Finding| match remaining.get_mut(&finding) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        };
+        result
+            .validation_errors
+            .retain(|e| is_new(Finding::new(e.kind(), Some(e.policy_id()), e)));
+        result
+            .validation_warnings
+            .retain(|w| is_new(Finding::new(w.kind(), w.policy_id(), w)));
+        result
+    }
+
+    /// Parse a baseline from JSON of the form
+    /// `{ "findings": [{ "code": ..., "policyId": ..., "spanHash": ... }, ...] }`,
+    /// as produced by [`ValidationBaseline::to_json_value`]
+    pub fn from_json_value(json: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(json)
+    }
+
+    /// Parse a baseline from a JSON string, in the format described in
+    /// [`ValidationBaseline::from_json_value`]
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Serialize this baseline as JSON
+    pub fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+
+    /// Serialize this baseline as a pretty-printed JSON string, suitable for
+    /// checking in and reviewing diffs
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PolicySet, Schema, ValidationMode, Validator};
+
+    fn validate(src: &str) -> ValidationResult {
+        let schema: Schema = "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };"
+            .parse()
+            .unwrap();
+        let pset: PolicySet = src.parse().unwrap();
+        Validator::new(schema).validate(&pset, ValidationMode::Strict)
+    }
+
+    #[test]
+    fn moved_findings_still_match() {
+        let old =
+            validate(r#"permit(principal, action, resource) when { principal.name == "a" };"#);
+        let baseline = ValidationBaseline::from_result(&old);
+        assert_eq!(baseline.len(), 1);
+        let baseline =
+            ValidationBaseline::from_json_str(&baseline.to_json_string().unwrap()).unwrap();
+
+        // the same finding, at a different offset
+        let moved = validate(
+            r#"
+
+            permit(principal, action, resource) when { principal.name == "a" };"#,
+        );
+        assert!(baseline.filter(moved).validation_passed());
+
+        // the same code in the same policy, but in edited code
+        let edited =
+            validate(r#"permit(principal, action, resource) when { principal.nick == "a" };"#);
+        assert!(!baseline.filter(edited).validation_passed());
+
+        // a second occurrence of the finding
+        let repeated = validate(
+            r#"permit(principal, action, resource) when { principal.name == "a" || principal.name == "b" };"#,
+        );
+        assert_eq!(baseline.filter(repeated).validation_errors().count(), 1);
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`ValidationProfile`], which reports where
+//! [`super::Validator::validate_with_profile`] spent its time.
+
+use super::{PolicyId, RequestEnv};
+use ref_cast::RefCast;
+use std::time::Duration;
+
+/// Time spent validating each policy and template of a policy set, returned
+/// by [`super::Validator::validate_with_profile`]
+#[derive(Debug, Clone)]
+pub struct ValidationProfile(pub(crate) cedar_policy_validator::ValidationProfile);
+
+impl ValidationProfile {
+    /// The profile of each policy and template, in the order they were
+    /// validated
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyProfile> {
+        self.0.policies().map(PolicyProfile::ref_cast)
+    }
+
+    /// Total time spent validating policies and templates
+    pub fn total_duration(&self) -> Duration {
+        self.0.total_duration()
+    }
+
+    /// Total number of least upper bounds computed while validating policies
+    /// and templates. Computing least upper bounds of large entity or record
+    /// types is a common cause of slow validation.
+    pub fn lub_computations(&self) -> usize {
+        self.0.lub_computations()
+    }
+}
+
+/// Time spent validating a single policy or template
+#[repr(transparent)]
+#[derive(Debug, Clone, RefCast)]
+pub struct PolicyProfile(cedar_policy_validator::PolicyProfile);
+
+impl PolicyProfile {
+    /// The id of the policy or template
+    pub fn id(&self) -> &PolicyId {
+        PolicyId::ref_cast(self.0.id())
+    }
+
+    /// Total time spent validating the policy, including the checks which are
+    /// not specific to a request environment
+    pub fn duration(&self) -> Duration {
+        self.0.duration()
+    }
+
+    /// Number of least upper bounds computed while validating the policy
+    pub fn lub_computations(&self) -> usize {
+        self.0.lub_computations()
+    }
+
+    /// Time spent typechecking the policy in each request environment
+    pub fn request_envs(&self) -> impl Iterator<Item = &RequestEnvProfile> {
+        self.0.request_envs().map(RequestEnvProfile::ref_cast)
+    }
+}
+
+/// Time spent typechecking a policy in a single request environment
+#[repr(transparent)]
+#[derive(Debug, Clone, RefCast)]
+pub struct RequestEnvProfile(cedar_policy_validator::RequestEnvProfile);
+
+impl RequestEnvProfile {
+    /// The request environment. This is `None` for the environment
+    /// representing undeclared actions in partial schema validation.
+    pub fn request_env(&self) -> Option<RequestEnv> {
+        self.0.request_type().map(|ty| RequestEnv {
+            principal: ty.principal.clone().into(),
+            action: ty.action.clone().into(),
+            resource: ty.resource.clone().into(),
+        })
+    }
+
+    /// Time spent typechecking the policy in this environment
+    pub fn duration(&self) -> Duration {
+        self.0.duration()
+    }
+
+    /// Number of least upper bounds computed while typechecking the policy in
+    /// this environment
+    pub fn lub_computations(&self) -> usize {
+        self.0.lub_computations()
+    }
+}
@@ -0,0 +1,498 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`VersionedSchema`], the versions of the schema
+//! fragment of each namespace, which validates each policy against the
+//! versions it pins.
+
+use super::{
+    EntityNamespace, PolicyId, PolicySet, Schema, SchemaError, SchemaFragment, SchemaKey,
+    ValidationMode, ValidationResult, Validator,
+};
+use cedar_policy_core::extensions::Extensions;
+use itertools::Itertools;
+use miette::Diagnostic;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// The annotation with which a policy or template pins the versions of the
+/// namespaces it is validated against, e.g.,
+/// `@schemaVersions("Photos@2, Docs@1")`. The empty namespace is pinned with
+/// `@<version>`.
+pub const SCHEMA_VERSIONS_ANNOTATION: &str = "schemaVersions";
+
+/// The versions of the schema fragment of each namespace, e.g., to upgrade
+/// the schema of a monorepo one namespace at a time.
+///
+/// Each policy is validated against a schema made of one version of each
+/// namespace: the versions it pins with the [`SCHEMA_VERSIONS_ANNOTATION`]
+/// annotation, and the default version of the other namespaces. Links are
+/// validated against the versions their template pins. All policies pinning
+/// the same versions are validated together, so a schema is built once for
+/// each combination of versions which is used.
+///
+/// ```
+/// # use cedar_policy::{PolicySet, SchemaFragment, SchemaKey, ValidationMode, VersionedSchema};
+/// let key = |version: &str| SchemaKey {
+///     namespace: Some("Photos".parse().unwrap()),
+///     version: version.to_string(),
+/// };
+/// let v1 = SchemaFragment::from_cedarschema_str(r#"
+///     namespace Photos { entity User; action view appliesTo { principal: User, resource: User }; }
+/// "#).unwrap().0;
+/// let v2 = SchemaFragment::from_cedarschema_str(r#"
+///     namespace Photos {
+///         entity User = { level: Long };
+///         action view appliesTo { principal: User, resource: User };
+///     }
+/// "#).unwrap().0;
+/// let mut schema = VersionedSchema::new();
+/// schema.add(key("1"), v1).unwrap();
+/// schema.add(key("2"), v2).unwrap();
+///
+/// let pset: PolicySet = r#"
+///     @schemaVersions("Photos@2")
+///     permit(principal, action == Photos::Action::"view", resource) when { principal.level > 3 };
+/// "#.parse().unwrap();
+/// assert!(schema.validate(&pset, ValidationMode::Strict).validation_passed());
+///
+/// // `Photos@1` is the default version, which has no `level` attribute
+/// let pset: PolicySet = r#"
+///     permit(principal, action == Photos::Action::"view", resource) when { principal.level > 3 };
+/// "#.parse().unwrap();
+/// assert!(!schema.validate(&pset, ValidationMode::Strict).validation_passed());
+/// ```
+#[derive(Debug, Default)]
+pub struct VersionedSchema {
+    /// The fragment of each version of each namespace
+    fragments: HashMap<SchemaKey, SchemaFragment>,
+    /// The default version of each namespace
+    defaults: BTreeMap<Option<EntityNamespace>, String>,
+}
+
+impl VersionedSchema {
+    /// No versions of any namespace
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `fragment` as the version `key` of its namespace. The fragment
+    /// must declare the namespace of `key` and no other. The first version of
+    /// a namespace which is added is its default version.
+    pub fn add(
+        &mut self,
+        key: SchemaKey,
+        fragment: SchemaFragment,
+    ) -> Result<(), VersionedSchemaError> {
+        let declares_namespace = {
+            let mut namespaces = fragment.namespaces();
+            namespaces.next().as_ref() == Some(&key.namespace)
+                && namespaces.all(|ns| ns == key.namespace)
+        };
+        if !declares_namespace {
+            return Err(VersionedSchemaError::NamespaceMismatch { key });
+        }
+        if self.fragments.contains_key(&key) {
+            return Err(VersionedSchemaError::DuplicateVersion { key });
+        }
+        self.defaults
+            .entry(key.namespace.clone())
+            .or_insert_with(|| key.version.clone());
+        self.fragments.insert(key, fragment);
+        Ok(())
+    }
+
+    /// Make `key` the default version of its namespace, e.g., once every
+    /// policy has been upgraded to it
+    pub fn set_default(&mut self, key: &SchemaKey) -> Result<(), VersionedSchemaError> {
+        if !self.fragments.contains_key(key) {
+            return Err(VersionedSchemaError::UnknownVersion { key: key.clone() });
+        }
+        self.defaults
+            .insert(key.namespace.clone(), key.version.clone());
+        Ok(())
+    }
+
+    /// The default version of each namespace, ordered by namespace
+    pub fn defaults(&self) -> impl Iterator<Item = SchemaKey> + '_ {
+        self.defaults.iter().map(|(namespace, version)| SchemaKey {
+            namespace: namespace.clone(),
+            version: version.clone(),
+        })
+    }
+
+    /// Validate each policy and template of `policies` against the versions
+    /// it pins. Policies whose pins are invalid are not validated.
+    pub fn validate(&self, policies: &PolicySet, mode: ValidationMode) -> PinnedValidationResult {
+        let mut errors = Vec::new();
+        let mut groups: BTreeMap<Vec<SchemaKey>, PolicySet> = BTreeMap::new();
+        // the versions of each template, if its pins are valid
+        let mut template_versions = HashMap::new();
+        for template in policies.templates() {
+            let versions = self.versions(
+                template.id(),
+                template.annotation(SCHEMA_VERSIONS_ANNOTATION),
+                &mut errors,
+            );
+            if let Some(versions) = &versions {
+                // PANIC SAFETY: template ids are unique in `policies`
+                #[allow(clippy::expect_used)]
+                groups
+                    .entry(versions.clone())
+                    .or_default()
+                    .add_template(template.clone())
+                    .expect("template ids are unique");
+            }
+            template_versions.insert(template.id(), versions);
+        }
+        for policy in policies.policies() {
+            match policy.template_id() {
+                None => {
+                    if let Some(versions) = self.versions(
+                        policy.id(),
+                        policy.annotation(SCHEMA_VERSIONS_ANNOTATION),
+                        &mut errors,
+                    ) {
+                        // PANIC SAFETY: policy ids are unique in `policies`
+                        #[allow(clippy::expect_used)]
+                        groups
+                            .entry(versions)
+                            .or_default()
+                            .add(policy.clone())
+                            .expect("policy ids are unique");
+                    }
+                }
+                Some(template_id) => {
+                    if let (Some(Some(versions)), Some(values)) =
+                        (template_versions.get(template_id), policy.template_links())
+                    {
+                        // PANIC SAFETY: the template of the link is in the same group
+                        #[allow(clippy::expect_used)]
+                        groups
+                            .entry(versions.clone())
+                            .or_default()
+                            .link(template_id.clone(), policy.id().clone(), values)
+                            .expect("template of the link is in the same group");
+                    }
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+        for (versions, group) in groups {
+            match self.schema(&versions) {
+                Ok(schema) => {
+                    let result = Validator::new(schema).validate(&group, mode);
+                    results.push((versions, result));
+                }
+                Err(error) => errors.push(PinnedValidationError::InvalidSchema {
+                    policy_ids: group
+                        .policies()
+                        .filter(|p| p.template_id().is_none())
+                        .map(|p| p.id().clone())
+                        .chain(group.templates().map(|t| t.id().clone()))
+                        .collect(),
+                    versions,
+                    error,
+                }),
+            }
+        }
+        PinnedValidationResult { results, errors }
+    }
+
+    /// The version of each namespace for the policy or template `id`, whose
+    /// pins are `pins`, ordered by namespace
+    fn versions(
+        &self,
+        id: &PolicyId,
+        pins: Option<&str>,
+        errors: &mut Vec<PinnedValidationError>,
+    ) -> Option<Vec<SchemaKey>> {
+        let mut versions = self.defaults.clone();
+        for pin in pins.into_iter().flat_map(|pins| pins.split(',')) {
+            let pin = pin.trim();
+            let Some(key) = parse_pin(pin) else {
+                errors.push(PinnedValidationError::MalformedPin {
+                    policy_id: id.clone(),
+                    pin: pin.to_string(),
+                });
+                return None;
+            };
+            if !self.fragments.contains_key(&key) {
+                errors.push(PinnedValidationError::UnknownVersion {
+                    policy_id: id.clone(),
+                    key,
+                });
+                return None;
+            }
+            versions.insert(key.namespace, key.version);
+        }
+        Some(
+            versions
+                .into_iter()
+                .map(|(namespace, version)| SchemaKey { namespace, version })
+                .collect(),
+        )
+    }
+
+    /// The schema made of the fragments of `versions`
+    fn schema(&self, versions: &[SchemaKey]) -> Result<Schema, SchemaError> {
+        let fragments = versions
+            .iter()
+            .filter_map(|key| self.fragments.get(key))
+            .map(|fragment| fragment.lossless.clone().try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Schema(
+            cedar_policy_validator::ValidatorSchema::from_schema_fragments(
+                fragments,
+                Extensions::all_available(),
+            )?,
+        ))
+    }
+}
+
+/// Parse a pin of the form `Namespace@version`, or `@version` for the empty
+/// namespace
+fn parse_pin(pin: &str) -> Option<SchemaKey> {
+    let (namespace, version) = pin.rsplit_once('@')?;
+    if version.is_empty() {
+        return None;
+    }
+    let namespace = match namespace.trim() {
+        "" => None,
+        namespace => Some(namespace.parse().ok()?),
+    };
+    Some(SchemaKey {
+        namespace,
+        version: version.to_string(),
+    })
+}
+
+/// The result of [`VersionedSchema::validate`]: the validation result of the
+/// policies pinning each combination of versions, and the policies which
+/// could not be validated
+#[derive(Debug)]
+pub struct PinnedValidationResult {
+    results: Vec<(Vec<SchemaKey>, ValidationResult)>,
+    errors: Vec<PinnedValidationError>,
+}
+
+impl PinnedValidationResult {
+    /// True when every policy could be validated, and passes validation
+    pub fn validation_passed(&self) -> bool {
+        self.errors.is_empty() && self.results.iter().all(|(_, r)| r.validation_passed())
+    }
+
+    /// The validation result of the policies validated against each
+    /// combination of versions, with the version of each namespace ordered by
+    /// namespace
+    pub fn results(&self) -> impl Iterator<Item = (&[SchemaKey], &ValidationResult)> {
+        self.results
+            .iter()
+            .map(|(versions, result)| (versions.as_slice(), result))
+    }
+
+    /// Why some policies could not be validated
+    pub fn errors(&self) -> impl Iterator<Item = &PinnedValidationError> {
+        self.errors.iter()
+    }
+}
+
+/// Why a policy could not be validated by [`VersionedSchema::validate`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum PinnedValidationError {
+    /// A pin is not of the form `Namespace@version`
+    #[error("policy `{policy_id}` has a malformed schema version pin `{pin}`")]
+    #[diagnostic(help("expected a comma-separated list of `Namespace@version`"))]
+    MalformedPin {
+        /// The policy or template with the pin
+        policy_id: PolicyId,
+        /// The pin
+        pin: String,
+    },
+    /// A pin is for a version which does not exist
+    #[error("policy `{policy_id}` pins the unknown schema version `{key}`")]
+    UnknownVersion {
+        /// The policy or template with the pin
+        policy_id: PolicyId,
+        /// The pinned version
+        key: SchemaKey,
+    },
+    /// The pinned versions do not make a valid schema, e.g., since a
+    /// namespace uses a type which another one doesn't declare in the pinned
+    /// version
+    #[error("the schema versions pinned by {} are not compatible", .policy_ids.iter().map(|id| format!("`{id}`")).join(", "))]
+    InvalidSchema {
+        /// The static policies and templates pinning the versions
+        policy_ids: Vec<PolicyId>,
+        /// The version of each namespace
+        versions: Vec<SchemaKey>,
+        /// Why the versions do not make a valid schema
+        #[source]
+        #[diagnostic_source]
+        error: SchemaError,
+    },
+}
+
+/// Errors when adding versions to a [`VersionedSchema`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum VersionedSchemaError {
+    /// The fragment does not declare exactly the namespace of its key
+    #[error("the schema fragment for `{key}` must declare its namespace and no other")]
+    NamespaceMismatch {
+        /// The version of the fragment
+        key: SchemaKey,
+    },
+    /// The version was already added
+    #[error("schema version `{key}` already exists")]
+    DuplicateVersion {
+        /// The version
+        key: SchemaKey,
+    },
+    /// The version does not exist
+    #[error("unknown schema version `{key}`")]
+    UnknownVersion {
+        /// The version
+        key: SchemaKey,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn key(namespace: &str, version: &str) -> SchemaKey {
+        SchemaKey {
+            namespace: Some(namespace.parse().unwrap()),
+            version: version.to_string(),
+        }
+    }
+
+    fn fragment(src: &str) -> SchemaFragment {
+        SchemaFragment::from_cedarschema_str(src).unwrap().0
+    }
+
+    /// `Docs@2` uses the `Team` type, which only `Org@2` declares
+    fn schema() -> VersionedSchema {
+        let mut schema = VersionedSchema::new();
+        for (key, src) in [
+            (key("Org", "1"), "namespace Org { entity User; }"),
+            (key("Org", "2"), "namespace Org { entity User in Team; entity Team; }"),
+            (
+                key("Docs", "1"),
+                "namespace Docs { entity Doc; action read appliesTo { principal: Org::User, resource: Doc }; }",
+            ),
+            (
+                key("Docs", "2"),
+                "namespace Docs { entity Doc = { team: Org::Team }; action read appliesTo { principal: Org::User, resource: Doc }; }",
+            ),
+        ] {
+            schema.add(key, fragment(src)).unwrap();
+        }
+        schema
+    }
+
+    #[test]
+    fn add_versions() {
+        let mut schema = schema();
+        assert_eq!(
+            schema.defaults().collect::<Vec<_>>(),
+            [key("Docs", "1"), key("Org", "1")]
+        );
+        assert_matches!(
+            schema.add(key("Org", "1"), fragment("namespace Org { entity User; }")),
+            Err(VersionedSchemaError::DuplicateVersion { .. })
+        );
+        assert_matches!(
+            schema.add(key("Org", "3"), fragment("namespace Docs { entity Doc; }")),
+            Err(VersionedSchemaError::NamespaceMismatch { .. })
+        );
+        assert_matches!(
+            schema.set_default(&key("Org", "3")),
+            Err(VersionedSchemaError::UnknownVersion { .. })
+        );
+        schema.set_default(&key("Org", "2")).unwrap();
+        assert_eq!(
+            schema.defaults().collect::<Vec<_>>(),
+            [key("Docs", "1"), key("Org", "2")]
+        );
+    }
+
+    #[test]
+    fn validate_pinned_versions() {
+        let schema = schema();
+        let pset: PolicySet = r#"
+            @id("old")
+            permit(principal, action == Docs::Action::"read", resource);
+            @id("new")
+            @schemaVersions("Docs@2, Org@2")
+            permit(principal, action == Docs::Action::"read", resource) when { principal in resource.team };
+            @id("template")
+            @schemaVersions("Org@2")
+            permit(principal in ?principal, action == Docs::Action::"read", resource);
+        "#
+        .parse()
+        .unwrap();
+        let result = schema.validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(
+            result
+                .results()
+                .map(|(versions, _)| versions)
+                .collect::<Vec<_>>(),
+            [
+                &[key("Docs", "1"), key("Org", "1")][..],
+                &[key("Docs", "1"), key("Org", "2")][..],
+                &[key("Docs", "2"), key("Org", "2")][..],
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_pins() {
+        let schema = schema();
+        let pset: PolicySet = r#"
+            @schemaVersions("Docs")
+            permit(principal, action, resource);
+            @schemaVersions("Docs@3")
+            permit(principal, action, resource);
+            @schemaVersions("Docs@2")
+            permit(principal, action, resource);
+        "#
+        .parse()
+        .unwrap();
+        let result = schema.validate(&pset, ValidationMode::Strict);
+        assert!(!result.validation_passed());
+        assert_eq!(result.results().count(), 0);
+        let errors = result.errors().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            PinnedValidationError::MalformedPin { policy_id, .. } if policy_id == &PolicyId::new("policy0")
+        )));
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            PinnedValidationError::UnknownVersion { key: k, .. } if k == &key("Docs", "3")
+        )));
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            PinnedValidationError::InvalidSchema { policy_ids, .. } if policy_ids == &[PolicyId::new("policy2")]
+        )));
+    }
+}
@@ -0,0 +1,550 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Conversion between this crate's formats and the shapes used by the
+//! Amazon Verified Permissions (AVP) API.
+//!
+//! - Policies: [`export_policy_store()`] and [`import_policy_store()`] convert
+//!   a policy set to and from an [`AvpPolicyStore`], whose policies and
+//!   templates have the shapes of AVP's `CreatePolicy` and
+//!   `CreatePolicyTemplate` requests.
+//! - Schemas: AVP's `PutSchema` takes the JSON schema as a string in the
+//!   `cedarJson` field of an [`AvpSchema`], rather than as a JSON object.
+//! - Entities and context: AVP's `IsAuthorized` takes entities as an
+//!   `entityList` and the context as a `contextMap`, in which every value is
+//!   explicitly tagged with its type (e.g., `{ "long": 3 }`), rather than in
+//!   the entity JSON format of this crate. [`entities_to_avp()`],
+//!   [`entities_from_avp()`], and [`context_from_avp()`] convert them.
+//!
+//! AVP generates policy ids when policies are created; the ids of an
+//! exported [`AvpPolicyStore`] are those of the policy set.
+#![doc = include_str!("../experimental_warning.md")]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::result_large_err, // see #878
+)]
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    AvpError, Context, Entities, EntityUid, PolicyId, PolicySet, Schema, SchemaFragment, SlotId,
+    Template,
+};
+
+/// The policies, templates, and optionally the schema of an AVP policy store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpPolicyStore {
+    /// Static and template-linked policies
+    #[serde(default)]
+    pub policies: Vec<AvpPolicy>,
+    /// Policy templates
+    #[serde(default)]
+    pub policy_templates: Vec<AvpPolicyTemplate>,
+    /// Schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<AvpSchema>,
+}
+
+/// A policy, as in AVP's `CreatePolicy` request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpPolicy {
+    /// Policy id
+    pub policy_id: String,
+    /// Policy definition
+    pub definition: AvpPolicyDefinition,
+}
+
+/// The definition of a policy: `{ "static": ... }` or
+/// `{ "templateLinked": ... }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AvpPolicyDefinition {
+    /// A static policy
+    Static(AvpStaticPolicy),
+    /// A template-linked policy
+    TemplateLinked(AvpTemplateLinkedPolicy),
+}
+
+/// A static policy definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpStaticPolicy {
+    /// Policy text
+    pub statement: String,
+    /// Description, which is not part of the policy text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A template-linked policy definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpTemplateLinkedPolicy {
+    /// Id of the template
+    pub policy_template_id: String,
+    /// Value of the `?principal` slot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<AvpEntityIdentifier>,
+    /// Value of the `?resource` slot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<AvpEntityIdentifier>,
+}
+
+/// A policy template, as in AVP's `CreatePolicyTemplate` request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpPolicyTemplate {
+    /// Template id
+    pub policy_template_id: String,
+    /// Template text
+    pub statement: String,
+    /// Description, which is not part of the template text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// An entity uid, as in AVP's API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpEntityIdentifier {
+    /// Entity type
+    pub entity_type: String,
+    /// Entity id
+    pub entity_id: String,
+}
+
+/// A schema, as in AVP's `PutSchema` request: the JSON schema, as a string
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvpSchema {
+    /// The schema in the JSON schema format, serialized to a string
+    pub cedar_json: String,
+}
+
+impl From<&EntityUid> for AvpEntityIdentifier {
+    fn from(uid: &EntityUid) -> Self {
+        Self {
+            entity_type: uid.type_name().to_string(),
+            entity_id: uid.id().as_ref().to_string(),
+        }
+    }
+}
+
+impl AvpEntityIdentifier {
+    fn to_cedar_json(&self) -> Value {
+        json!({ "type": self.entity_type, "id": self.entity_id })
+    }
+
+    fn to_uid(&self) -> Result<EntityUid, AvpError> {
+        Ok(EntityUid::from_json(self.to_cedar_json())?)
+    }
+}
+
+/// Convert `policies`, and `schema` if given, to an AVP policy store
+///
+/// ```
+/// # use cedar_policy::avp::{export_policy_store, import_policy_store};
+/// # use cedar_policy::PolicySet;
+/// # use std::str::FromStr;
+/// let policies = PolicySet::from_str(r#"
+///     permit(principal == User::"alice", action, resource);
+///     permit(principal in ?principal, action, resource);
+/// "#).unwrap();
+/// let store = export_policy_store(&policies, None).unwrap();
+/// assert_eq!(store.policies.len(), 1);
+/// assert_eq!(store.policy_templates.len(), 1);
+/// let (imported, schema) = import_policy_store(&store).unwrap();
+/// assert_eq!(imported.policies().count(), 1);
+/// assert!(schema.is_none());
+/// ```
+pub fn export_policy_store(
+    policies: &PolicySet,
+    schema: Option<&SchemaFragment>,
+) -> Result<AvpPolicyStore, AvpError> {
+    let mut exported: Vec<AvpPolicy> = policies
+        .policies()
+        .map(|p| {
+            let definition = match (p.template_id(), p.template_links()) {
+                (Some(template_id), Some(values)) => {
+                    AvpPolicyDefinition::TemplateLinked(AvpTemplateLinkedPolicy {
+                        policy_template_id: template_id.to_string(),
+                        principal: values.get(&SlotId::principal()).map(Into::into),
+                        resource: values.get(&SlotId::resource()).map(Into::into),
+                    })
+                }
+                _ => AvpPolicyDefinition::Static(AvpStaticPolicy {
+                    statement: p.to_string(),
+                    description: None,
+                }),
+            };
+            AvpPolicy {
+                policy_id: p.id().to_string(),
+                definition,
+            }
+        })
+        .collect();
+    exported.sort_by(|a, b| a.policy_id.cmp(&b.policy_id));
+    let mut policy_templates: Vec<AvpPolicyTemplate> = policies
+        .templates()
+        .map(|t| AvpPolicyTemplate {
+            policy_template_id: t.id().to_string(),
+            statement: t.to_string(),
+            description: None,
+        })
+        .collect();
+    policy_templates.sort_by(|a, b| a.policy_template_id.cmp(&b.policy_template_id));
+    Ok(AvpPolicyStore {
+        policies: exported,
+        policy_templates,
+        schema: schema.map(schema_to_avp).transpose()?,
+    })
+}
+
+/// Convert an AVP policy store to a policy set and, if the store has one, a
+/// schema. Descriptions of policies and templates are dropped.
+pub fn import_policy_store(
+    store: &AvpPolicyStore,
+) -> Result<(PolicySet, Option<SchemaFragment>), AvpError> {
+    let mut policies = PolicySet::new();
+    for template in &store.policy_templates {
+        let id = PolicyId::new(&template.policy_template_id);
+        let parsed = Template::parse(Some(id.clone()), &template.statement)
+            .map_err(|errors| AvpError::Parse { id, errors })?;
+        policies.add_template(parsed)?;
+    }
+    for policy in &store.policies {
+        let id = PolicyId::new(&policy.policy_id);
+        match &policy.definition {
+            AvpPolicyDefinition::Static(p) => {
+                let parsed = crate::Policy::parse(Some(id.clone()), &p.statement)
+                    .map_err(|errors| AvpError::Parse { id, errors })?;
+                policies.add(parsed)?;
+            }
+            AvpPolicyDefinition::TemplateLinked(p) => {
+                let mut values = std::collections::HashMap::new();
+                if let Some(principal) = &p.principal {
+                    values.insert(SlotId::principal(), principal.to_uid()?);
+                }
+                if let Some(resource) = &p.resource {
+                    values.insert(SlotId::resource(), resource.to_uid()?);
+                }
+                policies.link(PolicyId::new(&p.policy_template_id), id, values)?;
+            }
+        }
+    }
+    let schema = store.schema.as_ref().map(schema_from_avp).transpose()?;
+    Ok((policies, schema))
+}
+
+/// Convert `schema` to the shape of AVP's `PutSchema` request
+pub fn schema_to_avp(schema: &SchemaFragment) -> Result<AvpSchema, AvpError> {
+    Ok(AvpSchema {
+        cedar_json: schema.to_json_string()?,
+    })
+}
+
+/// Convert a schema in the shape of AVP's `PutSchema` request
+pub fn schema_from_avp(schema: &AvpSchema) -> Result<SchemaFragment, AvpError> {
+    Ok(SchemaFragment::from_json_str(&schema.cedar_json)?)
+}
+
+/// Convert `entities` to the `entityList` shape of AVP's `IsAuthorized`
+/// request, i.e., `{ "entityList": [...] }`
+///
+/// ```
+/// # use cedar_policy::avp::{entities_from_avp, entities_to_avp};
+/// # use cedar_policy::Entities;
+/// let entities = Entities::from_json_value(serde_json::json!([{
+///     "uid": { "type": "User", "id": "alice" },
+///     "attrs": { "age": 30, "ip": { "__extn": { "fn": "ip", "arg": "10.0.0.1" } } },
+///     "parents": [{ "type": "Group", "id": "admins" }],
+/// }]), None).unwrap();
+/// let avp = entities_to_avp(&entities).unwrap();
+/// assert_eq!(avp["entityList"][0]["attributes"]["age"], serde_json::json!({ "long": 30 }));
+/// assert_eq!(entities_from_avp(&avp, None).unwrap(), entities);
+/// ```
+pub fn entities_to_avp(entities: &Entities) -> Result<Value, AvpError> {
+    let mut buf = Vec::new();
+    entities.write_to_json(&mut buf)?;
+    let cedar: Vec<Value> = serde_json::from_slice(&buf).map_err(AvpError::Json)?;
+    let entity_list = cedar
+        .iter()
+        .map(|entity| {
+            let attrs = entity
+                .get("attrs")
+                .and_then(Value::as_object)
+                .map(|attrs| {
+                    attrs
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), attribute_to_avp(v)?)))
+                        .collect::<Result<Map<_, _>, AvpError>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            let parents = entity
+                .get("parents")
+                .and_then(Value::as_array)
+                .map(|parents| {
+                    parents
+                        .iter()
+                        .map(uid_to_avp)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            Ok(json!({
+                "identifier": uid_to_avp(entity.get("uid").unwrap_or(&Value::Null))?,
+                "attributes": attrs,
+                "parents": parents,
+            }))
+        })
+        .collect::<Result<Vec<_>, AvpError>>()?;
+    Ok(json!({ "entityList": entity_list }))
+}
+
+/// Convert entities in the `entityList` shape of AVP's `IsAuthorized`
+/// request, validating them against `schema`, if given
+pub fn entities_from_avp(avp: &Value, schema: Option<&Schema>) -> Result<Entities, AvpError> {
+    let entity_list = avp
+        .get("entityList")
+        .and_then(Value::as_array)
+        .ok_or_else(|| AvpError::invalid_shape("an object with an `entityList` array"))?;
+    let cedar = entity_list
+        .iter()
+        .map(|entity| {
+            let identifier: AvpEntityIdentifier =
+                serde_json::from_value(entity.get("identifier").cloned().unwrap_or(Value::Null))
+                    .map_err(AvpError::Json)?;
+            let parents = entity
+                .get("parents")
+                .map(|parents| serde_json::from_value::<Vec<AvpEntityIdentifier>>(parents.clone()))
+                .transpose()
+                .map_err(AvpError::Json)?
+                .unwrap_or_default();
+            Ok(json!({
+                "uid": identifier.to_cedar_json(),
+                "attrs": attribute_map_from_avp(entity.get("attributes"))?,
+                "parents": parents.iter().map(AvpEntityIdentifier::to_cedar_json).collect::<Vec<_>>(),
+            }))
+        })
+        .collect::<Result<Vec<_>, AvpError>>()?;
+    Ok(Entities::from_json_value(Value::Array(cedar), schema)?)
+}
+
+/// Convert a context in the `contextMap` shape of AVP's `IsAuthorized`
+/// request, i.e., `{ "contextMap": {...} }`, validating it against the
+/// context type of the given action, if any
+pub fn context_from_avp(
+    avp: &Value,
+    schema: Option<(&Schema, &EntityUid)>,
+) -> Result<Context, AvpError> {
+    let context_map = avp
+        .get("contextMap")
+        .ok_or_else(|| AvpError::invalid_shape("an object with a `contextMap` object"))?;
+    Ok(Context::from_json_value(
+        Value::Object(attribute_map_from_avp(Some(context_map))?),
+        schema,
+    )?)
+}
+
+/// Convert an entity uid in the entity JSON format to AVP's shape
+fn uid_to_avp(uid: &Value) -> Result<Value, AvpError> {
+    let uid = uid.get("__entity").unwrap_or(uid);
+    match (uid.get("type"), uid.get("id")) {
+        (Some(ty), Some(id)) => Ok(json!({ "entityType": ty, "entityId": id })),
+        _ => Err(AvpError::invalid_shape("an entity uid")),
+    }
+}
+
+/// Convert a value in the entity JSON format, as written by this crate, to
+/// AVP's explicitly tagged shape
+fn attribute_to_avp(value: &Value) -> Result<Value, AvpError> {
+    Ok(match value {
+        Value::Bool(b) => json!({ "boolean": b }),
+        Value::Number(n) => json!({ "long": n }),
+        Value::String(s) => json!({ "string": s }),
+        Value::Array(elems) => json!({
+            "set": elems.iter().map(attribute_to_avp).collect::<Result<Vec<_>, _>>()?
+        }),
+        Value::Object(obj) => {
+            if let Some(uid) = obj.get("__entity") {
+                json!({ "entityIdentifier": uid_to_avp(uid)? })
+            } else if let Some(extn) = obj.get("__extn") {
+                let arg = extn.get("arg").cloned().unwrap_or(Value::Null);
+                match extn.get("fn").and_then(Value::as_str) {
+                    Some("ip") => json!({ "ipaddr": arg }),
+                    Some("decimal") => json!({ "decimal": arg }),
+                    _ => {
+                        return Err(AvpError::invalid_shape(
+                            "an `ip` or `decimal` extension value",
+                        ))
+                    }
+                }
+            } else {
+                json!({
+                    "record": obj
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), attribute_to_avp(v)?)))
+                        .collect::<Result<Map<_, _>, AvpError>>()?
+                })
+            }
+        }
+        Value::Null => return Err(AvpError::invalid_shape("a non-null value")),
+    })
+}
+
+fn attribute_map_from_avp(attrs: Option<&Value>) -> Result<Map<String, Value>, AvpError> {
+    match attrs {
+        None => Ok(Map::new()),
+        Some(Value::Object(attrs)) => attrs
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), attribute_from_avp(v)?)))
+            .collect(),
+        Some(_) => Err(AvpError::invalid_shape("an object of attribute values")),
+    }
+}
+
+/// Convert a value in AVP's explicitly tagged shape to the entity JSON format
+fn attribute_from_avp(value: &Value) -> Result<Value, AvpError> {
+    let invalid = || {
+        AvpError::invalid_shape(
+            "an attribute value with one of the keys `boolean`, `long`, `string`, `entityIdentifier`, `set`, `record`, `ipaddr`, or `decimal`",
+        )
+    };
+    let obj = value
+        .as_object()
+        .filter(|obj| obj.len() == 1)
+        .ok_or_else(invalid)?;
+    let (tag, inner) = obj.iter().next().ok_or_else(invalid)?;
+    Ok(match tag.as_str() {
+        "boolean" | "long" | "string" => inner.clone(),
+        "entityIdentifier" => {
+            let uid: AvpEntityIdentifier =
+                serde_json::from_value(inner.clone()).map_err(AvpError::Json)?;
+            json!({ "__entity": uid.to_cedar_json() })
+        }
+        "set" => Value::Array(
+            inner
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(attribute_from_avp)
+                .collect::<Result<_, _>>()?,
+        ),
+        "record" => Value::Object(attribute_map_from_avp(Some(inner))?),
+        "ipaddr" => json!({ "__extn": { "fn": "ip", "arg": inner } }),
+        "decimal" => json!({ "__extn": { "fn": "decimal", "arg": inner } }),
+        _ => return Err(invalid()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn policy_store_roundtrip() {
+        let mut policies = PolicySet::new();
+        policies
+            .add(
+                crate::Policy::parse(
+                    Some(PolicyId::new("p0")),
+                    r#"permit(principal == User::"alice", action == Action::"view", resource);"#,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        policies
+            .add_template(
+                Template::parse(
+                    Some(PolicyId::new("t0")),
+                    "forbid(principal in ?principal, action, resource == ?resource);",
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        policies
+            .link(
+                PolicyId::new("t0"),
+                PolicyId::new("link0"),
+                [
+                    (
+                        SlotId::principal(),
+                        EntityUid::from_str(r#"Group::"banned""#).unwrap(),
+                    ),
+                    (
+                        SlotId::resource(),
+                        EntityUid::from_str(r#"Photo::"x""#).unwrap(),
+                    ),
+                ]
+                .into(),
+            )
+            .unwrap();
+        let (schema, _) = SchemaFragment::from_cedarschema_str(
+            "entity User, Group, Photo; action view appliesTo { principal: User, resource: Photo };",
+        )
+        .unwrap();
+
+        let store = export_policy_store(&policies, Some(&schema)).unwrap();
+        let json = serde_json::to_value(&store).unwrap();
+        assert_eq!(
+            json["policies"][0],
+            json!({
+                "policyId": "link0",
+                "definition": { "templateLinked": {
+                    "policyTemplateId": "t0",
+                    "principal": { "entityType": "Group", "entityId": "banned" },
+                    "resource": { "entityType": "Photo", "entityId": "x" },
+                } },
+            })
+        );
+        assert!(json["policies"][1]["definition"]["static"]["statement"].is_string());
+        assert_eq!(json["policyTemplates"][0]["policyTemplateId"], "t0");
+        assert!(json["schema"]["cedarJson"].is_string());
+
+        let (imported, imported_schema) =
+            import_policy_store(&serde_json::from_value(json).unwrap()).unwrap();
+        assert!(imported.policy(&PolicyId::new("p0")).is_some());
+        assert_eq!(
+            imported
+                .policy(&PolicyId::new("link0"))
+                .and_then(|p| p.template_id().cloned()),
+            Some(PolicyId::new("t0"))
+        );
+        assert!(imported_schema.is_some());
+    }
+
+    #[test]
+    fn attribute_values() {
+        let avp = json!({
+            "contextMap": {
+                "mfa": { "boolean": true },
+                "tags": { "set": [{ "string": "a" }, { "long": 1 }] },
+                "source": { "record": { "ip": { "ipaddr": "10.0.0.1" } } },
+                "owner": { "entityIdentifier": { "entityType": "User", "entityId": "alice" } },
+            }
+        });
+        context_from_avp(&avp, None).unwrap();
+        assert!(
+            context_from_avp(&json!({ "contextMap": { "x": { "float": 1.5 } } }), None).is_err()
+        );
+        assert!(context_from_avp(&json!({ "contextMap": { "x": 1 } }), None).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Ahead-of-time compilation of policy sets to Rust source code, for embedded
+//! or latency-critical deployments which can rebuild whenever their policies
+//! change.
+//!
+//! [`compile_to_rust()`] validates a policy set and emits a Rust module
+//! defining a function with the same signature and behavior as
+//! [`Authorizer::is_authorized()`] for that fixed policy set:
+//! ```ignore
+//! mod compiled_policies; // the output of `compile_to_rust()`
+//! let response = compiled_policies::is_authorized(&request, &entities);
+//! ```
+//! The generated code depends on this crate (with the `codegen` feature
+//! enabled). Use [`check_equivalence()`] in tests to confirm that the compiled
+//! policies agree with the interpreter on a corpus of requests.
+//!
+//! [`generate_types()`] emits Rust types for the entities and action contexts
+//! of a schema, so that the inputs to authorization are checked at compile
+//! time.
+#![doc = include_str!("../experimental_warning.md")]
+#![allow(clippy::missing_errors_doc)]
+
+use itertools::Itertools;
+
+use crate::{
+    Authorizer, CompileToRustError, Entities, PolicySet, Request, Response, Schema, ValidationMode,
+    Validator,
+};
+use cedar_policy_core::codegen;
+pub use cedar_policy_core::codegen::EquivalenceMismatch;
+
+mod types;
+pub use types::generate_types;
+
+/// Options for [`compile_to_rust()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// Name of the generated authorization function. Defaults to
+    /// `is_authorized`.
+    pub function_name: String,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            function_name: "is_authorized".into(),
+        }
+    }
+}
+
+/// Compile `policies` into the source of a Rust module.
+///
+/// The policies must validate against `schema` in strict mode. The module
+/// defines `pub fn is_authorized(request: &Request, entities: &Entities) ->
+/// Response` (renamed according to `options`), which returns the same
+/// decision, determining policies, and erroring policies as
+/// [`Authorizer::is_authorized()`] would for `policies`. Error messages in
+/// the response may lack source locations. The module also defines
+/// `pub const POLICY_IDS: &[&str]`, the ids of the compiled policies.
+pub fn compile_to_rust(
+    policies: &PolicySet,
+    schema: &Schema,
+    options: &CodegenOptions,
+) -> Result<String, CompileToRustError> {
+    let validation = Validator::new(schema.clone()).validate(policies, ValidationMode::Strict);
+    if !validation.validation_passed() {
+        return Err(validation.into());
+    }
+    Ok(codegen::compile_policy_set(
+        &policies.ast,
+        &codegen::CodegenOptions {
+            runtime_path: "::cedar_policy::codegen::runtime".into(),
+            function_name: options.function_name.clone(),
+        },
+    )?)
+}
+
+/// Check that `compiled`, the response of code generated from `policies` for
+/// `request`, is the same as the response of [`Authorizer::is_authorized()`]:
+/// the decision, the determining policies, and the set of policies which
+/// produced errors must all agree. Error messages are not compared.
+pub fn check_equivalence(
+    policies: &PolicySet,
+    request: &Request,
+    entities: &Entities,
+    compiled: &Response,
+) -> Result<(), EquivalenceMismatch> {
+    fn reasons(response: &Response) -> String {
+        format!(
+            "[{}]",
+            response
+                .diagnostics()
+                .reason()
+                .map(ToString::to_string)
+                .sorted()
+                .join(", ")
+        )
+    }
+    fn error_ids(response: &Response) -> String {
+        format!(
+            "[{}]",
+            response
+                .diagnostics()
+                .errors()
+                .map(|e| match e {
+                    crate::AuthorizationError::PolicyEvaluationError(e) => e.policy_id(),
+                })
+                .map(ToString::to_string)
+                .sorted()
+                .join(", ")
+        )
+    }
+    let interpreted = Authorizer::new().is_authorized(request, policies, entities);
+    let aspects = [
+        (
+            "decision",
+            format!("{:?}", compiled.decision()),
+            format!("{:?}", interpreted.decision()),
+        ),
+        (
+            "determining policies",
+            reasons(compiled),
+            reasons(&interpreted),
+        ),
+        (
+            "erroring policies",
+            error_ids(compiled),
+            error_ids(&interpreted),
+        ),
+    ];
+    for (aspect, compiled, interpreted) in aspects {
+        if compiled != interpreted {
+            return Err(EquivalenceMismatch {
+                aspect,
+                compiled,
+                interpreted,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Runtime support for generated code. This is not part of the stable API and
+/// should not be used directly.
+#[doc(hidden)]
+pub mod runtime {
+    pub use cedar_policy_core::codegen::runtime::*;
+
+    pub use crate::{Entities, Request, Response};
+
+    /// Create the evaluation environment for authorizing `request`
+    pub fn env<'a>(request: &Request, entities: &'a Entities) -> Env<'a> {
+        Env::new(&request.0, &entities.0)
+    }
+
+    /// Convert the response computed by [`Decisions::finish()`] into a
+    /// [`Response`]
+    pub fn response(response: cedar_policy_core::authorizer::Response) -> Response {
+        response.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, EntityUid, RestrictedExpression};
+    use cool_asserts::assert_matches;
+    use std::str::FromStr;
+
+    #[test]
+    fn compile_requires_validation() {
+        let schema = Schema::from_str(
+            "entity User; entity Photo; action view appliesTo { principal: User, resource: Photo };",
+        )
+        .unwrap();
+        let valid = PolicySet::from_str(
+            r#"permit(principal == User::"alice", action == Action::"view", resource);"#,
+        )
+        .unwrap();
+        let source = compile_to_rust(&valid, &schema, &CodegenOptions::default()).unwrap();
+        assert!(source.contains("pub fn is_authorized("));
+        assert!(source.contains("use ::cedar_policy::codegen::runtime as rt;"));
+
+        let invalid = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { principal.age > 3 };"#,
+        )
+        .unwrap();
+        assert_matches!(
+            compile_to_rust(&invalid, &schema, &CodegenOptions::default()),
+            Err(CompileToRustError::Validation(_))
+        );
+    }
+
+    #[test]
+    fn equivalence_with_interpreter() {
+        let policies = PolicySet::from_str(
+            r#"permit(principal == User::"alice", action, resource);
+            forbid(principal, action, resource) when { context.blocked };"#,
+        )
+        .unwrap();
+        let request = Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+            Context::from_pairs([("blocked".into(), RestrictedExpression::new_bool(false))])
+                .unwrap(),
+            None,
+        )
+        .unwrap();
+        let entities = Entities::empty();
+        let interpreted = Authorizer::new().is_authorized(&request, &policies, &entities);
+        assert_matches!(
+            check_equivalence(&policies, &request, &entities, &interpreted),
+            Ok(())
+        );
+        let denied = Authorizer::new().is_authorized(&request, &PolicySet::new(), &entities);
+        assert_matches!(
+            check_equivalence(&policies, &request, &entities, &denied),
+            Err(EquivalenceMismatch {
+                aspect: "decision",
+                ..
+            })
+        );
+    }
+}
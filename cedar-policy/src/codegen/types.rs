@@ -0,0 +1,439 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Generation of Rust types for the entities and contexts of a schema
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use cedar_policy_validator::types::{AttributeType, EntityRecordKind, Primitive, Type};
+use itertools::Itertools;
+use smol_str::SmolStr;
+
+use crate::{GenerateTypesError, Schema};
+
+const ENTITY_ID: &str = "::cedar_policy::EntityId";
+const ENTITY_UID: &str = "::cedar_policy::EntityUid";
+const RESTRICTED_EXPRESSION: &str = "::cedar_policy::RestrictedExpression";
+const STRING: &str = "::std::string::String";
+const VEC: &str = "::std::vec::Vec";
+const OPTION: &str = "::std::option::Option";
+
+/// Generate the source of Rust types for the entities and action contexts of
+/// `schema`, so that application code building authorization requests is
+/// checked against the schema at compile time.
+///
+/// For each entity type, e.g., `App::User`, the output defines a struct
+/// `AppUser` holding the entity's id, parents, and attributes (as a struct
+/// `AppUserAttrs`), and implements `From<AppUser> for Entity`. For each
+/// action which applies to some principal type, e.g., `App::Action::"view"`,
+/// it defines a struct `AppViewContext` and implements `From<AppViewContext>
+/// for Context`. Attribute names are converted to snake case, and optional
+/// attributes have `Option` types. Record-typed attributes get their own
+/// structs, and extension-typed attributes are given as
+/// [`crate::RestrictedExpression`]s; conversion to an `Entity` or `Context`
+/// panics if one of these fails to evaluate.
+///
+/// The output only contains items, and refers to this crate as
+/// `::cedar_policy`, so it is suitable for including from a build script:
+/// ```ignore
+/// // build.rs
+/// let schema = cedar_policy::Schema::from_cedarschema_str(SCHEMA)?.0;
+/// let types = cedar_policy::codegen::generate_types(&schema)?;
+/// std::fs::write(Path::new(&env::var("OUT_DIR")?).join("cedar_types.rs"), types)?;
+///
+/// // lib.rs
+/// include!(concat!(env!("OUT_DIR"), "/cedar_types.rs"));
+/// ```
+pub fn generate_types(schema: &Schema) -> Result<String, GenerateTypesError> {
+    let mut generator = Generator::default();
+    for (name, entity_type) in schema
+        .0
+        .entity_types()
+        .sorted_by_cached_key(|(name, _)| name.to_string())
+    {
+        let name = name.to_string();
+        let struct_name = generator.claim_type_name(pascal_case(name.split("::")), &name)?;
+        let attrs_name = generator
+            .claim_type_name(format!("{struct_name}Attrs"), &format!("{name} attributes"))?;
+        generator.record(
+            &attrs_name,
+            &format!("Attributes of entity type `{name}`"),
+            entity_type.attributes(),
+        )?;
+        let _ = writeln!(
+            generator.out,
+            "
+/// Entity of type `{name}`
+#[derive(Debug, Clone)]
+pub struct {struct_name} {{
+    /// Entity id
+    pub id: {ENTITY_ID},
+    /// Parents of the entity
+    pub parents: {VEC}<{ENTITY_UID}>,
+    /// Attributes of the entity
+    pub attrs: {attrs_name},
+}}
+
+impl ::std::convert::From<{struct_name}> for ::cedar_policy::Entity {{
+    fn from(entity: {struct_name}) -> Self {{
+        let uid = {ENTITY_UID}::from_type_name_and_id(
+            {name:?}.parse().expect(\"schema entity types are valid\"),
+            entity.id,
+        );
+        ::cedar_policy::Entity::new(
+            uid,
+            entity.attrs.into_pairs().into_iter().collect(),
+            entity.parents.into_iter().collect(),
+        )
+        .expect(\"attribute values should evaluate\")
+    }}
+}}"
+        );
+    }
+    for action in schema.0.actions().sorted_by_cached_key(ToString::to_string) {
+        let Some(action_id) = schema.0.get_action_id(action) else {
+            continue;
+        };
+        if action_id.principals().next().is_none() {
+            continue;
+        }
+        let name = action.to_string();
+        let type_name = action.entity_type().to_string();
+        let namespace = type_name.split("::").filter(|c| *c != "Action");
+        let struct_name = generator.claim_type_name(
+            format!(
+                "{}Context",
+                pascal_case(namespace.chain([action.eid().as_ref()]))
+            ),
+            &format!("context of {name}"),
+        )?;
+        let attrs: Vec<_> = match action_id.context_type() {
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => attrs.iter().collect(),
+            _ => vec![],
+        };
+        generator.record(&struct_name, &format!("Context of action `{name}`"), attrs)?;
+        let _ = writeln!(
+            generator.out,
+            "
+impl ::std::convert::From<{struct_name}> for ::cedar_policy::Context {{
+    fn from(context: {struct_name}) -> Self {{
+        ::cedar_policy::Context::from_pairs(context.into_pairs())
+            .expect(\"attribute values should evaluate\")
+    }}
+}}"
+        );
+    }
+    Ok(format!(
+        "// Generated by `cedar_policy::codegen::generate_types()`. Do not edit.\n{}",
+        generator.out
+    ))
+}
+
+#[derive(Debug, Default)]
+struct Generator {
+    out: String,
+    /// Generated type names, with what they were generated for
+    type_names: HashMap<String, String>,
+}
+
+impl Generator {
+    fn claim_type_name(
+        &mut self,
+        name: String,
+        origin: &str,
+    ) -> Result<String, GenerateTypesError> {
+        if let Some(first) = self.type_names.get(&name) {
+            return Err(GenerateTypesError::name_collision(&name, first, origin));
+        }
+        self.type_names.insert(name.clone(), origin.to_string());
+        Ok(name)
+    }
+
+    /// Emit a struct named `name` with fields for `attrs`, and an
+    /// `into_pairs()` method returning its attributes as restricted
+    /// expressions
+    fn record<'a>(
+        &mut self,
+        name: &str,
+        doc: &str,
+        attrs: impl IntoIterator<Item = (&'a SmolStr, &'a AttributeType)>,
+    ) -> Result<(), GenerateTypesError> {
+        let mut fields: BTreeMap<String, &str> = BTreeMap::new();
+        let mut decls = String::new();
+        let mut pairs = String::new();
+        for (
+            attr,
+            AttributeType {
+                attr_type,
+                is_required,
+            },
+        ) in attrs
+        {
+            let field = field_name(attr);
+            if let Some(first) = fields.insert(field.clone(), attr.as_str()) {
+                return Err(GenerateTypesError::name_collision(
+                    &format!("{name}::{field}"),
+                    &format!("attribute `{first}`"),
+                    &format!("attribute `{attr}`"),
+                ));
+            }
+            let ty = self.field_type(
+                &format!("{name}{}", pascal_case([attr.as_str()])),
+                attr,
+                attr_type,
+            )?;
+            let value = to_restricted_expression("value", attr_type);
+            if *is_required {
+                let _ = writeln!(decls, "    /// Attribute `{attr}`\n    pub {field}: {ty},");
+                let _ = writeln!(
+                    pairs,
+                    "        let value = self.{field};\n        pairs.push(({attr:?}.into(), {value}));"
+                );
+            } else {
+                let _ = writeln!(
+                    decls,
+                    "    /// Optional attribute `{attr}`\n    pub {field}: {OPTION}<{ty}>,"
+                );
+                let _ = writeln!(
+                    pairs,
+                    "        if let {OPTION}::Some(value) = self.{field} {{\n            pairs.push(({attr:?}.into(), {value}));\n        }}"
+                );
+            }
+        }
+        let _ = writeln!(
+            self.out,
+            "
+/// {doc}
+#[derive(Debug, Clone)]
+pub struct {name} {{
+{decls}}}
+
+impl {name} {{
+    /// The attributes which are present, as restricted expressions
+    #[allow(unused_mut)]
+    pub fn into_pairs(self) -> {VEC}<({STRING}, {RESTRICTED_EXPRESSION})> {{
+        let mut pairs = {VEC}::new();
+{pairs}        pairs
+    }}
+}}"
+        );
+        Ok(())
+    }
+
+    /// The Rust type of a field of type `ty`, generating a struct named
+    /// `record_name` if it is a record
+    fn field_type(
+        &mut self,
+        record_name: &str,
+        attr: &str,
+        ty: &Type,
+    ) -> Result<String, GenerateTypesError> {
+        Ok(match ty {
+            Type::True | Type::False => "bool".into(),
+            Type::Primitive { primitive_type } => match primitive_type {
+                Primitive::Bool => "bool".into(),
+                Primitive::Long => "i64".into(),
+                Primitive::String => STRING.into(),
+            },
+            Type::Set {
+                element_type: Some(element_type),
+            } => format!(
+                "{VEC}<{}>",
+                self.field_type(&format!("{record_name}Element"), attr, element_type)?
+            ),
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => {
+                let name = self.claim_type_name(
+                    record_name.to_string(),
+                    &format!("record attribute `{attr}`"),
+                )?;
+                self.record(&name, &format!("Value of attribute `{attr}`"), attrs.iter())?;
+                name
+            }
+            Type::EntityOrRecord(
+                EntityRecordKind::Entity(_)
+                | EntityRecordKind::AnyEntity
+                | EntityRecordKind::ActionEntity { .. },
+            ) => ENTITY_UID.into(),
+            Type::Never
+            | Type::Set { element_type: None }
+            | Type::ExtensionType { .. }
+            | Type::Union { .. }
+            | Type::Nullable { .. } => RESTRICTED_EXPRESSION.into(),
+        })
+    }
+}
+
+/// Rust expression converting `value`, a field of type `ty`, to a restricted
+/// expression
+fn to_restricted_expression(value: &str, ty: &Type) -> String {
+    match ty {
+        Type::True | Type::False => format!("{RESTRICTED_EXPRESSION}::new_bool({value})"),
+        Type::Primitive { primitive_type } => match primitive_type {
+            Primitive::Bool => format!("{RESTRICTED_EXPRESSION}::new_bool({value})"),
+            Primitive::Long => format!("{RESTRICTED_EXPRESSION}::new_long({value})"),
+            Primitive::String => format!("{RESTRICTED_EXPRESSION}::new_string({value})"),
+        },
+        Type::Set {
+            element_type: Some(element_type),
+        } => format!(
+            "{RESTRICTED_EXPRESSION}::new_set({value}.into_iter().map(|value| {}))",
+            to_restricted_expression("value", element_type)
+        ),
+        Type::EntityOrRecord(EntityRecordKind::Record { .. }) => format!(
+            "{RESTRICTED_EXPRESSION}::new_record({value}.into_pairs()).expect(\"attribute names are distinct\")"
+        ),
+        Type::EntityOrRecord(_) => format!("{RESTRICTED_EXPRESSION}::new_entity_uid({value})"),
+        Type::Never
+        | Type::Set { element_type: None }
+        | Type::ExtensionType { .. }
+        | Type::Union { .. }
+        | Type::Nullable { .. } => value.to_string(),
+    }
+}
+
+/// Split `s` into words at non-alphanumeric characters and at lowercase to
+/// uppercase transitions
+fn words(s: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    let mut prev_lower = false;
+    let mut current = String::new();
+    for c in s.chars() {
+        if !c.is_alphanumeric() {
+            words.extend((!current.is_empty()).then(|| std::mem::take(&mut current)));
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        current.push(c);
+    }
+    words.extend((!current.is_empty()).then_some(current));
+    words
+}
+
+/// Join `components` into an identifier in `PascalCase`
+fn pascal_case<'a>(components: impl IntoIterator<Item = &'a str>) -> String {
+    let name: String = components
+        .into_iter()
+        .flat_map(words)
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_alphabetic()) {
+        name
+    } else {
+        format!("T{name}")
+    }
+}
+
+/// The name of the field for attribute `attr`, in `snake_case`
+fn field_name(attr: &str) -> String {
+    let name = words(attr).iter().map(|word| word.to_lowercase()).join("_");
+    match name.as_str() {
+        "" => "_".into(),
+        "self" | "Self" | "super" | "crate" => format!("{name}_"),
+        "as" | "async" | "await" | "break" | "const" | "continue" | "dyn" | "else" | "enum"
+        | "extern" | "false" | "fn" | "for" | "gen" | "if" | "impl" | "in" | "let" | "loop"
+        | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct"
+        | "trait" | "true" | "type" | "unsafe" | "use" | "where" | "while" | "abstract"
+        | "become" | "box" | "do" | "final" | "macro" | "override" | "priv" | "try" | "typeof"
+        | "unsized" | "virtual" | "yield" => format!("r#{name}"),
+        _ if name.starts_with(|c: char| c.is_numeric()) => format!("_{name}"),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::str::FromStr;
+
+    #[test]
+    fn names() {
+        assert_eq!(pascal_case("App::User".split("::")), "AppUser");
+        assert_eq!(pascal_case(["my_app", "read file"]), "MyAppReadFile");
+        assert_eq!(pascal_case(["2fa"]), "T2fa");
+        assert_eq!(field_name("firstName"), "first_name");
+        assert_eq!(field_name("IP-address"), "ip_address");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("self"), "self_");
+    }
+
+    #[test]
+    fn generate() {
+        let schema = Schema::from_str(
+            r#"
+            namespace App {
+                entity Group;
+                entity User in [Group] = {
+                    name: String,
+                    manager?: User,
+                    address: { city: String, zip?: Long },
+                    tags: Set<String>,
+                    ip: ipaddr,
+                };
+                entity Doc;
+                action view appliesTo {
+                    principal: User,
+                    resource: Doc,
+                    context: { mfa: Bool, "type": String },
+                };
+            }
+            "#,
+        )
+        .unwrap();
+        let source = generate_types(&schema).unwrap();
+        for expected in [
+            "pub struct AppUser {",
+            "pub struct AppUserAttrs {",
+            "    pub manager: ::std::option::Option<::cedar_policy::EntityUid>,",
+            "    pub address: AppUserAttrsAddress,",
+            "    pub zip: ::std::option::Option<i64>,",
+            "    pub tags: ::std::vec::Vec<::std::string::String>,",
+            "    pub ip: ::cedar_policy::RestrictedExpression,",
+            "impl ::std::convert::From<AppUser> for ::cedar_policy::Entity {",
+            "pub struct AppViewContext {",
+            "    pub r#type: ::std::string::String,",
+            "impl ::std::convert::From<AppViewContext> for ::cedar_policy::Context {",
+        ] {
+            assert!(
+                source.contains(expected),
+                "missing `{expected}` in:\n{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn collision() {
+        let schema = Schema::from_str(
+            r#"action view appliesTo { principal: User, resource: User, context: { fooBar: Long, foo_bar: Long } }; entity User;"#,
+        )
+        .unwrap();
+        assert_matches!(
+            generate_types(&schema),
+            Err(GenerateTypesError::NameCollision { .. })
+        );
+    }
+}
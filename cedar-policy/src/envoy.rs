@@ -0,0 +1,348 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! An adapter for using Cedar as the authorization service of Envoy's
+//! external authorization (`ext_authz`) filter.
+//!
+//! An [`EnvoyMapping`] converts the attributes of a `CheckRequest`, in the
+//! protobuf JSON encoding (e.g., `attributes.request.http.method`), into a
+//! Cedar request, and converts the authorization response into a
+//! `CheckResponse`, adding response headers taken from annotations of the
+//! policies which determined the decision.
+//!
+//! Decoding the protobuf messages and serving the `Authorization` gRPC
+//! service is left to the caller.
+#![doc = include_str!("../experimental_warning.md")]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::result_large_err, // see #878
+)]
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    Authorizer, Context, Decision, Entities, EntityUid, EnvoyError, PolicySet, Request, Response,
+    Schema,
+};
+
+/// How to convert the attributes of a `CheckRequest` into a Cedar request,
+/// e.g., loaded from a JSON mapping spec:
+///
+/// ```json
+/// {
+///   "principal": { "entityType": "User", "id": "header:x-user-id" },
+///   "resource": { "entityType": "Route", "id": "/attributes/request/http/path" },
+///   "context": { "sourceIp": "/attributes/source/address/socketAddress/address" },
+///   "responseHeaders": { "reason": "x-authz-reason" }
+/// }
+/// ```
+///
+/// Attributes are named with an [`AttributeSource`]. By default, the action
+/// is `Action::"<HTTP method>"`. Context attributes which are missing from a
+/// `CheckRequest` are left out of the context, which fails validation if the
+/// schema requires them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct EnvoyMapping {
+    /// The principal
+    pub principal: EntityMapping,
+    /// The action
+    #[serde(default = "default_action")]
+    pub action: EntityMapping,
+    /// The resource
+    pub resource: EntityMapping,
+    /// Attributes to use as attributes of the context, by attribute name
+    #[serde(default)]
+    pub context: BTreeMap<String, AttributeSource>,
+    /// Annotations of the policies which determined the decision to add as
+    /// response headers, mapping annotation keys to header names. When
+    /// several of these policies have an annotation, the header value is the
+    /// comma-separated list of their values.
+    #[serde(default)]
+    pub response_headers: BTreeMap<String, String>,
+}
+
+fn default_action() -> EntityMapping {
+    EntityMapping {
+        entity_type: "Action".to_string(),
+        id: AttributeSource::Pointer("/attributes/request/http/method".to_string()),
+    }
+}
+
+/// How to obtain an entity uid from a `CheckRequest`, e.g.,
+/// `{ "entityType": "User", "id": "header:x-user-id" }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct EntityMapping {
+    /// Entity type
+    pub entity_type: String,
+    /// Attribute holding the entity id, which must be a string
+    pub id: AttributeSource,
+}
+
+/// An attribute of a `CheckRequest`: either a JSON pointer into the request
+/// (starting with `/`), or `header:<name>` for an HTTP request header
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AttributeSource {
+    /// A JSON pointer into the `CheckRequest`
+    Pointer(String),
+    /// An HTTP request header, whose name Envoy lowercases
+    Header(String),
+}
+
+impl TryFrom<String> for AttributeSource {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        if source.starts_with('/') {
+            Ok(Self::Pointer(source))
+        } else if let Some(name) = source.strip_prefix("header:") {
+            Ok(Self::Header(name.to_ascii_lowercase()))
+        } else {
+            Err(format!(
+                "invalid attribute `{source}`: expected a JSON pointer or `header:<name>`"
+            ))
+        }
+    }
+}
+
+impl From<AttributeSource> for String {
+    fn from(source: AttributeSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl std::fmt::Display for AttributeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pointer(pointer) => write!(f, "{pointer}"),
+            Self::Header(name) => write!(f, "header:{name}"),
+        }
+    }
+}
+
+impl AttributeSource {
+    fn lookup<'a>(&self, check_request: &'a Value) -> Option<&'a Value> {
+        match self {
+            Self::Pointer(pointer) => check_request.pointer(pointer),
+            Self::Header(name) => check_request
+                .pointer("/attributes/request/http/headers")?
+                .get(name),
+        }
+    }
+}
+
+impl EnvoyMapping {
+    /// Parse a mapping spec from JSON
+    pub fn from_json_value(json: Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(json)
+    }
+
+    /// Parse a mapping spec from a JSON string
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Convert `check_request`, a `CheckRequest` in the protobuf JSON
+    /// encoding, into a Cedar request, validated against `schema` if given
+    pub fn to_request(
+        &self,
+        check_request: &Value,
+        schema: Option<&Schema>,
+    ) -> Result<Request, EnvoyError> {
+        let principal = entity_uid(&self.principal, check_request)?;
+        let action = entity_uid(&self.action, check_request)?;
+        let resource = entity_uid(&self.resource, check_request)?;
+        let context = Context::from_json_value(
+            Value::Object(
+                self.context
+                    .iter()
+                    .filter_map(|(name, source)| {
+                        Some((name.clone(), source.lookup(check_request)?.clone()))
+                    })
+                    .collect(),
+            ),
+            schema.map(|schema| (schema, &action)),
+        )?;
+        Ok(Request::new(principal, action, resource, context, schema)?)
+    }
+
+    /// Convert `response`, the authorization response for a request with
+    /// `policies`, into a `CheckResponse` in the protobuf JSON encoding.
+    /// Denied requests get the HTTP status 403.
+    pub fn to_check_response(&self, response: &Response, policies: &PolicySet) -> Value {
+        let headers: Vec<Value> = self
+            .response_headers
+            .iter()
+            .filter_map(|(annotation, header)| {
+                let mut values: Vec<&str> = response
+                    .diagnostics()
+                    .reason()
+                    .filter_map(|id| policies.annotation(id, annotation))
+                    .collect();
+                values.sort_unstable();
+                (!values.is_empty())
+                    .then(|| json!({ "header": { "key": header, "value": values.join(", ") } }))
+            })
+            .collect();
+        match response.decision() {
+            Decision::Allow => json!({
+                "status": { "code": 0 },
+                "okResponse": { "headers": headers },
+            }),
+            // 7 is the gRPC status code `PERMISSION_DENIED`
+            Decision::Deny => json!({
+                "status": { "code": 7 },
+                "deniedResponse": {
+                    "status": { "code": "Forbidden" },
+                    "headers": headers,
+                },
+            }),
+        }
+    }
+
+    /// Answer `check_request` with `policies` and `entities`, returning a
+    /// `CheckResponse`
+    ///
+    /// ```
+    /// # use cedar_policy::envoy::EnvoyMapping;
+    /// # use cedar_policy::{Entities, PolicySet};
+    /// # use std::str::FromStr;
+    /// let mapping = EnvoyMapping::from_json_value(serde_json::json!({
+    ///     "principal": { "entityType": "User", "id": "header:x-user-id" },
+    ///     "resource": { "entityType": "Route", "id": "/attributes/request/http/path" },
+    ///     "responseHeaders": { "reason": "x-authz-reason" },
+    /// })).unwrap();
+    /// let policies = PolicySet::from_str(r#"
+    ///     @reason("admins may delete")
+    ///     permit(principal == User::"alice", action == Action::"DELETE", resource);
+    /// "#).unwrap();
+    /// let check_request = serde_json::json!({
+    ///     "attributes": { "request": { "http": {
+    ///         "method": "DELETE",
+    ///         "path": "/docs/1",
+    ///         "headers": { "x-user-id": "alice" },
+    ///     } } }
+    /// });
+    /// let response = mapping.check(&check_request, &policies, &Entities::empty(), None).unwrap();
+    /// assert_eq!(response["status"]["code"], 0);
+    /// assert_eq!(response["okResponse"]["headers"][0]["header"]["value"], "admins may delete");
+    /// ```
+    pub fn check(
+        &self,
+        check_request: &Value,
+        policies: &PolicySet,
+        entities: &Entities,
+        schema: Option<&Schema>,
+    ) -> Result<Value, EnvoyError> {
+        let request = self.to_request(check_request, schema)?;
+        let response = Authorizer::new().is_authorized(&request, policies, entities);
+        Ok(self.to_check_response(&response, policies))
+    }
+}
+
+fn entity_uid(mapping: &EntityMapping, check_request: &Value) -> Result<EntityUid, EnvoyError> {
+    let id = match mapping.id.lookup(check_request) {
+        Some(Value::String(id)) => id,
+        Some(_) => return Err(EnvoyError::invalid_attribute(&mapping.id)),
+        None => return Err(EnvoyError::missing_attribute(&mapping.id)),
+    };
+    Ok(EntityUid::from_json(
+        json!({ "type": mapping.entity_type, "id": id }),
+    )?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn mapping() -> EnvoyMapping {
+        EnvoyMapping::from_json_value(json!({
+            "principal": { "entityType": "User", "id": "header:X-User-Id" },
+            "resource": { "entityType": "Route", "id": "/attributes/request/http/path" },
+            "context": { "host": "/attributes/request/http/host" },
+            "responseHeaders": { "reason": "x-authz-reason" },
+        }))
+        .unwrap()
+    }
+
+    fn check_request(user: &str) -> Value {
+        json!({
+            "attributes": { "request": { "http": {
+                "method": "GET",
+                "path": "/admin",
+                "host": "example.com",
+                "headers": { "x-user-id": user },
+            } } }
+        })
+    }
+
+    #[test]
+    fn denied() {
+        let policies = PolicySet::from_str(
+            r#"
+            @reason("admin only")
+            forbid(principal, action, resource == Route::"/admin")
+            unless { principal == User::"root" && context.host == "example.com" };
+            permit(principal, action == Action::"GET", resource);
+            "#,
+        )
+        .unwrap();
+        let entities = Entities::empty();
+
+        let response = mapping()
+            .check(&check_request("alice"), &policies, &entities, None)
+            .unwrap();
+        assert_eq!(
+            response,
+            json!({
+                "status": { "code": 7 },
+                "deniedResponse": {
+                    "status": { "code": "Forbidden" },
+                    "headers": [{ "header": { "key": "x-authz-reason", "value": "admin only" } }],
+                },
+            })
+        );
+
+        let response = mapping()
+            .check(&check_request("root"), &policies, &entities, None)
+            .unwrap();
+        assert_eq!(
+            response,
+            json!({ "status": { "code": 0 }, "okResponse": { "headers": [] } })
+        );
+    }
+
+    #[test]
+    fn invalid_mapping() {
+        assert!(EnvoyMapping::from_json_value(json!({
+            "principal": { "entityType": "User", "id": "x-user-id" },
+            "resource": { "entityType": "Route", "id": "/attributes/request/http/path" },
+        }))
+        .is_err());
+        assert!(matches!(
+            mapping().to_request(&json!({ "attributes": {} }), None),
+            Err(EnvoyError::MissingAttribute { .. })
+        ));
+    }
+}
@@ -49,16 +49,24 @@ pub fn validate(call: ValidationCall) -> ValidationAnswer {
                     error: miette::Report::new(error).into(),
                 })
                 .collect();
+            let mut other_warnings: Vec<DetailedError> =
+                warnings.into_iter().map(Into::into).collect();
             let validation_warnings: Vec<ValidationError> = validation_warnings
-                .map(|error| ValidationError {
-                    policy_id: error.policy_id().clone(),
-                    error: miette::Report::new(error).into(),
+                .filter_map(|warning| {
+                    let Some(policy_id) = warning.policy_id().cloned() else {
+                        other_warnings.push(miette::Report::new(warning).into());
+                        return None;
+                    };
+                    Some(ValidationError {
+                        policy_id,
+                        error: miette::Report::new(warning).into(),
+                    })
                 })
                 .collect();
             ValidationAnswer::Success {
                 validation_errors,
                 validation_warnings,
-                other_warnings: warnings.into_iter().map(Into::into).collect(),
+                other_warnings,
             }
         }
         WithWarnings {
@@ -0,0 +1,719 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Best-effort conversion of AWS IAM policy documents to Cedar policies, to
+//! help migrate existing authorization rules.
+//!
+//! [`import_iam_policy()`] translates each IAM statement into one Cedar
+//! policy:
+//! * `Allow` statements become `permit` policies and `Deny` statements become
+//!   `forbid` policies.
+//! * `Action`/`NotAction` become constraints on the action. Actions are
+//!   entities of type [`IamImportOptions::action_type`] whose ids are the IAM
+//!   action names (e.g., `Action::"s3:GetObject"`). Action wildcards other
+//!   than `*` are expanded using [`IamImportOptions::known_actions`].
+//! * `Resource`/`NotResource` and `Principal`/`NotPrincipal` become `like`
+//!   patterns on the ARN attribute of the resource and principal.
+//! * `Condition` blocks become conditions on `context`, whose attributes are
+//!   the IAM condition keys (e.g., `context["aws:SourceIp"]`). The string,
+//!   numeric, boolean, and IP address operators are supported.
+//!
+//! Statements using anything else (e.g., policy variables, `?` wildcards, or
+//! `ForAnyValue:` condition operators) are not translated. Instead, each is
+//! described by an [`Untranslated`] entry in the returned report. Since a
+//! skipped `Deny` statement makes the resulting policies more permissive than
+//! the original, always review the report.
+#![doc = include_str!("../experimental_warning.md")]
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{IamImportError, Policy, PolicyId, PolicySet};
+
+/// Options for [`import_iam_policy()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IamImportOptions {
+    /// Entity type of the actions. Defaults to `Action`.
+    pub action_type: String,
+    /// Attribute of principals holding their ARN. Defaults to `arn`.
+    pub principal_arn_attr: String,
+    /// Attribute of resources holding their ARN. Defaults to `arn`.
+    pub resource_arn_attr: String,
+    /// IAM actions (e.g., `s3:GetObject`) used to expand action wildcards
+    /// such as `s3:Get*`. Statements with an action wildcard matching none of
+    /// these actions are not translated. Defaults to empty.
+    pub known_actions: Vec<String>,
+    /// Prefix of the ids of the generated policies, which are suffixed with
+    /// the index of the statement. Defaults to `iam`.
+    pub policy_id_prefix: String,
+}
+
+impl Default for IamImportOptions {
+    fn default() -> Self {
+        Self {
+            action_type: "Action".into(),
+            principal_arn_attr: "arn".into(),
+            resource_arn_attr: "arn".into(),
+            known_actions: Vec::new(),
+            policy_id_prefix: "iam".into(),
+        }
+    }
+}
+
+/// The result of [`import_iam_policy()`]
+#[derive(Debug, Clone)]
+pub struct IamImport {
+    /// The translated policies, one for each translated statement. The `Sid`
+    /// of the statement, if any, is recorded in the `sid` annotation.
+    pub policies: PolicySet,
+    /// The statements which were not translated, and why
+    pub untranslated: Vec<Untranslated>,
+}
+
+/// A statement which [`import_iam_policy()`] could not translate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Untranslated {
+    /// Index of the statement in the IAM policy
+    pub statement: usize,
+    /// `Sid` of the statement, if any
+    pub sid: Option<String>,
+    /// Whether the statement is a `Deny` statement. Skipping a `Deny`
+    /// statement makes the translated policies more permissive.
+    pub is_deny: bool,
+    /// Description of the construct which could not be translated
+    pub reason: String,
+}
+
+/// Translate the IAM policy document `json` into Cedar policies, reporting
+/// the statements which could not be translated.
+///
+/// ## Errors
+/// - [`IamImportError::InvalidDocument`] if `json` is not a well-formed IAM
+///   policy document
+///
+/// ```
+/// # use cedar_policy::iam_import::{import_iam_policy, IamImportOptions};
+/// let iam = r#"{
+///     "Version": "2012-10-17",
+///     "Statement": [{
+///         "Sid": "ReadReports",
+///         "Effect": "Allow",
+///         "Action": ["s3:GetObject", "s3:ListBucket"],
+///         "Resource": "arn:aws:s3:::reports/*",
+///         "Condition": { "Bool": { "aws:SecureTransport": "true" } }
+///     }]
+/// }"#;
+/// let import = import_iam_policy(iam, &IamImportOptions::default()).unwrap();
+/// assert!(import.untranslated.is_empty());
+/// assert_eq!(import.policies.policies().count(), 1);
+/// ```
+pub fn import_iam_policy(
+    json: &str,
+    options: &IamImportOptions,
+) -> Result<IamImport, IamImportError> {
+    let document: PolicyDocument = serde_json::from_str(json)?;
+    let mut policies = PolicySet::new();
+    let mut untranslated = Vec::new();
+    for (i, statement) in document.statement.into_vec().into_iter().enumerate() {
+        let is_deny = statement.effect == IamEffect::Deny;
+        let sid = statement.sid.clone();
+        let id = PolicyId::new(format!("{}{i}", options.policy_id_prefix));
+        let result = translate_statement(&statement, options).and_then(|src| {
+            let policy = Policy::parse(Some(id), &src)
+                .map_err(|e| format!("the translated policy is not valid Cedar: {e}\n{src}"))?;
+            policies.add(policy).map_err(|e| e.to_string())
+        });
+        if let Err(reason) = result {
+            untranslated.push(Untranslated {
+                statement: i,
+                sid,
+                is_deny,
+                reason,
+            });
+        }
+    }
+    Ok(IamImport {
+        policies,
+        untranslated,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PolicyDocument {
+    statement: OneOrMany<Statement>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Statement {
+    sid: Option<String>,
+    effect: IamEffect,
+    principal: Option<IamPrincipal>,
+    not_principal: Option<IamPrincipal>,
+    action: Option<OneOrMany<String>>,
+    not_action: Option<OneOrMany<String>>,
+    resource: Option<OneOrMany<String>>,
+    not_resource: Option<OneOrMany<String>>,
+    #[serde(default)]
+    condition: BTreeMap<String, BTreeMap<String, OneOrMany<serde_json::Value>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+enum IamEffect {
+    Allow,
+    Deny,
+}
+
+/// The `Principal` element: either `"*"`, or a map from the kind of principal
+/// (e.g., `AWS` or `Service`) to ARNs
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum IamPrincipal {
+    Wildcard(String),
+    ByKind(BTreeMap<String, OneOrMany<String>>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    // `Many` comes first so that arrays of JSON values are not parsed as a
+    // single value
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(t) => vec![t],
+            Self::Many(ts) => ts,
+        }
+    }
+
+    fn as_slice(&self) -> &[T] {
+        match self {
+            Self::One(t) => std::slice::from_ref(t),
+            Self::Many(ts) => ts,
+        }
+    }
+}
+
+/// Translate a single statement into the source of a Cedar policy, or
+/// describe why it can't be translated
+fn translate_statement(
+    statement: &Statement,
+    options: &IamImportOptions,
+) -> Result<String, String> {
+    let mut src = String::new();
+    if let Some(sid) = &statement.sid {
+        let _ = writeln!(src, "@sid(\"{}\")", sid.escape_debug());
+    }
+    let mut when = Vec::new();
+    let mut unless = Vec::new();
+
+    let action_scope = match (&statement.action, &statement.not_action) {
+        (Some(actions), None) => {
+            let actions = expand_actions(actions.as_slice(), options)?;
+            actions.map_or_else(
+                || "action".to_string(),
+                |actions| {
+                    format!(
+                        "action in [{}]",
+                        actions.iter().map(|a| action_uid(a, options)).join(", ")
+                    )
+                },
+            )
+        }
+        (None, Some(not_actions)) => {
+            match expand_actions(not_actions.as_slice(), options)? {
+                None => return Err("`NotAction` excludes all actions".into()),
+                Some(actions) => unless.push(format!(
+                    "action in [{}]",
+                    actions.iter().map(|a| action_uid(a, options)).join(", ")
+                )),
+            }
+            "action".to_string()
+        }
+        _ => return Err("a statement must have exactly one of `Action` and `NotAction`".into()),
+    };
+
+    match (&statement.resource, &statement.not_resource) {
+        (Some(resources), None) => when.extend(arn_condition(
+            "resource",
+            &options.resource_arn_attr,
+            resources.as_slice(),
+        )?),
+        (None, Some(resources)) => unless.push(
+            arn_condition("resource", &options.resource_arn_attr, resources.as_slice())?
+                .unwrap_or_else(|| "true".into()),
+        ),
+        // resource-based policies may omit the resource
+        (None, None) => {}
+        (Some(_), Some(_)) => {
+            return Err("a statement must not have both `Resource` and `NotResource`".into())
+        }
+    }
+
+    match (&statement.principal, &statement.not_principal) {
+        (Some(principal), None) => when.extend(principal_condition(principal, options)?),
+        (None, Some(principal)) => {
+            unless.push(principal_condition(principal, options)?.unwrap_or_else(|| "true".into()));
+        }
+        (None, None) => {}
+        (Some(_), Some(_)) => {
+            return Err("a statement must not have both `Principal` and `NotPrincipal`".into())
+        }
+    }
+
+    for (operator, keys) in &statement.condition {
+        for (key, values) in keys {
+            when.push(translate_condition(operator, key, values.as_slice())?);
+        }
+    }
+
+    let effect = match statement.effect {
+        IamEffect::Allow => "permit",
+        IamEffect::Deny => "forbid",
+    };
+    let _ = write!(src, "{effect}(principal, {action_scope}, resource)");
+    for cond in when {
+        let _ = write!(src, "\nwhen {{ {cond} }}");
+    }
+    for cond in unless {
+        let _ = write!(src, "\nunless {{ {cond} }}");
+    }
+    src.push(';');
+    Ok(src)
+}
+
+/// Expand wildcards in `actions` using the known actions. Returns `None` if
+/// the actions include `*`, i.e., all actions.
+fn expand_actions(
+    actions: &[String],
+    options: &IamImportOptions,
+) -> Result<Option<Vec<String>>, String> {
+    let mut expanded = Vec::new();
+    let mut seen = HashSet::new();
+    for action in actions {
+        if action == "*" {
+            return Ok(None);
+        }
+        if action.contains(['*', '?']) {
+            let matching = options
+                .known_actions
+                .iter()
+                .filter(|known| iam_wildcard_match(action, known))
+                .collect::<Vec<_>>();
+            if matching.is_empty() {
+                return Err(format!(
+                    "action wildcard `{action}` does not match any of the known actions"
+                ));
+            }
+            for known in matching {
+                if seen.insert(known.clone()) {
+                    expanded.push(known.clone());
+                }
+            }
+        } else if seen.insert(action.clone()) {
+            expanded.push(action.clone());
+        }
+    }
+    Ok(Some(expanded))
+}
+
+/// Match `s` against the IAM pattern `pattern`, where `*` matches any
+/// sequence of characters and `?` any single character. Like IAM, action
+/// names are matched case-insensitively.
+fn iam_wildcard_match(pattern: &str, s: &str) -> bool {
+    fn go(pattern: &[char], s: &[char]) -> bool {
+        match pattern.split_first() {
+            None => s.is_empty(),
+            Some(('*', rest)) => (0..=s.len()).any(|i| s.get(i..).is_some_and(|s| go(rest, s))),
+            Some(('?', rest)) => s.split_first().is_some_and(|(_, s)| go(rest, s)),
+            Some((c, rest)) => s
+                .split_first()
+                .is_some_and(|(d, s)| c.eq_ignore_ascii_case(d) && go(rest, s)),
+        }
+    }
+    go(
+        &pattern.chars().collect::<Vec<_>>(),
+        &s.chars().collect::<Vec<_>>(),
+    )
+}
+
+fn action_uid(action: &str, options: &IamImportOptions) -> String {
+    format!("{}::\"{}\"", options.action_type, action.escape_debug())
+}
+
+/// Translate a list of ARN patterns on `var` into a condition, or `None` if
+/// any pattern is `*`
+fn arn_condition(var: &str, attr: &str, arns: &[String]) -> Result<Option<String>, String> {
+    if arns.iter().any(|arn| arn == "*") {
+        return Ok(None);
+    }
+    let disjuncts = arns
+        .iter()
+        .map(|arn| {
+            let lhs = format!("{var}[\"{}\"]", attr.escape_debug());
+            string_match(&lhs, arn, arn.contains('*'))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(disjunction(disjuncts)))
+}
+
+fn principal_condition(
+    principal: &IamPrincipal,
+    options: &IamImportOptions,
+) -> Result<Option<String>, String> {
+    match principal {
+        IamPrincipal::Wildcard(s) if s == "*" => Ok(None),
+        IamPrincipal::Wildcard(s) => Err(format!("unsupported principal `{s}`")),
+        IamPrincipal::ByKind(kinds) => {
+            let arns = kinds
+                .values()
+                .flat_map(|arns| arns.as_slice().iter().cloned())
+                .collect::<Vec<_>>();
+            arn_condition("principal", &options.principal_arn_attr, &arns)
+        }
+    }
+}
+
+/// Translate a string comparison of `lhs` against `value`, which is an IAM
+/// pattern if `is_pattern`
+fn string_match(lhs: &str, value: &str, is_pattern: bool) -> Result<String, String> {
+    if value.contains("${") {
+        return Err(format!("policy variables are not supported: `{value}`"));
+    }
+    if is_pattern {
+        if value.contains('?') {
+            return Err(format!("the `?` wildcard is not supported: `{value}`"));
+        }
+        // IAM patterns have no escapes, so only `*` is special
+        Ok(format!("{lhs} like \"{}\"", value.escape_debug()))
+    } else {
+        Ok(format!("{lhs} == \"{}\"", value.escape_debug()))
+    }
+}
+
+fn disjunction(disjuncts: Vec<String>) -> String {
+    match disjuncts.len() {
+        1 => disjuncts.into_iter().join(""),
+        _ => format!(
+            "({})",
+            disjuncts.into_iter().map(|d| format!("({d})")).join(" || ")
+        ),
+    }
+}
+
+/// Translate one key of a `Condition` block. IAM requires a condition key to
+/// match any of its values, and the key to be present in the request.
+fn translate_condition(
+    operator: &str,
+    key: &str,
+    values: &[serde_json::Value],
+) -> Result<String, String> {
+    if operator.contains(':') {
+        return Err(format!(
+            "condition operator `{operator}` (on sets of values) is not supported"
+        ));
+    }
+    if operator.ends_with("IfExists") {
+        return Err(format!("condition operator `{operator}` is not supported"));
+    }
+    let key_escaped = key.escape_debug().to_string();
+    let lhs = format!("context[\"{key_escaped}\"]");
+    let value_str = |v: &serde_json::Value| match v {
+        serde_json::Value::String(s) => Ok(s.clone()),
+        serde_json::Value::Bool(b) => Ok(b.to_string()),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        _ => Err(format!("unsupported value `{v}` for condition key `{key}`")),
+    };
+    let values = values
+        .iter()
+        .map(value_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.is_empty() {
+        return Err(format!("condition key `{key}` has no values"));
+    }
+    let (negated, disjuncts) = match operator {
+        "StringEquals" | "StringNotEquals" | "StringLike" | "StringNotLike" => (
+            operator.contains("Not"),
+            values
+                .iter()
+                .map(|v| string_match(&lhs, v, operator.ends_with("Like")))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        "NumericEquals"
+        | "NumericNotEquals"
+        | "NumericLessThan"
+        | "NumericLessThanEquals"
+        | "NumericGreaterThan"
+        | "NumericGreaterThanEquals" => {
+            let op = match operator {
+                "NumericEquals" | "NumericNotEquals" => "==",
+                "NumericLessThan" => "<",
+                "NumericLessThanEquals" => "<=",
+                "NumericGreaterThan" => ">",
+                _ => ">=",
+            };
+            (
+                operator == "NumericNotEquals",
+                values
+                    .iter()
+                    .map(|v| {
+                        v.parse::<i64>()
+                            .map(|n| format!("{lhs} {op} {n}"))
+                            .map_err(|_| format!("only integer values are supported: `{v}`"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+        }
+        "Bool" => (
+            false,
+            values
+                .iter()
+                .map(|v| match v.as_str() {
+                    "true" | "false" => Ok(format!("{lhs} == {v}")),
+                    _ => Err(format!("invalid boolean value `{v}`")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        "IpAddress" | "NotIpAddress" => (
+            operator == "NotIpAddress",
+            values
+                .iter()
+                .map(|v| format!("ip({lhs}).isInRange(ip(\"{}\"))", v.escape_debug()))
+                .collect(),
+        ),
+        _ => return Err(format!("condition operator `{operator}` is not supported")),
+    };
+    let cond = disjunction(disjuncts);
+    Ok(if negated {
+        format!("context has \"{key_escaped}\" && !{cond}")
+    } else {
+        format!("context has \"{key_escaped}\" && {cond}")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        Authorizer, Context, Decision, Entities, EntityUid, Request, RestrictedExpression,
+    };
+    use cool_asserts::assert_matches;
+    use std::str::FromStr;
+
+    fn import(json: &str, options: &IamImportOptions) -> IamImport {
+        import_iam_policy(json, options).unwrap()
+    }
+
+    fn is_authorized(
+        policies: &PolicySet,
+        action: &str,
+        resource_arn: &str,
+        context: &[(&str, RestrictedExpression)],
+    ) -> Decision {
+        let resource = EntityUid::from_str(r#"Object::"o""#).unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([{
+                "uid": { "type": "Object", "id": "o" },
+                "attrs": { "arn": resource_arn },
+                "parents": []
+            }]),
+            None,
+        )
+        .unwrap();
+        let request = Request::new(
+            EntityUid::from_str(r#"User::"u""#).unwrap(),
+            EntityUid::from_str(&format!("Action::{action:?}")).unwrap(),
+            resource,
+            Context::from_pairs(context.iter().map(|(k, v)| ((*k).to_string(), v.clone())))
+                .unwrap(),
+            None,
+        )
+        .unwrap();
+        Authorizer::new()
+            .is_authorized(&request, policies, &entities)
+            .decision()
+    }
+
+    #[test]
+    fn actions_resources_and_conditions() {
+        let json = r#"{
+            "Version": "2012-10-17",
+            "Statement": [
+                {
+                    "Sid": "Read",
+                    "Effect": "Allow",
+                    "Action": "s3:Get*",
+                    "Resource": ["arn:aws:s3:::reports/*", "arn:aws:s3:::public"],
+                    "Condition": { "NumericLessThan": { "s3:max-keys": "10" } }
+                },
+                {
+                    "Effect": "Deny",
+                    "Action": "*",
+                    "Resource": "*",
+                    "Condition": { "Bool": { "aws:SecureTransport": "false" } }
+                }
+            ]
+        }"#;
+        let options = IamImportOptions {
+            known_actions: vec![
+                "s3:GetObject".into(),
+                "s3:GetBucketAcl".into(),
+                "s3:PutObject".into(),
+            ],
+            ..Default::default()
+        };
+        let import = import(json, &options);
+        assert_eq!(import.untranslated, vec![]);
+        let read = import.policies.policy(&PolicyId::new("iam0")).unwrap();
+        assert_eq!(read.annotation("sid"), Some("Read"));
+
+        let ctx = |keys: i64, secure: bool| {
+            vec![
+                ("s3:max-keys", RestrictedExpression::new_long(keys)),
+                (
+                    "aws:SecureTransport",
+                    RestrictedExpression::new_bool(secure),
+                ),
+            ]
+        };
+        let p = &import.policies;
+        assert_eq!(
+            is_authorized(p, "s3:GetObject", "arn:aws:s3:::reports/q1", &ctx(5, true)),
+            Decision::Allow
+        );
+        assert_eq!(
+            is_authorized(p, "s3:GetBucketAcl", "arn:aws:s3:::public", &ctx(5, true)),
+            Decision::Allow
+        );
+        // action not matched by the wildcard
+        assert_eq!(
+            is_authorized(p, "s3:PutObject", "arn:aws:s3:::reports/q1", &ctx(5, true)),
+            Decision::Deny
+        );
+        // resource not matched
+        assert_eq!(
+            is_authorized(p, "s3:GetObject", "arn:aws:s3:::private/x", &ctx(5, true)),
+            Decision::Deny
+        );
+        // condition not satisfied
+        assert_eq!(
+            is_authorized(p, "s3:GetObject", "arn:aws:s3:::reports/q1", &ctx(50, true)),
+            Decision::Deny
+        );
+        // denied by the second statement
+        assert_eq!(
+            is_authorized(p, "s3:GetObject", "arn:aws:s3:::reports/q1", &ctx(5, false)),
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn not_action_and_not_resource() {
+        let json = r#"{
+            "Statement": {
+                "Effect": "Allow",
+                "NotAction": "s3:DeleteObject",
+                "NotResource": "arn:aws:s3:::secret/*"
+            }
+        }"#;
+        let import = import(json, &IamImportOptions::default());
+        assert_eq!(import.untranslated, vec![]);
+        let p = &import.policies;
+        assert_eq!(
+            is_authorized(p, "s3:GetObject", "arn:aws:s3:::public/x", &[]),
+            Decision::Allow
+        );
+        assert_eq!(
+            is_authorized(p, "s3:DeleteObject", "arn:aws:s3:::public/x", &[]),
+            Decision::Deny
+        );
+        assert_eq!(
+            is_authorized(p, "s3:GetObject", "arn:aws:s3:::secret/x", &[]),
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn untranslatable_statements_are_reported() {
+        let json = r#"{
+            "Statement": [
+                {
+                    "Sid": "Vars",
+                    "Effect": "Allow",
+                    "Action": "s3:GetObject",
+                    "Resource": "arn:aws:s3:::home/${aws:username}/*"
+                },
+                {
+                    "Effect": "Deny",
+                    "Action": "s3:Put*",
+                    "Resource": "*"
+                },
+                {
+                    "Effect": "Allow",
+                    "Action": "s3:GetObject",
+                    "Resource": "*",
+                    "Condition": { "ForAnyValue:StringEquals": { "aws:TagKeys": ["a"] } }
+                },
+                {
+                    "Effect": "Allow",
+                    "Action": "s3:GetObject",
+                    "Resource": "*"
+                }
+            ]
+        }"#;
+        let import = import(json, &IamImportOptions::default());
+        assert_eq!(import.policies.policies().count(), 1);
+        assert_eq!(
+            import
+                .untranslated
+                .iter()
+                .map(|u| (u.statement, u.sid.as_deref(), u.is_deny))
+                .collect::<Vec<_>>(),
+            vec![(0, Some("Vars"), false), (1, None, true), (2, None, false)]
+        );
+        assert!(import.untranslated[0].reason.contains("policy variables"));
+        assert!(import.untranslated[1].reason.contains("s3:Put*"));
+        assert!(import.untranslated[2].reason.contains("ForAnyValue"));
+    }
+
+    #[test]
+    fn invalid_document() {
+        assert_matches!(
+            import_iam_policy(
+                r#"{ "Statement": [{ "Effect": "Maybe" }] }"#,
+                &IamImportOptions::default()
+            ),
+            Err(IamImportError::InvalidDocument(_))
+        );
+    }
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(iam_wildcard_match("s3:Get*", "s3:GetObject"));
+        assert!(iam_wildcard_match("s3:get*", "s3:GetObject"));
+        assert!(iam_wildcard_match("s3:?etObject", "s3:GetObject"));
+        assert!(!iam_wildcard_match("s3:Get*", "s3:PutObject"));
+        assert!(!iam_wildcard_match("s3:GetObject", "s3:GetObjectAcl"));
+    }
+}
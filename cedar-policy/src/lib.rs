@@ -51,5 +51,26 @@ pub use api::*;
 /// FFI utilities, see comments in the module itself
 pub mod ffi;
 
+/// Ahead-of-time compilation of policy sets to Rust, and generation of Rust
+/// types from schemas
+#[cfg(feature = "codegen")]
+pub mod codegen;
+
+/// Conversion to and from Amazon Verified Permissions API shapes
+#[cfg(feature = "avp")]
+pub mod avp;
+
+/// Adapter for Envoy's external authorization filter
+#[cfg(feature = "envoy")]
+pub mod envoy;
+
+/// Conversion of AWS IAM policies to Cedar policies
+#[cfg(feature = "iam-import")]
+pub mod iam_import;
+
+/// Export of policies to other policy languages
+#[cfg(feature = "policy-export")]
+pub mod policy_export;
+
 mod prop_test_policy_set;
 mod tests;
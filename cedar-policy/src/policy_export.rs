@@ -0,0 +1,1062 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Export of Cedar policies for review with other policy engines.
+//!
+//! [`export_policies()`] converts a policy set to an intermediate
+//! representation in which each policy is a single condition tree (a
+//! [`Node`]) combining its scope, `when`, and `unless` clauses. The tree is
+//! normalized: nested `&&` and `||` are flattened, negations are pushed into
+//! comparisons where possible, and trivially true conditions are dropped. The
+//! conversion preserves the meaning of the policies; only their formatting
+//! and source locations are lost. [`PolicyExport`] serializes to JSON, whose
+//! format is given by the `serde` attributes of [`Node`]: each node is an
+//! object whose `op` field names the variant.
+//!
+//! [`PolicyExport::to_rego()`] emits an OPA/Rego module from the
+//! intermediate representation. Rego's semantics differ from Cedar's, so this
+//! is best-effort: [`RegoExport::fidelity`] records, for each policy, whether
+//! it was translated exactly, translated with caveats, or omitted.
+#![doc = include_str!("../experimental_warning.md")]
+#![allow(clippy::missing_panics_doc, clippy::missing_errors_doc)]
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use cedar_policy_core::ast;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ActionConstraint, Effect, EntityUid, PolicySet, PrincipalConstraint, ResourceConstraint,
+};
+
+/// The intermediate representation of a policy set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyExport {
+    /// The exported policies, in the order of their ids
+    pub policies: Vec<ExportedPolicy>,
+}
+
+/// The intermediate representation of a single policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedPolicy {
+    /// The policy id
+    pub id: String,
+    /// `permit` or `forbid`
+    pub effect: ExportedEffect,
+    /// The policy's annotations
+    pub annotations: BTreeMap<String, String>,
+    /// The condition under which the policy applies, combining the scope and
+    /// the `when` and `unless` clauses
+    pub condition: Node,
+}
+
+/// Effect of an [`ExportedPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportedEffect {
+    /// The policy permits requests satisfying its condition
+    Permit,
+    /// The policy forbids requests satisfying its condition
+    Forbid,
+}
+
+/// A node of a normalized condition tree.
+///
+/// Entities are identified by their type and id. In the patterns of
+/// [`Node::Like`], `*` is a wildcard, `\*` a literal star, and `\\` a literal
+/// backslash; all other characters stand for themselves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Node {
+    /// All of `args` hold. They are evaluated in order, stopping at the first
+    /// which is false. There are always at least two arguments.
+    And {
+        /// The conjuncts
+        args: Vec<Self>,
+    },
+    /// Any of `args` holds. They are evaluated in order, stopping at the first
+    /// which is true. There are always at least two arguments.
+    Or {
+        /// The disjuncts
+        args: Vec<Self>,
+    },
+    /// `arg` does not hold
+    Not {
+        /// The negated condition
+        arg: Box<Self>,
+    },
+    /// `then` if `test` holds, otherwise `else`
+    If {
+        /// The condition
+        test: Box<Self>,
+        /// The result if `test` holds
+        then: Box<Self>,
+        /// The result if `test` does not hold
+        #[serde(rename = "else")]
+        otherwise: Box<Self>,
+    },
+    /// A comparison
+    Compare {
+        /// The comparison operator
+        cmp: Comparison,
+        /// The left operand
+        left: Box<Self>,
+        /// The right operand
+        right: Box<Self>,
+    },
+    /// Integer arithmetic, which errors on overflow
+    Arith {
+        /// The arithmetic operator
+        arith: Arithmetic,
+        /// The left operand
+        left: Box<Self>,
+        /// The right operand
+        right: Box<Self>,
+    },
+    /// Integer negation, which errors on overflow
+    Neg {
+        /// The negated integer
+        arg: Box<Self>,
+    },
+    /// `left` is equal to or a descendant of the entity `right`, or of one of
+    /// the entities in the set `right`
+    In {
+        /// The descendant
+        left: Box<Self>,
+        /// The ancestor, or set of ancestors
+        right: Box<Self>,
+    },
+    /// The set `set` contains `element`
+    Contains {
+        /// The set
+        set: Box<Self>,
+        /// The element
+        element: Box<Self>,
+    },
+    /// The set `left` contains all elements of the set `right`
+    ContainsAll {
+        /// The superset
+        left: Box<Self>,
+        /// The subset
+        right: Box<Self>,
+    },
+    /// The set `left` contains some element of the set `right`
+    ContainsAny {
+        /// The first set
+        left: Box<Self>,
+        /// The second set
+        right: Box<Self>,
+    },
+    /// The string `left` matches `pattern`
+    Like {
+        /// The string
+        left: Box<Self>,
+        /// The pattern
+        pattern: String,
+    },
+    /// The entity `left` has type `entity_type`
+    Is {
+        /// The entity
+        left: Box<Self>,
+        /// The entity type
+        entity_type: String,
+    },
+    /// The entity or record `left` has the attribute `attr`
+    Has {
+        /// The entity or record
+        left: Box<Self>,
+        /// The attribute
+        attr: String,
+    },
+    /// The attribute `attr` of the entity or record `left`
+    GetAttr {
+        /// The entity or record
+        left: Box<Self>,
+        /// The attribute
+        attr: String,
+    },
+    /// One of `principal`, `action`, `resource`, and `context`
+    Var {
+        /// The variable name
+        name: String,
+    },
+    /// A boolean literal
+    Bool {
+        /// The value
+        value: bool,
+    },
+    /// An integer literal
+    Long {
+        /// The value
+        value: i64,
+    },
+    /// A string literal
+    String {
+        /// The value
+        value: String,
+    },
+    /// An entity literal
+    Entity {
+        /// The entity type
+        entity_type: String,
+        /// The entity id
+        id: String,
+    },
+    /// The `null` literal
+    Null,
+    /// A set of values
+    Set {
+        /// The elements
+        elements: Vec<Self>,
+    },
+    /// A record
+    Record {
+        /// The attributes
+        fields: BTreeMap<String, Self>,
+    },
+    /// A call of an extension function or method, e.g., `ip` or `isInRange`
+    /// (whose first argument is the receiver)
+    Call {
+        /// The function name
+        function: String,
+        /// The arguments
+        args: Vec<Self>,
+    },
+}
+
+/// Comparison operators of [`Node::Compare`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Comparison {
+    /// `==`
+    Eq,
+    /// `!=`
+    NotEq,
+    /// `<`
+    Less,
+    /// `<=`
+    LessEq,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterEq,
+}
+
+impl Comparison {
+    fn negate(self) -> Self {
+        match self {
+            Self::Eq => Self::NotEq,
+            Self::NotEq => Self::Eq,
+            Self::Less => Self::GreaterEq,
+            Self::LessEq => Self::Greater,
+            Self::Greater => Self::LessEq,
+            Self::GreaterEq => Self::Less,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::NotEq => "!=",
+            Self::Less => "<",
+            Self::LessEq => "<=",
+            Self::Greater => ">",
+            Self::GreaterEq => ">=",
+        }
+    }
+}
+
+/// Arithmetic operators of [`Node::Arith`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Arithmetic {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+}
+
+impl Node {
+    fn var(name: &str) -> Self {
+        Self::Var { name: name.into() }
+    }
+
+    fn entity(uid: &EntityUid) -> Self {
+        let uid: &ast::EntityUID = uid.as_ref();
+        Self::Entity {
+            entity_type: uid.entity_type().to_string(),
+            id: AsRef::<str>::as_ref(uid.eid()).to_string(),
+        }
+    }
+
+    /// Conjunction of `args`, flattening nested conjunctions and dropping
+    /// trivially true conjuncts
+    fn and(args: impl IntoIterator<Item = Self>) -> Self {
+        let mut flat = Vec::new();
+        for arg in args {
+            match arg {
+                Self::And { args } => flat.extend(args),
+                Self::Bool { value: true } => {}
+                arg => flat.push(arg),
+            }
+        }
+        match flat.len() {
+            0 => Self::Bool { value: true },
+            1 => flat.remove(0),
+            _ => Self::And { args: flat },
+        }
+    }
+
+    /// Disjunction of `args`, flattening nested disjunctions
+    fn or(args: impl IntoIterator<Item = Self>) -> Self {
+        let mut flat = Vec::new();
+        for arg in args {
+            match arg {
+                Self::Or { args } => flat.extend(args),
+                arg => flat.push(arg),
+            }
+        }
+        match flat.len() {
+            0 => Self::Bool { value: false },
+            1 => flat.remove(0),
+            _ => Self::Or { args: flat },
+        }
+    }
+
+    /// Negation of `arg`, pushed into comparisons and double negations
+    fn not(arg: Self) -> Self {
+        match arg {
+            Self::Not { arg } => *arg,
+            Self::Compare { cmp, left, right } => Self::Compare {
+                cmp: cmp.negate(),
+                left,
+                right,
+            },
+            Self::Bool { value } => Self::Bool { value: !value },
+            arg => Self::Not { arg: Box::new(arg) },
+        }
+    }
+
+    fn binary(left: Self, right: Self, f: impl FnOnce(Box<Self>, Box<Self>) -> Self) -> Self {
+        f(Box::new(left), Box::new(right))
+    }
+}
+
+/// Convert `policies` to the intermediate representation. Templates which
+/// have not been linked are not exported.
+pub fn export_policies(policies: &PolicySet) -> PolicyExport {
+    let mut exported = policies
+        .policies()
+        .map(|policy| {
+            let principal = match policy.principal_constraint() {
+                PrincipalConstraint::Any => None,
+                PrincipalConstraint::In(uid) => Some(scope_in("principal", &uid)),
+                PrincipalConstraint::Eq(uid) => Some(scope_eq("principal", &uid)),
+                PrincipalConstraint::Is(ty) => Some(scope_is("principal", &ty.to_string())),
+                PrincipalConstraint::IsIn(ty, uid) => Some(Node::and([
+                    scope_is("principal", &ty.to_string()),
+                    scope_in("principal", &uid),
+                ])),
+            };
+            let action = match policy.action_constraint() {
+                ActionConstraint::Any => None,
+                ActionConstraint::Eq(uid) => Some(scope_eq("action", &uid)),
+                ActionConstraint::In(uids) => Some(Node::In {
+                    left: Box::new(Node::var("action")),
+                    right: Box::new(Node::Set {
+                        elements: uids.iter().map(Node::entity).collect(),
+                    }),
+                }),
+            };
+            let resource = match policy.resource_constraint() {
+                ResourceConstraint::Any => None,
+                ResourceConstraint::In(uid) => Some(scope_in("resource", &uid)),
+                ResourceConstraint::Eq(uid) => Some(scope_eq("resource", &uid)),
+                ResourceConstraint::Is(ty) => Some(scope_is("resource", &ty.to_string())),
+                ResourceConstraint::IsIn(ty, uid) => Some(Node::and([
+                    scope_is("resource", &ty.to_string()),
+                    scope_in("resource", &uid),
+                ])),
+            };
+            // PANIC SAFETY: every policy of the `PolicySet` is in its AST
+            #[allow(clippy::expect_used)]
+            let ast = policies
+                .ast
+                .get(policy.id().as_ref())
+                .expect("policy should be in the AST of its policy set");
+            let condition = Node::and(
+                [principal, action, resource]
+                    .into_iter()
+                    .flatten()
+                    .chain([convert_expr(ast.non_scope_constraints())]),
+            );
+            ExportedPolicy {
+                id: policy.id().to_string(),
+                effect: match policy.effect() {
+                    Effect::Permit => ExportedEffect::Permit,
+                    Effect::Forbid => ExportedEffect::Forbid,
+                },
+                annotations: policy
+                    .annotations()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                condition,
+            }
+        })
+        .collect::<Vec<_>>();
+    exported.sort_by(|a, b| a.id.cmp(&b.id));
+    PolicyExport { policies: exported }
+}
+
+fn scope_eq(var: &str, uid: &EntityUid) -> Node {
+    Node::Compare {
+        cmp: Comparison::Eq,
+        left: Box::new(Node::var(var)),
+        right: Box::new(Node::entity(uid)),
+    }
+}
+
+fn scope_in(var: &str, uid: &EntityUid) -> Node {
+    Node::In {
+        left: Box::new(Node::var(var)),
+        right: Box::new(Node::entity(uid)),
+    }
+}
+
+fn scope_is(var: &str, entity_type: &str) -> Node {
+    Node::Is {
+        left: Box::new(Node::var(var)),
+        entity_type: entity_type.into(),
+    }
+}
+
+fn convert_expr(expr: &ast::Expr) -> Node {
+    use ast::ExprKind;
+    let convert = |e: &ast::Expr| Box::new(convert_expr(e));
+    match expr.expr_kind() {
+        ExprKind::Lit(lit) => convert_literal(lit),
+        ExprKind::Var(v) => Node::var(&v.to_string()),
+        // Slots only occur in the scope of templates, and unknowns only in
+        // residuals, neither of which are exported
+        ExprKind::Slot(_) | ExprKind::Unknown(_) => Node::Call {
+            function: expr.to_string(),
+            args: Vec::new(),
+        },
+        ExprKind::If {
+            test_expr,
+            then_expr,
+            else_expr,
+        } => Node::If {
+            test: convert(test_expr),
+            then: convert(then_expr),
+            otherwise: convert(else_expr),
+        },
+        ExprKind::And { left, right } => Node::and([convert_expr(left), convert_expr(right)]),
+        ExprKind::Or { left, right } => Node::or([convert_expr(left), convert_expr(right)]),
+        ExprKind::UnaryApp { op, arg } => match op {
+            ast::UnaryOp::Not => Node::not(convert_expr(arg)),
+            ast::UnaryOp::Neg => Node::Neg { arg: convert(arg) },
+        },
+        ExprKind::BinaryApp { op, arg1, arg2 } => {
+            let (left, right) = (convert_expr(arg1), convert_expr(arg2));
+            let compare = |cmp| {
+                Node::binary(left.clone(), right.clone(), |left, right| Node::Compare {
+                    cmp,
+                    left,
+                    right,
+                })
+            };
+            let arith = |arith| {
+                Node::binary(left.clone(), right.clone(), |left, right| Node::Arith {
+                    arith,
+                    left,
+                    right,
+                })
+            };
+            match op {
+                ast::BinaryOp::Eq => compare(Comparison::Eq),
+                ast::BinaryOp::Less => compare(Comparison::Less),
+                ast::BinaryOp::LessEq => compare(Comparison::LessEq),
+                ast::BinaryOp::Add => arith(Arithmetic::Add),
+                ast::BinaryOp::Sub => arith(Arithmetic::Sub),
+                ast::BinaryOp::Mul => arith(Arithmetic::Mul),
+                ast::BinaryOp::In => {
+                    Node::binary(left, right, |left, right| Node::In { left, right })
+                }
+                ast::BinaryOp::Contains => {
+                    Node::binary(left, right, |set, element| Node::Contains { set, element })
+                }
+                ast::BinaryOp::ContainsAll => {
+                    Node::binary(left, right, |left, right| Node::ContainsAll { left, right })
+                }
+                ast::BinaryOp::ContainsAny => {
+                    Node::binary(left, right, |left, right| Node::ContainsAny { left, right })
+                }
+            }
+        }
+        ExprKind::ExtensionFunctionApp { fn_name, args } => Node::Call {
+            function: fn_name.to_string(),
+            args: args.iter().map(convert_expr).collect(),
+        },
+        ExprKind::GetAttr { expr, attr } => Node::GetAttr {
+            left: convert(expr),
+            attr: attr.to_string(),
+        },
+        ExprKind::HasAttr { expr, attr } => Node::Has {
+            left: convert(expr),
+            attr: attr.to_string(),
+        },
+        ExprKind::Like { expr, pattern } => Node::Like {
+            left: convert(expr),
+            pattern: pattern
+                .iter()
+                .map(|elem| match elem {
+                    ast::PatternElem::Wildcard => "*".to_string(),
+                    ast::PatternElem::Char(c @ ('*' | '\\')) => format!("\\{c}"),
+                    ast::PatternElem::Char(c) => c.to_string(),
+                })
+                .collect(),
+        },
+        ExprKind::Is { expr, entity_type } => Node::Is {
+            left: convert(expr),
+            entity_type: entity_type.to_string(),
+        },
+        ExprKind::Set(elements) => Node::Set {
+            elements: elements.iter().map(convert_expr).collect(),
+        },
+        ExprKind::Record(fields) => Node::Record {
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), convert_expr(v)))
+                .collect(),
+        },
+    }
+}
+
+fn convert_literal(lit: &ast::Literal) -> Node {
+    match lit {
+        ast::Literal::Bool(value) => Node::Bool { value: *value },
+        ast::Literal::Long(value) => Node::Long { value: *value },
+        ast::Literal::String(value) => Node::String {
+            value: value.to_string(),
+        },
+        ast::Literal::EntityUID(uid) => Node::Entity {
+            entity_type: uid.entity_type().to_string(),
+            id: AsRef::<str>::as_ref(uid.eid()).to_string(),
+        },
+        ast::Literal::Null => Node::Null,
+    }
+}
+
+/// How faithfully [`PolicyExport::to_rego()`] translated a policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fidelity {
+    /// The Rego rule agrees with the Cedar policy on all requests, provided
+    /// the input follows the conventions of [`PolicyExport::to_rego()`]
+    Exact,
+    /// The Rego rule may disagree with the Cedar policy on some requests, for
+    /// the given reasons
+    Lossy(Vec<String>),
+    /// The policy was not translated, for the given reason
+    Omitted(String),
+}
+
+/// The result of [`PolicyExport::to_rego()`]
+#[derive(Debug, Clone)]
+pub struct RegoExport {
+    /// The source of the Rego module
+    pub module: String,
+    /// The fidelity of the translation of each policy, by policy id
+    pub fidelity: BTreeMap<String, Fidelity>,
+}
+
+const REGO_PRELUDE: &str = r#"default allow := false
+
+allow if {
+	count(forbids) == 0
+	count(permits) > 0
+}
+
+cedar_attr(x, a) := data.entities[x].attrs[a] if is_string(x)
+
+cedar_attr(x, a) := x[a] if is_object(x)
+
+cedar_has(x, a) if {
+	is_string(x)
+	_ = data.entities[x].attrs[a]
+}
+
+cedar_has(x, a) if {
+	is_object(x)
+	_ = x[a]
+}
+
+cedar_in(x, y) if {
+	is_string(y)
+	x == y
+}
+
+cedar_in(x, y) if {
+	is_string(y)
+	y in data.entities[x].ancestors
+}
+
+cedar_in(x, ys) if {
+	not is_string(ys)
+	some y in ys
+	cedar_in(x, y)
+}
+
+cedar_contains(s, e) if {
+	some x in s
+	x == e
+}
+
+cedar_contains_all(a, b) if {
+	every x in b {
+		cedar_contains(a, x)
+	}
+}
+
+cedar_contains_any(a, b) if {
+	some x in b
+	cedar_contains(a, x)
+}
+
+cedar_is(x, t) if startswith(x, concat("", [t, "::\""]))
+"#;
+
+impl PolicyExport {
+    /// Serialize the intermediate representation to JSON
+    pub fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+
+    /// Emit a Rego module in package `package` defining `allow`, which holds
+    /// if some permit policy and no forbid policy applies, and the sets
+    /// `permits` and `forbids` of ids of the applicable policies.
+    ///
+    /// The module expects `input.principal`, `input.action`, and
+    /// `input.resource` to be entity uids in Cedar syntax (e.g.,
+    /// `User::"alice"`) and `input.context` to be an object. The entities are
+    /// read from `data.entities`, an object mapping entity uids to objects
+    /// with fields `attrs` and `ancestors`, which must list all ancestors,
+    /// including indirect ones. Entity-typed attributes and set elements
+    /// must also be entity uids in Cedar syntax.
+    ///
+    /// Policies using extension functions or `if-then-else` are omitted;
+    /// [`RegoExport::fidelity`] records which policies were translated and
+    /// with which caveats.
+    pub fn to_rego(&self, package: &str) -> RegoExport {
+        let mut module = format!("package {package}\n\nimport rego.v1\n\n{REGO_PRELUDE}");
+        let mut fidelity = BTreeMap::new();
+        let mut helpers = RegoHelpers::default();
+        for policy in &self.policies {
+            let mut translator = RegoTranslator {
+                helpers: &mut helpers,
+                caveats: Vec::new(),
+            };
+            let set = match policy.effect {
+                ExportedEffect::Permit => "permits",
+                ExportedEffect::Forbid => "forbids",
+            };
+            let id = serde_json::Value::String(policy.id.clone());
+            match translator.body(&policy.condition) {
+                Ok(body) => {
+                    let caveats = translator.caveats;
+                    let _ = writeln!(module, "\n{set} contains {id} if {{");
+                    if body.is_empty() {
+                        module.push_str("\ttrue\n");
+                    }
+                    for stmt in body {
+                        let _ = writeln!(module, "\t{stmt}");
+                    }
+                    module.push_str("}\n");
+                    fidelity.insert(
+                        policy.id.clone(),
+                        if caveats.is_empty() {
+                            Fidelity::Exact
+                        } else {
+                            Fidelity::Lossy(caveats.into_iter().unique().collect())
+                        },
+                    );
+                }
+                Err(reason) => {
+                    let _ = writeln!(module, "\n# policy {id} omitted: {reason}");
+                    fidelity.insert(policy.id.clone(), Fidelity::Omitted(reason));
+                }
+            }
+        }
+        module.push_str(&helpers.rules);
+        RegoExport { module, fidelity }
+    }
+}
+
+/// Helper rules generated for disjunctions and negations
+#[derive(Debug, Default)]
+struct RegoHelpers {
+    count: usize,
+    rules: String,
+}
+
+struct RegoTranslator<'a> {
+    helpers: &'a mut RegoHelpers,
+    caveats: Vec<String>,
+}
+
+impl RegoTranslator<'_> {
+    /// Translate a boolean-valued node into the statements of a rule body,
+    /// all of which must hold
+    fn body(&mut self, node: &Node) -> Result<Vec<String>, String> {
+        match node {
+            Node::Bool { value: true } => Ok(Vec::new()),
+            Node::Bool { value: false } => Ok(vec!["false".into()]),
+            Node::And { args } => {
+                let mut body = Vec::new();
+                for arg in args {
+                    body.extend(self.body(arg)?);
+                }
+                Ok(body)
+            }
+            Node::Or { args } => {
+                let bodies = args
+                    .iter()
+                    .map(|arg| self.body(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(vec![self.helper(bodies)])
+            }
+            Node::Not { arg } => {
+                self.caveats
+                    .push("a negated condition which errors in Cedar is satisfied in Rego".into());
+                let body = self.body(arg)?;
+                Ok(vec![format!("not {}", self.helper(vec![body]))])
+            }
+            Node::If { .. } => Err("`if-then-else` is not supported".into()),
+            Node::Compare { cmp, left, right } => {
+                if matches!(cmp, Comparison::Eq | Comparison::NotEq)
+                    && (matches!(**left, Node::Set { .. }) || matches!(**right, Node::Set { .. }))
+                {
+                    self.caveats.push(
+                        "set equality depends on how sets are represented in the input".into(),
+                    );
+                }
+                Ok(vec![format!(
+                    "{} {} {}",
+                    self.term(left)?,
+                    cmp.symbol(),
+                    self.term(right)?
+                )])
+            }
+            Node::In { left, right } => Ok(vec![format!(
+                "cedar_in({}, {})",
+                self.term(left)?,
+                self.term(right)?
+            )]),
+            Node::Contains { set, element } => Ok(vec![format!(
+                "cedar_contains({}, {})",
+                self.term(set)?,
+                self.term(element)?
+            )]),
+            Node::ContainsAll { left, right } => Ok(vec![format!(
+                "cedar_contains_all({}, {})",
+                self.term(left)?,
+                self.term(right)?
+            )]),
+            Node::ContainsAny { left, right } => Ok(vec![format!(
+                "cedar_contains_any({}, {})",
+                self.term(left)?,
+                self.term(right)?
+            )]),
+            Node::Like { left, pattern } => {
+                let regex = pattern_to_regex(pattern);
+                Ok(vec![format!(
+                    "regex.match({}, {})",
+                    serde_json::Value::String(regex),
+                    self.term(left)?
+                )])
+            }
+            Node::Is { left, entity_type } => Ok(vec![format!(
+                "cedar_is({}, {})",
+                self.term(left)?,
+                serde_json::Value::String(entity_type.clone())
+            )]),
+            Node::Has { left, attr } => Ok(vec![format!(
+                "cedar_has({}, {})",
+                self.term(left)?,
+                serde_json::Value::String(attr.clone())
+            )]),
+            Node::GetAttr { .. } | Node::Var { .. } | Node::Call { .. } => {
+                Ok(vec![format!("{} == true", self.term(node)?)])
+            }
+            Node::Arith { .. }
+            | Node::Neg { .. }
+            | Node::Long { .. }
+            | Node::String { .. }
+            | Node::Entity { .. }
+            | Node::Null
+            | Node::Set { .. }
+            | Node::Record { .. } => Err("the policy condition is not a boolean".into()),
+        }
+    }
+
+    /// Translate a node into a Rego term
+    fn term(&mut self, node: &Node) -> Result<String, String> {
+        match node {
+            Node::Var { name } => Ok(format!("input.{name}")),
+            Node::Bool { value } => Ok(value.to_string()),
+            Node::Long { value } => Ok(value.to_string()),
+            Node::String { value } => Ok(serde_json::Value::String(value.clone()).to_string()),
+            Node::Entity { entity_type, id } => Ok(serde_json::Value::String(format!(
+                "{entity_type}::\"{}\"",
+                id.escape_debug()
+            ))
+            .to_string()),
+            Node::Null => Ok("null".into()),
+            Node::Set { elements } if elements.is_empty() => Ok("set()".into()),
+            Node::Set { elements } => Ok(format!(
+                "{{{}}}",
+                elements
+                    .iter()
+                    .map(|e| self.term(e))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ")
+            )),
+            Node::Record { fields } => Ok(format!(
+                "{{{}}}",
+                fields
+                    .iter()
+                    .map(|(k, v)| Ok(format!(
+                        "{}: {}",
+                        serde_json::Value::String(k.clone()),
+                        self.term(v)?
+                    )))
+                    .collect::<Result<Vec<_>, String>>()?
+                    .join(", ")
+            )),
+            Node::GetAttr { left, attr } => Ok(format!(
+                "cedar_attr({}, {})",
+                self.term(left)?,
+                serde_json::Value::String(attr.clone())
+            )),
+            Node::Arith { arith, left, right } => {
+                self.caveats
+                    .push("Rego arithmetic does not error on overflow".into());
+                let op = match arith {
+                    Arithmetic::Add => "+",
+                    Arithmetic::Sub => "-",
+                    Arithmetic::Mul => "*",
+                };
+                Ok(format!("({} {op} {})", self.term(left)?, self.term(right)?))
+            }
+            Node::Neg { arg } => {
+                self.caveats
+                    .push("Rego arithmetic does not error on overflow".into());
+                Ok(format!("(0 - {})", self.term(arg)?))
+            }
+            Node::Call { function, .. } => {
+                Err(format!("extension function `{function}` is not supported"))
+            }
+            Node::If { .. } => Err("`if-then-else` is not supported".into()),
+            Node::And { .. }
+            | Node::Or { .. }
+            | Node::Not { .. }
+            | Node::Compare { .. }
+            | Node::In { .. }
+            | Node::Contains { .. }
+            | Node::ContainsAll { .. }
+            | Node::ContainsAny { .. }
+            | Node::Like { .. }
+            | Node::Is { .. }
+            | Node::Has { .. } => {
+                Err("boolean expressions used as values are not supported".into())
+            }
+        }
+    }
+
+    /// Define a helper rule which holds if any of `bodies` holds, and return
+    /// its name
+    fn helper(&mut self, bodies: Vec<Vec<String>>) -> String {
+        let name = format!("cond_{}", self.helpers.count);
+        self.helpers.count += 1;
+        for body in bodies {
+            let _ = writeln!(self.helpers.rules, "\n{name} if {{");
+            if body.is_empty() {
+                self.helpers.rules.push_str("\ttrue\n");
+            }
+            for stmt in body {
+                let _ = writeln!(self.helpers.rules, "\t{stmt}");
+            }
+            self.helpers.rules.push_str("}\n");
+        }
+        name
+    }
+}
+
+/// Convert the pattern of a [`Node::Like`] into an anchored regular expression
+fn pattern_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^(?s)");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '\\' => match chars.next() {
+                Some(c) => regex.push_str(&regex_escape(c)),
+                None => regex.push_str(&regex_escape('\\')),
+            },
+            c => regex.push_str(&regex_escape(c)),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn regex_escape(c: char) -> String {
+    if "\\.+*?()|[]{}^$".contains(c) {
+        format!("\\{c}")
+    } else {
+        c.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn export(src: &str) -> PolicyExport {
+        export_policies(&PolicySet::from_str(src).unwrap())
+    }
+
+    #[test]
+    fn normalization() {
+        let export = export(
+            r#"permit(principal in Group::"admins", action in [Action::"view"], resource is Photo)
+            when { (context.a && context.b) && context.c }
+            unless { principal.level < 3 || !context.d };"#,
+        );
+        let condition = &export.policies[0].condition;
+        let Node::And { args } = condition else {
+            panic!("expected a conjunction, got {condition:?}")
+        };
+        // three scope constraints, three `when` conjuncts, and one `unless`
+        assert_eq!(args.len(), 7);
+        assert_eq!(
+            args[0],
+            Node::In {
+                left: Box::new(Node::var("principal")),
+                right: Box::new(Node::Entity {
+                    entity_type: "Group".into(),
+                    id: "admins".into()
+                }),
+            }
+        );
+        assert_eq!(
+            args[6],
+            Node::Not {
+                arg: Box::new(Node::Or {
+                    args: vec![
+                        Node::Compare {
+                            cmp: Comparison::Less,
+                            left: Box::new(Node::GetAttr {
+                                left: Box::new(Node::var("principal")),
+                                attr: "level".into()
+                            }),
+                            right: Box::new(Node::Long { value: 3 }),
+                        },
+                        Node::Not {
+                            arg: Box::new(Node::GetAttr {
+                                left: Box::new(Node::var("context")),
+                                attr: "d".into()
+                            })
+                        },
+                    ]
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn negated_comparisons() {
+        let export =
+            export(r#"forbid(principal, action, resource) when { principal != resource };"#);
+        assert_eq!(
+            export.policies[0].condition,
+            Node::Compare {
+                cmp: Comparison::NotEq,
+                left: Box::new(Node::var("principal")),
+                right: Box::new(Node::var("resource")),
+            }
+        );
+        assert_eq!(export.policies[0].effect, ExportedEffect::Forbid);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let export = export(
+            r#"@doc("x") permit(principal, action, resource) when { resource.tags.contains("a") && ip("1.2.3.4").isLoopback() };"#,
+        );
+        let json = export.to_json().unwrap();
+        assert_eq!(json["policies"][0]["annotations"]["doc"], "x");
+        assert_eq!(json["policies"][0]["condition"]["op"], "and");
+        let back: PolicyExport = serde_json::from_value(json).unwrap();
+        assert_eq!(back, export);
+    }
+
+    #[test]
+    fn rego() {
+        let export = export(
+            r#"
+            permit(principal == User::"alice", action, resource) when { resource.owner == principal || resource.public };
+            forbid(principal, action, resource) unless { context.mfa };
+            permit(principal, action, resource) when { ip(context.ip).isLoopback() };
+            permit(principal, action, resource) when { resource.name like "*.jpg" && resource.size + 1 > 10 };
+            "#,
+        );
+        let rego = export.to_rego("cedar.authz");
+        assert!(rego
+            .module
+            .starts_with("package cedar.authz\n\nimport rego.v1\n"));
+        assert!(rego.module.contains("permits contains \"policy0\" if {"));
+        assert!(rego
+            .module
+            .contains("\tinput.principal == \"User::\\\"alice\\\"\"\n"));
+        assert!(rego
+            .module
+            .contains("\tcedar_attr(input.resource, \"owner\") == input.principal\n"));
+        assert!(rego.module.contains("forbids contains \"policy1\" if {"));
+        assert!(rego
+            .module
+            .contains("regex.match(\"^(?s).*\\\\.jpg$\", cedar_attr(input.resource, \"name\"))"));
+        assert!(rego.module.contains("# policy \"policy2\" omitted"));
+        assert_eq!(rego.fidelity["policy0"], Fidelity::Exact);
+        assert!(matches!(rego.fidelity["policy1"], Fidelity::Lossy(_)));
+        assert!(matches!(rego.fidelity["policy2"], Fidelity::Omitted(_)));
+        assert!(matches!(rego.fidelity["policy3"], Fidelity::Lossy(_)));
+    }
+
+    #[test]
+    fn like_patterns() {
+        assert_eq!(pattern_to_regex("a*b"), "^(?s)a.*b$");
+        assert_eq!(pattern_to_regex(r"a\*b.c"), r"^(?s)a\*b\.c$");
+        assert_eq!(pattern_to_regex(r"a\\b"), r"^(?s)a\\b$");
+    }
+}
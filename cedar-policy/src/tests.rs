@@ -26,7 +26,7 @@ use cedar_policy_core::authorizer;
 use cedar_policy_core::entities::{self};
 use cedar_policy_core::test_utils::{expect_err, ExpectedErrorMessageBuilder};
 use miette::Report;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 
 mod entity_uid_tests {
@@ -1369,6 +1369,43 @@ mod ancestors_tests {
         assert!(ans.contains(&b_euid));
         assert!(ans.contains(&a_euid));
     }
+
+    #[test]
+    fn test_ancestors_on_demand() {
+        let a_euid: EntityUid = EntityUid::from_strs("test", "A");
+        let b_euid: EntityUid = EntityUid::from_strs("test", "b");
+        let c_euid: EntityUid = EntityUid::from_strs("test", "C");
+        let d_euid: EntityUid = EntityUid::from_strs("test", "D");
+        let a = Entity::new_no_attrs(a_euid.clone(), HashSet::new());
+        let b = Entity::new_no_attrs(b_euid.clone(), std::iter::once(a_euid.clone()).collect());
+        let c = Entity::new_no_attrs(c_euid.clone(), std::iter::once(b_euid.clone()).collect());
+        let es = Entities::from_entities_with_tc([a, b, c], None, TCComputation::ComputeOnDemand)
+            .unwrap();
+        let ans = es.ancestors(&c_euid).unwrap().collect::<HashSet<_>>();
+        assert_eq!(ans, HashSet::from([&a_euid, &b_euid]));
+        assert!(es.is_ancestor_of(&a_euid, &c_euid));
+
+        // added entities are linked into the hierarchy on demand, too
+        let d = Entity::new_no_attrs(d_euid.clone(), std::iter::once(c_euid.clone()).collect());
+        let es = es.add_entities([d], None).unwrap();
+        assert!(es.0.computes_tc_on_demand());
+        assert!(es.is_ancestor_of(&a_euid, &d_euid));
+
+        let policies = PolicySet::from_str(
+            r#"permit(principal in test::"A", action, resource == test::"C");"#,
+        )
+        .unwrap();
+        let request = Request::new(
+            d_euid,
+            EntityUid::from_strs("Action", "view"),
+            c_euid,
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &policies, &es);
+        assert_eq!(response.decision(), Decision::Allow);
+    }
 }
 
 /// A few tests of validating entities.
@@ -4102,6 +4139,66 @@ mod error_source_tests {
     }
 }
 
+mod validation_summary_tests {
+    use super::*;
+
+    fn validate(max_errors: Option<usize>) -> ValidationResult {
+        let schema = Schema::from_str(
+            "entity User = { name: String }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action == Action::"view", resource) when { principal.nmae == "" };
+            permit(principal, action == Action::"view", resource);
+            permit(principal, action == Action::"view", resource) when { principal.age > 1 && resource.age > 1 };
+            "#,
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        max_errors.map_or_else(
+            || validator.validate(&pset, ValidationMode::Strict),
+            |max_errors| {
+                validator.validate_with_max_errors(&pset, ValidationMode::Strict, max_errors)
+            },
+        )
+    }
+
+    #[test]
+    fn errors_by_policy() {
+        let result = validate(None);
+        let by_policy = result.errors_by_policy();
+        assert_eq!(by_policy.len(), 2);
+        assert_eq!(by_policy[&PolicyId::new("policy0")].len(), 1);
+        assert_eq!(by_policy[&PolicyId::new("policy2")].len(), 2);
+        assert!(!by_policy.contains_key(&PolicyId::new("policy1")));
+
+        let summary = result.summary();
+        assert_eq!(summary.error_count(), 3);
+        assert_eq!(summary.errors_of_kind("UnsafeAttributeAccess"), 3);
+        assert_eq!(
+            summary.errors_by_kind().collect::<Vec<_>>(),
+            vec![("UnsafeAttributeAccess", 3)]
+        );
+        assert_eq!(summary.errors_in_policy(&PolicyId::new("policy2")), 2);
+        assert_eq!(summary.errors_in_policy(&PolicyId::new("policy1")), 0);
+        assert_eq!(summary.policies_with_errors(), 2);
+        assert!(!summary.is_truncated());
+    }
+
+    #[test]
+    fn max_errors() {
+        let result = validate(Some(1));
+        assert!(result.is_truncated());
+        assert_eq!(result.validation_errors().count(), 1);
+        assert!(result.summary().is_truncated());
+
+        let result = validate(Some(3));
+        assert!(!result.is_truncated());
+        assert_eq!(result.validation_errors().count(), 3);
+    }
+}
+
 mod issue_779 {
     use crate::Schema;
     use cool_asserts::assert_matches;
@@ -4174,6 +4271,576 @@ permit(principal, action, resource) when { {"\n": 0} };"#,
         round_trip(r#"permit(principal, action, resource) when { Foo::"\n\r\\" };"#);
     }
 }
+mod json_round_trip_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use miette::Diagnostic;
+    use serde_json::json;
+
+    const POLICY: &str = r#"
+        @id("view-photos")
+        @advice("ask an admin")
+        permit(principal in Group::"friends", action == Action::"view", resource)
+        when { resource.public }
+        unless { principal.blocked }
+        when { context.mfa };"#;
+
+    const TEMPLATE: &str = r#"
+        @id("share")
+        permit(principal == ?principal, action, resource in ?resource)
+        when { context.shared };"#;
+
+    #[test]
+    fn policy_preserves_annotations_and_conditions() {
+        let policy = Policy::parse(None, POLICY).unwrap();
+        let json = policy.to_json().unwrap();
+        let round_tripped = Policy::from_json(None, json.clone()).unwrap();
+        // the round trip loses source locations, so compare the ESTs, also
+        // after printing and reparsing the round-tripped policy
+        let reparsed = Policy::parse(None, round_tripped.to_string()).unwrap();
+        assert_eq!(reparsed.to_json().unwrap(), json);
+        assert_eq!(round_tripped.to_json().unwrap(), json);
+        assert_eq!(
+            round_tripped.annotations().collect::<Vec<_>>(),
+            policy.annotations().collect::<Vec<_>>()
+        );
+        // `when` and `unless` clauses stay in source order
+        assert_eq!(
+            json["conditions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| c["kind"].as_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["when", "unless", "when"]
+        );
+    }
+
+    #[test]
+    fn template_preserves_slots() {
+        let template = Template::parse(Some(PolicyId::new("t")), TEMPLATE).unwrap();
+        let json = template.to_json().unwrap();
+        let round_tripped = Template::from_json(Some(PolicyId::new("t")), json.clone()).unwrap();
+        assert_eq!(round_tripped.to_json().unwrap(), json);
+        assert_eq!(
+            round_tripped.slots().collect::<Vec<_>>(),
+            template.slots().collect::<Vec<_>>()
+        );
+        assert_eq!(round_tripped.annotation("id"), Some("share"));
+
+        // linking commutes with the round trip
+        let mut pset = PolicySet::new();
+        pset.add_template(round_tripped).unwrap();
+        let vals = HashMap::from([
+            (
+                SlotId::principal(),
+                EntityUid::from_str(r#"User::"a""#).unwrap(),
+            ),
+            (
+                SlotId::resource(),
+                EntityUid::from_str(r#"Album::"b""#).unwrap(),
+            ),
+        ]);
+        pset.link(PolicyId::new("t"), PolicyId::new("l"), vals)
+            .unwrap();
+        let linked = pset.policy(&PolicyId::new("l")).unwrap();
+        let linked_json = linked.to_json().unwrap();
+        assert_eq!(
+            Policy::from_json(Some(PolicyId::new("l")), linked_json.clone())
+                .unwrap()
+                .to_json()
+                .unwrap(),
+            linked_json
+        );
+    }
+
+    #[test]
+    fn lossless_retains_text_and_source_locations() {
+        let policy = Policy::parse(Some(PolicyId::new("p")), POLICY).unwrap();
+        let json = policy.to_json_lossless().unwrap();
+        assert_eq!(json["source"], POLICY);
+        let round_tripped = Policy::from_json_lossless(Some(PolicyId::new("p")), json).unwrap();
+        assert_eq!(round_tripped.to_string(), POLICY);
+
+        let validator = Validator::new(
+            Schema::from_json_value(json!({ "": { "actions": { "view": {} }, "entityTypes": {} }}))
+                .unwrap(),
+        );
+        let spans = |p: Policy| {
+            let pset = PolicySet::from_policies([p]).unwrap();
+            validator
+                .validate(&pset, ValidationMode::Strict)
+                .validation_errors()
+                .map(|e| {
+                    e.labels()
+                        .expect("validation errors should have source spans")
+                        .map(|l| (l.offset(), l.len()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = spans(policy);
+        assert!(!expected.is_empty());
+        assert_eq!(spans(round_tripped), expected);
+    }
+
+    #[test]
+    fn lossless_template() {
+        let template = Template::parse(None, TEMPLATE).unwrap();
+        let json = template.to_json_lossless().unwrap();
+        let round_tripped = Template::from_json_lossless(None, json).unwrap();
+        assert_eq!(round_tripped.to_string(), TEMPLATE);
+        // a template is not a static policy, with or without the source text
+        let json = template.to_json_lossless().unwrap();
+        assert_matches!(Policy::from_json_lossless(None, json), Err(_));
+    }
+
+    #[test]
+    fn lossless_ignores_stale_source() {
+        let policy = Policy::parse(None, POLICY).unwrap();
+        let mut json = policy.to_json_lossless().unwrap();
+        json["policy"]["effect"] = json!("forbid");
+        let edited = Policy::from_json_lossless(None, json).unwrap();
+        assert_eq!(edited.effect(), Effect::Forbid);
+        assert!(!edited.to_string().contains("permit"));
+    }
+
+    #[test]
+    fn lossless_without_source() {
+        let policy = Policy::from_json(
+            None,
+            Policy::parse(None, POLICY).unwrap().to_json().unwrap(),
+        )
+        .unwrap();
+        let json = policy.to_json_lossless().unwrap();
+        assert_eq!(json.get("source"), None);
+        assert_eq!(
+            Policy::from_json_lossless(None, json)
+                .unwrap()
+                .to_json()
+                .unwrap(),
+            policy.to_json().unwrap()
+        );
+        // the plain JSON policy format is not accepted
+        assert_matches!(
+            Policy::from_json_lossless(None, policy.to_json().unwrap()),
+            Err(_)
+        );
+    }
+}
+
+mod optimized_tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_policy_keeps_text() {
+        let src = "permit(principal, action, resource) when { principal.level > 3 };";
+        let policy = Policy::parse(None, src).unwrap();
+        assert_eq!(policy.optimized().to_string(), src);
+    }
+
+    #[test]
+    fn linked_policy() {
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            "permit(principal == ?principal, action, resource) when { context.n > 1 + 1 };",
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        pset.add_template(template).unwrap();
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        pset.link(
+            PolicyId::new("t"),
+            PolicyId::new("linked"),
+            HashMap::from([(SlotId::principal(), alice.clone())]),
+        )
+        .unwrap();
+        let optimized = pset.policy(&PolicyId::new("linked")).unwrap().optimized();
+        assert_eq!(optimized.id(), &PolicyId::new("linked"));
+        assert_eq!(optimized.template_id(), Some(&PolicyId::new("t")));
+        assert_eq!(
+            optimized.principal_constraint(),
+            PrincipalConstraint::Eq(alice)
+        );
+        // `context.n > 1 + 1` is represented as `!(context.n <= 1 + 1)`
+        let text = optimized.to_string();
+        assert!(text.contains("<= 2"), "{text}");
+        assert!(!text.contains('+'), "{text}");
+        // the JSON representation is linked, too
+        let json = optimized.to_json().unwrap();
+        let unoptimized = pset
+            .policy(&PolicyId::new("linked"))
+            .unwrap()
+            .to_json()
+            .unwrap();
+        assert_eq!(json["principal"], unoptimized["principal"]);
+        assert_eq!(json["principal"]["op"], "==");
+    }
+}
+
+mod find_duplicates_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        r#"
+            entity Group;
+            entity User in [Group];
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+        "#
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn linked_policies() {
+        let mut pset: PolicySet = r#"
+            permit(principal == User::"alice", action, resource) when { [1, 2].contains(3) };
+        "#
+        .parse()
+        .unwrap();
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            "permit(principal == ?principal, action, resource) when { [2, 1].contains(3) };",
+        )
+        .unwrap();
+        pset.add_template(template).unwrap();
+        for (id, name) in [("alice", "alice"), ("bob", "bob")] {
+            pset.link(
+                PolicyId::new("t"),
+                PolicyId::new(id),
+                HashMap::from([(
+                    SlotId::principal(),
+                    EntityUid::from_str(&format!(r#"User::"{name}""#)).unwrap(),
+                )]),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            pset.find_duplicates(&schema(), PolicyEquivalence::Canonical),
+            vec![vec![PolicyId::new("alice"), PolicyId::new("policy0")]]
+        );
+    }
+
+    #[test]
+    fn scope_normalization() {
+        let pset: PolicySet = r#"
+            permit(principal in User::"alice", action, resource in Photo::"p");
+            permit(principal == User::"alice", action == Action::"view", resource == Photo::"p");
+            permit(principal in Group::"g", action, resource);
+            permit(principal == Group::"g", action, resource);
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            pset.find_duplicates(&schema(), PolicyEquivalence::Canonical),
+            Vec::<Vec<PolicyId>>::new()
+        );
+        // `Group` has members, so `principal in Group::"g"` is not a
+        // duplicate of `principal == Group::"g"`
+        assert_eq!(
+            pset.find_duplicates(&schema(), PolicyEquivalence::Semantic),
+            vec![vec![PolicyId::new("policy0"), PolicyId::new("policy1")]]
+        );
+    }
+}
+
+mod permitted_actions_tests {
+    use super::*;
+
+    #[test]
+    fn hierarchy_and_conditions() {
+        let schema: Schema = r#"
+            entity Group;
+            entity User in [Group];
+            entity Doc;
+            action read, write, share appliesTo { principal: User, resource: Doc };
+        "#
+        .parse()
+        .unwrap();
+        let policies: PolicySet = r#"
+            permit(principal in Group::"staff", action == Action::"read", resource);
+            permit(principal in Group::"admins", action == Action::"write", resource);
+            permit(principal, action == Action::"share", resource) when { 1 > 2 };
+            forbid(principal, action == Action::"read", resource);
+        "#
+        .parse()
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Group", "id": "staff" }] },
+                { "uid": { "type": "Group", "id": "staff" }, "attrs": {}, "parents": [] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        // the forbid policy is not taken into account
+        assert_eq!(
+            policies.permitted_actions(&alice, &entities, &schema),
+            BTreeSet::from([(
+                EntityUid::from_str(r#"Action::"read""#).unwrap(),
+                EntityTypeName::from_str("Doc").unwrap()
+            )])
+        );
+        let bob = EntityUid::from_str(r#"User::"bob""#).unwrap();
+        assert!(policies
+            .permitted_actions(&bob, &entities, &schema)
+            .is_empty());
+    }
+}
+
+mod prefilter_tests {
+    use super::*;
+
+    #[test]
+    fn same_decisions_as_authorizer() {
+        let schema: Schema = r#"
+            entity Group;
+            entity User in [Group];
+            entity Doc;
+            action read, write in [readWrite] appliesTo { principal: User, resource: Doc };
+            action readWrite;
+        "#
+        .parse()
+        .unwrap();
+        let policies: PolicySet = r#"
+            permit(principal in Group::"staff", action in Action::"readWrite", resource);
+            permit(principal == User::"bob", action == Action::"read", resource == Doc::"d");
+            forbid(principal, action == Action::"write", resource);
+        "#
+        .parse()
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Group", "id": "staff" }] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let prefiltered = PrefilteredPolicySet::new(&policies, &schema);
+        for principal in ["alice", "bob", "carol"] {
+            for action in ["read", "write"] {
+                let request = Request::new(
+                    EntityUid::from_str(&format!(r#"User::"{principal}""#)).unwrap(),
+                    EntityUid::from_str(&format!(r#"Action::"{action}""#)).unwrap(),
+                    EntityUid::from_str(r#"Doc::"d""#).unwrap(),
+                    Context::empty(),
+                    None,
+                )
+                .unwrap();
+                let expected = Authorizer::new().is_authorized(&request, &policies, &entities);
+                let response = prefiltered.is_authorized(&request, &entities);
+                assert_eq!(response.decision(), expected.decision());
+                assert_eq!(
+                    response.diagnostics().reason().collect::<HashSet<_>>(),
+                    expected.diagnostics().reason().collect::<HashSet<_>>()
+                );
+                assert_eq!(
+                    response.diagnostics().errors().count(),
+                    expected.diagnostics().errors().count()
+                );
+            }
+        }
+        // carol may do nothing, and bob may not write
+        let stats = prefiltered.stats();
+        assert_eq!((stats.requests(), stats.skipped()), (6, 3));
+        assert!((stats.hit_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn indexes_by_resource_type() {
+        let schema: Schema = r#"
+            entity User;
+            entity Folder;
+            entity Doc in [Folder];
+            entity Photo in [Folder] { private: Bool };
+            action view appliesTo { principal: User, resource: [Doc, Photo] };
+        "#
+        .parse()
+        .unwrap();
+        let mut policies: PolicySet = r#"
+            permit(principal, action, resource is Doc);
+            forbid(principal, action, resource is Photo) when { resource.private };
+            permit(principal == ?principal, action, resource is Photo in ?resource);
+        "#
+        .parse()
+        .unwrap();
+        policies
+            .link(
+                PolicyId::from_str("policy2").unwrap(),
+                PolicyId::from_str("alice_photos").unwrap(),
+                HashMap::from([
+                    (
+                        SlotId::principal(),
+                        EntityUid::from_str(r#"User::"alice""#).unwrap(),
+                    ),
+                    (
+                        SlotId::resource(),
+                        EntityUid::from_str(r#"Folder::"f""#).unwrap(),
+                    ),
+                ]),
+            )
+            .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "Photo", "id": "p" }, "attrs": { "private": false }, "parents": [{ "type": "Folder", "id": "f" }] },
+                { "uid": { "type": "Photo", "id": "q" }, "attrs": { "private": true }, "parents": [{ "type": "Folder", "id": "f" }] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let prefiltered = PrefilteredPolicySet::new(&policies, &schema);
+        for principal in [r#"User::"alice""#, r#"User::"bob""#] {
+            for resource in [r#"Doc::"d""#, r#"Photo::"p""#, r#"Photo::"q""#] {
+                let request = Request::new(
+                    EntityUid::from_str(principal).unwrap(),
+                    EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                    EntityUid::from_str(resource).unwrap(),
+                    Context::empty(),
+                    None,
+                )
+                .unwrap();
+                let expected = Authorizer::new().is_authorized(&request, &policies, &entities);
+                let response = prefiltered.is_authorized(&request, &entities);
+                assert_eq!(response.decision(), expected.decision());
+                assert_eq!(
+                    response.diagnostics().reason().collect::<HashSet<_>>(),
+                    expected.diagnostics().reason().collect::<HashSet<_>>()
+                );
+                assert_eq!(
+                    response.diagnostics().errors().count(),
+                    expected.diagnostics().errors().count()
+                );
+            }
+        }
+    }
+}
+
+mod entity_migration_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn groups_issues_by_type() {
+        let old: Schema = r#"
+            entity User { age: Long };
+            entity Doc { title: String };
+            action view appliesTo { principal: User, resource: Doc };
+        "#
+        .parse()
+        .unwrap();
+        let new: Schema = r#"
+            entity User { age: String };
+            entity Doc { title: String };
+            action view appliesTo { principal: User, resource: Doc };
+        "#
+        .parse()
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "age": 3 }, "parents": [] },
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 5 }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "d" }, "attrs": { "title": "t" }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "e" }, "attrs": {}, "parents": [] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let report = entities.check_migration(&old, &new);
+        assert_eq!(report.len(), 3);
+        let groups = report
+            .by_entity_type()
+            .map(|(ty, issues)| {
+                (
+                    ty.to_string(),
+                    issues
+                        .iter()
+                        .map(|i| AsRef::<str>::as_ref(i.uid().id()).to_string())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![
+                ("Doc".to_string(), vec!["e".to_string()]),
+                (
+                    "User".to_string(),
+                    vec!["alice".to_string(), "bob".to_string()]
+                ),
+            ]
+        );
+        // `Doc::"e"` lacked its title before the migration
+        assert_eq!(report.regressions().count(), 2);
+        assert_matches!(
+            report.regressions().next().unwrap().error(),
+            conformance_errors::EntitySchemaConformanceError::TypeMismatch(_)
+        );
+    }
+}
+
+mod layered_tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn forbid_in_any_layer_wins() {
+        let global: PolicySet = r#"
+            permit(principal, action == Action::"view", resource);
+            forbid(principal == User::"mallory", action, resource);
+        "#
+        .parse()
+        .unwrap();
+        // the ids of this layer collide with those of the global layer
+        let tenant: PolicySet = r#"
+            permit(principal, action == Action::"edit", resource);
+            permit(principal == User::"mallory", action == Action::"view", resource);
+        "#
+        .parse()
+        .unwrap();
+        let layers = PolicyLayers::new()
+            .with_layer("global", Arc::new(global))
+            .with_layer("tenant", Arc::new(tenant));
+        let authorize = |principal: &str, action: &str| {
+            let request = Request::new(
+                EntityUid::from_str(&format!(r#"User::"{principal}""#)).unwrap(),
+                EntityUid::from_str(&format!(r#"Action::"{action}""#)).unwrap(),
+                EntityUid::from_str(r#"Doc::"d""#).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap();
+            Authorizer::new().is_authorized_layered(&request, &layers, &Entities::empty())
+        };
+
+        let response = authorize("alice", "edit");
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(
+            response.reason().collect::<Vec<_>>(),
+            vec![("tenant", &PolicyId::new("policy0"))]
+        );
+
+        let response = authorize("mallory", "view");
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(
+            response.reason().collect::<Vec<_>>(),
+            vec![("global", &PolicyId::new("policy1"))]
+        );
+        assert_eq!(
+            response
+                .layer_responses()
+                .map(|(name, r)| (name, r.decision()))
+                .collect::<Vec<_>>(),
+            vec![("global", Decision::Deny), ("tenant", Decision::Allow)]
+        );
+
+        let response = authorize("alice", "delete");
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.reason().count(), 0);
+    }
+}
+
 mod issue_604 {
     use crate::Policy;
     use cedar_policy_core::parser::parse_policy_or_template_to_est;
@@ -5715,3 +6382,220 @@ mod context_tests {
         );
     }
 }
+
+mod parent_attributes_tests {
+    use super::*;
+
+    #[test]
+    fn parents_implied_by_attributes() {
+        let schema: Schema = r#"
+            entity Account, Album;
+            entity Photo in [Account, Album] via [account, albums] {
+                account: Account,
+                albums: Set<Album>,
+            };
+        "#
+        .parse()
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                {
+                    "uid": { "type": "Photo", "id": "p" },
+                    "attrs": {
+                        "account": { "type": "Account", "id": "a" },
+                        "albums": [{ "type": "Album", "id": "x" }, { "type": "Album", "id": "y" }],
+                    },
+                    "parents": [],
+                },
+            ]),
+            Some(&schema),
+        )
+        .unwrap();
+        let photo = EntityUid::from_str(r#"Photo::"p""#).unwrap();
+        for parent in [r#"Account::"a""#, r#"Album::"x""#, r#"Album::"y""#] {
+            let parent = EntityUid::from_str(parent).unwrap();
+            assert!(entities.is_ancestor_of(&parent, &photo), "{parent}");
+        }
+        assert!(!entities.is_ancestor_of(&EntityUid::from_str(r#"Album::"z""#).unwrap(), &photo));
+
+        // without a schema, the attributes don't imply any parent
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                {
+                    "uid": { "type": "Photo", "id": "p" },
+                    "attrs": { "account": { "__entity": { "type": "Account", "id": "a" } } },
+                    "parents": [],
+                },
+            ]),
+            None,
+        )
+        .unwrap();
+        assert!(!entities.is_ancestor_of(&EntityUid::from_str(r#"Account::"a""#).unwrap(), &photo));
+    }
+}
+
+mod computed_attributes_tests {
+    use super::*;
+
+    #[test]
+    fn attributes_computed_on_load() {
+        let schema: Schema = r#"
+            entity User {
+                age: Long,
+                isAdult: Bool,
+            } computed { isAdult: "context.age >= 18" };
+            action view appliesTo { principal: User, resource: User };
+        "#
+        .parse()
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 30 }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "age": 12, "isAdult": true }, "parents": [] },
+            ]),
+            Some(&schema),
+        )
+        .unwrap();
+        let is_adult = |id: &str| {
+            let uid = EntityUid::from_type_name_and_id(
+                EntityTypeName::from_str("User").unwrap(),
+                EntityId::new(id),
+            );
+            entities
+                .get(&uid)
+                .unwrap()
+                .attr("isAdult")
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(is_adult("alice"), EvalResult::Bool(true));
+        // the computed value replaces the value in the entity data
+        assert_eq!(is_adult("bob"), EvalResult::Bool(false));
+
+        // computed attributes are visible to the typechecker
+        let policies: PolicySet = r#"
+            permit(principal, action, resource) when { principal.isAdult };
+        "#
+        .parse()
+        .unwrap();
+        let result = Validator::new(schema).validate(&policies, ValidationMode::default());
+        assert!(result.validation_passed());
+    }
+}
+
+mod evaluate_all_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn reports_every_policy() {
+        let policies: PolicySet = r#"
+            forbid(principal, action, resource);
+            permit(principal, action, resource) when { resource.owner == principal };
+            permit(principal, action, resource) when { context.level > 2 };
+        "#
+        .parse()
+        .unwrap();
+        let request = Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+            Context::from_pairs([("level".into(), RestrictedExpression::new_long(3))]).unwrap(),
+            None,
+        )
+        .unwrap();
+        let evaluations = Authorizer::new().evaluate_all(&request, &policies, &Entities::empty());
+        let results: Vec<_> = evaluations
+            .iter()
+            .map(|e| (e.id().to_string(), e.effect(), e.result().ok()))
+            .collect();
+        // the response only reports the forbid policy, which determines the
+        // decision, but all the policies are evaluated
+        assert_eq!(
+            results,
+            [
+                ("policy0".to_string(), Effect::Forbid, Some(true)),
+                ("policy1".to_string(), Effect::Permit, None),
+                ("policy2".to_string(), Effect::Permit, Some(true)),
+            ]
+        );
+        assert_matches!(
+            evaluations.get(1).map(PolicyEvaluation::result),
+            Some(Err(EvaluationError::EntityDoesNotExist(_)))
+        );
+    }
+}
+
+mod thread_safety_tests {
+    use super::*;
+
+    /// One `Authorizer`, `PolicySet`, `Entities`, and `Validator` can serve
+    /// many threads without a lock
+    #[test]
+    fn share_between_threads() {
+        let schema = Schema::from_str(
+            r#"
+            entity User in [Group];
+            entity Group;
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(
+            r#"permit(principal in Group::"friends", action == Action::"view", resource);"#,
+        )
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {},
+                  "parents": [{ "type": "Group", "id": "friends" }] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+            ]),
+            Some(&schema),
+        )
+        .unwrap();
+        let authorizer = Authorizer::new();
+        let validator = Validator::new(schema);
+
+        std::thread::scope(|s| {
+            let handles: Vec<_> = ["alice", "bob"]
+                .into_iter()
+                .cycle()
+                .take(8)
+                .map(|user| {
+                    let (authorizer, policies, entities, validator) =
+                        (&authorizer, &policies, &entities, &validator);
+                    s.spawn(move || {
+                        let request = Request::new(
+                            EntityUid::from_str(&format!(r#"User::"{user}""#)).unwrap(),
+                            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                            EntityUid::from_str(r#"Photo::"p""#).unwrap(),
+                            Context::empty(),
+                            None,
+                        )
+                        .unwrap();
+                        assert!(validator
+                            .validate(policies, ValidationMode::Strict)
+                            .validation_passed());
+                        (
+                            user,
+                            authorizer
+                                .is_authorized(&request, policies, entities)
+                                .decision(),
+                        )
+                    })
+                })
+                .collect();
+            for handle in handles {
+                let (user, decision) = handle.join().unwrap();
+                let expected = if user == "alice" {
+                    Decision::Allow
+                } else {
+                    Decision::Deny
+                };
+                assert_eq!(decision, expected, "{user}");
+            }
+        });
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Tests building and running code emitted by `compile_to_rust()`. The
+//! generated module is checked in as `codegen/photos.rs` so that it is
+//! compiled as part of this test; regenerate it with the output of
+//! `compile_to_rust()` when the code generator changes.
+#![cfg(feature = "codegen")]
+
+use cedar_policy::codegen::{check_equivalence, compile_to_rust, CodegenOptions};
+use cedar_policy::{Context, Entities, EntityUid, PolicySet, Request, Schema};
+use std::str::FromStr;
+
+#[allow(clippy::result_large_err)]
+mod photos {
+    include!("codegen/photos.rs");
+}
+
+const SCHEMA: &str = r#"
+entity User in [Group] { name: String, age: Long };
+entity Group;
+entity Album;
+entity Photo in [Album] { owner: User, tags: Set<String> };
+action view, edit appliesTo {
+    principal: User,
+    resource: Photo,
+    context: { hour: Long, offset: Long },
+};
+"#;
+
+const POLICIES: &str = r#"
+permit(principal == User::"alice", action, resource is Photo in Album::"trip");
+permit(principal in Group::"friends", action == Action::"view", resource)
+when { resource.tags.contains("public") && context.hour < 18 };
+permit(principal, action in [Action::"view", Action::"edit"], resource)
+when { resource.owner == principal };
+forbid(principal, action == Action::"edit", resource)
+unless { principal.name like "a*" && principal.age >= 18 };
+forbid(principal, action, resource)
+when { context.offset + 9223372036854775807 < 0 };
+"#;
+
+const ENTITIES: &str = r#"[
+    { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "alice", "age": 30 }, "parents": [] },
+    { "uid": { "type": "User", "id": "bob" }, "attrs": { "name": "bob", "age": 17 }, "parents": [{ "type": "Group", "id": "friends" }] },
+    { "uid": { "type": "User", "id": "carol" }, "attrs": { "name": "carol", "age": 40 }, "parents": [] },
+    { "uid": { "type": "Group", "id": "friends" }, "attrs": {}, "parents": [] },
+    { "uid": { "type": "Album", "id": "trip" }, "attrs": {}, "parents": [] },
+    { "uid": { "type": "Photo", "id": "beach" }, "attrs": { "owner": { "type": "User", "id": "bob" }, "tags": ["public"] }, "parents": [{ "type": "Album", "id": "trip" }] },
+    { "uid": { "type": "Photo", "id": "desk" }, "attrs": { "owner": { "type": "User", "id": "carol" }, "tags": [] }, "parents": [] }
+]"#;
+
+fn schema() -> Schema {
+    Schema::from_str(SCHEMA).unwrap()
+}
+
+fn policies() -> PolicySet {
+    PolicySet::from_str(POLICIES).unwrap()
+}
+
+fn uid(ty: &str, id: &str) -> EntityUid {
+    EntityUid::from_str(&format!("{ty}::{id:?}")).unwrap()
+}
+
+#[test]
+fn checked_in_code_is_up_to_date() {
+    let source = compile_to_rust(&policies(), &schema(), &CodegenOptions::default()).unwrap();
+    assert_eq!(
+        source,
+        include_str!("codegen/photos.rs"),
+        "tests/codegen/photos.rs is out of date; replace it with the output of `compile_to_rust()`"
+    );
+}
+
+#[test]
+fn compiled_code_agrees_with_interpreter() {
+    let schema = schema();
+    let policies = policies();
+    let entities = Entities::from_json_str(ENTITIES, Some(&schema)).unwrap();
+    let mut requests = 0;
+    for principal in ["alice", "bob", "carol"] {
+        for action in ["view", "edit"] {
+            for resource in ["beach", "desk"] {
+                for (hour, offset) in [(9, 0), (20, 0), (9, 1)] {
+                    let action_uid = uid("Action", action);
+                    let context = Context::from_json_value(
+                        serde_json::json!({ "hour": hour, "offset": offset }),
+                        Some((&schema, &action_uid)),
+                    )
+                    .unwrap();
+                    let request = Request::new(
+                        uid("User", principal),
+                        action_uid,
+                        uid("Photo", resource),
+                        context,
+                        Some(&schema),
+                    )
+                    .unwrap();
+                    let compiled = photos::is_authorized(&request, &entities);
+                    if let Err(e) = check_equivalence(&policies, &request, &entities, &compiled) {
+                        panic!("{principal} {action} {resource} at {hour} ({offset}): {e}");
+                    }
+                    requests += 1;
+                }
+            }
+        }
+    }
+    assert_eq!(requests, 36);
+    assert_eq!(photos::POLICY_IDS.len(), 5);
+}
@@ -0,0 +1,139 @@
+// This file was generated from a Cedar policy set. Do not edit it by hand;
+// regenerate it whenever the policies change.
+
+use ::cedar_policy::codegen::runtime as rt;
+
+/// Ids of the compiled policies
+pub const POLICY_IDS: &[&str] = &["policy0", "policy1", "policy2", "policy3", "policy4"];
+
+/// Authorize `request` against the compiled policies
+pub fn is_authorized(request: &rt::Request, entities: &rt::Entities) -> rt::Response {
+    let env = rt::env(request, entities);
+    let mut decisions = rt::Decisions::default();
+    decisions.record(POLICY_IDS[0], rt::Effect::Permit, policy_0(&env));
+    decisions.record(POLICY_IDS[1], rt::Effect::Permit, policy_1(&env));
+    decisions.record(POLICY_IDS[2], rt::Effect::Permit, policy_2(&env));
+    decisions.record(POLICY_IDS[3], rt::Effect::Forbid, policy_3(&env));
+    decisions.record(POLICY_IDS[4], rt::Effect::Forbid, policy_4(&env));
+    rt::response(decisions.finish())
+}
+
+/// Policy `policy0`
+fn policy_0(env: &rt::Env<'_>) -> rt::Result<bool> {
+    if env.principal()? != const_0() {
+        return Ok(false);
+    }
+    if env.resource()?.entity_type() != const_1()
+        || !env.is_descendant_of(env.resource()?, std::slice::from_ref(const_2()))
+    {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Policy `policy1`
+fn policy_1(env: &rt::Env<'_>) -> rt::Result<bool> {
+    if !env.is_descendant_of(env.principal()?, std::slice::from_ref(const_3())) {
+        return Ok(false);
+    }
+    if env.action()? != const_4() {
+        return Ok(false);
+    }
+    rt::as_bool(&rt::Value::from(rt::as_bool(&env.binary_app(rt::BinaryOp::Contains, env.get_attr(env.resource_value()?, const_5())?, const_6().clone())?)? && rt::as_bool(&env.binary_app(rt::BinaryOp::Less, env.get_attr(env.context()?, const_7())?, rt::Value::from(18_i64))?)?))
+}
+
+/// Policy `policy2`
+fn policy_2(env: &rt::Env<'_>) -> rt::Result<bool> {
+    if !env.is_descendant_of(env.action()?, const_9()) {
+        return Ok(false);
+    }
+    rt::as_bool(&env.binary_app(rt::BinaryOp::Eq, env.get_attr(env.resource_value()?, const_10())?, env.principal_value()?)?)
+}
+
+/// Policy `policy3`
+fn policy_3(env: &rt::Env<'_>) -> rt::Result<bool> {
+    if env.action()? != const_8() {
+        return Ok(false);
+    }
+    rt::as_bool(&rt::unary_app(rt::UnaryOp::Not, rt::Value::from(rt::as_bool(&rt::like(&env.get_attr(env.principal_value()?, const_12())?, const_11())?)? && rt::as_bool(&rt::unary_app(rt::UnaryOp::Not, env.binary_app(rt::BinaryOp::Less, env.get_attr(env.principal_value()?, const_13())?, rt::Value::from(18_i64))?)?)?))?)
+}
+
+/// Policy `policy4`
+fn policy_4(env: &rt::Env<'_>) -> rt::Result<bool> {
+    rt::as_bool(&env.binary_app(rt::BinaryOp::Less, env.binary_app(rt::BinaryOp::Add, env.get_attr(env.context()?, const_14())?, rt::Value::from(9223372036854775807_i64))?, rt::Value::from(0_i64))?)
+}
+
+fn const_0() -> &'static rt::EntityUID {
+    static CELL: std::sync::OnceLock<rt::EntityUID> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::entity_uid("User", "alice"))
+}
+
+fn const_1() -> &'static rt::EntityType {
+    static CELL: std::sync::OnceLock<rt::EntityType> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::entity_type("Photo"))
+}
+
+fn const_2() -> &'static rt::EntityUID {
+    static CELL: std::sync::OnceLock<rt::EntityUID> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::entity_uid("Album", "trip"))
+}
+
+fn const_3() -> &'static rt::EntityUID {
+    static CELL: std::sync::OnceLock<rt::EntityUID> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::entity_uid("Group", "friends"))
+}
+
+fn const_4() -> &'static rt::EntityUID {
+    static CELL: std::sync::OnceLock<rt::EntityUID> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::entity_uid("Action", "view"))
+}
+
+fn const_5() -> &'static rt::SmolStr {
+    static CELL: std::sync::OnceLock<rt::SmolStr> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::attr("tags"))
+}
+
+fn const_6() -> &'static rt::Value {
+    static CELL: std::sync::OnceLock<rt::Value> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::Value::from("public"))
+}
+
+fn const_7() -> &'static rt::SmolStr {
+    static CELL: std::sync::OnceLock<rt::SmolStr> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::attr("hour"))
+}
+
+fn const_8() -> &'static rt::EntityUID {
+    static CELL: std::sync::OnceLock<rt::EntityUID> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::entity_uid("Action", "edit"))
+}
+
+fn const_9() -> &'static Vec<rt::EntityUID> {
+    static CELL: std::sync::OnceLock<Vec<rt::EntityUID>> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| vec![const_4().clone(), const_8().clone()])
+}
+
+fn const_10() -> &'static rt::SmolStr {
+    static CELL: std::sync::OnceLock<rt::SmolStr> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::attr("owner"))
+}
+
+fn const_11() -> &'static rt::Pattern {
+    static CELL: std::sync::OnceLock<rt::Pattern> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::Pattern::new([rt::PatternElem::Char('a'), rt::PatternElem::Wildcard]))
+}
+
+fn const_12() -> &'static rt::SmolStr {
+    static CELL: std::sync::OnceLock<rt::SmolStr> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::attr("name"))
+}
+
+fn const_13() -> &'static rt::SmolStr {
+    static CELL: std::sync::OnceLock<rt::SmolStr> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::attr("age"))
+}
+
+fn const_14() -> &'static rt::SmolStr {
+    static CELL: std::sync::OnceLock<rt::SmolStr> = std::sync::OnceLock::new();
+    CELL.get_or_init(|| rt::attr("offset"))
+}
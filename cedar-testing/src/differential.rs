@@ -0,0 +1,373 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Stable serialization of the inputs and outputs of the authorizer and the
+//! evaluator, for differential testing against other implementations of
+//! Cedar, like the formal models in
+//! <https://github.com/cedar-policy/cedar-spec>.
+//!
+//! Inputs are self-contained: policies and expressions are in Cedar syntax,
+//! and entities and requests in the JSON formats used by `cedar-policy`, so
+//! that they can be stored in a corpus and replayed by any implementation.
+//! Outputs only contain what implementations are expected to agree on, in a
+//! canonical order, so that they can be compared for equality. In particular,
+//! errors are identified by the policies that produced them, not by their
+//! messages.
+//!
+//! The format is versioned with [`FORMAT_VERSION`], which is bumped on any
+//! breaking change to it.
+
+use crate::cedar_test_impl::{RustEngine, TestResult};
+use cedar_policy_core::ast::{Context, EntityUIDEntry, Expr, Request};
+use cedar_policy_core::authorizer::{AuthorizationError, Authorizer, Decision};
+use cedar_policy_core::entities::json::err::JsonDeserializationErrorContext;
+use cedar_policy_core::entities::{
+    CedarValueJson, Entities, EntityJsonParser, EntityUidJson, NoEntitiesSchema, TCComputation,
+};
+use cedar_policy_core::evaluator::Evaluator;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_core::parser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Version of the format of [`DifferentialTestCase`]s
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A request, in the JSON format used by `cedar-policy`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct DifferentialRequest {
+    /// Principal, in either explicit or implicit `__entity` form
+    pub principal: serde_json::Value,
+    /// Action, in either explicit or implicit `__entity` form
+    pub action: serde_json::Value,
+    /// Resource, in either explicit or implicit `__entity` form
+    pub resource: serde_json::Value,
+    /// Context, which must be a JSON object
+    pub context: serde_json::Value,
+}
+
+/// Input of a differential test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+#[serde(rename_all = "camelCase")]
+pub enum DifferentialInput {
+    /// Authorization of a request
+    Authorization {
+        /// Policy set, in Cedar syntax
+        policies: String,
+        /// Entities, in the JSON entity format
+        entities: serde_json::Value,
+        /// Request to authorize
+        request: DifferentialRequest,
+    },
+    /// Evaluation of an expression
+    Evaluation {
+        /// Expression to evaluate, in Cedar syntax
+        expr: String,
+        /// Entities, in the JSON entity format
+        entities: serde_json::Value,
+        /// Request the expression is evaluated in
+        request: DifferentialRequest,
+    },
+}
+
+/// Output of a differential test, which implementations must agree on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+#[serde(rename_all = "camelCase")]
+pub enum DifferentialOutput {
+    /// Response of the authorization of a request
+    Authorization {
+        /// Authorization decision
+        decision: Decision,
+        /// Ids of the policies which determined the decision
+        reason: BTreeSet<String>,
+        /// Ids of the policies whose evaluation errored
+        #[serde(rename = "erroringPolicies")]
+        erroring_policies: BTreeSet<String>,
+    },
+    /// Result of the evaluation of an expression
+    Evaluation {
+        /// Value of the expression, in the JSON value format, or `None` if its
+        /// evaluation errored
+        value: Option<serde_json::Value>,
+    },
+}
+
+/// A differential test: an input, together with its expected output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct DifferentialTestCase {
+    /// Version of the format, which must be [`FORMAT_VERSION`]
+    pub version: u32,
+    /// Input of the test
+    pub input: DifferentialInput,
+    /// Expected output of the test
+    pub expected: DifferentialOutput,
+}
+
+impl DifferentialTestCase {
+    /// Create a test case in the current format version
+    pub fn new(input: DifferentialInput, expected: DifferentialOutput) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            input,
+            expected,
+        }
+    }
+
+    /// Parse a test case from JSON, failing for test cases in another format
+    /// version
+    pub fn from_json_str(json: &str) -> Result<Self, String> {
+        let case: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if case.version != FORMAT_VERSION {
+            return Err(format!(
+                "unsupported format version {}, expected {FORMAT_VERSION}",
+                case.version
+            ));
+        }
+        Ok(case)
+    }
+}
+
+/// An implementation of Cedar which runs differential tests.
+///
+/// Implementations for the formal models can be provided by the test harness
+/// which links them, e.g., through FFI or by running them as a subprocess on
+/// the serialized [`DifferentialInput`].
+pub trait DifferentialRunner {
+    /// Name of the implementation, used to report mismatches
+    fn name(&self) -> &str;
+
+    /// Run the test with input `input`. Returns a `TestResult::Failure` if the
+    /// input is invalid, e.g., if its policies don't parse.
+    fn run(&self, input: &DifferentialInput) -> TestResult<DifferentialOutput>;
+}
+
+/// Outputs of implementations which disagree on an input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifferentialMismatch {
+    /// Input the implementations disagree on
+    pub input: DifferentialInput,
+    /// Names and outputs of the implementations
+    pub outputs: Vec<(String, DifferentialOutput)>,
+}
+
+/// Run `input` with each of `runners`, returning the output they agree on, or
+/// the mismatch between their outputs.
+///
+/// Runners for which the input is invalid are skipped, so that an input can be
+/// used with implementations which don't support all its features.
+pub fn run_differential(
+    input: &DifferentialInput,
+    runners: &[&dyn DifferentialRunner],
+) -> Result<Option<DifferentialOutput>, DifferentialMismatch> {
+    let outputs: Vec<(String, DifferentialOutput)> = runners
+        .iter()
+        .filter_map(|runner| match runner.run(input) {
+            TestResult::Success(output) => Some((runner.name().to_string(), output)),
+            TestResult::Failure(_) => None,
+        })
+        .collect();
+    match outputs.split_first() {
+        None => Ok(None),
+        Some(((_, first), rest)) if rest.iter().all(|(_, output)| output == first) => {
+            Ok(Some(first.clone()))
+        }
+        Some(_) => Err(DifferentialMismatch {
+            input: input.clone(),
+            outputs,
+        }),
+    }
+}
+
+fn parse_entities(json: &serde_json::Value) -> Result<Entities, String> {
+    EntityJsonParser::new(
+        None::<&NoEntitiesSchema>,
+        Extensions::all_available(),
+        TCComputation::ComputeNow,
+    )
+    .from_json_value(json.clone())
+    .map_err(|e| e.to_string())
+}
+
+fn parse_request(request: &DifferentialRequest) -> Result<Request, String> {
+    let parse_euid = |json: &serde_json::Value| {
+        let euid: EntityUidJson =
+            serde_json::from_value(json.clone()).map_err(|e| e.to_string())?;
+        euid.into_euid(|| JsonDeserializationErrorContext::EntityUid)
+            .map_err(|e| e.to_string())
+    };
+    let context = Context::from_json_value(request.context.clone()).map_err(|e| e.to_string())?;
+    Ok(Request::new_unchecked(
+        EntityUIDEntry::known(parse_euid(&request.principal)?, None),
+        EntityUIDEntry::known(parse_euid(&request.action)?, None),
+        EntityUIDEntry::known(parse_euid(&request.resource)?, None),
+        Some(context),
+    ))
+}
+
+impl RustEngine {
+    fn run_differential_input(input: &DifferentialInput) -> Result<DifferentialOutput, String> {
+        match input {
+            DifferentialInput::Authorization {
+                policies,
+                entities,
+                request,
+            } => {
+                let policies = parser::parse_policyset(policies).map_err(|e| e.to_string())?;
+                let entities = parse_entities(entities)?;
+                let request = parse_request(request)?;
+                let response = Authorizer::new().is_authorized(request, &policies, &entities);
+                Ok(DifferentialOutput::Authorization {
+                    decision: response.decision,
+                    reason: response
+                        .diagnostics
+                        .reason
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    erroring_policies: response
+                        .diagnostics
+                        .errors
+                        .iter()
+                        .map(|e| match e {
+                            AuthorizationError::PolicyEvaluationError { id, .. } => id.to_string(),
+                        })
+                        .collect(),
+                })
+            }
+            DifferentialInput::Evaluation {
+                expr,
+                entities,
+                request,
+            } => {
+                let expr: Expr = expr
+                    .parse()
+                    .map_err(|e: parser::err::ParseErrors| e.to_string())?;
+                let entities = parse_entities(entities)?;
+                let request = parse_request(request)?;
+                let evaluator = Evaluator::new(request, &entities, Extensions::all_available());
+                let value = evaluator
+                    .interpret(&expr, &HashMap::default())
+                    .ok()
+                    .map(|v| {
+                        CedarValueJson::from_value(v)
+                            .and_then(|v| serde_json::to_value(v).map_err(Into::into))
+                            .map_err(|e| e.to_string())
+                    })
+                    .transpose()?;
+                Ok(DifferentialOutput::Evaluation { value })
+            }
+        }
+    }
+}
+
+impl DifferentialRunner for RustEngine {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn run(&self, input: &DifferentialInput) -> TestResult<DifferentialOutput> {
+        match Self::run_differential_input(input) {
+            Ok(output) => TestResult::Success(output),
+            Err(err) => TestResult::Failure(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request() -> DifferentialRequest {
+        DifferentialRequest {
+            principal: json!({ "type": "User", "id": "alice" }),
+            action: json!({ "type": "Action", "id": "view" }),
+            resource: json!({ "type": "Photo", "id": "p" }),
+            context: json!({}),
+        }
+    }
+
+    #[test]
+    fn authorization_roundtrip() {
+        let input = DifferentialInput::Authorization {
+            policies: r#"
+                permit(principal == User::"alice", action, resource);
+                permit(principal, action, resource) when { resource.owner == principal };
+            "#
+            .to_string(),
+            entities: json!([]),
+            request: request(),
+        };
+        let output = RustEngine::new()
+            .run(&input)
+            .expect("input should be valid");
+        let case = DifferentialTestCase::new(input, output);
+        let json = serde_json::to_string(&case).unwrap();
+        assert_eq!(DifferentialTestCase::from_json_str(&json), Ok(case.clone()));
+        assert_eq!(
+            case.expected,
+            DifferentialOutput::Authorization {
+                decision: Decision::Allow,
+                reason: BTreeSet::from(["policy0".to_string()]),
+                erroring_policies: BTreeSet::from(["policy1".to_string()]),
+            }
+        );
+    }
+
+    #[test]
+    fn evaluation() {
+        let input = DifferentialInput::Evaluation {
+            expr: r#"[principal, "a", 1 + 2]"#.to_string(),
+            entities: json!([]),
+            request: request(),
+        };
+        assert_eq!(
+            run_differential(&input, &[&RustEngine::new(), &RustEngine::new()]),
+            Ok(Some(DifferentialOutput::Evaluation {
+                value: Some(json!([{ "__entity": { "type": "User", "id": "alice" } }, "a", 3])),
+            }))
+        );
+
+        let input = DifferentialInput::Evaluation {
+            expr: "principal.age".to_string(),
+            entities: json!([]),
+            request: request(),
+        };
+        assert_eq!(
+            RustEngine::new()
+                .run(&input)
+                .expect("input should be valid"),
+            DifferentialOutput::Evaluation { value: None }
+        );
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let json = json!({
+            "version": FORMAT_VERSION + 1,
+            "input": { "kind": "evaluation", "expr": "1", "entities": [], "request": request() },
+            "expected": { "kind": "evaluation", "value": 1 },
+        });
+        assert!(DifferentialTestCase::from_json_str(&json.to_string()).is_err());
+    }
+}
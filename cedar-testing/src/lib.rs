@@ -15,4 +15,6 @@
  */
 
 pub mod cedar_test_impl;
+#[cfg(feature = "differential-testing")]
+pub mod differential;
 pub mod integration_testing;